@@ -0,0 +1,148 @@
+use serde_json::Value;
+
+use crate::nodes::Block;
+use crate::DarkluaError;
+
+// variants of the Expression, LastStatement and Type enums that only hold a token
+const TOKEN_VARIANTS: [&str; 6] = [
+    "Nil",
+    "True",
+    "False",
+    "VariableArguments",
+    "Break",
+    "Continue",
+];
+
+/// Serializes a block into JSON.
+///
+/// Tokens hold the whitespaces and comments of the code (their trivia) and account for most
+/// of the size of the result, so they are only included when `include_tokens` is true. Note
+/// that the tokens of a block parsed with [`Parser::preserve_tokens`](crate::Parser::preserve_tokens)
+/// refer to the positions of the original code, which is needed to generate code from them.
+pub fn ast_to_json(block: &Block, include_tokens: bool) -> Result<String, DarkluaError> {
+    let mut value = serde_json::to_value(block).map_err(|err| {
+        DarkluaError::custom(format!("unable to serialize block to json: {}", err))
+    })?;
+
+    if !include_tokens {
+        remove_tokens(&mut value);
+    }
+
+    serde_json::to_string(&value)
+        .map_err(|err| DarkluaError::custom(format!("unable to serialize block to json: {}", err)))
+}
+
+/// Deserializes a block from JSON produced by [`ast_to_json`], with or without tokens.
+pub fn ast_from_json(json: &str) -> Result<Block, DarkluaError> {
+    serde_json::from_str(json).map_err(|err| {
+        DarkluaError::custom(format!("unable to deserialize block from json: {}", err))
+    })
+}
+
+fn remove_tokens(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.remove("token");
+            map.remove("tokens");
+
+            for (key, value) in map.iter_mut() {
+                if TOKEN_VARIANTS.contains(&key.as_str()) {
+                    *value = Value::Null;
+                } else {
+                    remove_tokens(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(remove_tokens),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::generator::{LuaGenerator, ReadableLuaGenerator, TokenBasedLuaGenerator};
+    use crate::Parser;
+
+    const CODE: &str = r#"
+local Module = {}
+-- a comment
+function Module.new(name: string, ...: number): Module
+    local self = setmetatable({ name = name, values = { ... } }, Module)
+    for i = 1, #self.values, 2 do
+        self.values[i] //= 2
+    end
+    return self
+end
+
+type Point<T = number> = { x: T, y: T, [string]: boolean? }
+
+local value = if Module.enabled then `value {Module.name}` else nil :: any
+while not value do
+    repeat value = (value or 0x10) + 0b101 until value > 1e3
+    if value == true then break elseif value == false then continue end
+end
+return Module, ...
+"#;
+
+    fn readable(block: &Block) -> String {
+        let mut generator = ReadableLuaGenerator::default();
+        generator.write_block(block);
+        generator.into_string()
+    }
+
+    #[test]
+    fn round_trip_without_tokens() {
+        let block = Parser::default().parse(CODE).unwrap();
+
+        let json = ast_to_json(&block, false).unwrap();
+        let deserialized = ast_from_json(&json).unwrap();
+
+        pretty_assertions::assert_eq!(readable(&deserialized), readable(&block));
+        pretty_assertions::assert_eq!(deserialized, block);
+    }
+
+    #[test]
+    fn round_trip_with_tokens() {
+        let block = Parser::default().preserve_tokens().parse(CODE).unwrap();
+
+        let json = ast_to_json(&block, true).unwrap();
+        let deserialized = ast_from_json(&json).unwrap();
+
+        pretty_assertions::assert_eq!(deserialized, block);
+
+        let mut generator = TokenBasedLuaGenerator::new(CODE);
+        generator.write_block(&deserialized);
+        pretty_assertions::assert_eq!(generator.into_string(), CODE);
+    }
+
+    #[test]
+    fn tokens_are_removed() {
+        let block = Parser::default().preserve_tokens().parse(CODE).unwrap();
+
+        let json = ast_to_json(&block, false).unwrap();
+        let deserialized = ast_from_json(&json).unwrap();
+
+        pretty_assertions::assert_eq!(deserialized, Parser::default().parse(CODE).unwrap());
+        pretty_assertions::assert_eq!(readable(&deserialized), readable(&block));
+    }
+
+    #[test]
+    fn tokens_dominate_the_size() {
+        let block = Parser::default().preserve_tokens().parse(CODE).unwrap();
+
+        assert!(
+            ast_to_json(&block, false).unwrap().len() < ast_to_json(&block, true).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn invalid_json_errors() {
+        let error = ast_from_json("{ \"statements\": 1 }").unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("unable to deserialize block from json: "));
+    }
+}
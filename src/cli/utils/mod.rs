@@ -32,6 +32,33 @@ pub fn report_process(
         process_duration
     );
 
+    for (source, warnings) in worker_tree.collect_warnings() {
+        eprintln!(
+            "{} warning{} in `{}`:",
+            warnings.len(),
+            maybe_plural(warnings.len()),
+            source.display()
+        );
+
+        for warning in warnings {
+            eprintln!("-> {}", warning);
+        }
+    }
+
+    let skipped_requires = worker_tree.collect_skipped_requires();
+
+    if !skipped_requires.is_empty() {
+        eprintln!(
+            "{} require call{} skipped:",
+            skipped_requires.len(),
+            maybe_plural(skipped_requires.len())
+        );
+
+        for warning in skipped_requires {
+            eprintln!("-> {}", warning);
+        }
+    }
+
     let errors = worker_tree.collect_errors();
 
     if errors.is_empty() {
@@ -1,21 +1,34 @@
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
+    convert::TryFrom,
     path::{Path, PathBuf},
+    rc::Rc,
     str::FromStr,
 };
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
-    generator::{DenseLuaGenerator, LuaGenerator, ReadableLuaGenerator, TokenBasedLuaGenerator, RetainLinesCompactLuaGenerator},
+    generator::{
+        DenseLuaGenerator, LuaGenerator, ReadableLuaGenerator, RetainLinesCompactLuaGenerator,
+        RetainLinesPreamble, TokenBasedLuaGenerator,
+    },
     nodes::Block,
+    process::{utils::is_valid_identifier, PureFunction},
     rules::{
-        bundle::{BundleRequireMode, Bundler},
-        get_default_rules, Rule,
+        bundle::{
+            BundleMetadata, BundleRequireMode, Bundler, CaseSensitivePaths, ExcludeRewrite,
+            IdentifierConflict, InternDataStrings, MetadataField, MetadataTemplate, ModuleNaming,
+            ModuleOrder, ModuleVarargs, MultiReturnModules, PcallRequires,
+        },
+        get_default_rules, QuoteStyle, Rule, RulePropertyValue, WarningCode,
+        INJECT_GLOBAL_VALUE_RULE_NAME,
     },
-    Parser,
+    DarkluaError, Parser,
 };
 
+use super::{target::LuaTarget, DarkluaResult};
+
 const DEFAULT_COLUMN_SPAN: usize = 80;
 
 fn get_default_column_span() -> usize {
@@ -28,6 +41,15 @@ fn get_default_column_span() -> usize {
 pub struct Configuration {
     #[serde(alias = "process", default = "get_default_rules")]
     rules: Vec<Box<dyn Rule>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_shared_rules",
+        deserialize_with = "deserialize_shared_rules"
+    )]
+    pre_bundle_rules: Vec<Rc<dyn Rule>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    post_rules: Option<Vec<Box<dyn Rule>>>,
     #[serde(default, deserialize_with = "crate::utils::string_or_struct")]
     generator: GeneratorParameters,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -36,6 +58,29 @@ pub struct Configuration {
     location: Option<PathBuf>,
     #[serde(default)]
     pub(crate) instance_indexing_is_pure: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pure_functions: Vec<PureFunction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<LuaTarget>,
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    warnings_as_errors: HashSet<WarningCode>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    copy_assets: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ignore: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ignore_files: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<String, ProfileConfiguration>,
+    #[serde(
+        default = "get_default_validate_output",
+        skip_serializing_if = "is_default_validate_output"
+    )]
+    validate_output: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    allow_overwrite: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    reproducible: bool,
 }
 
 impl Configuration {
@@ -43,10 +88,22 @@ impl Configuration {
     pub fn empty() -> Self {
         Self {
             rules: Vec::new(),
+            pre_bundle_rules: Vec::new(),
+            post_rules: None,
             generator: GeneratorParameters::default(),
             bundle: None,
             location: None,
             instance_indexing_is_pure: false,
+            pure_functions: Vec::new(),
+            target: None,
+            warnings_as_errors: HashSet::new(),
+            copy_assets: Vec::new(),
+            ignore: Vec::new(),
+            ignore_files: Vec::new(),
+            profiles: BTreeMap::new(),
+            validate_output: get_default_validate_output(),
+            allow_overwrite: false,
+            reproducible: false,
         }
     }
 
@@ -73,6 +130,23 @@ impl Configuration {
         self
     }
 
+    /// Adds a rule applied before bundling, to the processed file and to each bundled
+    /// module.
+    #[inline]
+    pub fn with_pre_bundle_rule(mut self, rule: impl Into<Box<dyn Rule>>) -> Self {
+        self.pre_bundle_rules.push(Rc::from(rule.into()));
+        self
+    }
+
+    /// Sets the rules applied after the configured rules. When not set, the
+    /// `remove_unused_variable` rule is applied if instance indexing is pure. An empty
+    /// list disables this final pass.
+    #[inline]
+    pub fn with_post_rules(mut self, rules: impl IntoIterator<Item = Box<dyn Rule>>) -> Self {
+        self.post_rules = Some(rules.into_iter().collect());
+        self
+    }
+
     /// Sets the bundle configuration for this configuration.
     #[inline]
     pub fn with_bundle_configuration(mut self, configuration: BundleConfiguration) -> Self {
@@ -80,6 +154,14 @@ impl Configuration {
         self
     }
 
+    /// Sets the bundle configuration for this configuration. This is the same as
+    /// [`with_bundle_configuration`](Self::with_bundle_configuration), to use with
+    /// [`BundleConfiguration::builder`].
+    #[inline]
+    pub fn with_bundle(self, configuration: BundleConfiguration) -> Self {
+        self.with_bundle_configuration(configuration)
+    }
+
     /// Sets the location of this configuration.
     #[inline]
     pub fn with_location(mut self, location: impl Into<PathBuf>) -> Self {
@@ -87,6 +169,91 @@ impl Configuration {
         self
     }
 
+    /// Sets the Lua version that the generated code must be compatible with.
+    #[inline]
+    pub fn with_target(mut self, target: LuaTarget) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Makes warnings with the given code fail the processing of the file
+    /// that emitted them.
+    #[inline]
+    pub fn with_warning_as_error(mut self, code: WarningCode) -> Self {
+        self.warnings_as_errors.insert(code);
+        self
+    }
+
+    /// Adds a glob pattern of the files copied as is to the output directory, when
+    /// processing a directory. Patterns starting with `!` exclude the matching files.
+    #[inline]
+    pub fn with_copy_asset(mut self, glob: impl Into<String>) -> Self {
+        self.copy_assets.push(glob.into());
+        self
+    }
+
+    /// Adds a pattern (using the gitignore syntax) of the files left out when processing a
+    /// directory. The pattern is relative to the processed directory.
+    #[inline]
+    pub fn with_ignore(mut self, pattern: impl Into<String>) -> Self {
+        self.ignore.push(pattern.into());
+        self
+    }
+
+    /// Adds the name of the ignore files (like `.gitignore`) read when processing a
+    /// directory. The patterns of an ignore file apply to the files of its directory, and
+    /// the patterns of nested ignore files take precedence.
+    #[inline]
+    pub fn with_ignore_file(mut self, file_name: impl Into<String>) -> Self {
+        self.ignore_files.push(file_name.into());
+        self
+    }
+
+    /// Adds a profile to this configuration. When profiles are defined, each file is
+    /// generated once for every profile, and the output path of each profile has its
+    /// suffix inserted before the extension (like `out.min.lua`).
+    #[inline]
+    pub fn with_profile(mut self, name: impl Into<String>, profile: ProfileConfiguration) -> Self {
+        self.profiles.insert(name.into(), profile);
+        self
+    }
+
+    /// Parses the generated code of each output again before writing it, to report rules
+    /// or generators producing invalid code. Invalid code is written next to the output
+    /// with an `.invalid` extension. Enabled by default when running darklua tests.
+    #[inline]
+    pub fn with_validate_output(mut self, validate: bool) -> Self {
+        self.validate_output = validate;
+        self
+    }
+
+    /// Allows different files to be written to the same output path, in which case the
+    /// last processed file overwrites the others. By default, processing fails before
+    /// writing any file.
+    #[inline]
+    pub fn with_allow_overwrite(mut self, allow: bool) -> Self {
+        self.allow_overwrite = allow;
+        self
+    }
+
+    /// Produces the same output files for the same inputs on any machine: the sources of
+    /// sourcemaps must be relative, the time placeholders of the bundle metadata are
+    /// removed, the dependency graph is sorted and rules cannot read environment variables.
+    #[inline]
+    pub fn with_reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+
+    /// Adds a function that rules can consider free of side effects when it is called with
+    /// arguments that are also free of side effects (like `Color3.fromRGB`, or `Maths.*`
+    /// for every function under `Maths`).
+    #[inline]
+    pub fn with_pure_function(mut self, function: PureFunction) -> Self {
+        self.pure_functions.push(function);
+        self
+    }
+
     /// Adds a rule to this configuration.
     #[inline]
     pub fn push_rule(&mut self, rule: impl Into<Box<dyn Rule>>) {
@@ -99,21 +266,130 @@ impl Configuration {
     }
 
     #[inline]
-    pub(crate) fn build_parser(&self) -> Parser {
-        self.generator.build_parser()
+    pub(crate) fn pre_bundle_rules<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = &'a dyn Rule> {
+        self.pre_bundle_rules.iter().map(AsRef::as_ref)
     }
 
     #[inline]
-    pub(crate) fn generate_lua(&self, block: &Block, code: &str) -> String {
-        self.generator.generate_lua(block, code)
+    pub(crate) fn profile_rules(&self) -> impl Iterator<Item = &dyn Rule> {
+        self.profiles
+            .values()
+            .filter_map(|profile| profile.rules.as_ref())
+            .flatten()
+            .map(AsRef::as_ref)
     }
 
     #[inline]
-    pub(crate) fn is_retain_lines(&self) -> bool { self.generator.is_retain_lines() }
+    pub(crate) fn post_rules<'a, 'b: 'a>(&'b self) -> Option<impl Iterator<Item = &'a dyn Rule>> {
+        self.post_rules
+            .as_ref()
+            .map(|rules| rules.iter().map(AsRef::as_ref))
+    }
 
-    #[inline]
-    pub(crate) fn retain_lines_compact_max_empty(&self) -> Option<usize> {
-        self.generator.retain_lines_compact_max_empty()
+    pub(crate) fn build_parser(&self) -> Parser {
+        // files are parsed once for all profiles, so tokens are kept if any profile needs them
+        if self
+            .profiles
+            .values()
+            .filter_map(|profile| profile.generator.as_ref())
+            .any(GeneratorParameters::is_retain_lines)
+        {
+            Parser::default().preserve_tokens()
+        } else {
+            self.generator.build_parser()
+        }
+    }
+
+    /// Returns true if any output generates a sourcemap from the tokens of the files.
+    pub(crate) fn has_retain_lines_sourcemap(&self) -> bool {
+        self.output_profiles().iter().any(|output| {
+            output.generator().is_retain_lines() && output.enabled_sourcemap().is_some()
+        })
+    }
+
+    pub(crate) fn case_sensitive_paths(&self) -> CaseSensitivePaths {
+        self.bundle
+            .as_ref()
+            .map(BundleConfiguration::case_sensitive_paths)
+            .unwrap_or_default()
+    }
+
+    fn is_sourcemap_enabled(&self) -> bool {
+        self.output_profiles()
+            .iter()
+            .any(|output| output.enabled_sourcemap().is_some())
+    }
+
+    pub(crate) fn output_profiles(&self) -> Vec<OutputProfile> {
+        let sourcemap = self
+            .bundle
+            .as_ref()
+            .and_then(BundleConfiguration::sourcemap);
+
+        if self.profiles.is_empty() {
+            return vec![OutputProfile {
+                suffix: None,
+                generator: self.generator.clone(),
+                sourcemap: sourcemap.cloned(),
+            }];
+        }
+
+        self.profiles
+            .iter()
+            .map(|(name, profile)| {
+                let suffix = profile.suffix.clone().unwrap_or_else(|| name.clone());
+                // an inherited sourcemap would be overwritten by each profile
+                let sourcemap = profile.sourcemap.clone().or_else(|| {
+                    sourcemap.map(|sourcemap| SourcemapOptions {
+                        output_path: sourcemap
+                            .output_path
+                            .as_ref()
+                            .map(|path| insert_suffix(path, &suffix)),
+                        ..sourcemap.clone()
+                    })
+                });
+
+                OutputProfile {
+                    generator: profile
+                        .generator
+                        .clone()
+                        .unwrap_or_else(|| self.generator.clone()),
+                    sourcemap,
+                    suffix: Some(suffix),
+                }
+            })
+            .collect()
+    }
+
+    /// Groups the outputs of the profiles that apply the same rules, so that the rules are
+    /// applied only once for each group.
+    pub(crate) fn profile_groups(&self) -> Vec<ProfileGroup<'_>> {
+        let profile_rules = self
+            .profiles
+            .values()
+            .map(|profile| profile.rules.as_deref().unwrap_or(&self.rules));
+        let rules: Vec<&[Box<dyn Rule>]> = if self.profiles.is_empty() {
+            vec![&self.rules]
+        } else {
+            profile_rules.collect()
+        };
+
+        let mut groups: Vec<ProfileGroup> = Vec::new();
+
+        for (rules, output) in rules.into_iter().zip(self.output_profiles()) {
+            match groups
+                .iter_mut()
+                .find(|group| are_same_rules(group.rules, rules))
+            {
+                Some(group) => group.outputs.push(output),
+                None => groups.push(ProfileGroup {
+                    rules,
+                    outputs: vec![output],
+                }),
+            }
+        }
+
+        groups
     }
 
     #[inline]
@@ -126,45 +402,244 @@ impl Configuration {
                 bundle_config.require_mode().clone(),
                 bundle_config.excludes(),
             )
-            .with_modules_identifier(bundle_config.modules_identifier());
-            // Pass project root to support relative exclude patterns
-            let bundler = if let Some(root) = self.location() {
-                bundler.with_project_root(root)
+            .with_modules_identifier(bundle_config.modules_identifier())
+            .with_strict_wait_for_child(bundle_config.is_strict_wait_for_child())
+            .with_verify_find_first_child(bundle_config.is_verify_find_first_child())
+            .with_hoist_excluded_requires(bundle_config.is_hoist_excluded_requires())
+            .with_exclude_rewrites(bundle_config.exclude_rewrites().to_vec())
+            .with_allow_non_module_requires(bundle_config.is_allow_non_module_requires())
+            .with_tree_shake_lazy_requires(bundle_config.is_tree_shake_lazy_requires())
+            .with_report_global_access(bundle_config.is_report_global_access())
+            .with_pcall_requires(bundle_config.pcall_requires())
+            .with_module_naming(bundle_config.module_naming())
+            .with_module_order(bundle_config.module_order())
+            .with_on_identifier_conflict(bundle_config.on_identifier_conflict())
+            .with_multi_return_modules(bundle_config.multi_return_modules())
+            .with_module_varargs(bundle_config.module_varargs())
+            .with_strip_types(bundle_config.is_strip_types())
+            .with_case_sensitive_paths(bundle_config.case_sensitive_paths())
+            .with_quote_style(bundle_config.quote_style())
+            .with_intern_data_strings(bundle_config.intern_data_strings())
+            .with_pre_bundle_rules(self.pre_bundle_rules.clone());
+            let bundler = if let Some(null_sentinel) = bundle_config.null_sentinel() {
+                bundler.with_null_sentinel(null_sentinel)
+            } else {
+                bundler
+            };
+            let bundler = if let Some(metadata) = bundle_config.metadata() {
+                bundler.with_metadata(if self.reproducible {
+                    metadata.without_time_fields()
+                } else {
+                    metadata
+                })
+            } else {
+                bundler
+            };
+            let bundler = bundler.with_raw_text_extensions(bundle_config.raw_text_extensions());
+            let bundler = if let Some(size) = bundle_config.max_raw_text_size() {
+                bundler.with_max_raw_text_size(size)
             } else {
                 bundler
             };
-            let bundler = if let Some(sm) = bundle_config.sourcemap() {
-                bundler.enable_sourcemap(sm.enabled)
+            let bundler = bundler.with_binary_extensions(bundle_config.binary_extensions());
+            let bundler = if let Some(wrapper) = bundle_config.binary_wrapper() {
+                bundler.with_binary_wrapper(wrapper)
             } else {
                 bundler
             };
-            Some(bundler)
+            let bundler = if let Some(size) = bundle_config.max_binary_size() {
+                bundler.with_max_binary_size(size)
+            } else {
+                bundler
+            };
+            let bundler = if let Some(max_depth) = bundle_config.max_depth() {
+                bundler.with_max_depth(max_depth)
+            } else {
+                bundler
+            };
+            let bundler = if bundle_config.graph_output().is_some() {
+                bundler.with_dependency_graph()
+            } else {
+                bundler
+            };
+            // Pass project root to support relative exclude patterns
+            let bundler = if let Some(root) = self.location() {
+                bundler.with_project_root(root)
+            } else {
+                bundler
+            };
+            Some(bundler.enable_sourcemap(self.is_sourcemap_enabled()))
         } else {
             None
         }
     }
 
     #[inline]
-    pub(crate) fn rules_len(&self) -> usize {
-        self.rules.len()
+    pub(crate) fn target(&self) -> Option<LuaTarget> {
+        self.target
+    }
+
+    #[inline]
+    pub(crate) fn is_warning_as_error(&self, code: WarningCode) -> bool {
+        self.warnings_as_errors.contains(&code)
+    }
+
+    #[inline]
+    pub(crate) fn copy_assets(&self) -> impl Iterator<Item = &str> {
+        self.copy_assets.iter().map(AsRef::as_ref)
+    }
+
+    #[inline]
+    pub(crate) fn ignore_patterns(&self) -> impl Iterator<Item = &str> {
+        self.ignore.iter().map(AsRef::as_ref)
+    }
+
+    #[inline]
+    pub(crate) fn ignore_files(&self) -> impl Iterator<Item = &str> {
+        self.ignore_files.iter().map(AsRef::as_ref)
+    }
+
+    #[inline]
+    pub(crate) fn has_ignore_rules(&self) -> bool {
+        !self.ignore.is_empty() || !self.ignore_files.is_empty()
     }
 
     #[inline]
     pub(crate) fn location(&self) -> Option<&Path> {
         self.location.as_deref()
     }
+
+    #[inline]
+    pub(crate) fn is_validate_output(&self) -> bool {
+        self.validate_output
+    }
+
+    #[inline]
+    pub(crate) fn is_allow_overwrite(&self) -> bool {
+        self.allow_overwrite
+    }
+
+    #[inline]
+    pub(crate) fn pure_functions(&self) -> &[PureFunction] {
+        &self.pure_functions
+    }
+
+    #[inline]
+    pub(crate) fn is_reproducible(&self) -> bool {
+        self.reproducible
+    }
+
+    /// Verifies that the rules do not depend on the environment where darklua runs, which
+    /// would produce different outputs on different machines.
+    pub(crate) fn verify_reproducible(&self) -> DarkluaResult<()> {
+        let all_rules = self
+            .rules()
+            .chain(self.pre_bundle_rules())
+            .chain(self.post_rules().into_iter().flatten())
+            .chain(self.profile_rules());
+
+        for rule in all_rules {
+            if rule.get_name() != INJECT_GLOBAL_VALUE_RULE_NAME {
+                continue;
+            }
+            let properties = rule.serialize_to_properties();
+            for property in ["env", "env_json"] {
+                if let Some(RulePropertyValue::String(variable)) = properties.get(property) {
+                    return Err(DarkluaError::custom(format!(
+                        "rule `{}` reads the environment variable `{}`, which is not allowed in reproducible mode",
+                        INJECT_GLOBAL_VALUE_RULE_NAME, variable
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for Configuration {
     fn default() -> Self {
         Self {
             rules: get_default_rules(),
+            pre_bundle_rules: Vec::new(),
+            post_rules: None,
             generator: Default::default(),
             bundle: None,
             location: None,
             instance_indexing_is_pure: false,
+            pure_functions: Vec::new(),
+            target: None,
+            warnings_as_errors: HashSet::new(),
+            copy_assets: Vec::new(),
+            ignore: Vec::new(),
+            ignore_files: Vec::new(),
+            profiles: BTreeMap::new(),
+            validate_output: get_default_validate_output(),
+            allow_overwrite: false,
+            reproducible: false,
+        }
+    }
+}
+
+fn are_same_rules(rules: &[Box<dyn Rule>], other_rules: &[Box<dyn Rule>]) -> bool {
+    std::ptr::eq(rules, other_rules)
+        || match (
+            serde_json::to_value(rules),
+            serde_json::to_value(other_rules),
+        ) {
+            (Ok(rules), Ok(other_rules)) => rules == other_rules,
+            _ => false,
+        }
+}
+
+// inserts the suffix before the extension (`out.lua` becomes `out.dev.lua`), and before the
+// extension of the generated file for sourcemaps (`out.lua.map` becomes `out.dev.lua.map`)
+fn insert_suffix(path: &Path, suffix: &str) -> PathBuf {
+    if suffix.is_empty() {
+        return path.to_path_buf();
+    }
+
+    let is_sourcemap = path.extension().is_some_and(|extension| extension == "map");
+    if let (true, Some(stem)) = (is_sourcemap, path.file_stem()) {
+        if Path::new(stem).extension().is_some() {
+            let mut name = insert_suffix(Path::new(stem), suffix).into_os_string();
+            name.push(".map");
+            return path.with_file_name(name);
         }
     }
+
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(extension)) => path.with_file_name(format!(
+            "{}.{}.{}",
+            stem.to_string_lossy(),
+            suffix,
+            extension.to_string_lossy()
+        )),
+        (Some(stem), None) => path.with_file_name(format!("{}.{}", stem.to_string_lossy(), suffix)),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn get_default_validate_output() -> bool {
+    cfg!(test)
+}
+
+fn is_default_validate_output(validate: &bool) -> bool {
+    *validate == get_default_validate_output()
+}
+
+fn serialize_shared_rules<S: Serializer>(
+    rules: &[Rc<dyn Rule>],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(rules.iter().map(AsRef::as_ref))
+}
+
+fn deserialize_shared_rules<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Rc<dyn Rule>>, D::Error> {
+    let rules = Vec::<Box<dyn Rule>>::deserialize(deserializer)?;
+    Ok(rules.into_iter().map(Rc::from).collect())
 }
 
 impl std::fmt::Debug for Configuration {
@@ -188,32 +663,153 @@ impl std::fmt::Debug for Configuration {
     }
 }
 
+/// A configuration overlay used to generate an additional output of each processed file.
+///
+/// Profiles share the parsing and the bundling of the files, and profiles that apply the
+/// same rules also share the result of these rules. Each setting that is not defined by the
+/// profile is taken from the configuration.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileConfiguration {
+    #[serde(alias = "process", default, skip_serializing_if = "Option::is_none")]
+    rules: Option<Vec<Box<dyn Rule>>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_profile_generator"
+    )]
+    generator: Option<GeneratorParameters>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sourcemap: Option<SourcemapOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    suffix: Option<String>,
+}
+
+impl ProfileConfiguration {
+    /// Adds a rule applied instead of the rules of the configuration.
+    #[inline]
+    pub fn with_rule(mut self, rule: impl Into<Box<dyn Rule>>) -> Self {
+        self.rules.get_or_insert_with(Vec::new).push(rule.into());
+        self
+    }
+
+    /// Sets the rules applied instead of the rules of the configuration. An empty list
+    /// applies no rules.
+    #[inline]
+    pub fn with_rules(mut self, rules: impl IntoIterator<Item = Box<dyn Rule>>) -> Self {
+        self.rules = Some(rules.into_iter().collect());
+        self
+    }
+
+    /// Sets the generator parameters of this profile.
+    #[inline]
+    pub fn with_generator(mut self, generator: GeneratorParameters) -> Self {
+        self.generator = Some(generator);
+        self
+    }
+
+    /// Sets the options of the sourcemap generated for this profile.
+    #[inline]
+    pub fn with_sourcemap(mut self, sourcemap: SourcemapOptions) -> Self {
+        self.sourcemap = Some(sourcemap);
+        self
+    }
+
+    /// Sets the suffix inserted before the extension of the output paths. By default,
+    /// the name of the profile is used. An empty suffix keeps the output paths unchanged.
+    #[inline]
+    pub fn with_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = Some(suffix.into());
+        self
+    }
+}
+
+fn deserialize_profile_generator<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<GeneratorParameters>, D::Error> {
+    crate::utils::string_or_struct(deserializer).map(Some)
+}
+
+/// An output of the processed files, generated with the settings of a profile.
+#[derive(Debug, Clone)]
+pub(crate) struct OutputProfile {
+    suffix: Option<String>,
+    generator: GeneratorParameters,
+    sourcemap: Option<SourcemapOptions>,
+}
+
+impl OutputProfile {
+    pub(crate) fn output_path(&self, output: &Path) -> PathBuf {
+        match &self.suffix {
+            Some(suffix) => insert_suffix(output, suffix),
+            None => output.to_path_buf(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn generator(&self) -> &GeneratorParameters {
+        &self.generator
+    }
+
+    #[inline]
+    pub(crate) fn enabled_sourcemap(&self) -> Option<&SourcemapOptions> {
+        self.sourcemap
+            .as_ref()
+            .filter(|sourcemap| sourcemap.enabled)
+    }
+}
+
+/// The outputs of the profiles that apply the same rules.
+pub(crate) struct ProfileGroup<'a> {
+    rules: &'a [Box<dyn Rule>],
+    outputs: Vec<OutputProfile>,
+}
+
+impl<'a> ProfileGroup<'a> {
+    #[inline]
+    pub(crate) fn rules(&self) -> impl Iterator<Item = &'a dyn Rule> {
+        self.rules.iter().map(AsRef::as_ref)
+    }
+
+    #[inline]
+    pub(crate) fn rules_len(&self) -> usize {
+        self.rules.len()
+    }
+
+    #[inline]
+    pub(crate) fn outputs(&self) -> &[OutputProfile] {
+        &self.outputs
+    }
+}
+
 /// Parameters for configuring the Lua code generator.
 ///
 /// This enum defines different modes for generating Lua code, each with its own
 /// formatting characteristics.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "snake_case", tag = "name")]
+#[serde(
+    try_from = "GeneratorParametersDefinition",
+    into = "GeneratorParametersDefinition"
+)]
 pub enum GeneratorParameters {
     /// Retains the original line structure of the input code.
-    #[serde(alias = "retain-lines")]
     RetainLines,
+    /// Retains the original line structure of the input code, with options for the lines
+    /// that do not fit in it.
+    RetainLinesWithOptions(RetainLinesOptions),
     /// Retains lines but compacts consecutive empty lines to a maximum count.
     RetainLinesCompact {
         /// Maximum number of consecutive empty lines to allow (default: 1)
-        #[serde(default = "GeneratorParameters::default_retain_lines_compact_max_empty")]
         max_empty_lines: usize,
     },
     /// Generates dense, compact code with a specified column span.
     Dense {
         /// The maximum number of characters per line.
-        #[serde(default = "get_default_column_span")]
         column_span: usize,
     },
     /// Attempts to generate readable code, with a specified column span.
     Readable {
         /// The maximum number of characters per line.
-        #[serde(default = "get_default_column_span")]
         column_span: usize,
     },
 }
@@ -242,13 +838,18 @@ impl GeneratorParameters {
     #[inline]
     fn default_retain_lines_compact_max_empty() -> usize { 1 }
 
-    fn generate_lua(&self, block: &Block, code: &str) -> String {
+    pub(crate) fn generate_lua(&self, block: &Block, code: &str) -> String {
         match self {
             Self::RetainLines => {
                 let mut generator = TokenBasedLuaGenerator::new(code);
                 generator.write_block(block);
                 generator.into_string()
             }
+            Self::RetainLinesWithOptions(options) => {
+                let mut generator = options.apply(TokenBasedLuaGenerator::new(code));
+                generator.write_block(block);
+                generator.into_string()
+            }
             Self::RetainLinesCompact { max_empty_lines } => {
                 let mut generator = RetainLinesCompactLuaGenerator::new(code, *max_empty_lines);
                 generator.write_block(block);
@@ -269,14 +870,27 @@ impl GeneratorParameters {
 
     fn build_parser(&self) -> Parser {
         match self {
-            Self::RetainLines | Self::RetainLinesCompact { .. } => Parser::default().preserve_tokens(),
+            Self::RetainLines
+            | Self::RetainLinesWithOptions(_)
+            | Self::RetainLinesCompact { .. } => Parser::default().preserve_tokens(),
             Self::Dense { .. } | Self::Readable { .. } => Parser::default(),
         }
     }
 
     #[inline]
     pub(crate) fn is_retain_lines(&self) -> bool {
-        matches!(self, Self::RetainLines | Self::RetainLinesCompact { .. })
+        matches!(
+            self,
+            Self::RetainLines | Self::RetainLinesWithOptions(_) | Self::RetainLinesCompact { .. }
+        )
+    }
+
+    #[inline]
+    pub(crate) fn retain_lines_options(&self) -> Option<&RetainLinesOptions> {
+        match self {
+            Self::RetainLinesWithOptions(options) => Some(options),
+            _ => None,
+        }
     }
 
     #[inline]
@@ -307,24 +921,221 @@ impl FromStr for GeneratorParameters {
     }
 }
 
-/// Configuration for bundling modules.
-///
-/// This struct defines how modules should be bundled together, including
-/// how requires are handled.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "snake_case")]
-pub struct BundleConfiguration {
-    #[serde(deserialize_with = "crate::utils::string_or_struct")]
-    require_mode: BundleRequireMode,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    modules_identifier: Option<String>,
-    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
-    excludes: HashSet<String>,
+/// Options of the `retain_lines` generator.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetainLinesOptions {
+    preamble: RetainLinesPreamble,
+    max_line_length: Option<usize>,
+}
+
+impl RetainLinesOptions {
+    /// Sets how the statements added before the original code are written.
+    pub fn with_preamble(mut self, preamble: RetainLinesPreamble) -> Self {
+        self.preamble = preamble;
+        self
+    }
+
+    /// Sets the maximum number of characters per line. Longer lines are split between
+    /// tokens. A maximum of zero does not split lines.
+    pub fn with_max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = Some(max_line_length);
+        self
+    }
+
+    /// Returns how the statements added before the original code are written.
+    pub fn preamble(&self) -> RetainLinesPreamble {
+        self.preamble
+    }
+
+    /// Returns the maximum number of characters per line, if any.
+    pub fn max_line_length(&self) -> Option<usize> {
+        self.max_line_length
+    }
+
+    pub(crate) fn apply<'a>(
+        &self,
+        generator: TokenBasedLuaGenerator<'a>,
+    ) -> TokenBasedLuaGenerator<'a> {
+        let generator = generator.with_preamble(self.preamble);
+        if let Some(max_line_length) = self.max_line_length {
+            generator.with_max_line_length(max_line_length)
+        } else {
+            generator
+        }
+    }
+}
+
+// The serialized form of the generator parameters. The options of the `retain_lines`
+// generator are written next to its name, and the generator without options is converted
+// to `GeneratorParameters::RetainLines`.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "snake_case", tag = "name")]
+enum GeneratorParametersDefinition {
+    #[serde(alias = "retain-lines")]
+    RetainLines {
+        #[serde(default, skip_serializing_if = "RetainLinesPreamble::is_pack")]
+        preamble: RetainLinesPreamble,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_line_length: Option<usize>,
+    },
+    RetainLinesCompact {
+        #[serde(default = "GeneratorParameters::default_retain_lines_compact_max_empty")]
+        max_empty_lines: usize,
+    },
+    Dense {
+        #[serde(default = "get_default_column_span")]
+        column_span: usize,
+    },
+    Readable {
+        #[serde(default = "get_default_column_span")]
+        column_span: usize,
+    },
+}
+
+impl TryFrom<GeneratorParametersDefinition> for GeneratorParameters {
+    type Error = String;
+
+    fn try_from(definition: GeneratorParametersDefinition) -> Result<Self, Self::Error> {
+        Ok(match definition {
+            GeneratorParametersDefinition::RetainLines {
+                preamble,
+                max_line_length,
+            } => {
+                if max_line_length == Some(0) {
+                    return Err("`max_line_length` must be greater than zero".to_owned());
+                }
+                let options = RetainLinesOptions {
+                    preamble,
+                    max_line_length,
+                };
+                if options == RetainLinesOptions::default() {
+                    Self::RetainLines
+                } else {
+                    Self::RetainLinesWithOptions(options)
+                }
+            }
+            GeneratorParametersDefinition::RetainLinesCompact { max_empty_lines } => {
+                Self::RetainLinesCompact { max_empty_lines }
+            }
+            GeneratorParametersDefinition::Dense { column_span } => Self::Dense { column_span },
+            GeneratorParametersDefinition::Readable { column_span } => {
+                Self::Readable { column_span }
+            }
+        })
+    }
+}
+
+impl From<GeneratorParameters> for GeneratorParametersDefinition {
+    fn from(parameters: GeneratorParameters) -> Self {
+        match parameters {
+            GeneratorParameters::RetainLines => Self::RetainLines {
+                preamble: RetainLinesPreamble::default(),
+                max_line_length: None,
+            },
+            GeneratorParameters::RetainLinesWithOptions(options) => Self::RetainLines {
+                preamble: options.preamble,
+                max_line_length: options.max_line_length,
+            },
+            GeneratorParameters::RetainLinesCompact { max_empty_lines } => {
+                Self::RetainLinesCompact { max_empty_lines }
+            }
+            GeneratorParameters::Dense { column_span } => Self::Dense { column_span },
+            GeneratorParameters::Readable { column_span } => Self::Readable { column_span },
+        }
+    }
+}
+
+/// Configuration for bundling modules.
+///
+/// This struct defines how modules should be bundled together, including
+/// how requires are handled.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub struct BundleConfiguration {
+    #[serde(deserialize_with = "crate::utils::string_or_struct")]
+    require_mode: BundleRequireMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modules_identifier: Option<String>,
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    excludes: HashSet<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude_rewrites: Vec<ExcludeRewrite>,
     #[serde(skip_serializing_if = "Option::is_none")]
     sourcemap: Option<SourcemapOptions>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    strict_wait_for_child: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    verify_find_first_child: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    hoist_excluded_requires: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    allow_non_module_requires: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    tree_shake_lazy_requires: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    report_global_access: bool,
+    #[serde(default, skip_serializing_if = "PcallRequires::is_ignore")]
+    pcall_requires: PcallRequires,
+    #[serde(default, skip_serializing_if = "ModuleNaming::is_sequential")]
+    module_naming: ModuleNaming,
+    #[serde(default, skip_serializing_if = "ModuleOrder::is_discovery")]
+    module_order: ModuleOrder,
+    #[serde(default, skip_serializing_if = "IdentifierConflict::is_error")]
+    on_identifier_conflict: IdentifierConflict,
+    #[serde(default, skip_serializing_if = "MultiReturnModules::is_error")]
+    multi_return_modules: MultiReturnModules,
+    #[serde(default, skip_serializing_if = "ModuleVarargs::is_error")]
+    module_varargs: ModuleVarargs,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    strip_types: bool,
+    #[serde(default, skip_serializing_if = "CaseSensitivePaths::is_auto")]
+    case_sensitive_paths: CaseSensitivePaths,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quote_style: Option<QuoteStyle>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    null_sentinel: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    intern_data_strings: Option<InternDataStrings>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_metadata_variable"
+    )]
+    metadata_variable: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, MetadataField>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    raw_text_extensions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_raw_text_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    binary_extensions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    binary_wrapper: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_binary_size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_depth: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    graph_output: Option<PathBuf>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_output_size"
+    )]
+    max_output_size: Option<usize>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_output_size"
+    )]
+    warn_output_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    prune_bundled_files: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Options of the sourcemap generated when bundling.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields, rename_all = "snake_case")]
 pub struct SourcemapOptions {
     #[serde(default)]
@@ -350,215 +1161,1438 @@ impl BundleConfiguration {
             require_mode: require_mode.into(),
             modules_identifier: None,
             excludes: Default::default(),
+            exclude_rewrites: Vec::new(),
             sourcemap: None,
+            strict_wait_for_child: false,
+            verify_find_first_child: false,
+            hoist_excluded_requires: false,
+            allow_non_module_requires: false,
+            tree_shake_lazy_requires: false,
+            report_global_access: false,
+            pcall_requires: PcallRequires::default(),
+            module_naming: ModuleNaming::default(),
+            module_order: ModuleOrder::default(),
+            on_identifier_conflict: IdentifierConflict::default(),
+            multi_return_modules: MultiReturnModules::default(),
+            module_varargs: ModuleVarargs::default(),
+            strip_types: false,
+            case_sensitive_paths: CaseSensitivePaths::default(),
+            quote_style: None,
+            null_sentinel: None,
+            intern_data_strings: None,
+            metadata_variable: None,
+            metadata: BTreeMap::new(),
+            raw_text_extensions: Vec::new(),
+            max_raw_text_size: None,
+            binary_extensions: Vec::new(),
+            binary_wrapper: None,
+            max_binary_size: None,
+            max_depth: None,
+            graph_output: None,
+            max_output_size: None,
+            warn_output_size: None,
+            prune_bundled_files: false,
+        }
+    }
+
+    /// Creates a builder for a bundle configuration, which uses the `path` require mode
+    /// unless another one is set.
+    pub fn builder() -> BundleConfigurationBuilder {
+        BundleConfigurationBuilder::default()
+    }
+
+    /// Sets the modules identifier for this bundle configuration.
+    pub fn with_modules_identifier(mut self, modules_identifier: impl Into<String>) -> Self {
+        self.modules_identifier = Some(modules_identifier.into());
+        self
+    }
+
+    /// Adds a module to exclude from bundling.
+    pub fn with_exclude(mut self, exclude: impl Into<String>) -> Self {
+        self.excludes.insert(exclude.into());
+        self
+    }
+
+    /// Rewrites the excluded requires matching the pattern of the given rewrite to its
+    /// target DataModel path. When multiple patterns match, the first one added is used.
+    pub fn with_exclude_rewrite(mut self, rewrite: ExcludeRewrite) -> Self {
+        self.exclude_rewrites.push(rewrite);
+        self
+    }
+
+    /// Skips inlining requires that locate their module with a `WaitForChild` call
+    /// using a timeout, instead of only emitting a warning.
+    pub fn with_strict_wait_for_child(mut self, strict: bool) -> Self {
+        self.strict_wait_for_child = strict;
+        self
+    }
+
+    /// Reports an error when a require locates its module with a `FindFirstChild` call
+    /// and the child does not exist in the Rojo sourcemap, instead of leaving the
+    /// require untouched.
+    pub fn with_verify_find_first_child(mut self, verify: bool) -> Self {
+        self.verify_find_first_child = verify;
+        self
+    }
+
+    /// Declares a single local at the top of the bundle for each distinct excluded require,
+    /// and replaces every call site with that local.
+    pub fn with_hoist_excluded_requires(mut self, hoist: bool) -> Self {
+        self.hoist_excluded_requires = hoist;
+        self
+    }
+
+    /// Allows requires of instances that are not a ModuleScript (like a Folder or a
+    /// LocalScript) when bundling with the Roblox require mode, instead of reporting an error.
+    pub fn with_allow_non_module_requires(mut self, allow: bool) -> Self {
+        self.allow_non_module_requires = allow;
+        self
+    }
+
+    /// Leaves out of the bundle the requires found inside local functions that are never
+    /// referenced, so that their modules are not bundled. The `remove_unused_variable` rule
+    /// can then remove these functions.
+    pub fn with_tree_shake_lazy_requires(mut self, tree_shake: bool) -> Self {
+        self.tree_shake_lazy_requires = tree_shake;
+        self
+    }
+
+    /// Reports a warning for each read or write of the `_G` and `shared` globals in the
+    /// bundled modules, since bundling can change when these globals are initialized.
+    pub fn with_report_global_access(mut self, report: bool) -> Self {
+        self.report_global_access = report;
+        self
+    }
+
+    /// Sets how requires made through `pcall(require, ...)` or `xpcall(require, handler, ...)`
+    /// are bundled.
+    pub fn with_pcall_requires(mut self, pcall_requires: PcallRequires) -> Self {
+        self.pcall_requires = pcall_requires;
+        self
+    }
+
+    /// Sets how modules are named in the bundle.
+    pub fn with_module_naming(mut self, module_naming: ModuleNaming) -> Self {
+        self.module_naming = module_naming;
+        self
+    }
+
+    /// Sets the order of the module definitions written in the bundle.
+    pub fn with_module_order(mut self, module_order: ModuleOrder) -> Self {
+        self.module_order = module_order;
+        self
+    }
+
+    /// Sets how modules that declare a variable named like the modules identifier are
+    /// handled.
+    pub fn with_on_identifier_conflict(
+        mut self,
+        on_identifier_conflict: IdentifierConflict,
+    ) -> Self {
+        self.on_identifier_conflict = on_identifier_conflict;
+        self
+    }
+
+    /// Sets how modules that do not return exactly one value are bundled.
+    pub fn with_multi_return_modules(mut self, multi_return_modules: MultiReturnModules) -> Self {
+        self.multi_return_modules = multi_return_modules;
+        self
+    }
+
+    /// Sets how required modules that use `...` outside of a function are bundled.
+    pub fn with_module_varargs(mut self, module_varargs: ModuleVarargs) -> Self {
+        self.module_varargs = module_varargs;
+        self
+    }
+
+    /// Removes every type annotation and type declaration from the bundle, including the
+    /// types forwarded by the bundler, so that it can run on Lua runtimes.
+    pub fn with_strip_types(mut self, strip_types: bool) -> Self {
+        self.strip_types = strip_types;
+        self
+    }
+
+    /// Sets if paths that only differ by their casing point to different modules. By
+    /// default, this follows the behavior of the platform.
+    pub fn with_case_sensitive_paths(mut self, case_sensitive_paths: CaseSensitivePaths) -> Self {
+        self.case_sensitive_paths = case_sensitive_paths;
+        self
+    }
+
+    /// Sets the quote used to write the strings generated by the bundler, like the
+    /// module names given to the `load` function or the paths of excluded requires.
+    pub fn with_quote_style(mut self, quote_style: QuoteStyle) -> Self {
+        self.quote_style = Some(quote_style);
+        self
+    }
+
+    /// Converts null values of bundled data files (json, yaml or toml) into a reference
+    /// to a table declared at the top of the bundle with the given identifier, instead of `nil`.
+    pub fn with_null_sentinel(mut self, identifier: impl Into<String>) -> Self {
+        self.null_sentinel = Some(identifier.into());
+        self
+    }
+
+    /// Hoists the long strings repeated in bundled data files (json, yaml or toml) into
+    /// locals declared in the module, instead of repeating each string.
+    pub fn with_intern_data_strings(mut self, intern_data_strings: InternDataStrings) -> Self {
+        self.intern_data_strings = Some(intern_data_strings);
+        self
+    }
+
+    /// Declares a local variable with the given name at the top of the bundle (after the
+    /// module definitions), assigned to a table of information about the build.
+    pub fn with_metadata_variable(mut self, variable: impl Into<String>) -> Self {
+        self.metadata_variable = Some(variable.into());
+        self
+    }
+
+    /// Sets a field of the metadata table, replacing the default field with the same name.
+    pub fn with_metadata_field(
+        mut self,
+        name: impl Into<String>,
+        template: MetadataTemplate,
+    ) -> Self {
+        self.metadata
+            .insert(name.into(), MetadataField::Template(template));
+        self
+    }
+
+    /// Removes a field from the metadata table, like the `time` field for reproducible
+    /// builds.
+    pub fn without_metadata_field(mut self, name: impl Into<String>) -> Self {
+        self.metadata.insert(name.into(), MetadataField::Disabled);
+        self
+    }
+
+    /// Adds a file extension that is required as a string of the file content, like
+    /// `txt` files.
+    pub fn with_raw_text_extension(mut self, extension: impl Into<String>) -> Self {
+        self.raw_text_extensions.push(extension.into());
+        self
+    }
+
+    /// Sets the maximum size in bytes of files required as strings.
+    pub fn with_max_raw_text_size(mut self, size: usize) -> Self {
+        self.max_raw_text_size = Some(size);
+        self
+    }
+
+    /// Adds a file extension that is required as a base64 encoded string of the file content.
+    pub fn with_binary_extension(mut self, extension: impl Into<String>) -> Self {
+        self.binary_extensions.push(extension.into());
+        self
+    }
+
+    /// Sets the function (like `Base64.decode`) called with the base64 encoded string of
+    /// binary files.
+    pub fn with_binary_wrapper(mut self, wrapper: impl Into<String>) -> Self {
+        self.binary_wrapper = Some(wrapper.into());
+        self
+    }
+
+    /// Sets the maximum size in bytes of binary files.
+    pub fn with_max_binary_size(mut self, size: usize) -> Self {
+        self.max_binary_size = Some(size);
+        self
+    }
+
+    /// Sets the maximum number of nested requires (a module requiring a module requiring
+    /// another module, and so on) before bundling fails. The default is 200.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Sets the path of a file where the dependency graph of the bundle is written in the
+    /// DOT format. A relative path is resolved from the configuration file location.
+    pub fn with_graph_output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.graph_output = Some(path.into());
+        self
+    }
+
+    /// Sets the maximum size in bytes of the generated code of a bundle. A larger output
+    /// is reported as an error that lists the largest bundled files.
+    pub fn with_max_output_size(mut self, size: usize) -> Self {
+        self.max_output_size = Some(size);
+        self
+    }
+
+    /// Sets the size in bytes of the generated code of a bundle above which a warning is
+    /// reported.
+    pub fn with_warn_output_size(mut self, size: usize) -> Self {
+        self.warn_output_size = Some(size);
+        self
+    }
+
+    /// Sets the options of the generated sourcemap.
+    pub fn with_sourcemap(mut self, sourcemap: SourcemapOptions) -> Self {
+        self.sourcemap = Some(sourcemap);
+        self
+    }
+
+    /// Removes the output of files that were entirely inlined into another bundled file,
+    /// when processing a directory into an output directory.
+    pub fn with_prune_bundled_files(mut self, prune: bool) -> Self {
+        self.prune_bundled_files = prune;
+        self
+    }
+
+    pub(crate) fn require_mode(&self) -> &BundleRequireMode {
+        &self.require_mode
+    }
+
+    pub(crate) fn modules_identifier(&self) -> &str {
+        self.modules_identifier
+            .as_ref()
+            .map(AsRef::as_ref)
+            .unwrap_or("__DARKLUA_BUNDLE_MODULES")
+    }
+
+    pub(crate) fn excludes(&self) -> impl Iterator<Item = &str> {
+        self.excludes.iter().map(AsRef::as_ref)
+    }
+
+    pub(crate) fn exclude_rewrites(&self) -> &[ExcludeRewrite] {
+        &self.exclude_rewrites
+    }
+
+    pub(crate) fn sourcemap(&self) -> Option<&SourcemapOptions> { self.sourcemap.as_ref() }
+
+    pub(crate) fn is_strict_wait_for_child(&self) -> bool {
+        self.strict_wait_for_child
+    }
+
+    pub(crate) fn is_verify_find_first_child(&self) -> bool {
+        self.verify_find_first_child
+    }
+
+    pub(crate) fn is_hoist_excluded_requires(&self) -> bool {
+        self.hoist_excluded_requires
+    }
+
+    pub(crate) fn is_allow_non_module_requires(&self) -> bool {
+        self.allow_non_module_requires
+    }
+
+    pub(crate) fn is_tree_shake_lazy_requires(&self) -> bool {
+        self.tree_shake_lazy_requires
+    }
+
+    pub(crate) fn is_report_global_access(&self) -> bool {
+        self.report_global_access
+    }
+
+    pub(crate) fn pcall_requires(&self) -> PcallRequires {
+        self.pcall_requires
+    }
+
+    pub(crate) fn module_naming(&self) -> ModuleNaming {
+        self.module_naming
+    }
+
+    pub(crate) fn module_order(&self) -> ModuleOrder {
+        self.module_order
+    }
+
+    pub(crate) fn on_identifier_conflict(&self) -> IdentifierConflict {
+        self.on_identifier_conflict
+    }
+
+    pub(crate) fn multi_return_modules(&self) -> MultiReturnModules {
+        self.multi_return_modules
+    }
+
+    pub(crate) fn module_varargs(&self) -> ModuleVarargs {
+        self.module_varargs
+    }
+
+    pub(crate) fn is_strip_types(&self) -> bool {
+        self.strip_types
+    }
+
+    pub(crate) fn case_sensitive_paths(&self) -> CaseSensitivePaths {
+        self.case_sensitive_paths
+    }
+
+    pub(crate) fn quote_style(&self) -> Option<QuoteStyle> {
+        self.quote_style
+    }
+
+    pub(crate) fn null_sentinel(&self) -> Option<&str> {
+        self.null_sentinel.as_deref()
+    }
+
+    pub(crate) fn intern_data_strings(&self) -> Option<InternDataStrings> {
+        self.intern_data_strings
+    }
+
+    pub(crate) fn metadata(&self) -> Option<BundleMetadata> {
+        self.metadata_variable
+            .as_ref()
+            .map(|variable| BundleMetadata::new(variable.as_str(), self.metadata.iter()))
+    }
+
+    pub(crate) fn raw_text_extensions(&self) -> impl Iterator<Item = &str> {
+        self.raw_text_extensions.iter().map(AsRef::as_ref)
+    }
+
+    pub(crate) fn max_raw_text_size(&self) -> Option<usize> {
+        self.max_raw_text_size
+    }
+
+    pub(crate) fn binary_extensions(&self) -> impl Iterator<Item = &str> {
+        self.binary_extensions.iter().map(AsRef::as_ref)
+    }
+
+    pub(crate) fn binary_wrapper(&self) -> Option<&str> {
+        self.binary_wrapper.as_deref()
+    }
+
+    pub(crate) fn max_binary_size(&self) -> Option<usize> {
+        self.max_binary_size
+    }
+
+    pub(crate) fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    pub(crate) fn graph_output(&self) -> Option<&Path> {
+        self.graph_output.as_deref()
+    }
+
+    pub(crate) fn is_prune_bundled_files(&self) -> bool {
+        self.prune_bundled_files
+    }
+
+    pub(crate) fn max_output_size(&self) -> Option<usize> {
+        self.max_output_size
+    }
+
+    pub(crate) fn warn_output_size(&self) -> Option<usize> {
+        self.warn_output_size
+    }
+}
+
+fn deserialize_metadata_variable<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    let variable = String::deserialize(deserializer)?;
+
+    if is_valid_identifier(&variable) {
+        Ok(Some(variable))
+    } else {
+        Err(serde::de::Error::custom(format!(
+            "invalid metadata variable `{}` (expected an identifier)",
+            variable
+        )))
+    }
+}
+
+// sizes are written as a number of bytes, or as a string with a `B`, `KB` or `MB` suffix
+fn deserialize_output_size<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<usize>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OutputSize {
+        Bytes(usize),
+        Text(String),
+    }
+
+    match OutputSize::deserialize(deserializer)? {
+        OutputSize::Bytes(size) => Ok(Some(size)),
+        OutputSize::Text(text) => parse_output_size(&text)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+fn parse_output_size(text: &str) -> Result<usize, String> {
+    let text = text.trim();
+    let upper_text = text.to_ascii_uppercase();
+
+    let (number, multiplier) = if let Some(number) = upper_text.strip_suffix("MB") {
+        (number, 1024 * 1024)
+    } else if let Some(number) = upper_text.strip_suffix("KB") {
+        (number, 1024)
+    } else if let Some(number) = upper_text.strip_suffix('B') {
+        (number, 1)
+    } else {
+        (upper_text.as_str(), 1)
+    };
+
+    match number.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => Ok((value * multiplier as f64) as usize),
+        _ => Err(format!(
+            "invalid size `{}` (expected a number of bytes optionally followed by `B`, `KB` or `MB`)",
+            text
+        )),
+    }
+}
+
+/// A builder for a [`BundleConfiguration`], created with [`BundleConfiguration::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct BundleConfigurationBuilder {
+    require_mode: BundleRequireMode,
+    excludes: Vec<String>,
+    modules_identifier: Option<String>,
+    sourcemap: Option<SourcemapOptions>,
+}
+
+impl BundleConfigurationBuilder {
+    /// Sets the require mode used to find the bundled modules.
+    pub fn require_mode(mut self, require_mode: impl Into<BundleRequireMode>) -> Self {
+        self.require_mode = require_mode.into();
+        self
+    }
+
+    /// Adds a glob pattern of the requires to exclude from the bundle.
+    pub fn exclude(mut self, glob: impl Into<String>) -> Self {
+        self.excludes.push(glob.into());
+        self
+    }
+
+    /// Sets the identifier of the variable holding the bundled modules.
+    pub fn modules_identifier(mut self, modules_identifier: impl Into<String>) -> Self {
+        self.modules_identifier = Some(modules_identifier.into());
+        self
+    }
+
+    /// Sets the options of the generated sourcemap.
+    pub fn sourcemap(mut self, sourcemap: SourcemapOptions) -> Self {
+        self.sourcemap = Some(sourcemap);
+        self
+    }
+
+    /// Builds the bundle configuration.
+    pub fn build(self) -> BundleConfiguration {
+        let mut configuration = BundleConfiguration::new(self.require_mode);
+        for exclude in self.excludes {
+            configuration = configuration.with_exclude(exclude);
+        }
+        if let Some(modules_identifier) = self.modules_identifier {
+            configuration = configuration.with_modules_identifier(modules_identifier);
+        }
+        if let Some(sourcemap) = self.sourcemap {
+            configuration = configuration.with_sourcemap(sourcemap);
+        }
+        configuration
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod generator_parameters {
+        use super::*;
+
+        #[test]
+        fn deserialize_retain_lines_params() {
+            let config: Configuration =
+                json5::from_str("{ generator: { name: 'retain_lines' } }").unwrap();
+
+            pretty_assertions::assert_eq!(config.generator, GeneratorParameters::RetainLines);
+        }
+
+        #[test]
+        fn deserialize_retain_lines_params_with_expand_preamble() {
+            let config: Configuration =
+                json5::from_str("{ generator: { name: 'retain_lines', preamble: 'expand' } }")
+                    .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.generator,
+                GeneratorParameters::RetainLinesWithOptions(
+                    RetainLinesOptions::default().with_preamble(RetainLinesPreamble::Expand)
+                )
+            );
+        }
+
+        #[test]
+        fn deserialize_retain_lines_params_with_max_line_length() {
+            let config: Configuration =
+                json5::from_str("{ generator: { name: 'retain_lines', max_line_length: 4000 } }")
+                    .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.generator,
+                GeneratorParameters::RetainLinesWithOptions(
+                    RetainLinesOptions::default().with_max_line_length(4000)
+                )
+            );
+        }
+
+        #[test]
+        fn deserialize_retain_lines_params_with_zero_max_line_length_errors() {
+            let error = json5::from_str::<Configuration>(
+                "{ generator: { name: 'retain_lines', max_line_length: 0 } }",
+            )
+            .unwrap_err();
+
+            pretty_assertions::assert_eq!(
+                error.to_string(),
+                "`max_line_length` must be greater than zero"
+            );
+        }
+
+        #[test]
+        fn deserialize_retain_lines_params_with_default_options() {
+            let config: Configuration =
+                json5::from_str("{ generator: { name: 'retain_lines', preamble: 'pack' } }")
+                    .unwrap();
+
+            pretty_assertions::assert_eq!(config.generator, GeneratorParameters::RetainLines);
+        }
+
+        #[test]
+        fn serialize_retain_lines_params_with_options() {
+            let generator = GeneratorParameters::RetainLinesWithOptions(
+                RetainLinesOptions::default()
+                    .with_preamble(RetainLinesPreamble::Expand)
+                    .with_max_line_length(100),
+            );
+
+            pretty_assertions::assert_eq!(
+                serde_json::to_string(&generator).unwrap(),
+                r#"{"name":"retain_lines","preamble":"expand","max_line_length":100}"#
+            );
+        }
+
+        #[test]
+        fn deserialize_retain_lines_params_deprecated() {
+            let config: Configuration =
+                json5::from_str("{ generator: { name: 'retain-lines' } }").unwrap();
+
+            pretty_assertions::assert_eq!(config.generator, GeneratorParameters::RetainLines);
+        }
+
+        #[test]
+        fn deserialize_dense_params() {
+            let config: Configuration = json5::from_str("{ generator: { name: 'dense' }}").unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.generator,
+                GeneratorParameters::Dense {
+                    column_span: DEFAULT_COLUMN_SPAN
+                }
+            );
+        }
+
+        #[test]
+        fn deserialize_dense_params_with_column_span() {
+            let config: Configuration =
+                json5::from_str("{ generator: { name: 'dense', column_span: 110 } }").unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.generator,
+                GeneratorParameters::Dense { column_span: 110 }
+            );
+        }
+
+        #[test]
+        fn deserialize_readable_params() {
+            let config: Configuration =
+                json5::from_str("{ generator: { name: 'readable' } }").unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.generator,
+                GeneratorParameters::Readable {
+                    column_span: DEFAULT_COLUMN_SPAN
+                }
+            );
+        }
+
+        #[test]
+        fn deserialize_readable_params_with_column_span() {
+            let config: Configuration =
+                json5::from_str("{ generator: { name: 'readable', column_span: 110 }}").unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.generator,
+                GeneratorParameters::Readable { column_span: 110 }
+            );
+        }
+
+        #[test]
+        fn deserialize_retain_lines_params_as_string() {
+            let config: Configuration = json5::from_str("{generator: 'retain_lines'}").unwrap();
+
+            pretty_assertions::assert_eq!(config.generator, GeneratorParameters::RetainLines);
+        }
+
+        #[test]
+        fn deserialize_dense_params_as_string() {
+            let config: Configuration = json5::from_str("{generator: 'dense'}").unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.generator,
+                GeneratorParameters::Dense {
+                    column_span: DEFAULT_COLUMN_SPAN
+                }
+            );
+        }
+
+        #[test]
+        fn deserialize_readable_params_as_string() {
+            let config: Configuration = json5::from_str("{generator: 'readable'}").unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.generator,
+                GeneratorParameters::Readable {
+                    column_span: DEFAULT_COLUMN_SPAN
+                }
+            );
+        }
+
+        #[test]
+        fn deserialize_unknown_generator_name() {
+            let result: Result<Configuration, _> = json5::from_str("{generator: 'oops'}");
+
+            pretty_assertions::assert_eq!(
+                result.expect_err("deserialization should fail").to_string(),
+                "invalid generator name `oops`"
+            );
+        }
+    }
+
+    mod target {
+        use super::*;
+
+        #[test]
+        fn deserialize_without_target() {
+            let config: Configuration = json5::from_str("{}").unwrap();
+
+            pretty_assertions::assert_eq!(config.target, None);
+        }
+
+        #[test]
+        fn deserialize_lua51_target() {
+            let config: Configuration = json5::from_str("{ target: 'lua51' }").unwrap();
+
+            pretty_assertions::assert_eq!(config.target, Some(LuaTarget::Lua51));
+        }
+
+        #[test]
+        fn deserialize_unknown_target() {
+            let result: Result<Configuration, _> = json5::from_str("{ target: 'lua54' }");
+
+            assert!(result.is_err());
+        }
+    }
+
+    mod warnings_as_errors {
+        use super::*;
+
+        #[test]
+        fn deserialize_without_warnings_as_errors() {
+            let config: Configuration = json5::from_str("{}").unwrap();
+
+            assert!(!config.is_warning_as_error(WarningCode::UnresolvedRequire));
+        }
+
+        #[test]
+        fn deserialize_unresolved_require() {
+            let config: Configuration =
+                json5::from_str("{ warnings_as_errors: ['unresolved_require'] }").unwrap();
+
+            assert!(config.is_warning_as_error(WarningCode::UnresolvedRequire));
+            assert!(!config.is_warning_as_error(WarningCode::ExcludedFallbackScriptRoot));
+        }
+
+        #[test]
+        fn deserialize_unknown_warning_code() {
+            let result: Result<Configuration, _> =
+                json5::from_str("{ warnings_as_errors: ['oops'] }");
+
+            assert!(result.is_err());
+        }
+    }
+
+    mod ignore {
+        use super::*;
+
+        #[test]
+        fn deserialize_ignore_patterns_and_files() {
+            let config: Configuration = json5::from_str(
+                "{ ignore: ['dist/**'], ignore_files: ['.gitignore', '.darkluaignore'] }",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.ignore_patterns().collect::<Vec<_>>(),
+                vec!["dist/**"]
+            );
+            pretty_assertions::assert_eq!(
+                config.ignore_files().collect::<Vec<_>>(),
+                vec![".gitignore", ".darkluaignore"]
+            );
+            assert!(config.has_ignore_rules());
+        }
+
+        #[test]
+        fn deserialize_without_ignore_rules() {
+            let config: Configuration = json5::from_str("{}").unwrap();
+
+            assert!(!config.has_ignore_rules());
+        }
+    }
+
+    mod reproducible {
+        use super::*;
+
+        #[test]
+        fn deserialize_reproducible() {
+            let config: Configuration = json5::from_str("{ reproducible: true }").unwrap();
+
+            assert!(config.is_reproducible());
+            config.verify_reproducible().unwrap();
+        }
+
+        #[test]
+        fn verify_rule_reading_environment_variable_error() {
+            let config: Configuration = json5::from_str(
+                "{ reproducible: true, rules: [{ rule: 'inject_global_value', identifier: 'HOST', env: 'DARKLUA_TEST_HOST', default_value: 'none' }] }",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.verify_reproducible().unwrap_err().to_string(),
+                "rule `inject_global_value` reads the environment variable `DARKLUA_TEST_HOST`, which is not allowed in reproducible mode"
+            );
+        }
+
+        #[test]
+        fn verify_rule_with_value() {
+            let config: Configuration = json5::from_str(
+                "{ reproducible: true, rules: [{ rule: 'inject_global_value', identifier: 'DEBUG', value: false }] }",
+            )
+            .unwrap();
+
+            config.verify_reproducible().unwrap();
+        }
+    }
+
+    mod pure_functions {
+        use super::*;
+
+        #[test]
+        fn serialize_and_deserialize_pure_functions() {
+            let config: Configuration =
+                json5::from_str("{ pure_functions: ['Color3.fromRGB', 'Maths.*'] }").unwrap();
+
+            let config: Configuration =
+                json5::from_str(&json5::to_string(&config).unwrap()).unwrap();
+
+            pretty_assertions::assert_eq!(
+                config
+                    .pure_functions()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+                vec!["Color3.fromRGB", "Maths.*"]
+            );
+        }
+
+        #[test]
+        fn deserialize_invalid_pure_function() {
+            let result: Result<Configuration, _> =
+                json5::from_str("{ pure_functions: ['Maths.*.clamp'] }");
+
+            assert!(result.is_err());
+        }
+    }
+
+    mod pre_bundle_rules {
+        use super::*;
+
+        #[test]
+        fn deserialize_without_pre_bundle_rules() {
+            let config: Configuration = json5::from_str("{}").unwrap();
+
+            assert_eq!(config.pre_bundle_rules().count(), 0);
+        }
+
+        #[test]
+        fn serialize_and_deserialize_pre_bundle_rules() {
+            let config: Configuration =
+                json5::from_str("{ pre_bundle_rules: ['remove_unused_if_branch'] }").unwrap();
+
+            let config: Configuration =
+                json5::from_str(&json5::to_string(&config).unwrap()).unwrap();
+
+            pretty_assertions::assert_eq!(
+                config
+                    .pre_bundle_rules()
+                    .map(|rule| rule.get_name())
+                    .collect::<Vec<_>>(),
+                vec!["remove_unused_if_branch"]
+            );
+        }
+    }
+
+    mod profiles {
+        use super::*;
+
+        #[test]
+        fn deserialize_without_profiles() {
+            let config: Configuration = json5::from_str("{}").unwrap();
+
+            pretty_assertions::assert_eq!(config.profile_groups().len(), 1);
+        }
+
+        #[test]
+        fn deserialize_profiles() {
+            let config: Configuration = json5::from_str(
+                "{ profiles: { dev: {}, min: { generator: 'dense', suffix: 'minified' } } }",
+            )
+            .unwrap();
+
+            let groups = config.profile_groups();
+            pretty_assertions::assert_eq!(groups.len(), 1);
+
+            let outputs = groups[0].outputs();
+            pretty_assertions::assert_eq!(
+                outputs
+                    .iter()
+                    .map(|output| output.output_path(Path::new("out.lua")))
+                    .collect::<Vec<_>>(),
+                vec![
+                    PathBuf::from("out.dev.lua"),
+                    PathBuf::from("out.minified.lua")
+                ]
+            );
+            pretty_assertions::assert_eq!(
+                outputs[0].generator(),
+                &GeneratorParameters::RetainLines
+            );
+            pretty_assertions::assert_eq!(
+                outputs[1].generator(),
+                &GeneratorParameters::default_dense()
+            );
+        }
+
+        #[test]
+        fn deserialize_unknown_profile_field() {
+            let result: Result<Configuration, _> =
+                json5::from_str("{ profiles: { dev: { bundle: {} } } }");
+
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn profiles_with_different_rules_are_in_different_groups() {
+            let config: Configuration = json5::from_str(
+                "{ rules: ['remove_comments'], profiles: { a: {}, b: { rules: ['remove_comments'] }, c: { rules: [] } } }",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config
+                    .profile_groups()
+                    .iter()
+                    .map(|group| group.outputs().len())
+                    .collect::<Vec<_>>(),
+                vec![2, 1]
+            );
+        }
+
+        #[test]
+        fn profiles_inherit_sourcemap_with_suffix() {
+            let config: Configuration = json5::from_str(
+                "{ bundle: { require_mode: 'path', sourcemap: { enabled: true, output_path: 'out.lua.map' } }, profiles: { dev: {} } }",
+            )
+            .unwrap();
+
+            let groups = config.profile_groups();
+            let sourcemap = groups[0].outputs()[0].enabled_sourcemap().unwrap();
+
+            pretty_assertions::assert_eq!(
+                sourcemap.output_path,
+                Some(PathBuf::from("out.dev.lua.map"))
+            );
+            assert!(config.has_retain_lines_sourcemap());
+        }
+
+        #[test]
+        fn insert_suffix_before_extension() {
+            pretty_assertions::assert_eq!(
+                insert_suffix(Path::new("dist/out.lua"), "min"),
+                PathBuf::from("dist/out.min.lua")
+            );
+        }
+
+        #[test]
+        fn insert_suffix_without_extension() {
+            pretty_assertions::assert_eq!(
+                insert_suffix(Path::new("dist/out"), "min"),
+                PathBuf::from("dist/out.min")
+            );
+        }
+
+        #[test]
+        fn insert_empty_suffix() {
+            pretty_assertions::assert_eq!(
+                insert_suffix(Path::new("out.lua"), ""),
+                PathBuf::from("out.lua")
+            );
+        }
+
+        #[test]
+        fn insert_suffix_in_sourcemap_path() {
+            pretty_assertions::assert_eq!(
+                insert_suffix(Path::new("out.lua.map"), "dev"),
+                PathBuf::from("out.dev.lua.map")
+            );
+            pretty_assertions::assert_eq!(
+                insert_suffix(Path::new("out.map"), "dev"),
+                PathBuf::from("out.dev.map")
+            );
+        }
+    }
+
+    mod bundle_configuration {
+        use crate::rules::require::{AutoRequireMode, PathRequireMode, RobloxRequireMode};
+
+        use super::*;
+
+        #[test]
+        fn deserialize_path_require_mode_as_string() {
+            let config: Configuration =
+                json5::from_str("{ bundle: { require_mode: 'path' } }").unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+            );
+        }
+
+        #[test]
+        fn deserialize_path_require_mode_as_object() {
+            let config: Configuration =
+                json5::from_str("{bundle: { require_mode: { name: 'path' } } }").unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+            );
+        }
+
+        #[test]
+        fn deserialize_path_require_mode_with_custom_module_folder_name() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: { name: 'path', module_folder_name: '__INIT__' } } }",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::new("__INIT__"))
+            );
+        }
+
+        #[test]
+        fn deserialize_path_require_mode_with_custom_module_identifier() {
+            let config: Configuration =
+                json5::from_str("{bundle: { require_mode: 'path', modules_identifier: '__M' } }")
+                    .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default()).with_modules_identifier("__M")
+            );
+        }
+
+        #[test]
+        fn deserialize_path_require_mode_with_custom_module_identifier_and_module_folder_name() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: { name: 'path', module_folder_name: '__INIT__' }, modules_identifier: '__M' } }",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::new("__INIT__"))
+                    .with_modules_identifier("__M")
+            );
+        }
+
+        #[test]
+        fn deserialize_strict_wait_for_child() {
+            let config: Configuration =
+                json5::from_str("{bundle: { require_mode: 'path', strict_wait_for_child: true } }")
+                    .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_strict_wait_for_child(true)
+            );
+        }
+
+        #[test]
+        fn deserialize_verify_find_first_child() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', verify_find_first_child: true } }",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_verify_find_first_child(true)
+            );
         }
-    }
 
-    /// Sets the modules identifier for this bundle configuration.
-    pub fn with_modules_identifier(mut self, modules_identifier: impl Into<String>) -> Self {
-        self.modules_identifier = Some(modules_identifier.into());
-        self
-    }
+        #[test]
+        fn deserialize_hoist_excluded_requires() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', hoist_excluded_requires: true } }",
+            )
+            .unwrap();
 
-    /// Adds a module to exclude from bundling.
-    pub fn with_exclude(mut self, exclude: impl Into<String>) -> Self {
-        self.excludes.insert(exclude.into());
-        self
-    }
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_hoist_excluded_requires(true)
+            );
+        }
 
-    pub(crate) fn require_mode(&self) -> &BundleRequireMode {
-        &self.require_mode
-    }
+        #[test]
+        fn deserialize_tree_shake_lazy_requires() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', tree_shake_lazy_requires: true } }",
+            )
+            .unwrap();
 
-    pub(crate) fn modules_identifier(&self) -> &str {
-        self.modules_identifier
-            .as_ref()
-            .map(AsRef::as_ref)
-            .unwrap_or("__DARKLUA_BUNDLE_MODULES")
-    }
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_tree_shake_lazy_requires(true)
+            );
+        }
 
-    pub(crate) fn excludes(&self) -> impl Iterator<Item = &str> {
-        self.excludes.iter().map(AsRef::as_ref)
-    }
+        #[test]
+        fn deserialize_report_global_access() {
+            let config: Configuration =
+                json5::from_str("{bundle: { require_mode: 'path', report_global_access: true } }")
+                    .unwrap();
 
-    pub(crate) fn sourcemap(&self) -> Option<&SourcemapOptions> { self.sourcemap.as_ref() }
-}
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_report_global_access(true)
+            );
+        }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+        #[test]
+        fn deserialize_exclude_rewrites() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'roblox', exclude_rewrites: [{ pattern: '**/Packages/**', target: 'game.ReplicatedStorage.{name}' }] } }",
+            )
+            .unwrap();
 
-    mod generator_parameters {
-        use super::*;
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(RobloxRequireMode::default()).with_exclude_rewrite(
+                    ExcludeRewrite::new("**/Packages/**", "game.ReplicatedStorage.{name}")
+                )
+            );
+        }
 
         #[test]
-        fn deserialize_retain_lines_params() {
-            let config: Configuration =
-                json5::from_str("{ generator: { name: 'retain_lines' } }").unwrap();
+        fn deserialize_allow_non_module_requires() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'roblox', allow_non_module_requires: true } }",
+            )
+            .unwrap();
 
-            pretty_assertions::assert_eq!(config.generator, GeneratorParameters::RetainLines);
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(RobloxRequireMode::default())
+                    .with_allow_non_module_requires(true)
+            );
         }
 
         #[test]
-        fn deserialize_retain_lines_params_deprecated() {
+        fn deserialize_auto_require_mode() {
             let config: Configuration =
-                json5::from_str("{ generator: { name: 'retain-lines' } }").unwrap();
+                json5::from_str("{bundle: { require_mode: 'auto' } }").unwrap();
 
-            pretty_assertions::assert_eq!(config.generator, GeneratorParameters::RetainLines);
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(AutoRequireMode::<RobloxRequireMode>::default())
+            );
         }
 
         #[test]
-        fn deserialize_dense_params() {
-            let config: Configuration = json5::from_str("{ generator: { name: 'dense' }}").unwrap();
+        fn deserialize_auto_require_mode_with_options() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: { name: 'auto', path: { module_folder_name: '__init__' }, roblox: { rojo_sourcemap: 'sourcemap.json' } } } }",
+            )
+            .unwrap();
 
             pretty_assertions::assert_eq!(
-                config.generator,
-                GeneratorParameters::Dense {
-                    column_span: DEFAULT_COLUMN_SPAN
-                }
+                config.bundle.unwrap(),
+                BundleConfiguration::new(
+                    AutoRequireMode::default()
+                        .with_path(PathRequireMode::new("__init__"))
+                        .with_roblox(
+                            RobloxRequireMode::new().with_rojo_sourcemap("sourcemap.json")
+                        )
+                )
             );
         }
 
         #[test]
-        fn deserialize_dense_params_with_column_span() {
-            let config: Configuration =
-                json5::from_str("{ generator: { name: 'dense', column_span: 110 } }").unwrap();
+        fn deserialize_roblox_require_mode_with_filesystem_fallback() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: { name: 'roblox', filesystem_fallback: true } } }",
+            )
+            .unwrap();
 
             pretty_assertions::assert_eq!(
-                config.generator,
-                GeneratorParameters::Dense { column_span: 110 }
+                config.bundle.unwrap(),
+                BundleConfiguration::new(RobloxRequireMode::new().with_filesystem_fallback(true))
             );
         }
 
         #[test]
-        fn deserialize_readable_params() {
-            let config: Configuration =
-                json5::from_str("{ generator: { name: 'readable' } }").unwrap();
+        fn deserialize_pcall_requires() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', pcall_requires: 'bundle' } }",
+            )
+            .unwrap();
 
             pretty_assertions::assert_eq!(
-                config.generator,
-                GeneratorParameters::Readable {
-                    column_span: DEFAULT_COLUMN_SPAN
-                }
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_pcall_requires(PcallRequires::Bundle)
             );
         }
 
         #[test]
-        fn deserialize_readable_params_with_column_span() {
+        fn deserialize_module_order() {
             let config: Configuration =
-                json5::from_str("{ generator: { name: 'readable', column_span: 110 }}").unwrap();
+                json5::from_str("{bundle: { require_mode: 'path', module_order: 'path' } }")
+                    .unwrap();
 
             pretty_assertions::assert_eq!(
-                config.generator,
-                GeneratorParameters::Readable { column_span: 110 }
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_module_order(ModuleOrder::Path)
             );
         }
 
         #[test]
-        fn deserialize_retain_lines_params_as_string() {
-            let config: Configuration = json5::from_str("{generator: 'retain_lines'}").unwrap();
+        fn deserialize_on_identifier_conflict() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', on_identifier_conflict: 'rename' } }",
+            )
+            .unwrap();
 
-            pretty_assertions::assert_eq!(config.generator, GeneratorParameters::RetainLines);
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_on_identifier_conflict(IdentifierConflict::Rename)
+            );
         }
 
         #[test]
-        fn deserialize_dense_params_as_string() {
-            let config: Configuration = json5::from_str("{generator: 'dense'}").unwrap();
+        fn deserialize_module_naming() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', module_naming: 'content_hash' } }",
+            )
+            .unwrap();
 
             pretty_assertions::assert_eq!(
-                config.generator,
-                GeneratorParameters::Dense {
-                    column_span: DEFAULT_COLUMN_SPAN
-                }
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_module_naming(ModuleNaming::ContentHash)
             );
         }
 
         #[test]
-        fn deserialize_readable_params_as_string() {
-            let config: Configuration = json5::from_str("{generator: 'readable'}").unwrap();
+        fn deserialize_multi_return_modules() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', multi_return_modules: 'wrap' } }",
+            )
+            .unwrap();
 
             pretty_assertions::assert_eq!(
-                config.generator,
-                GeneratorParameters::Readable {
-                    column_span: DEFAULT_COLUMN_SPAN
-                }
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_multi_return_modules(MultiReturnModules::Wrap)
             );
         }
 
         #[test]
-        fn deserialize_unknown_generator_name() {
-            let result: Result<Configuration, _> = json5::from_str("{generator: 'oops'}");
+        fn deserialize_module_varargs() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', module_varargs: 'nil' } }",
+            )
+            .unwrap();
 
             pretty_assertions::assert_eq!(
-                result.expect_err("deserialization should fail").to_string(),
-                "invalid generator name `oops`"
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_module_varargs(ModuleVarargs::Nil)
             );
         }
-    }
 
-    mod bundle_configuration {
-        use crate::rules::require::PathRequireMode;
+        #[test]
+        fn deserialize_null_sentinel() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', null_sentinel: 'NULL_SENTINEL' } }",
+            )
+            .unwrap();
 
-        use super::*;
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_null_sentinel("NULL_SENTINEL")
+            );
+        }
 
         #[test]
-        fn deserialize_path_require_mode_as_string() {
-            let config: Configuration =
-                json5::from_str("{ bundle: { require_mode: 'path' } }").unwrap();
+        fn deserialize_raw_text_extensions() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', raw_text_extensions: ['md', 'glsl'], max_raw_text_size: 2048 } }",
+            )
+            .unwrap();
 
             pretty_assertions::assert_eq!(
                 config.bundle.unwrap(),
                 BundleConfiguration::new(PathRequireMode::default())
+                    .with_raw_text_extension("md")
+                    .with_raw_text_extension("glsl")
+                    .with_max_raw_text_size(2048)
             );
         }
 
         #[test]
-        fn deserialize_path_require_mode_as_object() {
-            let config: Configuration =
-                json5::from_str("{bundle: { require_mode: { name: 'path' } } }").unwrap();
+        fn deserialize_binary_extensions() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', binary_extensions: ['png'], binary_wrapper: 'Base64.decode', max_binary_size: 4096 } }",
+            )
+            .unwrap();
 
             pretty_assertions::assert_eq!(
                 config.bundle.unwrap(),
                 BundleConfiguration::new(PathRequireMode::default())
+                    .with_binary_extension("png")
+                    .with_binary_wrapper("Base64.decode")
+                    .with_max_binary_size(4096)
             );
         }
 
         #[test]
-        fn deserialize_path_require_mode_with_custom_module_folder_name() {
+        fn deserialize_intern_data_strings() {
             let config: Configuration = json5::from_str(
-                "{bundle: { require_mode: { name: 'path', module_folder_name: '__INIT__' } } }",
+                "{bundle: { require_mode: 'path', intern_data_strings: { min_length: 8, min_count: 4 } } }",
             )
             .unwrap();
 
             pretty_assertions::assert_eq!(
                 config.bundle.unwrap(),
-                BundleConfiguration::new(PathRequireMode::new("__INIT__"))
+                BundleConfiguration::new(PathRequireMode::default()).with_intern_data_strings(
+                    InternDataStrings::new()
+                        .with_min_length(8)
+                        .with_min_count(4)
+                )
             );
         }
 
         #[test]
-        fn deserialize_path_require_mode_with_custom_module_identifier() {
+        fn deserialize_metadata() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: 'path', metadata_variable: 'BUILD', metadata: { time: false, name: 'app {version}' } } }",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_metadata_variable("BUILD")
+                    .without_metadata_field("time")
+                    .with_metadata_field("name", "app {version}".parse().unwrap())
+            );
+        }
+
+        #[test]
+        fn deserialize_metadata_with_invalid_variable_error() {
+            let result = json5::from_str::<Configuration>(
+                "{bundle: { require_mode: 'path', metadata_variable: 'build info' } }",
+            );
+
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn deserialize_metadata_with_unknown_placeholder_error() {
+            let result = json5::from_str::<Configuration>(
+                "{bundle: { require_mode: 'path', metadata_variable: 'BUILD', metadata: { name: '{date}' } } }",
+            );
+
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn deserialize_max_depth() {
             let config: Configuration =
-                json5::from_str("{bundle: { require_mode: 'path', modules_identifier: '__M' } }")
-                    .unwrap();
+                json5::from_str("{bundle: { require_mode: 'path', max_depth: 50 } }").unwrap();
 
             pretty_assertions::assert_eq!(
                 config.bundle.unwrap(),
-                BundleConfiguration::new(PathRequireMode::default()).with_modules_identifier("__M")
+                BundleConfiguration::new(PathRequireMode::default()).with_max_depth(50)
             );
         }
 
         #[test]
-        fn deserialize_path_require_mode_with_custom_module_identifier_and_module_folder_name() {
+        fn deserialize_output_sizes() {
             let config: Configuration = json5::from_str(
-                "{bundle: { require_mode: { name: 'path', module_folder_name: '__INIT__' }, modules_identifier: '__M' } }",
+                "{bundle: { require_mode: 'path', max_output_size: '3MB', warn_output_size: 2048 } }",
             )
             .unwrap();
 
             pretty_assertions::assert_eq!(
                 config.bundle.unwrap(),
-                BundleConfiguration::new(PathRequireMode::new("__INIT__"))
-                    .with_modules_identifier("__M")
+                BundleConfiguration::new(PathRequireMode::default())
+                    .with_max_output_size(3 * 1024 * 1024)
+                    .with_warn_output_size(2048)
+            );
+        }
+
+        #[test]
+        fn deserialize_invalid_output_size() {
+            let error = json5::from_str::<Configuration>(
+                "{bundle: { require_mode: 'path', max_output_size: '3GB' } }",
+            )
+            .unwrap_err();
+
+            assert!(error.to_string().contains("invalid size `3GB`"));
+        }
+
+        #[test]
+        fn parse_output_size_with_suffixes() {
+            pretty_assertions::assert_eq!(parse_output_size("512"), Ok(512));
+            pretty_assertions::assert_eq!(parse_output_size("512B"), Ok(512));
+            pretty_assertions::assert_eq!(parse_output_size("2kb"), Ok(2048));
+            pretty_assertions::assert_eq!(parse_output_size("1.5 MB"), Ok(1536 * 1024));
+            assert!(parse_output_size("-1KB").is_err());
+            assert!(parse_output_size("KB").is_err());
+        }
+
+        #[test]
+        fn deserialize_graph_output() {
+            let config: Configuration =
+                json5::from_str("{bundle: { require_mode: 'path', graph_output: 'deps.dot' } }")
+                    .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::new(PathRequireMode::default()).with_graph_output("deps.dot")
             );
         }
 
@@ -577,6 +2611,36 @@ mod test {
             );
         }
 
+        #[test]
+        fn builder_matches_deserialized_configuration() {
+            let config: Configuration = json5::from_str(
+                "{bundle: { require_mode: { name: 'roblox', rojo_sourcemap: 'sourcemap.json' }, excludes: ['@lune/**'], modules_identifier: '__M', sourcemap: { enabled: true, output_path: 'out.lua.map' } } }",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.bundle.unwrap(),
+                BundleConfiguration::builder()
+                    .require_mode(BundleRequireMode::roblox("sourcemap.json"))
+                    .exclude("@lune/**")
+                    .modules_identifier("__M")
+                    .sourcemap(SourcemapOptions {
+                        enabled: true,
+                        output_path: Some("out.lua.map".into()),
+                        ..Default::default()
+                    })
+                    .build()
+            );
+        }
+
+        #[test]
+        fn builder_defaults_to_path_require_mode() {
+            pretty_assertions::assert_eq!(
+                BundleConfiguration::builder().build(),
+                BundleConfiguration::new(PathRequireMode::default())
+            );
+        }
+
         #[test]
         fn deserialize_unknown_require_mode_name() {
             let result: Result<Configuration, _> =
@@ -4,22 +4,35 @@ use std::{
     collections::HashSet,
     ffi::{OsStr, OsString},
     fmt::{self, Display},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use crate::{process::LuaSerializerError, rules::Rule, ParserError};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    process::LuaSerializerError,
+    rules::{Rule, RuleWarning},
+    ParserError,
+};
 
 use super::{
     resources::ResourceError,
+    target::{LuaTarget, TargetIncompatibility},
+    utils::maybe_plural,
     work_item::{WorkData, WorkItem, WorkStatus},
 };
 
 #[derive(Debug, Clone)]
-enum ErrorKind {
+enum ErrorDetails {
     Parser {
         path: PathBuf,
         error: ParserError,
     },
+    InvalidOutput {
+        path: PathBuf,
+        rules: Vec<String>,
+        error: ParserError,
+    },
     ResourceNotFound {
         path: PathBuf,
     },
@@ -29,6 +42,9 @@ enum ErrorKind {
     MultipleConfigurationFound {
         paths: Vec<PathBuf>,
     },
+    OutputCollision {
+        collisions: Vec<(PathBuf, Vec<PathBuf>)>,
+    },
     IO {
         path: PathBuf,
         error: String,
@@ -45,6 +61,25 @@ enum ErrorKind {
     CyclicWork {
         work: Vec<(WorkData, Vec<PathBuf>)>,
     },
+    CyclicRequire {
+        paths: Vec<PathBuf>,
+    },
+    TargetIncompatibility {
+        path: PathBuf,
+        target: LuaTarget,
+        incompatibilities: Vec<TargetIncompatibility>,
+    },
+    WarningsAsErrors {
+        path: PathBuf,
+        warnings: Vec<RuleWarning>,
+    },
+    OutputSizeExceeded {
+        path: PathBuf,
+        size: usize,
+        max_size: usize,
+        // the path, size in bytes and number of lines of the largest bundled files
+        largest_files: Vec<(PathBuf, usize, usize)>,
+    },
     Deserialization {
         message: String,
         data_type: &'static str,
@@ -68,6 +103,65 @@ enum ErrorKind {
     },
 }
 
+/// The category of a [`DarkluaError`].
+///
+/// New kinds may be added in future versions, so matching on this enum
+/// should always include a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The code of a file could not be parsed.
+    Parser,
+    /// The generated code of a file could not be parsed back.
+    InvalidOutput,
+    /// A file or resource could not be found.
+    ResourceNotFound,
+    /// Modules required each other in a cycle.
+    CyclicRequire,
+    /// A rule failed while processing a file.
+    Rule,
+    /// The configuration is invalid or ambiguous.
+    Configuration,
+    /// An input/output operation failed.
+    Io,
+    /// A file is not compatible with the configured Lua target.
+    Target,
+    /// Warnings were configured to be reported as errors.
+    Warning,
+    /// The generated code of a bundle is larger than the configured maximum size.
+    OutputSize,
+    /// Data could not be read or serialized.
+    Data,
+    /// A required resource has an invalid path or extension.
+    InvalidResource,
+    /// Any other error.
+    Custom,
+}
+
+/// A serializable description of a [`DarkluaError`].
+///
+/// Obtained with [`DarkluaError::to_report`]. The `message` does not include the
+/// `context`, which is reported separately.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct ErrorReport {
+    /// The category of the error.
+    pub kind: ErrorKind,
+    /// The error message.
+    pub message: String,
+    /// The path of the file associated with the error.
+    pub path: Option<String>,
+    /// The line (starting at 1) where the error occurred.
+    pub line: Option<usize>,
+    /// The name of the rule that failed.
+    pub rule: Option<String>,
+    /// Additional information about where the error happened.
+    pub context: Option<String>,
+    /// The reports of the errors that caused this error.
+    pub causes: Vec<ErrorReport>,
+}
+
 /// A type alias for `Result<T, DarkluaError>`.
 pub type DarkluaResult<T> = Result<T, DarkluaError>;
 
@@ -78,15 +172,17 @@ pub type DarkluaResult<T> = Result<T, DarkluaError>;
 /// configuration, and rule application.
 #[derive(Debug, Clone)]
 pub struct DarkluaError {
-    kind: Box<ErrorKind>,
+    details: Box<ErrorDetails>,
     context: Option<Cow<'static, str>>,
+    causes: Vec<DarkluaError>,
 }
 
 impl DarkluaError {
-    fn new(kind: ErrorKind) -> Self {
+    fn new(details: ErrorDetails) -> Self {
         Self {
-            kind: kind.into(),
+            details: details.into(),
             context: None,
+            causes: Vec::new(),
         }
     }
 
@@ -95,8 +191,113 @@ impl DarkluaError {
         self
     }
 
+    pub(crate) fn with_causes(mut self, causes: impl IntoIterator<Item = DarkluaError>) -> Self {
+        self.causes.extend(causes);
+        self
+    }
+
+    /// Returns the category of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match &*self.details {
+            ErrorDetails::Parser { .. } => ErrorKind::Parser,
+            ErrorDetails::InvalidOutput { .. } => ErrorKind::InvalidOutput,
+            ErrorDetails::ResourceNotFound { .. } => ErrorKind::ResourceNotFound,
+            ErrorDetails::InvalidConfiguration { .. }
+            | ErrorDetails::MultipleConfigurationFound { .. }
+            | ErrorDetails::OutputCollision { .. } => ErrorKind::Configuration,
+            ErrorDetails::IO { .. } => ErrorKind::Io,
+            ErrorDetails::RuleError { .. } => ErrorKind::Rule,
+            ErrorDetails::CyclicWork { .. } | ErrorDetails::CyclicRequire { .. } => {
+                ErrorKind::CyclicRequire
+            }
+            ErrorDetails::TargetIncompatibility { .. } => ErrorKind::Target,
+            ErrorDetails::WarningsAsErrors { .. } => ErrorKind::Warning,
+            ErrorDetails::OutputSizeExceeded { .. } => ErrorKind::OutputSize,
+            ErrorDetails::Deserialization { .. } | ErrorDetails::Serialization { .. } => {
+                ErrorKind::Data
+            }
+            ErrorDetails::InvalidResourcePath { .. }
+            | ErrorDetails::InvalidResourceExtension { .. } => ErrorKind::InvalidResource,
+            ErrorDetails::UncachedWork { .. }
+            | ErrorDetails::OsStringConversion { .. }
+            | ErrorDetails::Custom { .. } => ErrorKind::Custom,
+        }
+    }
+
+    /// Returns the path of the file associated with this error, if any.
+    ///
+    /// For cyclic requires, this is the first module of the cycle.
+    pub fn path(&self) -> Option<&Path> {
+        match &*self.details {
+            ErrorDetails::Parser { path, .. }
+            | ErrorDetails::InvalidOutput { path, .. }
+            | ErrorDetails::ResourceNotFound { path }
+            | ErrorDetails::InvalidConfiguration { path }
+            | ErrorDetails::IO { path, .. }
+            | ErrorDetails::UncachedWork { path }
+            | ErrorDetails::RuleError { path, .. }
+            | ErrorDetails::TargetIncompatibility { path, .. }
+            | ErrorDetails::WarningsAsErrors { path, .. }
+            | ErrorDetails::OutputSizeExceeded { path, .. } => Some(path),
+            ErrorDetails::InvalidResourceExtension { location } => Some(location),
+            ErrorDetails::CyclicRequire { paths } => paths.first().map(PathBuf::as_path),
+            ErrorDetails::OutputCollision { collisions } => {
+                collisions.first().map(|(output, _)| output.as_path())
+            }
+            ErrorDetails::MultipleConfigurationFound { .. }
+            | ErrorDetails::CyclicWork { .. }
+            | ErrorDetails::Deserialization { .. }
+            | ErrorDetails::Serialization { .. }
+            | ErrorDetails::InvalidResourcePath { .. }
+            | ErrorDetails::OsStringConversion { .. }
+            | ErrorDetails::Custom { .. } => None,
+        }
+    }
+
+    /// Returns the line (starting at 1) where this error occurred, if known.
+    pub fn line(&self) -> Option<usize> {
+        match &*self.details {
+            ErrorDetails::Parser { error, .. } | ErrorDetails::InvalidOutput { error, .. } => {
+                error.line()
+            }
+            ErrorDetails::WarningsAsErrors { warnings, .. } => {
+                warnings.iter().find_map(RuleWarning::line)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the rule that failed, for rule errors.
+    pub fn rule_name(&self) -> Option<&str> {
+        match &*self.details {
+            ErrorDetails::RuleError { rule_name, .. } => Some(rule_name),
+            _ => None,
+        }
+    }
+
+    /// Converts this error into a serializable report, meant to be consumed by tools.
+    pub fn to_report(&self) -> ErrorReport {
+        ErrorReport {
+            kind: self.kind(),
+            message: self.details.to_string(),
+            path: self.path().map(|path| path.display().to_string()),
+            line: self.line(),
+            rule: self.rule_name().map(str::to_owned),
+            context: self.context.as_ref().map(ToString::to_string),
+            causes: self.causes.iter().map(DarkluaError::to_report).collect(),
+        }
+    }
+
+    /// Returns the underlying errors that caused this error.
+    ///
+    /// For example, a bundling rule error lists the errors of each require
+    /// call that could not be bundled.
+    pub fn causes(&self) -> &[DarkluaError] {
+        &self.causes
+    }
+
     pub(crate) fn parser_error(path: impl Into<PathBuf>, error: ParserError) -> Self {
-        Self::new(ErrorKind::Parser {
+        Self::new(ErrorDetails::Parser {
             path: path.into(),
             error,
         })
@@ -105,28 +306,32 @@ impl DarkluaError {
     pub(crate) fn multiple_configuration_found(
         configuration_files: impl Iterator<Item = PathBuf>,
     ) -> Self {
-        Self::new(ErrorKind::MultipleConfigurationFound {
+        Self::new(ErrorDetails::MultipleConfigurationFound {
             paths: configuration_files.collect(),
         })
     }
 
+    pub(crate) fn output_collision(collisions: Vec<(PathBuf, Vec<PathBuf>)>) -> Self {
+        Self::new(ErrorDetails::OutputCollision { collisions })
+    }
+
     pub(crate) fn io_error(path: impl Into<PathBuf>, error: impl Into<String>) -> Self {
-        Self::new(ErrorKind::IO {
+        Self::new(ErrorDetails::IO {
             path: path.into(),
             error: error.into(),
         })
     }
 
     pub(crate) fn resource_not_found(path: impl Into<PathBuf>) -> Self {
-        Self::new(ErrorKind::ResourceNotFound { path: path.into() })
+        Self::new(ErrorDetails::ResourceNotFound { path: path.into() })
     }
 
     pub(crate) fn invalid_configuration_file(path: impl Into<PathBuf>) -> Self {
-        Self::new(ErrorKind::InvalidConfiguration { path: path.into() })
+        Self::new(ErrorDetails::InvalidConfiguration { path: path.into() })
     }
 
     pub(crate) fn uncached_work(path: impl Into<PathBuf>) -> Self {
-        Self::new(ErrorKind::UncachedWork { path: path.into() })
+        Self::new(ErrorDetails::UncachedWork { path: path.into() })
     }
 
     pub(crate) fn rule_error(
@@ -135,7 +340,7 @@ impl DarkluaError {
         rule_index: usize,
         rule_error: impl Into<String>,
     ) -> Self {
-        Self::new(ErrorKind::RuleError {
+        Self::new(ErrorDetails::RuleError {
             path: path.into(),
             rule_name: rule.get_name().to_owned(),
             rule_number: Some(rule_index),
@@ -148,7 +353,7 @@ impl DarkluaError {
         rule: &dyn Rule,
         rule_error: impl Into<String>,
     ) -> Self {
-        Self::new(ErrorKind::RuleError {
+        Self::new(ErrorDetails::RuleError {
             path: path.into(),
             rule_name: rule.get_name().to_owned(),
             rule_number: None,
@@ -193,36 +398,85 @@ impl DarkluaError {
 
         required_work.sort_by_key(|(_, content)| content.len());
 
-        Self::new(ErrorKind::CyclicWork {
+        Self::new(ErrorDetails::CyclicWork {
             work: required_work,
         })
     }
 
+    pub(crate) fn cyclic_require(paths: Vec<PathBuf>) -> Self {
+        Self::new(ErrorDetails::CyclicRequire { paths })
+    }
+
+    pub(crate) fn target_incompatibility(
+        path: impl Into<PathBuf>,
+        target: LuaTarget,
+        incompatibilities: Vec<TargetIncompatibility>,
+    ) -> Self {
+        Self::new(ErrorDetails::TargetIncompatibility {
+            path: path.into(),
+            target,
+            incompatibilities,
+        })
+    }
+
+    pub(crate) fn warnings_as_errors(path: impl Into<PathBuf>, warnings: Vec<RuleWarning>) -> Self {
+        Self::new(ErrorDetails::WarningsAsErrors {
+            path: path.into(),
+            warnings,
+        })
+    }
+
+    pub(crate) fn output_size_exceeded(
+        path: impl Into<PathBuf>,
+        size: usize,
+        max_size: usize,
+        largest_files: Vec<(PathBuf, usize, usize)>,
+    ) -> Self {
+        Self::new(ErrorDetails::OutputSizeExceeded {
+            path: path.into(),
+            size,
+            max_size,
+            largest_files,
+        })
+    }
+
     pub(crate) fn invalid_resource_path(
         path: impl Into<String>,
         message: impl Into<String>,
     ) -> Self {
-        Self::new(ErrorKind::InvalidResourcePath {
+        Self::new(ErrorDetails::InvalidResourcePath {
             location: path.into(),
             message: message.into(),
         })
     }
 
     pub(crate) fn invalid_resource_extension(path: impl Into<PathBuf>) -> Self {
-        Self::new(ErrorKind::InvalidResourceExtension {
+        Self::new(ErrorDetails::InvalidResourceExtension {
             location: path.into(),
         })
     }
 
     pub(crate) fn os_string_conversion(os_string: impl Into<OsString>) -> Self {
-        Self::new(ErrorKind::OsStringConversion {
+        Self::new(ErrorDetails::OsStringConversion {
             os_string: os_string.into(),
         })
     }
 
     /// Creates a custom error with the given message.
+    pub(crate) fn invalid_output(
+        path: impl Into<PathBuf>,
+        rules: impl IntoIterator<Item = impl Into<String>>,
+        error: ParserError,
+    ) -> Self {
+        Self::new(ErrorDetails::InvalidOutput {
+            path: path.into(),
+            rules: rules.into_iter().map(Into::into).collect(),
+            error,
+        })
+    }
+
     pub fn custom(message: impl Into<Cow<'static, str>>) -> Self {
-        Self::new(ErrorKind::Custom {
+        Self::new(ErrorDetails::Custom {
             message: message.into(),
         })
     }
@@ -239,7 +493,7 @@ impl From<ResourceError> for DarkluaError {
 
 impl From<json5::Error> for DarkluaError {
     fn from(error: json5::Error) -> Self {
-        Self::new(ErrorKind::Deserialization {
+        Self::new(ErrorDetails::Deserialization {
             message: error.to_string(),
             data_type: "json",
         })
@@ -248,7 +502,7 @@ impl From<json5::Error> for DarkluaError {
 
 impl From<serde_json::Error> for DarkluaError {
     fn from(error: serde_json::Error) -> Self {
-        Self::new(ErrorKind::Deserialization {
+        Self::new(ErrorDetails::Deserialization {
             message: error.to_string(),
             data_type: "json",
         })
@@ -257,7 +511,7 @@ impl From<serde_json::Error> for DarkluaError {
 
 impl From<serde_yaml::Error> for DarkluaError {
     fn from(error: serde_yaml::Error) -> Self {
-        Self::new(ErrorKind::Deserialization {
+        Self::new(ErrorDetails::Deserialization {
             message: error.to_string(),
             data_type: "yaml",
         })
@@ -266,7 +520,7 @@ impl From<serde_yaml::Error> for DarkluaError {
 
 impl From<toml::de::Error> for DarkluaError {
     fn from(error: toml::de::Error) -> Self {
-        Self::new(ErrorKind::Deserialization {
+        Self::new(ErrorDetails::Deserialization {
             message: error.to_string(),
             data_type: "toml",
         })
@@ -275,7 +529,7 @@ impl From<toml::de::Error> for DarkluaError {
 
 impl From<toml::ser::Error> for DarkluaError {
     fn from(error: toml::ser::Error) -> Self {
-        Self::new(ErrorKind::Serialization {
+        Self::new(ErrorDetails::Serialization {
             message: error.to_string(),
             data_type: "toml",
         })
@@ -284,26 +538,38 @@ impl From<toml::ser::Error> for DarkluaError {
 
 impl From<LuaSerializerError> for DarkluaError {
     fn from(error: LuaSerializerError) -> Self {
-        Self::new(ErrorKind::Serialization {
+        Self::new(ErrorDetails::Serialization {
             message: error.to_string(),
             data_type: "lua",
         })
     }
 }
 
-impl Display for DarkluaError {
+impl Display for ErrorDetails {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &*self.kind {
-            ErrorKind::Parser { path, error } => {
+        match self {
+            ErrorDetails::Parser { path, error } => {
                 write!(f, "unable to parse `{}`: {}", path.display(), error)?;
             }
-            ErrorKind::ResourceNotFound { path } => {
+            ErrorDetails::InvalidOutput { path, rules, error } => {
+                write!(f, "generated code for `{}` is invalid", path.display())?;
+                if let (Some(line), Some(column)) = (error.line(), error.column()) {
+                    write!(f, " at line {}, column {}", line, column)?;
+                }
+                if rules.is_empty() {
+                    write!(f, " (no rules applied)")?;
+                } else {
+                    write!(f, " (rules: {})", rules.join(", "))?;
+                }
+                write!(f, ": {}", error)?;
+            }
+            ErrorDetails::ResourceNotFound { path } => {
                 write!(f, "unable to find `{}`", path.display())?;
             }
-            ErrorKind::InvalidConfiguration { path } => {
+            ErrorDetails::InvalidConfiguration { path } => {
                 write!(f, "invalid configuration file at `{}`", path.display())?;
             }
-            ErrorKind::MultipleConfigurationFound { paths } => {
+            ErrorDetails::MultipleConfigurationFound { paths } => {
                 write!(
                     f,
                     "multiple default configuration file found: {}",
@@ -314,13 +580,34 @@ impl Display for DarkluaError {
                         .join(", ")
                 )?;
             }
-            ErrorKind::IO { path, error } => {
+            ErrorDetails::OutputCollision { collisions } => {
+                write!(
+                    f,
+                    "multiple files are written to the same output (enable `allow_overwrite` to ignore):\n{}",
+                    collisions
+                        .iter()
+                        .map(|(output, sources)| {
+                            format!(
+                                "    - `{}` from {}",
+                                output.display(),
+                                sources
+                                    .iter()
+                                    .map(|source| format!("`{}`", source.display()))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                )?;
+            }
+            ErrorDetails::IO { path, error } => {
                 write!(f, "IO error with `{}`: {}", path.display(), error)?;
             }
-            ErrorKind::UncachedWork { path } => {
+            ErrorDetails::UncachedWork { path } => {
                 write!(f, "attempt to obtain work at `{}`", path.display())?;
             }
-            ErrorKind::RuleError {
+            ErrorDetails::RuleError {
                 path,
                 rule_name,
                 rule_number,
@@ -347,7 +634,7 @@ impl Display for DarkluaError {
                     )?;
                 }
             }
-            ErrorKind::CyclicWork { work } => {
+            ErrorDetails::CyclicWork { work } => {
                 const MAX_PRINTED_WORK: usize = 12;
                 const MAX_REQUIRED_PATH: usize = 20;
 
@@ -381,20 +668,91 @@ impl Display for DarkluaError {
                     }
                 )?;
             }
-            ErrorKind::Deserialization { message, data_type } => {
+            ErrorDetails::CyclicRequire { paths } => {
+                write!(
+                    f,
+                    "cyclic require detected with `{}`",
+                    paths
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join("` > `")
+                )?;
+            }
+            ErrorDetails::TargetIncompatibility {
+                path,
+                target,
+                incompatibilities,
+            } => {
+                write!(
+                    f,
+                    "`{}` is not compatible with target `{}`:\n{}",
+                    path.display(),
+                    target,
+                    incompatibilities
+                        .iter()
+                        .map(|incompatibility| format!("    - {}", incompatibility))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                )?;
+            }
+            ErrorDetails::WarningsAsErrors { path, warnings } => {
+                write!(
+                    f,
+                    "`{}` emitted warnings configured as errors:\n{}",
+                    path.display(),
+                    warnings
+                        .iter()
+                        .map(|warning| format!("    - [{}] {}", warning.code(), warning))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                )?;
+            }
+            ErrorDetails::OutputSizeExceeded {
+                path,
+                size,
+                max_size,
+                largest_files,
+            } => {
+                write!(
+                    f,
+                    "generated code of `{}` is {} bytes, which exceeds the maximum output size of {} bytes",
+                    path.display(),
+                    size,
+                    max_size
+                )?;
+                if !largest_files.is_empty() {
+                    write!(
+                        f,
+                        "\n  largest bundled files:\n{}",
+                        largest_files
+                            .iter()
+                            .map(|(path, bytes, lines)| format!(
+                                "    - `{}`: {} bytes, {} line{}",
+                                path.display(),
+                                bytes,
+                                lines,
+                                maybe_plural(*lines)
+                            ))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    )?;
+                }
+            }
+            ErrorDetails::Deserialization { message, data_type } => {
                 write!(f, "unable to read {} data: {}", data_type, message)?;
             }
-            ErrorKind::Serialization { message, data_type } => {
+            ErrorDetails::Serialization { message, data_type } => {
                 write!(f, "unable to serialize {} data: {}", data_type, message)?;
             }
-            ErrorKind::InvalidResourcePath { location, message } => {
+            ErrorDetails::InvalidResourcePath { location, message } => {
                 write!(
                     f,
                     "unable to require resource at `{}`: {}",
                     location, message
                 )?;
             }
-            ErrorKind::InvalidResourceExtension { location } => {
+            ErrorDetails::InvalidResourceExtension { location } => {
                 if let Some(extension) = location.extension().map(OsStr::to_string_lossy) {
                     write!(
                         f,
@@ -410,18 +768,26 @@ impl Display for DarkluaError {
                     )?;
                 }
             }
-            ErrorKind::OsStringConversion { os_string } => {
+            ErrorDetails::OsStringConversion { os_string } => {
                 write!(
                     f,
                     "unable to convert operating system string (`{}`) into a utf-8 string",
                     os_string.to_string_lossy(),
                 )?;
             }
-            ErrorKind::Custom { message } => {
+            ErrorDetails::Custom { message } => {
                 write!(f, "{}", message)?;
             }
         };
 
+        Ok(())
+    }
+}
+
+impl Display for DarkluaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.details)?;
+
         if let Some(context) = &self.context {
             write!(f, " ({})", context)?;
         }
@@ -429,3 +795,9 @@ impl Display for DarkluaError {
         Ok(())
     }
 }
+
+impl Serialize for DarkluaError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_report().serialize(serializer)
+    }
+}
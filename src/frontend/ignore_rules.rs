@@ -0,0 +1,321 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use super::{Configuration, DarkluaError, DarkluaResult, Resources};
+
+/// A pattern of an ignore file, using the gitignore syntax.
+#[derive(Debug)]
+struct IgnorePattern {
+    base: PathBuf,
+    regex: Regex,
+    negated: bool,
+    directory_only: bool,
+    // patterns without a slash match the name of a file or a directory at any depth
+    match_name: bool,
+}
+
+impl IgnorePattern {
+    fn parse(base: &Path, line: &str) -> Option<Result<Self, regex::Error>> {
+        let line = line.trim_end();
+
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (directory_only, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let match_name = !pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+
+        if pattern.is_empty() {
+            return None;
+        }
+
+        Some(Regex::new(&glob_to_regex(pattern)).map(|regex| Self {
+            base: base.to_path_buf(),
+            regex,
+            negated,
+            directory_only,
+            match_name,
+        }))
+    }
+
+    fn is_match(&self, path: &Path, is_directory: bool) -> bool {
+        if self.directory_only && !is_directory {
+            return false;
+        }
+
+        let relative_path = match path.strip_prefix(&self.base) {
+            Ok(relative_path) if relative_path.as_os_str().is_empty() => return false,
+            Ok(relative_path) => relative_path,
+            Err(_) => return false,
+        };
+
+        if self.match_name {
+            relative_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| self.regex.is_match(name))
+        } else {
+            self.regex
+                .is_match(&relative_path.to_string_lossy().replace('\\', "/"))
+        }
+    }
+}
+
+fn glob_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                regex.push('[');
+                if chars.next_if(|char| *char == '!' || *char == '^').is_some() {
+                    regex.push('^');
+                }
+                for char in chars.by_ref() {
+                    if char == ']' {
+                        break;
+                    }
+                    if char == '\\' || char == '[' {
+                        regex.push('\\');
+                    }
+                    regex.push(char);
+                }
+                regex.push(']');
+            }
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    regex.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            _ => regex.push_str(&regex::escape(&char.to_string())),
+        }
+    }
+
+    regex.push('$');
+    regex
+}
+
+/// The files to leave out when processing a directory, from the `ignore` patterns of the
+/// configuration and from the ignore files found in the processed directory.
+#[derive(Debug, Default)]
+pub(crate) struct IgnoreRules {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreRules {
+    /// Loads the ignore patterns that apply to the files of the given directories. The
+    /// patterns of the configuration are relative to the input directory, and the patterns
+    /// of an ignore file are relative to the directory of that file.
+    pub(crate) fn load(
+        resources: &Resources,
+        configuration: &Configuration,
+        input: &Path,
+        directories: BTreeSet<PathBuf>,
+    ) -> DarkluaResult<Self> {
+        let mut rules = Self::default();
+
+        for pattern in configuration.ignore_patterns() {
+            rules.push_line(input, pattern, || "the configuration".to_owned())?;
+        }
+
+        // parent directories are ordered before their children, so that the patterns of
+        // nested ignore files take precedence
+        for directory in directories {
+            for ignore_file in configuration.ignore_files() {
+                let ignore_file_path = directory.join(ignore_file);
+
+                if !resources.is_file(&ignore_file_path).unwrap_or_default() {
+                    continue;
+                }
+
+                log::trace!("load ignore file `{}`", ignore_file_path.display());
+                let content = resources.get(&ignore_file_path)?;
+
+                for line in content.lines() {
+                    rules.push_line(&directory, line, || {
+                        format!("`{}`", ignore_file_path.display())
+                    })?;
+                }
+            }
+        }
+
+        Ok(rules)
+    }
+
+    fn push_line(
+        &mut self,
+        base: &Path,
+        line: &str,
+        origin: impl Fn() -> String,
+    ) -> DarkluaResult<()> {
+        if let Some(pattern) = IgnorePattern::parse(base, line) {
+            let pattern = pattern.map_err(|err| {
+                DarkluaError::custom(format!(
+                    "invalid ignore pattern `{}` in {}: {}",
+                    line.trim_end(),
+                    origin(),
+                    err
+                ))
+            })?;
+            self.patterns.push(pattern);
+        }
+        Ok(())
+    }
+
+    /// Returns true if the file is ignored, or if one of its parent directories is ignored.
+    pub(crate) fn is_ignored(&self, path: &Path) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+
+        let mut directories: Vec<_> = path.ancestors().skip(1).collect();
+        directories.reverse();
+
+        directories
+            .into_iter()
+            .any(|directory| self.matches(directory, true))
+            || self.matches(path, false)
+    }
+
+    // the last matching pattern decides if the path is ignored
+    fn matches(&self, path: &Path, is_directory: bool) -> bool {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| pattern.is_match(path, is_directory))
+            .is_some_and(|pattern| !pattern.negated)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rules(patterns: &[(&str, &str)]) -> IgnoreRules {
+        let mut rules = IgnoreRules::default();
+        for (base, line) in patterns {
+            rules
+                .push_line(Path::new(base), line, || "test".to_owned())
+                .unwrap();
+        }
+        rules
+    }
+
+    fn is_ignored(patterns: &[(&str, &str)], path: &str) -> bool {
+        rules(patterns).is_ignored(Path::new(path))
+    }
+
+    #[test]
+    fn name_pattern_matches_at_any_depth() {
+        assert!(is_ignored(&[("src", "*.bak.lua")], "src/a/b/c.bak.lua"));
+    }
+
+    #[test]
+    fn name_pattern_does_not_match_other_files() {
+        assert!(!is_ignored(&[("src", "*.bak.lua")], "src/a/c.lua"));
+    }
+
+    #[test]
+    fn anchored_pattern_matches_from_its_base() {
+        assert!(is_ignored(&[("src", "/main.lua")], "src/main.lua"));
+        assert!(!is_ignored(&[("src", "/main.lua")], "src/nested/main.lua"));
+    }
+
+    #[test]
+    fn pattern_with_slash_is_anchored() {
+        assert!(is_ignored(&[("src", "dist/**")], "src/dist/a/b.lua"));
+        assert!(!is_ignored(&[("src", "dist/**")], "src/nested/dist/b.lua"));
+    }
+
+    #[test]
+    fn double_star_prefix_matches_at_any_depth() {
+        assert!(is_ignored(&[("src", "**/dist/*.lua")], "src/a/dist/b.lua"));
+        assert!(is_ignored(&[("src", "**/dist/*.lua")], "src/dist/b.lua"));
+    }
+
+    #[test]
+    fn directory_pattern_ignores_its_files() {
+        assert!(is_ignored(&[("src", "build/")], "src/a/build/init.lua"));
+    }
+
+    #[test]
+    fn directory_pattern_does_not_match_files() {
+        assert!(!is_ignored(&[("src", "build/")], "src/a/build"));
+    }
+
+    #[test]
+    fn negated_pattern_includes_file_again() {
+        assert!(!is_ignored(
+            &[("src", "*.lua"), ("src", "!keep.lua")],
+            "src/keep.lua"
+        ));
+    }
+
+    #[test]
+    fn negated_pattern_cannot_include_file_of_ignored_directory() {
+        assert!(is_ignored(
+            &[("src", "dist/"), ("src", "!dist/keep.lua")],
+            "src/dist/keep.lua"
+        ));
+    }
+
+    #[test]
+    fn pattern_does_not_apply_outside_of_its_base() {
+        assert!(!is_ignored(&[("src/a", "*.lua")], "src/b/init.lua"));
+    }
+
+    #[test]
+    fn comments_and_empty_lines_are_skipped() {
+        assert!(rules(&[("src", "# comment"), ("src", ""), ("src", "   ")])
+            .patterns
+            .is_empty());
+    }
+
+    #[test]
+    fn character_class_pattern() {
+        assert!(is_ignored(&[("src", "file[0-9].lua")], "src/file4.lua"));
+        assert!(!is_ignored(&[("src", "file[!0-9].lua")], "src/file4.lua"));
+    }
+
+    #[test]
+    fn escaped_characters_are_literal() {
+        assert!(is_ignored(&[("src", "\\#file.lua")], "src/#file.lua"));
+        assert!(is_ignored(&[("src", "what\\?.lua")], "src/what?.lua"));
+        assert!(!is_ignored(&[("src", "what\\?.lua")], "src/whats.lua"));
+    }
+
+    #[test]
+    fn invalid_pattern_errors() {
+        let error = IgnoreRules::default()
+            .push_line(Path::new("src"), "[z-a].lua", || {
+                "`src/.gitignore`".to_owned()
+            })
+            .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("invalid ignore pattern `[z-a].lua` in `src/.gitignore`: "));
+    }
+}
@@ -1,22 +1,31 @@
 mod configuration;
 mod error;
+mod ignore_rules;
 mod options;
 mod resources;
+mod target;
 mod utils;
 mod work_cache;
 mod work_item;
 mod worker;
 mod worker_tree;
 
-pub use configuration::{BundleConfiguration, Configuration, GeneratorParameters};
-pub use error::{DarkluaError, DarkluaResult};
+pub use configuration::{
+    BundleConfiguration, BundleConfigurationBuilder, Configuration, GeneratorParameters,
+    ProfileConfiguration, RetainLinesOptions, SourcemapOptions,
+};
+pub use error::{DarkluaError, DarkluaResult, ErrorKind, ErrorReport};
 pub use options::Options;
 pub use resources::Resources;
+pub use target::LuaTarget;
 use serde::Serialize;
+pub use work_item::ArtifactKind;
 use work_item::WorkItem;
 use worker::Worker;
 pub use worker_tree::WorkerTree;
 
+use std::path::Path;
+
 use crate::{
     generator::{DenseLuaGenerator, LuaGenerator},
     nodes::{Block, ReturnStatement},
@@ -73,3 +82,69 @@ pub fn process(resources: &Resources, options: Options) -> DarkluaResult<WorkerT
 
     Ok(worker_tree)
 }
+
+/// Process a code snippet as if it was a file located at the given virtual path.
+///
+/// The configured rules are applied exactly like when processing a single file. When the
+/// configuration bundles requires, modules are resolved against the given resources
+/// (or an empty set of resources if `None` is given). The resources are never modified.
+///
+/// # Example
+///
+/// ```rust
+/// # use darklua_core::{process_code, Configuration};
+/// # use darklua_core::rules::{RemoveSpaces, Rule};
+/// let rule: Box<dyn Rule> = Box::new(RemoveSpaces::default());
+/// let configuration = Configuration::empty().with_rule(rule);
+///
+/// let code = process_code("local a = 1", "src/main.lua", configuration, None).unwrap();
+///
+/// assert_eq!(code, "local a=1");
+/// ```
+///
+/// Modules required by the snippet can be provided with in-memory resources:
+///
+/// ```rust
+/// # use darklua_core::{process_code, BundleConfiguration, Configuration, Resources};
+/// # use darklua_core::rules::PathRequireMode;
+/// let resources = Resources::from_memory();
+/// resources.write("src/value.lua", "return true").unwrap();
+///
+/// let configuration = Configuration::empty()
+///     .with_bundle_configuration(BundleConfiguration::new(PathRequireMode::default()));
+///
+/// let code = process_code(
+///     "local value = require('./value')",
+///     "src/main.lua",
+///     configuration,
+///     Some(&resources),
+/// )
+/// .unwrap();
+///
+/// assert!(code.contains("__DARKLUA_BUNDLE_MODULES"));
+/// ```
+pub fn process_code(
+    code: &str,
+    virtual_path: impl AsRef<Path>,
+    configuration: Configuration,
+    resources: Option<&Resources>,
+) -> Result<String, Vec<DarkluaError>> {
+    let virtual_path = virtual_path.as_ref();
+    let resources = resources
+        .cloned()
+        .unwrap_or_else(Resources::from_memory)
+        .overlay();
+
+    resources
+        .write(virtual_path, code)
+        .map_err(|err| vec![err.into()])?;
+
+    process(
+        &resources,
+        Options::new(virtual_path).with_configuration(configuration),
+    )
+    .map_err(|err| vec![err])?
+    .result()?;
+
+    resources.get(virtual_path).map_err(|err| vec![err.into()])
+}
@@ -1,5 +1,8 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use crate::rules::RuleFactory;
+
 use super::configuration::{Configuration, GeneratorParameters};
 
 /// Options for configuring the darklua process function. This is not
@@ -12,6 +15,9 @@ pub struct Options {
     config_generator_override: Option<GeneratorParameters>,
     output: Option<PathBuf>,
     fail_fast: bool,
+    capture_output: bool,
+    extra_rules: HashMap<&'static str, RuleFactory>,
+    extension_rewrites: HashMap<String, String>,
 }
 
 impl Options {
@@ -23,7 +29,10 @@ impl Options {
             config: None,
             output: None,
             fail_fast: false,
+            capture_output: false,
             config_generator_override: None,
+            extra_rules: HashMap::new(),
+            extension_rewrites: HashMap::new(),
         }
     }
 
@@ -53,6 +62,15 @@ impl Options {
         self
     }
 
+    /// Keeps the generated code in memory instead of writing it to the resources.
+    ///
+    /// The generated code can then be obtained with
+    /// [`WorkerTree::generated`](crate::WorkerTree::generated).
+    pub fn capture_output(mut self, capture: bool) -> Self {
+        self.capture_output = capture;
+        self
+    }
+
     /// Sets a generator override for the configuration.
     ///
     /// This will override any generator settings in the configuration file.
@@ -61,6 +79,35 @@ impl Options {
         self
     }
 
+    /// Adds rules that can be referenced by name from the configuration file
+    /// during this run only.
+    ///
+    /// Unlike [`register_rule`](crate::rules::register_rule), these rules are not
+    /// available outside of the processing started with these options.
+    pub fn with_extra_rules(
+        mut self,
+        rules: impl IntoIterator<Item = (&'static str, RuleFactory)>,
+    ) -> Self {
+        self.extra_rules.extend(rules);
+        self
+    }
+
+    /// Changes the extension of the output files generated from input files with the given
+    /// extension (without the leading dot), like writing `.luau` files as `.lua` files.
+    ///
+    /// The rewrite only applies to output paths computed from the input file names (when
+    /// processing a directory, or a file into a directory), and does not change how
+    /// requires are resolved.
+    pub fn with_extension_rewrite(
+        mut self,
+        extension: impl Into<String>,
+        new_extension: impl Into<String>,
+    ) -> Self {
+        self.extension_rewrites
+            .insert(extension.into(), new_extension.into());
+        self
+    }
+
     /// Gets the input path.
     pub fn input(&self) -> &Path {
         &self.input
@@ -76,6 +123,11 @@ impl Options {
         self.fail_fast
     }
 
+    /// Checks if the generated code is kept in memory instead of being written.
+    pub fn should_capture_output(&self) -> bool {
+        self.capture_output
+    }
+
     /// Gets the configuration file path, if set.
     pub fn configuration_path(&self) -> Option<&Path> {
         self.config_path.as_ref().map(AsRef::as_ref)
@@ -86,6 +138,21 @@ impl Options {
         self.config_generator_override.as_ref()
     }
 
+    pub(crate) fn extra_rules(&self) -> &HashMap<&'static str, RuleFactory> {
+        &self.extra_rules
+    }
+
+    pub(crate) fn rewrite_output_extension(&self, mut output: PathBuf) -> PathBuf {
+        let new_extension = output
+            .extension()
+            .and_then(|extension| self.extension_rewrites.get(extension.to_str()?));
+
+        if let Some(new_extension) = new_extension.cloned() {
+            output.set_extension(new_extension);
+        }
+        output
+    }
+
     /// Takes the configuration, if set.
     ///
     /// This removes the configuration from the options and returns it.
@@ -1,38 +1,98 @@
 use std::{
     collections::HashMap,
-    ffi::OsStr,
+    ffi::{OsStr, OsString},
     fs::{self, File},
     io::{self, BufWriter, ErrorKind as IOErrorKind, Write},
     iter,
-    path::{Path, PathBuf},
+    panic::RefUnwindSafe,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
     sync::{Arc, Mutex},
+    time::SystemTime,
 };
 
 use crate::utils::normalize_path;
 
 #[derive(Debug, Clone)]
 enum Source {
-    FileSystem,
-    Memory(Arc<Mutex<HashMap<PathBuf, String>>>),
+    FileSystem {
+        follow_symlinks: bool,
+    },
+    Memory {
+        files: Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>,
+        case_sensitive: bool,
+    },
+    // files written to the layer hide the files of the base, which is never modified. The
+    // removed paths hide the files of the base that were removed through the overlay
+    Overlay {
+        base: Box<Source>,
+        layer: Box<Source>,
+        removed: Arc<Mutex<Vec<PathBuf>>>,
+    },
 }
 
 impl Source {
+    // returns the base of an overlay, unless the location was removed through the overlay
+    fn visible_base<'a>(
+        base: &'a Source,
+        removed: &Mutex<Vec<PathBuf>>,
+        location: &Path,
+    ) -> Option<&'a Source> {
+        let location = normalize_path(location);
+        let is_removed = removed
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|removed_path| is_nested_path(&location, removed_path, true));
+
+        if is_removed {
+            None
+        } else {
+            Some(base)
+        }
+    }
+
     pub fn exists(&self, location: &Path) -> ResourceResult<bool> {
         match self {
-            Self::FileSystem => Ok(location.exists()),
-            Self::Memory(data) => Ok(data.lock().unwrap().contains_key(&normalize_path(location))),
+            Self::FileSystem { .. } => Ok(location.exists()),
+            Self::Memory { .. } => self.is_file(location),
+            Self::Overlay {
+                base,
+                layer,
+                removed,
+            } => Ok(layer.exists(location)?
+                || match Self::visible_base(base, removed, location) {
+                    Some(base) => base.exists(location)?,
+                    None => false,
+                }),
         }
     }
 
     pub fn is_directory(&self, location: &Path) -> ResourceResult<bool> {
         let is_directory = match self {
-            Source::FileSystem => self.exists(location)? && location.is_dir(),
-            Source::Memory(data) => {
-                let data = data.lock().unwrap();
+            Source::FileSystem { .. } => self.exists(location)? && location.is_dir(),
+            Source::Memory {
+                files,
+                case_sensitive,
+            } => {
+                let files = files.lock().unwrap();
                 let location = normalize_path(location);
 
-                data.iter()
-                    .any(|(path, _content)| path != &location && path.starts_with(&location))
+                files.keys().any(|path| {
+                    is_nested_path(path, &location, *case_sensitive)
+                        && !is_same_path(path, &location, *case_sensitive)
+                })
+            }
+            Source::Overlay {
+                base,
+                layer,
+                removed,
+            } => {
+                layer.is_directory(location)?
+                    || match Self::visible_base(base, removed, location) {
+                        Some(base) => base.is_directory(location)?,
+                        None => false,
+                    }
             }
         };
         Ok(is_directory)
@@ -40,12 +100,26 @@ impl Source {
 
     pub fn is_file(&self, location: &Path) -> ResourceResult<bool> {
         let is_file = match self {
-            Source::FileSystem => self.exists(location)? && location.is_file(),
-            Source::Memory(data) => {
-                let data = data.lock().unwrap();
-                let location = normalize_path(location);
+            Source::FileSystem { .. } => self.exists(location)? && location.is_file(),
+            Source::Memory {
+                files,
+                case_sensitive,
+            } => {
+                let files = files.lock().unwrap();
+                let location = find_memory_path(&files, location, *case_sensitive);
 
-                data.contains_key(&location)
+                files.contains_key(&location)
+            }
+            Source::Overlay {
+                base,
+                layer,
+                removed,
+            } => {
+                layer.is_file(location)?
+                    || match Self::visible_base(base, removed, location) {
+                        Some(base) => base.is_file(location)?,
+                        None => false,
+                    }
             }
         };
         Ok(is_file)
@@ -53,24 +127,70 @@ impl Source {
 
     pub fn get(&self, location: &Path) -> ResourceResult<String> {
         match self {
-            Self::FileSystem => fs::read_to_string(location).map_err(|err| match err.kind() {
+            Self::FileSystem { .. } => {
+                fs::read_to_string(location).map_err(|err| match err.kind() {
+                    IOErrorKind::NotFound => ResourceError::not_found(location),
+                    _ => ResourceError::io_error(location, err),
+                })
+            }
+            Self::Memory {
+                files,
+                case_sensitive,
+            } => {
+                let files = files.lock().unwrap();
+                let location = find_memory_path(&files, location, *case_sensitive);
+
+                let content = files
+                    .get(&location)
+                    .ok_or_else(|| ResourceError::not_found(&location))?;
+
+                String::from_utf8(content.clone()).map_err(|err| {
+                    ResourceError::io_error(location, io::Error::new(IOErrorKind::InvalidData, err))
+                })
+            }
+            Self::Overlay {
+                base,
+                layer,
+                removed,
+            } => match Self::visible_base(base, removed, location) {
+                Some(base) if !layer.is_file(location)? => base.get(location),
+                _ => layer.get(location),
+            },
+        }
+    }
+
+    pub fn get_bytes(&self, location: &Path) -> ResourceResult<Vec<u8>> {
+        match self {
+            Self::FileSystem { .. } => fs::read(location).map_err(|err| match err.kind() {
                 IOErrorKind::NotFound => ResourceError::not_found(location),
                 _ => ResourceError::io_error(location, err),
             }),
-            Self::Memory(data) => {
-                let data = data.lock().unwrap();
-                let location = normalize_path(location);
+            Self::Memory {
+                files,
+                case_sensitive,
+            } => {
+                let files = files.lock().unwrap();
+                let location = find_memory_path(&files, location, *case_sensitive);
 
-                data.get(&location)
-                    .map(String::from)
+                files
+                    .get(&location)
+                    .cloned()
                     .ok_or_else(|| ResourceError::not_found(location))
             }
+            Self::Overlay {
+                base,
+                layer,
+                removed,
+            } => match Self::visible_base(base, removed, location) {
+                Some(base) if !layer.is_file(location)? => base.get_bytes(location),
+                _ => layer.get_bytes(location),
+            },
         }
     }
 
-    pub fn write(&self, location: &Path, content: &str) -> ResourceResult<()> {
+    pub fn write(&self, location: &Path, content: &[u8]) -> ResourceResult<()> {
         match self {
-            Self::FileSystem => {
+            Self::FileSystem { .. } => {
                 if let Some(parent) = location.parent() {
                     fs::create_dir_all(parent)
                         .map_err(|err| ResourceError::io_error(parent, err))?;
@@ -80,35 +200,110 @@ impl Source {
                     File::create(location).map_err(|err| ResourceError::io_error(location, err))?;
 
                 let mut file = BufWriter::new(file);
-                file.write_all(content.as_bytes())
+                file.write_all(content)
                     .map_err(|err| ResourceError::io_error(location, err))
             }
-            Self::Memory(data) => {
-                let mut data = data.lock().unwrap();
-                data.insert(normalize_path(location), content.to_string());
+            Self::Memory {
+                files,
+                case_sensitive,
+            } => {
+                let mut files = files.lock().unwrap();
+                let location = find_memory_path(&files, location, *case_sensitive);
+                files.insert(location, content.to_vec());
                 Ok(())
             }
+            Self::Overlay { layer, .. } => layer.write(location, content),
+        }
+    }
+
+    pub fn modified(&self, location: &Path) -> Option<SystemTime> {
+        match self {
+            Self::FileSystem { .. } => fs::metadata(location)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            Self::Memory { .. } => None,
+            Self::Overlay {
+                base,
+                layer,
+                removed,
+            } => match Self::visible_base(base, removed, location) {
+                Some(base) if !layer.is_file(location).unwrap_or(false) => base.modified(location),
+                _ => layer.modified(location),
+            },
         }
     }
 
     pub fn walk(&self, location: &Path) -> impl Iterator<Item = PathBuf> {
         match self {
-            Self::FileSystem => Box::new(walk_file_system(location.to_path_buf()))
-                as Box<dyn Iterator<Item = PathBuf>>,
-            Self::Memory(data) => {
-                let data = data.lock().unwrap();
+            Self::FileSystem { follow_symlinks } => {
+                Box::new(walk_file_system(location.to_path_buf(), *follow_symlinks))
+                    as Box<dyn Iterator<Item = PathBuf>>
+            }
+            Self::Memory {
+                files,
+                case_sensitive,
+            } => {
+                let files = files.lock().unwrap();
                 let location = normalize_path(location);
-                let mut paths: Vec<_> = data.keys().map(normalize_path).collect();
-                paths.retain(|path| path.starts_with(&location));
+                let mut paths: Vec<_> = files.keys().map(normalize_path).collect();
+                paths.retain(|path| is_nested_path(path, &location, *case_sensitive));
+                paths.sort();
 
                 Box::new(paths.into_iter())
             }
+            Self::Overlay {
+                base,
+                layer,
+                removed,
+            } => {
+                let mut paths: Vec<_> = layer.walk(location).collect();
+                let base_paths: Vec<_> = base
+                    .walk(location)
+                    .filter(|path| {
+                        !paths.contains(path) && Self::visible_base(base, removed, path).is_some()
+                    })
+                    .collect();
+                paths.extend(base_paths);
+                paths.sort();
+
+                Box::new(paths.into_iter())
+            }
+        }
+    }
+
+    pub fn canonicalize(&self, location: &Path) -> PathBuf {
+        match self {
+            Self::FileSystem { .. } => {
+                fs::canonicalize(location).unwrap_or_else(|_| normalize_path(location))
+            }
+            Self::Memory { .. } => normalize_path(location),
+            Self::Overlay { base, .. } => base.canonicalize(location),
+        }
+    }
+
+    pub fn resolve_casing(&self, location: &Path) -> PathBuf {
+        match self {
+            Self::FileSystem { .. } => resolve_file_system_casing(location),
+            Self::Memory {
+                files,
+                case_sensitive,
+            } => find_memory_path(&files.lock().unwrap(), location, *case_sensitive),
+            Self::Overlay {
+                base,
+                layer,
+                removed,
+            } => match Self::visible_base(base, removed, location) {
+                Some(base) if !layer.is_file(location).unwrap_or(false) => {
+                    base.resolve_casing(location)
+                }
+                _ => layer.resolve_casing(location),
+            },
         }
     }
 
     fn remove(&self, location: &Path) -> Result<(), ResourceError> {
         match self {
-            Self::FileSystem => {
+            Self::FileSystem { .. } => {
                 if !self.exists(location)? {
                     Ok(())
                 } else if self.is_file(location)? {
@@ -120,37 +315,148 @@ impl Source {
                     Ok(())
                 }
             }
-            Self::Memory(data) => {
+            Self::Memory {
+                files,
+                case_sensitive,
+            } => {
                 if self.is_file(location)? {
-                    let mut data = data.lock().unwrap();
-                    data.remove(&normalize_path(location));
+                    let mut files = files.lock().unwrap();
+                    let location = find_memory_path(&files, location, *case_sensitive);
+                    files.remove(&location);
                 } else if self.is_directory(location)? {
-                    let mut data = data.lock().unwrap();
+                    let mut files = files.lock().unwrap();
                     let location = normalize_path(location);
-                    data.retain(|path, _| !path.starts_with(&location));
+                    files.retain(|path, _| !is_nested_path(path, &location, *case_sensitive));
                 }
 
                 Ok(())
             }
+            Self::Overlay { layer, removed, .. } => {
+                layer.remove(location)?;
+                removed.lock().unwrap().push(normalize_path(location));
+                Ok(())
+            }
         }
     }
 }
 
-fn walk_file_system(location: PathBuf) -> impl Iterator<Item = PathBuf> {
-    let mut unknown_paths = vec![location];
+// finds the path of a file stored in memory. When the memory is not case-sensitive, the
+// path of an existing file with a different casing is returned
+fn find_memory_path(
+    files: &HashMap<PathBuf, Vec<u8>>,
+    location: &Path,
+    case_sensitive: bool,
+) -> PathBuf {
+    let location = normalize_path(location);
+
+    if case_sensitive || files.contains_key(&location) {
+        return location;
+    }
+
+    files
+        .keys()
+        .find(|path| is_same_path(path, &location, false))
+        .cloned()
+        .unwrap_or(location)
+}
+
+fn is_same_path(path: &Path, other: &Path, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        path == other
+    } else {
+        path.to_string_lossy().to_lowercase() == other.to_string_lossy().to_lowercase()
+    }
+}
+
+fn is_nested_path(path: &Path, location: &Path, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        path.starts_with(location)
+    } else {
+        Path::new(&path.to_string_lossy().to_lowercase())
+            .starts_with(location.to_string_lossy().to_lowercase())
+    }
+}
+
+// replaces each component of the path with the name of the matching directory entry,
+// so that the path uses the casing of the files on disk
+fn resolve_file_system_casing(location: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+
+    for component in location.components() {
+        match component {
+            Component::Normal(name) => {
+                let directory = if resolved.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    resolved.as_path()
+                };
+
+                let entry_names: Vec<_> = fs::read_dir(directory)
+                    .map(|entries| {
+                        entries
+                            .filter_map(Result::ok)
+                            .map(|entry| entry.file_name())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                let lowercase_name = name.to_string_lossy().to_lowercase();
+                let on_disk_name = entry_names
+                    .iter()
+                    .find(|entry_name| entry_name.as_os_str() == name)
+                    .or_else(|| {
+                        entry_names.iter().find(|entry_name| {
+                            entry_name.to_string_lossy().to_lowercase() == lowercase_name
+                        })
+                    });
+
+                resolved.push(on_disk_name.map(OsString::as_os_str).unwrap_or(name));
+            }
+            component => resolved.push(component),
+        }
+    }
+
+    resolved
+}
+
+// walks through the files of a directory. Each path keeps the canonical paths of the
+// directories it is nested in, so that a symlink pointing back to one of them is not
+// walked forever
+fn walk_file_system(location: PathBuf, follow_symlinks: bool) -> impl Iterator<Item = PathBuf> {
+    let mut unknown_paths = vec![(location, Rc::new(Vec::new()))];
     let mut file_paths = Vec::new();
-    let mut dir_entries = Vec::new();
+    let mut dir_entries: Vec<(PathBuf, Rc<Vec<PathBuf>>)> = Vec::new();
 
     iter::from_fn(move || loop {
-        if let Some(location) = unknown_paths.pop() {
-            match location.metadata() {
+        if let Some((location, ancestors)) = unknown_paths.pop() {
+            // the walked location itself is always followed
+            let metadata = if follow_symlinks || ancestors.is_empty() {
+                location.metadata()
+            } else {
+                location.symlink_metadata()
+            };
+
+            match metadata {
                 Ok(metadata) => {
                     if metadata.is_file() {
-                        file_paths.push(location.to_path_buf());
+                        file_paths.push(location);
                     } else if metadata.is_dir() {
-                        dir_entries.push(location.to_path_buf());
+                        let canonical = fs::canonicalize(&location)
+                            .unwrap_or_else(|_| normalize_path(&location));
+
+                        if ancestors.contains(&canonical) {
+                            log::warn!(
+                                "symlink cycle detected: `{}` points to `{}` which is already walked",
+                                location.display(),
+                                canonical.display()
+                            );
+                        } else {
+                            let mut directory_ancestors = Vec::clone(&ancestors);
+                            directory_ancestors.push(canonical);
+                            dir_entries.push((location, Rc::new(directory_ancestors)));
+                        }
                     } else if metadata.is_symlink() {
-                        log::warn!("unexpected symlink `{}` not followed", location.display());
+                        log::debug!("symlink `{}` not followed", location.display());
                     } else {
                         log::warn!(
                             concat!(
@@ -169,13 +475,15 @@ fn walk_file_system(location: PathBuf) -> impl Iterator<Item = PathBuf> {
                     );
                 }
             }
-        } else if let Some(dir_location) = dir_entries.pop() {
+        } else if let Some((dir_location, ancestors)) = dir_entries.pop() {
             match dir_location.read_dir() {
                 Ok(read_dir) => {
+                    let mut entry_paths = Vec::new();
+
                     for entry in read_dir {
                         match entry {
                             Ok(entry) => {
-                                unknown_paths.push(entry.path());
+                                entry_paths.push(entry.path());
                             }
                             Err(err) => {
                                 log::warn!(
@@ -186,6 +494,14 @@ fn walk_file_system(location: PathBuf) -> impl Iterator<Item = PathBuf> {
                             }
                         }
                     }
+
+                    // the order of directory entries depends on the file system
+                    entry_paths.sort();
+                    unknown_paths.extend(
+                        entry_paths
+                            .into_iter()
+                            .map(|path| (path, ancestors.clone())),
+                    );
                 }
                 Err(err) => {
                     log::warn!(
@@ -211,13 +527,31 @@ fn walk_file_system(location: PathBuf) -> impl Iterator<Item = PathBuf> {
 #[derive(Debug, Clone)]
 pub struct Resources {
     source: Source,
+    canonicalizer: Option<PathCanonicalizer>,
+}
+
+type CanonicalizeFn = dyn Fn(&Path) -> PathBuf + Send + Sync + RefUnwindSafe;
+
+#[derive(Clone)]
+struct PathCanonicalizer(Arc<CanonicalizeFn>);
+
+impl std::fmt::Debug for PathCanonicalizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PathCanonicalizer").finish()
+    }
 }
 
 impl Resources {
     /// Creates a new resource manager that operates on the file system.
+    ///
+    /// Symlinks are followed when walking directories. Use
+    /// [`follow_symlinks`](Resources::follow_symlinks) to change this behavior.
     pub fn from_file_system() -> Self {
         Self {
-            source: Source::FileSystem,
+            source: Source::FileSystem {
+                follow_symlinks: true,
+            },
+            canonicalizer: None,
         }
     }
 
@@ -227,8 +561,65 @@ impl Resources {
     /// writing to disk.
     pub fn from_memory() -> Self {
         Self {
-            source: Source::Memory(Arc::new(Mutex::new(HashMap::new()))),
+            source: Source::Memory {
+                files: Arc::new(Mutex::new(HashMap::new())),
+                case_sensitive: true,
+            },
+            canonicalizer: None,
+        }
+    }
+
+    /// Sets if symlinks found while walking directories are followed. This only applies to
+    /// resources created with [`from_file_system`](Resources::from_file_system). When a
+    /// followed symlink points back to a directory that is being walked, it is skipped.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        if let Source::FileSystem {
+            follow_symlinks: current,
+        } = &mut self.source
+        {
+            *current = follow_symlinks;
         }
+        self
+    }
+
+    /// Matches the paths of memory resources without considering their casing, like the
+    /// file systems of Windows and macOS. This only applies to resources created with
+    /// [`from_memory`](Resources::from_memory).
+    pub fn case_insensitive(mut self) -> Self {
+        if let Source::Memory { case_sensitive, .. } = &mut self.source {
+            *case_sensitive = false;
+        }
+        self
+    }
+
+    /// Replaces how paths are canonicalized. This can be used with memory resources to
+    /// simulate a file that is reachable through different paths, like a symlink.
+    pub fn with_canonicalizer(
+        mut self,
+        canonicalizer: impl Fn(&Path) -> PathBuf + Send + Sync + RefUnwindSafe + 'static,
+    ) -> Self {
+        self.canonicalizer = Some(PathCanonicalizer(Arc::new(canonicalizer)));
+        self
+    }
+
+    /// Creates a resource manager that reads from these resources, but keeps every
+    /// written file in memory. Files written to the overlay hide the underlying files,
+    /// which are never modified. Removing a file or a directory from the overlay hides the
+    /// underlying files, until a file is written again at the same location.
+    pub(crate) fn overlay(&self) -> Self {
+        Self {
+            source: Source::Overlay {
+                base: Box::new(self.source.clone()),
+                layer: Box::new(Self::from_memory().source),
+                removed: Default::default(),
+            },
+            canonicalizer: self.canonicalizer.clone(),
+        }
+    }
+
+    /// Returns true if the resources operate directly on the file system.
+    pub(crate) fn is_file_system(&self) -> bool {
+        matches!(self.source, Source::FileSystem { .. })
     }
 
     /// Collects all Lua and Luau files in the specified location.
@@ -241,6 +632,16 @@ impl Resources {
         })
     }
 
+    /// Collects all files that are not Lua or Luau files in the specified location.
+    pub fn collect_assets(&self, location: impl AsRef<Path>) -> impl Iterator<Item = PathBuf> {
+        self.source.walk(location.as_ref()).filter(|path| {
+            !matches!(
+                path.extension().and_then(OsStr::to_str),
+                Some("lua") | Some("luau")
+            )
+        })
+    }
+
     /// Checks if a path exists.
     pub fn exists(&self, location: impl AsRef<Path>) -> ResourceResult<bool> {
         self.source.exists(location.as_ref())
@@ -261,20 +662,59 @@ impl Resources {
         self.source.get(location.as_ref())
     }
 
+    /// Reads the raw bytes of a file.
+    pub fn get_bytes(&self, location: impl AsRef<Path>) -> ResourceResult<Vec<u8>> {
+        self.source.get_bytes(location.as_ref())
+    }
+
     /// Writes content to a file.
     pub fn write(&self, location: impl AsRef<Path>, content: &str) -> ResourceResult<()> {
+        self.source.write(location.as_ref(), content.as_bytes())
+    }
+
+    /// Writes raw bytes to a file.
+    pub fn write_bytes(&self, location: impl AsRef<Path>, content: &[u8]) -> ResourceResult<()> {
         self.source.write(location.as_ref(), content)
     }
 
+    /// Copies the raw bytes of a file to another location.
+    pub fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> ResourceResult<()> {
+        let content = self.source.get_bytes(from.as_ref())?;
+        self.source.write(to.as_ref(), &content)
+    }
+
     /// Removes a file or directory.
     pub fn remove(&self, location: impl AsRef<Path>) -> ResourceResult<()> {
         self.source.remove(location.as_ref())
     }
 
+    /// Returns the last modification time of a file, when it is known. Files in memory
+    /// do not have a modification time.
+    pub(crate) fn modified(&self, location: impl AsRef<Path>) -> Option<SystemTime> {
+        self.source.modified(location.as_ref())
+    }
+
     /// Walks through all files in a directory.
     pub fn walk(&self, location: impl AsRef<Path>) -> impl Iterator<Item = PathBuf> {
         self.source.walk(location.as_ref())
     }
+
+    /// Returns the path of a file with the casing of the existing file, which can differ
+    /// from the given location on case-insensitive file systems.
+    pub fn resolve_casing(&self, location: impl AsRef<Path>) -> PathBuf {
+        self.source.resolve_casing(location.as_ref())
+    }
+
+    /// Returns the canonical path of a location, so that a file reachable through
+    /// different paths (like a symlink and its target) can be identified. On the file
+    /// system, symlinks are resolved. Otherwise, the path is normalized.
+    pub fn canonicalize(&self, location: impl AsRef<Path>) -> PathBuf {
+        let location = location.as_ref();
+        match &self.canonicalizer {
+            Some(PathCanonicalizer(canonicalize)) => canonicalize(location),
+            None => self.source.canonicalize(location),
+        }
+    }
 }
 
 /// An error that can occur during operations on [`Resource`].
@@ -368,6 +808,137 @@ mod test {
             assert_eq!(resources.get(any_path()), Ok(ANY_CONTENT.to_string()));
         }
 
+        #[test]
+        fn read_bytes_of_created_file() {
+            let resources = new();
+            resources.write_bytes(any_path(), &[0, 159, 255]).unwrap();
+
+            assert_eq!(resources.get_bytes(any_path()), Ok(vec![0, 159, 255]));
+        }
+
+        #[test]
+        fn read_content_of_non_utf8_file_errors() {
+            let resources = new();
+            resources.write_bytes(any_path(), &[0, 159, 255]).unwrap();
+
+            assert!(matches!(
+                resources.get(any_path()),
+                Err(ResourceError::IO { .. })
+            ));
+        }
+
+        #[test]
+        fn copy_created_file() {
+            let resources = new();
+            resources.write_bytes(any_path(), &[0, 159, 255]).unwrap();
+
+            resources.copy(any_path(), "copy.png").unwrap();
+
+            assert_eq!(resources.get_bytes("copy.png"), Ok(vec![0, 159, 255]));
+        }
+
+        #[test]
+        fn canonicalize_normalizes_path() {
+            assert_eq!(
+                new().canonicalize("src/./a/../test.lua"),
+                PathBuf::from("src/test.lua")
+            );
+        }
+
+        #[test]
+        fn canonicalize_with_canonicalizer() {
+            let resources = new().with_canonicalizer(|path| path.with_extension("luau"));
+
+            assert_eq!(
+                resources.canonicalize(any_path()),
+                PathBuf::from("test.luau")
+            );
+        }
+
+        #[test]
+        fn overlay_keeps_canonicalizer() {
+            let resources = new().with_canonicalizer(|path| path.with_extension("luau"));
+
+            assert_eq!(
+                resources.overlay().canonicalize(any_path()),
+                PathBuf::from("test.luau")
+            );
+        }
+
+        #[test]
+        fn overlay_removes_file_of_base() {
+            let resources = new();
+            resources.write(any_path(), ANY_CONTENT).unwrap();
+            let overlay = resources.overlay();
+
+            overlay.remove(any_path()).unwrap();
+
+            assert_eq!(overlay.exists(any_path()), Ok(false));
+            assert_eq!(
+                overlay.get(any_path()),
+                Err(ResourceError::not_found(any_path()))
+            );
+            assert_eq!(resources.exists(any_path()), Ok(true));
+        }
+
+        #[test]
+        fn overlay_writes_file_removed_from_base() {
+            let resources = new();
+            resources.write(any_path(), ANY_CONTENT).unwrap();
+            let overlay = resources.overlay();
+
+            overlay.remove(any_path()).unwrap();
+            overlay.write(any_path(), "return false").unwrap();
+
+            assert_eq!(overlay.get(any_path()), Ok("return false".to_owned()));
+            assert_eq!(resources.get(any_path()), Ok(ANY_CONTENT.to_owned()));
+        }
+
+        #[test]
+        fn overlay_removes_directory_of_base() {
+            let resources = new();
+            resources.write("src/a.lua", ANY_CONTENT).unwrap();
+            resources.write("src/b.lua", ANY_CONTENT).unwrap();
+            resources.write("main.lua", ANY_CONTENT).unwrap();
+            let overlay = resources.overlay();
+
+            overlay.remove("src").unwrap();
+
+            assert_eq!(overlay.is_directory("src"), Ok(false));
+            assert_eq!(overlay.is_file("src/a.lua"), Ok(false));
+            assert_eq!(
+                overlay.collect_work("").collect::<Vec<_>>(),
+                vec![PathBuf::from("main.lua")]
+            );
+        }
+
+        #[test]
+        fn case_insensitive_reads_file_with_different_casing() {
+            let resources = new().case_insensitive();
+            resources.write("src/Utils.lua", ANY_CONTENT).unwrap();
+
+            assert_eq!(resources.get("SRC/utils.lua"), Ok(ANY_CONTENT.to_string()));
+        }
+
+        #[test]
+        fn case_sensitive_does_not_find_file_with_different_casing() {
+            let resources = new();
+            resources.write("src/Utils.lua", ANY_CONTENT).unwrap();
+
+            assert_eq!(resources.is_file("src/utils.lua"), Ok(false));
+        }
+
+        #[test]
+        fn case_insensitive_resolve_casing_returns_existing_path() {
+            let resources = new().case_insensitive();
+            resources.write("src/Utils.lua", ANY_CONTENT).unwrap();
+
+            assert_eq!(
+                resources.resolve_casing("src/utils.lua"),
+                PathBuf::from("src/Utils.lua")
+            );
+        }
+
         #[test]
         fn collect_work_contains_created_files() {
             let resources = new();
@@ -379,4 +950,97 @@ mod test {
             );
         }
     }
+    #[cfg(unix)]
+    mod file_system {
+        use std::os::unix::fs::symlink;
+
+        use tempfile::TempDir;
+
+        use super::*;
+
+        // creates `store/module.lua` and a `src` directory that links to the store
+        fn setup() -> TempDir {
+            let directory = tempfile::tempdir().unwrap();
+            let root = directory.path();
+
+            fs::create_dir_all(root.join("store")).unwrap();
+            fs::write(root.join("store/module.lua"), ANY_CONTENT).unwrap();
+            fs::create_dir_all(root.join("src")).unwrap();
+            fs::write(root.join("src/main.lua"), ANY_CONTENT).unwrap();
+            symlink(root.join("store"), root.join("src/Packages")).unwrap();
+
+            directory
+        }
+
+        fn walk_sorted(resources: &Resources, location: &Path) -> Vec<PathBuf> {
+            let mut paths: Vec<_> = resources.walk(location).collect();
+            paths.sort();
+            paths
+        }
+
+        #[test]
+        fn walk_follows_symlinks_by_default() {
+            let directory = setup();
+            let root = directory.path();
+
+            assert_eq!(
+                walk_sorted(&Resources::from_file_system(), &root.join("src")),
+                vec![
+                    root.join("src/Packages/module.lua"),
+                    root.join("src/main.lua")
+                ]
+            );
+        }
+
+        #[test]
+        fn walk_without_following_symlinks() {
+            let directory = setup();
+            let root = directory.path();
+            let resources = Resources::from_file_system().follow_symlinks(false);
+
+            assert_eq!(
+                walk_sorted(&resources, &root.join("src")),
+                vec![root.join("src/main.lua")]
+            );
+        }
+
+        #[test]
+        fn walk_stops_at_symlink_cycle() {
+            let directory = setup();
+            let root = directory.path();
+            symlink(root.join("src"), root.join("src/self")).unwrap();
+
+            assert_eq!(
+                walk_sorted(&Resources::from_file_system(), &root.join("src")),
+                vec![
+                    root.join("src/Packages/module.lua"),
+                    root.join("src/main.lua")
+                ]
+            );
+        }
+
+        #[test]
+        fn resolve_casing_uses_casing_on_disk() {
+            let directory = setup();
+            let root = directory.path();
+            fs::write(root.join("src/Utils.lua"), ANY_CONTENT).unwrap();
+
+            assert_eq!(
+                Resources::from_file_system().resolve_casing(root.join("SRC/utils.lua")),
+                root.join("src/Utils.lua")
+            );
+        }
+
+        #[test]
+        fn canonicalize_resolves_symlink() {
+            let directory = setup();
+            let root = directory.path();
+            let resources = Resources::from_file_system();
+
+            assert_eq!(
+                resources.canonicalize(root.join("src/Packages/module.lua")),
+                resources.canonicalize(root.join("store/module.lua"))
+            );
+        }
+    }
 }
@@ -0,0 +1,331 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    nodes::{
+        BinaryExpression, BinaryOperator, Block, CompoundAssignStatement, FunctionBodyTokens,
+        FunctionExpression, FunctionStatement, GenericForStatement, IfExpression,
+        InterpolatedStringExpression, LastStatement, LocalAssignStatement, LocalFunctionStatement,
+        NumberExpression, NumericForStatement, Token, TypeCastExpression, TypeDeclarationStatement,
+        TypedIdentifier,
+    },
+    process::{DefaultVisitor, NodeProcessor, NodeVisitor},
+};
+
+/// The Lua version that the generated code must be compatible with.
+///
+/// When a target is configured, darklua verifies the code produced after all
+/// rules were applied and reports any syntax that the target does not support.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LuaTarget {
+    /// Lua 5.1
+    Lua51,
+    /// Lua 5.3
+    Lua53,
+    /// Luau
+    Luau,
+}
+
+impl LuaTarget {
+    pub(crate) fn find_incompatibilities(&self, block: &mut Block) -> Vec<TargetIncompatibility> {
+        if *self == Self::Luau {
+            return Vec::new();
+        }
+
+        let mut finder = IncompatibilityFinder::new(*self);
+        DefaultVisitor::visit_block(block, &mut finder);
+
+        let mut incompatibilities = finder.incompatibilities;
+        incompatibilities.sort_by_key(|incompatibility| incompatibility.line);
+        incompatibilities
+    }
+
+    fn supports_floor_division(&self) -> bool {
+        match self {
+            Self::Lua51 => false,
+            Self::Lua53 | Self::Luau => true,
+        }
+    }
+}
+
+impl fmt::Display for LuaTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lua51 => write!(f, "lua51"),
+            Self::Lua53 => write!(f, "lua53"),
+            Self::Luau => write!(f, "luau"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TargetIncompatibility {
+    construct: &'static str,
+    line: Option<usize>,
+}
+
+impl fmt::Display for TargetIncompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "{} (line {})", self.construct, line)
+        } else {
+            write!(f, "{}", self.construct)
+        }
+    }
+}
+
+struct IncompatibilityFinder {
+    target: LuaTarget,
+    incompatibilities: Vec<TargetIncompatibility>,
+}
+
+impl IncompatibilityFinder {
+    fn new(target: LuaTarget) -> Self {
+        Self {
+            target,
+            incompatibilities: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, construct: &'static str, token: Option<&Token>) {
+        self.incompatibilities.push(TargetIncompatibility {
+            construct,
+            line: token.and_then(Token::get_line_number),
+        });
+    }
+
+    fn verify_typed_identifiers<'a>(
+        &mut self,
+        identifiers: impl Iterator<Item = &'a TypedIdentifier>,
+    ) {
+        for identifier in identifiers.filter(|identifier| identifier.has_type()) {
+            self.report("type annotation", identifier.get_token());
+        }
+    }
+
+    fn verify_function_types(
+        &mut self,
+        has_signature_types: bool,
+        tokens: Option<&FunctionBodyTokens>,
+    ) {
+        if has_signature_types {
+            self.report("type annotation", tokens.map(|tokens| &tokens.function));
+        }
+    }
+}
+
+impl NodeProcessor for IncompatibilityFinder {
+    fn process_compound_assign_statement(&mut self, assignment: &mut CompoundAssignStatement) {
+        self.report(
+            "compound assignment",
+            assignment.get_tokens().map(|tokens| &tokens.operator),
+        );
+    }
+
+    fn process_last_statement(&mut self, statement: &mut LastStatement) {
+        if let LastStatement::Continue(token) = statement {
+            self.report("continue statement", token.as_ref());
+        }
+    }
+
+    fn process_type_declaration(&mut self, declaration: &mut TypeDeclarationStatement) {
+        self.report(
+            "type declaration",
+            declaration.get_tokens().map(|tokens| &tokens.r#type),
+        );
+    }
+
+    fn process_local_assign_statement(&mut self, assignment: &mut LocalAssignStatement) {
+        self.verify_typed_identifiers(assignment.iter_variables());
+    }
+
+    fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
+        self.verify_typed_identifiers(generic_for.iter_identifiers());
+    }
+
+    fn process_numeric_for_statement(&mut self, numeric_for: &mut NumericForStatement) {
+        self.verify_typed_identifiers(std::iter::once(numeric_for.get_identifier()));
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        self.verify_typed_identifiers(function.iter_parameters());
+        self.verify_function_types(
+            function.get_return_type().is_some()
+                || function.get_variadic_type().is_some()
+                || function.get_generic_parameters().is_some(),
+            function.get_tokens(),
+        );
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        self.verify_typed_identifiers(function.iter_parameters());
+        self.verify_function_types(
+            function.get_return_type().is_some()
+                || function.get_variadic_type().is_some()
+                || function.get_generic_parameters().is_some(),
+            function.get_tokens().map(|tokens| &tokens.function_body),
+        );
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        self.verify_typed_identifiers(function.iter_parameters());
+        self.verify_function_types(
+            function.get_return_type().is_some()
+                || function.get_variadic_type().is_some()
+                || function.get_generic_parameters().is_some(),
+            function.get_tokens(),
+        );
+    }
+
+    fn process_if_expression(&mut self, if_expression: &mut IfExpression) {
+        self.report(
+            "if expression",
+            if_expression.get_tokens().map(|tokens| &tokens.r#if),
+        );
+    }
+
+    fn process_interpolated_string_expression(
+        &mut self,
+        string: &mut InterpolatedStringExpression,
+    ) {
+        self.report(
+            "interpolated string",
+            string.get_tokens().map(|tokens| &tokens.opening_tick),
+        );
+    }
+
+    fn process_type_cast_expression(&mut self, type_cast: &mut TypeCastExpression) {
+        self.report("type cast", type_cast.get_token());
+    }
+
+    fn process_binary_expression(&mut self, binary: &mut BinaryExpression) {
+        if binary.operator() == BinaryOperator::DoubleSlash
+            && !self.target.supports_floor_division()
+        {
+            self.report("floor division operator `//`", binary.get_token());
+        }
+    }
+
+    fn process_number_expression(&mut self, number: &mut NumberExpression) {
+        if let NumberExpression::Binary(binary) = number {
+            self.report("binary number literal", binary.get_token());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Parser;
+
+    fn find_incompatibilities(target: LuaTarget, code: &str) -> Vec<String> {
+        let mut block = Parser::default()
+            .preserve_tokens()
+            .parse(code)
+            .expect("code should parse");
+
+        target
+            .find_incompatibilities(&mut block)
+            .into_iter()
+            .map(|incompatibility| incompatibility.to_string())
+            .collect()
+    }
+
+    macro_rules! test_incompatibilities {
+        ($($name:ident ($target:expr, $code:literal) => [$($expected:literal),* $(,)?]),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    let expected: Vec<&str> = vec![$($expected),*];
+                    pretty_assertions::assert_eq!(find_incompatibilities($target, $code), expected);
+                }
+            )*
+        };
+    }
+
+    test_incompatibilities!(
+        lua51_continue(LuaTarget::Lua51, "for i = 1, 10 do\n  continue\nend") => [
+            "continue statement (line 2)",
+        ],
+        lua51_compound_assignment(LuaTarget::Lua51, "local a = 1\na += 1") => [
+            "compound assignment (line 2)",
+        ],
+        lua51_if_expression(LuaTarget::Lua51, "return if a then b else c") => [
+            "if expression (line 1)",
+        ],
+        lua51_interpolated_string(LuaTarget::Lua51, "return `{a}`") => [
+            "interpolated string (line 1)",
+        ],
+        lua51_type_declaration(LuaTarget::Lua51, "type T = string") => [
+            "type declaration (line 1)",
+        ],
+        lua51_local_type_annotation(LuaTarget::Lua51, "local a: string = ''") => [
+            "type annotation (line 1)",
+        ],
+        lua51_function_parameter_type(LuaTarget::Lua51, "local function f(a: string) end") => [
+            "type annotation (line 1)",
+        ],
+        lua51_function_return_type(LuaTarget::Lua51, "\nfunction f(): string end") => [
+            "type annotation (line 2)",
+        ],
+        lua51_function_expression_generics(LuaTarget::Lua51, "return function<T>(a) end") => [
+            "type annotation (line 1)",
+        ],
+        lua51_type_cast(LuaTarget::Lua51, "return a :: string") => [
+            "type cast (line 1)",
+        ],
+        lua51_floor_division(LuaTarget::Lua51, "return a // b") => [
+            "floor division operator `//` (line 1)",
+        ],
+        lua51_binary_number(LuaTarget::Lua51, "return 0b101") => [
+            "binary number literal (line 1)",
+        ],
+        lua51_multiple_constructs_sorted_by_line(
+            LuaTarget::Lua51,
+            "local a: number = 1\na += if b then 1 else 2"
+        ) => [
+            "type annotation (line 1)",
+            "compound assignment (line 2)",
+            "if expression (line 2)",
+        ],
+        lua51_regular_lua(LuaTarget::Lua51, "local a = 1 for i = 1, 10 do a = a + i end return a") => [],
+        lua53_floor_division(LuaTarget::Lua53, "return a // b") => [],
+        lua53_continue(LuaTarget::Lua53, "while true do continue end") => [
+            "continue statement (line 1)",
+        ],
+        luau_continue(LuaTarget::Luau, "while true do continue end") => [],
+        luau_everything(
+            LuaTarget::Luau,
+            "type T = string local a: T = `{if b then 1 // 2 else 0b1}` a ..= '' return a :: T"
+        ) => [],
+    );
+
+    #[test]
+    fn incompatibility_without_tokens_has_no_line() {
+        let mut block = Parser::default()
+            .parse("while true do continue end")
+            .expect("code should parse");
+
+        let incompatibilities: Vec<_> = LuaTarget::Lua51
+            .find_incompatibilities(&mut block)
+            .into_iter()
+            .map(|incompatibility| incompatibility.to_string())
+            .collect();
+
+        pretty_assertions::assert_eq!(incompatibilities, vec!["continue statement"]);
+    }
+
+    #[test]
+    fn deserialize_targets() {
+        for (value, target) in [
+            ("'lua51'", LuaTarget::Lua51),
+            ("'lua53'", LuaTarget::Lua53),
+            ("'luau'", LuaTarget::Luau),
+        ] {
+            pretty_assertions::assert_eq!(json5::from_str::<LuaTarget>(value).unwrap(), target);
+        }
+    }
+}
@@ -3,7 +3,11 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::{nodes::Block, utils::Timer};
+use crate::{
+    nodes::Block,
+    rules::{DetectedRequireMode, GeneratedIdentifiers, RuleWarning},
+    utils::{normalize_path, Timer},
+};
 
 use super::{DarkluaError, DarkluaResult};
 
@@ -50,17 +54,53 @@ impl Progress {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub(crate) struct WorkProgress {
     pub(crate) content: String,
     pub(crate) progress: Progress,
+    // the work is sent between threads, so the identifiers are only shared while the rules
+    // are applied
+    pub(crate) generated_identifiers: GeneratedIdentifiers,
+    // the block and the generated identifiers before any rule is applied, kept to apply
+    // the rules of the next profiles
+    initial_block: Option<(Block, GeneratedIdentifiers)>,
+    profile_group: usize,
 }
 
 impl WorkProgress {
-    pub(crate) fn new(content: String, block: Block) -> Self {
+    pub(crate) fn new(
+        content: String,
+        block: Block,
+        generated_identifiers: GeneratedIdentifiers,
+    ) -> Self {
         Self {
             content,
             progress: Progress::new(block),
+            generated_identifiers,
+            initial_block: None,
+            profile_group: 0,
+        }
+    }
+
+    pub(crate) fn keep_initial_block(mut self) -> Self {
+        self.initial_block = Some((
+            self.progress.block.clone(),
+            self.generated_identifiers.clone(),
+        ));
+        self
+    }
+
+    pub(crate) fn profile_group(&self) -> usize {
+        self.profile_group
+    }
+
+    /// Restarts the progress from the initial block to apply the rules of the next group
+    /// of profiles.
+    pub(crate) fn start_next_profile_group(&mut self) {
+        self.profile_group += 1;
+        if let Some((block, generated_identifiers)) = self.initial_block.clone() {
+            self.progress = Progress::new(block);
+            self.generated_identifiers = generated_identifiers;
         }
     }
 
@@ -122,11 +162,44 @@ impl WorkData {
     }
 }
 
+/// The kind of a file written while processing a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArtifactKind {
+    /// The generated Lua code.
+    Output,
+    /// A sourcemap generated for a bundled output.
+    Sourcemap,
+    /// The dependency graph of a bundle, written in the DOT format.
+    DependencyGraph,
+    /// A file written to help debugging, like the AST view written in debug builds or the
+    /// invalid code written when an output fails validation.
+    Debug,
+}
+
+/// Maps a line of a generated file to the line of a source it was generated from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LineMappingSegment {
+    pub(crate) output: PathBuf,
+    /// The generated line, starting at 1.
+    pub(crate) generated_line: usize,
+    pub(crate) source: PathBuf,
+    /// The original line, starting at 1.
+    pub(crate) original_line: usize,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct WorkItem {
     pub(crate) data: WorkData,
     pub(crate) status: WorkStatus,
     pub(crate) external_file_dependencies: HashSet<PathBuf>,
+    pub(crate) warnings: Vec<RuleWarning>,
+    pub(crate) detected_require_modes: Vec<DetectedRequireMode>,
+    pub(crate) sourcemap_mappings: Vec<(String, usize)>,
+    pub(crate) line_mappings: Vec<LineMappingSegment>,
+    pub(crate) bundled_files: Vec<PathBuf>,
+    pub(crate) generated: Vec<(PathBuf, String)>,
+    pub(crate) outputs: Vec<PathBuf>,
+    pub(crate) artifacts: Vec<(PathBuf, usize, ArtifactKind)>,
 }
 
 impl WorkItem {
@@ -138,6 +211,14 @@ impl WorkItem {
             },
             status: Default::default(),
             external_file_dependencies: Default::default(),
+            warnings: Vec::new(),
+            detected_require_modes: Vec::new(),
+            sourcemap_mappings: Vec::new(),
+            line_mappings: Vec::new(),
+            bundled_files: Vec::new(),
+            generated: Vec::new(),
+            outputs: Vec::new(),
+            artifacts: Vec::new(),
         }
     }
 
@@ -160,5 +241,22 @@ impl WorkItem {
     pub(crate) fn reset(&mut self) {
         self.status = WorkStatus::NotStarted;
         self.external_file_dependencies.clear();
+        self.warnings.clear();
+        self.detected_require_modes.clear();
+        self.sourcemap_mappings.clear();
+        self.line_mappings.clear();
+        self.bundled_files.clear();
+        self.generated.clear();
+        self.outputs.clear();
+        self.artifacts.clear();
+    }
+
+    /// Records a file written for this work item. Writing again to the same path replaces
+    /// the previous record, since the file was overwritten.
+    pub(crate) fn push_artifact(&mut self, path: &Path, size: usize, kind: ArtifactKind) {
+        let path = normalize_path(path);
+        self.artifacts
+            .retain(|(artifact_path, _, _)| *artifact_path != path);
+        self.artifacts.push((path, size, kind));
     }
 }
@@ -1,28 +1,56 @@
-use std::path::Path;
+use std::{
+    collections::HashSet,
+    iter, mem,
+    path::{Path, PathBuf},
+};
 
 use super::{
-    configuration::Configuration,
+    configuration::{BundleConfiguration, Configuration, OutputProfile, ProfileGroup},
     resources::Resources,
     utils::maybe_plural,
     work_cache::WorkCache,
-    work_item::{WorkItem, WorkProgress, WorkStatus},
+    work_item::{ArtifactKind, LineMappingSegment, WorkItem, WorkProgress, WorkStatus},
     DarkluaError, DarkluaResult, Options,
 };
 
 use crate::{
     nodes::Block,
-    rules::{bundle::Bundler, ContextBuilder, FlawlessRule, Rule, RuleConfiguration},
-    utils::{normalize_path, Timer},
+    rules::{
+        bundle::{Bundler, DependencyGraph},
+        get_all_rule_names, verify_rule_name, with_scoped_rules, ContextBuilder, DisabledLines,
+        FlawlessRule, RemoveUnusedVariable, Rule, RuleConfiguration, RuleWarning,
+        SharedGeneratedIdentifiers, WarningCode,
+    },
+    utils::{find_leading_comment_directives, normalize_path, split_directive, Timer},
     GeneratorParameters,
 };
 
 use crate::utils::source_registry::SourceRegistry;
+use indexmap::IndexSet;
 
-use crate::process::set_instance_indexing_is_pure;
-use crate::process::{clear_known_instance_aliases, set_known_instance_aliases};
+use crate::process::{
+    clear_known_instance_aliases, reset_instance_indexing, set_instance_indexing_is_pure,
+    set_known_instance_aliases,
+};
 use crate::rules::ReplaceReferencedTokens;
 use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
 
+const DISABLE_DIRECTIVE: &str = "disable";
+const MAX_REPORTED_BUNDLED_FILES: usize = 10;
+
+// the rules disabled for a whole file by a directive
+#[derive(Debug, Default)]
+struct DisabledRules {
+    all_rules: bool,
+    rule_names: HashSet<String>,
+}
+
+impl DisabledRules {
+    fn contains(&self, rule_name: &str) -> bool {
+        self.all_rules || self.rule_names.contains(rule_name)
+    }
+}
+
 struct InstanceAliasCollector(std::collections::HashSet<String>);
 
 impl InstanceAliasCollector {
@@ -115,6 +143,7 @@ pub(crate) struct Worker<'a> {
     configuration: Configuration,
     cached_bundler: Option<Bundler>,
     shared_registry: std::rc::Rc<std::cell::RefCell<SourceRegistry>>,
+    capture_output: bool,
 }
 
 impl<'a> Worker<'a> {
@@ -125,12 +154,19 @@ impl<'a> Worker<'a> {
             configuration: Configuration::default(),
             cached_bundler: None,
             shared_registry: std::rc::Rc::new(std::cell::RefCell::new(SourceRegistry::new())),
+            capture_output: false,
         }
     }
 
     pub(crate) fn setup_worker(&mut self, options: &mut Options) -> DarkluaResult<()> {
         let configuration_setup_timer = Timer::now();
 
+        self.capture_output = options.should_capture_output();
+
+        for name in options.extra_rules().keys() {
+            verify_rule_name(name).map_err(|err| DarkluaError::custom(err.to_string()))?;
+        }
+
         if let Some(config) = options.take_configuration() {
             self.configuration = config;
             if let Some(config_path) = options.configuration_path() {
@@ -146,7 +182,7 @@ impl<'a> Worker<'a> {
             }
         } else if let Some(config) = options.configuration_path() {
             if self.resources.exists(config)? {
-                self.configuration = self.read_configuration(config)?;
+                self.configuration = self.read_configuration(config, options)?;
                 log::info!("using configuration file `{}`", config.display());
             } else {
                 return Err(DarkluaError::resource_not_found(config)
@@ -166,7 +202,8 @@ impl<'a> Worker<'a> {
                 }
                 1 => {
                     let configuration_file_path = configuration_files.first().unwrap();
-                    self.configuration = self.read_configuration(configuration_file_path)?;
+                    self.configuration =
+                        self.read_configuration(configuration_file_path, options)?;
                     log::info!(
                         "using configuration file `{}`",
                         configuration_file_path.display()
@@ -184,7 +221,8 @@ impl<'a> Worker<'a> {
             log::trace!(
                 "override with {} generator",
                 match generator {
-                    GeneratorParameters::RetainLines => "`retain_lines`".to_owned(),
+                    GeneratorParameters::RetainLines
+                    | GeneratorParameters::RetainLinesWithOptions(_) => "`retain_lines`".to_owned(),
                     GeneratorParameters::RetainLinesCompact { max_empty_lines } =>
                         format!("retain_lines_compact (max_empty_lines={})", max_empty_lines),
                     GeneratorParameters::Dense { column_span } =>
@@ -207,6 +245,10 @@ impl<'a> Worker<'a> {
             })
         );
 
+        if self.configuration.is_reproducible() {
+            self.configuration.verify_reproducible()?;
+        }
+
         // Apply global evaluator behavior based on configuration
         set_instance_indexing_is_pure(self.configuration.instance_indexing_is_pure());
 
@@ -234,15 +276,17 @@ impl<'a> Worker<'a> {
                 let mut block = {
                     // If sourcemaps are enabled for bundling, parse the entry file with the
                     // shared registry source_id so that sourcemap indices align.
-                    let sourcemap_requested = self
-                        .configuration
-                        .bundle_config()
-                        .and_then(|bundle| bundle.sourcemap())
-                        .map_or(false, |sm| sm.enabled);
-                    let use_shared_registry = sourcemap_requested && self.configuration.is_retain_lines();
+                    let use_shared_registry = self.configuration.has_retain_lines_sourcemap();
 
                     if use_shared_registry {
-                        let source_id = self.shared_registry.borrow_mut().intern(work_item.source());
+                        let module_key = self
+                            .configuration
+                            .case_sensitive_paths()
+                            .path_key(&self.resources.canonicalize(work_item.source()));
+                        let source_id = self
+                            .shared_registry
+                            .borrow_mut()
+                            .intern_canonical(work_item.source(), module_key);
                         parser
                             .parse_with_source_id(source_id, &content)
                             .map_err(|parser_error| {
@@ -261,17 +305,31 @@ impl<'a> Worker<'a> {
                 log::debug!("parsed `{}` in {}", source_display, parser_time);
 
                 // If configured, precompute aliases to instance paths for this block
-                if self.configuration.instance_indexing_is_pure() {
-                    let mut collector = InstanceAliasCollector::new();
-                    DefaultVisitor::visit_block(&mut block, &mut collector);
-                    set_known_instance_aliases(collector.into_set());
-                } else {
-                    clear_known_instance_aliases();
-                }
+                self.update_known_instance_aliases(&mut block);
+
+                // every rule applied to the file draws its generated names from the same
+                // identifiers, so that they never collide with each other
+                let generated_identifiers = SharedGeneratedIdentifiers::default();
 
-                self.bundle(work_item, &mut block, &content)?;
+                self.apply_pre_bundle_rules(
+                    work_item,
+                    &mut block,
+                    &content,
+                    &generated_identifiers,
+                )?;
 
-                work_item.status = WorkProgress::new(content, block).into();
+                self.bundle(work_item, &mut block, &content, &generated_identifiers)?;
+
+                // bundled modules declare their own aliases inside the module functions
+                self.update_known_instance_aliases(&mut block);
+
+                let work_progress = WorkProgress::new(content, block, generated_identifiers.take());
+                work_item.status = if self.configuration.profile_groups().len() > 1 {
+                    work_progress.keep_initial_block()
+                } else {
+                    work_progress
+                }
+                .into();
 
                 self.apply_rules(work_item)
             }
@@ -280,9 +338,13 @@ impl<'a> Worker<'a> {
         }
     }
 
-    fn read_configuration(&self, config: &Path) -> DarkluaResult<Configuration> {
+    fn read_configuration(
+        &self,
+        config: &Path,
+        options: &Options,
+    ) -> DarkluaResult<Configuration> {
         let config_content = self.resources.get(config)?;
-        json5::from_str(&config_content)
+        with_scoped_rules(options.extra_rules(), || json5::from_str(&config_content))
             .map_err(|err| {
                 DarkluaError::invalid_configuration_file(config).context(err.to_string())
             })
@@ -300,11 +362,65 @@ impl<'a> Worker<'a> {
     }
 
     fn apply_rules(&mut self, work_item: &mut WorkItem) -> DarkluaResult<()> {
-        let work_progress = match &mut work_item.status {
-            WorkStatus::InProgress(progress) => progress.as_mut(),
-            _ => return Ok(()),
+        let mut work_progress = match mem::take(&mut work_item.status) {
+            WorkStatus::InProgress(progress) => progress,
+            status => {
+                work_item.status = status;
+                return Ok(());
+            }
         };
 
+        let profile_groups = self.configuration.profile_groups();
+
+        loop {
+            let group = &profile_groups[work_progress.profile_group()];
+
+            let generated_identifiers = SharedGeneratedIdentifiers::new(
+                mem::take(&mut work_progress.generated_identifiers).into(),
+            );
+            let applied = self.apply_profile_rules(
+                work_item,
+                &mut work_progress,
+                group,
+                &generated_identifiers,
+            );
+            work_progress.generated_identifiers = generated_identifiers.take();
+
+            if !applied? {
+                work_item.status = WorkStatus::InProgress(work_progress);
+                return Ok(());
+            }
+
+            for output in group.outputs() {
+                self.generate_output(work_item, &work_progress, group, output)?;
+            }
+
+            if work_progress.profile_group() + 1 < profile_groups.len() {
+                work_progress.start_next_profile_group();
+            } else {
+                break;
+            }
+        }
+
+        if let Some(output) = work_item.outputs.first() {
+            self.cache
+                .link_source_to_output(normalize_path(work_item.data.source()), output);
+        }
+
+        work_item.status = WorkStatus::done();
+        Ok(())
+    }
+
+    /// Applies the rules of a group of profiles. Returns false when the work has to wait
+    /// for the content required by a rule.
+    fn apply_profile_rules(
+        &self,
+        work_item: &mut WorkItem,
+        work_progress: &mut WorkProgress,
+        group: &ProfileGroup,
+        generated_identifiers: &SharedGeneratedIdentifiers,
+    ) -> DarkluaResult<bool> {
+        let is_first_profile_group = work_progress.profile_group() == 0;
         let progress = &mut work_progress.progress;
 
         let source_display = work_item.data.source().display();
@@ -312,14 +428,30 @@ impl<'a> Worker<'a> {
 
         progress.duration().start();
 
-        for (index, rule) in self
-            .configuration
-            .rules()
-            .enumerate()
-            .skip(progress.next_rule())
-        {
-            let mut context_builder =
-                self.create_rule_context(work_item.data.source(), &work_progress.content);
+        let disabled_rules = self.find_disabled_rules(
+            work_item.data.source(),
+            &work_progress.content,
+            // the directives are only reported the first time the rules are applied, since
+            // the work can resume after the content it requires is processed
+            (is_first_profile_group && progress.next_rule() == 0)
+                .then_some(&mut work_item.warnings),
+        );
+
+        for (index, rule) in group.rules().enumerate().skip(progress.next_rule()) {
+            if disabled_rules.contains(rule.get_name()) {
+                log::trace!(
+                    "[{}] skip rule `{}` disabled by a directive",
+                    source_display,
+                    rule.get_name()
+                );
+                continue;
+            }
+
+            let mut context_builder = self.create_rule_context(
+                work_item.data.source(),
+                &work_progress.content,
+                generated_identifiers,
+            );
             log::trace!(
                 "[{}] apply rule `{}`{}",
                 source_display,
@@ -379,7 +511,7 @@ impl<'a> Worker<'a> {
 
                     progress.set_next_rule(index);
                     progress.set_required_content(required_content);
-                    return Ok(());
+                    return Ok(false);
                 }
             }
 
@@ -388,16 +520,13 @@ impl<'a> Worker<'a> {
             let rule_timer = Timer::now();
 
             // Recompute instance aliases prior to running each rule to reflect any changes
-            if self.configuration.instance_indexing_is_pure() {
-                let mut collector = InstanceAliasCollector::new();
-                DefaultVisitor::visit_block(block, &mut collector);
-                set_known_instance_aliases(collector.into_set());
-            }
+            self.update_known_instance_aliases(block);
 
             let source = work_item.data.source();
 
             let rule_result = rule.process(block, &context).map_err(|rule_error| {
-                let error = DarkluaError::rule_error(source, rule, index, rule_error);
+                let error = DarkluaError::rule_error(source, rule, index, rule_error)
+                    .with_causes(context.take_error_causes());
 
                 log::trace!(
                     "[{}] rule `{}` errored: {}",
@@ -409,6 +538,10 @@ impl<'a> Worker<'a> {
                 error
             });
 
+            work_item.warnings.extend(context.warnings());
+            work_item
+                .detected_require_modes
+                .extend(context.detected_require_modes());
             work_item
                 .external_file_dependencies
                 .extend(context.into_dependencies());
@@ -424,19 +557,77 @@ impl<'a> Worker<'a> {
             );
         }
 
-        // Final cleanup pass to remove variables that became unused after prior rules
-        if self.configuration.instance_indexing_is_pure() {
-            let cleanup_context = self
-                .create_rule_context(work_item.data.source(), &work_progress.content)
+        // Final cleanup pass, like removing variables that became unused after prior rules
+        let default_post_rules = self.default_post_rules();
+        let post_rules: Vec<&dyn Rule> = match self.configuration.post_rules() {
+            Some(post_rules) => post_rules.collect(),
+            None => default_post_rules.iter().map(AsRef::as_ref).collect(),
+        };
+
+        if !post_rules.is_empty() {
+            for rule in post_rules {
+                if disabled_rules.contains(rule.get_name()) {
+                    continue;
+                }
+
+                log::trace!("[{}] apply post rule `{}`", source_display, rule.get_name());
+
+                self.update_known_instance_aliases(progress.mutate_block());
+
+                let context = self
+                    .create_rule_context(
+                        work_item.data.source(),
+                        &work_progress.content,
+                        generated_identifiers,
+                    )
+                    .build();
+
+                let source = work_item.data.source();
+
+                let rule_result =
+                    rule.process(progress.mutate_block(), &context)
+                        .map_err(|rule_error| {
+                            DarkluaError::orphan_rule_error(source, rule, rule_error)
+                                .with_causes(context.take_error_causes())
+                        });
+
+                work_item.warnings.extend(context.warnings());
+                work_item
+                    .detected_require_modes
+                    .extend(context.detected_require_modes());
+                work_item
+                    .external_file_dependencies
+                    .extend(context.into_dependencies());
+
+                rule_result?;
+            }
+
+            let context = self
+                .create_rule_context(
+                    work_item.data.source(),
+                    &work_progress.content,
+                    generated_identifiers,
+                )
                 .build();
-            let cleanup_rule = crate::rules::RemoveUnusedVariable::default();
-            cleanup_rule.flawless_process(progress.mutate_block(), &cleanup_context);
-            ReplaceReferencedTokens::default()
-                .flawless_process(progress.mutate_block(), &cleanup_context);
+            ReplaceReferencedTokens::default().flawless_process(progress.mutate_block(), &context);
+        }
+
+        self.promote_warnings(work_item.data.source(), &mut work_item.warnings)?;
+
+        if let Some(target) = self.configuration.target() {
+            let incompatibilities = target.find_incompatibilities(progress.mutate_block());
+
+            if !incompatibilities.is_empty() {
+                return Err(DarkluaError::target_incompatibility(
+                    work_item.data.source(),
+                    target,
+                    incompatibilities,
+                ));
+            }
         }
 
         let rule_time = progress.duration().duration_label();
-        let total_rules = self.configuration.rules_len();
+        let total_rules = group.rules_len();
         log::debug!(
             "{} rule{} applied in {} for `{}`",
             total_rules,
@@ -445,27 +636,40 @@ impl<'a> Worker<'a> {
             source_display,
         );
 
+        Ok(true)
+    }
+
+    fn generate_output(
+        &self,
+        work_item: &mut WorkItem,
+        work_progress: &WorkProgress,
+        group: &ProfileGroup,
+        output: &OutputProfile,
+    ) -> DarkluaResult<()> {
+        let progress = &work_progress.progress;
+        let source = work_item.data.source().to_path_buf();
+        let source_display = source.display();
+        let output_path = output.output_path(work_item.data.output());
+
         log::trace!("begin generating code for `{}`", source_display);
 
-        if cfg!(test) || (cfg!(debug_assertions) && log::log_enabled!(log::Level::Trace)) {
-            log::trace!(
-                "generate AST debugging view at `{}`",
-                work_item.data.output().display()
-            );
-            self.resources
-                .write(work_item.data.output(), &format!("{:#?}", progress.block()))?;
+        if !self.capture_output
+            && (cfg!(test) || (cfg!(debug_assertions) && log::log_enabled!(log::Level::Trace)))
+        {
+            log::trace!("generate AST debugging view at `{}`", output_path.display());
+            self.write_artifact(
+                work_item,
+                &output_path,
+                &format!("{:#?}", progress.block()),
+                ArtifactKind::Debug,
+            )?;
         }
 
         let generator_timer = Timer::now();
 
-        let lua_code = if self.configuration.is_retain_lines() {
+        let lua_code = if output.generator().is_retain_lines() {
             log::trace!("Retain lines mode enabled for `{}`", source_display);
-            match self
-                .configuration
-                .bundle_config()
-                .and_then(|bundle| bundle.sourcemap())
-                .filter(|sm| sm.enabled)
-            {
+            match output.enabled_sourcemap() {
                 Some(sm) => {
                     log::trace!(
                         "Sourcemap generation requested for `{}` (output path: {:?})",
@@ -503,6 +707,8 @@ impl<'a> Worker<'a> {
                     // Pre-register all known source paths so the sourcemap `sources` field
                     // contains every file that participated in the bundle, even if some
                     // do not end up with explicit mappings on certain lines.
+                    let mut registered_sources = Vec::new();
+                    let mut registered_paths = Vec::new();
                     {
                         use std::path::PathBuf;
                         let paths: Vec<PathBuf> = if let Some(bundler) = self.cached_bundler.as_ref() {
@@ -521,17 +727,14 @@ impl<'a> Worker<'a> {
 
                         for p in paths {
                             // Normalize and relativize similar to how MappingRecorder does
-                            let p_norm = crate::utils::normalize_path_with_current_dir(&p);
-                            let src_name = if let Some(base) = &relative_base {
-                                let base_norm = crate::utils::normalize_path_with_current_dir(base);
-                                match p_norm.strip_prefix(&base_norm) {
-                                    Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
-                                    Err(_) => p_norm.to_string_lossy().replace('\\', "/"),
-                                }
-                            } else {
-                                p_norm.to_string_lossy().replace('\\', "/")
-                            };
+                            let src_name = sourcemap_path_name(
+                                &p,
+                                relative_base.as_deref(),
+                                self.configuration.is_reproducible(),
+                            )?;
                             builder.add_source(&src_name);
+                            registered_sources.push(src_name);
+                            registered_paths.push(p);
                         }
                     }
 
@@ -541,24 +744,18 @@ impl<'a> Worker<'a> {
                         if let Some(override_file) = sm.file.as_ref() {
                             builder.set_file(Some(override_file.as_str()));
                         } else {
-                            let out_path_norm = crate::utils::normalize_path_with_current_dir(work_item.data.output());
-                            let file_path = if let Some(base) = relative_base.as_ref() {
-                                let base = crate::utils::normalize_path_with_current_dir(base);
-                                match out_path_norm.strip_prefix(&base) {
-                                    Ok(rel) => rel.to_path_buf(),
-                                    Err(_) => out_path_norm.clone(),
-                                }
-                            } else {
-                                out_path_norm.clone()
-                            };
-                            let file_string = file_path.to_string_lossy().replace('\\', "/");
+                            let file_string = sourcemap_path_name(
+                                &output_path,
+                                relative_base.as_deref(),
+                                self.configuration.is_reproducible(),
+                            )?;
                             builder.set_file(Some(file_string.as_str()));
                         }
                     }
 
 
                     // Choose retain-lines variant with sourcemap support
-                    let (code, map_opt) = if let Some(max_empty) = self.configuration.retain_lines_compact_max_empty() {
+                    let (code, map_opt) = if let Some(max_empty) = output.generator().retain_lines_compact_max_empty() {
                         let mut gen = crate::generator::RetainLinesCompactLuaGenerator::new(&work_progress.content, max_empty)
                             .with_sourcemap(builder, self.shared_registry.clone(), relative_base.clone());
                         gen.write_block(progress.block());
@@ -566,10 +763,41 @@ impl<'a> Worker<'a> {
                     } else {
                         let mut gen = crate::generator::TokenBasedLuaGenerator::new(&work_progress.content)
                             .with_sourcemap(builder, self.shared_registry.clone(), relative_base.clone());
+                        if let Some(options) = output.generator().retain_lines_options() {
+                            gen = options.apply(gen);
+                        }
                         gen.write_block(progress.block());
                         gen.into_string_and_sourcemap()
                     };
 
+                    if let Some(map) = map_opt.as_ref() {
+                        work_item.line_mappings.extend(collect_line_mappings(
+                            map,
+                            &output_path,
+                            &registered_sources,
+                            &registered_paths,
+                        ));
+                        let mappings = count_source_mappings(map, registered_sources);
+                        for source in find_unmapped_sources(&mappings) {
+                            log::warn!(
+                                "bundled source `{}` has no mapping in the sourcemap of `{}`",
+                                source,
+                                source_display
+                            );
+                            work_item.warnings.push(
+                                RuleWarning::new(
+                                    WarningCode::UnmappedSource,
+                                    format!(
+                                        "bundled source `{}` has no mapping in the generated sourcemap",
+                                        source
+                                    ),
+                                )
+                                .with_path(work_item.data.source()),
+                            );
+                        }
+                        work_item.sourcemap_mappings = mappings;
+                    }
+
                     if let (Some(map), Some(path)) = (map_opt, sm.output_path.as_ref()) {
                         let mut out = Vec::new();
                         match map.to_writer(&mut out) {
@@ -588,7 +816,21 @@ impl<'a> Worker<'a> {
                                         path.clone()
                                     };
 
-                                    match self.resources.write(&target_path, &json) {
+                                    let write_result = if self.capture_output {
+                                        work_item
+                                            .generated
+                                            .push((normalize_path(&target_path), json));
+                                        Ok(())
+                                    } else {
+                                        self.write_artifact(
+                                            work_item,
+                                            &target_path,
+                                            &json,
+                                            ArtifactKind::Sourcemap,
+                                        )
+                                    };
+
+                                    match write_result {
                                         Ok(_) => {
                                             let abs_for_log = target_path
                                                 .canonicalize()
@@ -637,18 +879,14 @@ impl<'a> Worker<'a> {
                         "Sourcemap generation NOT requested for `{}`: either no bundle config, no sourcemap config, or sourcemap not enabled",
                         source_display
                     );
-                    self.configuration.generate_lua(progress.block(), &work_progress.content)
+                    output
+                        .generator()
+                        .generate_lua(progress.block(), &work_progress.content)
                 }
             }
         } else {
             // Warn if sourcemaps were requested but the generator mode is incompatible
-            let sourcemap_requested = self
-                .configuration
-                .bundle_config()
-                .and_then(|bundle| bundle.sourcemap())
-                .map_or(false, |sm| sm.enabled);
-
-            if sourcemap_requested {
+            if output.enabled_sourcemap().is_some() {
                 log::warn!(
                     "sourcemap generation requested for `{}` but current generator mode does not support sourcemaps; enable the `retain_lines` or `retain_lines_compact` generator to produce sourcemaps",
                     source_display
@@ -660,7 +898,9 @@ impl<'a> Worker<'a> {
                 );
             }
 
-            self.configuration.generate_lua(progress.block(), &work_progress.content)
+            output
+                .generator()
+                .generate_lua(progress.block(), &work_progress.content)
         };
 
         let generator_time = generator_timer.duration_label();
@@ -670,21 +910,299 @@ impl<'a> Worker<'a> {
             generator_time,
         );
 
-        self.resources.write(work_item.data.output(), &lua_code)?;
+        self.verify_output_size(work_item, &output_path, &lua_code)?;
 
-        self.cache
-            .link_source_to_output(normalized_source, work_item.data.output());
+        // generating the sourcemap can emit warnings
+        self.promote_warnings(work_item.data.source(), &mut work_item.warnings)?;
+
+        if self.configuration.is_validate_output() {
+            self.validate_output(work_item, &output_path, &lua_code, group)?;
+        }
+
+        if self.capture_output {
+            work_item
+                .generated
+                .push((normalize_path(&output_path), lua_code));
+        } else {
+            self.write_artifact(work_item, &output_path, &lua_code, ArtifactKind::Output)?;
+        }
+
+        if !work_item.outputs.contains(&output_path) {
+            work_item.outputs.push(output_path);
+        }
+
+        Ok(())
+    }
+
+    fn write_artifact(
+        &self,
+        work_item: &mut WorkItem,
+        path: &Path,
+        content: &str,
+        kind: ArtifactKind,
+    ) -> DarkluaResult<()> {
+        self.resources.write(path, content)?;
+        work_item.push_artifact(path, content.len(), kind);
+        Ok(())
+    }
+
+    fn verify_output_size(
+        &self,
+        work_item: &mut WorkItem,
+        output_path: &Path,
+        lua_code: &str,
+    ) -> DarkluaResult<()> {
+        let bundle_config = match self.configuration.bundle_config() {
+            Some(bundle_config) => bundle_config,
+            None => return Ok(()),
+        };
+        let size = lua_code.len();
+
+        if let Some(max_size) = bundle_config.max_output_size().filter(|max| size > *max) {
+            return Err(DarkluaError::output_size_exceeded(
+                output_path,
+                size,
+                max_size,
+                self.largest_bundled_files(work_item),
+            ));
+        }
+
+        if let Some(warn_size) = bundle_config.warn_output_size().filter(|warn| size > *warn) {
+            work_item.warnings.push(
+                RuleWarning::new(
+                    WarningCode::OutputSize,
+                    format!(
+                        "generated code of `{}` is {} bytes, which exceeds the warning size of {} bytes",
+                        output_path.display(),
+                        size,
+                        warn_size
+                    ),
+                )
+                .with_path(work_item.data.source()),
+            );
+        }
+
+        Ok(())
+    }
+
+    // the path, size in bytes and number of lines of the largest files of a bundle
+    fn largest_bundled_files(&self, work_item: &WorkItem) -> Vec<(PathBuf, usize, usize)> {
+        let mut files: Vec<_> = iter::once(work_item.data.source())
+            .chain(work_item.bundled_files.iter().map(PathBuf::as_path))
+            .collect::<IndexSet<_>>()
+            .into_iter()
+            .filter_map(|path| {
+                let content = self.resources.get_bytes(path).ok()?;
+                let lines = content.split(|byte| *byte == b'\n').count();
+                Some((path.to_path_buf(), content.len(), lines))
+            })
+            .collect();
+
+        files.sort_by(|(path_a, bytes_a, _), (path_b, bytes_b, _)| {
+            bytes_b.cmp(bytes_a).then_with(|| path_a.cmp(path_b))
+        });
+        files.truncate(MAX_REPORTED_BUNDLED_FILES);
+        files
+    }
+
+    fn write_dependency_graph(
+        &self,
+        work_item: &mut WorkItem,
+        graph: &DependencyGraph,
+    ) -> DarkluaResult<()> {
+        let path = match self
+            .configuration
+            .bundle_config()
+            .and_then(BundleConfiguration::graph_output)
+        {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let location = self.configuration.location();
+        let target_path = match location {
+            Some(base) if path.is_relative() => base.join(path),
+            _ => path.to_path_buf(),
+        };
+        let content = graph.to_dot(location);
+
+        log::debug!(
+            "write dependency graph of `{}` to `{}`",
+            work_item.source().display(),
+            target_path.display()
+        );
+
+        if self.capture_output {
+            work_item
+                .generated
+                .push((normalize_path(&target_path), content));
+            Ok(())
+        } else {
+            self.write_artifact(
+                work_item,
+                &target_path,
+                &content,
+                ArtifactKind::DependencyGraph,
+            )
+        }
+    }
+
+    /// Parses the generated code again to make sure the rules and the generator produced
+    /// valid code. When the code is invalid, it is written next to the output with an
+    /// `.invalid` extension so it can be inspected.
+    fn validate_output(
+        &self,
+        work_item: &mut WorkItem,
+        output_path: &Path,
+        lua_code: &str,
+        group: &ProfileGroup,
+    ) -> DarkluaResult<()> {
+        let validation_timer = Timer::now();
+
+        let result = self.configuration.build_parser().parse(lua_code);
+
+        log::debug!(
+            "validated generated code for `{}` in {}",
+            output_path.display(),
+            validation_timer.duration_label(),
+        );
+
+        if let Err(parser_error) = result {
+            let mut invalid_path = output_path.as_os_str().to_owned();
+            invalid_path.push(".invalid");
+            let invalid_path = PathBuf::from(invalid_path);
+
+            if self.capture_output {
+                work_item
+                    .generated
+                    .push((normalize_path(&invalid_path), lua_code.to_owned()));
+            } else {
+                self.write_artifact(work_item, &invalid_path, lua_code, ArtifactKind::Debug)?;
+            }
+
+            let default_post_rules = self.default_post_rules();
+            let post_rules: Vec<&dyn Rule> = match self.configuration.post_rules() {
+                Some(post_rules) => post_rules.collect(),
+                None => default_post_rules.iter().map(AsRef::as_ref).collect(),
+            };
+
+            return Err(DarkluaError::invalid_output(
+                output_path,
+                group
+                    .rules()
+                    .chain(post_rules)
+                    .map(|rule| rule.get_name()),
+                parser_error,
+            ));
+        }
 
-        work_item.status = WorkStatus::done();
         Ok(())
     }
 
+    /// Finds the rules disabled for a whole file by a `-- darklua: disable <rules>` comment
+    /// written before its first statement and never closed by a `-- darklua: enable <rules>`
+    /// comment. Without rule names, the directive disables every rule. Unknown rule names
+    /// are reported for every directive, including the ones that disable rules on some lines.
+    fn find_disabled_rules(
+        &self,
+        source: &Path,
+        code: &str,
+        warnings: Option<&mut Vec<RuleWarning>>,
+    ) -> DisabledRules {
+        let mut disabled_rules = DisabledRules::default();
+        let disabled_lines = DisabledLines::from_code(code);
+
+        for (line, directive) in find_leading_comment_directives(code) {
+            let (name, _) = split_directive(directive);
+
+            if name != DISABLE_DIRECTIVE {
+                continue;
+            }
+
+            for rule_name in disabled_lines.iter_unclosed_regions(line) {
+                match rule_name {
+                    Some(rule_name) => {
+                        disabled_rules.rule_names.insert(rule_name.to_owned());
+                    }
+                    None => disabled_rules.all_rules = true,
+                }
+            }
+        }
+
+        if let Some(warnings) = warnings {
+            for (rule_name, line) in disabled_lines.iter_rule_names() {
+                self.verify_directive_rule_name(source, rule_name, line, Some(&mut *warnings));
+            }
+        }
+
+        disabled_rules
+    }
+
+    fn verify_directive_rule_name(
+        &self,
+        source: &Path,
+        rule_name: &str,
+        line: usize,
+        warnings: Option<&mut Vec<RuleWarning>>,
+    ) {
+        if self.is_known_rule_name(rule_name) {
+            return;
+        }
+
+        log::warn!(
+            "unknown rule `{}` in directive of `{}` at line {}",
+            rule_name,
+            source.display(),
+            line
+        );
+        if let Some(warnings) = warnings {
+            warnings.push(
+                RuleWarning::new(
+                    WarningCode::UnknownDirectiveRule,
+                    format!("unable to disable unknown rule `{}`", rule_name),
+                )
+                .with_path(source)
+                .with_line(line),
+            );
+        }
+    }
+
+    fn is_known_rule_name(&self, name: &str) -> bool {
+        get_all_rule_names().contains(&name)
+            || self
+                .configuration
+                .rules()
+                .chain(self.configuration.profile_rules())
+                .chain(self.configuration.pre_bundle_rules())
+                .chain(self.configuration.post_rules().into_iter().flatten())
+                .any(|rule| rule.get_name() == name)
+    }
+
+    fn promote_warnings(
+        &self,
+        source: &Path,
+        warnings: &mut Vec<RuleWarning>,
+    ) -> DarkluaResult<()> {
+        let (promoted_warnings, remaining_warnings): (Vec<_>, Vec<_>) = mem::take(warnings)
+            .into_iter()
+            .partition(|warning| self.configuration.is_warning_as_error(warning.code()));
+        *warnings = remaining_warnings;
+
+        if promoted_warnings.is_empty() {
+            Ok(())
+        } else {
+            Err(DarkluaError::warnings_as_errors(source, promoted_warnings))
+        }
+    }
+
     fn create_rule_context<'block, 'src>(
         &self,
         source: &Path,
         original_code: &'src str,
+        generated_identifiers: &SharedGeneratedIdentifiers,
     ) -> ContextBuilder<'block, 'a, 'src> {
-        let builder = ContextBuilder::new(normalize_path(source), self.resources, original_code);
+        let builder = ContextBuilder::new(normalize_path(source), self.resources, original_code)
+            .with_generated_identifiers(generated_identifiers.clone())
+            .with_pure_functions(self.configuration.pure_functions().to_vec());
         if let Some(project_location) = self.configuration.location() {
             builder.with_project_location(project_location)
         } else {
@@ -692,11 +1210,69 @@ impl<'a> Worker<'a> {
         }
     }
 
+    /// The rules applied after the configured rules when the configuration does not
+    /// define them.
+    fn default_post_rules(&self) -> Vec<Box<dyn Rule>> {
+        if self.configuration.instance_indexing_is_pure() {
+            vec![Box::<RemoveUnusedVariable>::default()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn update_known_instance_aliases(&self, block: &mut Block) {
+        if self.configuration.instance_indexing_is_pure() {
+            let mut collector = InstanceAliasCollector::new();
+            DefaultVisitor::visit_block(block, &mut collector);
+            set_known_instance_aliases(collector.into_set());
+        } else {
+            clear_known_instance_aliases();
+        }
+    }
+
+    fn apply_pre_bundle_rules(
+        &self,
+        work_item: &mut WorkItem,
+        block: &mut Block,
+        original_code: &str,
+        generated_identifiers: &SharedGeneratedIdentifiers,
+    ) -> DarkluaResult<()> {
+        for rule in self.configuration.pre_bundle_rules() {
+            log::trace!(
+                "[{}] apply pre-bundle rule `{}`",
+                work_item.source().display(),
+                rule.get_name()
+            );
+
+            let context = self
+                .create_rule_context(work_item.source(), original_code, generated_identifiers)
+                .build();
+
+            let rule_result = rule.process(block, &context).map_err(|rule_error| {
+                DarkluaError::orphan_rule_error(work_item.source(), rule, rule_error)
+                    .with_causes(context.take_error_causes())
+            });
+
+            work_item.warnings.extend(context.warnings());
+            work_item
+                .detected_require_modes
+                .extend(context.detected_require_modes());
+            work_item
+                .external_file_dependencies
+                .extend(context.into_dependencies());
+
+            rule_result?;
+        }
+
+        Ok(())
+    }
+
     fn bundle(
         &mut self,
         work_item: &mut WorkItem,
         block: &mut Block,
         original_code: &str,
+        generated_identifiers: &SharedGeneratedIdentifiers,
     ) -> DarkluaResult<()> {
         if self.cached_bundler.is_none() {
             if let Some(bundler) = self.configuration.bundle() {
@@ -714,11 +1290,12 @@ impl<'a> Worker<'a> {
         let bundle_timer = Timer::now();
 
         let context = self
-            .create_rule_context(work_item.source(), original_code)
+            .create_rule_context(work_item.source(), original_code, generated_identifiers)
             .build();
 
         let rule_result = bundler.process(block, &context).map_err(|rule_error| {
-            let error = DarkluaError::orphan_rule_error(work_item.source(), bundler, rule_error);
+            let error = DarkluaError::orphan_rule_error(work_item.source(), bundler, rule_error)
+                .with_causes(context.take_error_causes());
 
             log::trace!(
                 "[{}] rule `{}` errored: {}",
@@ -730,11 +1307,29 @@ impl<'a> Worker<'a> {
             error
         });
 
+        work_item.warnings.extend(context.warnings());
+        work_item
+            .detected_require_modes
+            .extend(context.detected_require_modes());
+        let dependencies: Vec<_> = context.into_dependencies().collect();
         work_item
-            .external_file_dependencies
-            .extend(context.into_dependencies());
+            .bundled_files
+            .extend(dependencies.iter().map(normalize_path));
+        work_item.external_file_dependencies.extend(dependencies);
+
+        // the graph is also written when bundling fails, to help find cyclic requires
+        let graph_result = match bundler.options().take_dependency_graph() {
+            Some(mut graph) => {
+                if self.configuration.is_reproducible() {
+                    graph.sort();
+                }
+                self.write_dependency_graph(work_item, &graph)
+            }
+            None => Ok(()),
+        };
 
         rule_result?;
+        graph_result?;
 
         let bundle_time = bundle_timer.duration_label();
         log::debug!(
@@ -746,3 +1341,166 @@ impl<'a> Worker<'a> {
         Ok(())
     }
 }
+
+impl Drop for Worker<'_> {
+    fn drop(&mut self) {
+        // the evaluator state configured by the worker must not leak after processing
+        reset_instance_indexing();
+    }
+}
+
+// Writes a path of a sourcemap relative to the given base when possible. In reproducible
+// mode, a path that cannot be made relative is an error, since it depends on the location
+// of the project on the machine.
+fn sourcemap_path_name(
+    path: &Path,
+    relative_base: Option<&Path>,
+    reproducible: bool,
+) -> DarkluaResult<String> {
+    let path = crate::utils::normalize_path_with_current_dir(path);
+    let relative_path = relative_base
+        .and_then(|base| {
+            path.strip_prefix(crate::utils::normalize_path_with_current_dir(base))
+                .ok()
+        })
+        .unwrap_or(&path);
+
+    if reproducible && relative_path.has_root() {
+        return Err(DarkluaError::custom(format!(
+            "unable to write the absolute path `{}` in a sourcemap in reproducible mode",
+            relative_path.display(),
+        ))
+        .context("set the `relative_to` field of the sourcemap to a directory containing every bundled file"));
+    }
+
+    Ok(relative_path.to_string_lossy().replace('\\', "/"))
+}
+
+/// Counts the mappings of the sourcemap that point to each of the given sources.
+fn count_source_mappings(map: &sourcemap::SourceMap, sources: Vec<String>) -> Vec<(String, usize)> {
+    let mut counts = vec![0; map.get_source_count() as usize];
+    for token in map.tokens() {
+        if let Some(count) = counts.get_mut(token.get_src_id() as usize) {
+            *count += 1;
+        }
+    }
+
+    sources
+        .into_iter()
+        .map(|source| {
+            let count = map
+                .sources()
+                .position(|name| name == source)
+                .and_then(|index| counts.get(index).copied())
+                .unwrap_or(0);
+            (source, count)
+        })
+        .collect()
+}
+
+fn collect_line_mappings(
+    map: &sourcemap::SourceMap,
+    output: &Path,
+    sources: &[String],
+    paths: &[std::path::PathBuf],
+) -> Vec<LineMappingSegment> {
+    let mut segments: Vec<_> = map
+        .tokens()
+        .filter_map(|token| {
+            let index = sources
+                .iter()
+                .position(|source| Some(source.as_str()) == token.get_source())?;
+            Some(LineMappingSegment {
+                output: output.to_path_buf(),
+                generated_line: token.get_dst_line() as usize + 1,
+                source: paths.get(index)?.clone(),
+                original_line: token.get_src_line() as usize + 1,
+            })
+        })
+        .collect();
+
+    segments.sort_by_key(|segment| segment.generated_line);
+    segments
+}
+
+/// Returns the sources without any mapping, except data files (like json or toml files)
+/// which are bundled as Lua values and lose their mapping when they share a generated
+/// line with a Lua source.
+fn find_unmapped_sources(mappings: &[(String, usize)]) -> impl Iterator<Item = &str> {
+    mappings
+        .iter()
+        .filter(|(source, count)| *count == 0 && !is_data_source(source))
+        .map(|(source, _)| source.as_str())
+}
+
+fn is_data_source(source: &str) -> bool {
+    Path::new(source)
+        .extension()
+        .map(|extension| {
+            !matches!(
+                extension.to_string_lossy().to_ascii_lowercase().as_str(),
+                "lua" | "luau"
+            )
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use sourcemap::SourceMapBuilder;
+
+    use super::*;
+
+    fn build_sourcemap(sources: &[&str], mapped_sources: &[&str]) -> sourcemap::SourceMap {
+        let mut builder = SourceMapBuilder::new(None);
+        for source in sources {
+            builder.add_source(source);
+        }
+        for (line, source) in mapped_sources.iter().enumerate() {
+            builder.add(line as u32, 0, 0, 0, Some(source), None, false);
+        }
+        builder.into_sourcemap()
+    }
+
+    fn sources(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn count_mappings_of_each_source() {
+        let map = build_sourcemap(
+            &["src/main.lua", "src/value.lua"],
+            &["src/main.lua", "src/value.lua", "src/main.lua"],
+        );
+
+        pretty_assertions::assert_eq!(
+            count_source_mappings(&map, sources(&["src/main.lua", "src/value.lua"])),
+            vec![
+                ("src/main.lua".to_owned(), 2),
+                ("src/value.lua".to_owned(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_module_is_unmapped() {
+        let map = build_sourcemap(&["src/main.lua", "src/empty.lua"], &["src/main.lua"]);
+        let mappings = count_source_mappings(&map, sources(&["src/main.lua", "src/empty.lua"]));
+
+        pretty_assertions::assert_eq!(
+            find_unmapped_sources(&mappings).collect::<Vec<_>>(),
+            vec!["src/empty.lua"]
+        );
+    }
+
+    #[test]
+    fn data_module_without_mappings_is_not_unmapped() {
+        let map = build_sourcemap(&["src/main.lua", "src/data.json"], &["src/main.lua"]);
+        let mappings = count_source_mappings(&map, sources(&["src/main.lua", "src/data.json"]));
+
+        pretty_assertions::assert_eq!(
+            find_unmapped_sources(&mappings).collect::<Vec<_>>(),
+            Vec::<&str>::new()
+        );
+    }
+}
@@ -1,22 +1,34 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
 use petgraph::{algo::toposort, graph::NodeIndex, stable_graph::StableDiGraph, visit::Dfs};
+use wax::Pattern;
 use xxhash_rust::xxh3::xxh3_64;
 
 use crate::{
     frontend::utils::maybe_plural,
+    rules::{DetectedRequireMode, RuleWarning, WarningCode},
     utils::{clear_luau_configuration_cache, Timer},
     DarkluaError,
 };
 
 use super::{
-    normalize_path, work_item::WorkStatus, Configuration, DarkluaResult, Options, Resources,
-    WorkItem, Worker,
+    ignore_rules::IgnoreRules,
+    normalize_path,
+    work_item::{ArtifactKind, WorkStatus},
+    Configuration, DarkluaResult, Options, Resources, WorkItem, Worker,
 };
 
+// the work is sorted so that the order of the work items (and therefore the source ids of
+// sourcemaps and the names of bundled modules) does not depend on the file system
+fn collect_sorted_work(resources: &Resources, input: &Path) -> Vec<PathBuf> {
+    let mut sources: Vec<_> = resources.collect_work(input).map(normalize_path).collect();
+    sources.sort();
+    sources
+}
+
 /// A structure that manages the processing of Lua/Luau files and their dependencies.
 ///
 /// Under the hood, the `WorkerTree` maintains a directed graph of work items, where each node
@@ -30,6 +42,13 @@ pub struct WorkerTree {
     external_dependencies: HashMap<PathBuf, HashSet<NodeIndex>>,
     remove_files: Vec<PathBuf>,
     last_configuration_hash: Option<u64>,
+    assets: HashMap<PathBuf, Asset>,
+}
+
+#[derive(Debug)]
+struct Asset {
+    relative_path: PathBuf,
+    output: PathBuf,
 }
 
 impl WorkerTree {
@@ -52,7 +71,10 @@ impl WorkerTree {
                         ))
                     })?;
 
-                    self.add_source_if_missing(options.input(), Some(output.join(file_name)));
+                    self.add_source_if_missing(
+                        options.input(),
+                        Some(options.rewrite_output_extension(output.join(file_name))),
+                    );
                 } else if resources.is_file(&output)? || output.extension().is_some() {
                     self.add_source_if_missing(options.input(), Some(output));
                 } else {
@@ -63,14 +85,15 @@ impl WorkerTree {
                         ))
                     })?;
 
-                    self.add_source_if_missing(options.input(), Some(output.join(file_name)));
+                    self.add_source_if_missing(
+                        options.input(),
+                        Some(options.rewrite_output_extension(output.join(file_name))),
+                    );
                 }
             } else {
                 let input = options.input().to_path_buf();
 
-                for source in resources.collect_work(&input) {
-                    let source = normalize_path(source);
-
+                for source in collect_sorted_work(resources, &input) {
                     let relative_path = source.strip_prefix(&input).map_err(|err| {
                         DarkluaError::custom(format!(
                             "unable to remove path prefix `{}` from `{}`: {}",
@@ -80,14 +103,27 @@ impl WorkerTree {
                         ))
                     })?;
 
-                    let output_path = Some(output.join(relative_path));
+                    let output_path =
+                        Some(options.rewrite_output_extension(output.join(relative_path)));
                     self.add_source_if_missing(source, output_path);
                 }
+
+                for source in resources.collect_assets(&input) {
+                    let source = normalize_path(source);
+
+                    if let Ok(relative_path) = source.strip_prefix(&input) {
+                        let asset = Asset {
+                            relative_path: relative_path.to_path_buf(),
+                            output: output.join(relative_path),
+                        };
+                        self.assets.insert(source, asset);
+                    }
+                }
             }
         } else {
             let input = options.input().to_path_buf();
 
-            for source in resources.collect_work(input) {
+            for source in collect_sorted_work(resources, &input) {
                 self.add_source_if_missing(source, None);
             }
         }
@@ -122,11 +158,17 @@ impl WorkerTree {
         let mut worker = Worker::new(resources);
         worker.setup_worker(&mut options)?;
 
+        self.remove_ignored_work(resources, worker.configuration(), &options)?;
+
         if self.has_configuration_changed(worker.configuration()) {
             log::debug!("configuration change detected");
             self.reset();
         }
 
+        if !worker.configuration().is_allow_overwrite() {
+            self.verify_output_collisions(worker.configuration())?;
+        }
+
         let total_not_done = self
             .graph
             .node_weights()
@@ -134,7 +176,7 @@ impl WorkerTree {
             .count();
 
         if total_not_done == 0 {
-            return Ok(());
+            return self.complete_output(resources, worker.configuration(), &options);
         }
 
         let work_timer = Timer::now();
@@ -237,6 +279,189 @@ impl WorkerTree {
 
         log::info!("executed work in {}", work_timer.duration_label());
 
+        self.complete_output(resources, worker.configuration(), &options)
+    }
+
+    /// Makes sure that each output is written by a single source, since the last processed
+    /// source would silently overwrite the others.
+    // ignore rules only apply to the files collected from an input directory, so that
+    // an explicitly named input file is always processed
+    fn remove_ignored_work(
+        &mut self,
+        resources: &Resources,
+        configuration: &Configuration,
+        options: &Options,
+    ) -> DarkluaResult<()> {
+        if !configuration.has_ignore_rules() || !resources.is_directory(options.input())? {
+            return Ok(());
+        }
+
+        let input = normalize_path(options.input());
+
+        let directories: BTreeSet<PathBuf> = self
+            .node_map
+            .keys()
+            .chain(self.assets.keys())
+            .flat_map(|path| path.ancestors().skip(1))
+            .filter(|directory| directory.starts_with(&input))
+            .map(Path::to_path_buf)
+            .collect();
+
+        let rules = IgnoreRules::load(resources, configuration, &input, directories)?;
+
+        let mut remove_nodes = Vec::new();
+
+        self.node_map.retain(|path, node_index| {
+            if rules.is_ignored(path) {
+                log::debug!("ignore `{}`", path.display());
+                remove_nodes.push(*node_index);
+                false
+            } else {
+                true
+            }
+        });
+
+        for node_index in remove_nodes {
+            self.graph.remove_node(node_index);
+        }
+
+        self.assets.retain(|path, _| !rules.is_ignored(path));
+
+        Ok(())
+    }
+
+    fn verify_output_collisions(&self, configuration: &Configuration) -> DarkluaResult<()> {
+        let output_profiles = configuration.output_profiles();
+        let mut sources_by_output: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+
+        for work_item in self.graph.node_weights() {
+            for output in output_profiles.iter() {
+                let output_path = normalize_path(output.output_path(work_item.data.output()));
+                let sources = sources_by_output.entry(output_path).or_default();
+
+                if !sources.iter().any(|source| source == work_item.source()) {
+                    sources.push(work_item.source().to_path_buf());
+                }
+            }
+        }
+
+        let collisions: Vec<_> = sources_by_output
+            .into_iter()
+            .filter(|(_, sources)| sources.len() > 1)
+            .map(|(output, mut sources)| {
+                sources.sort();
+                (output, sources)
+            })
+            .collect();
+
+        if collisions.is_empty() {
+            Ok(())
+        } else {
+            Err(DarkluaError::output_collision(collisions))
+        }
+    }
+
+    fn complete_output(
+        &self,
+        resources: &Resources,
+        configuration: &Configuration,
+        options: &Options,
+    ) -> DarkluaResult<()> {
+        if options.should_capture_output() {
+            return Ok(());
+        }
+
+        let bundled_files = if configuration
+            .bundle_config()
+            .is_some_and(|bundle| bundle.is_prune_bundled_files())
+        {
+            let bundled_files = self.collect_bundled_files();
+            self.prune_bundled_files(resources, &bundled_files);
+            bundled_files
+        } else {
+            HashSet::new()
+        };
+
+        self.copy_assets(resources, configuration, &bundled_files)
+    }
+
+    fn collect_bundled_files(&self) -> HashSet<&Path> {
+        self.graph
+            .node_weights()
+            .filter(|work_item| matches!(work_item.status, WorkStatus::Done(Ok(()))))
+            .flat_map(|work_item| work_item.bundled_files.iter().map(PathBuf::as_path))
+            .collect()
+    }
+
+    fn prune_bundled_files(&self, resources: &Resources, bundled_files: &HashSet<&Path>) {
+        for work_item in self.graph.node_weights() {
+            if work_item.data.is_in_place() || !bundled_files.contains(work_item.source()) {
+                continue;
+            }
+
+            for output in work_item.outputs.iter() {
+                if !resources.exists(output).unwrap_or_default() {
+                    continue;
+                }
+
+                log::debug!(
+                    "prune `{}` because it was bundled into another file",
+                    output.display()
+                );
+                if let Err(err) = resources.remove(output).map_err(DarkluaError::from) {
+                    log::warn!("failed to remove resource: {}", err);
+                }
+            }
+        }
+    }
+
+    fn copy_assets(
+        &self,
+        resources: &Resources,
+        configuration: &Configuration,
+        bundled_files: &HashSet<&Path>,
+    ) -> DarkluaResult<()> {
+        let mut includes = Vec::new();
+        let mut excludes = Vec::new();
+
+        for pattern in configuration.copy_assets() {
+            let (pattern, container) = match pattern.strip_prefix('!') {
+                Some(pattern) => (pattern, &mut excludes),
+                None => (pattern, &mut includes),
+            };
+            let glob = wax::Glob::new(pattern).map_err(|err| {
+                DarkluaError::custom(format!("invalid asset pattern `{}`: {}", pattern, err))
+            })?;
+            container.push(glob);
+        }
+
+        if includes.is_empty() {
+            return Ok(());
+        }
+
+        for (source, asset) in self.assets.iter() {
+            let is_match = |glob: &wax::Glob| glob.is_match(asset.relative_path.as_path());
+
+            if !includes.iter().any(is_match) || excludes.iter().any(is_match) {
+                continue;
+            }
+
+            if bundled_files.contains(source.as_path()) {
+                log::trace!(
+                    "skip asset `{}` because it was bundled into another file",
+                    source.display()
+                );
+                continue;
+            }
+
+            log::trace!(
+                "copy asset `{}` to `{}`",
+                source.display(),
+                asset.output.display()
+            );
+            resources.copy(source, &asset.output)?;
+        }
+
         Ok(())
     }
 
@@ -258,6 +483,16 @@ impl WorkerTree {
         self.iter_errors().collect()
     }
 
+    /// Returns a JSON array with a report of every error that occurred during processing.
+    ///
+    /// See [`ErrorReport`](crate::ErrorReport) for the content of each entry. Entries are
+    /// sorted by path, then by message.
+    pub fn errors_json(&self) -> String {
+        let mut reports: Vec<_> = self.iter_errors().map(DarkluaError::to_report).collect();
+        reports.sort_by(|a, b| (&a.path, &a.message).cmp(&(&b.path, &b.message)));
+        serde_json::to_string_pretty(&reports).expect("error reports should serialize to json")
+    }
+
     fn iter_errors(&self) -> impl Iterator<Item = &DarkluaError> {
         self.graph
             .node_weights()
@@ -267,6 +502,148 @@ impl WorkerTree {
             })
     }
 
+    /// Returns the code generated for the given output path, when the processing was
+    /// started with [`Options::capture_output`]. Sourcemaps generated while bundling are
+    /// also available at their output path.
+    pub fn generated(&self, output_path: impl AsRef<Path>) -> Option<&str> {
+        let output_path = normalize_path(output_path.as_ref());
+
+        self.graph
+            .node_weights()
+            .flat_map(|work_item| work_item.generated.iter())
+            .find(|(path, _)| *path == output_path)
+            .map(|(_, content)| content.as_str())
+    }
+
+    /// Returns every file written while processing the source files, with its size in bytes
+    /// and its kind, sorted by path.
+    ///
+    /// Files are only listed once they are written, so nothing is listed when the processing
+    /// was started with [`Options::capture_output`]. Copied assets are not listed.
+    pub fn artifacts(&self) -> Vec<(PathBuf, usize, ArtifactKind)> {
+        let mut artifacts: Vec<_> = self
+            .graph
+            .node_weights()
+            .flat_map(|work_item| work_item.artifacts.iter().cloned())
+            .collect();
+
+        artifacts.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+        artifacts
+    }
+
+    /// Collects the warnings emitted while processing each source file.
+    ///
+    /// Only files with at least one warning are included.
+    pub fn collect_warnings(&self) -> Vec<(&Path, &[RuleWarning])> {
+        let mut warnings: Vec<_> = self
+            .graph
+            .node_weights()
+            .filter(|work_item| !work_item.warnings.is_empty())
+            .map(|work_item| (work_item.source(), work_item.warnings.as_slice()))
+            .collect();
+
+        warnings.sort_by_key(|(source, _)| *source);
+        warnings
+    }
+
+    /// Collects the warnings of every require call left unchanged because it could not be
+    /// resolved, like requires built from unsupported expressions. The warnings of all
+    /// processed files are sorted by path and line.
+    pub fn collect_skipped_requires(&self) -> Vec<&RuleWarning> {
+        let mut skipped_requires: Vec<_> = self
+            .graph
+            .node_weights()
+            .flat_map(|work_item| work_item.warnings.iter())
+            .filter(|warning| warning.code() == WarningCode::UnresolvedRequire)
+            .collect();
+
+        skipped_requires.sort_by_key(|warning| (warning.path(), warning.line()));
+        skipped_requires
+    }
+
+    /// Collects the require modes selected by the `auto` require mode for each source file.
+    ///
+    /// Only files where a require mode was detected are included.
+    pub fn collect_detected_require_modes(&self) -> Vec<(&Path, &[DetectedRequireMode])> {
+        let mut modes: Vec<_> = self
+            .graph
+            .node_weights()
+            .filter(|work_item| !work_item.detected_require_modes.is_empty())
+            .map(|work_item| {
+                (
+                    work_item.source(),
+                    work_item.detected_require_modes.as_slice(),
+                )
+            })
+            .collect();
+
+        modes.sort_by_key(|(source, _)| *source);
+        modes
+    }
+
+    /// Collects the number of sourcemap mappings pointing to each bundled source, for every
+    /// file that generated a sourcemap.
+    pub fn collect_sourcemap_mappings(&self) -> Vec<(&Path, &[(String, usize)])> {
+        let mut mappings: Vec<_> = self
+            .graph
+            .node_weights()
+            .filter(|work_item| !work_item.sourcemap_mappings.is_empty())
+            .map(|work_item| (work_item.source(), work_item.sourcemap_mappings.as_slice()))
+            .collect();
+
+        mappings.sort_by_key(|(source, _)| *source);
+        mappings
+    }
+
+    /// Maps a line of a generated file to the source file and line it was generated from.
+    ///
+    /// Lines are numbered starting from 1. The mapping is only available for files that
+    /// generated a sourcemap (see the bundle `sourcemap` configuration). Lines that do not
+    /// come from a source file, like the module definitions injected by the bundler,
+    /// return `None`.
+    pub fn map_generated_to_original(
+        &self,
+        output_path: impl AsRef<Path>,
+        line: usize,
+    ) -> Option<(PathBuf, usize)> {
+        let output_path = normalize_path(output_path.as_ref());
+
+        self.graph
+            .node_weights()
+            .flat_map(|work_item| work_item.line_mappings.iter())
+            .find(|segment| {
+                segment.generated_line == line && normalize_path(&segment.output) == output_path
+            })
+            .map(|segment| (segment.source.clone(), segment.original_line))
+    }
+
+    /// Returns the lines of the generated files that map to the given line of a source file.
+    ///
+    /// Lines are numbered starting from 1 and the result is sorted. Like
+    /// [`map_generated_to_original`](Self::map_generated_to_original), only files that
+    /// generated a sourcemap are considered.
+    pub fn map_original_to_generated(
+        &self,
+        source_path: impl AsRef<Path>,
+        line: usize,
+    ) -> Vec<usize> {
+        let source_path = normalize_path(source_path.as_ref());
+
+        let mut lines: Vec<_> = self
+            .graph
+            .node_weights()
+            .flat_map(|work_item| work_item.line_mappings.iter())
+            .filter(|segment| {
+                segment.original_line == line && normalize_path(&segment.source) == source_path
+            })
+            .map(|segment| segment.generated_line)
+            .collect();
+
+        lines.sort_unstable();
+        lines.dedup();
+        lines
+    }
+
     /// Returns the number of successfully processed work items.
     pub fn success_count(&self) -> usize {
         self.graph
@@ -882,7 +882,7 @@ impl LuaGenerator for DenseLuaGenerator {
     }
 
     fn write_string(&mut self, string: &nodes::StringExpression) {
-        let result = utils::write_string(string.get_value());
+        let result = utils::write_string_with_quote(string.get_value(), string.get_quote());
         if result.starts_with('[') {
             self.push_str_and_break_if(&result, utils::break_long_string);
         } else {
@@ -9,7 +9,7 @@ pub mod utils;
 
 pub use dense::DenseLuaGenerator;
 pub use readable::ReadableLuaGenerator;
-pub use token_based::TokenBasedLuaGenerator;
+pub use token_based::{LineMapping, RetainLinesPreamble, TokenBasedLuaGenerator};
 pub use retain_lines_compact::RetainLinesCompactLuaGenerator;
 
 use crate::nodes;
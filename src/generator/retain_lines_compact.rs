@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::BTreeMap;
 use std::iter;
 
 use sourcemap::SourceMapBuilder;
@@ -718,7 +718,7 @@ impl LuaGenerator for RetainLinesCompactLuaGenerator<'_> {
     }
 
     fn write_string(&mut self, string: &StringExpression) {
-        if let Some(token) = string.get_token() { self.write_token(token) } else { self.write_symbol(&utils::write_string(string.get_value())); }
+        if let Some(token) = string.get_token() { self.write_token(token) } else { self.write_symbol(&utils::write_string_with_quote(string.get_value(), string.get_quote())); }
     }
 
     fn write_interpolated_string(&mut self, interpolated_string: &InterpolatedStringExpression) {
@@ -1368,37 +1368,80 @@ impl RetainLinesCompactLuaGenerator<'_> {
 
 struct MappingRecorder {
     builder: SourceMapBuilder,
-    recorded_lines: HashSet<usize>,
+    recorded_lines: BTreeMap<usize, (usize, u32)>,
     registry: Option<Rc<RefCell<SourceRegistry>>>,
     relative_base: Option<std::path::PathBuf>,
 }
 
 impl MappingRecorder {
-    fn new(builder: SourceMapBuilder, registry: Option<Rc<RefCell<SourceRegistry>>>, relative_base: Option<std::path::PathBuf>) -> Self { Self { builder, recorded_lines: HashSet::new(), registry, relative_base } }
+    fn new(builder: SourceMapBuilder, registry: Option<Rc<RefCell<SourceRegistry>>>, relative_base: Option<std::path::PathBuf>) -> Self {
+        Self { builder, recorded_lines: BTreeMap::new(), registry, relative_base }
+    }
+
     fn record_line(&mut self, dst_line0: usize, src_line0: usize, src_id: u32) {
-        if self.recorded_lines.insert(dst_line0) {
-            let source_name_string: Option<String> = if let Some(reg) = &self.registry {
-                let reg_borrow = reg.borrow();
-                reg_borrow
-                    .get_path(src_id)
-                    .map(|p| {
-                        let p_norm = crate::utils::normalize_path_with_current_dir(p);
-                        if let Some(base) = &self.relative_base {
-                            let base_norm = crate::utils::normalize_path_with_current_dir(base);
-                            match p_norm.strip_prefix(&base_norm) {
-                                Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
-                                Err(_) => p_norm.to_string_lossy().replace('\\', "/"),
-                            }
-                        } else {
-                            p_norm.to_string_lossy().replace('\\', "/")
-                        }
-                    })
-            } else { None };
-            let source_name = source_name_string.as_deref();
-            self.builder.add(dst_line0 as u32, 0, src_line0 as u32, 0, source_name, None, false);
+        // the first mapping of a line is kept, except that Lua sources take precedence over
+        // bundled data files (like json files) written on the same line
+        let replace = match self.recorded_lines.get(&dst_line0) {
+            None => true,
+            Some((_, recorded_id)) => !self.is_lua_source(*recorded_id) && self.is_lua_source(src_id),
+        };
+        if replace {
+            self.recorded_lines.insert(dst_line0, (src_line0, src_id));
+        }
+    }
+
+    fn is_lua_source(&self, src_id: u32) -> bool {
+        self.registry
+            .as_ref()
+            .and_then(|reg| {
+                reg.borrow().get_path(src_id).map(|p| {
+                    p.extension()
+                        .map(|extension| {
+                            matches!(
+                                extension.to_string_lossy().to_ascii_lowercase().as_str(),
+                                "lua" | "luau"
+                            )
+                        })
+                        .unwrap_or(true)
+                })
+            })
+            .unwrap_or(true)
+    }
+
+    fn source_name(&self, src_id: u32) -> Option<String> {
+        let reg = self.registry.as_ref()?;
+        let reg_borrow = reg.borrow();
+        reg_borrow.get_path(src_id).map(|p| {
+            // Normalize against current dir to avoid accidental absolute paths leaking
+            let p_norm = crate::utils::normalize_path_with_current_dir(p);
+            if let Some(base) = &self.relative_base {
+                let base_norm = crate::utils::normalize_path_with_current_dir(base);
+                match p_norm.strip_prefix(&base_norm) {
+                    Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
+                    Err(_) => p_norm.to_string_lossy().replace('\\', "/"),
+                }
+            } else {
+                p_norm.to_string_lossy().replace('\\', "/")
+            }
+        })
+    }
+
+    fn into_builder(mut self) -> SourceMapBuilder {
+        for (dst_line0, (src_line0, src_id)) in std::mem::take(&mut self.recorded_lines) {
+            // columns are 0 for line-level mapping
+            let source_name = self.source_name(src_id);
+            self.builder.add(
+                dst_line0 as u32,
+                0,
+                src_line0 as u32,
+                0,
+                source_name.as_deref(),
+                None,
+                false,
+            );
         }
+        self.builder
     }
-    fn into_builder(self) -> SourceMapBuilder { self.builder }
 }
 
 fn intersect_with_token(token: Token, list_length: usize) -> Vec<Token> {
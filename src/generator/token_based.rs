@@ -1,15 +1,37 @@
+use std::borrow::Cow;
 use std::iter;
-use std::collections::HashSet;
+use std::collections::BTreeMap;
 use sourcemap::SourceMapBuilder;
 use std::rc::Rc;
 use std::cell::RefCell;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
     generator::{utils, LuaGenerator},
     nodes::*,
     utils::source_registry::SourceRegistry,
 };
 
+/// Defines how the [TokenBasedLuaGenerator](struct.TokenBasedLuaGenerator.html) writes
+/// the statements found at the start of a file that do not come from the original code
+/// (like statements inserted by rules or by the bundler).
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RetainLinesPreamble {
+    /// Writes the statements on the same lines as the original code.
+    #[default]
+    Pack,
+    /// Writes each statement on a new line, and moves the original code after them.
+    Expand,
+}
+
+impl RetainLinesPreamble {
+    pub(crate) fn is_pack(&self) -> bool {
+        matches!(self, Self::Pack)
+    }
+}
+
 /// This implementation of [LuaGenerator](trait.LuaGenerator.html) outputs the
 /// AST nodes from the tokens associated with each of them.
 #[derive(Clone)]
@@ -19,6 +41,18 @@ pub struct TokenBasedLuaGenerator<'a> {
     currently_commenting: bool,
     current_line: usize,
     mapping: Option<std::rc::Rc<std::cell::RefCell<MappingRecorder>>>,
+    preamble: RetainLinesPreamble,
+    block_depth: usize,
+    // whether a token with a line number was written
+    has_line_info: bool,
+    // the number of lines added to the line of each token, after an expanded preamble or
+    // continuation lines
+    line_offset: usize,
+    // the continuation lines included in the line offset, which following empty lines absorb
+    // to realign the code with the original lines
+    continuation_lines: usize,
+    max_line_length: Option<usize>,
+    interpolated_string_depth: usize,
 }
 
 impl<'a> TokenBasedLuaGenerator<'a> {
@@ -29,9 +63,32 @@ impl<'a> TokenBasedLuaGenerator<'a> {
             currently_commenting: false,
             current_line: 1,
             mapping: None,
+            preamble: RetainLinesPreamble::default(),
+            block_depth: 0,
+            has_line_info: false,
+            line_offset: 0,
+            continuation_lines: 0,
+            max_line_length: None,
+            interpolated_string_depth: 0,
         }
     }
 
+    /// Sets how the statements without line information at the start of the file are
+    /// written.
+    pub fn with_preamble(mut self, preamble: RetainLinesPreamble) -> Self {
+        self.preamble = preamble;
+        self
+    }
+
+    /// Sets the maximum length of the generated lines. Longer lines are split between
+    /// tokens, and the following lines are moved down by the number of continuation lines
+    /// until enough empty lines are removed to realign them with the original code. A
+    /// maximum length of zero does not split lines.
+    pub fn with_max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = Some(max_line_length).filter(|length| *length > 0);
+        self
+    }
+
     /// Attach a sourcemap builder with a shared source registry for resolving source_id → path.
     pub fn with_sourcemap(
         self,
@@ -44,6 +101,35 @@ impl<'a> TokenBasedLuaGenerator<'a> {
         Self { mapping: Some(mapping), ..s }
     }
 
+    /// Attach a sourcemap builder without any source registry. The mappings are recorded
+    /// from the line and source id of each token, but no source names are resolved.
+    pub fn with_line_mapping(self, builder: SourceMapBuilder) -> Self {
+        let mapping = Rc::new(RefCell::new(MappingRecorder::new(builder, None, None)));
+        Self {
+            mapping: Some(mapping),
+            ..self
+        }
+    }
+
+    /// Returns the line mappings recorded so far, ordered by their generated line. When no
+    /// sourcemap builder is attached, the returned list is empty.
+    pub fn line_mappings(&self) -> Vec<LineMapping> {
+        self.mapping
+            .as_ref()
+            .map(|mapping| mapping.borrow().line_mappings())
+            .unwrap_or_default()
+    }
+
+    /// Returns every line mapping recorded so far, in the order the generator emitted them.
+    /// A generated line appears more than once when its mapping was replaced. When no
+    /// sourcemap builder is attached, the returned list is empty.
+    pub fn emitted_line_mappings(&self) -> Vec<LineMapping> {
+        self.mapping
+            .as_ref()
+            .map(|mapping| mapping.borrow().emitted_mappings.clone())
+            .unwrap_or_default()
+    }
+
     /// Consume the generator and return its output and an optional sourcemap.
     pub fn into_string_and_sourcemap(self) -> (String, Option<sourcemap::SourceMap>) {
         let map = if let Some(rc) = self.mapping {
@@ -67,7 +153,13 @@ impl<'a> TokenBasedLuaGenerator<'a> {
 
     fn write_trivia(&mut self, trivia: &Trivia) {
         let content = trivia.read(self.original_code);
-        self.push_str(content);
+
+        if trivia.kind() == TriviaKind::Whitespace && self.continuation_lines > 0 {
+            let content = self.absorb_empty_lines(content);
+            self.push_str(&content);
+        } else {
+            self.push_str(content);
+        }
 
         match trivia.kind() {
             TriviaKind::Comment => {
@@ -101,12 +193,24 @@ impl<'a> TokenBasedLuaGenerator<'a> {
             }
 
             if let Some(line_number) = token.get_line_number() {
+                let mut line_number = self.output_line(line_number);
+
+                if line_number > self.current_line && self.continuation_lines > 0 {
+                    let absorbed = self.absorbable_lines(line_number - self.current_line);
+                    self.realign(absorbed);
+                    line_number -= absorbed;
+                }
+
                 while line_number > self.current_line {
                     self.output.push('\n');
                     self.current_line += 1;
                 }
             }
 
+            if space_check {
+                self.split_long_line(content);
+            }
+
             // record first mapping for this generated line if we have origin on token
             if let Some(map) = &self.mapping {
                 if let (Some(src_line), Some(src_id)) = (token.get_line_number(), token.get_source_id()) {
@@ -130,10 +234,124 @@ impl<'a> TokenBasedLuaGenerator<'a> {
         }
     }
 
+    // Returns the line where a token from the given line is written. The lines used by an
+    // expanded preamble are added to the line of every token after it.
+    fn output_line(&mut self, line_number: usize) -> usize {
+        if !self.has_line_info {
+            self.has_line_info = true;
+
+            if !self.preamble.is_pack() && !self.output.is_empty() {
+                let next_line = if self.output.ends_with('\n') {
+                    self.current_line
+                } else {
+                    self.current_line + 1
+                };
+                self.line_offset = next_line.saturating_sub(line_number);
+            }
+        }
+
+        line_number + self.line_offset
+    }
+
+    // Starts a continuation line when the given content would make the current line longer
+    // than the maximum line length. Lines are never split inside interpolated strings or
+    // before an opening parenthesis, which could turn a call into a new statement.
+    fn split_long_line(&mut self, content: &str) {
+        let max_line_length = match self.max_line_length {
+            Some(max_line_length) => max_line_length,
+            None => return,
+        };
+
+        if self.interpolated_string_depth > 0 || content.starts_with('(') {
+            return;
+        }
+
+        let content_length = content.lines().next().unwrap_or_default().chars().count();
+        let line = match self.output.rfind('\n') {
+            Some(index) => &self.output[index + 1..],
+            None => &self.output,
+        };
+
+        if content_length > max_line_length {
+            log::warn!(
+                "unable to split line {} of the generated code to fit the maximum line length ({}), a token is {} characters long",
+                self.current_line,
+                max_line_length,
+                content_length,
+            );
+            return;
+        }
+
+        // count one more character for the space that may be written before the content
+        if !line.trim().is_empty() && line.chars().count() + content_length + 1 > max_line_length {
+            let trimmed_length = self.output.trim_end_matches([' ', '\t']).len();
+            self.output.truncate(trimmed_length);
+            self.output.push('\n');
+            self.current_line += 1;
+            self.line_offset += 1;
+            self.continuation_lines += 1;
+
+            if let Some(map) = &self.mapping {
+                map.borrow_mut().continue_line(self.current_line - 1);
+            }
+        }
+    }
+
+    // Returns how many of the given new lines can be removed to realign the following code
+    // with the original lines. The current line is never joined with the next one, so one
+    // new line is kept unless the current line is already empty.
+    fn absorbable_lines(&self, new_lines: usize) -> usize {
+        let kept_lines = if self.output.is_empty() || self.output.ends_with('\n') {
+            0
+        } else {
+            1
+        };
+
+        self.continuation_lines
+            .min(new_lines.saturating_sub(kept_lines))
+    }
+
+    // Removes the empty lines of a whitespace that are needed to realign the following code
+    // with the original lines.
+    fn absorb_empty_lines<'b>(&mut self, content: &'b str) -> Cow<'b, str> {
+        let new_lines = content.matches('\n').count();
+        let absorbed = self.absorbable_lines(new_lines);
+
+        if absorbed == 0 {
+            return Cow::Borrowed(content);
+        }
+
+        self.realign(absorbed);
+
+        let mut lines = content.split_inclusive('\n');
+        let mut realigned: String = lines.by_ref().take(new_lines - absorbed).collect();
+        realigned.extend(lines.skip(absorbed));
+        Cow::Owned(realigned)
+    }
+
+    fn realign(&mut self, lines: usize) {
+        self.continuation_lines -= lines;
+        self.line_offset -= lines;
+    }
+
+    // In an expanded preamble, each statement of the file before the original code starts
+    // on a new line.
+    fn start_preamble_line(&mut self) {
+        if !self.preamble.is_pack()
+            && self.block_depth == 1
+            && !self.has_line_info
+            && !self.output.is_empty()
+            && !self.output.ends_with('\n')
+        {
+            self.uncomment();
+        }
+    }
+
     fn write_block_with_tokens(&mut self, block: &Block, tokens: &BlockTokens) {
         let mut iterator = block.iter_statements().enumerate().peekable();
 
         while let Some((index, statement)) = iterator.next() {
+            self.start_preamble_line();
             self.write_statement(statement);
 
             if let Some(semicolon) = tokens.semicolons.get(index).unwrap_or(&None) {
@@ -148,6 +366,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
         }
 
         if let Some(statement) = block.get_last_statement() {
+            self.start_preamble_line();
             self.write_last_statement(statement);
         }
 
@@ -1151,6 +1370,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
         tokens: &InterpolatedStringTokens,
     ) {
         self.write_token(&tokens.opening_tick);
+        self.interpolated_string_depth += 1;
 
         for segment in interpolated_string.iter_segments() {
             match segment {
@@ -1175,6 +1395,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
         }
 
         self.write_token(&tokens.closing_tick);
+        self.interpolated_string_depth -= 1;
     }
 
     fn write_string_value_segment_with_tokens(
@@ -1652,8 +1873,11 @@ impl<'a> TokenBasedLuaGenerator<'a> {
     fn write_symbol(&mut self, symbol: &str) {
         if self.currently_commenting {
             self.uncomment();
-        } else if self.needs_space(symbol.chars().next().expect("symbol cannot be empty")) {
-            self.output.push(' ');
+        } else {
+            self.split_long_line(symbol);
+            if self.needs_space(symbol.chars().next().expect("symbol cannot be empty")) {
+                self.output.push(' ');
+            }
         }
         self.push_str(symbol);
     }
@@ -1736,11 +1960,13 @@ impl LuaGenerator for TokenBasedLuaGenerator<'_> {
     }
 
     fn write_block(&mut self, block: &Block) {
+        self.block_depth += 1;
         if let Some(tokens) = block.get_tokens() {
             self.write_block_with_tokens(block, tokens);
         } else {
             self.write_block_with_tokens(block, &self.generate_block_tokens(block));
         }
+        self.block_depth -= 1;
     }
 
     fn write_assign_statement(&mut self, assign: &AssignStatement) {
@@ -2070,7 +2296,10 @@ impl LuaGenerator for TokenBasedLuaGenerator<'_> {
         if let Some(token) = string.get_token() {
             self.write_token(token);
         } else {
-            self.write_symbol(&utils::write_string(string.get_value()));
+            self.write_symbol(&utils::write_string_with_quote(
+                string.get_value(),
+                string.get_quote(),
+            ));
         }
     }
 
@@ -2279,54 +2508,141 @@ impl LuaGenerator for TokenBasedLuaGenerator<'_> {
     }
 }
 
+/// A mapping from a line of the generated code to a line of its source. Like in sourcemaps,
+/// line numbers start at 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineMapping {
+    generated_line: usize,
+    source_line: usize,
+    source_id: u32,
+}
+
+impl LineMapping {
+    /// The line of the generated code (starting at 0).
+    pub fn get_generated_line(&self) -> usize {
+        self.generated_line
+    }
+
+    /// The line of the source code (starting at 0).
+    pub fn get_source_line(&self) -> usize {
+        self.source_line
+    }
+
+    /// The id of the source that the line comes from.
+    pub fn get_source_id(&self) -> u32 {
+        self.source_id
+    }
+}
+
 struct MappingRecorder {
     builder: SourceMapBuilder,
-    recorded_lines: HashSet<usize>,
+    recorded_lines: BTreeMap<usize, (usize, u32)>,
+    // every mapping in the order it was recorded, to verify that lines are emitted in order
+    emitted_mappings: Vec<LineMapping>,
     registry: Option<Rc<RefCell<SourceRegistry>>>,
     relative_base: Option<std::path::PathBuf>,
 }
 
 impl MappingRecorder {
     fn new(builder: SourceMapBuilder, registry: Option<Rc<RefCell<SourceRegistry>>>, relative_base: Option<std::path::PathBuf>) -> Self {
-        Self { builder, recorded_lines: HashSet::new(), registry, relative_base }
+        Self { builder, recorded_lines: BTreeMap::new(), emitted_mappings: Vec::new(), registry, relative_base }
     }
 
     fn record_line(&mut self, dst_line0: usize, src_line0: usize, src_id: u32) {
-        if self.recorded_lines.insert(dst_line0) {
+        // the first mapping of a line is kept, except that Lua sources take precedence over
+        // bundled data files (like json files) written on the same line
+        let replace = match self.recorded_lines.get(&dst_line0) {
+            None => true,
+            Some((_, recorded_id)) => !self.is_lua_source(*recorded_id) && self.is_lua_source(src_id),
+        };
+        if replace {
+            self.insert_line(dst_line0, (src_line0, src_id));
+        }
+    }
+
+    // maps a continuation line to the same source line as the line before it
+    fn continue_line(&mut self, dst_line0: usize) {
+        let previous = dst_line0
+            .checked_sub(1)
+            .and_then(|previous_line0| self.recorded_lines.get(&previous_line0))
+            .copied();
+        if let Some(previous) = previous {
+            self.insert_line(dst_line0, previous);
+        }
+    }
+
+    fn insert_line(&mut self, dst_line0: usize, (src_line0, src_id): (usize, u32)) {
+        self.recorded_lines.insert(dst_line0, (src_line0, src_id));
+        self.emitted_mappings.push(LineMapping {
+            generated_line: dst_line0,
+            source_line: src_line0,
+            source_id: src_id,
+        });
+    }
+
+    fn line_mappings(&self) -> Vec<LineMapping> {
+        self.recorded_lines
+            .iter()
+            .map(|(dst_line0, (src_line0, src_id))| LineMapping {
+                generated_line: *dst_line0,
+                source_line: *src_line0,
+                source_id: *src_id,
+            })
+            .collect()
+    }
+
+    fn is_lua_source(&self, src_id: u32) -> bool {
+        self.registry
+            .as_ref()
+            .and_then(|reg| {
+                reg.borrow().get_path(src_id).map(|p| {
+                    p.extension()
+                        .map(|extension| {
+                            matches!(
+                                extension.to_string_lossy().to_ascii_lowercase().as_str(),
+                                "lua" | "luau"
+                            )
+                        })
+                        .unwrap_or(true)
+                })
+            })
+            .unwrap_or(true)
+    }
+
+    fn source_name(&self, src_id: u32) -> Option<String> {
+        let reg = self.registry.as_ref()?;
+        let reg_borrow = reg.borrow();
+        reg_borrow.get_path(src_id).map(|p| {
+            // Normalize against current dir to avoid accidental absolute paths leaking
+            let p_norm = crate::utils::normalize_path_with_current_dir(p);
+            if let Some(base) = &self.relative_base {
+                let base_norm = crate::utils::normalize_path_with_current_dir(base);
+                match p_norm.strip_prefix(&base_norm) {
+                    Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
+                    Err(_) => p_norm.to_string_lossy().replace('\\', "/"),
+                }
+            } else {
+                p_norm.to_string_lossy().replace('\\', "/")
+            }
+        })
+    }
+
+    fn into_builder(mut self) -> SourceMapBuilder {
+        for (dst_line0, (src_line0, src_id)) in std::mem::take(&mut self.recorded_lines) {
             // columns are 0 for line-level mapping
-            let source_name_string: Option<String> = if let Some(reg) = &self.registry {
-                let reg_borrow = reg.borrow();
-                reg_borrow
-                    .get_path(src_id)
-                    .map(|p| {
-                        // Normalize against current dir to avoid accidental absolute paths leaking
-                        let p_norm = crate::utils::normalize_path_with_current_dir(p);
-                        if let Some(base) = &self.relative_base {
-                            let base_norm = crate::utils::normalize_path_with_current_dir(base);
-                            match p_norm.strip_prefix(&base_norm) {
-                                Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
-                                Err(_) => p_norm.to_string_lossy().replace('\\', "/"),
-                            }
-                        } else {
-                            p_norm.to_string_lossy().replace('\\', "/")
-                        }
-                    })
-            } else { None };
-            let source_name = source_name_string.as_deref();
+            let source_name = self.source_name(src_id);
             self.builder.add(
                 dst_line0 as u32,
                 0,
                 src_line0 as u32,
                 0,
-                source_name,
+                source_name.as_deref(),
                 None,
                 false,
             );
         }
+        self.builder
     }
-
-    #[allow(dead_code)]
-    fn into_builder(self) -> SourceMapBuilder { self.builder }
 }
 
 fn intersect_with_token(token: Token, list_length: usize) -> Vec<Token> {
@@ -2544,4 +2860,229 @@ mod test {
 
         insta::assert_snapshot!("inserts_a_new_line_after_custom_added_comments", output);
     }
+
+    fn generate_with_inserted_statements(preamble: RetainLinesPreamble) -> String {
+        let code = "local a = 1\nprint(a)\n";
+        let mut block = crate::Parser::default()
+            .preserve_tokens()
+            .parse(code)
+            .expect("unable to parse code");
+
+        block.insert_statement(0, LocalAssignStatement::from_variable("c"));
+        block.insert_statement(0, LocalAssignStatement::from_variable("b"));
+
+        let mut generator = TokenBasedLuaGenerator::new(code).with_preamble(preamble);
+        generator.write_block(&block);
+        generator.into_string()
+    }
+
+    #[test]
+    fn pack_preamble_writes_inserted_statements_on_first_line() {
+        assert_eq!(
+            generate_with_inserted_statements(RetainLinesPreamble::Pack),
+            "local b local c local a = 1\nprint(a)\n"
+        );
+    }
+
+    #[test]
+    fn expand_preamble_writes_inserted_statements_on_new_lines() {
+        assert_eq!(
+            generate_with_inserted_statements(RetainLinesPreamble::Expand),
+            "local b\nlocal c\nlocal a = 1\nprint(a)\n"
+        );
+    }
+
+    mod max_line_length {
+        use super::*;
+
+        fn long_line_code() -> String {
+            let values: Vec<_> = (0..2000).map(|value| value.to_string()).collect();
+            format!("local list = {{ {} }}\nprint(list)\n", values.join(", "))
+        }
+
+        fn generate(code: &str, max_line_length: usize) -> String {
+            let block = crate::Parser::default()
+                .preserve_tokens()
+                .parse(code)
+                .expect("unable to parse code");
+
+            let mut generator =
+                TokenBasedLuaGenerator::new(code).with_max_line_length(max_line_length);
+            generator.write_block(&block);
+            generator.into_string()
+        }
+
+        fn assert_same_code(code: &str, output: &str) {
+            let parser = crate::Parser::default();
+            pretty_assertions::assert_eq!(
+                parser.parse(code).expect("unable to parse code"),
+                parser
+                    .parse(output)
+                    .unwrap_or_else(|_| panic!("failed to parse generated code `{}`", output))
+            );
+        }
+
+        #[test]
+        fn splits_long_line() {
+            let code = long_line_code();
+            assert!(code.lines().next().unwrap().len() > 10_000);
+
+            let output = generate(&code, 100);
+
+            assert!(output.lines().count() > 100);
+            assert!(output.lines().all(|line| line.chars().count() <= 100));
+            assert_same_code(&code, &output);
+        }
+
+        #[test]
+        fn continuation_lines_map_to_the_original_line() {
+            let code = long_line_code();
+            let block = crate::Parser::default()
+                .preserve_tokens()
+                .parse_with_source_id(0, &code)
+                .expect("unable to parse code");
+
+            let mut generator = TokenBasedLuaGenerator::new(&code)
+                .with_max_line_length(100)
+                .with_line_mapping(SourceMapBuilder::new(None));
+            generator.write_block(&block);
+            let mappings = generator.line_mappings();
+            let output = generator.into_string();
+
+            let print_line = output
+                .lines()
+                .position(|line| line.contains("print(list)"))
+                .unwrap();
+
+            pretty_assertions::assert_eq!(
+                mappings
+                    .iter()
+                    .map(|mapping| (mapping.get_generated_line(), mapping.get_source_line()))
+                    .collect::<Vec<_>>(),
+                (0..=print_line)
+                    .map(|line| (line, if line == print_line { 1 } else { 0 }))
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        #[test]
+        fn zero_max_line_length_does_not_split() {
+            let code = long_line_code();
+
+            pretty_assertions::assert_eq!(generate(&code, 0), code);
+        }
+
+        #[test]
+        fn does_not_split_long_string() {
+            let code = format!("local value = '{}'\nprint(value)\n", "a".repeat(10_000));
+
+            pretty_assertions::assert_eq!(generate(&code, 100), code);
+        }
+
+        #[test]
+        fn does_not_split_before_call_parenthesis() {
+            let code = "local value = call(argument)\n";
+
+            let output = generate(code, 10);
+
+            assert!(output.lines().all(|line| !line.starts_with('(')));
+            assert_same_code(code, &output);
+        }
+
+        #[test]
+        fn does_not_split_interpolated_string() {
+            let code = "local value = `hello {name} world {other}`\n";
+
+            let output = generate(code, 10);
+
+            assert!(output.contains("`hello {name} world {other}`"));
+            assert_same_code(code, &output);
+        }
+
+        #[test]
+        fn moves_following_lines_after_continuation_lines() {
+            let code = "local a = 'aaaa' .. 'bbbb' .. 'cccc'\nlocal b = 2\nreturn a, b\n";
+
+            pretty_assertions::assert_eq!(
+                generate(code, 20),
+                "local a = 'aaaa' ..\n'bbbb' .. 'cccc'\nlocal b = 2\nreturn a, b\n"
+            );
+        }
+
+        #[test]
+        fn realigns_lines_after_empty_lines() {
+            let code = "local a = 'aaaa' .. 'bbbb' .. 'cccc'\nlocal b = 2\n\n\nreturn a, b\n";
+
+            pretty_assertions::assert_eq!(
+                generate(code, 20),
+                "local a = 'aaaa' ..\n'bbbb' .. 'cccc'\nlocal b = 2\n\nreturn a, b\n"
+            );
+        }
+
+        #[test]
+        fn realigns_lines_without_whitespace_tokens() {
+            let code = "local a = 'aaaa' .. 'bbbb' .. 'cccc'\nlocal b = 2\n\n\nreturn a, b\n";
+            let mut block = crate::Parser::default()
+                .preserve_tokens()
+                .parse(code)
+                .expect("unable to parse code");
+            let resources = crate::Resources::from_memory();
+            let context = crate::rules::ContextBuilder::new(".", &resources, code).build();
+            crate::rules::Rule::process(
+                &crate::rules::RemoveSpaces::default(),
+                &mut block,
+                &context,
+            )
+            .expect("rule should succeed");
+
+            let mut generator = TokenBasedLuaGenerator::new(code).with_max_line_length(20);
+            generator.write_block(&block);
+
+            pretty_assertions::assert_eq!(
+                generator.into_string(),
+                "local a='aaaa'..\n'bbbb'..'cccc'\nlocal b=2\n\nreturn a,b"
+            );
+        }
+
+        #[test]
+        fn realigned_lines_map_to_their_original_line() {
+            let code = format!("{}\n\n\nreturn list\n", long_line_code());
+            let block = crate::Parser::default()
+                .preserve_tokens()
+                .parse_with_source_id(0, &code)
+                .expect("unable to parse code");
+
+            let mut generator = TokenBasedLuaGenerator::new(&code)
+                .with_max_line_length(4000)
+                .with_line_mapping(SourceMapBuilder::new(None));
+            generator.write_block(&block);
+            let mappings = generator.line_mappings();
+
+            let last = mappings.last().unwrap();
+            pretty_assertions::assert_eq!(
+                (last.get_generated_line(), last.get_source_line()),
+                (5, 5)
+            );
+        }
+
+        #[test]
+        fn emitted_mappings_follow_generated_lines() {
+            let code = format!("local a = 1\n{}\nreturn list\n", long_line_code());
+            let block = crate::Parser::default()
+                .preserve_tokens()
+                .parse_with_source_id(0, &code)
+                .expect("unable to parse code");
+
+            let mut generator = TokenBasedLuaGenerator::new(&code)
+                .with_max_line_length(4000)
+                .with_line_mapping(SourceMapBuilder::new(None));
+            generator.write_block(&block);
+            let emitted = generator.emitted_line_mappings();
+
+            assert!(emitted.len() >= generator.line_mappings().len());
+            assert!(emitted
+                .windows(2)
+                .all(|pair| pair[0].get_generated_line() <= pair[1].get_generated_line()));
+        }
+    }
 }
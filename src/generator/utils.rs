@@ -7,7 +7,7 @@ use bstr::ByteSlice;
 
 use crate::nodes::{
     Expression, FieldExpression, FunctionCall, IndexExpression, NumberExpression, Prefix,
-    Statement, StringSegment, TableExpression, Variable,
+    Statement, StringQuote, StringSegment, TableExpression, Variable,
 };
 
 const QUOTED_STRING_MAX_LENGTH: usize = 60;
@@ -296,6 +296,15 @@ pub fn count_new_lines(string: &[u8]) -> usize {
     string.iter().filter(|c| **c == b'\n').count()
 }
 
+/// Writes a string literal with the given quote, escaping the quote when it appears in
+/// the value. Without a quote, the quote that needs the least escaping is used.
+pub fn write_string_with_quote(value: &[u8], quote: Option<StringQuote>) -> String {
+    match quote {
+        Some(quote) => write_quoted(value, quote.symbol()),
+        None => write_string(value),
+    }
+}
+
 pub fn write_string(value: &[u8]) -> String {
     if value.is_empty() {
         return "''".to_owned();
@@ -324,9 +333,9 @@ pub fn write_string(value: &[u8]) -> String {
         && (value.len() >= QUOTED_STRING_MAX_LENGTH
             || count_new_lines(value) >= FORCE_LONG_STRING_NEW_LINE_THRESHOLD)
     {
-        write_long_bracket(value).unwrap_or_else(|| write_quoted(value))
+        write_long_bracket(value).unwrap_or_else(|| write_quoted(value, get_quote_symbol(value)))
     } else {
-        write_quoted(value)
+        write_quoted(value, get_quote_symbol(value))
     }
 }
 
@@ -385,11 +394,10 @@ fn write_long_bracket(value: &[u8]) -> Option<String> {
     ))
 }
 
-fn write_quoted(value: &[u8]) -> String {
+fn write_quoted(value: &[u8], quote_symbol: char) -> String {
     let mut quoted = String::new();
     quoted.reserve(value.len() + 2);
 
-    let quote_symbol = get_quote_symbol(value);
     quoted.push(quote_symbol);
 
     if let Ok(stringified) = str::from_utf8(value) {
@@ -492,4 +500,45 @@ mod test {
                 => "'\\nooof\\nooof\\nooof\\nooof\\nooof\\nooof\\nooof\\nooof\\noof\\u{10ffff}'",
         );
     }
+
+    mod write_string_with_quote {
+        use super::*;
+
+        #[test]
+        fn single_quote() {
+            assert_eq!(
+                write_string_with_quote(b"./a.lua", Some(StringQuote::Single)),
+                "'./a.lua'"
+            );
+        }
+
+        #[test]
+        fn double_quote() {
+            assert_eq!(
+                write_string_with_quote(b"./a.lua", Some(StringQuote::Double)),
+                "\"./a.lua\""
+            );
+        }
+
+        #[test]
+        fn double_quote_escapes_double_quote() {
+            assert_eq!(
+                write_string_with_quote(b"./\"a\".lua", Some(StringQuote::Double)),
+                "\"./\\\"a\\\".lua\""
+            );
+        }
+
+        #[test]
+        fn single_quote_escapes_single_quote() {
+            assert_eq!(
+                write_string_with_quote(b"./it's.lua", Some(StringQuote::Single)),
+                "'./it\\'s.lua'"
+            );
+        }
+
+        #[test]
+        fn without_quote_uses_least_escaping_quote() {
+            assert_eq!(write_string_with_quote(b"it's", None), "\"it's\"");
+        }
+    }
 }
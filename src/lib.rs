@@ -52,8 +52,16 @@
 //!     "print('Hello, world!')"
 //! );
 //! ```
+//!
+//! # Cargo Features
+//!
+//! - `serde-ast`: implements `Serialize` and `Deserialize` for the [`nodes`] of the syntax
+//!   tree and provides the `ast_to_json` and `ast_from_json` functions, to export a parsed
+//!   block as JSON and import it back.
 
 mod ast_converter;
+#[cfg(feature = "serde-ast")]
+mod ast_json;
 mod frontend;
 pub mod generator;
 pub mod nodes;
@@ -63,7 +71,12 @@ pub mod rules;
 mod utils;
 
 pub use frontend::{
-    convert_data, process, BundleConfiguration, Configuration, DarkluaError, GeneratorParameters,
-    Options, Resources, WorkerTree,
+    convert_data, process, process_code, ArtifactKind, BundleConfiguration,
+    BundleConfigurationBuilder, Configuration, DarkluaError, ErrorKind, ErrorReport,
+    GeneratorParameters, LuaTarget, Options, ProfileConfiguration, Resources, RetainLinesOptions,
+    SourcemapOptions, WorkerTree,
 };
 pub use parser::{Parser, ParserError};
+
+#[cfg(feature = "serde-ast")]
+pub use ast_json::{ast_from_json, ast_to_json};
@@ -4,6 +4,7 @@ use crate::nodes::{Expression, StringExpression, TableExpression, Token};
 
 /// Tokens associated with tuple arguments.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TupleArgumentsTokens {
     pub opening_parenthese: Token,
     pub closing_parenthese: Token,
@@ -19,6 +20,7 @@ impl TupleArgumentsTokens {
 
 /// Represents a list of arguments enclosed in parentheses.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TupleArguments {
     values: Vec<Expression>,
     tokens: Option<TupleArgumentsTokens>,
@@ -142,6 +144,7 @@ impl iter::FromIterator<Expression> for TupleArguments {
 
 /// Represents the different ways arguments can be passed to a function call.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum Arguments {
     /// Multiple arguments in parentheses: `func(arg1, arg2)`
     Tuple(TupleArguments),
@@ -257,10 +260,13 @@ impl Arguments {
         }
     }
 
-    /// Filters comments using the provided predicate.
-    pub(crate) fn filter_comments(&mut self, filter: impl Fn(&super::Trivia) -> bool) {
+    /// Mutates the trivia of the tokens in these arguments.
+    pub(crate) fn mutate_trivia(
+        &mut self,
+        mutate: impl Fn(&mut Vec<super::Trivia>, super::TriviaSide),
+    ) {
         match self {
-            Arguments::Tuple(tuple) => tuple.filter_comments(filter),
+            Arguments::Tuple(tuple) => tuple.mutate_trivia(mutate),
             Arguments::String(_) | Arguments::Table(_) => {}
         }
     }
@@ -10,6 +10,7 @@ use crate::nodes::{LastStatement, ReturnStatement, Statement, Token};
 ///
 /// Typically created by the parser to preserve source formatting for roundtrip transformations.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockTokens {
     pub semicolons: Vec<Option<Token>>,
     pub last_semicolon: Option<Token>,
@@ -25,6 +26,7 @@ impl BlockTokens {
 
 /// Represents a block, a collection of [`Statement`]s that can end with a [`LastStatement`].
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     statements: Vec<Statement>,
     last_statement: Option<LastStatement>,
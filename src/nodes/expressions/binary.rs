@@ -2,6 +2,7 @@ use crate::nodes::{Expression, FunctionReturnType, Token, Type};
 
 /// Represents binary operators used in a binary expression.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinaryOperator {
     /// Logical AND operator (`and`)
     And,
@@ -238,6 +239,7 @@ impl BinaryOperator {
 
 /// Represents a binary operation in expressions.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct BinaryExpression {
     operator: BinaryOperator,
     left: Expression,
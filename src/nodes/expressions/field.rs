@@ -6,6 +6,7 @@ use crate::nodes::{Identifier, Prefix, Token};
 /// such as `table.field`. It consists of a prefix (the table being accessed)
 /// and a field identifier.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FieldExpression {
     prefix: Prefix,
     field: Identifier,
@@ -14,6 +14,7 @@ use crate::nodes::{
 /// end
 /// ```
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionExpression {
     block: Block,
     parameters: Vec<TypedIdentifier>,
@@ -4,6 +4,7 @@ use super::Expression;
 
 /// Represents an if expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct IfExpression {
     condition: Expression,
     result: Expression,
@@ -148,6 +149,7 @@ impl IfExpression {
 ///
 /// Each branch has a condition and a result expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ElseIfExpressionBranch {
     condition: Expression,
     result: Expression,
@@ -210,6 +212,7 @@ impl ElseIfExpressionBranch {
 
 /// Contains token information for an if expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct IfExpressionTokens {
     /// The 'if' keyword token
     pub r#if: Token,
@@ -225,6 +228,7 @@ impl IfExpressionTokens {
 
 /// Contains token information for an elseif branch in an if expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ElseIfExpressionBranchTokens {
     /// The 'elseif' keyword token
     pub elseif: Token,
@@ -2,6 +2,7 @@ use crate::nodes::{Expression, Prefix, Token};
 
 /// Contains token information for an index expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct IndexExpressionTokens {
     /// The opening bracket token
     pub opening_bracket: Token,
@@ -19,6 +20,7 @@ impl IndexExpressionTokens {
 /// such as `table[key]`. It consists of a prefix (the table being accessed)
 /// and an index expression that evaluates to the key.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct IndexExpression {
     prefix: Prefix,
     index: Expression,
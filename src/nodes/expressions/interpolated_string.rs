@@ -1,6 +1,6 @@
 use std::iter::FromIterator;
 
-use crate::nodes::{IntoLuaStringValue, StringError, Token, Trivia};
+use crate::nodes::{IntoLuaStringValue, StringError, Token, Trivia, TriviaSide};
 
 use super::{string_utils, Expression};
 
@@ -9,6 +9,7 @@ use super::{string_utils, Expression};
 /// String segments are the literal text parts of an interpolated string,
 /// appearing between expression segments.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringSegment {
     value: Vec<u8>,
     token: Option<Token>,
@@ -82,6 +83,7 @@ impl StringSegment {
 /// Value segments contain expressions that are evaluated and converted to strings
 /// when the interpolated string is evaluated.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueSegment {
     value: Box<Expression>,
     tokens: Option<ValueSegmentTokens>,
@@ -127,6 +129,7 @@ impl ValueSegment {
 
 /// Contains token information for a value segment in an interpolated string.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueSegmentTokens {
     /// The opening brace token (`{`)
     pub opening_brace: Token,
@@ -140,6 +143,7 @@ impl ValueSegmentTokens {
 
 /// Represents a segment in an interpolated string.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum InterpolationSegment {
     /// A literal string segment
     String(StringSegment),
@@ -178,10 +182,10 @@ impl InterpolationSegment {
         }
     }
 
-    pub(crate) fn filter_comments(&mut self, filter: impl Fn(&Trivia) -> bool) {
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl Fn(&mut Vec<Trivia>, TriviaSide)) {
         match self {
-            InterpolationSegment::String(segment) => segment.filter_comments(filter),
-            InterpolationSegment::Value(segment) => segment.filter_comments(filter),
+            InterpolationSegment::String(segment) => segment.mutate_trivia(mutate),
+            InterpolationSegment::Value(segment) => segment.mutate_trivia(mutate),
         }
     }
 }
@@ -224,6 +228,7 @@ impl From<String> for InterpolationSegment {
 
 /// Represents an interpolated string expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterpolatedStringExpression {
     segments: Vec<InterpolationSegment>,
     tokens: Option<InterpolatedStringTokens>,
@@ -327,6 +332,7 @@ impl FromIterator<InterpolationSegment> for InterpolatedStringExpression {
 
 /// Contains token information for an interpolated string expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterpolatedStringTokens {
     /// The opening backtick token
     pub opening_tick: Token,
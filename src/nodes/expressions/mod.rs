@@ -36,6 +36,7 @@ use std::num::FpCategory;
 
 /// Represents all possible expressions.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expression {
     /// A binary operation (e.g., `a + b`, `x == y`)
     Binary(Box<BinaryExpression>),
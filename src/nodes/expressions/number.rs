@@ -1,10 +1,11 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::str::FromStr;
 
-use crate::nodes::{Token, Trivia};
+use crate::nodes::{Token, Trivia, TriviaSide};
 
 /// Represents a decimal number.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct DecimalNumber {
     float: f64,
     exponent: Option<(i64, bool)>,
@@ -93,6 +94,7 @@ impl DecimalNumber {
 /// Hexadecimal numbers are prefixed with '0x' or '0X' and can include
 /// optional binary exponents.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct HexNumber {
     integer: u64,
     exponent: Option<(u32, bool)>,
@@ -193,6 +195,7 @@ impl HexNumber {
 ///
 /// Binary numbers are prefixed with '0b' or '0B' and consist of 0s and 1s.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct BinaryNumber {
     value: u64,
     is_b_uppercase: bool,
@@ -263,6 +266,7 @@ impl BinaryNumber {
 
 /// Represents a numeric literal expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum NumberExpression {
     /// A decimal number (e.g., `123.45`, `1e10`)
     Decimal(DecimalNumber),
@@ -365,11 +369,11 @@ impl NumberExpression {
         }
     }
 
-    pub(crate) fn filter_comments(&mut self, filter: impl Fn(&Trivia) -> bool) {
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl Fn(&mut Vec<Trivia>, TriviaSide)) {
         match self {
-            NumberExpression::Decimal(number) => number.filter_comments(filter),
-            NumberExpression::Hex(number) => number.filter_comments(filter),
-            NumberExpression::Binary(number) => number.filter_comments(filter),
+            NumberExpression::Decimal(number) => number.mutate_trivia(mutate),
+            NumberExpression::Hex(number) => number.mutate_trivia(mutate),
+            NumberExpression::Binary(number) => number.mutate_trivia(mutate),
         }
     }
 }
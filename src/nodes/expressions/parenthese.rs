@@ -2,6 +2,7 @@ use crate::nodes::{Expression, Token};
 
 /// Contains token information for a parenthesized expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParentheseTokens {
     /// The left (opening) parenthesis token
     pub left_parenthese: Token,
@@ -15,6 +16,7 @@ impl ParentheseTokens {
 
 /// Represents a parenthesized expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParentheseExpression {
     expression: Expression,
     tokens: Option<ParentheseTokens>,
@@ -7,6 +7,7 @@ use crate::nodes::{
 /// Prefix expressions form the base for more complex expressions like method calls
 /// and property access chains.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum Prefix {
     /// A function call expression (e.g., `func()`)
     Call(Box<FunctionCall>),
@@ -7,9 +7,40 @@ use super::string_utils;
 /// String literals in Lua can be written with single quotes, double quotes,
 /// or with long brackets (`[[...]]` or `[=[...]=]` etc.) for multi-line strings.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringExpression {
     value: Vec<u8>,
     token: Option<Token>,
+    quote: Option<StringQuote>,
+}
+
+/// The quote character used to write a string literal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
+pub enum StringQuote {
+    /// A string delimited by `'`.
+    Single,
+    /// A string delimited by `"`.
+    Double,
+}
+
+impl StringQuote {
+    /// Returns the quote used by a string literal, like `'hello'` or `"hello"`.
+    pub fn from_literal(literal: &str) -> Option<Self> {
+        match literal.chars().next()? {
+            '\'' => Some(Self::Single),
+            '"' => Some(Self::Double),
+            _ => None,
+        }
+    }
+
+    /// Returns the quote character.
+    pub fn symbol(&self) -> char {
+        match self {
+            Self::Single => '\'',
+            Self::Double => '"',
+        }
+    }
 }
 
 impl StringExpression {
@@ -69,6 +100,7 @@ impl StringExpression {
         Self {
             value: b"".to_vec(),
             token: None,
+            quote: None,
         }
     }
 
@@ -77,9 +109,29 @@ impl StringExpression {
         Self {
             value: value.into_lua_string_value(),
             token: None,
+            quote: None,
         }
     }
 
+    /// Sets the quote used when this string is generated without its token. Without a
+    /// quote, generators pick the quote that needs the least escaping.
+    pub fn with_quote(mut self, quote: StringQuote) -> Self {
+        self.quote = Some(quote);
+        self
+    }
+
+    /// Sets the quote used when this string is generated without its token.
+    #[inline]
+    pub fn set_quote(&mut self, quote: StringQuote) {
+        self.quote = Some(quote);
+    }
+
+    /// Returns the quote used when this string is generated without its token, if any.
+    #[inline]
+    pub fn get_quote(&self) -> Option<StringQuote> {
+        self.quote
+    }
+
     /// Attaches a token to this string expression.
     pub fn with_token(mut self, token: Token) -> Self {
         self.token = Some(token);
@@ -1,5 +1,5 @@
 use crate::{
-    nodes::{Expression, Identifier, Token, Trivia},
+    nodes::{Expression, Identifier, Token, Trivia, TriviaSide},
     process::utils::is_valid_identifier,
 };
 
@@ -9,6 +9,7 @@ use super::StringExpression;
 ///
 /// This corresponds to the form: `{ field = value }`
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableFieldEntry {
     field: Identifier,
     value: Expression,
@@ -75,6 +76,7 @@ impl TableFieldEntry {
 
 /// Contains tokens for a table index entry.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableIndexEntryTokens {
     /// Token for the opening bracket `[`
     pub opening_bracket: Token,
@@ -90,6 +92,7 @@ impl TableIndexEntryTokens {
 
 /// Represents an index entry in a table literal where the key is a computed expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableIndexEntry {
     key: Expression,
     value: Expression,
@@ -153,6 +156,7 @@ impl TableIndexEntry {
 
 /// Represents a single entry in a table literal.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum TableEntry {
     /// A named field entry (e.g., `{ field = value }`)
     Field(Box<TableFieldEntry>),
@@ -224,10 +228,10 @@ impl TableEntry {
         }
     }
 
-    pub(crate) fn filter_comments(&mut self, filter: impl Fn(&Trivia) -> bool) {
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl Fn(&mut Vec<Trivia>, TriviaSide)) {
         match self {
-            TableEntry::Field(entry) => entry.filter_comments(filter),
-            TableEntry::Index(entry) => entry.filter_comments(filter),
+            TableEntry::Field(entry) => entry.mutate_trivia(mutate),
+            TableEntry::Index(entry) => entry.mutate_trivia(mutate),
             TableEntry::Value(_) => {}
         }
     }
@@ -247,6 +251,7 @@ impl From<TableIndexEntry> for TableEntry {
 
 /// Contains tokens for a table expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableTokens {
     /// Token for the opening brace `{`
     pub opening_brace: Token,
@@ -265,6 +270,7 @@ impl TableTokens {
 
 /// Represents a table expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableExpression {
     entries: Vec<TableEntry>,
     tokens: Option<TableTokens>,
@@ -4,6 +4,7 @@ use crate::nodes::{Expression, Token, Type};
 ///
 /// This corresponds to expressions like: `expression :: type`
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeCastExpression {
     expression: Box<Expression>,
     r#type: Box<Type>,
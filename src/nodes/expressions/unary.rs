@@ -2,6 +2,7 @@ use crate::nodes::{Expression, Token};
 
 /// Represents the type of operator in a unary expression.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnaryOperator {
     /// The length operator (`#`)
     Length,
@@ -24,6 +25,7 @@ impl UnaryOperator {
 
 /// Represents a unary operation applied to an expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnaryExpression {
     operator: UnaryOperator,
     expression: Expression,
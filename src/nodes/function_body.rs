@@ -226,6 +226,7 @@ impl FunctionBuilder {
 
 /// Represents the token information associated with a function body.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionBodyTokens {
     /// The 'function' keyword token.
     pub function: Token,
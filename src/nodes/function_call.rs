@@ -2,6 +2,7 @@ use crate::nodes::{Arguments, Expression, Identifier, Prefix, Token};
 
 /// Tokens associated with a function call.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionCallTokens {
     pub colon: Option<Token>,
 }
@@ -12,6 +13,7 @@ impl FunctionCallTokens {
 
 /// Represents a function call expression (e.g., `func()`, `obj:method()`, `a.b.c()`).
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionCall {
     prefix: Box<Prefix>,
     arguments: Arguments,
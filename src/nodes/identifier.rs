@@ -4,6 +4,7 @@ use super::{Type, TypedIdentifier};
 
 /// Represents an identifier (variable name).
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct Identifier {
     name: String,
     token: Option<Token>,
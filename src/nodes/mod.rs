@@ -7,6 +7,7 @@ mod function_body;
 mod function_call;
 mod identifier;
 mod statements;
+mod structural;
 mod token;
 mod typed_identifier;
 mod types;
@@ -96,18 +97,18 @@ macro_rules! impl_token_fns {
             )*)?
         }
 
-        pub(crate) fn filter_comments(&mut self, filter: impl Fn(&crate::nodes::Trivia) -> bool) {
+        pub(crate) fn mutate_trivia(&mut self, mutate: impl Fn(&mut Vec<crate::nodes::Trivia>, crate::nodes::TriviaSide)) {
             $(
-                self.$field.filter_comments(&filter);
+                self.$field.mutate_trivia(&mutate);
             )*
             $($(
                 for token in self.$iter_field.iter_mut() {
-                    token.filter_comments(&filter);
+                    token.mutate_trivia(&mutate);
                 }
             )*)?
             $($(
                 for token in self.$iter_flatten_field.iter_mut().flatten() {
-                    token.filter_comments(&filter);
+                    token.mutate_trivia(&mutate);
                 }
             )*)?
         }
@@ -2,6 +2,7 @@ use crate::nodes::{Expression, Token, Variable};
 
 /// Tokens associated with an assignment statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssignTokens {
     pub equal: Token,
     pub variable_commas: Vec<Token>,
@@ -17,6 +18,7 @@ impl AssignTokens {
 
 /// Represents a variable assignment statement (e.g., `a, b = 1, 2`).
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssignStatement {
     variables: Vec<Variable>,
     values: Vec<Expression>,
@@ -2,6 +2,7 @@ use crate::nodes::{BinaryOperator, Expression, Token, Variable};
 
 /// Represents compound assignment operators (e.g., `+=`, `-=`, etc.).
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum CompoundOperator {
     /// Addition and assignment (`+=`)
     Plus,
@@ -53,6 +54,7 @@ impl CompoundOperator {
 
 /// Tokens associated with a compound assignment statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct CompoundAssignTokens {
     /// The operator token for the compound assignment.
     pub operator: Token,
@@ -64,6 +66,7 @@ impl CompoundAssignTokens {
 
 /// Represents a compound assignment statement (e.g., `a += 1`).
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct CompoundAssignStatement {
     operator: CompoundOperator,
     variable: Variable,
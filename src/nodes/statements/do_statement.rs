@@ -2,6 +2,7 @@ use crate::nodes::{Block, Token};
 
 /// Tokens associated with a do statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct DoTokens {
     pub r#do: Token,
     pub end: Token,
@@ -13,6 +14,7 @@ impl DoTokens {
 
 /// Represents a do statement (e.g., `do ... end`).
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct DoStatement {
     block: Block,
     tokens: Option<DoTokens>,
@@ -5,6 +5,7 @@ use crate::nodes::{
 
 /// Tokens associated with a function name.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionNameTokens {
     /// The tokens for the periods in the function name.
     pub periods: Vec<Token>,
@@ -21,6 +22,7 @@ impl FunctionNameTokens {
 /// Function names can include table fields and methods
 /// ([e.g., `module.table:method`]).
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionName {
     name: Identifier,
     field_names: Vec<Identifier>,
@@ -137,6 +139,7 @@ impl FunctionName {
 
 /// Represents a function declaration statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionStatement {
     name: FunctionName,
     block: Block,
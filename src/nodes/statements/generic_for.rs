@@ -2,6 +2,7 @@ use crate::nodes::{Block, Expression, Token, TypedIdentifier};
 
 /// Tokens associated with a generic for statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenericForTokens {
     pub r#for: Token,
     pub r#in: Token,
@@ -22,6 +23,7 @@ impl GenericForTokens {
 
 /// Represents a generic for loop statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenericForStatement {
     identifiers: Vec<TypedIdentifier>,
     expressions: Vec<Expression>,
@@ -4,6 +4,7 @@ use crate::nodes::{Block, Expression, Token};
 
 /// Tokens associated with an if branch.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct IfBranchTokens {
     pub elseif: Token,
     pub then: Token,
@@ -15,6 +16,7 @@ impl IfBranchTokens {
 
 /// Represents a conditional branch in an if statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct IfBranch {
     condition: Expression,
     block: Block,
@@ -93,6 +95,7 @@ impl IfBranch {
 
 /// Tokens associated with an if statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct IfStatementTokens {
     pub r#if: Token,
     pub then: Token,
@@ -109,6 +112,7 @@ impl IfStatementTokens {
 
 /// Represents an if statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct IfStatement {
     branches: Vec<IfBranch>,
     else_block: Option<Block>,
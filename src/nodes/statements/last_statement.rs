@@ -2,6 +2,7 @@ use crate::nodes::{Expression, Token};
 
 /// Tokens associated with a return statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReturnTokens {
     pub r#return: Token,
     /// The tokens for the commas between expressions.
@@ -17,6 +18,7 @@ impl ReturnTokens {
 
 /// Represents a return statement.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReturnStatement {
     expressions: Vec<Expression>,
     tokens: Option<ReturnTokens>,
@@ -112,6 +114,7 @@ impl ReturnStatement {
 
 /// Represents a statement that can appear as the last statement in a block.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum LastStatement {
     Break(Option<Token>),
     Continue(Option<Token>),
@@ -2,6 +2,7 @@ use crate::nodes::{Expression, Token, TypedIdentifier};
 
 /// Tokens associated with a local variable assignment statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalAssignTokens {
     pub local: Token,
     /// The token for the equal sign, if any.
@@ -21,6 +22,7 @@ impl LocalAssignTokens {
 
 /// Represents a local variable assignment statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalAssignStatement {
     variables: Vec<TypedIdentifier>,
     values: Vec<Expression>,
@@ -5,6 +5,7 @@ use crate::nodes::{
 
 /// Tokens associated with a local function statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalFunctionTokens {
     pub local: Token,
     pub function_body: FunctionBodyTokens,
@@ -30,6 +31,7 @@ impl std::ops::DerefMut for LocalFunctionTokens {
 
 /// Represents a local function declaration statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalFunctionStatement {
     identifier: Identifier,
     block: Block,
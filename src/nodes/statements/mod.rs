@@ -32,6 +32,7 @@ use super::impl_token_fns;
 
 /// Represents all possible statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum Statement {
     /// An assignment statement (e.g., `a = 1`)
     Assign(AssignStatement),
@@ -2,6 +2,7 @@ use crate::nodes::{Block, Expression, Token, TypedIdentifier};
 
 /// Tokens associated with a numeric for statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct NumericForTokens {
     pub r#for: Token,
     pub equal: Token,
@@ -22,6 +23,7 @@ impl NumericForTokens {
 
 /// Represents a numeric for loop statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct NumericForStatement {
     identifier: TypedIdentifier,
     start: Expression,
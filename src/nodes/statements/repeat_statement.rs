@@ -2,6 +2,7 @@ use crate::nodes::{Block, Expression, Token};
 
 /// Tokens associated with a repeat statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct RepeatTokens {
     pub repeat: Token,
     pub until: Token,
@@ -13,6 +14,7 @@ impl RepeatTokens {
 
 /// Represents a repeat loop statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct RepeatStatement {
     block: Block,
     condition: Expression,
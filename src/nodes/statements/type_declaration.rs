@@ -1,9 +1,11 @@
 use crate::nodes::{
-    GenericParameterMutRef, GenericParametersWithDefaults, Identifier, Token, Trivia, Type,
+    GenericParameterMutRef, GenericParametersWithDefaults, Identifier, Token, Trivia, TriviaSide,
+    Type,
 };
 
 /// Tokens associated with a type declaration statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeDeclarationTokens {
     pub r#type: Token,
     pub equal: Token,
@@ -19,6 +21,7 @@ impl TypeDeclarationTokens {
 
 /// Represents a type declaration statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeDeclarationStatement {
     name: Identifier,
     r#type: Box<Type>,
@@ -251,27 +254,27 @@ impl TypeDeclarationStatement {
         }
     }
 
-    pub(crate) fn filter_comments(&mut self, filter: impl Fn(&Trivia) -> bool) {
-        self.name.filter_comments(&filter);
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl Fn(&mut Vec<Trivia>, TriviaSide)) {
+        self.name.mutate_trivia(&mutate);
         if let Some(tokens) = &mut self.tokens {
-            tokens.filter_comments(&filter);
+            tokens.mutate_trivia(&mutate);
         }
         if let Some(parameters) = self.generic_parameters.as_mut() {
-            parameters.filter_comments(&filter);
+            parameters.mutate_trivia(&mutate);
 
             for parameter in parameters {
                 match parameter {
                     GenericParameterMutRef::TypeVariable(variable) => {
-                        variable.filter_comments(&filter);
+                        variable.mutate_trivia(&mutate);
                     }
                     GenericParameterMutRef::TypeVariableWithDefault(variable_with_default) => {
-                        variable_with_default.filter_comments(&filter);
+                        variable_with_default.mutate_trivia(&mutate);
                     }
                     GenericParameterMutRef::GenericTypePack(_) => {}
                     GenericParameterMutRef::GenericTypePackWithDefault(
                         generic_pack_with_default,
                     ) => {
-                        generic_pack_with_default.filter_comments(&filter);
+                        generic_pack_with_default.mutate_trivia(&mutate);
                     }
                 }
             }
@@ -2,6 +2,7 @@ use crate::nodes::{token::Token, Block, Expression};
 
 /// Tokens associated with a while statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct WhileTokens {
     pub r#while: Token,
     pub r#do: Token,
@@ -14,6 +15,7 @@ impl WhileTokens {
 
 /// Represents a while loop statement.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct WhileStatement {
     block: Block,
     condition: Expression,
@@ -0,0 +1,348 @@
+use std::mem;
+
+use crate::generator::{LuaGenerator, ReadableLuaGenerator};
+use crate::nodes::{Block, LastStatement, Statement};
+use crate::Parser;
+
+impl Block {
+    /// Returns true if both blocks have the same structure, ignoring their tokens (and
+    /// therefore their comments and whitespaces).
+    pub fn structural_eq(&self, other: &Block) -> bool {
+        self.structural_diff(other).is_none()
+    }
+
+    /// Compares the structure of both blocks, ignoring their tokens (and therefore their
+    /// comments and whitespaces). When the blocks are different, a human-readable description
+    /// of the first different node and its path in the block is returned. Tokens are removed
+    /// by generating the code of each block and parsing it back, so if the generated code of a
+    /// block cannot be parsed, the parser error is returned instead. A block is always equal
+    /// to itself.
+    ///
+    /// ```
+    /// # use darklua_core::Parser;
+    /// let parser = Parser::default();
+    /// let block = parser.parse("do local a = 1 end").unwrap();
+    /// let other = parser.parse("do local a = 2 end").unwrap();
+    ///
+    /// let difference = block.structural_diff(&other).unwrap();
+    ///
+    /// assert!(difference.contains("statements[0].block.statements[0]"));
+    /// ```
+    pub fn structural_diff(&self, other: &Block) -> Option<String> {
+        if self == other {
+            return None;
+        }
+
+        match (without_tokens(self), without_tokens(other)) {
+            (Ok(left), Ok(right)) => diff_block("", &left, &right),
+            (Err(error), _) | (_, Err(error)) => Some(error),
+        }
+    }
+}
+
+/// Asserts that two strings of Lua code have the same structure, ignoring comments and
+/// whitespaces. When the code is different, the panic message contains the path of the first
+/// different node.
+///
+/// ```
+/// darklua_core::assert_block_equivalent!(
+///     "local a = 1 -- comment",
+///     "local   a=1",
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_block_equivalent {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let left: &str = ::std::convert::AsRef::<str>::as_ref(left);
+                let right: &str = ::std::convert::AsRef::<str>::as_ref(right);
+
+                let parse = |code: &str| {
+                    $crate::Parser::default()
+                        .parse(code)
+                        .unwrap_or_else(|error| {
+                            panic!("could not parse content: {:?}\ncontent:\n{}", error, code)
+                        })
+                };
+
+                if let Some(difference) = parse(left).structural_diff(&parse(right)) {
+                    panic!(
+                        "blocks are not equivalent: {}\n\nleft:\n{}\n\nright:\n{}",
+                        difference, left, right
+                    );
+                }
+            }
+        }
+    };
+}
+
+// generating the code and parsing it back drops the tokens of every node
+fn without_tokens(block: &Block) -> Result<Block, String> {
+    let mut generator = ReadableLuaGenerator::default();
+    generator.write_block(block);
+    let code = generator.into_string();
+
+    Parser::default().parse(&code).map_err(|error| {
+        format!(
+            "unable to parse the generated code of a block: {}\ncode:\n{}",
+            error, code
+        )
+    })
+}
+
+fn statement_code(statement: &Statement) -> String {
+    let mut generator = ReadableLuaGenerator::default();
+    generator.write_statement(statement);
+    generator.into_string().trim().to_owned()
+}
+
+fn last_statement_code(statement: &LastStatement) -> String {
+    let mut generator = ReadableLuaGenerator::default();
+    generator.write_last_statement(statement);
+    generator.into_string().trim().to_owned()
+}
+
+fn describe(path: &str, left: Option<String>, right: Option<String>) -> String {
+    format!(
+        "`{}` is different\n  left:  {}\n  right: {}",
+        path,
+        left.unwrap_or_else(|| "<missing>".to_owned()),
+        right.unwrap_or_else(|| "<missing>".to_owned()),
+    )
+}
+
+fn join_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn diff_block(path: &str, left: &Block, right: &Block) -> Option<String> {
+    let statement_path = |index: usize| join_path(path, &format!("statements[{}]", index));
+
+    for (index, (left_statement, right_statement)) in left
+        .iter_statements()
+        .zip(right.iter_statements())
+        .enumerate()
+    {
+        if left_statement != right_statement {
+            return Some(diff_statement(
+                &statement_path(index),
+                left_statement,
+                right_statement,
+            ));
+        }
+    }
+
+    if left.statements_len() != right.statements_len() {
+        let index = left.statements_len().min(right.statements_len());
+
+        return Some(describe(
+            &statement_path(index),
+            left.iter_statements().nth(index).map(statement_code),
+            right.iter_statements().nth(index).map(statement_code),
+        ));
+    }
+
+    let left_last = left.get_last_statement();
+    let right_last = right.get_last_statement();
+
+    if left_last != right_last {
+        return Some(describe(
+            &join_path(path, "last_statement"),
+            left_last.map(last_statement_code),
+            right_last.map(last_statement_code),
+        ));
+    }
+
+    None
+}
+
+fn diff_statement(path: &str, left: &Statement, right: &Statement) -> String {
+    let (left_header, left_blocks) = split_blocks(left);
+    let (right_header, right_blocks) = split_blocks(right);
+
+    // when only the blocks are different, look for the difference inside them
+    if left_header == right_header && left_blocks.len() == right_blocks.len() {
+        let difference = left_blocks.iter().zip(right_blocks.iter()).find_map(
+            |((name, left_block), (_, right_block))| {
+                diff_block(&join_path(path, name), left_block, right_block)
+            },
+        );
+
+        if let Some(difference) = difference {
+            return difference;
+        }
+    }
+
+    describe(
+        path,
+        Some(statement_code(left)),
+        Some(statement_code(right)),
+    )
+}
+
+// returns a copy of the statement without its blocks, and the blocks with their path
+fn split_blocks(statement: &Statement) -> (Statement, Vec<(String, Block)>) {
+    let mut header = statement.clone();
+    let mut blocks = Vec::new();
+
+    match &mut header {
+        Statement::Do(do_statement) => {
+            blocks.push(("block".to_owned(), mem::take(do_statement.mutate_block())));
+        }
+        Statement::Function(function) => {
+            blocks.push(("block".to_owned(), mem::take(function.mutate_block())));
+        }
+        Statement::GenericFor(generic_for) => {
+            blocks.push(("block".to_owned(), mem::take(generic_for.mutate_block())));
+        }
+        Statement::If(if_statement) => {
+            for (index, branch) in if_statement.mutate_branches().iter_mut().enumerate() {
+                blocks.push((
+                    format!("branches[{}].block", index),
+                    mem::take(branch.mutate_block()),
+                ));
+            }
+            if let Some(else_block) = if_statement.mutate_else_block() {
+                blocks.push(("else_block".to_owned(), mem::take(else_block)));
+            }
+        }
+        Statement::LocalFunction(function) => {
+            blocks.push(("block".to_owned(), mem::take(function.mutate_block())));
+        }
+        Statement::NumericFor(numeric_for) => {
+            blocks.push(("block".to_owned(), mem::take(numeric_for.mutate_block())));
+        }
+        Statement::Repeat(repeat) => {
+            blocks.push(("block".to_owned(), mem::take(repeat.mutate_block())));
+        }
+        Statement::While(while_statement) => {
+            blocks.push((
+                "block".to_owned(),
+                mem::take(while_statement.mutate_block()),
+            ));
+        }
+        Statement::Assign(_)
+        | Statement::Call(_)
+        | Statement::CompoundAssign(_)
+        | Statement::LocalAssign(_)
+        | Statement::TypeDeclaration(_) => {}
+    }
+
+    (header, blocks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(code: &str) -> Block {
+        Parser::default().parse(code).expect("code should parse")
+    }
+
+    fn parse_with_tokens(code: &str) -> Block {
+        Parser::default()
+            .preserve_tokens()
+            .parse(code)
+            .expect("code should parse")
+    }
+
+    #[test]
+    fn ignores_tokens() {
+        let block = parse_with_tokens("local a = 1 -- comment\nreturn a");
+        let other = parse_with_tokens("local   a=1\n\nreturn    a");
+
+        assert_ne!(block, other);
+        assert!(block.structural_eq(&other));
+    }
+
+    #[test]
+    fn different_statement() {
+        pretty_assertions::assert_eq!(
+            parse("local a = 1 print(a)").structural_diff(&parse("local a = 2 print(a)")),
+            Some(
+                "`statements[0]` is different\n  left:  local a = 1\n  right: local a = 2"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn missing_statement() {
+        pretty_assertions::assert_eq!(
+            parse("local a = 1").structural_diff(&parse("local a = 1 print(a)")),
+            Some("`statements[1]` is different\n  left:  <missing>\n  right: print(a)".to_owned())
+        );
+    }
+
+    #[test]
+    fn different_last_statement() {
+        pretty_assertions::assert_eq!(
+            parse("return 1").structural_diff(&parse("return 2")),
+            Some("`last_statement` is different\n  left:  return 1\n  right: return 2".to_owned())
+        );
+    }
+
+    #[test]
+    fn difference_inside_nested_block() {
+        let difference = parse("if a then elseif b then while c do call() end end")
+            .structural_diff(&parse("if a then elseif b then while c do other() end end"))
+            .unwrap();
+
+        assert!(difference
+            .starts_with("`statements[0].branches[1].block.statements[0].block.statements[0]`"));
+    }
+
+    #[test]
+    fn difference_in_statement_header() {
+        let difference = parse("while a do call() end")
+            .structural_diff(&parse("while b do other() end"))
+            .unwrap();
+
+        assert!(difference.starts_with("`statements[0]` is different"));
+    }
+
+    #[test]
+    fn different_blocks_with_many_statements() {
+        let code: String = (0..1000).map(|i| format!("print({})\n", i)).collect();
+
+        pretty_assertions::assert_eq!(
+            parse(&code).structural_diff(&parse(&format!("{}print(1000)", code))),
+            Some(
+                "`statements[1000]` is different\n  left:  <missing>\n  right: print(1000)"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn reports_generated_code_that_cannot_be_parsed() {
+        let invalid = Block::default().with_statement(crate::nodes::FunctionCall::from_name("end"));
+
+        let difference = invalid.structural_diff(&parse("print()")).unwrap();
+
+        assert!(difference.starts_with("unable to parse the generated code of a block"));
+    }
+
+    #[test]
+    fn block_is_structurally_equal_to_itself() {
+        let invalid = Block::default().with_statement(crate::nodes::FunctionCall::from_name("end"));
+
+        assert!(invalid.structural_eq(&invalid));
+    }
+
+    #[test]
+    fn assert_block_equivalent_passes() {
+        crate::assert_block_equivalent!("local a = {1, 2}", "local a = { 1, 2, }");
+    }
+
+    #[test]
+    #[should_panic(expected = "blocks are not equivalent")]
+    fn assert_block_equivalent_fails() {
+        crate::assert_block_equivalent!("local a = 1", String::from("local b = 1"));
+    }
+}
@@ -2,6 +2,7 @@ use std::borrow::Cow;
 
 /// Represents a position in the source code.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum Position {
     /// A position that references a specific range in the source code
     /// with line number information.
@@ -50,6 +51,7 @@ impl Position {
 
 /// An enum to represent source code text.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum TriviaKind {
     /// A comment.
     Comment,
@@ -97,6 +99,7 @@ impl TriviaKind {
 
 /// Represents a piece of trivia (whitespace or comments) in the source code.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct Trivia {
     position: Position,
     kind: TriviaKind,
@@ -143,8 +146,19 @@ impl Trivia {
     }
 }
 
+/// The side of a token where trivia are attached.
+///
+/// Leading trivia start at the beginning of a line, while trailing trivia follow the token
+/// until the end of its line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TriviaSide {
+    Leading,
+    Trailing,
+}
+
 /// Represents a token in the source code with its position and associated comments or whitespaces.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct Token {
     position: Position,
     leading_trivia: Vec<Trivia>,
@@ -267,6 +281,11 @@ impl Token {
         self.trailing_trivia.push(trivia);
     }
 
+    /// Inserts trivia before the existing leading trivia of the token.
+    pub(crate) fn insert_leading_trivia(&mut self, trivia: impl IntoIterator<Item = Trivia>) {
+        self.leading_trivia.splice(0..0, trivia);
+    }
+
     /// Returns an iterator over the leading trivia.
     #[inline]
     pub fn iter_leading_trivia(&self) -> impl Iterator<Item = &Trivia> {
@@ -292,6 +311,15 @@ impl Token {
         }
     }
 
+    /// Reads the content of the token from the source code, or returns `None` when the
+    /// position does not fit in the given code.
+    pub(crate) fn try_read_from<'a: 'b, 'b>(&'a self, code: &'b str) -> Option<&'b str> {
+        match &self.position {
+            Position::LineNumberReference { start, end, .. } => code.get(*start..*end),
+            Position::LineNumber { content, .. } | Position::Any { content } => Some(content),
+        }
+    }
+
     /// Returns the line number of the token, if available.
     pub fn get_line_number(&self) -> Option<usize> {
         match &self.position {
@@ -345,11 +373,11 @@ impl Token {
             .retain(|trivia| trivia.kind() != TriviaKind::Whitespace);
     }
 
-    pub(crate) fn filter_comments(&mut self, filter: impl Fn(&Trivia) -> bool) {
-        self.leading_trivia
-            .retain(|trivia| trivia.kind() != TriviaKind::Comment || filter(trivia));
-        self.trailing_trivia
-            .retain(|trivia| trivia.kind() != TriviaKind::Comment || filter(trivia));
+    /// Mutates the leading and trailing trivia of the token. Rules that rewrite comments or
+    /// whitespaces go through this function instead of editing the trivia lists directly.
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl Fn(&mut Vec<Trivia>, TriviaSide)) {
+        mutate(&mut self.leading_trivia, TriviaSide::Leading);
+        mutate(&mut self.trailing_trivia, TriviaSide::Trailing);
     }
 
     pub(crate) fn replace_referenced_tokens(&mut self, code: &str) {
@@ -6,6 +6,7 @@ use crate::nodes::{Identifier, Token, Type};
 /// variables and parameters can have explicit type annotations. It stores the
 /// identifier itself, the optional type, and the colon token for source preservation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypedIdentifier {
     name: Identifier,
     r#type: Option<Type>,
@@ -4,6 +4,7 @@ use super::Type;
 
 /// Represents an array type annotation (e.g. `{ ElementType }`).
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ArrayType {
     inner_type: Box<Type>,
     tokens: Option<ArrayTypeTokens>,
@@ -53,6 +54,7 @@ impl ArrayType {
 ///
 /// These tokens represent the opening and closing braces in an array type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ArrayTypeTokens {
     /// The opening brace token.
     pub opening_brace: Token,
@@ -2,6 +2,7 @@ use crate::nodes::{Expression, Token};
 
 /// Represents a `typeof(expression)` type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExpressionType {
     expression: Box<Expression>,
     tokens: Option<ExpressionTypeTokens>,
@@ -53,6 +54,7 @@ impl ExpressionType {
 ///
 /// These tokens represent the `typeof` keyword and the parentheses around the expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExpressionTypeTokens {
     /// The `typeof` keyword token.
     pub r#typeof: Token,
@@ -4,6 +4,7 @@ use super::{GenericParameters, GenericTypePack, Type, TypePack, VariadicTypePack
 
 /// Represents a single argument in a function type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionArgumentType {
     argument_type: Type,
     name: Option<Identifier>,
@@ -84,6 +85,7 @@ impl FunctionArgumentType {
 
 /// Represents the return type of a function type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum FunctionReturnType {
     /// A single type return value.
     Type(Box<Type>),
@@ -126,6 +128,7 @@ impl From<VariadicTypePack> for FunctionReturnType {
 
 /// Represents a variadic argument type in a function annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum VariadicArgumentType {
     /// A generic type pack used as a variadic argument.
     GenericTypePack(GenericTypePack),
@@ -147,6 +150,7 @@ impl From<VariadicTypePack> for VariadicArgumentType {
 
 /// Represents a function type annotation in Luau.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionType {
     arguments: Vec<FunctionArgumentType>,
     variadic_argument_type: Option<VariadicArgumentType>,
@@ -289,6 +293,7 @@ impl FunctionType {
 
 /// Represents the tokens associated with a function type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionTypeTokens {
     /// The opening parenthesis token.
     pub opening_parenthese: Token,
@@ -2,6 +2,7 @@ use super::{GenericTypePack, Type};
 
 /// Represents a variadic type in a function signature.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum FunctionVariadicType {
     /// A specific type for variadic arguments.
     Type(Box<Type>),
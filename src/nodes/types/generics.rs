@@ -9,6 +9,7 @@ use super::Type;
 /// Generic type packs represent a pack of types that can be specified later,
 /// written as `T...` where T is a type pack parameter name.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenericTypePack {
     // name ...
     name: Identifier,
@@ -65,6 +66,7 @@ impl GenericTypePack {
 /// Generic parameters allow type signatures to be parameterized,
 /// written as `<T, U...>` in Luau type annotations.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenericParameters {
     // generic type list
     type_variables: Vec<Identifier>,
@@ -176,6 +178,7 @@ impl GenericParameters {
 ///
 /// These tokens represent the angle brackets and commas in generic parameters.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenericParametersTokens {
     /// The opening angle bracket token.
     pub opening_list: Token,
@@ -194,6 +197,7 @@ impl GenericParametersTokens {
 
 /// Represents the default value for a generic type pack.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum GenericTypePackDefault {
     /// A type pack default.
     TypePack(Box<TypePack>),
@@ -223,6 +227,7 @@ impl From<GenericTypePack> for GenericTypePackDefault {
 
 /// Represents a generic type pack with a default value.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenericTypePackWithDefault {
     generic_type_pack: GenericTypePack,
     default: GenericTypePackDefault,
@@ -290,6 +295,7 @@ impl GenericTypePackWithDefault {
 
 /// Represents a type variable with a default value.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeVariableWithDefault {
     variable: Identifier,
     default: Type,
@@ -357,6 +363,7 @@ impl TypeVariableWithDefault {
 
 /// Represents a collection of generic parameters that may include default values.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenericParametersWithDefaults {
     type_variables: Vec<Identifier>,
     middle: GenericParametersWithDefaultsMiddle,
@@ -551,6 +558,7 @@ impl GenericParametersWithDefaults {
 
 /// Represents a generic parameter in a type or function signature.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum GenericParameter {
     /// A simple type variable like `T`.
     TypeVariable(Identifier),
@@ -837,6 +845,7 @@ impl<'a> IntoIterator for &'a GenericParametersWithDefaults {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 enum GenericParametersWithDefaultsMiddle {
     Empty,
     GenericTypePacks(Vec<GenericTypePack>),
@@ -6,6 +6,7 @@ use super::Type;
 
 /// Represents an intersection type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntersectionType {
     types: Vec<Type>,
     leading_operator: bool,
@@ -153,6 +154,7 @@ impl iter::FromIterator<Type> for IntersectionType {
 ///
 /// These tokens represent the `&` operators that separate type components.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntersectionTypeTokens {
     /// Optional leading `&` token before the first type.
     pub leading_token: Option<Token>,
@@ -36,6 +36,7 @@ use super::impl_token_fns;
 
 /// Represents a type annotation in Luau.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     /// A named type, such as `string` or a user-defined type.
     Name(TypeName),
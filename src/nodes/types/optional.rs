@@ -4,6 +4,7 @@ use super::Type;
 
 /// Represents an optional type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct OptionalType {
     inner_type: Box<Type>,
     token: Option<Token>,
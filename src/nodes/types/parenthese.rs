@@ -4,6 +4,7 @@ use super::Type;
 
 /// Represents a parenthesized type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParentheseType {
     inner_type: Box<Type>,
     tokens: Option<ParentheseTypeTokens>,
@@ -65,6 +66,7 @@ impl ParentheseType {
 
 /// Contains the tokens that define the parenthesized type syntax.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParentheseTypeTokens {
     /// The left parenthesis token.
     pub left_parenthese: Token,
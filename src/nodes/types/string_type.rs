@@ -2,6 +2,7 @@ use crate::nodes::{StringError, StringExpression, Token};
 
 /// Represents a string literal used in type annotations.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringType {
     value: StringExpression,
 }
@@ -1,9 +1,10 @@
-use crate::nodes::{Identifier, Token, Trivia};
+use crate::nodes::{Identifier, Token, Trivia, TriviaSide};
 
 use super::{StringType, Type};
 
 /// Represents an indexer in a table type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableIndexerType {
     key_type: Box<Type>,
     value_type: Box<Type>,
@@ -67,6 +68,7 @@ impl TableIndexerType {
 
 /// Contains the tokens that define an indexer's syntax.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableIndexTypeTokens {
     /// The opening bracket token.
     pub opening_bracket: Token,
@@ -82,6 +84,7 @@ impl TableIndexTypeTokens {
 
 /// Represents a named property in a table type annotation (i.e. `name: Type`).
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TablePropertyType {
     property: Identifier,
     r#type: Box<Type>,
@@ -145,6 +148,7 @@ impl TablePropertyType {
 
 /// Represents a string literal property in a table type annotation (i.e. `["key"]: Type`).
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableLiteralPropertyType {
     string: StringType,
     r#type: Box<Type>,
@@ -208,6 +212,7 @@ impl TableLiteralPropertyType {
 
 /// Represents an entry in a table type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum TableEntryType {
     /// A named property entry.
     Property(TablePropertyType),
@@ -252,11 +257,11 @@ impl TableEntryType {
         }
     }
 
-    pub(crate) fn filter_comments(&mut self, filter: impl Fn(&Trivia) -> bool) {
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl Fn(&mut Vec<Trivia>, TriviaSide)) {
         match self {
-            TableEntryType::Property(property) => property.filter_comments(filter),
-            TableEntryType::Literal(literal) => literal.filter_comments(filter),
-            TableEntryType::Indexer(indexer) => indexer.filter_comments(filter),
+            TableEntryType::Property(property) => property.mutate_trivia(mutate),
+            TableEntryType::Literal(literal) => literal.mutate_trivia(mutate),
+            TableEntryType::Indexer(indexer) => indexer.mutate_trivia(mutate),
         }
     }
 }
@@ -281,6 +286,7 @@ impl From<TableIndexerType> for TableEntryType {
 
 /// Represents a table type annotation.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableType {
     entries: Vec<TableEntryType>,
     tokens: Option<TableTypeTokens>,
@@ -414,6 +420,7 @@ impl TableType {
 
 /// Contains the tokens that define a table type's syntax.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableTypeTokens {
     /// The opening brace token.
     pub opening_brace: Token,
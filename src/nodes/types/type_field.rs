@@ -4,6 +4,7 @@ use super::TypeName;
 
 /// Represents a field access on a type.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeField {
     namespace: Identifier,
     name: TypeName,
@@ -6,6 +6,7 @@ use super::{GenericTypePack, Type, TypePack, VariadicTypePack};
 
 /// Represents a named type.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeName {
     type_name: Identifier,
     type_parameters: Option<Box<TypeParameters>>,
@@ -82,6 +83,7 @@ impl TypeName {
 /// Type parameters are used in generic types, written as `Array<T>`
 /// or `Map<K, V>` in type annotations.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeParameters {
     parameters: Vec<TypeParameter>,
     tokens: Option<TypeParametersTokens>,
@@ -190,6 +192,7 @@ impl<'a> IntoIterator for &'a TypeParameters {
 
 /// Represents a type parameter in a generic type.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypeParameter {
     /// A single type parameter.
     Type(Type),
@@ -232,6 +235,7 @@ impl From<GenericTypePack> for TypeParameter {
 
 /// Contains the tokens that define the type parameters syntax.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeParametersTokens {
     /// The opening angle bracket token.
     pub opening_list: Token,
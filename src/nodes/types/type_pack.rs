@@ -6,6 +6,7 @@ use super::{Type, VariadicArgumentType};
 
 /// Represents a pack of types.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypePack {
     types: Vec<Type>,
     variadic_type: Option<VariadicArgumentType>,
@@ -145,6 +146,7 @@ impl<'a> IntoIterator for &'a TypePack {
 ///
 /// These tokens represent the parentheses and commas in a type pack.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypePackTokens {
     /// The left parenthesis token.
     pub left_parenthese: Token,
@@ -6,6 +6,7 @@ use super::Type;
 
 /// Represents a union type annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnionType {
     types: Vec<Type>,
     leading_operator: bool,
@@ -145,6 +146,7 @@ impl iter::FromIterator<Type> for UnionType {
 
 /// Contains the tokens that define the union type syntax.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnionTypeTokens {
     /// Optional leading `|` token before the first type.
     pub leading_token: Option<Token>,
@@ -7,6 +7,7 @@ use super::Type;
 /// Variadic type packs represent an arbitrary number of values of the same type,
 /// written with a leading `...` and a type.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub struct VariadicTypePack {
     // ... type
     inner_type: Box<Type>,
@@ -2,6 +2,7 @@ use crate::nodes::{FieldExpression, Identifier, IndexExpression};
 
 /// Represents a variable reference.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-ast", derive(serde::Serialize, serde::Deserialize))]
 pub enum Variable {
     /// A simple named variable (e.g., `x`, `count`, `self`).
     Identifier(Identifier),
@@ -121,6 +121,24 @@ impl ParserError {
             kind: ParserErrorKind::Converting(err).into(),
         }
     }
+
+    /// Returns the line of the first syntax error, if the error can be located.
+    pub fn line(&self) -> Option<usize> {
+        match &*self.kind {
+            ParserErrorKind::Parsing(errors) => errors.first().map(|error| error.range().0.line()),
+            ParserErrorKind::Converting(_) => None,
+        }
+    }
+
+    /// Returns the column of the first syntax error, if the error can be located.
+    pub fn column(&self) -> Option<usize> {
+        match &*self.kind {
+            ParserErrorKind::Parsing(errors) => {
+                errors.first().map(|error| error.range().0.character())
+            }
+            ParserErrorKind::Converting(_) => None,
+        }
+    }
 }
 
 impl fmt::Display for ParserError {
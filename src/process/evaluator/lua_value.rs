@@ -1,15 +1,20 @@
 use crate::nodes::{Expression, NumberExpression, StringExpression};
 
-/// Represents an evaluated Expression result.
+/// Represents an evaluated Expression result, as computed by the
+/// [`Evaluator`](crate::process::Evaluator).
 #[derive(Debug, Clone, PartialEq)]
 pub enum LuaValue {
     False,
+    /// A function, without any information about what it does.
     Function,
     Nil,
     Number(f64),
+    /// A string, as bytes since Lua strings are not required to be valid UTF-8.
     String(Vec<u8>),
+    /// A table, without any information about its content or its metatable.
     Table,
     True,
+    /// A value that could not be evaluated statically.
     Unknown,
 }
 
@@ -68,7 +73,15 @@ impl LuaValue {
         }
     }
 
-    /// Attempt to convert the Lua value into an expression node.
+    /// Attempt to convert the Lua value into an expression node. Functions, tables and
+    /// unknown values cannot be converted.
+    /// ```rust
+    /// # use darklua_core::nodes::Expression;
+    /// # use darklua_core::process::LuaValue;
+    ///
+    /// assert_eq!(LuaValue::Number(1.0).to_expression(), Some(Expression::from(1.0)));
+    /// assert_eq!(LuaValue::Table.to_expression(), None);
+    /// ```
     pub fn to_expression(self) -> Option<Expression> {
         match self {
             Self::False => Some(Expression::from(false)),
@@ -1,8 +1,14 @@
 mod lua_value;
+mod pure_function;
 
 pub use lua_value::*;
+pub(crate) use pure_function::PureCalls;
+pub use pure_function::PureFunction;
+
+use pure_function::PureCall;
 
 use crate::nodes::*;
+use crate::process::IdentifierTracker;
 
 // Add a thread-local flag to control whether instance indexing is treated as pure (no side effects)
 thread_local! {
@@ -15,16 +21,14 @@ thread_local! {
         std::cell::RefCell::new(std::collections::HashSet::new());
 }
 
-/// Enable or disable the global evaluator behavior that treats Roblox instance indexing
-/// (dot/bracket access from `script`/`game`, and calls to `WaitForChild`, `FindFirstChild`,
-/// and `FindFirstAncestor`) as having no side effects.
-pub fn set_instance_indexing_is_pure(enabled: bool) {
+// The worker enables the `instance_indexing_is_pure` behavior and tracks the aliases of the
+// file being processed while it runs, and resets them when it is dropped, so that evaluators
+// created outside of processing are never affected by a configuration.
+pub(crate) fn set_instance_indexing_is_pure(enabled: bool) {
     INSTANCE_INDEXING_IS_PURE.with(|cell| cell.set(enabled));
 }
 
-/// Replace the set of known instance aliases used by the evaluator when
-/// `instance_indexing_is_pure` is enabled.
-pub fn set_known_instance_aliases<I>(aliases: I)
+pub(crate) fn set_known_instance_aliases<I>(aliases: I)
 where
     I: IntoIterator<Item = String>,
 {
@@ -37,16 +41,32 @@ where
     });
 }
 
-/// Clear known instance aliases.
-pub fn clear_known_instance_aliases() {
+pub(crate) fn clear_known_instance_aliases() {
     set_known_instance_aliases(std::iter::empty());
 }
 
+pub(crate) fn reset_instance_indexing() {
+    set_instance_indexing_is_pure(false);
+    clear_known_instance_aliases();
+}
+
 #[inline]
 fn is_instance_indexing_is_pure_enabled() -> bool {
     INSTANCE_INDEXING_IS_PURE.with(|cell| cell.get())
 }
 
+fn may_define_gc_metamethod(table: &TableExpression) -> bool {
+    table.iter_entries().any(|entry| match entry {
+        TableEntry::Field(entry) => entry.get_field().get_name() == "__gc",
+        TableEntry::Index(entry) => match entry.get_key() {
+            Expression::String(string) => string.get_value() == b"__gc",
+            Expression::Number(_) | Expression::True(_) | Expression::False(_) => false,
+            _ => true,
+        },
+        TableEntry::Value(_) => false,
+    })
+}
+
 fn is_string_literal(expression: &Expression) -> bool {
     matches!(expression, Expression::String(s) if s.get_string_value().is_some())
 }
@@ -145,10 +165,47 @@ pub fn is_strict_instance_indexing(expression: &Expression) -> bool {
     }
 }
 
-/// A struct to convert an Expression node into a LuaValue object.
+/// Statically evaluates expressions into [`LuaValue`], and answers questions about them
+/// without running any code.
+///
+/// The evaluator is conservative: when it cannot be sure of the result of an expression,
+/// [`evaluate`](Self::evaluate) returns [`LuaValue::Unknown`] and
+/// [`has_side_effects`](Self::has_side_effects) returns `true`. Identifiers are never
+/// resolved to the values they are assigned, so a variable always evaluates to an unknown
+/// value. Tables and functions evaluate to [`LuaValue::Table`] and [`LuaValue::Function`]
+/// without their content.
+///
+/// ```
+/// # use darklua_core::nodes::{BinaryExpression, BinaryOperator, Expression};
+/// # use darklua_core::process::{Evaluator, LuaValue};
+/// let evaluator = Evaluator::default();
+///
+/// let sum = BinaryExpression::new(BinaryOperator::Plus, 1, 2).into();
+/// assert_eq!(evaluator.evaluate(&sum), LuaValue::Number(3.0));
+/// assert_eq!(
+///     evaluator.evaluate(&sum).to_expression(),
+///     Some(Expression::from(3.0))
+/// );
+///
+/// // the value of a variable is unknown
+/// assert_eq!(
+///     evaluator.evaluate(&Expression::identifier("value")),
+///     LuaValue::Unknown
+/// );
+/// ```
+///
+/// When darklua processes files with the `instance_indexing_is_pure` configuration enabled,
+/// the rules consider indexing Roblox instances from `script` or `game` (and from locals
+/// assigned to these instances) free of side effects. This behavior only applies while
+/// darklua processes files: an evaluator created anywhere else always considers that
+/// indexing an instance can have side effects. The same goes for calls to the functions
+/// listed in the `pure_functions` configuration, which are only known by the evaluators of
+/// the rules that track the scope of the visited nodes.
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Evaluator {
     pure_metamethods: bool,
+    pure_calls: PureCalls,
+    identifier_tracker: IdentifierTracker,
 }
 
 impl Evaluator {
@@ -161,6 +218,27 @@ impl Evaluator {
         self
     }
 
+    /// Considers the given calls free of side effects when their root name does not refer
+    /// to a local variable. The local variables are found in the identifier tracker of the
+    /// evaluator, so the rule must visit the nodes with a
+    /// [`ScopeVisitor`](crate::process::ScopeVisitor) that updates it.
+    pub(crate) fn with_pure_calls(mut self, pure_calls: PureCalls) -> Self {
+        self.pure_calls = pure_calls;
+        self
+    }
+
+    pub(crate) fn identifier_tracker(&self) -> &IdentifierTracker {
+        &self.identifier_tracker
+    }
+
+    pub(crate) fn mutate_identifier_tracker(&mut self) -> &mut IdentifierTracker {
+        &mut self.identifier_tracker
+    }
+
+    /// Evaluates the value of an expression, or returns [`LuaValue::Unknown`] when it
+    /// cannot be computed. Note that the returned value does not tell if the expression
+    /// has side effects: for example, `(f() and false)` evaluates to `false` even if the
+    /// call to `f` must still happen.
     pub fn evaluate(&self, expression: &Expression) -> LuaValue {
         match expression {
             Expression::False(_) => LuaValue::False,
@@ -218,6 +296,8 @@ impl Evaluator {
         }
     }
 
+    /// Returns true if the expression can produce more than one value (like a function
+    /// call or `...`), in which case wrapping it in parentheses would change its result.
     #[allow(clippy::only_used_in_recursion)]
     pub fn can_return_multiple_values(&self, expression: &Expression) -> bool {
         match expression {
@@ -246,6 +326,27 @@ impl Evaluator {
         }
     }
 
+    /// Returns true if evaluating the expression may have an effect other than producing
+    /// its value, so that removing the expression or evaluating it in a different order
+    /// could change the behavior of the program.
+    ///
+    /// Function calls have side effects, unless they call one of the `pure_functions` from the
+    /// configuration while darklua processes files. Indexing a table or applying an operator
+    /// to a value that may have a metatable is considered to have side effects, unless
+    /// [`assume_pure_metamethods`](Self::assume_pure_metamethods) is used.
+    ///
+    /// ```
+    /// # use darklua_core::nodes::{FieldExpression, FunctionCall, Identifier};
+    /// # use darklua_core::process::Evaluator;
+    /// let call = FunctionCall::from_name("print").into();
+    /// assert!(Evaluator::default().has_side_effects(&call));
+    ///
+    /// let field = FieldExpression::new(Identifier::new("object"), "value").into();
+    /// assert!(Evaluator::default().has_side_effects(&field));
+    /// assert!(!Evaluator::default()
+    ///     .assume_pure_metamethods()
+    ///     .has_side_effects(&field));
+    /// ```
     pub fn has_side_effects(&self, expression: &Expression) -> bool {
         match expression {
             Expression::False(_)
@@ -377,7 +478,60 @@ impl Evaluator {
         {
             return false;
         }
-        true
+        match self.pure_calls.find(call, &self.identifier_tracker) {
+            Some(PureCall::Function) => self.arguments_have_side_effects(call.get_arguments()),
+            Some(PureCall::TableFreeze) => match call.get_arguments() {
+                Arguments::Tuple(tuple) if tuple.len() == 1 => tuple
+                    .iter_values()
+                    .any(|value| !self.is_new_table(value, false)),
+                Arguments::Table(table) => self.table_has_side_effects(table),
+                _ => true,
+            },
+            Some(PureCall::SetMetatable) => match call.get_arguments() {
+                Arguments::Tuple(tuple) if tuple.len() == 2 => {
+                    let mut values = tuple.iter_values();
+                    let (table, metatable) = (values.next().unwrap(), values.next().unwrap());
+
+                    !self.is_new_table(table, false)
+                        || !(matches!(metatable, Expression::Nil(_))
+                            || self.is_new_table(metatable, true))
+                }
+                _ => true,
+            },
+            None => true,
+        }
+    }
+
+    fn arguments_have_side_effects(&self, arguments: &Arguments) -> bool {
+        match arguments {
+            Arguments::Tuple(tuple) => tuple
+                .iter_values()
+                .any(|value| self.has_side_effects(value)),
+            Arguments::String(_) => false,
+            Arguments::Table(table) => self.table_has_side_effects(table),
+        }
+    }
+
+    // `table.freeze` and `setmetatable` only mutate their first argument, so they can be
+    // removed when this argument is a new table that is not referenced anywhere else. A
+    // metatable defining `__gc` is kept, since the collector would call it.
+    fn is_new_table(&self, expression: &Expression, is_metatable: bool) -> bool {
+        match expression {
+            Expression::Table(table) => {
+                !(self.table_has_side_effects(table)
+                    || is_metatable && may_define_gc_metamethod(table))
+            }
+            Expression::Parenthese(parenthese) => {
+                self.is_new_table(parenthese.inner_expression(), is_metatable)
+            }
+            _ => false,
+        }
+    }
+
+    fn table_has_side_effects(&self, table: &TableExpression) -> bool {
+        table
+            .iter_entries()
+            .any(|entry| self.table_entry_has_side_effects(entry))
     }
 
     #[inline]
@@ -0,0 +1,361 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::nodes::{Block, FunctionCall, FunctionStatement, Prefix, Variable};
+use crate::process::utils::is_valid_identifier;
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
+
+const WILDCARD: &str = "*";
+
+/// The calls that an [`Evaluator`](super::Evaluator) can consider free of side effects:
+/// the configured pure functions, `table.freeze` and `setmetatable`. Functions whose root
+/// global is assigned somewhere in the block are never pure, and calls whose root name
+/// refers to a local variable are never pure either.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct PureCalls {
+    functions: Vec<PureFunction>,
+    table_freeze: bool,
+    setmetatable: bool,
+}
+
+impl PureCalls {
+    pub(crate) fn new(functions: &[PureFunction], block: &mut Block) -> Self {
+        let mut collector = GlobalAssignmentCollector::default();
+        ScopeVisitor::visit_block(block, &mut collector);
+        let assigned_globals = collector.assigned_globals;
+
+        Self {
+            functions: functions
+                .iter()
+                .filter(|function| !assigned_globals.contains(function.root()))
+                .cloned()
+                .collect(),
+            table_freeze: !assigned_globals.contains("table"),
+            setmetatable: !assigned_globals.contains("setmetatable"),
+        }
+    }
+
+    pub(crate) fn find(
+        &self,
+        call: &FunctionCall,
+        identifiers: &IdentifierTracker,
+    ) -> Option<PureCall> {
+        if call.has_method() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        if !push_prefix_path(call.get_prefix(), &mut path)
+            || identifiers.is_identifier_used(path[0])
+        {
+            return None;
+        }
+
+        if self
+            .functions
+            .iter()
+            .any(|function| function.matches(&path))
+        {
+            Some(PureCall::Function)
+        } else {
+            match path.as_slice() {
+                ["table", "freeze"] if self.table_freeze => Some(PureCall::TableFreeze),
+                ["setmetatable"] if self.setmetatable => Some(PureCall::SetMetatable),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// The kind of call that the evaluator can consider free of side effects, depending on
+/// its arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PureCall {
+    /// A call to one of the `pure_functions` from the configuration.
+    Function,
+    /// A call to the global `table.freeze`.
+    TableFreeze,
+    /// A call to the global `setmetatable`.
+    SetMetatable,
+}
+
+/// A function that can be called without side effects, identified by a dotted path like
+/// `Color3.fromRGB`. A path ending with `.*` (like `Maths.*`) matches every function
+/// found under the given path.
+///
+/// ```
+/// # use darklua_core::process::PureFunction;
+/// let function: PureFunction = "Maths.*".parse().unwrap();
+///
+/// assert_eq!(function.root(), "Maths");
+/// assert!("Maths.*.clamp".parse::<PureFunction>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PureFunction {
+    path: Vec<String>,
+    wildcard: bool,
+}
+
+impl PureFunction {
+    /// Returns the first identifier of the path, which must refer to a global variable
+    /// for the function to be considered pure.
+    pub fn root(&self) -> &str {
+        &self.path[0]
+    }
+
+    fn matches(&self, path: &[&str]) -> bool {
+        if self.wildcard {
+            path.len() > self.path.len() && self.path.iter().zip(path).all(|(a, b)| a == b)
+        } else {
+            self.path.len() == path.len() && self.path.iter().zip(path).all(|(a, b)| a == b)
+        }
+    }
+}
+
+impl FromStr for PureFunction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut path: Vec<_> = value.split('.').collect();
+        let wildcard = path.last() == Some(&WILDCARD);
+
+        if wildcard {
+            path.pop();
+        }
+
+        if path.is_empty() || !path.iter().all(|name| is_valid_identifier(name)) {
+            return Err(format!(
+                "invalid pure function `{}` (expected a path of identifiers separated by dots, optionally ending with `.*`)",
+                value
+            ));
+        }
+
+        Ok(Self {
+            path: path.into_iter().map(ToOwned::to_owned).collect(),
+            wildcard,
+        })
+    }
+}
+
+impl TryFrom<String> for PureFunction {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for PureFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.join("."))?;
+        if self.wildcard {
+            write!(f, ".{}", WILDCARD)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<PureFunction> for String {
+    fn from(function: PureFunction) -> Self {
+        function.to_string()
+    }
+}
+
+// Collects the roots of the globals assigned in a block, like `Maths` in
+// `Maths.clamp = clamp` or `function Maths.clamp() end`. Assigning a global can change the
+// function called anywhere in the file, while a local only shadows a function in its scope.
+#[derive(Default)]
+struct GlobalAssignmentCollector {
+    identifier_tracker: IdentifierTracker,
+    assigned_globals: HashSet<String>,
+}
+
+impl GlobalAssignmentCollector {
+    fn insert_global(&mut self, name: &str) {
+        if !self.is_identifier_used(name) {
+            self.assigned_globals.insert(name.to_owned());
+        }
+    }
+}
+
+impl Deref for GlobalAssignmentCollector {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for GlobalAssignmentCollector {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for GlobalAssignmentCollector {
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name().get_name().get_name().to_owned();
+        self.insert_global(&name);
+    }
+
+    fn process_variable(&mut self, variable: &mut Variable) {
+        let mut prefix = match variable {
+            Variable::Identifier(identifier) => {
+                let name = identifier.get_name().to_owned();
+                self.insert_global(&name);
+                return;
+            }
+            Variable::Field(field) => field.get_prefix(),
+            Variable::Index(index) => index.get_prefix(),
+        };
+        loop {
+            prefix = match prefix {
+                Prefix::Identifier(identifier) => {
+                    let name = identifier.get_name().to_owned();
+                    self.insert_global(&name);
+                    return;
+                }
+                Prefix::Field(field) => field.get_prefix(),
+                Prefix::Index(index) => index.get_prefix(),
+                Prefix::Call(_) | Prefix::Parenthese(_) => return,
+            }
+        }
+    }
+}
+
+fn push_prefix_path<'a>(prefix: &'a Prefix, path: &mut Vec<&'a str>) -> bool {
+    match prefix {
+        Prefix::Identifier(identifier) => {
+            path.push(identifier.get_name());
+            true
+        }
+        Prefix::Field(field) => {
+            if push_prefix_path(field.get_prefix(), path) {
+                path.push(field.get_field().get_name());
+                true
+            } else {
+                false
+            }
+        }
+        Prefix::Call(_) | Prefix::Index(_) | Prefix::Parenthese(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::nodes::FieldExpression;
+    use crate::process::Scope;
+    use crate::Parser;
+
+    fn parse(value: &str) -> PureFunction {
+        value.parse().unwrap()
+    }
+
+    fn pure_calls(code: &str) -> PureCalls {
+        let mut block = Parser::default().parse(code).expect("code should parse");
+        PureCalls::new(&[parse("Maths.*")], &mut block)
+    }
+
+    fn call(path: &str) -> FunctionCall {
+        let mut names = path.split('.');
+        let prefix = names.next().map(Prefix::from_name).unwrap();
+        FunctionCall::from_prefix(names.fold(prefix, |prefix, name| {
+            FieldExpression::new(prefix, name).into()
+        }))
+    }
+
+    #[test]
+    fn matches_exact_path() {
+        let function = parse("Color3.fromRGB");
+
+        assert!(function.matches(&["Color3", "fromRGB"]));
+        assert!(!function.matches(&["Color3"]));
+        assert!(!function.matches(&["Color3", "fromRGB", "value"]));
+        assert!(!function.matches(&["Color3", "new"]));
+    }
+
+    #[test]
+    fn wildcard_matches_nested_paths() {
+        let function = parse("Maths.*");
+
+        assert!(function.matches(&["Maths", "clamp"]));
+        assert!(function.matches(&["Maths", "vector", "add"]));
+        assert!(!function.matches(&["Maths"]));
+        assert!(!function.matches(&["Other", "clamp"]));
+    }
+
+    #[test]
+    fn display_round_trip() {
+        pretty_assertions::assert_eq!(parse("Maths.*").to_string(), "Maths.*");
+        pretty_assertions::assert_eq!(parse("print").to_string(), "print");
+    }
+
+    #[test]
+    fn invalid_paths_are_rejected() {
+        for value in [
+            "",
+            "*",
+            "Maths.",
+            "Maths..clamp",
+            "Maths.*.clamp",
+            "local.value",
+        ] {
+            assert!(value.parse::<PureFunction>().is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn find_configured_function() {
+        let identifiers = IdentifierTracker::default();
+
+        assert_eq!(
+            pure_calls("").find(&call("Maths.clamp"), &identifiers),
+            Some(PureCall::Function)
+        );
+        assert_eq!(pure_calls("").find(&call("print"), &identifiers), None);
+    }
+
+    #[test]
+    fn find_ignores_calls_on_locals() {
+        let mut identifiers = IdentifierTracker::default();
+        identifiers.push();
+        identifiers.insert(&mut "Maths".to_owned());
+
+        assert_eq!(
+            pure_calls("").find(&call("Maths.clamp"), &identifiers),
+            None
+        );
+    }
+
+    #[test]
+    fn assigned_global_is_never_pure() {
+        let identifiers = IdentifierTracker::default();
+        let pure_calls = pure_calls("function Maths.clamp() end setmetatable = nil");
+
+        assert_eq!(pure_calls.find(&call("Maths.clamp"), &identifiers), None);
+        assert_eq!(pure_calls.find(&call("setmetatable"), &identifiers), None);
+        assert_eq!(
+            pure_calls.find(&call("table.freeze"), &identifiers),
+            Some(PureCall::TableFreeze)
+        );
+    }
+
+    #[test]
+    fn assigned_local_does_not_disable_global() {
+        let identifiers = IdentifierTracker::default();
+        let pure_calls = pure_calls("local Maths = {} function Maths.clamp() end");
+
+        assert_eq!(
+            pure_calls.find(&call("Maths.clamp"), &identifiers),
+            Some(PureCall::Function)
+        );
+    }
+}
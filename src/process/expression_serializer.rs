@@ -9,11 +9,20 @@ pub(crate) fn to_expression<T>(value: &T) -> Result<Expression>
 where
     T: Serialize,
 {
-    let mut serializer = Serializer {
-        output: Expression::nil(),
-        operation: Vec::new(),
-        expression_stack: Vec::new(),
-    };
+    let mut serializer = Serializer::new(None, false);
+    value.serialize(&mut serializer)?;
+    Ok(serializer.output)
+}
+
+/// Convert deserialized data (from json, yaml or toml files) into a Lua Expression.
+///
+/// Null values are converted to the given sentinel identifier. Without a sentinel, they
+/// are converted to `nil`, except inside arrays where they would leave a hole.
+pub(crate) fn to_data_expression<T>(value: &T, null_sentinel: Option<&str>) -> Result<Expression>
+where
+    T: Serialize,
+{
+    let mut serializer = Serializer::new(null_sentinel.map(ToOwned::to_owned), true);
     value.serialize(&mut serializer)?;
     Ok(serializer.output)
 }
@@ -49,9 +58,9 @@ impl ser::Error for LuaSerializerError {
 impl fmt::Display for LuaSerializerError {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         if self.is_internal {
-            formatter.write_str(&self.message)
-        } else {
             write!(formatter, "{} [internal]", self.message)
+        } else {
+            formatter.write_str(&self.message)
         }
     }
 }
@@ -73,13 +82,100 @@ enum SerializeOperation {
     TableEntryValue,
 }
 
+enum DataPathComponent {
+    Index(usize),
+    Key(String),
+    UnknownKey,
+}
+
 struct Serializer {
     output: Expression,
     operation: Vec<SerializeOperation>,
     expression_stack: Vec<Expression>,
+    null_sentinel: Option<String>,
+    reject_array_holes: bool,
+    path: Vec<DataPathComponent>,
 }
 
 impl Serializer {
+    fn new(null_sentinel: Option<String>, reject_array_holes: bool) -> Self {
+        Self {
+            output: Expression::nil(),
+            operation: Vec::new(),
+            expression_stack: Vec::new(),
+            null_sentinel,
+            reject_array_holes,
+            path: Vec::new(),
+        }
+    }
+
+    fn process_null(&mut self) -> Result<()> {
+        if let Some(sentinel) = &self.null_sentinel {
+            let sentinel = Identifier::new(sentinel.clone());
+            return self.process(sentinel.into());
+        }
+
+        // a null value pushed directly into a table is an element of a sequence
+        let is_array_element = matches!(self.operation.last(), Some(SerializeOperation::Table(_)));
+
+        if self.reject_array_holes && is_array_element {
+            Err(LuaSerializerError::new(format!(
+                "null value at `{}` would leave a hole in a Lua array",
+                self.format_path()
+            )))
+        } else {
+            self.process(Expression::nil())
+        }
+    }
+
+    fn serialize_sequence_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let index = match self.operation.last() {
+            Some(SerializeOperation::Table(entries)) => entries.len(),
+            _ => 0,
+        };
+        self.path.push(DataPathComponent::Index(index));
+        value.serialize(&mut *self)?;
+        self.path.pop();
+        Ok(())
+    }
+
+    fn serialize_entry_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = match self.expression_stack.last() {
+            Some(Expression::String(string)) => string
+                .get_string_value()
+                .map(|value| DataPathComponent::Key(value.to_owned()))
+                .unwrap_or(DataPathComponent::UnknownKey),
+            _ => DataPathComponent::UnknownKey,
+        };
+        self.path.push(key);
+        self.begin_table_entry_value();
+        value.serialize(&mut *self)?;
+        self.path.pop();
+        Ok(())
+    }
+
+    fn format_path(&self) -> String {
+        let mut path = "$".to_owned();
+        for component in &self.path {
+            match component {
+                DataPathComponent::Index(index) => path.push_str(&format!("[{}]", index)),
+                DataPathComponent::Key(key) if is_valid_identifier(key) => {
+                    path.push('.');
+                    path.push_str(key);
+                }
+                DataPathComponent::Key(key) => path.push_str(&format!("[{:?}]", key)),
+                DataPathComponent::UnknownKey => path.push_str("[?]"),
+            }
+        }
+        path
+    }
+
     fn process(&mut self, expression: Expression) -> Result<()> {
         if let Some(mut operation) = self.operation.pop() {
             let keep = match &mut operation {
@@ -277,7 +373,7 @@ impl ser::Serializer for &mut Serializer {
     }
 
     fn serialize_none(self) -> Result<()> {
-        self.process(Expression::nil())
+        self.process_null()
     }
 
     fn serialize_some<T>(self, value: &T) -> Result<()>
@@ -289,7 +385,7 @@ impl ser::Serializer for &mut Serializer {
 
     // In Serde, unit means an anonymous value containing no data.
     fn serialize_unit(self) -> Result<()> {
-        self.process(Expression::nil())
+        self.process_null()
     }
 
     // Unit struct means a named value containing no data.
@@ -438,7 +534,7 @@ impl ser::SerializeSeq for &mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        (**self).serialize_sequence_element(value)
     }
 
     // Close the sequence.
@@ -456,7 +552,7 @@ impl ser::SerializeTuple for &mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        (**self).serialize_sequence_element(value)
     }
 
     fn end(self) -> Result<()> {
@@ -473,7 +569,7 @@ impl ser::SerializeTupleStruct for &mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        (**self).serialize_sequence_element(value)
     }
 
     fn end(self) -> Result<()> {
@@ -493,7 +589,7 @@ impl ser::SerializeTupleVariant for &mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        (**self).serialize_sequence_element(value)
     }
 
     fn end(self) -> Result<()> {
@@ -526,8 +622,7 @@ impl ser::SerializeMap for &mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        self.begin_table_entry_value();
-        value.serialize(&mut **self)
+        (**self).serialize_entry_value(value)
     }
 
     fn end(self) -> Result<()> {
@@ -547,8 +642,7 @@ impl ser::SerializeStruct for &mut Serializer {
     {
         self.begin_table_entry_key();
         key.serialize(&mut **self)?;
-        self.begin_table_entry_value();
-        value.serialize(&mut **self)
+        (**self).serialize_entry_value(value)
     }
 
     fn end(self) -> Result<()> {
@@ -568,8 +662,7 @@ impl ser::SerializeStructVariant for &mut Serializer {
     {
         self.begin_table_entry_key();
         key.serialize(&mut **self)?;
-        self.begin_table_entry_value();
-        value.serialize(&mut **self)
+        (**self).serialize_entry_value(value)
     }
 
     fn end(self) -> Result<()> {
@@ -783,4 +876,64 @@ mod test {
             Test("value".to_owned(), 1, "".to_owned())
         }) => expression!("{ 'value', 1, '' }"),
     );
+
+    mod data {
+        use super::*;
+
+        fn json(value: &str) -> serde_json::Value {
+            serde_json::from_str(value).expect("json should parse")
+        }
+
+        #[test]
+        fn serializes_null_in_object_as_nil() {
+            pretty_assertions::assert_eq!(
+                to_data_expression(&json(r#"{ "value": null }"#), None).unwrap(),
+                expression!("{ value = nil }"),
+            );
+        }
+
+        #[test]
+        fn serializes_non_identifier_keys_as_index_entries() {
+            pretty_assertions::assert_eq!(
+                to_data_expression(&json(r#"{ "1abc": 1, "foo-bar": 2 }"#), None).unwrap(),
+                expression!("{ ['1abc'] = 1, ['foo-bar'] = 2 }"),
+            );
+        }
+
+        #[test]
+        fn serializes_nulls_with_sentinel() {
+            pretty_assertions::assert_eq!(
+                to_data_expression(&json(r#"{ "list": [null, { "a": null }] }"#), Some("NULL"))
+                    .unwrap(),
+                expression!("{ list = { NULL, { a = NULL } } }"),
+            );
+        }
+
+        #[test]
+        fn rejects_null_in_nested_array() {
+            let error =
+                to_data_expression(&json(r#"{ "a": [{ "b-c": [1, null] }] }"#), None).unwrap_err();
+
+            pretty_assertions::assert_eq!(
+                error.to_string(),
+                "null value at `$.a[0][\"b-c\"][1]` would leave a hole in a Lua array"
+            );
+        }
+    }
+
+    #[test]
+    fn error_display_is_the_message() {
+        pretty_assertions::assert_eq!(
+            LuaSerializerError::new("unable to serialize").to_string(),
+            "unable to serialize"
+        );
+    }
+
+    #[test]
+    fn internal_error_display_is_marked_as_internal() {
+        pretty_assertions::assert_eq!(
+            LuaSerializerError::internal("unexpected state").to_string(),
+            "unexpected state [internal]"
+        );
+    }
 }
@@ -17,6 +17,5 @@ pub(crate) use expression_serializer::*;
 pub use node_counter::NodeCounter;
 pub use node_processor::{NodePostProcessor, NodeProcessor};
 pub use post_visitor::{DefaultPostVisitor, NodePostVisitor};
-pub(crate) use scope_visitor::IdentifierTracker;
-pub use scope_visitor::{Scope, ScopePostVisitor, ScopeVisitor};
+pub use scope_visitor::{IdentifierTracker, Scope, ScopePostVisitor, ScopeVisitor};
 pub use visitors::{DefaultVisitor, NodeVisitor};
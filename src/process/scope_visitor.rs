@@ -449,8 +449,9 @@ impl<T: NodeProcessor + NodePostProcessor + Scope> NodePostVisitor<T> for ScopeP
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub(crate) struct IdentifierTracker {
+/// A scope that tracks the identifiers declared in the visited blocks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdentifierTracker {
     identifiers: Vec<HashSet<String>>,
 }
 
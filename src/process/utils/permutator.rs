@@ -1,4 +1,4 @@
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Permutator<I> {
     original_producer: I,
     current_producers: Vec<I>,
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Defines if two paths that only differ by their casing (like `src/Utils.lua` and
+/// `src/utils.lua`) point to the same module.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseSensitivePaths {
+    /// Uses the behavior of the platform: paths are case-insensitive on Windows and macOS.
+    #[default]
+    Auto,
+    /// Paths with a different casing are different modules.
+    Sensitive,
+    /// Paths with a different casing are the same module.
+    Insensitive,
+}
+
+impl CaseSensitivePaths {
+    pub(crate) fn is_auto(&self) -> bool {
+        matches!(self, Self::Auto)
+    }
+
+    pub(crate) fn is_case_sensitive(&self) -> bool {
+        match self {
+            Self::Auto => !cfg!(any(windows, target_os = "macos")),
+            Self::Sensitive => true,
+            Self::Insensitive => false,
+        }
+    }
+
+    /// Returns the path used to identify a module, so that paths pointing to the same
+    /// module have the same key.
+    pub(crate) fn path_key(&self, path: &Path) -> PathBuf {
+        if self.is_case_sensitive() {
+            path.to_path_buf()
+        } else {
+            PathBuf::from(path.to_string_lossy().to_lowercase())
+        }
+    }
+}
+
+impl Serialize for CaseSensitivePaths {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Auto => serializer.serialize_str("auto"),
+            Self::Sensitive => serializer.serialize_bool(true),
+            Self::Insensitive => serializer.serialize_bool(false),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CaseSensitivePaths {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CaseSensitivePathsVisitor;
+
+        impl de::Visitor<'_> for CaseSensitivePathsVisitor {
+            type Value = CaseSensitivePaths;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a boolean or \"auto\"")
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+                Ok(if value {
+                    CaseSensitivePaths::Sensitive
+                } else {
+                    CaseSensitivePaths::Insensitive
+                })
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                match value {
+                    "auto" => Ok(CaseSensitivePaths::Auto),
+                    _ => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(CaseSensitivePathsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deserialize_auto() {
+        assert_eq!(
+            serde_json::from_str::<CaseSensitivePaths>("\"auto\"").unwrap(),
+            CaseSensitivePaths::Auto
+        );
+    }
+
+    #[test]
+    fn deserialize_true() {
+        assert_eq!(
+            serde_json::from_str::<CaseSensitivePaths>("true").unwrap(),
+            CaseSensitivePaths::Sensitive
+        );
+    }
+
+    #[test]
+    fn deserialize_false() {
+        assert_eq!(
+            serde_json::from_str::<CaseSensitivePaths>("false").unwrap(),
+            CaseSensitivePaths::Insensitive
+        );
+    }
+
+    #[test]
+    fn deserialize_unknown_string_errors() {
+        assert_eq!(
+            serde_json::from_str::<CaseSensitivePaths>("\"yes\"")
+                .unwrap_err()
+                .to_string(),
+            "invalid value: string \"yes\", expected a boolean or \"auto\" at line 1 column 5"
+        );
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        for value in [
+            CaseSensitivePaths::Auto,
+            CaseSensitivePaths::Sensitive,
+            CaseSensitivePaths::Insensitive,
+        ] {
+            let serialized = serde_json::to_string(&value).unwrap();
+            assert_eq!(
+                serde_json::from_str::<CaseSensitivePaths>(&serialized).unwrap(),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn insensitive_path_key_ignores_casing() {
+        assert_eq!(
+            CaseSensitivePaths::Insensitive.path_key(Path::new("src/Utils.lua")),
+            CaseSensitivePaths::Insensitive.path_key(Path::new("src/utils.lua")),
+        );
+    }
+
+    #[test]
+    fn sensitive_path_key_keeps_casing() {
+        assert_eq!(
+            CaseSensitivePaths::Sensitive.path_key(Path::new("src/Utils.lua")),
+            PathBuf::from("src/Utils.lua"),
+        );
+    }
+}
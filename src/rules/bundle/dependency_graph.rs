@@ -0,0 +1,171 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use indexmap::{IndexMap, IndexSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum RequireKind {
+    Bundled,
+    Excluded,
+    Cyclic,
+}
+
+/// The modules found while bundling and the requires between them, in the order they
+/// were found.
+#[derive(Debug, Default)]
+pub(crate) struct DependencyGraph {
+    // each module is mapped to whether it is only required through excluded requires
+    modules: IndexMap<PathBuf, bool>,
+    requires: IndexSet<(PathBuf, PathBuf, RequireKind)>,
+}
+
+impl DependencyGraph {
+    pub(crate) fn add_module(&mut self, path: &Path) {
+        self.insert_module(path, false);
+    }
+
+    pub(crate) fn add_require(&mut self, from: &Path, to: &Path, kind: RequireKind) {
+        self.insert_module(from, false);
+        self.insert_module(to, kind == RequireKind::Excluded);
+        self.requires
+            .insert((from.to_path_buf(), to.to_path_buf(), kind));
+    }
+
+    fn insert_module(&mut self, path: &Path, excluded: bool) {
+        let is_excluded = self.modules.entry(path.to_path_buf()).or_insert(excluded);
+        *is_excluded = *is_excluded && excluded;
+    }
+
+    /// Sorts the modules and the requires by path, instead of the order in which they were
+    /// found.
+    pub(crate) fn sort(&mut self) {
+        self.modules.sort_keys();
+        self.requires.sort();
+    }
+
+    /// Writes the graph in the DOT format. Module paths are written relative to the
+    /// given location when possible.
+    pub(crate) fn to_dot(&self, relative_to: Option<&Path>) -> String {
+        let label = |path: &Path| -> String {
+            let path = relative_to
+                .and_then(|location| path.strip_prefix(location).ok())
+                .unwrap_or(path);
+            path.to_string_lossy()
+                .replace('\\', "/")
+                .replace('"', "\\\"")
+        };
+
+        let mut dot = String::from("digraph bundle {\n");
+
+        for (path, excluded) in self.modules.iter() {
+            let _ = write!(dot, "    \"{}\"", label(path));
+            if *excluded {
+                dot.push_str(" [style=dashed]");
+            }
+            dot.push_str(";\n");
+        }
+
+        for (from, to, kind) in self.requires.iter() {
+            let _ = write!(dot, "    \"{}\" -> \"{}\"", label(from), label(to));
+            match kind {
+                RequireKind::Bundled => {}
+                RequireKind::Excluded => dot.push_str(" [style=dashed]"),
+                RequireKind::Cyclic => dot.push_str(" [color=red]"),
+            }
+            dot.push_str(";\n");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn empty_graph() {
+        pretty_assertions::assert_eq!(
+            DependencyGraph::default().to_dot(None),
+            "digraph bundle {\n}\n"
+        );
+    }
+
+    #[test]
+    fn module_required_by_excluded_and_bundled_requires_is_not_dashed() {
+        let mut graph = DependencyGraph::default();
+        graph.add_module(Path::new("main.lua"));
+        graph.add_require(
+            Path::new("main.lua"),
+            Path::new("a.lua"),
+            RequireKind::Excluded,
+        );
+        graph.add_require(Path::new("b.lua"), Path::new("a.lua"), RequireKind::Bundled);
+
+        pretty_assertions::assert_eq!(
+            graph.to_dot(None),
+            concat!(
+                "digraph bundle {\n",
+                "    \"main.lua\";\n",
+                "    \"a.lua\";\n",
+                "    \"b.lua\";\n",
+                "    \"main.lua\" -> \"a.lua\" [style=dashed];\n",
+                "    \"b.lua\" -> \"a.lua\";\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn labels_are_relative_to_location() {
+        let mut graph = DependencyGraph::default();
+        graph.add_require(
+            Path::new("project/src/main.lua"),
+            Path::new("project/src/a.lua"),
+            RequireKind::Cyclic,
+        );
+
+        pretty_assertions::assert_eq!(
+            graph.to_dot(Some(Path::new("project"))),
+            concat!(
+                "digraph bundle {\n",
+                "    \"src/main.lua\";\n",
+                "    \"src/a.lua\";\n",
+                "    \"src/main.lua\" -> \"src/a.lua\" [color=red];\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn sorted_graph() {
+        let mut graph = DependencyGraph::default();
+        graph.add_require(
+            Path::new("main.lua"),
+            Path::new("b.lua"),
+            RequireKind::Bundled,
+        );
+        graph.add_require(
+            Path::new("main.lua"),
+            Path::new("a.lua"),
+            RequireKind::Bundled,
+        );
+        graph.add_require(Path::new("b.lua"), Path::new("a.lua"), RequireKind::Bundled);
+        graph.sort();
+
+        pretty_assertions::assert_eq!(
+            graph.to_dot(None),
+            concat!(
+                "digraph bundle {\n",
+                "    \"a.lua\";\n",
+                "    \"b.lua\";\n",
+                "    \"main.lua\";\n",
+                "    \"b.lua\" -> \"a.lua\";\n",
+                "    \"main.lua\" -> \"a.lua\";\n",
+                "    \"main.lua\" -> \"b.lua\";\n",
+                "}\n",
+            )
+        );
+    }
+}
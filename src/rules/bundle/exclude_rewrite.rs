@@ -0,0 +1,133 @@
+use std::ffi::OsStr;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rules::convert_require::InstancePath;
+
+const NAME_PLACEHOLDER: &str = "{name}";
+const INIT_MODULE_NAME: &str = "init";
+
+/// Rewrites the requires of excluded modules matching a glob pattern to an explicit
+/// DataModel path, instead of the path found in the Rojo sourcemap.
+///
+/// The target is a textual instance path (like `game.ReplicatedStorage.Packages.{name}`)
+/// where `{name}` is replaced with the name of the excluded module.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub struct ExcludeRewrite {
+    pattern: String,
+    target: String,
+}
+
+impl ExcludeRewrite {
+    /// Creates a rewrite of the excluded requires matching the given glob pattern.
+    pub fn new(pattern: impl Into<String>, target: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            target: target.into(),
+        }
+    }
+
+    pub(crate) fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the instance path that replaces the require of the given excluded module.
+    pub(crate) fn target_path(&self, module_path: &Path) -> Result<InstancePath, String> {
+        self.target
+            .replace(NAME_PLACEHOLDER, &module_name(module_path))
+            .parse()
+    }
+}
+
+// the file stem of the module, or the name of its directory for `init` files
+fn module_name(module_path: &Path) -> String {
+    let stem = module_path.file_stem().and_then(OsStr::to_str);
+
+    match stem {
+        Some(INIT_MODULE_NAME) => module_path
+            .parent()
+            .and_then(Path::file_name)
+            .and_then(OsStr::to_str)
+            .unwrap_or(INIT_MODULE_NAME),
+        Some(stem) => stem,
+        None => "",
+    }
+    .to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn target(target: &str, module_path: &str) -> Result<String, String> {
+        ExcludeRewrite::new("**", target)
+            .target_path(Path::new(module_path))
+            .map(|instance_path| instance_path.to_string())
+    }
+
+    #[test]
+    fn replaces_name_with_file_stem() {
+        assert_eq!(
+            target(
+                "game.ReplicatedStorage.RuntimePackages.{name}",
+                "Packages/Promise.lua"
+            ),
+            Ok("game.ReplicatedStorage.RuntimePackages.Promise".to_owned())
+        );
+    }
+
+    #[test]
+    fn replaces_name_with_directory_of_init_file() {
+        assert_eq!(
+            target(
+                "game.ReplicatedStorage.{name}",
+                "Packages/Promise/init.luau"
+            ),
+            Ok("game.ReplicatedStorage.Promise".to_owned())
+        );
+    }
+
+    #[test]
+    fn target_without_name_placeholder() {
+        assert_eq!(
+            target("game.ReplicatedStorage.Shared", "Packages/Promise.lua"),
+            Ok("game.ReplicatedStorage.Shared".to_owned())
+        );
+    }
+
+    #[test]
+    fn replaces_name_in_string_index() {
+        assert_eq!(
+            target(
+                "game.ReplicatedStorage[\"{name}\"]",
+                "Packages/my module.lua"
+            ),
+            Ok("game.ReplicatedStorage[\"my module\"]".to_owned())
+        );
+    }
+
+    #[test]
+    fn invalid_target_errors() {
+        assert_eq!(
+            target("ReplicatedStorage.{name}", "Packages/Promise.lua"),
+            Err(concat!(
+                "invalid instance path `ReplicatedStorage.Promise`: expected the path to start ",
+                "with `game` or `script` but found `ReplicatedStorage`"
+            )
+            .to_owned())
+        );
+    }
+
+    #[test]
+    fn deserialize_exclude_rewrite() {
+        assert_eq!(
+            json5::from_str::<ExcludeRewrite>(
+                "{ pattern: '**/Packages/**', target: 'game.ReplicatedStorage.{name}' }"
+            )
+            .unwrap(),
+            ExcludeRewrite::new("**/Packages/**", "game.ReplicatedStorage.{name}")
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::ops;
+use std::path::Path;
+
+use crate::nodes::{Block, Identifier, Prefix, Variable};
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
+use crate::rules::{RuleWarning, WarningCode};
+
+const SHARED_GLOBALS: [&str; 2] = ["_G", "shared"];
+
+/// A read or a write of a global table shared between scripts (`_G` or `shared`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GlobalAccess {
+    name: String,
+    line: Option<usize>,
+    is_write: bool,
+}
+
+impl GlobalAccess {
+    pub(crate) fn into_warning(self, path: &Path) -> RuleWarning {
+        let message = format!(
+            "global `{}` is {} (bundling can change when globals are initialized)",
+            self.name,
+            if self.is_write { "written" } else { "read" }
+        );
+        let warning = RuleWarning::new(WarningCode::GlobalAccess, message).with_path(path);
+        match self.line {
+            Some(line) => warning.with_line(line),
+            None => warning,
+        }
+    }
+}
+
+/// Finds every access to `_G` and `shared` in a block, ignoring locals that shadow them.
+pub(crate) fn find_global_accesses(block: &mut Block) -> Vec<GlobalAccess> {
+    let mut finder = GlobalAccessFinder::default();
+    ScopeVisitor::visit_block(block, &mut finder);
+    finder.accesses
+}
+
+#[derive(Default)]
+struct GlobalAccessFinder {
+    identifier_tracker: IdentifierTracker,
+    accesses: Vec<GlobalAccess>,
+    // set when visiting an assignment target, so that its root identifier is a write
+    assigned_root: bool,
+}
+
+impl ops::Deref for GlobalAccessFinder {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for GlobalAccessFinder {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+fn root_identifier(prefix: &Prefix) -> Option<&Identifier> {
+    match prefix {
+        Prefix::Identifier(identifier) => Some(identifier),
+        Prefix::Field(field) => root_identifier(field.get_prefix()),
+        Prefix::Index(index) => root_identifier(index.get_prefix()),
+        Prefix::Call(_) | Prefix::Parenthese(_) => None,
+    }
+}
+
+impl NodeProcessor for GlobalAccessFinder {
+    fn process_variable(&mut self, variable: &mut Variable) {
+        // the root identifier of a variable is the first identifier visited in it
+        self.assigned_root = match variable {
+            Variable::Identifier(_) => true,
+            Variable::Field(field) => root_identifier(field.get_prefix()).is_some(),
+            Variable::Index(index) => root_identifier(index.get_prefix()).is_some(),
+        };
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        let is_write = std::mem::take(&mut self.assigned_root);
+        let name = identifier.get_name();
+
+        if SHARED_GLOBALS.contains(&name.as_str()) && !self.is_identifier_used(name) {
+            self.accesses.push(GlobalAccess {
+                name: name.to_owned(),
+                line: identifier
+                    .get_token()
+                    .and_then(|token| token.get_line_number()),
+                is_write,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Parser;
+
+    fn find(code: &str) -> Vec<(String, Option<usize>, bool)> {
+        let mut block = Parser::default().preserve_tokens().parse(code).unwrap();
+        find_global_accesses(&mut block)
+            .into_iter()
+            .map(|access| (access.name, access.line, access.is_write))
+            .collect()
+    }
+
+    #[test]
+    fn finds_reads_and_writes() {
+        pretty_assertions::assert_eq!(
+            find("_G.value = 1\nprint(shared.value)\nshared = {}"),
+            vec![
+                ("_G".to_owned(), Some(1), true),
+                ("shared".to_owned(), Some(2), false),
+                ("shared".to_owned(), Some(3), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn index_key_of_assignment_is_a_read() {
+        pretty_assertions::assert_eq!(
+            find("t[_G.key] = 1"),
+            vec![("_G".to_owned(), Some(1), false)]
+        );
+    }
+
+    #[test]
+    fn ignores_shadowed_globals() {
+        pretty_assertions::assert_eq!(
+            find("local _G = {}\n_G.value = 1\nlocal function f(shared) return shared end"),
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn global_is_visible_again_after_shadowing_scope() {
+        pretty_assertions::assert_eq!(
+            find("do local _G = {} end\nreturn _G"),
+            vec![("_G".to_owned(), Some(2), false)]
+        );
+    }
+
+    #[test]
+    fn warning_contains_path_and_line() {
+        let access = GlobalAccess {
+            name: "_G".to_owned(),
+            line: Some(4),
+            is_write: true,
+        };
+
+        assert_eq!(
+            access.into_warning(Path::new("src/a.lua")).to_string(),
+            "global `_G` is written (bundling can change when globals are initialized) (`src/a.lua` at line 4)"
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::frontend::DarkluaResult;
+use crate::nodes::{Block, Expression, Identifier, LocalFunctionStatement};
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, Scope, ScopeVisitor};
+use crate::rules::GeneratedIdentifiers;
+use crate::DarkluaError;
+
+/// Defines how the bundler handles required modules that declare a variable with the same
+/// name as the modules table of the bundle.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentifierConflict {
+    /// Reports an error for modules that declare a variable named like the modules table.
+    #[default]
+    Error,
+    /// Renames the variables of the module that are named like the modules table.
+    Rename,
+}
+
+impl IdentifierConflict {
+    pub(crate) fn is_error(&self) -> bool {
+        matches!(self, Self::Error)
+    }
+
+    /// Verifies that a required module does not declare a variable named like the modules
+    /// table, since the requires of the module are replaced with references to that table.
+    pub(crate) fn resolve(
+        &self,
+        block: &mut Block,
+        modules_identifier: &str,
+        module_path: &Path,
+        generated_identifiers: &mut GeneratedIdentifiers,
+    ) -> DarkluaResult<()> {
+        let mut finder = ModulesIdentifierRenamer::new(modules_identifier, None);
+        ScopeVisitor::visit_block(block, &mut finder);
+
+        if !finder.found {
+            return Ok(());
+        }
+
+        match self {
+            Self::Error => Err(DarkluaError::custom(format!(
+                "module `{}` declares a variable named `{}`, which shadows the modules table of the bundle (set `on_identifier_conflict` to `rename` to rename it)",
+                module_path.display(),
+                modules_identifier,
+            ))),
+            Self::Rename => {
+                generated_identifiers.reserve_block(block);
+                let new_identifier = generated_identifiers.unique_name(modules_identifier);
+
+                log::debug!(
+                    "rename `{}` to `{}` in `{}` because it shadows the modules table",
+                    modules_identifier,
+                    new_identifier,
+                    module_path.display()
+                );
+
+                let mut renamer =
+                    ModulesIdentifierRenamer::new(modules_identifier, Some(&new_identifier));
+                ScopeVisitor::visit_block(block, &mut renamer);
+
+                Ok(())
+            }
+        }
+    }
+}
+
+// finds the declarations of the modules identifier, and renames them with their references
+// when a new identifier is given
+struct ModulesIdentifierRenamer<'a> {
+    identifier: &'a str,
+    new_identifier: Option<&'a str>,
+    identifier_tracker: IdentifierTracker,
+    found: bool,
+}
+
+impl<'a> ModulesIdentifierRenamer<'a> {
+    fn new(identifier: &'a str, new_identifier: Option<&'a str>) -> Self {
+        Self {
+            identifier,
+            new_identifier,
+            identifier_tracker: IdentifierTracker::new(),
+            found: false,
+        }
+    }
+
+    fn declare(&mut self, identifier: &mut String) {
+        if identifier != self.identifier {
+            return;
+        }
+        self.found = true;
+        self.identifier_tracker.insert(identifier);
+
+        if let Some(new_identifier) = self.new_identifier {
+            *identifier = new_identifier.to_owned();
+        }
+    }
+}
+
+impl Scope for ModulesIdentifierRenamer<'_> {
+    fn push(&mut self) {
+        self.identifier_tracker.push();
+    }
+
+    fn pop(&mut self) {
+        self.identifier_tracker.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut String) {
+        self.declare(identifier);
+    }
+
+    fn insert_self(&mut self) {}
+
+    fn insert_local(&mut self, identifier: &mut String, _value: Option<&mut Expression>) {
+        self.declare(identifier);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.declare(function.mutate_identifier().mutate_name());
+    }
+}
+
+impl NodeProcessor for ModulesIdentifierRenamer<'_> {
+    fn process_variable_expression(&mut self, variable: &mut Identifier) {
+        if let Some(new_identifier) = self.new_identifier {
+            if variable.get_name() == self.identifier
+                && self.identifier_tracker.is_identifier_used(self.identifier)
+            {
+                variable.set_name(new_identifier);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::generator::{LuaGenerator, ReadableLuaGenerator};
+    use crate::Parser;
+
+    fn resolve(conflict: IdentifierConflict, code: &str) -> DarkluaResult<String> {
+        let mut block = Parser::default().parse(code).unwrap();
+        let mut generated_identifiers = GeneratedIdentifiers::default();
+        generated_identifiers.reserve("modules");
+
+        conflict.resolve(
+            &mut block,
+            "modules",
+            Path::new("src/module.lua"),
+            &mut generated_identifiers,
+        )?;
+
+        let mut generator = ReadableLuaGenerator::new(80);
+        generator.write_block(&block);
+        Ok(generator.into_string())
+    }
+
+    #[test]
+    fn module_without_conflict_is_unchanged() {
+        assert_eq!(
+            resolve(IdentifierConflict::Error, "print(modules)").unwrap(),
+            "print(modules)\n"
+        );
+    }
+
+    #[test]
+    fn conflicting_local_errors() {
+        assert_eq!(
+            resolve(IdentifierConflict::Error, "local modules = {}")
+                .unwrap_err()
+                .to_string(),
+            "module `src/module.lua` declares a variable named `modules`, which shadows the modules table of the bundle (set `on_identifier_conflict` to `rename` to rename it)"
+        );
+    }
+
+    #[test]
+    fn rename_conflicting_local_and_its_references() {
+        assert_eq!(
+            resolve(
+                IdentifierConflict::Rename,
+                "print(modules)\nlocal modules = {}\nreturn modules"
+            )
+            .unwrap(),
+            "print(modules)\n\nlocal modules_2 = {}\n\nreturn modules_2\n"
+        );
+    }
+
+    #[test]
+    fn rename_conflicting_parameter() {
+        assert_eq!(
+            resolve(
+                IdentifierConflict::Rename,
+                "local function f(modules) return modules end\nreturn modules"
+            )
+            .unwrap(),
+            "local function f(modules_2)\n    return modules_2\nend\n\nreturn modules\n"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::nodes::{Expression, Identifier, LocalAssignStatement, Statement, StringExpression};
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+
+const DEFAULT_MIN_LENGTH: usize = 12;
+const DEFAULT_MIN_COUNT: usize = 3;
+// a Lua function cannot declare more than 200 locals
+const MAX_INTERNED_STRINGS: usize = 150;
+
+/// Hoists the strings repeated in the data files (like json files) required by the bundle
+/// into locals declared above the returned value, to reduce the size of the bundle.
+///
+/// A string is interned when it is at least `min_length` bytes long (12 by default) and
+/// appears at least `min_count` times (3 by default) in the same data file.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub struct InternDataStrings {
+    #[serde(default = "get_default_min_length")]
+    min_length: usize,
+    #[serde(default = "get_default_min_count")]
+    min_count: usize,
+}
+
+fn get_default_min_length() -> usize {
+    DEFAULT_MIN_LENGTH
+}
+
+fn get_default_min_count() -> usize {
+    DEFAULT_MIN_COUNT
+}
+
+impl Default for InternDataStrings {
+    fn default() -> Self {
+        Self {
+            min_length: DEFAULT_MIN_LENGTH,
+            min_count: DEFAULT_MIN_COUNT,
+        }
+    }
+}
+
+impl InternDataStrings {
+    /// Creates the interning options with the default minimum length and count.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the minimum length in bytes of the interned strings.
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Sets the minimum number of times a string must appear in a data file to be interned.
+    pub fn with_min_count(mut self, min_count: usize) -> Self {
+        self.min_count = min_count;
+        self
+    }
+
+    /// Replaces the repeated strings of the expression with identifiers, and returns the
+    /// local assignments declaring these identifiers.
+    pub(crate) fn intern(&self, expression: &mut Expression) -> Vec<Statement> {
+        let mut counter = StringCounter::default();
+        DefaultVisitor::visit_expression(expression, &mut counter);
+        let StringCounter {
+            strings,
+            identifiers,
+        } = counter;
+
+        // a string that appears only once is never smaller as a local
+        let min_count = self.min_count.max(2);
+        let mut candidates: Vec<_> = strings
+            .into_iter()
+            .filter(|(value, occurrences)| {
+                value.len() >= self.min_length && occurrences.count >= min_count
+            })
+            .collect();
+
+        if candidates.is_empty() {
+            return Vec::new();
+        }
+
+        // keep the strings that save the most bytes, then declare them in order of appearance
+        candidates.sort_by_key(|(value, occurrences)| {
+            (
+                Reverse(value.len() * (occurrences.count - 1)),
+                occurrences.first,
+            )
+        });
+        candidates.truncate(MAX_INTERNED_STRINGS);
+        candidates.sort_by_key(|(_, occurrences)| occurrences.first);
+
+        let mut names = (1..)
+            .map(|index| format!("s{}", index))
+            .filter(|name| !identifiers.contains(name));
+
+        let mut statements = Vec::with_capacity(candidates.len());
+        let mut replacer = StringReplacer::default();
+
+        for (value, _) in candidates {
+            let name = names.next().expect("name generator should be infinite");
+            statements.push(
+                LocalAssignStatement::from_variable(name.as_str())
+                    .with_value(StringExpression::from_value(value.as_slice()))
+                    .into(),
+            );
+            replacer.identifiers.insert(value, name);
+        }
+
+        DefaultVisitor::visit_expression(expression, &mut replacer);
+
+        log::debug!("interned {} repeated data strings", statements.len());
+
+        statements
+    }
+}
+
+#[derive(Debug)]
+struct Occurrences {
+    count: usize,
+    first: usize,
+}
+
+#[derive(Debug, Default)]
+struct StringCounter {
+    strings: HashMap<Vec<u8>, Occurrences>,
+    identifiers: HashSet<String>,
+}
+
+impl NodeProcessor for StringCounter {
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        let next_index = self.strings.len();
+        self.strings
+            .entry(string.get_value().to_vec())
+            .or_insert(Occurrences {
+                count: 0,
+                first: next_index,
+            })
+            .count += 1;
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        self.identifiers.insert(identifier.get_name().to_owned());
+    }
+}
+
+#[derive(Debug, Default)]
+struct StringReplacer {
+    identifiers: HashMap<Vec<u8>, String>,
+}
+
+impl NodeProcessor for StringReplacer {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if let Expression::String(string) = expression {
+            if let Some(name) = self.identifiers.get(string.get_value()) {
+                *expression = Expression::identifier(name.as_str());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::nodes::TableExpression;
+    use crate::process::to_data_expression;
+
+    fn data(value: serde_json::Value) -> Expression {
+        to_data_expression(&value, None).unwrap()
+    }
+
+    // replaces the identifiers with the values of the locals, to compare with the
+    // original expression
+    fn expand(statements: &[Statement], expression: &Expression) -> Expression {
+        struct Expander(HashMap<String, Expression>);
+
+        impl NodeProcessor for Expander {
+            fn process_expression(&mut self, expression: &mut Expression) {
+                if let Expression::Identifier(identifier) = expression {
+                    if let Some(value) = self.0.get(identifier.get_name()) {
+                        *expression = value.clone();
+                    }
+                }
+            }
+        }
+
+        let mut expander = Expander(
+            statements
+                .iter()
+                .map(|statement| match statement {
+                    Statement::LocalAssign(assign) => (
+                        assign.get_variables()[0].get_name().to_owned(),
+                        assign.iter_values().next().unwrap().clone(),
+                    ),
+                    _ => panic!("unexpected statement"),
+                })
+                .collect(),
+        );
+        let mut expression = expression.clone();
+        DefaultVisitor::visit_expression(&mut expression, &mut expander);
+        expression
+    }
+
+    fn local_names(statements: &[Statement]) -> Vec<String> {
+        statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::LocalAssign(assign) => assign.get_variables()[0].get_name().to_owned(),
+                _ => panic!("unexpected statement"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn interns_repeated_long_strings() {
+        let original = data(serde_json::json!([
+            { "label": "a repeated long value", "other": "short" },
+            { "label": "a repeated long value", "other": "short" },
+            { "label": "a repeated long value", "other": "short" },
+        ]));
+        let mut expression = original.clone();
+
+        let statements = InternDataStrings::new().intern(&mut expression);
+
+        pretty_assertions::assert_eq!(local_names(&statements), vec!["s1"]);
+        assert_ne!(expression, original);
+        pretty_assertions::assert_eq!(expand(&statements, &expression), original);
+    }
+
+    #[test]
+    fn keeps_strings_below_min_count() {
+        let original = data(serde_json::json!([
+            "a repeated long value",
+            "a repeated long value"
+        ]));
+        let mut expression = original.clone();
+
+        let statements = InternDataStrings::new().intern(&mut expression);
+
+        assert!(statements.is_empty());
+        pretty_assertions::assert_eq!(expression, original);
+    }
+
+    #[test]
+    fn keeps_strings_below_min_length() {
+        let original = data(serde_json::json!(["value", "value", "value", "value"]));
+        let mut expression = original.clone();
+
+        let statements = InternDataStrings::new().intern(&mut expression);
+
+        assert!(statements.is_empty());
+        pretty_assertions::assert_eq!(expression, original);
+    }
+
+    #[test]
+    fn interns_index_keys() {
+        let original = data(serde_json::json!([
+            { "not an identifier key": 1 },
+            { "not an identifier key": 2 },
+        ]));
+        let mut expression = original.clone();
+
+        let statements = InternDataStrings::new()
+            .with_min_count(2)
+            .with_min_length(4)
+            .intern(&mut expression);
+
+        pretty_assertions::assert_eq!(local_names(&statements), vec!["s1"]);
+        pretty_assertions::assert_eq!(expand(&statements, &expression), original);
+    }
+
+    #[test]
+    fn locals_are_declared_in_order_of_appearance() {
+        let original = data(serde_json::json!(["first", "second", "second", "first"]));
+        let mut expression = original.clone();
+
+        let statements = InternDataStrings::new()
+            .with_min_count(2)
+            .with_min_length(1)
+            .intern(&mut expression);
+
+        pretty_assertions::assert_eq!(
+            statements
+                .iter()
+                .map(|statement| match statement {
+                    Statement::LocalAssign(assign) => assign.iter_values().next().unwrap().clone(),
+                    _ => panic!("unexpected statement"),
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                Expression::from(StringExpression::from_value("first")),
+                Expression::from(StringExpression::from_value("second")),
+            ]
+        );
+        pretty_assertions::assert_eq!(expand(&statements, &expression), original);
+    }
+
+    #[test]
+    fn skips_names_of_referenced_identifiers() {
+        let repeated = StringExpression::from_value("a repeated long value");
+        let original: Expression = TableExpression::default()
+            .append_array_value(Expression::identifier("s1"))
+            .append_array_value(repeated.clone())
+            .append_array_value(repeated.clone())
+            .append_array_value(repeated)
+            .into();
+        let mut expression = original.clone();
+
+        let statements = InternDataStrings::new().intern(&mut expression);
+
+        pretty_assertions::assert_eq!(local_names(&statements), vec!["s2"]);
+        pretty_assertions::assert_eq!(expand(&statements, &expression), original);
+    }
+
+    #[test]
+    fn limits_the_number_of_locals() {
+        let values: Vec<_> = (0..MAX_INTERNED_STRINGS + 20)
+            .map(|i| format!("repeated value number {}", i))
+            .collect();
+        let original = data(serde_json::json!([values, values, values]));
+        let mut expression = original.clone();
+
+        let statements = InternDataStrings::new().intern(&mut expression);
+
+        pretty_assertions::assert_eq!(statements.len(), MAX_INTERNED_STRINGS);
+        pretty_assertions::assert_eq!(expand(&statements, &expression), original);
+    }
+
+    #[test]
+    fn deserialize_uses_default_values() {
+        let options: InternDataStrings = json5::from_str("{ min_count: 5 }").unwrap();
+
+        pretty_assertions::assert_eq!(options, InternDataStrings::new().with_min_count(5));
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+use std::mem;
+
+use crate::nodes::{Block, Expression, Statement};
+use crate::process::processors::FindUsage;
+use crate::process::{NodeVisitor, ScopeVisitor};
+
+#[derive(Debug, Default)]
+struct LazyScope {
+    // true when the scope is inside the body of a local function that is never referenced
+    unreferenced: bool,
+    prepared: bool,
+    // for each local function of the block that is not visited yet, if it is never referenced
+    local_functions: VecDeque<bool>,
+}
+
+/// Tracks the local functions that are never referenced, so that the requires found in
+/// their body are left out of the bundle. These functions (and their requires) can then
+/// be removed by the `remove_unused_variable` rule.
+#[derive(Debug, Default)]
+pub(crate) struct LazyRequires {
+    enabled: bool,
+    scopes: Vec<LazyScope>,
+    // set after visiting an unreferenced local function, until its scope is pushed
+    entering_unreferenced: bool,
+}
+
+impl LazyRequires {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn push_scope(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        let unreferenced = mem::take(&mut self.entering_unreferenced) || self.is_unreferenced();
+        self.scopes.push(LazyScope {
+            unreferenced,
+            ..Default::default()
+        });
+    }
+
+    pub(crate) fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Finds the local functions of a block that is about to be visited. The condition of
+    /// a `repeat` statement can reference the locals of its block.
+    pub(crate) fn prepare_block(&mut self, block: &mut Block, condition: Option<&mut Expression>) {
+        let scope = match self.scopes.last_mut() {
+            Some(scope) if !scope.prepared && !scope.unreferenced => scope,
+            _ => return,
+        };
+        scope.prepared = true;
+        scope.local_functions = find_unreferenced_local_functions(block, condition);
+    }
+
+    pub(crate) fn prepare_local_function(&mut self) {
+        self.entering_unreferenced = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.local_functions.pop_front())
+            .unwrap_or_default();
+    }
+
+    /// Returns true when the visited code is inside a local function that is never referenced.
+    pub(crate) fn is_unreferenced(&self) -> bool {
+        self.scopes.last().is_some_and(|scope| scope.unreferenced)
+    }
+}
+
+fn find_unreferenced_local_functions(
+    block: &mut Block,
+    mut condition: Option<&mut Expression>,
+) -> VecDeque<bool> {
+    let local_functions: Vec<_> = block
+        .iter_statements()
+        .enumerate()
+        .filter_map(|(index, statement)| match statement {
+            Statement::LocalFunction(function) => Some((index, function.get_name().to_owned())),
+            _ => None,
+        })
+        .collect();
+
+    local_functions
+        .into_iter()
+        .map(|(index, name)| {
+            let mut find_usage = FindUsage::new(&name);
+
+            let is_used = block
+                .iter_mut_statements()
+                .skip(index + 1)
+                .any(|next_statement| {
+                    ScopeVisitor::visit_statement(next_statement, &mut find_usage);
+                    find_usage.has_found_usage()
+                })
+                || block.mutate_last_statement().is_some_and(|last_statement| {
+                    ScopeVisitor::visit_last_statement(last_statement, &mut find_usage);
+                    find_usage.has_found_usage()
+                })
+                || condition.as_deref_mut().is_some_and(|condition| {
+                    ScopeVisitor::visit_expression(condition, &mut find_usage);
+                    find_usage.has_found_usage()
+                });
+
+            !is_used
+        })
+        .collect()
+}
@@ -0,0 +1,452 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::nodes::{
+    Expression, LocalAssignStatement, StringExpression, TableEntry, TableExpression,
+    TableFieldEntry, TableIndexEntry,
+};
+use crate::process::utils::is_valid_identifier;
+use crate::rules::Context;
+use crate::Resources;
+
+const DEFAULT_FIELDS: [(&str, Placeholder); 4] = [
+    ("time", Placeholder::Time),
+    ("entry", Placeholder::Entry),
+    ("modules", Placeholder::Modules),
+    ("darklua", Placeholder::Version),
+];
+
+const UNKNOWN_MODIFIED_TIME: &str = "unknown";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Time,
+    Modified,
+    Entry,
+    Modules,
+    Version,
+}
+
+impl Placeholder {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Time => "time",
+            Self::Modified => "modified",
+            Self::Entry => "entry",
+            Self::Modules => "modules",
+            Self::Version => "version",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "time" => Some(Self::Time),
+            "modified" => Some(Self::Modified),
+            "entry" => Some(Self::Entry),
+            "modules" => Some(Self::Modules),
+            "version" => Some(Self::Version),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// The value of a field of the bundle metadata table. Templates are strings that can
+/// contain the `{time}`, `{modified}`, `{entry}`, `{modules}` and `{version}` placeholders.
+///
+/// ```
+/// # use darklua_core::rules::bundle::MetadataTemplate;
+/// let template: MetadataTemplate = "darklua {version}".parse().unwrap();
+///
+/// assert_eq!(template.to_string(), "darklua {version}");
+/// assert!("{unknown}".parse::<MetadataTemplate>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MetadataTemplate {
+    segments: Vec<Segment>,
+}
+
+impl MetadataTemplate {
+    fn placeholder(placeholder: Placeholder) -> Self {
+        Self {
+            segments: vec![Segment::Placeholder(placeholder)],
+        }
+    }
+
+    fn as_placeholder(&self) -> Option<Placeholder> {
+        match self.segments.as_slice() {
+            [Segment::Placeholder(placeholder)] => Some(*placeholder),
+            _ => None,
+        }
+    }
+
+    fn has_time(&self) -> bool {
+        self.segments.iter().any(|segment| {
+            matches!(
+                segment,
+                Segment::Placeholder(Placeholder::Time)
+                    | Segment::Placeholder(Placeholder::Modified)
+            )
+        })
+    }
+}
+
+impl FromStr for MetadataTemplate {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut rest = value;
+
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed placeholder in metadata template `{}`", value))?
+                + start;
+            let name = &rest[start + 1..end];
+            let placeholder = Placeholder::from_name(name).ok_or_else(|| {
+                format!(
+                    "unknown placeholder `{{{}}}` in metadata template `{}` (expected `{{time}}`, `{{modified}}`, `{{entry}}`, `{{modules}}` or `{{version}}`)",
+                    name, value
+                )
+            })?;
+
+            if start > 0 {
+                segments.push(Segment::Text(rest[..start].to_owned()));
+            }
+            segments.push(Segment::Placeholder(placeholder));
+            rest = &rest[end + 1..];
+        }
+
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_owned()));
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+impl TryFrom<String> for MetadataTemplate {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for MetadataTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in self.segments.iter() {
+            match segment {
+                Segment::Text(text) => write!(f, "{}", text)?,
+                Segment::Placeholder(placeholder) => write!(f, "{{{}}}", placeholder.name())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<MetadataTemplate> for String {
+    fn from(template: MetadataTemplate) -> Self {
+        template.to_string()
+    }
+}
+
+/// A field of the `metadata` bundle option: `false` removes one of the default fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "MetadataFieldValue", into = "MetadataFieldValue")]
+pub(crate) enum MetadataField {
+    Disabled,
+    Template(MetadataTemplate),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum MetadataFieldValue {
+    Enabled(bool),
+    Template(MetadataTemplate),
+}
+
+impl TryFrom<MetadataFieldValue> for MetadataField {
+    type Error = String;
+
+    fn try_from(value: MetadataFieldValue) -> Result<Self, Self::Error> {
+        match value {
+            MetadataFieldValue::Enabled(false) => Ok(Self::Disabled),
+            MetadataFieldValue::Enabled(true) => {
+                Err("expected `false` or a template string for a metadata field".to_owned())
+            }
+            MetadataFieldValue::Template(template) => Ok(Self::Template(template)),
+        }
+    }
+}
+
+impl From<MetadataField> for MetadataFieldValue {
+    fn from(field: MetadataField) -> Self {
+        match field {
+            MetadataField::Disabled => Self::Enabled(false),
+            MetadataField::Template(template) => Self::Template(template),
+        }
+    }
+}
+
+/// The table of build information inserted in a bundle, after the module definitions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BundleMetadata {
+    variable: String,
+    fields: IndexMap<String, MetadataTemplate>,
+}
+
+impl BundleMetadata {
+    pub(crate) fn new<'a>(
+        variable: impl Into<String>,
+        fields: impl Iterator<Item = (&'a String, &'a MetadataField)>,
+    ) -> Self {
+        let mut metadata_fields: IndexMap<_, _> = DEFAULT_FIELDS
+            .iter()
+            .map(|(name, placeholder)| {
+                (
+                    (*name).to_owned(),
+                    MetadataTemplate::placeholder(*placeholder),
+                )
+            })
+            .collect();
+
+        for (name, field) in fields {
+            match field {
+                MetadataField::Disabled => {
+                    metadata_fields.shift_remove(name);
+                }
+                MetadataField::Template(template) => {
+                    metadata_fields.insert(name.clone(), template.clone());
+                }
+            }
+        }
+
+        Self {
+            variable: variable.into(),
+            fields: metadata_fields,
+        }
+    }
+
+    /// Removes the fields using the `{time}` or `{modified}` placeholders, which differ
+    /// from one build to another.
+    pub(crate) fn without_time_fields(mut self) -> Self {
+        self.fields.retain(|_, template| !template.has_time());
+        self
+    }
+
+    /// Builds the local assignment of the metadata table for a bundle made from the
+    /// current file of the context and the given module files.
+    pub(crate) fn build_statement<'a>(
+        &self,
+        context: &Context,
+        modules: impl Iterator<Item = &'a Path> + Clone,
+    ) -> LocalAssignStatement {
+        let entry = context.current_path();
+        let mut entries = Vec::new();
+
+        for (name, template) in self.fields.iter() {
+            let value: Expression = match template.as_placeholder() {
+                Some(Placeholder::Modules) => Expression::from(modules.clone().count()),
+                Some(Placeholder::Modified) => {
+                    match last_modified_time(context.resources(), entry, modules.clone()) {
+                        Some(time) => StringExpression::from_value(format_time(time)).into(),
+                        // fields assigned to nil would not be in the table anyway
+                        None => continue,
+                    }
+                }
+                _ => {
+                    let mut value = String::new();
+                    for segment in template.segments.iter() {
+                        match segment {
+                            Segment::Text(text) => value.push_str(text),
+                            Segment::Placeholder(placeholder) => {
+                                value.push_str(&match placeholder {
+                                    Placeholder::Time => format_time(SystemTime::now()),
+                                    Placeholder::Modified => last_modified_time(
+                                        context.resources(),
+                                        entry,
+                                        modules.clone(),
+                                    )
+                                    .map(format_time)
+                                    .unwrap_or_else(|| UNKNOWN_MODIFIED_TIME.to_owned()),
+                                    Placeholder::Entry => {
+                                        display_path(entry, context.project_location())
+                                    }
+                                    Placeholder::Modules => modules.clone().count().to_string(),
+                                    Placeholder::Version => env!("CARGO_PKG_VERSION").to_owned(),
+                                })
+                            }
+                        }
+                    }
+                    StringExpression::from_value(value).into()
+                }
+            };
+
+            let entry: TableEntry = if is_valid_identifier(name) {
+                TableFieldEntry::new(name.as_str(), value).into()
+            } else {
+                TableIndexEntry::new(StringExpression::from_value(name.as_str()), value).into()
+            };
+            entries.push(entry);
+        }
+
+        LocalAssignStatement::from_variable(self.variable.as_str())
+            .with_value(TableExpression::new(entries))
+    }
+}
+
+fn last_modified_time<'a>(
+    resources: &Resources,
+    entry: &Path,
+    modules: impl Iterator<Item = &'a Path>,
+) -> Option<SystemTime> {
+    std::iter::once(resources.modified(entry))
+        .chain(modules.map(|path| resources.modified(path)))
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .max()
+}
+
+fn display_path(path: &Path, location: &Path) -> String {
+    path.strip_prefix(location)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+// formats a time as an ISO 8601 date in UTC, like `2024-01-31T12:00:00Z`
+fn format_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let (days, seconds_of_day) = (seconds / 86_400, seconds % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+// converts a number of days since 1970-01-01 into a (year, month, day) date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn format_unix_epoch() {
+        pretty_assertions::assert_eq!(format_time(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn format_leap_day() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+
+        pretty_assertions::assert_eq!(format_time(time), "2024-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn parse_template_with_placeholders() {
+        let template: MetadataTemplate = "v{version} ({entry})".parse().unwrap();
+
+        pretty_assertions::assert_eq!(
+            template.segments,
+            vec![
+                Segment::Text("v".to_owned()),
+                Segment::Placeholder(Placeholder::Version),
+                Segment::Text(" (".to_owned()),
+                Segment::Placeholder(Placeholder::Entry),
+                Segment::Text(")".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_template_with_unclosed_placeholder() {
+        assert!("{version".parse::<MetadataTemplate>().is_err());
+    }
+
+    #[test]
+    fn disabled_fields_are_removed_from_defaults() {
+        let fields: IndexMap<String, MetadataField> = vec![
+            ("time".to_owned(), MetadataField::Disabled),
+            (
+                "commit".to_owned(),
+                MetadataField::Template("abc".parse().unwrap()),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let metadata = BundleMetadata::new("INFO", fields.iter());
+
+        pretty_assertions::assert_eq!(
+            metadata.fields.keys().collect::<Vec<_>>(),
+            vec!["entry", "modules", "darklua", "commit"]
+        );
+    }
+
+    #[test]
+    fn without_time_fields_removes_time_placeholders() {
+        let fields: IndexMap<String, MetadataField> = vec![
+            (
+                "built".to_owned(),
+                MetadataField::Template("at {modified}".parse().unwrap()),
+            ),
+            (
+                "name".to_owned(),
+                MetadataField::Template("app {version}".parse().unwrap()),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let metadata = BundleMetadata::new("INFO", fields.iter()).without_time_fields();
+
+        pretty_assertions::assert_eq!(
+            metadata.fields.keys().collect::<Vec<_>>(),
+            vec!["entry", "modules", "darklua", "name"]
+        );
+    }
+}
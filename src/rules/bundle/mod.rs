@@ -1,21 +1,61 @@
+mod case_sensitive_paths;
+mod dependency_graph;
+mod exclude_rewrite;
+mod global_access;
+mod identifier_conflict;
+mod intern_data_strings;
+mod lazy_requires;
+mod metadata;
+mod module_naming;
+mod module_order;
+mod module_varargs;
+mod multi_return_modules;
 pub(crate) mod path_require_mode;
+mod pcall_requires;
 mod rename_type_declaration;
 mod require_mode;
+mod require_statements;
 pub(crate) mod roblox_require_mode;
 
-use std::path::Path;
+use std::iter;
+use std::mem;
+use std::path::{Path, PathBuf};
 
-use crate::nodes::Block;
+use crate::frontend::{DarkluaResult, Resources};
+use crate::nodes::{
+    Block, Expression, FieldExpression, FunctionCall, Prefix, ReturnStatement, ReturnTokens,
+    StringExpression, Token, TriviaKind,
+};
+use crate::process::utils::is_valid_identifier;
 use crate::rules::{
-    Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult, RuleProperties,
+    Context, QuoteStyle, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult,
+    RuleProperties, RuleWarning,
 };
-use crate::Parser;
+use crate::{DarkluaError, Parser};
+use crate::utils::encode_base64;
 use crate::utils::source_registry::SourceRegistry;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+pub use case_sensitive_paths::CaseSensitivePaths;
+pub(crate) use dependency_graph::{DependencyGraph, RequireKind};
+pub use exclude_rewrite::ExcludeRewrite;
+pub use identifier_conflict::IdentifierConflict;
+pub use intern_data_strings::InternDataStrings;
+pub(crate) use lazy_requires::LazyRequires;
+pub use metadata::MetadataTemplate;
+pub(crate) use metadata::{BundleMetadata, MetadataField};
+pub use module_naming::ModuleNaming;
+pub(crate) use module_naming::ModuleNameGenerator;
+pub use module_order::ModuleOrder;
+pub use module_varargs::ModuleVarargs;
+pub(crate) use module_varargs::TopLevelVariableArguments;
+pub use multi_return_modules::MultiReturnModules;
+pub(crate) use multi_return_modules::{wrap_module_returns, WrappedModules};
+pub use pcall_requires::PcallRequires;
 pub(crate) use rename_type_declaration::RenameTypeDeclarationProcessor;
 pub use require_mode::BundleRequireMode;
+pub(crate) use require_statements::RequireStatements;
 use wax::Pattern;
 
 pub const BUNDLER_RULE_NAME: &str = "bundler";
@@ -27,10 +67,39 @@ pub(crate) struct BundleOptions {
     // Keep raw patterns so we can (re)compile them when a project root is provided
     exclude_patterns: Vec<String>,
     excludes: Option<wax::Any<'static>>,
+    // each rewrite with the matcher compiled from its pattern
+    exclude_rewrites: Vec<(ExcludeRewrite, Option<wax::Any<'static>>)>,
     registry: Rc<RefCell<SourceRegistry>>,
     sourcemap_enabled: bool,
+    strict_wait_for_child: bool,
+    verify_find_first_child: bool,
+    hoist_excluded_requires: bool,
+    allow_non_module_requires: bool,
+    tree_shake_lazy_requires: bool,
+    report_global_access: bool,
+    pcall_requires: PcallRequires,
+    module_naming: ModuleNaming,
+    module_order: ModuleOrder,
+    on_identifier_conflict: IdentifierConflict,
+    multi_return_modules: MultiReturnModules,
+    module_varargs: ModuleVarargs,
+    strip_types: bool,
+    case_sensitive_paths: CaseSensitivePaths,
+    quote_style: Option<QuoteStyle>,
+    null_sentinel: Option<String>,
+    intern_data_strings: Option<InternDataStrings>,
+    metadata: Option<BundleMetadata>,
+    raw_text_extensions: Vec<String>,
+    max_raw_text_size: usize,
+    binary_extensions: Vec<String>,
+    binary_wrapper: Option<String>,
+    max_binary_size: usize,
+    max_depth: usize,
+    // only recorded when the graph is written to a file
+    dependency_graph: Option<RefCell<DependencyGraph>>,
     // Optional project root used to resolve relative exclude patterns like "./" or "../"
     project_root: Option<std::path::PathBuf>,
+    pre_bundle_rules: Vec<Rc<dyn Rule>>,
 }
 
 impl BundleOptions {
@@ -45,9 +114,36 @@ impl BundleOptions {
             modules_identifier: modules_identifier.into(),
             exclude_patterns,
             excludes: None,
+            exclude_rewrites: Vec::new(),
             registry: Rc::new(RefCell::new(SourceRegistry::new())),
             sourcemap_enabled: false,
+            strict_wait_for_child: false,
+            verify_find_first_child: false,
+            hoist_excluded_requires: false,
+            allow_non_module_requires: false,
+            tree_shake_lazy_requires: false,
+            report_global_access: false,
+            pcall_requires: PcallRequires::default(),
+            module_naming: ModuleNaming::default(),
+            module_order: ModuleOrder::default(),
+            on_identifier_conflict: IdentifierConflict::default(),
+            multi_return_modules: MultiReturnModules::default(),
+            module_varargs: ModuleVarargs::default(),
+            strip_types: false,
+            case_sensitive_paths: CaseSensitivePaths::default(),
+            quote_style: None,
+            null_sentinel: None,
+            intern_data_strings: None,
+            metadata: None,
+            raw_text_extensions: Vec::new(),
+            max_raw_text_size: DEFAULT_MAX_RAW_TEXT_SIZE,
+            binary_extensions: Vec::new(),
+            binary_wrapper: None,
+            max_binary_size: DEFAULT_MAX_BINARY_SIZE,
+            max_depth: DEFAULT_MAX_DEPTH,
+            dependency_graph: None,
             project_root: None,
+            pre_bundle_rules: Vec::new(),
         };
         options.rebuild_excludes();
         options
@@ -68,7 +164,13 @@ impl BundleOptions {
             .unwrap_or(false)
     }
 
-    pub(crate) fn registry(&self) -> Rc<RefCell<SourceRegistry>> { self.registry.clone() }
+    /// Returns the first rewrite whose pattern matches the given excluded require.
+    fn find_exclude_rewrite(&self, require: &Path) -> Option<&ExcludeRewrite> {
+        self.exclude_rewrites
+            .iter()
+            .find(|(_, matcher)| matcher.as_ref().is_some_and(|any| any.is_match(require)))
+            .map(|(rewrite, _)| rewrite)
+    }
 
     pub(crate) fn source_paths_snapshot(&self) -> Vec<String> {
         let reg = self.registry.borrow();
@@ -79,46 +181,295 @@ impl BundleOptions {
 
     pub(crate) fn is_sourcemap_enabled(&self) -> bool { self.sourcemap_enabled }
 
-    fn rebuild_excludes(&mut self) {
-        // Compile provided patterns and, if a project root is set, also compile
-        // project-root-resolved versions of relative patterns (starting with '.' or '..').
-        let mut globs: Vec<wax::Glob> = Vec::new();
-        for pattern in &self.exclude_patterns {
-            match wax::Glob::new(pattern) {
-                Ok(glob) => globs.push(glob.into_owned()),
-                Err(err) => log::warn!(
-                    "unable to create exclude matcher from `{}`: {}",
-                    pattern,
-                    err.to_string()
-                ),
+    /// Returns the key identifying the module at the given path, so that a module reachable
+    /// through different paths (like a symlink or a different casing) has a single key.
+    pub(crate) fn module_key(&self, resources: &Resources, path: &Path) -> PathBuf {
+        self.case_sensitive_paths
+            .path_key(&resources.canonicalize(path))
+    }
+
+    /// Interns a bundled file in the shared registry. When paths are case-insensitive, the
+    /// path kept for the sourcemap uses the casing of the file on disk.
+    pub(crate) fn intern_source(&self, resources: &Resources, path: &Path) -> u32 {
+        let source_path = if self.case_sensitive_paths.is_case_sensitive() {
+            path.to_path_buf()
+        } else {
+            resources.resolve_casing(path)
+        };
+        let module_key = self.module_key(resources, path);
+
+        self.registry
+            .borrow_mut()
+            .intern_canonical(source_path, module_key)
+    }
+
+    /// Returns the source id of a bundled resource when sourcemaps are enabled.
+    pub(crate) fn resource_source_id(&self, resources: &Resources, path: &Path) -> Option<u32> {
+        self.sourcemap_enabled
+            .then(|| self.intern_source(resources, path))
+    }
+
+    /// Applies the rules configured to run before bundling to a required module.
+    fn apply_pre_bundle_rules(&self, block: &mut Block, context: &Context) -> DarkluaResult<()> {
+        for rule in self.pre_bundle_rules.iter() {
+            rule.process(block, context).map_err(|rule_error| {
+                DarkluaError::orphan_rule_error(context.current_path(), rule.as_ref(), rule_error)
+                    .with_causes(context.take_error_causes())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn is_strict_wait_for_child(&self) -> bool {
+        self.strict_wait_for_child
+    }
+
+    fn is_verify_find_first_child(&self) -> bool {
+        self.verify_find_first_child
+    }
+
+    fn is_hoist_excluded_requires(&self) -> bool {
+        self.hoist_excluded_requires
+    }
+
+    fn is_allow_non_module_requires(&self) -> bool {
+        self.allow_non_module_requires
+    }
+
+    fn is_tree_shake_lazy_requires(&self) -> bool {
+        self.tree_shake_lazy_requires
+    }
+
+    /// Returns a warning for each access to `_G` or `shared` in a bundled file, when
+    /// reporting these accesses is enabled.
+    fn global_access_warnings(&self, block: &mut Block, path: &Path) -> Vec<RuleWarning> {
+        if !self.report_global_access {
+            return Vec::new();
+        }
+        global_access::find_global_accesses(block)
+            .into_iter()
+            .map(|access| access.into_warning(path))
+            .collect()
+    }
+
+    fn pcall_requires(&self) -> PcallRequires {
+        self.pcall_requires
+    }
+
+    fn module_naming(&self) -> ModuleNaming {
+        self.module_naming
+    }
+
+    fn module_order(&self) -> ModuleOrder {
+        self.module_order
+    }
+
+    fn on_identifier_conflict(&self) -> IdentifierConflict {
+        self.on_identifier_conflict
+    }
+
+    fn multi_return_modules(&self) -> MultiReturnModules {
+        self.multi_return_modules
+    }
+
+    fn module_varargs(&self) -> ModuleVarargs {
+        self.module_varargs
+    }
+
+    fn is_strip_types(&self) -> bool {
+        self.strip_types
+    }
+
+    fn case_sensitive_paths(&self) -> CaseSensitivePaths {
+        self.case_sensitive_paths
+    }
+
+    fn quote_style(&self) -> Option<QuoteStyle> {
+        self.quote_style
+    }
+
+    fn intern_data_strings(&self) -> Option<InternDataStrings> {
+        self.intern_data_strings
+    }
+
+    fn metadata(&self) -> Option<BundleMetadata> {
+        self.metadata.clone()
+    }
+
+    fn null_sentinel(&self) -> Option<&str> {
+        self.null_sentinel.as_deref()
+    }
+
+    fn is_raw_text_extension(&self, extension: &str) -> bool {
+        extension == "txt"
+            || self
+                .raw_text_extensions
+                .iter()
+                .any(|raw_extension| raw_extension == extension)
+    }
+
+    /// Returns an error when requiring the given path from the bundled modules of the stack
+    /// would nest requires deeper than the maximum depth.
+    fn record_module(&self, path: &Path) {
+        if let Some(graph) = &self.dependency_graph {
+            graph.borrow_mut().add_module(path);
+        }
+    }
+
+    fn record_require(&self, from: &Path, to: &Path, kind: RequireKind) {
+        if let Some(graph) = &self.dependency_graph {
+            graph.borrow_mut().add_require(from, to, kind);
+        }
+    }
+
+    /// Returns the dependency graph recorded since the last call, when it is enabled.
+    pub(crate) fn take_dependency_graph(&self) -> Option<DependencyGraph> {
+        self.dependency_graph.as_ref().map(RefCell::take)
+    }
+
+    fn verify_require_depth(
+        &self,
+        require_stack: &[PathBuf],
+        require_path: &Path,
+    ) -> DarkluaResult<()> {
+        if require_stack.len() < self.max_depth {
+            return Ok(());
+        }
+
+        let first_printed = require_stack.len().saturating_sub(MAX_PRINTED_REQUIRES);
+        let deepest_requires: Vec<_> = require_stack[first_printed..]
+            .iter()
+            .map(|path| path.display().to_string())
+            .chain(iter::once(require_path.display().to_string()))
+            .collect();
+
+        Err(DarkluaError::custom(format!(
+            "unable to require `{}`: requires are nested deeper than the maximum depth of {} (see `max_depth`), with the deepest requires being `{}`",
+            require_path.display(),
+            self.max_depth,
+            deepest_requires.join("` > `")
+        )))
+    }
+
+    fn raw_text_expression(&self, path: &Path, content: String) -> DarkluaResult<Expression> {
+        if content.len() > self.max_raw_text_size {
+            return Err(DarkluaError::custom(format!(
+                "unable to require `{}` as a string: its size ({} bytes) exceeds the maximum of {} bytes (see `max_raw_text_size`)",
+                path.display(),
+                content.len(),
+                self.max_raw_text_size
+            )));
+        }
+        Ok(StringExpression::from_value(content).into())
+    }
+
+    fn is_binary_extension(&self, extension: &str) -> bool {
+        self.binary_extensions
+            .iter()
+            .any(|binary_extension| binary_extension == extension)
+    }
+
+    fn binary_expression(&self, path: &Path, content: &[u8]) -> DarkluaResult<Expression> {
+        if content.len() > self.max_binary_size {
+            return Err(DarkluaError::custom(format!(
+                "unable to require binary file `{}`: its size ({} bytes) exceeds the maximum of {} bytes (see `max_binary_size`)",
+                path.display(),
+                content.len(),
+                self.max_binary_size
+            )));
+        }
+
+        let encoded = StringExpression::from_value(encode_base64(content));
+
+        if let Some(wrapper) = &self.binary_wrapper {
+            let mut names = wrapper.split('.');
+            let invalid_wrapper = || {
+                DarkluaError::custom(format!(
+                    "invalid `binary_wrapper` value `{}`: expected identifiers separated by `.`",
+                    wrapper
+                ))
+            };
+            let root = names
+                .next()
+                .filter(|name| is_valid_identifier(name))
+                .ok_or_else(invalid_wrapper)?;
+            let mut prefix = Prefix::from_name(root);
+            for name in names {
+                if !is_valid_identifier(name) {
+                    return Err(invalid_wrapper());
+                }
+                prefix = FieldExpression::new(prefix, name).into();
             }
+            Ok(FunctionCall::from_prefix(prefix)
+                .with_argument(encoded)
+                .into())
+        } else {
+            Ok(encoded.into())
+        }
+    }
+
+    fn rebuild_excludes(&mut self) {
+        let globs: Vec<_> = self
+            .exclude_patterns
+            .iter()
+            .flat_map(|pattern| self.compile_pattern(pattern))
+            .collect();
+
+        self.excludes = Self::any_glob(globs);
+
+        let exclude_rewrites = mem::take(&mut self.exclude_rewrites);
+        self.exclude_rewrites = exclude_rewrites
+            .into_iter()
+            .map(|(rewrite, _)| {
+                let matcher = Self::any_glob(self.compile_pattern(rewrite.pattern()));
+                (rewrite, matcher)
+            })
+            .collect();
+    }
 
-            if let Some(root) = &self.project_root {
-                let is_relative = pattern.starts_with('.') || pattern.starts_with("..");
-                if is_relative {
-                    use crate::utils::normalize_path_with_current_dir;
-                    let resolved = normalize_path_with_current_dir(root.join(pattern));
-                    let resolved_str = resolved.to_string_lossy();
-                    match wax::Glob::new(&resolved_str) {
-                        Ok(glob) => globs.push(glob.into_owned()),
-                        Err(err) => log::warn!(
-                            "unable to create exclude matcher from project-resolved `{}`: {}",
-                            resolved_str,
-                            err.to_string()
-                        ),
-                    }
+    // Compile the given pattern and, if a project root is set, also compile the
+    // project-root-resolved version of a relative pattern (starting with '.' or '..').
+    fn compile_pattern(&self, pattern: &str) -> Vec<wax::Glob<'static>> {
+        let mut globs = Vec::new();
+
+        match wax::Glob::new(pattern) {
+            Ok(glob) => globs.push(glob.into_owned()),
+            Err(err) => log::warn!(
+                "unable to create exclude matcher from `{}`: {}",
+                pattern,
+                err.to_string()
+            ),
+        }
+
+        if let Some(root) = &self.project_root {
+            let is_relative = pattern.starts_with('.') || pattern.starts_with("..");
+            if is_relative {
+                use crate::utils::normalize_path_with_current_dir;
+                let resolved = normalize_path_with_current_dir(root.join(pattern));
+                let resolved_str = resolved.to_string_lossy();
+                match wax::Glob::new(&resolved_str) {
+                    Ok(glob) => globs.push(glob.into_owned()),
+                    Err(err) => log::warn!(
+                        "unable to create exclude matcher from project-resolved `{}`: {}",
+                        resolved_str,
+                        err.to_string()
+                    ),
                 }
             }
         }
 
-        self.excludes = if globs.is_empty() {
+        globs
+    }
+
+    fn any_glob(globs: Vec<wax::Glob<'static>>) -> Option<wax::Any<'static>> {
+        if globs.is_empty() {
             None
         } else {
             Some(
                 wax::any::<wax::Glob, _>(globs)
                     .expect("exclude globs errors should be filtered and only emit a warning"),
             )
-        };
+        }
     }
 }
 
@@ -146,6 +497,167 @@ impl Bundler {
         self
     }
 
+    pub(crate) fn with_strict_wait_for_child(mut self, strict: bool) -> Self {
+        self.options.strict_wait_for_child = strict;
+        self
+    }
+
+    pub(crate) fn with_verify_find_first_child(mut self, verify: bool) -> Self {
+        self.options.verify_find_first_child = verify;
+        self
+    }
+
+    pub(crate) fn with_hoist_excluded_requires(mut self, hoist: bool) -> Self {
+        self.options.hoist_excluded_requires = hoist;
+        self
+    }
+
+    pub(crate) fn with_exclude_rewrites(mut self, rewrites: Vec<ExcludeRewrite>) -> Self {
+        self.options.exclude_rewrites = rewrites
+            .into_iter()
+            .map(|rewrite| (rewrite, None))
+            .collect();
+        self.options.rebuild_excludes();
+        self
+    }
+
+    pub(crate) fn with_allow_non_module_requires(mut self, allow: bool) -> Self {
+        self.options.allow_non_module_requires = allow;
+        self
+    }
+
+    pub(crate) fn with_tree_shake_lazy_requires(mut self, tree_shake: bool) -> Self {
+        self.options.tree_shake_lazy_requires = tree_shake;
+        self
+    }
+
+    pub(crate) fn with_report_global_access(mut self, report: bool) -> Self {
+        self.options.report_global_access = report;
+        self
+    }
+
+    pub(crate) fn with_pcall_requires(mut self, pcall_requires: PcallRequires) -> Self {
+        self.options.pcall_requires = pcall_requires;
+        self
+    }
+
+    pub(crate) fn with_module_naming(mut self, module_naming: ModuleNaming) -> Self {
+        self.options.module_naming = module_naming;
+        self
+    }
+
+    pub(crate) fn with_module_order(mut self, module_order: ModuleOrder) -> Self {
+        self.options.module_order = module_order;
+        self
+    }
+
+    pub(crate) fn with_on_identifier_conflict(
+        mut self,
+        on_identifier_conflict: IdentifierConflict,
+    ) -> Self {
+        self.options.on_identifier_conflict = on_identifier_conflict;
+        self
+    }
+
+    pub(crate) fn with_multi_return_modules(
+        mut self,
+        multi_return_modules: MultiReturnModules,
+    ) -> Self {
+        self.options.multi_return_modules = multi_return_modules;
+        self
+    }
+
+    pub(crate) fn with_module_varargs(mut self, module_varargs: ModuleVarargs) -> Self {
+        self.options.module_varargs = module_varargs;
+        self
+    }
+
+    pub(crate) fn with_strip_types(mut self, strip_types: bool) -> Self {
+        self.options.strip_types = strip_types;
+        self
+    }
+
+    pub(crate) fn with_case_sensitive_paths(
+        mut self,
+        case_sensitive_paths: CaseSensitivePaths,
+    ) -> Self {
+        self.options.case_sensitive_paths = case_sensitive_paths;
+        self
+    }
+
+    pub(crate) fn with_quote_style(mut self, quote_style: Option<QuoteStyle>) -> Self {
+        self.options.quote_style = quote_style;
+        self
+    }
+
+    pub(crate) fn with_null_sentinel(mut self, identifier: impl Into<String>) -> Self {
+        self.options.null_sentinel = Some(identifier.into());
+        self
+    }
+
+    pub(crate) fn with_intern_data_strings(
+        mut self,
+        intern_data_strings: Option<InternDataStrings>,
+    ) -> Self {
+        self.options.intern_data_strings = intern_data_strings;
+        self
+    }
+
+    pub(crate) fn with_metadata(mut self, metadata: BundleMetadata) -> Self {
+        self.options.metadata = Some(metadata);
+        self
+    }
+
+    pub(crate) fn with_raw_text_extensions<'a>(
+        mut self,
+        extensions: impl Iterator<Item = &'a str>,
+    ) -> Self {
+        self.options.raw_text_extensions = extensions
+            .map(|extension| extension.trim_start_matches('.').to_ascii_lowercase())
+            .collect();
+        self
+    }
+
+    pub(crate) fn with_max_raw_text_size(mut self, size: usize) -> Self {
+        self.options.max_raw_text_size = size;
+        self
+    }
+
+    pub(crate) fn with_binary_extensions<'a>(
+        mut self,
+        extensions: impl Iterator<Item = &'a str>,
+    ) -> Self {
+        self.options.binary_extensions = extensions
+            .map(|extension| extension.trim_start_matches('.').to_ascii_lowercase())
+            .collect();
+        self
+    }
+
+    pub(crate) fn with_binary_wrapper(mut self, wrapper: impl Into<String>) -> Self {
+        self.options.binary_wrapper = Some(wrapper.into());
+        self
+    }
+
+    pub(crate) fn with_max_binary_size(mut self, size: usize) -> Self {
+        self.options.max_binary_size = size;
+        self
+    }
+
+    pub(crate) fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.options.max_depth = max_depth;
+        self
+    }
+
+    pub(crate) fn with_dependency_graph(mut self) -> Self {
+        self.options.dependency_graph = Some(RefCell::default());
+        self
+    }
+
+    pub(crate) fn with_pre_bundle_rules(mut self, rules: Vec<Rc<dyn Rule>>) -> Self {
+        self.options.pre_bundle_rules = rules;
+        self
+    }
+
     /// Use an external shared SourceRegistry so that source ids remain consistent
     /// across the entry file, bundled modules, and sourcemap generation.
     pub(crate) fn with_registry(
@@ -172,6 +684,7 @@ impl Bundler {
 
 impl Rule for Bundler {
     fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+        self.options.record_module(context.current_path());
         self.require_mode
             .process_block(block, context, &self.options)
     }
@@ -193,7 +706,31 @@ impl RuleConfiguration for Bundler {
     }
 }
 
+/// Sorts errors by path, then by message, so that bundle errors are reported in the same
+/// order between runs.
+pub(crate) fn sort_errors(errors: &mut [DarkluaError]) {
+    errors.sort_by_cached_key(|error| (error.path().map(Path::to_path_buf), error.to_string()));
+}
+
+/// Builds the return statement of a module bundled from a non-Lua resource (like a json
+/// file). With a source id, the statement maps to the first line of the resource.
+pub(crate) fn value_module_return(expression: Expression, source_id: Option<u32>) -> ReturnStatement {
+    let statement = ReturnStatement::one(expression);
+    match source_id {
+        Some(source_id) => statement.with_tokens(ReturnTokens {
+            r#return: Token::from_content_with_origin("return", 1, source_id)
+                .with_trailing_trivia(TriviaKind::Whitespace.with_content(" ")),
+            commas: Vec::new(),
+        }),
+        None => statement,
+    }
+}
+
 const DEFAULT_MODULE_IDENTIFIER: &str = "__DARKLUA_BUNDLE_MODULES";
+const DEFAULT_MAX_RAW_TEXT_SIZE: usize = 1024 * 1024;
+const DEFAULT_MAX_BINARY_SIZE: usize = 1024 * 1024;
+const DEFAULT_MAX_DEPTH: usize = 200;
+const MAX_PRINTED_REQUIRES: usize = 5;
 
 #[cfg(test)]
 mod test {
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::rules::GeneratedIdentifiers;
+use crate::utils;
+
+// fields of the modules table that cannot be used as module names
+const RESERVED_MODULE_NAMES: [&str; 2] = ["cache", "load"];
+// module names are fields of the modules table, so they are not reserved for the other
+// generated identifiers of the file
+const MODULE_NAMES_NAMESPACE: &str = "bundle_modules";
+
+/// Defines how the bundler names the modules stored in the modules table.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleNaming {
+    /// Names modules `a`, `b`, `c`, ... in the order they are required.
+    #[default]
+    Sequential,
+    /// Names modules from a hash of their path relative to the project, so that names do
+    /// not change when other modules are added or removed.
+    ContentHash,
+}
+
+impl ModuleNaming {
+    pub(crate) fn is_sequential(&self) -> bool {
+        matches!(self, Self::Sequential)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ModuleNameGenerator {
+    naming: ModuleNaming,
+    project_location: Option<PathBuf>,
+    used_names: HashMap<String, PathBuf>,
+}
+
+impl ModuleNameGenerator {
+    pub(crate) fn new(naming: ModuleNaming) -> Self {
+        Self {
+            naming,
+            project_location: None,
+            used_names: Default::default(),
+        }
+    }
+
+    pub(crate) fn with_project_location(mut self, location: impl Into<PathBuf>) -> Self {
+        self.project_location = Some(location.into());
+        self
+    }
+
+    /// Generates the name of the module at the given path. Sequential names are drawn from
+    /// the names generated for the current file and avoid its reserved names. Content hash
+    /// names only depend on the module path. When two different modules have the same hash,
+    /// a counter is appended to the name of the module found last.
+    pub(crate) fn generate(
+        &mut self,
+        module_path: &Path,
+        generated_identifiers: &mut GeneratedIdentifiers,
+    ) -> String {
+        match self.naming {
+            ModuleNaming::Sequential => loop {
+                let name = generated_identifiers.generate_name(MODULE_NAMES_NAMESPACE);
+
+                if !RESERVED_MODULE_NAMES.contains(&name.as_str()) {
+                    break name;
+                }
+            },
+            ModuleNaming::ContentHash => {
+                let hashed_path = self.hashed_path(module_path);
+                let base_name = content_hash_name(&hashed_path);
+                let mut name = base_name.clone();
+                let mut counter = 1;
+
+                loop {
+                    match self.used_names.get(&name) {
+                        Some(used_path) if used_path == &hashed_path => break name,
+                        Some(_) => {
+                            counter += 1;
+                            name = format!("{}_{}", base_name, counter);
+                        }
+                        None => {
+                            self.used_names.insert(name.clone(), hashed_path);
+                            break name;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // the path is made relative to the project so that names do not depend on where the
+    // project is located
+    fn hashed_path(&self, module_path: &Path) -> PathBuf {
+        let module_path = utils::normalize_path(module_path);
+
+        self.project_location
+            .as_ref()
+            .map(utils::normalize_path)
+            .and_then(|location| {
+                module_path
+                    .strip_prefix(location)
+                    .ok()
+                    .map(Path::to_path_buf)
+            })
+            .unwrap_or(module_path)
+    }
+}
+
+// hashes the path with forward slashes so that names are the same on every platform. The
+// name starts with an underscore because module names must be valid identifiers
+fn content_hash_name(module_path: &Path) -> String {
+    let path = module_path.to_string_lossy().replace('\\', "/");
+    format!("_{:08x}", xxh3_64(path.as_bytes()) as u32)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sequential_names_skip_reserved_names() {
+        let mut generator = ModuleNameGenerator::new(ModuleNaming::Sequential);
+        let mut identifiers = GeneratedIdentifiers::default();
+
+        let names: Vec<_> = (0..64)
+            .map(|i| generator.generate(Path::new(&format!("module{}.lua", i)), &mut identifiers))
+            .collect();
+
+        assert_eq!(names[0], "a");
+        assert!(names
+            .iter()
+            .all(|name| !RESERVED_MODULE_NAMES.contains(&name.as_str())));
+    }
+
+    #[test]
+    fn sequential_names_avoid_names_reserved_by_the_file() {
+        let mut generator = ModuleNameGenerator::new(ModuleNaming::Sequential);
+        let mut identifiers = GeneratedIdentifiers::default();
+        identifiers.reserve("a");
+
+        assert_eq!(
+            generator.generate(Path::new("module.lua"), &mut identifiers),
+            "b"
+        );
+    }
+
+    #[test]
+    fn content_hash_name_has_8_hex_characters() {
+        let name = ModuleNameGenerator::new(ModuleNaming::ContentHash).generate(
+            Path::new("src/value.lua"),
+            &mut GeneratedIdentifiers::default(),
+        );
+
+        assert_eq!(name.len(), 9);
+        assert!(name.starts_with('_'));
+        assert!(name[1..].chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn content_hash_name_does_not_depend_on_separators() {
+        assert_eq!(
+            content_hash_name(Path::new("src/value.lua")),
+            content_hash_name(Path::new("src\\value.lua"))
+        );
+    }
+
+    #[test]
+    fn content_hash_name_does_not_depend_on_project_location() {
+        let mut identifiers = GeneratedIdentifiers::default();
+
+        let first = ModuleNameGenerator::new(ModuleNaming::ContentHash)
+            .with_project_location("/home/user/project")
+            .generate(
+                Path::new("/home/user/project/src/value.lua"),
+                &mut identifiers,
+            );
+        let second = ModuleNameGenerator::new(ModuleNaming::ContentHash)
+            .with_project_location("/tmp/build/project")
+            .generate(
+                Path::new("/tmp/build/project/src/value.lua"),
+                &mut identifiers,
+            );
+
+        assert_eq!(first, second);
+        assert_eq!(first, content_hash_name(Path::new("src/value.lua")));
+    }
+
+    #[test]
+    fn content_hash_name_is_the_same_for_the_same_module() {
+        let mut generator = ModuleNameGenerator::new(ModuleNaming::ContentHash);
+        let mut identifiers = GeneratedIdentifiers::default();
+
+        let first = generator.generate(Path::new("src/value.lua"), &mut identifiers);
+        let second = generator.generate(Path::new("./src/value.lua"), &mut identifiers);
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn content_hash_name_collision_appends_counter() {
+        let mut generator = ModuleNameGenerator::new(ModuleNaming::ContentHash);
+        let mut identifiers = GeneratedIdentifiers::default();
+        let name = content_hash_name(Path::new("src/value.lua"));
+        generator
+            .used_names
+            .insert(name.clone(), PathBuf::from("src/other.lua"));
+
+        let first = generator.generate(Path::new("src/value.lua"), &mut identifiers);
+        let second = generator.generate(Path::new("src/value.lua"), &mut identifiers);
+
+        pretty_assertions::assert_eq!(first, format!("{}_2", name));
+        pretty_assertions::assert_eq!(second, first);
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Defines the order of the module definitions written in the bundle.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleOrder {
+    /// Writes modules in the order they are first required.
+    #[default]
+    Discovery,
+    /// Sorts modules by their path, so that reordering requires does not move the
+    /// module definitions.
+    Path,
+}
+
+impl ModuleOrder {
+    pub(crate) fn is_discovery(&self) -> bool {
+        matches!(self, Self::Discovery)
+    }
+
+    /// Sorts the module definitions (indexed by module name) using the path of each module.
+    pub(crate) fn sort<T>(
+        &self,
+        module_definitions: &mut IndexMap<String, T>,
+        get_path: impl Fn(&T) -> &Path,
+    ) {
+        match self {
+            Self::Discovery => {}
+            Self::Path => module_definitions.sort_by(|_, a, _, b| get_path(a).cmp(get_path(b))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sorted_names(order: ModuleOrder, paths: &[&'static str]) -> Vec<String> {
+        let mut module_definitions: IndexMap<_, _> = paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| (format!("m{}", i), Path::new(path)))
+            .collect();
+
+        order.sort(&mut module_definitions, |path| path);
+
+        module_definitions.into_keys().collect()
+    }
+
+    #[test]
+    fn discovery_order_keeps_insertion_order() {
+        assert_eq!(
+            sorted_names(ModuleOrder::Discovery, &["src/b.lua", "src/a.lua"]),
+            vec!["m0", "m1"]
+        );
+    }
+
+    #[test]
+    fn path_order_sorts_by_path() {
+        assert_eq!(
+            sorted_names(
+                ModuleOrder::Path,
+                &["src/b.lua", "src/a/init.lua", "src/a.lua"]
+            ),
+            vec!["m1", "m2", "m0"]
+        );
+    }
+
+    #[test]
+    fn deserialize_path_order() {
+        assert_eq!(
+            serde_json::from_str::<ModuleOrder>("\"path\"").unwrap(),
+            ModuleOrder::Path
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+use crate::nodes::{
+    Block, Expression, FunctionExpression, FunctionStatement, LocalFunctionStatement,
+};
+use crate::process::{DefaultPostVisitor, NodePostProcessor, NodePostVisitor, NodeProcessor};
+use crate::DarkluaError;
+
+/// Defines how the bundler handles required modules that use `...` outside of a function.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleVarargs {
+    /// Reports an error for modules that use `...` outside of a function.
+    #[default]
+    Error,
+    /// Declares the module functions as variadic, so `...` receives nothing like when the
+    /// module is loaded with `require`.
+    Nil,
+}
+
+impl ModuleVarargs {
+    pub(crate) fn is_error(&self) -> bool {
+        matches!(self, Self::Error)
+    }
+}
+
+#[derive(Debug, Default)]
+struct TopLevelVariableArgumentsFinder {
+    function_depth: usize,
+    found: bool,
+    line: Option<usize>,
+}
+
+impl NodeProcessor for TopLevelVariableArgumentsFinder {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if self.found || self.function_depth != 0 {
+            return;
+        }
+        if let Expression::VariableArguments(token) = expression {
+            self.found = true;
+            self.line = token.as_ref().and_then(|token| token.get_line_number());
+        }
+    }
+
+    fn process_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.function_depth += 1;
+    }
+
+    fn process_function_statement(&mut self, _: &mut FunctionStatement) {
+        self.function_depth += 1;
+    }
+
+    fn process_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.function_depth += 1;
+    }
+}
+
+impl NodePostProcessor for TopLevelVariableArgumentsFinder {
+    fn process_after_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.function_depth -= 1;
+    }
+
+    fn process_after_function_statement(&mut self, _: &mut FunctionStatement) {
+        self.function_depth -= 1;
+    }
+
+    fn process_after_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.function_depth -= 1;
+    }
+}
+
+/// A use of `...` in the main chunk of a module, outside of any function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TopLevelVariableArguments {
+    line: Option<usize>,
+}
+
+impl TopLevelVariableArguments {
+    /// Finds the first use of `...` outside of any function of the given block.
+    pub(crate) fn find(block: &mut Block) -> Option<Self> {
+        let mut finder = TopLevelVariableArgumentsFinder::default();
+        DefaultPostVisitor::visit_block(block, &mut finder);
+        finder.found.then_some(Self { line: finder.line })
+    }
+
+    pub(crate) fn into_error(self, module: impl std::fmt::Display) -> DarkluaError {
+        let location = match self.line {
+            Some(line) => format!(" at line {}", line),
+            None => String::new(),
+        };
+        DarkluaError::custom(format!(
+            concat!(
+                "invalid Lua module at `{}`: `...` is used outside of a function{}, ",
+                "but bundled modules do not receive their own arguments ",
+                "(set `module_varargs` to `nil` to make `...` empty)"
+            ),
+            module, location
+        ))
+    }
+}
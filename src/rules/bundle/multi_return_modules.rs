@@ -0,0 +1,287 @@
+use std::mem;
+
+use serde::{Deserialize, Serialize};
+
+use crate::nodes::{
+    Arguments, AssignStatement, Block, Expression, FieldExpression, FunctionCall,
+    FunctionExpression, FunctionStatement, Identifier, IndexExpression, LastStatement,
+    LocalAssignStatement, LocalFunctionStatement, ReturnStatement, ReturnTokens, TableEntry,
+    TableExpression, TupleArguments,
+};
+use crate::process::{
+    DefaultPostVisitor, DefaultVisitor, NodePostProcessor, NodePostVisitor, NodeProcessor,
+    NodeVisitor,
+};
+
+/// Defines how the bundler handles modules that do not return exactly one value.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MultiReturnModules {
+    /// Reports an error for modules that do not return exactly one value.
+    #[default]
+    Error,
+    /// Returns the values of the module packed into a table, which is unpacked where the
+    /// module is required.
+    Wrap,
+}
+
+impl MultiReturnModules {
+    pub(crate) fn is_error(&self) -> bool {
+        matches!(self, Self::Error)
+    }
+}
+
+const COUNT_FIELD: &str = "n";
+
+/// The number of values packed into the table returned by a wrapped module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ValueCount {
+    Known(usize),
+    // the last value can expand to any number of values, so the count is read from the
+    // `n` field of the table created with `table.pack`
+    Packed,
+}
+
+#[derive(Debug, Clone)]
+struct WrappedModule {
+    // the expression that loads the module, which evaluates to the packed values
+    value: Expression,
+    count: ValueCount,
+}
+
+impl WrappedModule {
+    fn first_value(&self) -> Expression {
+        IndexExpression::new(self.value.clone(), 1).into()
+    }
+
+    fn all_values(&self) -> Expression {
+        let count = match self.count {
+            ValueCount::Known(count) => Expression::from(count),
+            ValueCount::Packed => FieldExpression::new(self.value.clone(), COUNT_FIELD).into(),
+        };
+
+        table_function_call("unpack")
+            .with_arguments(
+                TupleArguments::default()
+                    .with_argument(self.value.clone())
+                    .with_argument(1)
+                    .with_argument(count),
+            )
+            .into()
+    }
+}
+
+/// Tracks the bundled modules that return their values packed into a table.
+#[derive(Debug, Default)]
+pub(crate) struct WrappedModules {
+    modules: Vec<WrappedModule>,
+}
+
+impl WrappedModules {
+    /// Records that the given module value evaluates to a table of packed values.
+    pub(crate) fn insert(&mut self, value: Expression, count: ValueCount) {
+        self.modules.push(WrappedModule { value, count });
+    }
+
+    /// Converts the value of a required module into its first value when the module is
+    /// wrapped.
+    pub(crate) fn single_value(&self, value: Expression) -> Expression {
+        match self.find(&value) {
+            Some(module) => module.first_value(),
+            None => value,
+        }
+    }
+
+    /// Converts the value of a required module into all its values when the module is
+    /// wrapped.
+    pub(crate) fn all_values(&self, value: Expression) -> Expression {
+        match self.find(&value) {
+            Some(module) => module.all_values(),
+            None => value,
+        }
+    }
+
+    /// Unpacks all the values of wrapped modules required where multiple values are
+    /// expected, like the last value of a local assignment with more variables than values.
+    pub(crate) fn expand_values(&self, block: &mut Block) {
+        if self.modules.is_empty() {
+            return;
+        }
+        let mut processor = ExpandWrappedValues { modules: self };
+        DefaultVisitor::visit_block(block, &mut processor);
+    }
+
+    fn find(&self, value: &Expression) -> Option<&WrappedModule> {
+        self.modules.iter().find(|module| &module.value == value)
+    }
+
+    fn find_first_value(&self, value: &Expression) -> Option<&WrappedModule> {
+        if !matches!(value, Expression::Index(_)) {
+            return None;
+        }
+        self.modules
+            .iter()
+            .find(|module| &module.first_value() == value)
+    }
+}
+
+/// Replaces the values of every return statement of a module (outside of the functions it
+/// defines) with a single table containing them, when one of these statements does not
+/// return exactly one value.
+pub(crate) fn wrap_module_returns(block: &mut Block) -> Option<ValueCount> {
+    let mut finder = ModuleReturns::default();
+    DefaultPostVisitor::visit_block(block, &mut finder);
+
+    if !finder.has_multiple_values {
+        return None;
+    }
+
+    let mut wrapper = ModuleReturns {
+        wrap: true,
+        ..Default::default()
+    };
+    DefaultPostVisitor::visit_block(block, &mut wrapper);
+
+    let mut counts = wrapper.counts.into_iter();
+    let first = counts.next()?;
+    Some(if counts.all(|count| count == first) {
+        first
+    } else {
+        // every wrapped table has a `n` field, so the count can be read from it
+        ValueCount::Packed
+    })
+}
+
+// Finds or wraps the return statements of a module that are not inside one of its functions.
+#[derive(Default)]
+struct ModuleReturns {
+    function_depth: usize,
+    wrap: bool,
+    has_multiple_values: bool,
+    counts: Vec<ValueCount>,
+}
+
+impl NodeProcessor for ModuleReturns {
+    fn process_last_statement(&mut self, statement: &mut LastStatement) {
+        if self.function_depth != 0 {
+            return;
+        }
+        if let LastStatement::Return(statement) = statement {
+            if self.wrap {
+                self.counts.push(wrap_module_return(statement));
+            } else if statement.len() != 1 {
+                self.has_multiple_values = true;
+            }
+        }
+    }
+
+    fn process_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.function_depth += 1;
+    }
+
+    fn process_function_statement(&mut self, _: &mut FunctionStatement) {
+        self.function_depth += 1;
+    }
+
+    fn process_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.function_depth += 1;
+    }
+}
+
+impl NodePostProcessor for ModuleReturns {
+    fn process_after_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.function_depth -= 1;
+    }
+
+    fn process_after_function_statement(&mut self, _: &mut FunctionStatement) {
+        self.function_depth -= 1;
+    }
+
+    fn process_after_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.function_depth -= 1;
+    }
+}
+
+// Replaces the values of a module return statement with a single table containing them.
+fn wrap_module_return(statement: &mut ReturnStatement) -> ValueCount {
+    let tokens = statement.get_tokens().map(|tokens| ReturnTokens {
+        r#return: tokens.r#return.clone(),
+        commas: Vec::new(),
+    });
+    let values: Vec<_> = mem::take(statement).into_iter_expressions().collect();
+
+    let is_packed = values
+        .last()
+        .is_some_and(|last| matches!(last, Expression::Call(_) | Expression::VariableArguments(_)));
+
+    let (table, count) = if is_packed {
+        let call = table_function_call("pack").with_arguments(TupleArguments::new(values));
+        (Expression::from(call), ValueCount::Packed)
+    } else {
+        let count = values.len();
+        let table = values
+            .into_iter()
+            .fold(TableExpression::default(), |table, value| {
+                table.append_array_value(value)
+            })
+            .append_field(COUNT_FIELD, count);
+        (table.into(), ValueCount::Known(count))
+    };
+
+    *statement = ReturnStatement::one(table);
+    if let Some(tokens) = tokens {
+        statement.set_tokens(tokens);
+    }
+
+    count
+}
+
+fn table_function_call(field: &str) -> FunctionCall {
+    FunctionCall::from_prefix(FieldExpression::new(Identifier::new("table"), field))
+}
+
+struct ExpandWrappedValues<'a> {
+    modules: &'a WrappedModules,
+}
+
+impl ExpandWrappedValues<'_> {
+    fn expand(&self, value: Option<&mut Expression>) {
+        if let Some(value) = value {
+            if let Some(module) = self.modules.find_first_value(value) {
+                *value = module.all_values();
+            }
+        }
+    }
+}
+
+impl NodeProcessor for ExpandWrappedValues<'_> {
+    fn process_local_assign_statement(&mut self, statement: &mut LocalAssignStatement) {
+        if statement.variables_len() > statement.values_len() {
+            self.expand(statement.iter_mut_values().last());
+        }
+    }
+
+    fn process_assign_statement(&mut self, statement: &mut AssignStatement) {
+        if statement.variables_len() > statement.values_len() {
+            self.expand(statement.iter_mut_values().last());
+        }
+    }
+
+    fn process_last_statement(&mut self, statement: &mut LastStatement) {
+        if let LastStatement::Return(statement) = statement {
+            self.expand(statement.iter_mut_expressions().last());
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if let Arguments::Tuple(arguments) = call.mutate_arguments() {
+            self.expand(arguments.iter_mut_values().last());
+        }
+    }
+
+    fn process_table_expression(&mut self, table: &mut TableExpression) {
+        if let Some(TableEntry::Value(value)) = table.iter_mut_entries().last() {
+            self.expand(Some(value.as_mut()));
+        }
+    }
+}
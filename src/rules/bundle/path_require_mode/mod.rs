@@ -3,7 +3,6 @@ mod module_definitions;
 use module_definitions::BuildModuleDefinitions;
 
 use std::collections::{HashMap, HashSet};
-use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 use std::{iter, mem};
 
@@ -11,22 +10,29 @@ use serde::Serialize;
 
 use crate::frontend::DarkluaResult;
 use crate::nodes::{
-    Block, DoStatement, Expression, FunctionCall, LocalAssignStatement, Prefix, Statement,
-    StringExpression,
+    AssignStatement, Block, CompoundAssignStatement, Expression, FunctionCall,
+    LocalAssignStatement, LocalFunctionStatement, Prefix, Statement, StringQuote, TableExpression,
+    Variable,
 };
+use crate::process::utils::is_valid_identifier;
 use crate::process::{
-    to_expression, DefaultVisitor, IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor,
+    to_data_expression, NodePostProcessor, NodePostVisitor, NodeProcessor, Scope, ScopePostVisitor,
 };
 use crate::rules::require::{
-    is_require_call, match_path_require_call, PathRequireMode, RequirePathLocator,
+    find_arguments_quote, format_require_call, match_path_require_call,
+    match_protected_require_call, match_require_directive, require_call_line, PathRequireMode,
+    ProtectedRequireCall, RequireAliases, RequireDirective, RequirePathLocator,
 };
 use crate::rules::{
-    Context, ContextBuilder, FlawlessRule, ReplaceReferencedTokens, RuleProcessResult,
+    Context, ContextBuilder, FlawlessRule, QuoteStyle, ReplaceReferencedTokens, RuleProcessResult,
+    RuleWarning, SharedGeneratedIdentifiers, WarningCode,
 };
 use crate::utils::Timer;
 use crate::{DarkluaError, Resources};
 
-use super::BundleOptions;
+use super::{
+    sort_errors, BundleOptions, LazyRequires, PcallRequires, RequireKind, RequireStatements,
+};
 
 pub(crate) enum RequiredResource {
     Block(Block),
@@ -36,15 +42,23 @@ pub(crate) enum RequiredResource {
 #[derive(Debug)]
 struct RequirePathProcessor<'a, 'b, 'resources, 'code> {
     options: &'a BundleOptions,
-    identifier_tracker: IdentifierTracker,
+    require_aliases: RequireAliases,
+    lazy_requires: LazyRequires,
+    require_statements: RequireStatements,
     path_locator: RequirePathLocator<'b, 'code, 'resources>,
     module_definitions: BuildModuleDefinitions,
+    generated_identifiers: SharedGeneratedIdentifiers,
     source: PathBuf,
+    original_code: &'b str,
+    // the module content is only stored once in the module definitions, and each cached
+    // expression is the small call that loads it
     module_cache: HashMap<PathBuf, Expression>,
     require_stack: Vec<PathBuf>,
     skip_module_paths: HashSet<PathBuf>,
     resources: &'resources Resources,
-    errors: Vec<String>,
+    errors: Vec<DarkluaError>,
+    warnings: Vec<RuleWarning>,
+    uses_null_sentinel: bool,
 }
 
 impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources> {
@@ -58,53 +72,179 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
         'context: 'resources,
         'context: 'code,
     {
+        let modules_identifier = context
+            .generated_identifiers()
+            .borrow_mut()
+            .unique_name(options.modules_identifier());
         Self {
             options,
-            identifier_tracker: IdentifierTracker::new(),
+            require_aliases: RequireAliases::default(),
+            lazy_requires: LazyRequires::new(options.is_tree_shake_lazy_requires()),
+            require_statements: Default::default(),
             path_locator: RequirePathLocator::new(
                 path_require_mode,
                 context.project_location(),
                 context.resources(),
             ),
             module_definitions: BuildModuleDefinitions::new(
-                options.modules_identifier(),
+                modules_identifier,
                 options.is_sourcemap_enabled(),
-            ),
+                options.module_naming(),
+                options.multi_return_modules(),
+                options.module_varargs(),
+                options.is_strip_types(),
+                options.quote_style(),
+            )
+            .with_module_order(options.module_order())
+            .with_intern_data_strings(options.intern_data_strings())
+            .with_metadata(options.metadata())
+            .with_project_location(context.project_location()),
+            generated_identifiers: context.generated_identifiers().clone(),
             source: context.current_path().to_path_buf(),
+            original_code: context.original_code(),
             module_cache: Default::default(),
             require_stack: Default::default(),
             skip_module_paths: Default::default(),
             resources: context.resources(),
             errors: Vec::new(),
+            warnings: Vec::new(),
+            uses_null_sentinel: false,
         }
     }
 
     fn apply(self, block: &mut Block, context: &Context) -> RuleProcessResult {
+        self.module_definitions
+            .wrapped_modules()
+            .expand_values(block);
         self.module_definitions.apply(block, context);
-        match self.errors.len() {
+        let uses_null_sentinel = self.uses_null_sentinel;
+        if let Some(null_sentinel) = self.options.null_sentinel().filter(|_| uses_null_sentinel) {
+            block.insert_statement(
+                0,
+                LocalAssignStatement::from_variable(null_sentinel)
+                    .with_value(TableExpression::default()),
+            );
+        }
+        for warning in self.warnings {
+            context.push_warning(warning);
+        }
+        let mut error_causes = self.errors;
+        sort_errors(&mut error_causes);
+        let errors: Vec<_> = error_causes.iter().map(ToString::to_string).collect();
+        for error in error_causes {
+            context.push_error_cause(error);
+        }
+        match errors.len() {
             0 => Ok(()),
-            1 => Err(self.errors.first().unwrap().to_string()),
-            _ => Err(format!("- {}", self.errors.join("\n- "))),
+            1 => Err(errors.first().unwrap().to_string()),
+            _ => Err(format!("- {}", errors.join("\n- "))),
         }
     }
 
-    fn require_call(&self, call: &FunctionCall) -> Option<PathBuf> {
-        if is_require_call(call, self) {
-            match_path_require_call(call)
-        } else {
-            None
+    fn require_call(&mut self, call: &FunctionCall) -> Option<PathBuf> {
+        if !self.require_aliases.is_require_call(call) {
+            return None;
+        }
+
+        let require_path = match_path_require_call(call);
+
+        if require_path.is_none() {
+            let code = format_require_call(call);
+            log::warn!(
+                "unable to bundle `{}` [from `{}`] because its argument is not a string literal",
+                code,
+                self.source.display()
+            );
+            self.add_warning(
+                WarningCode::UnresolvedRequire,
+                format!(
+                    "unable to bundle `{}` because its argument is not a string literal",
+                    code
+                ),
+                call,
+            );
+        }
+
+        require_path
+    }
+
+    fn add_warning(&mut self, code: WarningCode, message: String, call: &FunctionCall) {
+        let mut warning = RuleWarning::new(code, message).with_path(&self.source);
+        if let Some(line) = require_call_line(call) {
+            warning = warning.with_line(line);
+        }
+        self.warnings.push(warning);
+    }
+
+    fn protected_require_call(&self, call: &FunctionCall) -> Option<ProtectedRequireCall> {
+        if self.options.pcall_requires().is_ignore() {
+            return None;
+        }
+        match_protected_require_call(
+            call,
+            |name| self.require_aliases.is_global(name),
+            |name| self.require_aliases.is_require_identifier(name),
+        )
+    }
+
+    fn try_inline_call(&mut self, call: &FunctionCall, is_statement: bool) -> Option<Expression> {
+        if self.lazy_requires.is_unreferenced() {
+            log::trace!(
+                "skip `{}` because its function is never referenced [from `{}`]",
+                format_require_call(call),
+                self.source.display()
+            );
+            return None;
+        }
+
+        let directive = match_require_directive(call);
+
+        if directive == Some(RequireDirective::KeepRequire) {
+            // kept requires are handled like excluded requires, which are left untouched
+            log::trace!(
+                "keep `{}` because of its directive [from `{}`]",
+                format_require_call(call),
+                self.source.display()
+            );
+            return None;
         }
+
+        if let Some(protected_call) = self.protected_require_call(call) {
+            return match self.options.pcall_requires() {
+                PcallRequires::Bundle => self
+                    .try_inline_require(protected_call.require_call(), directive, false)
+                    .map(|value| {
+                        let value = self.module_definitions.wrapped_modules().all_values(value);
+                        protected_call.wrap(value).into()
+                    }),
+                // excluded path requires are left untouched
+                PcallRequires::Exclude | PcallRequires::Ignore => None,
+            };
+        }
+
+        self.try_inline_require(call, directive, is_statement)
     }
 
-    fn try_inline_call(&mut self, call: &FunctionCall) -> Option<Expression> {
+    fn try_inline_require(
+        &mut self,
+        call: &FunctionCall,
+        directive: Option<RequireDirective>,
+        is_statement: bool,
+    ) -> Option<Expression> {
         let literal_require_path = self.require_call(call)?;
+        let ignore_excludes = directive == Some(RequireDirective::Bundle);
 
-        if self.options.is_excluded(&literal_require_path) {
+        if !ignore_excludes && self.options.is_excluded(&literal_require_path) {
             log::info!(
                 "exclude `{}` from bundle [from `{}`]",
                 literal_require_path.display(),
                 self.source.display()
             );
+            self.options.record_require(
+                self.requiring_module(),
+                &literal_require_path,
+                RequireKind::Excluded,
+            );
             return None;
         }
 
@@ -114,20 +254,25 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
         {
             Ok(path) => path,
             Err(err) => {
-                self.errors.push(err.to_string());
+                self.errors.push(err);
                 return None;
             }
         };
 
         // Re-check excludes against the resolved filesystem path to support
         // relative patterns resolved from the project root.
-        if self.options.is_excluded(&require_path) {
+        if !ignore_excludes && self.options.is_excluded(&require_path) {
             log::info!(
                 "exclude `{}` (resolved from `{}`) from bundle [from `{}`]",
                 require_path.display(),
                 literal_require_path.display(),
                 self.source.display()
             );
+            self.options.record_require(
+                self.requiring_module(),
+                &require_path,
+                RequireKind::Excluded,
+            );
             return None;
         }
 
@@ -137,7 +282,9 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
             require_path.display()
         );
 
-        if self.skip_module_paths.contains(&require_path) {
+        let module_key = self.options.module_key(self.resources, &require_path);
+
+        if self.skip_module_paths.contains(&module_key) {
             log::trace!(
                 "skip `{}` because it previously errored",
                 require_path.display()
@@ -145,65 +292,150 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
             return None;
         }
 
-        match self.inline_require(&require_path, call) {
+        match self.inline_require(&require_path, call, is_statement) {
             Ok(expression) => Some(expression),
             Err(error) => {
-                self.errors.push(error.to_string());
-                self.skip_module_paths.insert(require_path);
+                self.errors.push(error);
+                self.skip_module_paths.insert(module_key);
                 None
             }
         }
     }
 
+    // the module being bundled when a require is found
+    fn requiring_module(&self) -> &Path {
+        self.require_stack.last().unwrap_or(&self.source)
+    }
+
     fn inline_require(
         &mut self,
         require_path: &Path,
         call: &FunctionCall,
+        is_statement: bool,
     ) -> DarkluaResult<Expression> {
-        if let Some(expression) = self.module_cache.get(require_path) {
+        // a module reachable through different paths (like a symlink or a different casing)
+        // is only bundled once
+        let module_key = self.options.module_key(self.resources, require_path);
+
+        if let Some(expression) = self.module_cache.get(&module_key) {
+            self.options.record_require(
+                self.requiring_module(),
+                require_path,
+                RequireKind::Bundled,
+            );
             Ok(expression.clone())
         } else {
             if let Some(i) = self
                 .require_stack
                 .iter()
                 .enumerate()
-                .find(|(_, path)| **path == require_path)
+                .find(|(_, path)| self.options.module_key(self.resources, path) == module_key)
                 .map(|(i, _)| i)
             {
                 let require_stack_paths: Vec<_> = self
                     .require_stack
                     .iter()
                     .skip(i)
-                    .map(|path| path.display().to_string())
-                    .chain(iter::once(require_path.display().to_string()))
+                    .cloned()
+                    .chain(iter::once(require_path.to_path_buf()))
                     .collect();
 
-                return Err(DarkluaError::custom(format!(
-                    "cyclic require detected with `{}`",
-                    require_stack_paths.join("` > `")
-                )));
+                self.options.record_require(
+                    self.requiring_module(),
+                    require_path,
+                    RequireKind::Cyclic,
+                );
+
+                return Err(DarkluaError::cyclic_require(require_stack_paths));
             }
 
+            self.options.record_require(
+                self.requiring_module(),
+                require_path,
+                RequireKind::Bundled,
+            );
+
+            self.options
+                .verify_require_depth(&self.require_stack, require_path)?;
+
             self.require_stack.push(require_path.to_path_buf());
             let required_resource = self.require_resource(require_path);
             self.require_stack.pop();
 
+            let required_resource = required_resource?;
+
+            // a data file required as a statement is only read to report its errors: its
+            // value is returned without a module, so that the statement can be removed
+            if is_statement {
+                if let RequiredResource::Expression(value) = required_resource {
+                    log::debug!(
+                        "skip module of `{}` required as a statement",
+                        require_path.display()
+                    );
+                    return Ok(value);
+                }
+            }
+
             let module_value = self.module_definitions.build_module_from_resource(
-                required_resource?,
+                required_resource,
+                self.options
+                    .resource_source_id(self.resources, require_path),
                 require_path,
                 call,
+                self.original_quote(call),
+                &mut self.generated_identifiers.borrow_mut(),
             )?;
 
-            self.module_cache
-                .insert(require_path.to_path_buf(), module_value.clone());
+            self.module_cache.insert(module_key, module_value.clone());
 
             Ok(module_value)
         }
     }
 
+    // validates the null sentinel and records that it must be declared in the bundle
+    fn data_null_sentinel(&mut self) -> DarkluaResult<Option<&'a str>> {
+        let options: &'a BundleOptions = self.options;
+        match options.null_sentinel() {
+            Some(null_sentinel) if !is_valid_identifier(null_sentinel) => {
+                Err(DarkluaError::custom(format!(
+                    "invalid `null_sentinel` value `{}`: expected a Lua identifier",
+                    null_sentinel
+                )))
+            }
+            Some(null_sentinel) => {
+                self.uses_null_sentinel = true;
+                Ok(Some(null_sentinel))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // the quote of the original require is only read when it needs to be preserved
+    fn original_quote(&self, call: &FunctionCall) -> Option<StringQuote> {
+        match self.options.quote_style() {
+            Some(QuoteStyle::Preserve) => {
+                find_arguments_quote(call.get_arguments(), self.original_code)
+            }
+            _ => None,
+        }
+    }
+
     fn require_resource(&mut self, path: impl AsRef<Path>) -> DarkluaResult<RequiredResource> {
         let path = path.as_ref();
         log::trace!("look for resource `{}`", path.display());
+
+        let is_binary = path.extension().is_some_and(|extension| {
+            self.options
+                .is_binary_extension(&extension.to_string_lossy().to_ascii_lowercase())
+        });
+        if is_binary {
+            let content = self.resources.get_bytes(path).map_err(DarkluaError::from)?;
+            return self
+                .options
+                .binary_expression(path, &content)
+                .map(RequiredResource::Expression);
+        }
+
         let content = self.resources.get(path).map_err(DarkluaError::from)?;
 
         match path.extension() {
@@ -211,7 +443,7 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
                 "lua" | "luau" => {
                     let parser_timer = Timer::now();
                     // Intern the file for source ids and parse using shared source_id
-                    let source_id = self.options.registry().borrow_mut().intern(path);
+                    let source_id = self.options.intern_source(self.resources, path);
                     let mut block = self
                         .options
                         .parser()
@@ -225,9 +457,13 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
                         parser_timer.duration_label()
                     );
 
+                    let context = ContextBuilder::new(path, self.resources, &content)
+                        .with_generated_identifiers(self.generated_identifiers.clone())
+                        .build();
+                    self.options.apply_pre_bundle_rules(&mut block, &context)?;
+
                     if self.options.parser().is_preserving_tokens() {
                         log::trace!("replacing token references of {}", path.display());
-                        let context = ContextBuilder::new(path, self.resources, &content).build();
                         // run `replace_referenced_tokens` rule to avoid generating invalid code
                         // when using the token-based generator
                         let replace_tokens = ReplaceReferencedTokens::default();
@@ -243,10 +479,31 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
                         );
                     }
 
+                    // the names generated for the bundle must not collide with the names of
+                    // the inlined modules
+                    self.generated_identifiers
+                        .borrow_mut()
+                        .reserve_block(&mut block);
+
+                    self.options.on_identifier_conflict().resolve(
+                        &mut block,
+                        self.module_definitions.modules_identifier(),
+                        path,
+                        &mut self.generated_identifiers.borrow_mut(),
+                    )?;
+
+                    let warnings = self.options.global_access_warnings(&mut block, path);
+                    self.warnings.extend(warnings);
+
                     let current_source = mem::replace(&mut self.source, path.to_path_buf());
+                    // the locals of the requiring module are not visible from the required one
+                    let previous_require_aliases = mem::take(&mut self.require_aliases);
 
                     let apply_processor_timer = Timer::now();
-                    DefaultVisitor::visit_block(&mut block, self);
+                    ScopePostVisitor::visit_block(&mut block, self);
+                    self.module_definitions
+                        .wrapped_modules()
+                        .expand_values(&mut block);
 
                     log::debug!(
                         "processed `{}` into bundle in {}",
@@ -255,22 +512,35 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
                     );
 
                     self.source = current_source;
+                    self.require_aliases = previous_require_aliases;
 
                     Ok(RequiredResource::Block(block))
                 }
-                "json" | "json5" => {
-                    transcode("json", path, json5::from_str::<serde_json::Value>, &content)
-                }
+                "json" | "json5" => transcode(
+                    "json",
+                    path,
+                    json5::from_str::<serde_json::Value>,
+                    &content,
+                    self.data_null_sentinel()?,
+                ),
                 "yml" | "yaml" => transcode(
                     "yaml",
                     path,
                     serde_yaml::from_str::<serde_yaml::Value>,
                     &content,
+                    self.data_null_sentinel()?,
+                ),
+                "toml" => transcode(
+                    "toml",
+                    path,
+                    toml::from_str::<toml::Value>,
+                    &content,
+                    self.data_null_sentinel()?,
                 ),
-                "toml" => transcode("toml", path, toml::from_str::<toml::Value>, &content),
-                "txt" => Ok(RequiredResource::Expression(
-                    StringExpression::from_value(content).into(),
-                )),
+                extension if self.options.is_raw_text_extension(extension) => self
+                    .options
+                    .raw_text_expression(path, content)
+                    .map(RequiredResource::Expression),
                 _ => Err(DarkluaError::invalid_resource_extension(path)),
             },
             None => unreachable!("extension should be defined"),
@@ -278,17 +548,32 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
     }
 }
 
-impl Deref for RequirePathProcessor<'_, '_, '_, '_> {
-    type Target = IdentifierTracker;
+impl Scope for RequirePathProcessor<'_, '_, '_, '_> {
+    fn push(&mut self) {
+        self.require_aliases.push_scope();
+        self.lazy_requires.push_scope();
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.identifier_tracker
+    fn pop(&mut self) {
+        self.require_aliases.pop_scope();
+        self.lazy_requires.pop_scope();
+    }
+
+    fn insert(&mut self, identifier: &mut String) {
+        self.require_aliases.insert_other(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.require_aliases.insert_other("self");
     }
-}
 
-impl DerefMut for RequirePathProcessor<'_, '_, '_, '_> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.identifier_tracker
+    fn insert_local(&mut self, identifier: &mut String, _value: Option<&mut Expression>) {
+        self.require_aliases.insert_local(identifier);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.require_aliases
+            .insert_other(function.get_identifier().get_name());
     }
 }
 
@@ -297,6 +582,7 @@ fn transcode<'a, T, E>(
     path: &Path,
     deserialize_value: impl Fn(&'a str) -> Result<T, E>,
     content: &'a str,
+    null_sentinel: Option<&str>,
 ) -> Result<RequiredResource, DarkluaError>
 where
     T: Serialize,
@@ -305,9 +591,11 @@ where
     log::trace!("transcode {} data to Lua from `{}`", label, path.display());
     let transcode_duration = Timer::now();
     let value = deserialize_value(content).map_err(E::into)?;
-    let expression = to_expression(&value)
+    let expression = to_data_expression(&value, null_sentinel)
         .map(RequiredResource::Expression)
-        .map_err(DarkluaError::from);
+        .map_err(|error| {
+            DarkluaError::from(error).context(format!("transcoding `{}`", path.display()))
+        });
     log::debug!(
         "transcoded {} data to Lua from `{}` in {}",
         label,
@@ -318,41 +606,76 @@ where
 }
 
 impl NodeProcessor for RequirePathProcessor<'_, '_, '_, '_> {
+    fn process_scope(&mut self, block: &mut Block, extra: Option<&mut Expression>) {
+        if extra.is_some() {
+            self.lazy_requires.prepare_block(block, extra);
+        }
+    }
+
+    fn process_block(&mut self, block: &mut Block) {
+        self.lazy_requires.prepare_block(block, None);
+        self.require_statements.enter_block();
+    }
+
+    fn process_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.lazy_requires.prepare_local_function();
+    }
+
+    fn process_local_assign_statement(&mut self, statement: &mut LocalAssignStatement) {
+        self.require_aliases.prepare_local_assign(statement);
+    }
+
+    fn process_assign_statement(&mut self, statement: &mut AssignStatement) {
+        for variable in statement.iter_variables() {
+            if let Variable::Identifier(identifier) = variable {
+                self.require_aliases.reassign(identifier.get_name());
+            }
+        }
+    }
+
+    fn process_compound_assign_statement(&mut self, statement: &mut CompoundAssignStatement) {
+        if let Variable::Identifier(identifier) = statement.get_variable() {
+            self.require_aliases.reassign(identifier.get_name());
+        }
+    }
+
     fn process_expression(&mut self, expression: &mut Expression) {
         if let Expression::Call(call) = expression {
-            if let Some(replace_with) = self.try_inline_call(call) {
-                *expression = replace_with;
+            if let Some(replace_with) = self.try_inline_call(call, false) {
+                *expression = self
+                    .module_definitions
+                    .wrapped_modules()
+                    .single_value(replace_with);
             }
         }
     }
 
     fn process_prefix_expression(&mut self, prefix: &mut Prefix) {
         if let Prefix::Call(call) = prefix {
-            if let Some(replace_with) = self.try_inline_call(call) {
-                *prefix = replace_with.into();
+            if let Some(replace_with) = self.try_inline_call(call, false) {
+                *prefix = self
+                    .module_definitions
+                    .wrapped_modules()
+                    .single_value(replace_with)
+                    .into();
             }
         }
     }
 
     fn process_statement(&mut self, statement: &mut Statement) {
+        self.require_statements.enter_statement();
         if let Statement::Call(call) = statement {
-            if let Some(replace_with) = self.try_inline_call(call) {
-                if let Expression::Call(replace_with) = replace_with {
-                    *call = *replace_with;
-                } else {
-                    *statement = convert_expression_to_statement(replace_with);
-                }
+            if let Some(replace_with) = self.try_inline_call(call, true) {
+                self.require_statements.replace(statement, replace_with);
             }
         }
     }
 }
 
-fn convert_expression_to_statement(expression: Expression) -> Statement {
-    DoStatement::new(
-        Block::default()
-            .with_statement(LocalAssignStatement::from_variable("_").with_value(expression)),
-    )
-    .into()
+impl NodePostProcessor for RequirePathProcessor<'_, '_, '_, '_> {
+    fn process_after_block(&mut self, block: &mut Block) {
+        self.require_statements.exit_block(block);
+    }
 }
 
 pub(crate) fn process_block(
@@ -379,7 +702,15 @@ pub(crate) fn process_block(
         );
     }
 
+    context
+        .generated_identifiers()
+        .borrow_mut()
+        .reserve_block(block);
+
     let mut processor = RequirePathProcessor::new(context, options, path_require_mode);
-    ScopeVisitor::visit_block(block, &mut processor);
+    processor
+        .warnings
+        .extend(options.global_access_warnings(block, context.current_path()));
+    ScopePostVisitor::visit_block(block, &mut processor);
     processor.apply(block, context)
 }
@@ -1,3 +1,4 @@
+use std::iter;
 use std::path::{Path, PathBuf};
 
 use indexmap::IndexMap;
@@ -7,12 +8,17 @@ use crate::nodes::{
     Arguments, AssignStatement, Block, DoStatement, Expression, FieldExpression, FunctionCall,
     FunctionExpression, FunctionName, FunctionStatement, Identifier, IfStatement, IndexExpression,
     LastStatement, LocalAssignStatement, Prefix, ReturnStatement, Statement, StringExpression,
-    TableEntry, TableExpression, Token, TupleArguments, TupleArgumentsTokens, UnaryExpression,
-    UnaryOperator,
+    StringQuote, TableEntry, TableExpression, Token, TupleArguments, TupleArgumentsTokens,
+    UnaryExpression, UnaryOperator,
+};
+use crate::rules::bundle::{
+    value_module_return, wrap_module_returns, BundleMetadata, InternDataStrings,
+    ModuleNameGenerator, ModuleNaming, ModuleOrder, ModuleVarargs, MultiReturnModules,
+    RenameTypeDeclarationProcessor, TopLevelVariableArguments, WrappedModules,
+};
+use crate::rules::{
+    Context, FlawlessRule, GeneratedIdentifiers, QuoteStyle, RemoveTypes, ShiftTokenLine,
 };
-use crate::process::utils::{generate_identifier, identifier_permutator, CharPermutator};
-use crate::rules::bundle::RenameTypeDeclarationProcessor;
-use crate::rules::{Context, FlawlessRule, ShiftTokenLine};
 use crate::utils::lines;
 use crate::DarkluaError;
 
@@ -22,20 +28,33 @@ use super::RequiredResource;
 pub(crate) struct BuildModuleDefinitions {
     modules_identifier: String,
     module_definitions: IndexMap<String, ModuleDefinition>,
-    module_name_permutator: CharPermutator,
+    module_names: ModuleNameGenerator,
+    module_order: ModuleOrder,
     rename_type_declaration: RenameTypeDeclarationProcessor,
     sourcemap_enabled: bool,
+    multi_return_modules: MultiReturnModules,
+    module_varargs: ModuleVarargs,
+    strip_types: bool,
+    quote_style: Option<QuoteStyle>,
+    wrapped_modules: WrappedModules,
+    intern_data_strings: Option<InternDataStrings>,
+    metadata: Option<BundleMetadata>,
 }
 
 #[derive(Debug)]
 struct ModuleDefinition {
     block: Block,
     path: PathBuf,
+    is_variadic: bool,
 }
 
 impl ModuleDefinition {
-    fn new(block: Block, path: PathBuf) -> Self {
-        Self { block, path }
+    fn new(block: Block, path: PathBuf, is_variadic: bool) -> Self {
+        Self {
+            block,
+            path,
+            is_variadic,
+        }
     }
 }
 
@@ -43,30 +62,88 @@ const BUNDLE_MODULES_VARIABLE_LOAD_FIELD: &str = "load";
 const BUNDLE_MODULES_VARIABLE_CACHE_FIELD: &str = "cache";
 
 impl BuildModuleDefinitions {
-    pub(crate) fn new(modules_identifier: impl Into<String>, sourcemap_enabled: bool) -> Self {
+    pub(crate) fn new(
+        modules_identifier: impl Into<String>,
+        sourcemap_enabled: bool,
+        module_naming: ModuleNaming,
+        multi_return_modules: MultiReturnModules,
+        module_varargs: ModuleVarargs,
+        strip_types: bool,
+        quote_style: Option<QuoteStyle>,
+    ) -> Self {
         let modules_identifier = modules_identifier.into();
         Self {
             modules_identifier: modules_identifier.clone(),
             module_definitions: Default::default(),
-            module_name_permutator: identifier_permutator(),
+            module_names: ModuleNameGenerator::new(module_naming),
+            module_order: ModuleOrder::default(),
             rename_type_declaration: RenameTypeDeclarationProcessor::new(
                 modules_identifier,
                 BUNDLE_MODULES_VARIABLE_LOAD_FIELD,
             ),
             sourcemap_enabled,
+            multi_return_modules,
+            module_varargs,
+            strip_types,
+            quote_style,
+            wrapped_modules: Default::default(),
+            intern_data_strings: None,
+            metadata: None,
         }
     }
 
+    pub(crate) fn with_module_order(mut self, module_order: ModuleOrder) -> Self {
+        self.module_order = module_order;
+        self
+    }
+
+    pub(crate) fn with_intern_data_strings(
+        mut self,
+        intern_data_strings: Option<InternDataStrings>,
+    ) -> Self {
+        self.intern_data_strings = intern_data_strings;
+        self
+    }
+
+    pub(crate) fn with_project_location(mut self, location: &Path) -> Self {
+        self.module_names = self.module_names.with_project_location(location);
+        self
+    }
+
+    pub(crate) fn with_metadata(mut self, metadata: Option<BundleMetadata>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub(crate) fn modules_identifier(&self) -> &str {
+        &self.modules_identifier
+    }
+
+    pub(crate) fn wrapped_modules(&self) -> &WrappedModules {
+        &self.wrapped_modules
+    }
+
     pub(crate) fn build_module_from_resource(
         &mut self,
         required_resource: RequiredResource,
+        source_id: Option<u32>,
         require_path: &Path,
         call: &FunctionCall,
+        original_quote: Option<StringQuote>,
+        generated_identifiers: &mut GeneratedIdentifiers,
     ) -> DarkluaResult<Expression> {
+        let mut value_count = None;
+        let mut is_variadic = false;
         let mut block = match required_resource {
-            RequiredResource::Block(block) => {
+            RequiredResource::Block(mut block) => {
+                if let Some(variable_arguments) = TopLevelVariableArguments::find(&mut block) {
+                    if self.module_varargs.is_error() {
+                        return Err(variable_arguments.into_error(require_path.display()));
+                    }
+                    is_variadic = true;
+                }
                 if let Some(LastStatement::Return(return_statement)) = block.get_last_statement() {
-                    if return_statement.len() != 1 {
+                    if return_statement.len() != 1 && self.multi_return_modules.is_error() {
                         return Err(DarkluaError::custom(format!(
                             "invalid Lua module at `{}`: module must return exactly one value",
                             require_path.display()
@@ -78,10 +155,20 @@ impl BuildModuleDefinitions {
                         require_path.display()
                     )));
                 };
+                if !self.multi_return_modules.is_error() {
+                    value_count = wrap_module_returns(&mut block);
+                }
                 block
             }
-            RequiredResource::Expression(expression) => {
-                Block::default().with_last_statement(ReturnStatement::one(expression))
+            RequiredResource::Expression(mut expression) => {
+                let statements = self
+                    .intern_data_strings
+                    .map(|intern_data_strings| intern_data_strings.intern(&mut expression))
+                    .unwrap_or_default();
+                Block::new(
+                    statements,
+                    Some(value_module_return(expression, source_id).into()),
+                )
             }
         };
 
@@ -89,11 +176,11 @@ impl BuildModuleDefinitions {
             .rename_type_declaration
             .extract_exported_types(&mut block);
 
-        let module_name = self.generate_module_name();
+        let module_name = self.generate_module_name(require_path, generated_identifiers);
 
         self.module_definitions.insert(
             module_name.clone(),
-            ModuleDefinition::new(block, require_path.to_path_buf()),
+            ModuleDefinition::new(block, require_path.to_path_buf(), is_variadic),
         );
         self.rename_type_declaration
             .insert_module_types(module_name.clone(), exported_types);
@@ -145,30 +232,46 @@ impl BuildModuleDefinitions {
             Arguments::Table(_) => TupleArguments::default(),
         };
 
-        let new_require_call = FunctionCall::from_prefix(FieldExpression::new(
+        let mut module_name_argument = StringExpression::from_value(module_name);
+        if let Some(quote) = self
+            .quote_style
+            .and_then(|quote_style| quote_style.resolve(original_quote))
+        {
+            module_name_argument.set_quote(quote);
+        }
+
+        let new_require_call: Expression = FunctionCall::from_prefix(FieldExpression::new(
             Identifier::from(&self.modules_identifier),
             load_field,
         ))
-        .with_arguments(arguments.with_argument(StringExpression::from_value(module_name)))
+        .with_arguments(arguments.with_argument(module_name_argument))
         .into();
 
+        if let Some(value_count) = value_count {
+            self.wrapped_modules
+                .insert(new_require_call.clone(), value_count);
+        }
+
         Ok(new_require_call)
     }
 
-    fn generate_module_name(&mut self) -> String {
-        loop {
-            let name = generate_identifier(&mut self.module_name_permutator);
-
-            if name != BUNDLE_MODULES_VARIABLE_CACHE_FIELD
-                && name != BUNDLE_MODULES_VARIABLE_LOAD_FIELD
-            {
-                break name;
-            }
-        }
+    fn generate_module_name(
+        &mut self,
+        require_path: &Path,
+        generated_identifiers: &mut GeneratedIdentifiers,
+    ) -> String {
+        self.module_names
+            .generate(require_path, generated_identifiers)
     }
 
     pub(crate) fn apply(mut self, block: &mut Block, context: &Context) {
         if self.module_definitions.is_empty() {
+            if let Some(metadata) = &self.metadata {
+                block.insert_statement(0, metadata.build_statement(context, iter::empty()));
+            }
+            if self.strip_types {
+                RemoveTypes::default().flawless_process(block, context);
+            }
             return;
         }
 
@@ -178,9 +281,18 @@ impl BuildModuleDefinitions {
 
         self.rename_type_declaration.rename_types(block);
 
+        // the lines are shifted after sorting, so that they follow the order of the bundle
+        self.module_order
+            .sort(&mut self.module_definitions, |module| &module.path);
+
         let modules_identifier = Identifier::from(&self.modules_identifier);
 
-        let mut shift_lines = self.rename_type_declaration.get_type_lines();
+        // stripped type declarations are not inserted at the top of the bundle
+        let mut shift_lines = if self.strip_types {
+            0
+        } else {
+            self.rename_type_declaration.get_type_lines()
+        };
         let sourcemap_enabled = self.sourcemap_enabled;
 
         for module in self.module_definitions.values_mut() {
@@ -197,13 +309,23 @@ impl BuildModuleDefinitions {
             ShiftTokenLine::new(shift_lines).flawless_process(block, context);
         }
 
+        // the metadata statement has no tokens, so it does not shift the lines of the
+        // code that follows it
+        if let Some(metadata) = &self.metadata {
+            let module_paths = self
+                .module_definitions
+                .values()
+                .map(|module| module.path.as_path());
+            block.insert_statement(0, metadata.build_statement(context, module_paths));
+        }
+
         let statements = self
             .module_definitions
             .drain(..)
             .map(|(module_name, module)| {
                 let function_name =
                     FunctionName::from_name(modules_identifier.clone()).with_field(&module_name);
-                FunctionStatement::new(function_name, module.block, Vec::new(), false)
+                FunctionStatement::new(function_name, module.block, Vec::new(), module.is_variadic)
             })
             .map(Statement::from)
             .collect();
@@ -219,14 +341,12 @@ impl BuildModuleDefinitions {
             LocalAssignStatement::from_variable(self.modules_identifier),
         );
 
-        for statement in self
-            .rename_type_declaration
-            .extract_type_declarations()
-            .into_iter()
-            .rev()
-        {
-            block.insert_statement(0, statement);
+        if self.strip_types {
+            RemoveTypes::default().flawless_process(block, context);
+            return;
         }
+
+        self.rename_type_declaration.insert_type_declarations(block);
     }
 
     fn build_modules_table(&self) -> TableExpression {
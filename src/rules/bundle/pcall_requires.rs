@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Defines how the bundler handles requires made through `pcall(require, ...)` or
+/// `xpcall(require, handler, ...)`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PcallRequires {
+    /// Inlines the required module and protects the module loading, as in
+    /// `pcall(function() return <module> end)`.
+    Bundle,
+    /// Treats the require like an excluded require.
+    Exclude,
+    /// Leaves the protected call untouched.
+    #[default]
+    Ignore,
+}
+
+impl PcallRequires {
+    pub(crate) fn is_ignore(&self) -> bool {
+        matches!(self, Self::Ignore)
+    }
+}
@@ -2,10 +2,11 @@ use std::collections::HashMap;
 use std::mem;
 
 use crate::nodes::*;
+use crate::process::processors::FindUsage;
 use crate::process::utils::{identifier_permutator, CharPermutator};
 use crate::process::{
     DefaultVisitor, NodePostProcessor, NodePostVisitor, NodeProcessor, NodeVisitor, Scope,
-    ScopePostVisitor,
+    ScopePostVisitor, ScopeVisitor,
 };
 use crate::rules::ShiftTokenLineProcessor;
 use crate::utils::{lines, ScopedHashMap};
@@ -74,6 +75,18 @@ impl RenameTypeDeclarationProcessor {
     }
 
     fn get_module_name(&self, value: &Expression) -> Option<Vec<u8>> {
+        // the value of a required module can be wrapped in parentheses or cast to another
+        // type, like `local Signal = (require('./signal') :: any)`
+        match value {
+            Expression::Parenthese(parenthese) => {
+                return self.get_module_name(parenthese.inner_expression());
+            }
+            Expression::TypeCast(type_cast) => {
+                return self.get_module_name(type_cast.get_expression());
+            }
+            _ => {}
+        }
+
         if let Expression::Call(value) = value {
             if let Prefix::Field(field) = value.get_prefix() {
                 if field.get_field().get_name() == self.module_load_field {
@@ -120,8 +133,23 @@ impl RenameTypeDeclarationProcessor {
         self.type_lines
     }
 
-    pub(crate) fn extract_type_declarations(&mut self) -> Vec<Statement> {
-        mem::take(&mut self.type_declarations)
+    /// Inserts the hoisted type declarations at the top of the bundle, which must start
+    /// with the declaration of the modules variable. Types defined with
+    /// `typeof(require(...))` reference the modules variable, so they are inserted after
+    /// its declaration.
+    pub(crate) fn insert_type_declarations(&mut self, block: &mut Block) {
+        let mut type_declarations = mem::take(&mut self.type_declarations);
+
+        let references_modules = type_declarations.iter_mut().any(|statement| {
+            let mut find_usage = FindUsage::new(&self.modules_identifier);
+            ScopeVisitor::visit_statement(statement, &mut find_usage);
+            find_usage.has_found_usage()
+        });
+        let index = if references_modules { 1 } else { 0 };
+
+        for statement in type_declarations.into_iter().rev() {
+            block.insert_statement(index, statement);
+        }
     }
 }
 
@@ -176,7 +204,16 @@ impl NodeProcessor for RenameTypeDeclarationProcessor {
                 .get(type_field.get_namespace().get_name())
                 .and_then(|module_name| self.all_types.get(module_name))
                 .and_then(|module_types| {
-                    module_types.get(type_field.get_type_name().get_type_name().get_name())
+                    let type_name = type_field.get_type_name().get_type_name().get_name();
+                    let exported_type = module_types.get(type_name);
+                    if exported_type.is_none() {
+                        log::warn!(
+                            "unable to find exported type `{}` from `{}` in the bundled module",
+                            type_name,
+                            type_field.get_namespace().get_name(),
+                        );
+                    }
+                    exported_type
                 })
                 .map(TypeName::new)
                 .map(|type_name| {
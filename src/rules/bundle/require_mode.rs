@@ -1,8 +1,12 @@
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
-use crate::rules::{require::PathRequireMode, require::RobloxRequireMode, RuleProcessResult};
+use crate::rules::require::{
+    AutoRequireMode, DetectedRequireMode, PathRequireMode, RobloxRequireMode,
+};
+use crate::rules::RuleProcessResult;
 use crate::{nodes::Block, rules::Context};
 
 use super::{path_require_mode, roblox_require_mode, BundleOptions};
@@ -12,6 +16,7 @@ use super::{path_require_mode, roblox_require_mode, BundleOptions};
 pub enum BundleRequireMode {
     Path(PathRequireMode),
     Roblox(RobloxRequireMode),
+    Auto(AutoRequireMode<RobloxRequireMode>),
 }
 
 impl From<PathRequireMode> for BundleRequireMode {
@@ -26,6 +31,12 @@ impl From<RobloxRequireMode> for BundleRequireMode {
     }
 }
 
+impl From<AutoRequireMode<RobloxRequireMode>> for BundleRequireMode {
+    fn from(mode: AutoRequireMode<RobloxRequireMode>) -> Self {
+        Self::Auto(mode)
+    }
+}
+
 impl FromStr for BundleRequireMode {
     type Err = String;
 
@@ -33,6 +44,7 @@ impl FromStr for BundleRequireMode {
         Ok(match s {
             "path" => Self::Path(Default::default()),
             "roblox" => Self::Roblox(Default::default()),
+            "auto" => Self::Auto(Default::default()),
             _ => return Err(format!("invalid require mode `{}`", s)),
         })
     }
@@ -45,6 +57,11 @@ impl Default for BundleRequireMode {
 }
 
 impl BundleRequireMode {
+    /// Creates a Roblox require mode that locates modules with the given Rojo sourcemap.
+    pub fn roblox(rojo_sourcemap: impl Into<PathBuf>) -> Self {
+        Self::Roblox(RobloxRequireMode::new().with_rojo_sourcemap(rojo_sourcemap))
+    }
+
     pub(crate) fn process_block(
         &self,
         block: &mut Block,
@@ -53,19 +70,46 @@ impl BundleRequireMode {
     ) -> RuleProcessResult {
         match self {
             Self::Path(path_require_mode) => {
-                let mut require_mode = path_require_mode.clone();
-                require_mode
-                    .initialize(context)
-                    .map_err(|err| err.to_string())?;
-                path_require_mode::process_block(block, context, options, &require_mode)
+                process_path_block(block, context, options, path_require_mode)
             }
             Self::Roblox(roblox_require_mode) => {
-                let mut require_mode = roblox_require_mode.clone();
-                require_mode
-                    .initialize(context)
-                    .map_err(|err| err.to_string())?;
-                roblox_require_mode::process_block(block, context, options, &require_mode)
+                process_roblox_block(block, context, options, roblox_require_mode)
             }
+            Self::Auto(auto_require_mode) => match auto_require_mode.detect(block, context) {
+                DetectedRequireMode::Path => {
+                    process_path_block(block, context, options, auto_require_mode.path_mode())
+                }
+                DetectedRequireMode::Roblox => {
+                    process_roblox_block(block, context, options, auto_require_mode.roblox_mode())
+                }
+            },
         }
     }
 }
+
+fn process_path_block(
+    block: &mut Block,
+    context: &Context,
+    options: &BundleOptions,
+    path_require_mode: &PathRequireMode,
+) -> RuleProcessResult {
+    let mut require_mode = path_require_mode.clone();
+    require_mode
+        .initialize(context)
+        .map_err(|err| err.to_string())?;
+    path_require_mode::process_block(block, context, options, &require_mode)
+}
+
+fn process_roblox_block(
+    block: &mut Block,
+    context: &Context,
+    options: &BundleOptions,
+    roblox_require_mode: &RobloxRequireMode,
+) -> RuleProcessResult {
+    let mut require_mode = roblox_require_mode.clone();
+    require_mode.set_case_sensitive_paths(options.case_sensitive_paths());
+    require_mode
+        .initialize(context)
+        .map_err(|err| err.to_string())?;
+    roblox_require_mode::process_block(block, context, options, &require_mode)
+}
@@ -0,0 +1,119 @@
+use crate::nodes::{Block, DoStatement, Expression, LocalAssignStatement, Statement};
+use crate::process::Evaluator;
+
+#[derive(Debug, Default)]
+struct BlockStatements {
+    visited_statements: usize,
+    removed: Vec<usize>,
+}
+
+/// Replaces require calls used as statements with the expression they are bundled into.
+/// Statements are removed once their block is visited, since a statement cannot remove
+/// itself while the block is being visited.
+#[derive(Debug, Default)]
+pub(crate) struct RequireStatements {
+    blocks: Vec<BlockStatements>,
+    evaluator: Evaluator,
+}
+
+impl RequireStatements {
+    pub(crate) fn enter_block(&mut self) {
+        self.blocks.push(BlockStatements::default());
+    }
+
+    /// Must be called before each statement of the current block is visited.
+    pub(crate) fn enter_statement(&mut self) {
+        if let Some(block) = self.blocks.last_mut() {
+            block.visited_statements += 1;
+        }
+    }
+
+    /// Replaces a require statement with the bundled expression. A call expression stays a
+    /// call statement, an expression with side effects is assigned to a local inside a `do`
+    /// block and any other expression is removed.
+    pub(crate) fn replace(&mut self, statement: &mut Statement, replace_with: Expression) {
+        match replace_with {
+            Expression::Call(call) => {
+                *statement = Statement::Call(*call);
+            }
+            replace_with if self.evaluator.has_side_effects(&replace_with) => {
+                *statement = DoStatement::new(Block::default().with_statement(
+                    LocalAssignStatement::from_variable("_").with_value(replace_with),
+                ))
+                .into();
+            }
+            _ => {
+                log::debug!("remove require statement bundled into a value without side effects");
+                *statement = DoStatement::default().into();
+                if let Some(block) = self.blocks.last_mut() {
+                    block.removed.push(block.visited_statements - 1);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn exit_block(&mut self, block: &mut Block) {
+        if let Some(statements) = self.blocks.pop() {
+            for index in statements.removed.into_iter().rev() {
+                block.remove_statement(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::nodes::{FieldExpression, FunctionCall, Prefix, StringExpression};
+
+    fn replace_first_statement(replace_with: Expression) -> Block {
+        let mut block = Block::default()
+            .with_statement(FunctionCall::from_name("require"))
+            .with_statement(FunctionCall::from_name("print"));
+        let mut require_statements = RequireStatements::default();
+
+        require_statements.enter_block();
+        for (index, statement) in block.iter_mut_statements().enumerate() {
+            require_statements.enter_statement();
+            if index == 0 {
+                require_statements.replace(statement, replace_with.clone());
+            }
+        }
+        require_statements.exit_block(&mut block);
+
+        block
+    }
+
+    #[test]
+    fn call_stays_a_statement() {
+        pretty_assertions::assert_eq!(
+            replace_first_statement(FunctionCall::from_name("load").into()),
+            Block::default()
+                .with_statement(FunctionCall::from_name("load"))
+                .with_statement(FunctionCall::from_name("print"))
+        );
+    }
+
+    #[test]
+    fn value_without_side_effects_is_removed() {
+        pretty_assertions::assert_eq!(
+            replace_first_statement(StringExpression::from_value("content").into()),
+            Block::default().with_statement(FunctionCall::from_name("print"))
+        );
+    }
+
+    #[test]
+    fn value_with_side_effects_is_assigned_in_a_do_block() {
+        let value: Expression = FieldExpression::new(Prefix::from_name("object"), "field").into();
+
+        pretty_assertions::assert_eq!(
+            replace_first_statement(value.clone()),
+            Block::default()
+                .with_statement(DoStatement::new(Block::default().with_statement(
+                    LocalAssignStatement::from_variable("_").with_value(value)
+                )))
+                .with_statement(FunctionCall::from_name("print"))
+        );
+    }
+}
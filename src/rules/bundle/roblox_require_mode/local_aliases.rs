@@ -0,0 +1,65 @@
+use crate::nodes::{Expression, LocalAssignStatement};
+use crate::rules::require::{iter_local_assign_values, ScopedLocals};
+
+/// The value bound to a local variable that may alias an instance.
+#[derive(Debug, Clone)]
+pub(crate) enum AliasValue {
+    /// The local is initialized with an expression that can be resolved later.
+    Expression(Box<Expression>),
+    /// The local is a parameter, a loop variable, a function or it has no initial value
+    /// that could refer to an instance.
+    Unknown,
+    /// The local was assigned a new value after its declaration.
+    Reassigned,
+}
+
+/// Tracks the local variables visible at each point of a block, so that identifiers used in
+/// require arguments resolve to the binding that is actually in scope.
+#[derive(Debug, Default)]
+pub(crate) struct LocalAliases {
+    locals: ScopedLocals<AliasValue>,
+}
+
+impl LocalAliases {
+    pub(crate) fn push_scope(&mut self) {
+        self.locals.push_scope();
+    }
+
+    pub(crate) fn pop_scope(&mut self) {
+        self.locals.pop_scope();
+    }
+
+    // the values are captured before the local assignments are visited, since requires
+    // found in these values are replaced with the bundled module by the time the locals
+    // are inserted
+    pub(crate) fn prepare_local_assign(&mut self, statement: &LocalAssignStatement) {
+        let values = iter_local_assign_values(statement).map(|value| match value {
+            Some(Expression::Function(_) | Expression::Table(_)) | None => AliasValue::Unknown,
+            Some(value) => AliasValue::Expression(Box::new(value.clone())),
+        });
+        self.locals.prepare_local_assign(values);
+    }
+
+    pub(crate) fn insert_local(&mut self, name: &str) {
+        self.locals.insert_local(name, AliasValue::Unknown);
+    }
+
+    pub(crate) fn insert_unknown(&mut self, name: &str) {
+        self.locals.insert(name, AliasValue::Unknown);
+    }
+
+    pub(crate) fn reassign(&mut self, name: &str) {
+        self.locals.reassign(name, AliasValue::Reassigned);
+    }
+
+    /// Returns the number of locals currently in scope.
+    pub(crate) fn visible(&self) -> usize {
+        self.locals.visible()
+    }
+
+    /// Finds the innermost local named `name` among the first `visible` locals. The returned
+    /// position is the number of locals visible from the value of that local.
+    pub(crate) fn find(&self, name: &str, visible: usize) -> Option<(usize, &AliasValue)> {
+        self.locals.find(name, visible)
+    }
+}
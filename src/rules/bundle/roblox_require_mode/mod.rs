@@ -1,34 +1,51 @@
+mod local_aliases;
 mod module_definitions;
 
+use local_aliases::{AliasValue, LocalAliases};
 use module_definitions::BuildModuleDefinitions;
 
-use std::collections::{HashMap, HashSet};
-use std::ops::{Deref, DerefMut};
+use std::cell::RefCell;
 use std::path::{Path, PathBuf};
 use std::{iter, mem};
 
+use indexmap::{IndexMap, IndexSet};
+
 use crate::frontend::DarkluaResult;
 use crate::nodes::{
-    Arguments, Block, DoStatement, Expression, FieldExpression, FunctionCall, LocalAssignStatement,
-    Prefix, Statement, StringExpression,
+    Arguments, AssignStatement, Block, CompoundAssignStatement, Expression, FieldExpression,
+    FunctionCall, Identifier, LocalAssignStatement, LocalFunctionStatement, Prefix, Statement,
+    StringExpression, StringQuote, TableExpression, Variable,
 };
+use crate::process::utils::is_valid_identifier;
 use crate::process::{
-    to_expression, DefaultVisitor, IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor,
+    to_data_expression, IdentifierTracker, NodePostProcessor, NodePostVisitor, NodeProcessor,
+    Scope, ScopePostVisitor,
+};
+use crate::rules::require::{
+    find_arguments_quote, format_expression, format_require_argument, format_require_call,
+    match_protected_require_call, match_require_argument, match_require_directive,
+    require_call_line, ProtectedRequireCall, RequireAliases, RequireArgument, RequireDirective,
 };
-use crate::rules::require::is_require_call;
 use crate::rules::{
-    Context, ContextBuilder, FlawlessRule, ReplaceReferencedTokens, RuleProcessResult,
+    Context, ContextBuilder, FlawlessRule, QuoteStyle, ReplaceReferencedTokens, RuleProcessResult,
+    RuleWarning, SharedGeneratedIdentifiers, WarningCode,
 };
 use crate::utils::Timer;
 use crate::{DarkluaError, Resources};
 
-use super::BundleOptions;
+use super::{
+    sort_errors, BundleOptions, LazyRequires, PcallRequires, RequireKind, RequireStatements,
+};
 use crate::rules::convert_require::{
-    datamodel_identifier, get_parent_instance, InstancePath, InstancePathComponent,
-    InstancePathRoot,
+    datamodel_identifier, get_parent_instance, read_first_string_argument, read_string_index,
+    InstancePath, InstancePathComponent, InstancePathResolutionError, InstancePathRoot,
+    RobloxIndexStyle,
 };
 use crate::rules::require::RobloxRequireMode;
 
+const HOISTED_REQUIRE_PREFIX: &str = "__DARKLUA_EXT_";
+const MODULE_SCRIPT_CLASS_NAME: &str = "ModuleScript";
+
 pub(crate) enum RequiredResource {
     Block(Block),
     Expression(Expression),
@@ -40,14 +57,26 @@ struct RequireRobloxProcessor<'a, 'b, 'resources> {
     identifier_tracker: IdentifierTracker,
     roblox_require_mode: &'b RobloxRequireMode,
     module_definitions: BuildModuleDefinitions,
+    generated_identifiers: SharedGeneratedIdentifiers,
     source: PathBuf,
-    module_cache: HashMap<PathBuf, Expression>,
+    original_code: &'b str,
+    // the module content is only stored once in the module definitions, and each cached
+    // expression is the small call that loads it
+    module_cache: IndexMap<PathBuf, Expression>,
     require_stack: Vec<PathBuf>,
-    skip_module_paths: HashSet<PathBuf>,
+    skip_module_paths: IndexSet<PathBuf>,
     resources: &'resources Resources,
-    errors: Vec<String>,
-    current_block_clone: Block,
-    root_block_clone: Block,
+    errors: RefCell<Vec<DarkluaError>>,
+    warnings: RefCell<Vec<RuleWarning>>,
+    // files listed by the sourcemap next to the required scripts, like `.meta.json` files
+    file_dependencies: RefCell<IndexSet<PathBuf>>,
+    local_aliases: LocalAliases,
+    require_aliases: RequireAliases,
+    lazy_requires: LazyRequires,
+    require_statements: RequireStatements,
+    // excluded requires declared once at the top of the bundle, indexed by their code
+    hoisted_requires: IndexMap<String, (String, FunctionCall)>,
+    uses_null_sentinel: bool,
 }
 
 impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
@@ -55,270 +84,333 @@ impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
         context: &'context Context<'b, 'resources, '_>,
         options: &'a BundleOptions,
         roblox_require_mode: &'b RobloxRequireMode,
-        current_block_clone: Block,
     ) -> Self
     where
         'context: 'b,
         'context: 'resources,
     {
+        let modules_identifier = context
+            .generated_identifiers()
+            .borrow_mut()
+            .unique_name(options.modules_identifier());
         Self {
             options,
             identifier_tracker: IdentifierTracker::new(),
             roblox_require_mode,
             module_definitions: BuildModuleDefinitions::new(
-                options.modules_identifier(),
+                modules_identifier,
                 options.is_sourcemap_enabled(),
-            ),
+                options.module_naming(),
+                options.multi_return_modules(),
+                options.module_varargs(),
+                options.is_strip_types(),
+                options.quote_style(),
+            )
+            .with_module_order(options.module_order())
+            .with_intern_data_strings(options.intern_data_strings())
+            .with_metadata(options.metadata())
+            .with_project_location(context.project_location()),
+            generated_identifiers: context.generated_identifiers().clone(),
             source: context.current_path().to_path_buf(),
+            original_code: context.original_code(),
             module_cache: Default::default(),
             require_stack: Default::default(),
             skip_module_paths: Default::default(),
             resources: context.resources(),
-            errors: Vec::new(),
-            current_block_clone: current_block_clone.clone(),
-            root_block_clone: current_block_clone,
+            errors: Default::default(),
+            warnings: Default::default(),
+            file_dependencies: Default::default(),
+            local_aliases: Default::default(),
+            require_aliases: Default::default(),
+            lazy_requires: LazyRequires::new(options.is_tree_shake_lazy_requires()),
+            require_statements: Default::default(),
+            hoisted_requires: Default::default(),
+            uses_null_sentinel: false,
         }
     }
 
     fn apply(self, block: &mut Block, context: &Context) -> RuleProcessResult {
-        self.module_definitions.apply(block, context);
-        match self.errors.len() {
+        self.module_definitions
+            .wrapped_modules()
+            .expand_values(block);
+        let null_sentinel = self
+            .options
+            .null_sentinel()
+            .filter(|_| self.uses_null_sentinel)
+            .map(|null_sentinel| {
+                LocalAssignStatement::from_variable(null_sentinel)
+                    .with_value(TableExpression::default())
+                    .into()
+            });
+        let hoisted_statements = null_sentinel
+            .into_iter()
+            .chain(
+                self.hoisted_requires
+                    .into_values()
+                    .map(|(identifier, call)| {
+                        LocalAssignStatement::from_variable(identifier)
+                            .with_value(call)
+                            .into()
+                    }),
+            )
+            .collect();
+        self.module_definitions
+            .apply(block, context, hoisted_statements);
+        for file_path in self.file_dependencies.into_inner() {
+            context.add_file_dependency(file_path);
+        }
+        for warning in self.warnings.into_inner() {
+            context.push_warning(warning);
+        }
+        let mut error_causes = self.errors.into_inner();
+        sort_errors(&mut error_causes);
+        let errors: Vec<_> = error_causes.iter().map(ToString::to_string).collect();
+        for error in error_causes {
+            context.push_error_cause(error);
+        }
+        match errors.len() {
             0 => Ok(()),
-            1 => Err(self.errors.first().unwrap().to_string()),
-            _ => Err(format!("- {}", self.errors.join("\n- "))),
+            1 => Err(errors.first().unwrap().to_string()),
+            _ => Err(format!("- {}", errors.join("\n- "))),
         }
     }
 
-    fn parse_expression_to_instance_path(&self, expression: &Expression) -> Option<InstancePath> {
+    fn parse_expression_to_instance_path(
+        &self,
+        expression: &Expression,
+        visible: usize,
+    ) -> Result<InstancePath, String> {
         match expression {
-            Expression::Identifier(id) => match id.get_name().as_str() {
-                "script" => {
-                    Some(InstancePath::from_script())
-                }
-                "game" => {
-                    Some(InstancePath::from_root())
-                }
-                other => {
-                    let resolved = self.resolve_identifier_to_instance_path(other);
-                    if resolved.is_none() {
-                        log::warn!(
-                            "resolve_identifier_to_instance_path failed for Identifier({})",
-                            other
-                        );
-                    }
-                    resolved
-                }
-            },
+            Expression::Identifier(id) => {
+                self.parse_identifier_to_instance_path(id.get_name(), visible)
+            }
             Expression::Field(field) => {
-                let mut base = self.parse_prefix_to_instance_path(field.get_prefix())?;
+                let mut base = self.parse_prefix_to_instance_path(field.get_prefix(), visible)?;
                 let name = field.get_field().get_name();
                 if name == "Parent" {
                     base.parent();
                 } else {
                     base.child(name);
                 }
-                Some(base)
+                Ok(base)
             }
             Expression::Index(index) => {
-                let mut base = self.parse_prefix_to_instance_path(index.get_prefix())?;
-                let child_name = match index.get_index() {
-                    Expression::String(s) => s.get_string_value()?.to_string(),
-                    _ => {
-                        log::warn!(
-                            "parse_expression_to_instance_path: Index with non-string index: cannot handle this expression"
-                        );
-                        log::warn!("index expression kind not supported for instance path");
-                        return None;
-                    }
-                };
+                let child_name = read_string_index(index.get_index())?;
+                let mut base = self.parse_prefix_to_instance_path(index.get_prefix(), visible)?;
                 base.child(child_name);
-                Some(base)
-            }
-            Expression::Call(call) => {
-                self.parse_call_to_instance_path(call)
+                Ok(base)
             }
+            Expression::Call(call) => self.parse_call_to_instance_path(call, visible),
             Expression::Parenthese(paren) => {
-                self.parse_expression_to_instance_path(paren.inner_expression())
+                self.parse_expression_to_instance_path(paren.inner_expression(), visible)
             }
             _ => {
                 log::warn!(
                     "parse_expression_to_instance_path: unsupported expression kind for instance path resolution"
                 );
-                None
+                Err(format!(
+                    "unsupported expression `{}`",
+                    format_expression(expression)
+                ))
             }
         }
     }
 
-    fn parse_prefix_to_instance_path(&self, prefix: &Prefix) -> Option<InstancePath> {
+    fn parse_prefix_to_instance_path(
+        &self,
+        prefix: &Prefix,
+        visible: usize,
+    ) -> Result<InstancePath, String> {
         match prefix {
-            Prefix::Identifier(id) => match id.get_name().as_str() {
-                "script" => {
-                    Some(InstancePath::from_script())
-                }
-                "game" => {
-                    Some(InstancePath::from_root())
-                }
-                other => {
-                    let resolved = self.resolve_identifier_to_instance_path(other);
-                    if resolved.is_none() {
-                        log::warn!(
-                            "resolve_identifier_to_instance_path failed for Identifier({})",
-                            other
-                        );
-                    }
-                    resolved
-                }
-            },
+            Prefix::Identifier(id) => {
+                self.parse_identifier_to_instance_path(id.get_name(), visible)
+            }
             Prefix::Field(field) => {
-                let mut base = self.parse_prefix_to_instance_path(field.get_prefix())?;
+                let mut base = self.parse_prefix_to_instance_path(field.get_prefix(), visible)?;
                 let name = field.get_field().get_name();
                 if name == "Parent" {
                     base.parent();
                 } else {
                     base.child(name);
                 }
-                Some(base)
+                Ok(base)
             }
             Prefix::Index(index) => {
-                let mut base = self.parse_prefix_to_instance_path(index.get_prefix())?;
-                let child_name = match index.get_index() {
-                    Expression::String(s) => s.get_string_value()?.to_string(),
-                    _ => {
-                        log::warn!(
-                            "parse_prefix_to_instance_path: Index with non-string index: cannot handle this prefix"
-                        );
-                        return None;
-                    }
-                };
+                let child_name = read_string_index(index.get_index())?;
+                let mut base = self.parse_prefix_to_instance_path(index.get_prefix(), visible)?;
                 base.child(child_name);
-                Some(base)
-            }
-            Prefix::Call(call) => {
-                self.parse_call_to_instance_path(call)
+                Ok(base)
             }
+            Prefix::Call(call) => self.parse_call_to_instance_path(call, visible),
             Prefix::Parenthese(paren) => {
-                self.parse_expression_to_instance_path(paren.inner_expression())
+                self.parse_expression_to_instance_path(paren.inner_expression(), visible)
             }
         }
     }
 
-    fn parse_call_to_instance_path(&self, call: &FunctionCall) -> Option<InstancePath> {
-        let method = call.get_method().map(|m| m.get_name().as_str());
-        let mut base = self.parse_prefix_to_instance_path(call.get_prefix())?;
+    fn parse_identifier_to_instance_path(
+        &self,
+        name: &str,
+        visible: usize,
+    ) -> Result<InstancePath, String> {
+        match name {
+            "script" => Ok(InstancePath::from_script()),
+            "game" => Ok(InstancePath::from_root()),
+            other => self
+                .resolve_identifier_to_instance_path(other, visible)
+                .inspect_err(|_| {
+                    log::warn!(
+                        "resolve_identifier_to_instance_path failed for Identifier({})",
+                        other
+                    );
+                }),
+        }
+    }
+
+    fn parse_call_to_instance_path(
+        &self,
+        call: &FunctionCall,
+        visible: usize,
+    ) -> Result<InstancePath, String> {
+        let method = match call.get_method() {
+            Some(method) => method.get_name().as_str(),
+            None => {
+                return Err(format!(
+                    "unsupported function call `{}`",
+                    format_require_call(call)
+                ))
+            }
+        };
+        let mut base = self.parse_prefix_to_instance_path(call.get_prefix(), visible)?;
         match method {
-            Some("GetService") => {
-                let child = self.read_first_string_argument(call)?;
+            "GetService" => {
+                let child = read_first_string_argument(call, method)?;
                 base.child(child);
-                Some(base)
+                Ok(base)
             }
-            Some("WaitForChild") | Some("FindFirstChild") => {
-                let child = self.read_first_string_argument(call)?;
+            "WaitForChild" => {
+                let child = read_first_string_argument(call, method)?;
+                if !self.check_wait_for_child_timeout(call) {
+                    return Err(
+                        "`WaitForChild` with a timeout is not bundled in strict mode".to_owned(),
+                    );
+                }
                 base.child(child);
-                Some(base)
+                Ok(base)
             }
-            Some("FindFirstAncestor") => {
-                let ancestor = self.read_first_string_argument(call)?;
-                base.ancestor(ancestor);
-                Some(base)
+            "FindFirstChild" => {
+                let child = read_first_string_argument(call, method)?;
+                base.find_first_child(child);
+                Ok(base)
             }
-            _ => {
-                None
+            "FindFirstAncestor" => {
+                let ancestor = read_first_string_argument(call, method)?;
+                base.ancestor(ancestor);
+                Ok(base)
             }
+            _ => Err(format!("unsupported method `{}`", method)),
         }
     }
 
-    fn read_first_string_argument(&self, call: &FunctionCall) -> Option<String> {
-        match call.get_arguments() {
-            Arguments::String(s) => {
-                let value = s.get_string_value().map(|s| s.to_string());
-                if value.is_none() {
-                    log::warn!(
-                        "read_first_string_argument: got String argument but could not get value"
-                    );
-                }
-                value
-            }
-            Arguments::Tuple(tuple) if tuple.len() >= 1 => {
-                let first = tuple.iter_values().next().unwrap();
-                match first {
-                    Expression::String(s) => {
-                        let value = s.get_string_value().map(|s| s.to_string());
-                        if value.is_none() {
-                            log::warn!(
-                                "read_first_string_argument: first tuple arg is String but could not get value"
-                            );
-                        }
-                        value
-                    }
-                    _ => {
-                        log::warn!(
-                            "read_first_string_argument: first tuple arg is not a String expression"
-                        );
-                        None
-                    }
-                }
-            }
-            _ => {
-                log::warn!("read_first_string_argument: unsupported arguments kind");
-                None
-            }
+    /// Returns false when the require using this `WaitForChild` call must not be inlined.
+    fn check_wait_for_child_timeout(&self, call: &FunctionCall) -> bool {
+        let has_timeout =
+            matches!(call.get_arguments(), Arguments::Tuple(tuple) if tuple.len() > 1);
+        if !has_timeout {
+            return true;
         }
+
+        let code = format_require_call(call);
+        let line = call
+            .get_method()
+            .and_then(|method| method.get_token())
+            .and_then(|token| token.get_line_number());
+        let strict = self.options.is_strict_wait_for_child();
+
+        let message = if strict {
+            format!(
+                "skipped bundling a require using `{}` because the module may not exist when the timeout expires",
+                code
+            )
+        } else {
+            format!(
+                "the timeout of `{}` is discarded by bundling and the required module is always inlined",
+                code
+            )
+        };
+        log::warn!("{} [from `{}`]", message, self.source.display());
+        self.push_warning(WarningCode::WaitForChildTimeout, message, line);
+
+        !strict
     }
 
-    fn resolve_identifier_to_instance_path(&self, name: &str) -> Option<InstancePath> {
-        fn resolve_in_block(
-            this: &RequireRobloxProcessor<'_, '_, '_>,
-            name: &str,
-            block: &Block,
-        ) -> Option<InstancePath> {
-            for statement in block.iter_statements() {
-                if let Statement::LocalAssign(local) = statement {
-                    for (var, value) in local.iter_variables().zip(local.iter_values()) {
-                        if var.get_identifier().get_name() == name {
-                            log::trace!(
-                                "resolve_identifier_to_instance_path: found local `{}`; try resolving its value",
-                                name
-                            );
-                            if let Some(path) = this.parse_expression_to_instance_path(value) {
-                                log::trace!(
-                                    "resolve_identifier_to_instance_path: successfully resolved `{}`",
-                                    name
-                                );
-                                return Some(path);
-                            } else {
-                                // Additionally handle locals initialized from a require call by resolving that require
-                                if let Expression::Call(call) = value {
-                                    if let Some((_, _, abs_instance_path)) = this.require_call(call) {
-                                        log::trace!(
-                                            "resolve_identifier_to_instance_path: resolved `{}` from require call",
-                                            name
-                                        );
-                                        return Some(abs_instance_path);
-                                    }
-                                }
-                                log::warn!(
-                                    "resolve_identifier_to_instance_path: failed to resolve value for `{}`",
-                                    name
-                                );
-                            }
-                        }
-                    }
-                }
+    /// Resolves an identifier through the innermost local variable visible among the first
+    /// `visible` locals in scope.
+    fn resolve_identifier_to_instance_path(
+        &self,
+        name: &str,
+        visible: usize,
+    ) -> Result<InstancePath, String> {
+        let (position, value) = match self.local_aliases.find(name, visible) {
+            Some(local) => local,
+            None => {
+                log::warn!(
+                    "resolve_identifier_to_instance_path: no local in scope named `{}`",
+                    name
+                );
+                return Err(format!(
+                    "unable to find a local variable `{}` referring to an instance",
+                    name
+                ));
             }
-            None
-        }
+        };
 
-        if let Some(p) = resolve_in_block(self, name, &self.current_block_clone) {
-            return Some(p);
-        }
-        if let Some(p) = resolve_in_block(self, name, &self.root_block_clone) {
-            return Some(p);
-        }
-        log::warn!(
-            "resolve_identifier_to_instance_path: no matching local found for `{}` in root scope",
+        let value = match value {
+            AliasValue::Expression(value) => value.as_ref(),
+            AliasValue::Unknown => {
+                return Err(format!(
+                    "local variable `{}` does not refer to a known instance",
+                    name
+                ))
+            }
+            AliasValue::Reassigned => {
+                return Err(format!(
+                    "local variable `{}` is reassigned before the require call",
+                    name
+                ))
+            }
+        };
+
+        log::trace!(
+            "resolve_identifier_to_instance_path: found local `{}`; try resolving its value",
             name
         );
-        None
+        // the value of the local can only reference the locals declared before it
+        match self.parse_expression_to_instance_path(value, position) {
+            Ok(path) => Ok(path),
+            Err(err) => {
+                // Additionally handle locals initialized from a require call by resolving that require
+                if let Expression::Call(call) = value {
+                    if let Some((_, _, abs_instance_path)) =
+                        self.resolve_require_call(call, position)
+                    {
+                        log::trace!(
+                            "resolve_identifier_to_instance_path: resolved `{}` from require call",
+                            name
+                        );
+                        return Ok(abs_instance_path);
+                    }
+                }
+                log::warn!(
+                    "resolve_identifier_to_instance_path: failed to resolve value for `{}`",
+                    name
+                );
+                Err(format!(
+                    "unable to resolve local variable `{}`: {}",
+                    name, err
+                ))
+            }
+        }
     }
 
     fn instance_path_to_game_string(&self, path: &InstancePath) -> String {
@@ -348,7 +440,6 @@ impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
 
     // Convert an InstancePath to a Prefix starting from DataModel (game)
     fn instance_path_to_game_prefix(&self, path: &InstancePath) -> Prefix {
-        use crate::rules::convert_require::RobloxIndexStyle;
         // Manually build a property-based path from `game`, avoiding GetService for the first component
         let mut components = path.components().iter();
         let mut prefix: Prefix = datamodel_identifier().into();
@@ -395,67 +486,170 @@ impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
         prefix
     }
 
+    fn push_error(&self, message: String, call: &FunctionCall) {
+        let location = match require_call_line(call) {
+            Some(line) => format!("`{}` at line {}", self.source.display(), line),
+            None => format!("`{}`", self.source.display()),
+        };
+        let error = format!("{} ({})", message, location);
+        // the same call can be resolved more than once when it is referenced by a local
+        let mut errors = self.errors.borrow_mut();
+        if !errors.iter().any(|existing| existing.to_string() == error) {
+            errors.push(DarkluaError::custom(error));
+        }
+    }
+
+    fn add_warning(&self, code: WarningCode, message: String, call: &FunctionCall) {
+        self.push_warning(code, message, require_call_line(call));
+    }
+
+    fn push_warning(&self, code: WarningCode, message: String, line: Option<usize>) {
+        let mut warning = RuleWarning::new(code, message).with_path(&self.source);
+        if let Some(line) = line {
+            warning = warning.with_line(line);
+        }
+        // the same call can be resolved more than once when it is referenced by a local
+        let mut warnings = self.warnings.borrow_mut();
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+
     fn require_call(&self, call: &FunctionCall) -> Option<(String, PathBuf, InstancePath)> {
-        if !is_require_call(call, self) {
+        self.resolve_require_call(call, self.local_aliases.visible())
+    }
+
+    fn resolve_require_call(
+        &self,
+        call: &FunctionCall,
+        visible: usize,
+    ) -> Option<(String, PathBuf, InstancePath)> {
+        if !self.require_aliases.is_require_call(call) {
             return None;
         }
         log::trace!(
             "require_call: processing require in `{}`",
             self.source.display()
         );
-        let instance_path = match call.get_arguments() {
-            Arguments::Tuple(tuple) if tuple.len() == 1 => {
-                log::trace!("require_call: arguments is Tuple(len=1) -> attempt to resolve path from single argument");
-                let expr = tuple.iter_values().next().unwrap();
-                self.parse_expression_to_instance_path(expr)
+        let instance_path = match (match_require_argument(call), call.get_arguments()) {
+            (Some(RequireArgument::Expression(expr)), _) => {
+                log::trace!("require_call: single argument -> attempt to resolve path from it");
+                self.parse_expression_to_instance_path(expr, visible)
             }
-            Arguments::Tuple(tuple) => {
+            (Some(RequireArgument::String(_)), _) => {
+                log::warn!(
+                    "require_call: arguments is a single String (unsupported for instance path)"
+                );
+                Err("string arguments are not supported".to_owned())
+            }
+            (None, Arguments::Tuple(tuple)) => {
                 log::trace!(
                     "require_call: arguments is Tuple(len={}), expected len=1",
                     tuple.len()
                 );
-                None
+                Err(format!("expected 1 argument but got {}", tuple.len()))
             }
-            Arguments::String(_) => {
+            (None, _) => {
+                log::warn!("require_call: unsupported arguments variant for require call");
+                Err("table arguments are not supported".to_owned())
+            }
+        };
+        let instance_path = match instance_path {
+            Ok(instance_path) => instance_path,
+            Err(reason) => {
+                let argument = format_require_argument(call);
+                let line = require_call_line(call);
                 log::warn!(
-                    "require_call: arguments is a single String (unsupported for instance path)"
+                    "could not resolve instance path for require call in `{}`{}: {}",
+                    self.source.display(),
+                    line.map(|line| format!(" at line {}", line))
+                        .unwrap_or_default(),
+                    reason
                 );
-                None
-            }
-            _ => {
-                log::warn!("require_call: unsupported arguments variant for require call");
-                None
+                self.push_warning(
+                    WarningCode::UnresolvedRequire,
+                    format!(
+                        "could not resolve instance path of `{}`: {}",
+                        argument, reason
+                    ),
+                    line,
+                );
+                return None;
             }
         };
-        if instance_path.is_none() {
-            log::warn!(
-                "could not resolve instance path for require call in `{}`",
-                self.source.display()
-            );
-            log::warn!(
-                "require_call: instance path resolution returned None (check earlier trace logs for why)"
-            );
-            return None;
-        }
-        let instance_path = instance_path?;
 
         // Use sourcemap to resolve to a file path
         let source_path = &self.source;
-        let target_file = match self
-            .roblox_require_mode
-            .get_file_from_instance_path(source_path, &instance_path)
-        {
-            Some(p) => p,
-            None => {
+        let target_file = match self.roblox_require_mode.get_file_from_instance_path(
+            source_path,
+            &instance_path,
+            self.resources,
+        ) {
+            Ok(p) => {
+                self.file_dependencies.borrow_mut().extend(
+                    self.roblox_require_mode
+                        .get_non_lua_files_from_instance_path(source_path, &instance_path),
+                );
+                p
+            }
+            Err(InstancePathResolutionError::MissingSourcemap) => {
+                self.push_error(
+                    format!(
+                        "unable to bundle `{}`: {}",
+                        format_require_call(call),
+                        InstancePathResolutionError::MissingSourcemap
+                    ),
+                    call,
+                );
+                return None;
+            }
+            Err(err) => {
+                if let InstancePathResolutionError::MissingChild {
+                    component_index,
+                    child,
+                    parent,
+                } = &err
+                {
+                    if self.options.is_verify_find_first_child()
+                        && instance_path.is_find_first_child(*component_index)
+                    {
+                        self.push_error(
+                            format!(
+                                "unable to bundle `{}`: `FindFirstChild` searched for `{}` under `{}` but it does not exist in the Rojo sourcemap",
+                                format_require_call(call),
+                                child,
+                                parent
+                            ),
+                            call,
+                        );
+                        return None;
+                    }
+                }
+                if matches!(instance_path.root(), InstancePathRoot::Script) {
+                    if let Some(message) =
+                        self.roblox_require_mode.missing_file_message(source_path)
+                    {
+                        // reported once per file instead of once per require
+                        log::debug!("{}", message);
+                        self.push_warning(WarningCode::FileNotInSourcemap, message, None);
+                        return None;
+                    }
+                }
                 log::warn!(
                     concat!(
-                        "require_call: unable to resolve file from instance path via sourcemap; ",
+                        "require_call: unable to resolve file from instance path via sourcemap ({}); ",
                         "instance path = {:?}, source = `{}`; ",
                         "skipping exclusion handling and DataModel-root rewrite for this require"
                     ),
+                    err,
                     instance_path,
                     source_path.display()
                 );
+                self.add_warning(
+                    WarningCode::UnresolvedRequire,
+                    format!("unable to resolve `{}`: {}", format_require_call(call), err),
+                    call,
+                );
                 return None;
             }
         };
@@ -466,6 +660,8 @@ impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
             .get_instance_path_for_file(source_path, &target_file)
         {
             Some(p) => p,
+            // files resolved from the file layout only have the path inferred from code
+            None if !self.roblox_require_mode.has_sourcemap() => instance_path.clone(),
             None => {
                 log::warn!(
                     concat!(
@@ -481,40 +677,217 @@ impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
 
         let roblox_reference = self.instance_path_to_game_string(&abs_instance_path);
 
+        if !self.options.is_allow_non_module_requires() {
+            if let Ok(class_name) = self
+                .roblox_require_mode
+                .get_class_name_from_instance_path(source_path, &instance_path)
+            {
+                if class_name != MODULE_SCRIPT_CLASS_NAME {
+                    self.push_error(
+                        format!(
+                            "unable to bundle `{}`: `{}` is a `{}`, only a `{}` can be required",
+                            format_require_call(call),
+                            roblox_reference,
+                            class_name,
+                            MODULE_SCRIPT_CLASS_NAME
+                        ),
+                        call,
+                    );
+                    return None;
+                }
+            }
+        }
+
         Some((roblox_reference, target_file, abs_instance_path))
     }
 
-    fn try_inline_call(&mut self, call: &FunctionCall) -> Option<Expression> {
+    fn protected_require_call(&self, call: &FunctionCall) -> Option<ProtectedRequireCall> {
+        if self.options.pcall_requires().is_ignore() {
+            return None;
+        }
+        match_protected_require_call(
+            call,
+            |name| self.require_aliases.is_global(name),
+            |name| self.require_aliases.is_require_identifier(name),
+        )
+    }
+
+    fn is_excluded_require(
+        &self,
+        require_path: &Path,
+        directive: Option<RequireDirective>,
+    ) -> bool {
+        match directive {
+            Some(RequireDirective::KeepRequire) => true,
+            Some(RequireDirective::Bundle) => false,
+            None => self.options.is_excluded(require_path),
+        }
+    }
+
+    fn try_inline_call(&mut self, call: &FunctionCall, is_statement: bool) -> Option<Expression> {
+        if self.lazy_requires.is_unreferenced() {
+            log::trace!(
+                "skip `{}` because its function is never referenced [from `{}`]",
+                format_require_call(call),
+                self.source.display()
+            );
+            return None;
+        }
+
+        let directive = match_require_directive(call);
+
+        if let Some(protected_call) = self.protected_require_call(call) {
+            return self.try_inline_protected_call(&protected_call, directive);
+        }
+
         let (roblox_reference, require_path, abs_instance_path) = self.require_call(call)?;
 
-        if self.options.is_excluded(&require_path) {
+        if self.is_excluded_require(&require_path, directive) {
             log::info!(
                 "exclude `{}` from bundle [from `{}`]",
                 require_path.display(),
                 self.source.display()
             );
-            // Instead of skipping, rewrite the require argument to a DataModel-rooted path
-            let rewrite_path = self
-                .roblox_require_mode
-                .get_absolute_instance_path_for_file(&require_path)
-                .or_else(|| self.roblox_require_mode.get_instance_path_for_file(&self.source, &require_path))
-                .unwrap_or(abs_instance_path);
-            if matches!(rewrite_path.root(), InstancePathRoot::Script) {
-                log::warn!(
-                    concat!(
-                        "exclude prevented inlining but could not compute absolute DataModel path for `{}`; ",
-                        "falling back to script-rooted path (require may not use `game` root)"
-                    ),
-                    require_path.display()
-                );
+            self.options.record_require(
+                self.requiring_module(),
+                &require_path,
+                RequireKind::Excluded,
+            );
+            let new_call = self.rewrite_excluded_require(call, &require_path, abs_instance_path);
+            if self.options.is_hoist_excluded_requires() {
+                if let Some(identifier) = self.hoist_excluded_require(&new_call) {
+                    return Some(Identifier::new(identifier).into());
+                }
             }
-            let new_prefix = self.instance_path_to_game_prefix(&rewrite_path);
-            let mut new_call = call.clone();
-            new_call.set_arguments(Arguments::default().with_argument(new_prefix));
             return Some(Expression::Call(Box::new(new_call)));
         }
 
-        if self.skip_module_paths.contains(&require_path) {
+        self.inline_resolved_require(&roblox_reference, require_path, call, is_statement)
+    }
+
+    fn try_inline_protected_call(
+        &mut self,
+        protected_call: &ProtectedRequireCall,
+        directive: Option<RequireDirective>,
+    ) -> Option<Expression> {
+        let call = protected_call.require_call();
+        let (roblox_reference, require_path, abs_instance_path) = self.require_call(call)?;
+
+        if self.options.pcall_requires() == PcallRequires::Exclude
+            || self.is_excluded_require(&require_path, directive)
+        {
+            log::info!(
+                "exclude protected require of `{}` from bundle [from `{}`]",
+                require_path.display(),
+                self.source.display()
+            );
+            self.options.record_require(
+                self.requiring_module(),
+                &require_path,
+                RequireKind::Excluded,
+            );
+            let new_call = self.rewrite_excluded_require(call, &require_path, abs_instance_path);
+            return Some(protected_call.with_require_call(&new_call).into());
+        }
+
+        self.inline_resolved_require(&roblox_reference, require_path, call, false)
+            .map(|value| {
+                let value = self.module_definitions.wrapped_modules().all_values(value);
+                protected_call.wrap(value).into()
+            })
+    }
+
+    /// Returns the path configured by the first exclude rewrite matching the given
+    /// excluded require, if any.
+    fn configured_rewrite_path(
+        &mut self,
+        call: &FunctionCall,
+        require_path: &Path,
+    ) -> Option<InstancePath> {
+        let rewrite = self.options.find_exclude_rewrite(require_path)?;
+
+        match rewrite.target_path(require_path) {
+            Ok(instance_path) => Some(instance_path),
+            Err(err) => {
+                self.errors
+                    .get_mut()
+                    .push(DarkluaError::custom(err).context(format!(
+                        "unable to rewrite excluded `{}` with the rewrite of pattern `{}`",
+                        format_require_call(call),
+                        rewrite.pattern()
+                    )));
+                None
+            }
+        }
+    }
+
+    /// Rewrites the argument of an excluded require to a DataModel-rooted path.
+    fn rewrite_excluded_require(
+        &mut self,
+        call: &FunctionCall,
+        require_path: &Path,
+        abs_instance_path: InstancePath,
+    ) -> FunctionCall {
+        if let Some(rewrite_path) = self.configured_rewrite_path(call, require_path) {
+            let new_argument = match rewrite_path.root() {
+                InstancePathRoot::Root => self.instance_path_to_game_prefix(&rewrite_path),
+                InstancePathRoot::Script => rewrite_path.convert(&RobloxIndexStyle::Property),
+            };
+            return self.replace_require_argument(call, new_argument);
+        }
+
+        let rewrite_path = self
+            .roblox_require_mode
+            .get_absolute_instance_path_for_file(require_path)
+            .or_else(|| {
+                self.roblox_require_mode
+                    .get_instance_path_for_file(&self.source, require_path)
+            })
+            .unwrap_or(abs_instance_path);
+        if matches!(rewrite_path.root(), InstancePathRoot::Script) {
+            log::warn!(
+                concat!(
+                    "exclude prevented inlining but could not compute absolute DataModel path for `{}`; ",
+                    "falling back to script-rooted path (require may not use `game` root)"
+                ),
+                require_path.display()
+            );
+            self.add_warning(
+                WarningCode::ExcludedFallbackScriptRoot,
+                format!(
+                    concat!(
+                        "excluded `{}` could not be rewritten from the DataModel root, ",
+                        "falling back to a script-rooted path"
+                    ),
+                    format_require_call(call)
+                ),
+                call,
+            );
+        }
+        let new_argument = self.instance_path_to_game_prefix(&rewrite_path);
+        self.replace_require_argument(call, new_argument)
+    }
+
+    fn replace_require_argument(&self, call: &FunctionCall, new_argument: Prefix) -> FunctionCall {
+        let mut new_argument: Expression = new_argument.into();
+        if let Some(quote_style) = self.options.quote_style() {
+            quote_style.apply_to_expression(&mut new_argument, self.original_quote(call));
+        }
+        let mut new_call = call.clone();
+        new_call.set_arguments(Arguments::default().with_argument(new_argument));
+        new_call
+    }
+
+    fn inline_resolved_require(
+        &mut self,
+        roblox_reference: &str,
+        require_path: PathBuf,
+        call: &FunctionCall,
+        is_statement: bool,
+    ) -> Option<Expression> {
+        let module_key = self.options.module_key(self.resources, &require_path);
+
+        if self.skip_module_paths.contains(&module_key) {
             log::trace!(
                 "skip `{}` because it previously errored",
                 require_path.display()
@@ -522,66 +895,187 @@ impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
             return None;
         }
 
-        match self.inline_require(&roblox_reference, &require_path, call) {
+        match self.inline_require(roblox_reference, &require_path, call, is_statement) {
             Ok(expression) => Some(expression),
             Err(error) => {
-                self.errors.push(error.to_string());
-                self.skip_module_paths.insert(require_path);
+                self.errors.get_mut().push(error);
+                self.skip_module_paths.insert(module_key);
                 None
             }
         }
     }
 
+    /// Returns the local holding the result of the given excluded require, declaring it the
+    /// first time the require is found. Returns `None` when that local is shadowed at the
+    /// call site.
+    fn hoist_excluded_require(&mut self, call: &FunctionCall) -> Option<String> {
+        let code = format_require_call(call);
+
+        if let Some((identifier, _)) = self.hoisted_requires.get(&code) {
+            if self.identifier_tracker.is_identifier_used(identifier) {
+                log::warn!(
+                    "unable to hoist `{}` because `{}` is shadowed [from `{}`]",
+                    code,
+                    identifier,
+                    self.source.display()
+                );
+                return None;
+            }
+            return Some(identifier.clone());
+        }
+
+        let identifier = loop {
+            let identifier = self
+                .generated_identifiers
+                .borrow_mut()
+                .generate_with_prefix(HOISTED_REQUIRE_PREFIX);
+            if !self.identifier_tracker.is_identifier_used(&identifier) {
+                break identifier;
+            }
+        };
+        log::debug!("hoist `{}` into `{}`", code, identifier);
+        self.hoisted_requires
+            .insert(code, (identifier.clone(), call.clone()));
+
+        Some(identifier)
+    }
+
+    // the module being bundled when a require is found
+    fn requiring_module(&self) -> &Path {
+        self.require_stack.last().unwrap_or(&self.source)
+    }
+
     fn inline_require(
         &mut self,
         roblox_reference: &str,
         require_path: &Path,
         call: &FunctionCall,
+        is_statement: bool,
     ) -> DarkluaResult<Expression> {
-        if let Some(expression) = self.module_cache.get(require_path) {
+        // a module reachable through different paths (like a symlink or a different casing)
+        // is only bundled once
+        let module_key = self.options.module_key(self.resources, require_path);
+
+        if let Some(expression) = self.module_cache.get(&module_key) {
+            self.options.record_require(
+                self.requiring_module(),
+                require_path,
+                RequireKind::Bundled,
+            );
             Ok(expression.clone())
         } else {
             if let Some(i) = self
                 .require_stack
                 .iter()
                 .enumerate()
-                .find(|(_, path)| *path == require_path)
+                .find(|(_, path)| self.options.module_key(self.resources, path) == module_key)
                 .map(|(i, _)| i)
             {
                 let require_stack_paths: Vec<_> = self
                     .require_stack
                     .iter()
                     .skip(i)
-                    .map(|path| path.display().to_string())
-                    .chain(iter::once(require_path.display().to_string()))
+                    .cloned()
+                    .chain(iter::once(require_path.to_path_buf()))
                     .collect();
 
-                return Err(DarkluaError::custom(format!(
-                    "cyclic require detected with `{}`",
-                    require_stack_paths.join("` > `")
-                )));
+                self.options.record_require(
+                    self.requiring_module(),
+                    require_path,
+                    RequireKind::Cyclic,
+                );
+
+                return Err(DarkluaError::cyclic_require(require_stack_paths));
             }
 
+            self.options.record_require(
+                self.requiring_module(),
+                require_path,
+                RequireKind::Bundled,
+            );
+
+            self.options
+                .verify_require_depth(&self.require_stack, require_path)?;
+
             self.require_stack.push(require_path.to_path_buf());
             let required_resource = self.require_resource(require_path);
             self.require_stack.pop();
 
+            let required_resource = required_resource?;
+
+            // a data file required as a statement is only read to report its errors: its
+            // value is returned without a module, so that the statement can be removed
+            if is_statement {
+                if let RequiredResource::Expression(value) = required_resource {
+                    log::debug!(
+                        "skip module of `{}` required as a statement",
+                        require_path.display()
+                    );
+                    return Ok(value);
+                }
+            }
+
             let module_value = self.module_definitions.build_module_from_resource(
-                required_resource?,
+                required_resource,
+                self.options
+                    .resource_source_id(self.resources, require_path),
                 roblox_reference,
+                require_path,
                 call,
+                self.original_quote(call),
+                &mut self.generated_identifiers.borrow_mut(),
             )?;
 
-            self.module_cache
-                .insert(require_path.to_path_buf(), module_value.clone());
+            self.module_cache.insert(module_key, module_value.clone());
 
             Ok(module_value)
         }
     }
 
+    // validates the null sentinel and records that it must be declared in the bundle
+    fn data_null_sentinel(&mut self) -> DarkluaResult<Option<&'a str>> {
+        let options: &'a BundleOptions = self.options;
+        match options.null_sentinel() {
+            Some(null_sentinel) if !is_valid_identifier(null_sentinel) => {
+                Err(DarkluaError::custom(format!(
+                    "invalid `null_sentinel` value `{}`: expected a Lua identifier",
+                    null_sentinel
+                )))
+            }
+            Some(null_sentinel) => {
+                self.uses_null_sentinel = true;
+                Ok(Some(null_sentinel))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // the quote of the original require is only read when it needs to be preserved
+    fn original_quote(&self, call: &FunctionCall) -> Option<StringQuote> {
+        match self.options.quote_style() {
+            Some(QuoteStyle::Preserve) => {
+                find_arguments_quote(call.get_arguments(), self.original_code)
+            }
+            _ => None,
+        }
+    }
+
     fn require_resource(&mut self, path: impl AsRef<Path>) -> DarkluaResult<RequiredResource> {
         let path = path.as_ref();
         log::trace!("look for resource `{}`", path.display());
+
+        let is_binary = path.extension().is_some_and(|extension| {
+            self.options
+                .is_binary_extension(&extension.to_string_lossy().to_ascii_lowercase())
+        });
+        if is_binary {
+            let content = self.resources.get_bytes(path).map_err(DarkluaError::from)?;
+            return self
+                .options
+                .binary_expression(path, &content)
+                .map(RequiredResource::Expression);
+        }
+
         let content = self.resources.get(path).map_err(DarkluaError::from)?;
 
         match path.extension() {
@@ -589,7 +1083,7 @@ impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
                 "lua" | "luau" => {
                     let parser_timer = Timer::now();
                     // Intern the file for source ids and parse using shared source_id
-                    let source_id = self.options.registry().borrow_mut().intern(path);
+                    let source_id = self.options.intern_source(self.resources, path);
                     let mut block = self
                         .options
                         .parser()
@@ -603,9 +1097,13 @@ impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
                         parser_timer.duration_label()
                     );
 
+                    let context = ContextBuilder::new(path, self.resources, &content)
+                        .with_generated_identifiers(self.generated_identifiers.clone())
+                        .build();
+                    self.options.apply_pre_bundle_rules(&mut block, &context)?;
+
                     if self.options.parser().is_preserving_tokens() {
                         log::trace!("replacing token references of {}", path.display());
-                        let context = ContextBuilder::new(path, self.resources, &content).build();
                         let replace_tokens = ReplaceReferencedTokens::default();
 
                         let apply_replace_tokens_timer = Timer::now();
@@ -619,14 +1117,33 @@ impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
                         );
                     }
 
+                    // the names generated for the bundle must not collide with the names of
+                    // the inlined modules
+                    self.generated_identifiers
+                        .borrow_mut()
+                        .reserve_block(&mut block);
+
+                    self.options.on_identifier_conflict().resolve(
+                        &mut block,
+                        self.module_definitions.modules_identifier(),
+                        path,
+                        &mut self.generated_identifiers.borrow_mut(),
+                    )?;
+
+                    let warnings = self.options.global_access_warnings(&mut block, path);
+                    self.warnings.borrow_mut().extend(warnings);
+
                     let current_source = mem::replace(&mut self.source, path.to_path_buf());
-                    let previous_block_clone =
-                        mem::replace(&mut self.current_block_clone, block.clone());
-                    let previous_root_block_clone =
-                        mem::replace(&mut self.root_block_clone, block.clone());
+                    // the locals of the requiring module are not visible from the required one
+                    let previous_identifier_tracker = mem::take(&mut self.identifier_tracker);
+                    let previous_local_aliases = mem::take(&mut self.local_aliases);
+                    let previous_require_aliases = mem::take(&mut self.require_aliases);
 
                     let apply_processor_timer = Timer::now();
-                    DefaultVisitor::visit_block(&mut block, self);
+                    ScopePostVisitor::visit_block(&mut block, self);
+                    self.module_definitions
+                        .wrapped_modules()
+                        .expand_values(&mut block);
 
                     log::debug!(
                         "processed `{}` into bundle in {}",
@@ -635,24 +1152,37 @@ impl<'a, 'b, 'resources> RequireRobloxProcessor<'a, 'b, 'resources> {
                     );
 
                     self.source = current_source;
-                    self.current_block_clone = previous_block_clone;
-                    self.root_block_clone = previous_root_block_clone;
+                    self.identifier_tracker = previous_identifier_tracker;
+                    self.local_aliases = previous_local_aliases;
+                    self.require_aliases = previous_require_aliases;
 
                     Ok(RequiredResource::Block(block))
                 }
-                "json" | "json5" => {
-                    transcode("json", path, json5::from_str::<serde_json::Value>, &content)
-                }
+                "json" | "json5" => transcode(
+                    "json",
+                    path,
+                    json5::from_str::<serde_json::Value>,
+                    &content,
+                    self.data_null_sentinel()?,
+                ),
                 "yml" | "yaml" => transcode(
                     "yaml",
                     path,
                     serde_yaml::from_str::<serde_yaml::Value>,
                     &content,
+                    self.data_null_sentinel()?,
                 ),
-                "toml" => transcode("toml", path, toml::from_str::<toml::Value>, &content),
-                "txt" => Ok(RequiredResource::Expression(
-                    StringExpression::from_value(content).into(),
-                )),
+                "toml" => transcode(
+                    "toml",
+                    path,
+                    toml::from_str::<toml::Value>,
+                    &content,
+                    self.data_null_sentinel()?,
+                ),
+                extension if self.options.is_raw_text_extension(extension) => self
+                    .options
+                    .raw_text_expression(path, content)
+                    .map(RequiredResource::Expression),
                 _ => Err(DarkluaError::invalid_resource_extension(path)),
             },
             None => unreachable!("extension should be defined"),
@@ -665,6 +1195,7 @@ fn transcode<'a, T, E>(
     path: &Path,
     deserialize_value: impl Fn(&'a str) -> Result<T, E>,
     content: &'a str,
+    null_sentinel: Option<&str>,
 ) -> Result<RequiredResource, DarkluaError>
 where
     T: serde::Serialize,
@@ -673,9 +1204,11 @@ where
     log::trace!("transcode {} data to Lua from `{}`", label, path.display());
     let transcode_duration = Timer::now();
     let value = deserialize_value(content).map_err(E::into)?;
-    let expression = to_expression(&value)
+    let expression = to_data_expression(&value, null_sentinel)
         .map(RequiredResource::Expression)
-        .map_err(DarkluaError::from);
+        .map_err(|error| {
+            DarkluaError::from(error).context(format!("transcoding `{}`", path.display()))
+        });
     log::debug!(
         "transcoded {} data to Lua from `{}` in {}",
         label,
@@ -685,60 +1218,122 @@ where
     expression
 }
 
-impl Deref for RequireRobloxProcessor<'_, '_, '_> {
-    type Target = IdentifierTracker;
+impl Scope for RequireRobloxProcessor<'_, '_, '_> {
+    fn push(&mut self) {
+        self.identifier_tracker.push();
+        self.local_aliases.push_scope();
+        self.require_aliases.push_scope();
+        self.lazy_requires.push_scope();
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.identifier_tracker
+    fn pop(&mut self) {
+        self.identifier_tracker.pop();
+        self.local_aliases.pop_scope();
+        self.require_aliases.pop_scope();
+        self.lazy_requires.pop_scope();
+    }
+
+    fn insert(&mut self, identifier: &mut String) {
+        self.identifier_tracker.insert(identifier);
+        self.local_aliases.insert_unknown(identifier);
+        self.require_aliases.insert_other(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+        self.local_aliases.insert_unknown("self");
+        self.require_aliases.insert_other("self");
+    }
+
+    fn insert_local(&mut self, identifier: &mut String, value: Option<&mut Expression>) {
+        self.identifier_tracker.insert_local(identifier, value);
+        self.local_aliases.insert_local(identifier);
+        self.require_aliases.insert_local(identifier);
     }
-}
 
-impl DerefMut for RequireRobloxProcessor<'_, '_, '_> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.identifier_tracker
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.identifier_tracker.insert_local_function(function);
+        self.local_aliases
+            .insert_unknown(function.get_identifier().get_name());
+        self.require_aliases
+            .insert_other(function.get_identifier().get_name());
     }
 }
 
 impl NodeProcessor for RequireRobloxProcessor<'_, '_, '_> {
-    fn process_scope(&mut self, block: &mut Block, _extra: Option<&mut Expression>) {
-        self.current_block_clone = block.clone();
+    fn process_scope(&mut self, block: &mut Block, extra: Option<&mut Expression>) {
+        if extra.is_some() {
+            self.lazy_requires.prepare_block(block, extra);
+        }
+    }
+
+    fn process_block(&mut self, block: &mut Block) {
+        self.lazy_requires.prepare_block(block, None);
+        self.require_statements.enter_block();
+    }
+
+    fn process_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.lazy_requires.prepare_local_function();
+    }
+
+    fn process_local_assign_statement(&mut self, statement: &mut LocalAssignStatement) {
+        self.local_aliases.prepare_local_assign(statement);
+        self.require_aliases.prepare_local_assign(statement);
+    }
+
+    fn process_assign_statement(&mut self, statement: &mut AssignStatement) {
+        for variable in statement.iter_variables() {
+            if let Variable::Identifier(identifier) = variable {
+                self.local_aliases.reassign(identifier.get_name());
+                self.require_aliases.reassign(identifier.get_name());
+            }
+        }
+    }
+
+    fn process_compound_assign_statement(&mut self, statement: &mut CompoundAssignStatement) {
+        if let Variable::Identifier(identifier) = statement.get_variable() {
+            self.local_aliases.reassign(identifier.get_name());
+            self.require_aliases.reassign(identifier.get_name());
+        }
     }
 
     fn process_expression(&mut self, expression: &mut Expression) {
         if let Expression::Call(call) = expression {
-            if let Some(replace_with) = self.try_inline_call(call) {
-                *expression = replace_with;
+            if let Some(replace_with) = self.try_inline_call(call, false) {
+                *expression = self
+                    .module_definitions
+                    .wrapped_modules()
+                    .single_value(replace_with);
             }
         }
     }
 
     fn process_prefix_expression(&mut self, prefix: &mut Prefix) {
         if let Prefix::Call(call) = prefix {
-            if let Some(replace_with) = self.try_inline_call(call) {
-                *prefix = replace_with.into();
+            if let Some(replace_with) = self.try_inline_call(call, false) {
+                *prefix = self
+                    .module_definitions
+                    .wrapped_modules()
+                    .single_value(replace_with)
+                    .into();
             }
         }
     }
 
     fn process_statement(&mut self, statement: &mut Statement) {
+        self.require_statements.enter_statement();
         if let Statement::Call(call) = statement {
-            if let Some(replace_with) = self.try_inline_call(call) {
-                if let Expression::Call(replace_with) = replace_with {
-                    *call = *replace_with;
-                } else {
-                    *statement = convert_expression_to_statement(replace_with);
-                }
+            if let Some(replace_with) = self.try_inline_call(call, true) {
+                self.require_statements.replace(statement, replace_with);
             }
         }
     }
 }
 
-fn convert_expression_to_statement(expression: Expression) -> Statement {
-    DoStatement::new(
-        Block::default()
-            .with_statement(LocalAssignStatement::from_variable("_").with_value(expression)),
-    )
-    .into()
+impl NodePostProcessor for RequireRobloxProcessor<'_, '_, '_> {
+    fn process_after_block(&mut self, block: &mut Block) {
+        self.require_statements.exit_block(block);
+    }
 }
 
 pub(crate) fn process_block(
@@ -765,8 +1360,16 @@ pub(crate) fn process_block(
         );
     }
 
-    let mut processor =
-        RequireRobloxProcessor::new(context, options, roblox_require_mode, block.clone());
-    ScopeVisitor::visit_block(block, &mut processor);
+    context
+        .generated_identifiers()
+        .borrow_mut()
+        .reserve_block(block);
+
+    let mut processor = RequireRobloxProcessor::new(context, options, roblox_require_mode);
+    processor
+        .warnings
+        .borrow_mut()
+        .extend(options.global_access_warnings(block, context.current_path()));
+    ScopePostVisitor::visit_block(block, &mut processor);
     processor.apply(block, context)
 }
@@ -0,0 +1,251 @@
+use crate::nodes::{
+    AssignStatement, Block, CompoundAssignStatement, Expression, FunctionExpression,
+    LocalFunctionStatement, Statement, Variable,
+};
+use crate::process::{processors::FindVariables, DefaultVisitor, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+};
+
+use std::mem;
+
+use super::verify_no_rule_properties;
+
+fn into_local_function(name: String, function: &mut FunctionExpression) -> LocalFunctionStatement {
+    let mut local_function = LocalFunctionStatement::new(
+        name,
+        mem::take(function.mutate_block()),
+        mem::take(function.mutate_parameters()),
+        function.is_variadic(),
+    );
+
+    if let Some(variadic_type) = function.get_variadic_type() {
+        local_function.set_variadic_type(variadic_type.clone());
+    }
+    if let Some(return_type) = function.get_return_type() {
+        local_function.set_return_type(return_type.clone());
+    }
+    if let Some(generic_parameters) = function.get_generic_parameters() {
+        local_function.set_generic_parameters(generic_parameters.clone());
+    }
+
+    local_function
+}
+
+fn is_variable_named(variable: &Variable, name: &str) -> bool {
+    matches!(variable, Variable::Identifier(identifier) if identifier.get_name() == name)
+}
+
+// matches `local name` without any value or type
+fn get_declared_name(statement: &Statement) -> Option<&str> {
+    match statement {
+        Statement::LocalAssign(local_assign)
+            if local_assign.variables_len() == 1 && !local_assign.has_values() =>
+        {
+            let variable = local_assign.get_variables().first()?;
+            (!variable.has_type()).then(|| variable.get_identifier().get_name().as_str())
+        }
+        _ => None,
+    }
+}
+
+// matches `name = function(...) ... end`
+fn get_assigned_function<'a>(
+    statement: &'a mut Statement,
+    name: &str,
+) -> Option<&'a mut FunctionExpression> {
+    match statement {
+        Statement::Assign(assign) if assign.variables_len() == 1 && assign.values_len() == 1 => {
+            if !assign
+                .iter_variables()
+                .all(|variable| is_variable_named(variable, name))
+            {
+                return None;
+            }
+            match assign.iter_mut_values().next()? {
+                Expression::Function(function) => Some(function),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn declares_name(statement: &Statement, name: &str) -> bool {
+    match statement {
+        Statement::LocalAssign(local_assign) => local_assign
+            .iter_variables()
+            .any(|variable| variable.get_identifier().get_name() == name),
+        Statement::LocalFunction(local_function) => local_function.get_name() == name,
+        _ => false,
+    }
+}
+
+fn references_name(statement: &mut Statement, name: &str) -> bool {
+    let mut find_usage = FindVariables::new(name);
+    DefaultVisitor::visit_statement(statement, &mut find_usage);
+    find_usage.has_found_usage()
+}
+
+// finds assignments to any variable with the given name, including variables that
+// shadow the local
+struct FindWrites<'a> {
+    name: &'a str,
+    found: bool,
+}
+
+impl<'a> FindWrites<'a> {
+    fn new(name: &'a str) -> Self {
+        Self { name, found: false }
+    }
+}
+
+impl NodeProcessor for FindWrites<'_> {
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        if assign
+            .iter_variables()
+            .any(|variable| is_variable_named(variable, self.name))
+        {
+            self.found = true;
+        }
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        if is_variable_named(assign.get_variable(), self.name) {
+            self.found = true;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Processor;
+
+impl Processor {
+    // finds the index of the statement that assigns the function to the local declared at
+    // the given index, if the local can be declared by a local function statement instead
+    fn find_function_assign(&self, block: &mut Block, index: usize) -> Option<(String, usize)> {
+        let name = get_declared_name(block.iter_statements().nth(index)?)?.to_owned();
+
+        // the local must not be used before the function is assigned to it
+        let assign_index = {
+            let mut statements = block.iter_mut_statements().enumerate().skip(index + 1);
+
+            loop {
+                let (statement_index, statement) = statements.next()?;
+
+                if get_assigned_function(statement, &name).is_some() {
+                    break statement_index;
+                }
+                if declares_name(statement, &name) || references_name(statement, &name) {
+                    return None;
+                }
+            }
+        };
+
+        // the function must be the only value assigned to the local
+        let mut find_writes = FindWrites::new(&name);
+
+        if let Some(function) = block
+            .iter_mut_statements()
+            .nth(assign_index)
+            .and_then(|statement| get_assigned_function(statement, &name))
+        {
+            DefaultVisitor::visit_block(function.mutate_block(), &mut find_writes);
+        }
+
+        for statement in block.iter_mut_statements().skip(assign_index + 1) {
+            DefaultVisitor::visit_statement(statement, &mut find_writes);
+        }
+        if let Some(last_statement) = block.mutate_last_statement() {
+            DefaultVisitor::visit_last_statement(last_statement, &mut find_writes);
+        }
+
+        if find_writes.found {
+            None
+        } else {
+            Some((name, assign_index))
+        }
+    }
+}
+
+impl NodeProcessor for Processor {
+    fn process_block(&mut self, block: &mut Block) {
+        let mut index = 0;
+
+        while index < block.statements_len() {
+            if let Some((name, assign_index)) = self.find_function_assign(block, index) {
+                if let Some(statement) = block.iter_mut_statements().nth(assign_index) {
+                    let local_function = get_assigned_function(statement, &name)
+                        .map(|function| into_local_function(name, function));
+
+                    if let Some(local_function) = local_function {
+                        *statement = local_function.into();
+                    }
+                }
+                block.remove_statement(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+pub const COLLAPSE_FUNCTION_ASSIGN_RULE_NAME: &str = "collapse_function_assign";
+
+/// A rule that converts a local declaration followed by the assignment of a function
+/// expression into a local function statement.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CollapseFunctionAssign {}
+
+impl FlawlessRule for CollapseFunctionAssign {
+    fn flawless_process(&self, block: &mut Block, _: &Context) {
+        let mut processor = Processor;
+        DefaultVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for CollapseFunctionAssign {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)?;
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        COLLAPSE_FUNCTION_ASSIGN_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> CollapseFunctionAssign {
+        CollapseFunctionAssign::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_collapse_function_assign", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'collapse_function_assign',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -1,18 +1,37 @@
+use std::ops::{Deref, DerefMut};
+
 use crate::nodes::{BinaryOperator, Block, Expression};
 use crate::utils::origin::{anchor_from_expression, token_from_content_with_anchor};
-use crate::process::{DefaultVisitor, Evaluator, NodeProcessor, NodeVisitor};
+use crate::process::{
+    Evaluator, IdentifierTracker, NodeProcessor, NodeVisitor, PureCalls, ScopeVisitor,
+};
 use crate::rules::{
     Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
 };
 
 use super::verify_no_rule_properties;
 
-#[derive(Debug, Clone, Default)]
-struct Computer {
+#[derive(Debug, Clone)]
+struct Computer<'a> {
     evaluator: Evaluator,
+    context: &'a Context<'a, 'a, 'a>,
 }
 
-impl Computer {
+impl<'a> Computer<'a> {
+    fn new(context: &'a Context, pure_calls: PureCalls) -> Self {
+        Self {
+            evaluator: Evaluator::default().with_pure_calls(pure_calls),
+            context,
+        }
+    }
+
+    fn is_disabled(&self, expression: &Expression) -> bool {
+        anchor_from_expression(expression).is_some_and(|anchor| {
+            self.context
+                .is_line_disabled(COMPUTE_EXPRESSIONS_RULE_NAME, anchor.line_number)
+        })
+    }
+
     fn replace_with(&mut self, expression: &Expression) -> Option<Expression> {
         match expression {
             Expression::Unary(_) => {
@@ -106,8 +125,26 @@ impl Computer {
     }
 }
 
-impl NodeProcessor for Computer {
+impl Deref for Computer<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        self.evaluator.identifier_tracker()
+    }
+}
+
+impl DerefMut for Computer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.evaluator.mutate_identifier_tracker()
+    }
+}
+
+impl NodeProcessor for Computer<'_> {
     fn process_expression(&mut self, expression: &mut Expression) {
+        if self.is_disabled(expression) {
+            return;
+        }
+
         if let Some(evaluated) = self.replace_with(expression) {
             let mut replace_with = evaluated;
             use crate::generator::utils as gen_utils;
@@ -162,9 +199,9 @@ pub const COMPUTE_EXPRESSIONS_RULE_NAME: &str = "compute_expression";
 pub struct ComputeExpression {}
 
 impl FlawlessRule for ComputeExpression {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
-        let mut processor = Computer::default();
-        DefaultVisitor::visit_block(block, &mut processor);
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = Computer::new(context, context.pure_calls(block));
+        ScopeVisitor::visit_block(block, &mut processor);
     }
 }
 
@@ -38,6 +38,9 @@ pub enum RuleConfigurationError {
     /// When a rule can only be used internally by darklua. The string is the rule name
     /// (this error should not surface to external consumers)
     InternalUsageOnly(String),
+    /// When a custom rule is registered with the name of a built-in rule. The string is the
+    /// rule name.
+    RuleNameCollision(String),
 }
 
 fn enumerate_properties(properties: &[String]) -> String {
@@ -99,6 +102,13 @@ impl fmt::Display for RuleConfigurationError {
                     rule_name
                 )
             }
+            RuleNameCollision(rule_name) => {
+                write!(
+                    f,
+                    "unable to register rule `{}` because a built-in rule already uses this name",
+                    rule_name
+                )
+            }
         }
     }
 }
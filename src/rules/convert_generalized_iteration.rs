@@ -0,0 +1,259 @@
+use crate::nodes::{Block, Expression, FieldExpression, FunctionCall, GenericForStatement, Prefix};
+use crate::process::utils::is_valid_identifier;
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+};
+
+pub const CONVERT_GENERALIZED_ITERATION_RULE_NAME: &str = "convert_generalized_iteration";
+
+const DEFAULT_ITERATOR_FUNCTION: &str = "pairs";
+const KNOWN_ITERATOR_FUNCTIONS: [&str; 3] = ["pairs", "ipairs", "next"];
+
+fn is_valid_iterator_function(name: &str) -> bool {
+    name.split('.').all(is_valid_identifier)
+}
+
+struct Processor<'a> {
+    iterator_function: Vec<&'a str>,
+    identifier_tracker: IdentifierTracker,
+}
+
+impl<'a> Processor<'a> {
+    fn new(iterator_function: &'a str) -> Self {
+        Self {
+            iterator_function: iterator_function.split('.').collect(),
+            identifier_tracker: IdentifierTracker::new(),
+        }
+    }
+
+    fn root_name(&self) -> &'a str {
+        self.iterator_function[0]
+    }
+
+    fn iterator_call(&self, value: Expression) -> FunctionCall {
+        let mut names = self.iterator_function.iter();
+        let root = Prefix::from_name(*names.next().expect("iterator function root name"));
+        let prefix = names.fold(root, |prefix, name| {
+            FieldExpression::new(prefix, *name).into()
+        });
+
+        FunctionCall::from_prefix(prefix).with_argument(value)
+    }
+
+    fn is_generalized_iteration(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::Identifier(identifier) => {
+                !KNOWN_ITERATOR_FUNCTIONS.contains(&identifier.get_name().as_str())
+            }
+            Expression::Field(_)
+            | Expression::Index(_)
+            | Expression::Parenthese(_)
+            | Expression::Table(_)
+            | Expression::TypeCast(_) => true,
+            Expression::Binary(_)
+            | Expression::Call(_)
+            | Expression::False(_)
+            | Expression::Function(_)
+            | Expression::If(_)
+            | Expression::InterpolatedString(_)
+            | Expression::Nil(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+            | Expression::True(_)
+            | Expression::Unary(_)
+            | Expression::VariableArguments(_) => false,
+        }
+    }
+}
+
+impl std::ops::Deref for Processor<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl std::ops::DerefMut for Processor<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for Processor<'_> {
+    fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
+        if generic_for.expressions_len() != 1 {
+            return;
+        }
+
+        if self.is_identifier_used(self.root_name()) {
+            return;
+        }
+
+        let expressions = generic_for.mutate_expressions();
+        let expression = expressions.first_mut().expect("one expression");
+
+        if self.is_generalized_iteration(expression) {
+            let value = std::mem::replace(expression, Expression::nil());
+            *expression = self.iterator_call(value).into();
+        }
+    }
+}
+
+/// A rule that converts Luau generalized iteration (`for k, v in t do`) into
+/// an explicit iterator call (`for k, v in pairs(t) do`).
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ConvertGeneralizedIteration {
+    iterator_function: Option<String>,
+    assume_no_iter_metamethod: bool,
+}
+
+impl ConvertGeneralizedIteration {
+    /// Uses a custom function to wrap iterated values instead of `pairs`. The
+    /// name can be a path of identifiers separated by dots (like `Iter.wrap`).
+    pub fn with_iterator_function(mut self, name: impl Into<String>) -> Self {
+        self.iterator_function = Some(name.into());
+        self
+    }
+
+    /// Assumes that iterated values never define an `__iter` metamethod, which
+    /// makes it safe to replace generalized iteration with `pairs`.
+    pub fn assume_no_iter_metamethod(mut self) -> Self {
+        self.assume_no_iter_metamethod = true;
+        self
+    }
+}
+
+impl FlawlessRule for ConvertGeneralizedIteration {
+    fn flawless_process(&self, block: &mut Block, _: &Context) {
+        let iterator_function = match self.iterator_function.as_deref() {
+            Some(iterator_function) => iterator_function,
+            None if self.assume_no_iter_metamethod => DEFAULT_ITERATOR_FUNCTION,
+            None => {
+                log::warn!(
+                    concat!(
+                        "rule `{}` does not convert anything unless `assume_no_iter_metamethod` ",
+                        "is enabled or an `iterator_function` is provided"
+                    ),
+                    CONVERT_GENERALIZED_ITERATION_RULE_NAME
+                );
+                return;
+            }
+        };
+
+        let mut processor = Processor::new(iterator_function);
+        ScopeVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for ConvertGeneralizedIteration {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "iterator_function" => {
+                    let iterator_function = value.expect_string(&key)?;
+
+                    if !is_valid_iterator_function(&iterator_function) {
+                        return Err(RuleConfigurationError::UnexpectedValue {
+                            property: key,
+                            message: format!(
+                                "invalid iterator function `{}` (expected a path of identifiers separated by dots)",
+                                iterator_function
+                            ),
+                        });
+                    }
+
+                    self.iterator_function = Some(iterator_function);
+                }
+                "assume_no_iter_metamethod" => {
+                    self.assume_no_iter_metamethod = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        CONVERT_GENERALIZED_ITERATION_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if let Some(iterator_function) = &self.iterator_function {
+            properties.insert(
+                "iterator_function".to_owned(),
+                iterator_function.clone().into(),
+            );
+        }
+        if self.assume_no_iter_metamethod {
+            properties.insert("assume_no_iter_metamethod".to_owned(), true.into());
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ConvertGeneralizedIteration {
+        ConvertGeneralizedIteration::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!(rule, @r###""convert_generalized_iteration""###);
+    }
+
+    #[test]
+    fn serialize_rule_with_properties() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_iterator_function("iter")
+                .assume_no_iter_metamethod(),
+        );
+
+        assert_json_snapshot!(rule, @r###"
+        {
+          "rule": "convert_generalized_iteration",
+          "assume_no_iter_metamethod": true,
+          "iterator_function": "iter"
+        }
+        "###);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_generalized_iteration',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+
+    #[test]
+    fn configure_with_invalid_iterator_function_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_generalized_iteration',
+            iterator_function: "Iter..wrap",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'iterator_function': invalid iterator function `Iter..wrap` (expected a path of identifiers separated by dots)"
+        );
+    }
+}
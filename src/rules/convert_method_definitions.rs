@@ -0,0 +1,290 @@
+use std::cell::RefCell;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use crate::nodes::{
+    Block, DoStatement, Expression, FieldExpression, FunctionCall, FunctionStatement,
+    LocalAssignStatement, Prefix, Statement,
+};
+use crate::process::{Evaluator, IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, GeneratedIdentifiers, RuleConfiguration, RuleConfigurationError,
+    RuleProperties, WarningCode,
+};
+
+const RECEIVER_VARIABLE_PREFIX: &str = "__DARKLUA_SELF";
+
+// expressions that can be evaluated twice and still produce the same value
+fn is_reusable_expression(expression: &Expression) -> bool {
+    match expression {
+        Expression::False(_)
+        | Expression::Identifier(_)
+        | Expression::Nil(_)
+        | Expression::Number(_)
+        | Expression::String(_)
+        | Expression::True(_) => true,
+        Expression::Field(field) => is_reusable_prefix(field.get_prefix()),
+        Expression::Index(index) => {
+            is_reusable_prefix(index.get_prefix()) && is_reusable_expression(index.get_index())
+        }
+        Expression::Parenthese(parenthese) => is_reusable_expression(parenthese.inner_expression()),
+        Expression::Binary(_)
+        | Expression::Call(_)
+        | Expression::Function(_)
+        | Expression::If(_)
+        | Expression::InterpolatedString(_)
+        | Expression::Table(_)
+        | Expression::TypeCast(_)
+        | Expression::Unary(_)
+        | Expression::VariableArguments(_) => false,
+    }
+}
+
+fn is_reusable_prefix(prefix: &Prefix) -> bool {
+    match prefix {
+        Prefix::Identifier(_) => true,
+        Prefix::Field(field) => is_reusable_prefix(field.get_prefix()),
+        Prefix::Index(index) => {
+            is_reusable_prefix(index.get_prefix()) && is_reusable_expression(index.get_index())
+        }
+        Prefix::Parenthese(parenthese) => is_reusable_expression(parenthese.inner_expression()),
+        Prefix::Call(_) => false,
+    }
+}
+
+fn into_receiver_expression(prefix: Prefix) -> Expression {
+    match prefix {
+        Prefix::Parenthese(parenthese) => parenthese.into_inner_expression(),
+        prefix => prefix.into(),
+    }
+}
+
+struct Processor<'a> {
+    identifier_tracker: IdentifierTracker,
+    evaluator: Evaluator,
+    generated_identifiers: &'a RefCell<GeneratedIdentifiers>,
+    receiver_variable: Option<String>,
+    also_calls: bool,
+    skipped_call_lines: Vec<Option<usize>>,
+}
+
+impl<'a> Processor<'a> {
+    fn new(also_calls: bool, generated_identifiers: &'a RefCell<GeneratedIdentifiers>) -> Self {
+        Self {
+            identifier_tracker: IdentifierTracker::new(),
+            evaluator: Evaluator::default(),
+            generated_identifiers,
+            receiver_variable: None,
+            also_calls,
+            skipped_call_lines: Vec::new(),
+        }
+    }
+
+    // every temporary is declared in its own `do` block, so they can all share the same name
+    fn receiver_variable(&mut self) -> String {
+        let generated_identifiers = self.generated_identifiers;
+        self.receiver_variable
+            .get_or_insert_with(|| {
+                generated_identifiers
+                    .borrow_mut()
+                    .unique_name(RECEIVER_VARIABLE_PREFIX)
+            })
+            .clone()
+    }
+
+    fn can_reuse_receiver(&self, call: &FunctionCall) -> bool {
+        let prefix = call.get_prefix();
+        is_reusable_prefix(prefix) && !self.evaluator.has_side_effects(&prefix.clone().into())
+    }
+
+    // converts `receiver:method(...)` into `receiver.method(receiver, ...)`
+    fn convert_call(&self, call: &mut FunctionCall, receiver: Prefix) {
+        let method = call
+            .take_method()
+            .expect("method name is expected to exist");
+
+        let self_argument = into_receiver_expression(receiver.clone());
+        *call.mutate_prefix() = FieldExpression::new(receiver, method).into();
+        call.mutate_arguments().insert(0, self_argument);
+    }
+
+    // converts `receiver:method(...)` into
+    // `do local __DARKLUA_SELF = receiver __DARKLUA_SELF.method(__DARKLUA_SELF, ...) end`
+    fn convert_call_statement(&mut self, mut call: FunctionCall) -> Statement {
+        let variable = self.receiver_variable();
+
+        let receiver = mem::replace(call.mutate_prefix(), Prefix::from_name(&variable));
+        self.convert_call(&mut call, Prefix::from_name(&variable));
+
+        DoStatement::new(
+            Block::default()
+                .with_statement(
+                    LocalAssignStatement::from_variable(variable)
+                        .with_value(into_receiver_expression(receiver)),
+                )
+                .with_statement(call),
+        )
+        .into()
+    }
+}
+
+impl Deref for Processor<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for Processor<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for Processor<'_> {
+    fn process_statement(&mut self, statement: &mut Statement) {
+        if !self.also_calls {
+            return;
+        }
+
+        if let Statement::Call(call) = statement {
+            if call.has_method() && !self.can_reuse_receiver(call) {
+                let call = mem::replace(call, FunctionCall::from_name(""));
+                *statement = self.convert_call_statement(call);
+            }
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if !self.also_calls || !call.has_method() {
+            return;
+        }
+
+        if self.can_reuse_receiver(call) {
+            let receiver = call.get_prefix().clone();
+            self.convert_call(call, receiver);
+        } else {
+            self.skipped_call_lines.push(
+                call.get_method()
+                    .and_then(|method| method.get_token())
+                    .and_then(|token| token.get_line_number()),
+            );
+        }
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        function.remove_method();
+    }
+}
+
+pub const CONVERT_METHOD_DEFINITIONS_RULE_NAME: &str = "convert_method_definitions";
+
+/// A rule that converts method definitions (`function Class:method()`) into functions
+/// with an explicit `self` parameter, and optionally converts method calls too.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ConvertMethodDefinitions {
+    also_calls: bool,
+}
+
+impl ConvertMethodDefinitions {
+    /// Also converts method calls (`object:method()`) into regular calls.
+    pub fn with_calls(mut self) -> Self {
+        self.also_calls = true;
+        self
+    }
+}
+
+impl FlawlessRule for ConvertMethodDefinitions {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        if self.also_calls {
+            // the temporaries are declared around the call arguments, so they must not
+            // shadow any name of the block
+            context
+                .generated_identifiers()
+                .borrow_mut()
+                .reserve_block(block);
+        }
+
+        let mut processor = Processor::new(self.also_calls, context.generated_identifiers());
+        ScopeVisitor::visit_block(block, &mut processor);
+
+        for line in processor.skipped_call_lines {
+            context.add_warning(
+                WarningCode::UnconvertedMethodCall,
+                concat!(
+                    "unable to convert method call because its receiver may have side effects ",
+                    "and the call is not a statement"
+                ),
+                Some(context.current_path().to_path_buf()),
+                line,
+            );
+        }
+    }
+}
+
+impl RuleConfiguration for ConvertMethodDefinitions {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "also_calls" => {
+                    self.also_calls = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        CONVERT_METHOD_DEFINITIONS_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.also_calls {
+            properties.insert("also_calls".to_owned(), true.into());
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ConvertMethodDefinitions {
+        ConvertMethodDefinitions::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_convert_method_definitions", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_calls() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_calls());
+
+        assert_json_snapshot!("convert_method_definitions_with_calls", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_method_definitions',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -1,4 +1,11 @@
-use crate::nodes::{FieldExpression, FunctionCall, Identifier, Prefix, StringExpression};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::nodes::{
+    Arguments, Expression, FieldExpression, FunctionCall, Identifier, Prefix, StringExpression,
+};
+use crate::process::utils::is_valid_identifier;
+use crate::rules::require::format_expression;
 
 use super::RobloxIndexStyle;
 
@@ -6,6 +13,8 @@ use super::RobloxIndexStyle;
 pub(crate) struct InstancePath {
     root: InstancePathRoot,
     components: Vec<InstancePathComponent>,
+    // indexes of the child components located with a `FindFirstChild` call
+    find_first_child_components: Vec<usize>,
 }
 
 impl InstancePath {
@@ -13,6 +22,7 @@ impl InstancePath {
         Self {
             root: InstancePathRoot::Root,
             components: Vec::new(),
+            find_first_child_components: Vec::new(),
         }
     }
 
@@ -20,6 +30,7 @@ impl InstancePath {
         Self {
             root: InstancePathRoot::Script,
             components: Vec::new(),
+            find_first_child_components: Vec::new(),
         }
     }
 
@@ -32,6 +43,11 @@ impl InstancePath {
             .push(InstancePathComponent::Child(child_name.into()));
     }
 
+    pub(crate) fn find_first_child(&mut self, child_name: impl Into<String>) {
+        self.find_first_child_components.push(self.components.len());
+        self.child(child_name);
+    }
+
     pub(crate) fn ancestor(&mut self, ancestor_name: impl Into<String>) {
         self.components
             .push(InstancePathComponent::Ancestor(ancestor_name.into()));
@@ -81,6 +97,253 @@ impl InstancePath {
     pub(crate) fn components(&self) -> &[InstancePathComponent] {
         &self.components
     }
+
+    pub(crate) fn is_find_first_child(&self, component_index: usize) -> bool {
+        self.find_first_child_components.contains(&component_index)
+    }
+}
+
+/// Writes the instance path as Luau code, like `game.ReplicatedStorage.Packages.Promise`
+/// or `script.Parent:FindFirstAncestor("Client")`. The text can be parsed back into the
+/// same instance path.
+impl fmt::Display for InstancePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.root {
+            InstancePathRoot::Root => write!(f, "{}", DATAMODEL_NAME)?,
+            InstancePathRoot::Script => write!(f, "{}", SCRIPT_NAME)?,
+        }
+
+        for (index, component) in self.components.iter().enumerate() {
+            match component {
+                InstancePathComponent::Parent => write!(f, ".{}", PARENT_NAME)?,
+                InstancePathComponent::Child(name) if self.is_find_first_child(index) => {
+                    write!(f, ":{}({})", FIND_FIRST_CHILD, quote_name(name))?
+                }
+                InstancePathComponent::Child(name) => {
+                    if is_valid_identifier(name) && name != PARENT_NAME {
+                        write!(f, ".{}", name)?
+                    } else {
+                        write!(f, "[{}]", quote_name(name))?
+                    }
+                }
+                InstancePathComponent::Ancestor(name) => {
+                    write!(f, ":{}({})", FIND_FIRST_ANCESTOR, quote_name(name))?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a textual instance path, like the ones written in configuration files. The path
+/// starts from `game` or `script`, followed by `.Name` or `["Name"]` children, `.Parent`
+/// and method calls (`:GetService("Service")`, `:FindFirstChild("Name")`,
+/// `:WaitForChild("Name")` and `:FindFirstAncestor("Name")`).
+impl FromStr for InstancePath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InstancePathParser::new(s.trim())
+            .parse()
+            .map_err(|err| format!("invalid instance path `{}`: {}", s, err))
+    }
+}
+
+const DATAMODEL_NAME: &str = "game";
+const SCRIPT_NAME: &str = "script";
+const PARENT_NAME: &str = "Parent";
+const GET_SERVICE: &str = "GetService";
+const FIND_FIRST_CHILD: &str = "FindFirstChild";
+const WAIT_FOR_CHILD: &str = "WaitForChild";
+const FIND_FIRST_ANCESTOR: &str = "FindFirstAncestor";
+
+fn quote_name(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+struct InstancePathParser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> InstancePathParser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, position: 0 }
+    }
+
+    fn parse(mut self) -> Result<InstancePath, String> {
+        if self.text.is_empty() {
+            return Err("the path is empty".to_owned());
+        }
+
+        let root = self.read_identifier();
+        let mut instance_path = match root {
+            DATAMODEL_NAME => InstancePath::from_root(),
+            SCRIPT_NAME => InstancePath::from_script(),
+            _ => {
+                return Err(format!(
+                    "expected the path to start with `{}` or `{}` but found `{}`",
+                    DATAMODEL_NAME,
+                    SCRIPT_NAME,
+                    self.segment_from(0)
+                ))
+            }
+        };
+
+        while let Some(next) = self.peek() {
+            let segment_start = self.position;
+            self.position += next.len_utf8();
+
+            match next {
+                '.' => {
+                    let name = self.read_identifier();
+                    if name.is_empty() {
+                        return Err(format!(
+                            "expected a name after `.` in `{}`",
+                            self.segment_from(segment_start)
+                        ));
+                    }
+                    if name == PARENT_NAME {
+                        instance_path.parent();
+                    } else {
+                        instance_path.child(name);
+                    }
+                }
+                '[' => {
+                    let name = self.read_string(segment_start)?;
+                    self.expect(']', segment_start)?;
+                    instance_path.child(name);
+                }
+                ':' => {
+                    let method = self.read_identifier();
+                    self.expect('(', segment_start)?;
+                    let name = self.read_string(segment_start)?;
+                    self.expect(')', segment_start)?;
+
+                    match method {
+                        GET_SERVICE => {
+                            if instance_path.root != InstancePathRoot::Root
+                                || !instance_path.components.is_empty()
+                            {
+                                return Err(format!(
+                                    "`{}` can only be called on `{}` in `{}`",
+                                    GET_SERVICE,
+                                    DATAMODEL_NAME,
+                                    self.segment(segment_start)
+                                ));
+                            }
+                            instance_path.child(name);
+                        }
+                        FIND_FIRST_CHILD => instance_path.find_first_child(name),
+                        WAIT_FOR_CHILD => instance_path.child(name),
+                        FIND_FIRST_ANCESTOR => instance_path.ancestor(name),
+                        _ => {
+                            return Err(format!(
+                                "unknown method `{}` in `{}` (expected `{}`, `{}`, `{}` or `{}`)",
+                                method,
+                                self.segment(segment_start),
+                                GET_SERVICE,
+                                FIND_FIRST_CHILD,
+                                WAIT_FOR_CHILD,
+                                FIND_FIRST_ANCESTOR
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "unexpected character `{}` in `{}`",
+                        next,
+                        self.segment_from(segment_start)
+                    ))
+                }
+            }
+        }
+
+        Ok(instance_path)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    // the text of a segment that was completely read
+    fn segment(&self, start: usize) -> &'a str {
+        &self.text[start..self.position]
+    }
+
+    // the text of a segment, until the next segment
+    fn segment_from(&self, start: usize) -> &'a str {
+        let rest = &self.text[start..];
+        let first_length = rest.chars().next().map(char::len_utf8).unwrap_or(0);
+        let end = rest[first_length..]
+            .find(['.', '[', ':'])
+            .map(|offset| first_length + offset)
+            .unwrap_or(rest.len());
+        &rest[..end]
+    }
+
+    fn read_identifier(&mut self) -> &'a str {
+        let start = self.position;
+        let length = self.text[start..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(self.text.len() - start);
+        self.position += length;
+        &self.text[start..self.position]
+    }
+
+    fn expect(&mut self, expected: char, segment_start: usize) -> Result<(), String> {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            Ok(())
+        } else {
+            Err(format!(
+                "expected `{}` in `{}`",
+                expected,
+                self.segment_from(segment_start)
+            ))
+        }
+    }
+
+    fn read_string(&mut self, segment_start: usize) -> Result<String, String> {
+        let quote = match self.peek() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => {
+                return Err(format!(
+                    "expected a string in `{}`",
+                    self.segment_from(segment_start)
+                ))
+            }
+        };
+        self.position += 1;
+
+        let mut value = String::new();
+        let mut chars = self.text[self.position..].chars();
+
+        while let Some(c) = chars.next() {
+            self.position += c.len_utf8();
+
+            if c == quote {
+                return Ok(value);
+            } else if c == '\\' {
+                match chars.next() {
+                    Some(escaped) => {
+                        self.position += escaped.len_utf8();
+                        value.push(escaped);
+                    }
+                    None => break,
+                }
+            } else {
+                value.push(c);
+            }
+        }
+
+        Err(format!(
+            "unterminated string in `{}`",
+            &self.text[segment_start..]
+        ))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -107,3 +370,190 @@ pub(crate) fn datamodel_identifier() -> Identifier {
 pub(crate) fn get_parent_instance(instance: impl Into<Prefix>) -> Prefix {
     FieldExpression::new(instance.into(), "Parent").into()
 }
+
+/// Reads the name of a child from an index expression like `instance["Child"]`.
+pub(crate) fn read_string_index(index: &Expression) -> Result<String, String> {
+    match index {
+        Expression::String(string) => string
+            .get_string_value()
+            .map(str::to_owned)
+            .ok_or_else(|| "index is not a valid UTF-8 string".to_owned()),
+        Expression::Number(_) => Err(format!(
+            "numeric index `[{}]` is not supported, only string indexes can be resolved",
+            format_expression(index)
+        )),
+        _ => Err(format!(
+            "index `[{}]` is not supported, only string indexes can be resolved",
+            format_expression(index)
+        )),
+    }
+}
+
+/// Reads the first argument of method calls like `instance:FindFirstChild("Child")`.
+pub(crate) fn read_first_string_argument(
+    call: &FunctionCall,
+    method: &str,
+) -> Result<String, String> {
+    let value = match call.get_arguments() {
+        Arguments::String(string) => string.get_string_value(),
+        Arguments::Tuple(tuple) => match tuple.iter_values().next() {
+            Some(Expression::String(string)) => string.get_string_value(),
+            _ => None,
+        },
+        Arguments::Table(_) => None,
+    };
+
+    value
+        .map(str::to_owned)
+        .ok_or_else(|| format!("first argument of `{}` must be a string", method))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(text: &str) -> InstancePath {
+        text.parse()
+            .unwrap_or_else(|err| panic!("unable to parse `{}`: {}", text, err))
+    }
+
+    fn parse_error(text: &str) -> String {
+        text.parse::<InstancePath>()
+            .expect_err("parsing should fail")
+    }
+
+    macro_rules! round_trip {
+        ($($name:ident => $text:literal),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    pretty_assertions::assert_eq!(parse($text).to_string(), $text);
+                }
+            )*
+        };
+    }
+
+    round_trip!(
+        round_trip_datamodel => "game",
+        round_trip_script => "script",
+        round_trip_service_child => "game.ReplicatedStorage.Packages.Promise",
+        round_trip_script_parent => "script.Parent.Parent.value",
+        round_trip_find_first_ancestor => "script:FindFirstAncestor(\"Client\").Modules",
+        round_trip_find_first_child => "script.Parent:FindFirstChild(\"value\")",
+        round_trip_child_with_space => "game.Workspace[\"My Model\"]",
+        round_trip_child_named_parent => "script[\"Parent\"]",
+        round_trip_child_with_quote => "script[\"a\\\"b\"]",
+        round_trip_keyword_child => "script[\"end\"]",
+    );
+
+    #[test]
+    fn parse_service_path() {
+        let mut expected = InstancePath::from_root();
+        expected.child("ReplicatedStorage");
+        expected.child("Packages");
+
+        assert_eq!(parse("game.ReplicatedStorage.Packages"), expected);
+    }
+
+    #[test]
+    fn parse_get_service() {
+        assert_eq!(
+            parse("game:GetService('ReplicatedStorage').Packages"),
+            parse("game.ReplicatedStorage.Packages")
+        );
+    }
+
+    #[test]
+    fn parse_wait_for_child_as_child() {
+        assert_eq!(
+            parse("script.Parent:WaitForChild(\"value\")"),
+            parse("script.Parent.value")
+        );
+    }
+
+    #[test]
+    fn parse_script_ancestor() {
+        let mut expected = InstancePath::from_script();
+        expected.parent();
+        expected.ancestor("Client");
+
+        assert_eq!(parse("script.Parent:FindFirstAncestor('Client')"), expected);
+    }
+
+    #[test]
+    fn parse_trims_whitespaces() {
+        assert_eq!(parse("  game.Workspace\n"), parse("game.Workspace"));
+    }
+
+    #[test]
+    fn parse_empty_path_errors() {
+        assert_eq!(
+            parse_error(""),
+            "invalid instance path ``: the path is empty"
+        );
+    }
+
+    #[test]
+    fn parse_unknown_root_errors() {
+        assert_eq!(
+            parse_error("Workspace.Part"),
+            "invalid instance path `Workspace.Part`: expected the path to start with `game` or `script` but found `Workspace`"
+        );
+    }
+
+    #[test]
+    fn parse_missing_name_errors() {
+        assert_eq!(
+            parse_error("game..Workspace"),
+            "invalid instance path `game..Workspace`: expected a name after `.` in `.`"
+        );
+    }
+
+    #[test]
+    fn parse_unknown_method_errors() {
+        assert_eq!(
+            parse_error("script:FindFirstDescendant(\"a\").b"),
+            "invalid instance path `script:FindFirstDescendant(\"a\").b`: unknown method `FindFirstDescendant` in `:FindFirstDescendant(\"a\")` (expected `GetService`, `FindFirstChild`, `WaitForChild` or `FindFirstAncestor`)"
+        );
+    }
+
+    #[test]
+    fn parse_get_service_on_script_errors() {
+        assert_eq!(
+            parse_error("script:GetService(\"Players\")"),
+            "invalid instance path `script:GetService(\"Players\")`: `GetService` can only be called on `game` in `:GetService(\"Players\")`"
+        );
+    }
+
+    #[test]
+    fn parse_method_without_string_errors() {
+        assert_eq!(
+            parse_error("script:FindFirstChild(value)"),
+            "invalid instance path `script:FindFirstChild(value)`: expected a string in `:FindFirstChild(value)`"
+        );
+    }
+
+    #[test]
+    fn parse_unterminated_string_errors() {
+        assert_eq!(
+            parse_error("game[\"Workspace"),
+            "invalid instance path `game[\"Workspace`: unterminated string in `[\"Workspace`"
+        );
+    }
+
+    #[test]
+    fn parse_missing_closing_bracket_errors() {
+        assert_eq!(
+            parse_error("game[\"Workspace\".Part"),
+            "invalid instance path `game[\"Workspace\".Part`: expected `]` in `[\"Workspace\"`"
+        );
+    }
+
+    #[test]
+    fn parse_unexpected_character_errors() {
+        assert_eq!(
+            parse_error("game.Workspace/Part"),
+            "invalid instance path `game.Workspace/Part`: unexpected character `/` in `/Part`"
+        );
+    }
+}
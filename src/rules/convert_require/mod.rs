@@ -1,25 +1,35 @@
 mod instance_path;
 mod roblox_index_style;
 mod roblox_require_mode;
+mod rojo_project;
 mod rojo_sourcemap;
+mod sourcemap_command;
 
 // Re-exports used by bundling Roblox require mode
 pub(crate) use instance_path::{InstancePath, InstancePathComponent, InstancePathRoot};
-pub(crate) use rojo_sourcemap::RojoSourcemap;
-pub(crate) use instance_path::{datamodel_identifier, get_parent_instance};
+pub(crate) use rojo_sourcemap::{InstancePathResolutionError, RojoSourcemap};
+pub(crate) use sourcemap_command::SourcemapCommandState;
+pub(crate) use instance_path::{
+    datamodel_identifier, get_parent_instance, read_first_string_argument, read_string_index,
+};
 
 use serde::{Deserialize, Serialize};
 
 use crate::frontend::DarkluaResult;
 use crate::nodes::{Arguments, Block, FunctionCall};
 use crate::process::{DefaultVisitor, IdentifierTracker, NodeProcessor, NodeVisitor};
-use crate::rules::require::is_require_call;
+use crate::rules::require::{
+    find_arguments_quote, is_require_call, match_protected_require_call, require_call_line,
+};
 use crate::rules::{Context, RuleConfiguration, RuleConfigurationError, RuleProperties};
 
 pub use roblox_index_style::RobloxIndexStyle;
 pub use roblox_require_mode::RobloxRequireMode;
 
-use super::{verify_required_properties, PathRequireMode, Rule, RuleProcessResult};
+use super::{
+    verify_required_properties, AutoRequireMode, DetectedRequireMode, PathRequireMode, QuoteStyle,
+    Rule, RuleProcessResult,
+};
 
 use std::ffi::OsStr;
 use std::ops::{Deref, DerefMut};
@@ -34,9 +44,22 @@ pub enum RequireMode {
     Path(PathRequireMode),
     /// Handles requires using Roblox's instance-based require system
     Roblox(RobloxRequireMode),
+    /// Detects the path or Roblox require mode from the require calls of each file
+    Auto(Box<AutoRequireMode<RobloxRequireMode>>),
 }
 
 impl RequireMode {
+    /// Replaces the `auto` require mode with the mode detected from the given block.
+    fn resolve(&self, block: &mut Block, context: &Context) -> Self {
+        match self {
+            RequireMode::Auto(auto_mode) => match auto_mode.detect(block, context) {
+                DetectedRequireMode::Path => RequireMode::Path(auto_mode.path_mode().clone()),
+                DetectedRequireMode::Roblox => RequireMode::Roblox(auto_mode.roblox_mode().clone()),
+            },
+            _ => self.clone(),
+        }
+    }
+
     fn find_require(
         &self,
         call: &FunctionCall,
@@ -45,6 +68,9 @@ impl RequireMode {
     ) -> DarkluaResult<Option<PathBuf>> {
         match self {
             RequireMode::Path(path_mode) => path_mode.find_require(call, context),
+            // the `auto` mode is resolved before processing, until then it behaves like
+            // its path require mode
+            RequireMode::Auto(auto_mode) => auto_mode.path_mode().find_require(call, context),
             RequireMode::Roblox(roblox_mode) => {
                 roblox_mode.find_require(call, context, current_block)
             }
@@ -59,15 +85,30 @@ impl RequireMode {
     ) -> DarkluaResult<Option<Arguments>> {
         match self {
             RequireMode::Path(path_mode) => path_mode.generate_require(path, current_mode, context),
+            RequireMode::Auto(auto_mode) => {
+                auto_mode
+                    .path_mode()
+                    .generate_require(path, current_mode, context)
+            }
             RequireMode::Roblox(roblox_mode) => {
                 roblox_mode.generate_require(path, current_mode, context)
             }
         }
     }
 
+    fn quote_style(&self) -> Option<QuoteStyle> {
+        match self {
+            RequireMode::Path(path_mode) => path_mode.quote_style(),
+            RequireMode::Auto(auto_mode) => auto_mode.path_mode().quote_style(),
+            RequireMode::Roblox(_) => None,
+        }
+    }
+
     fn is_module_folder_name(&self, path: &Path) -> bool {
         match self {
             RequireMode::Path(path_mode) => path_mode.is_module_folder_name(path),
+            RequireMode::Auto(auto_mode) => auto_mode.path_mode().is_module_folder_name(path),
+            // in Roblox mode, module folder is always `init`
             RequireMode::Roblox(_roblox_mode) => {
                 matches!(path.file_stem().and_then(OsStr::to_str), Some("init"))
             }
@@ -78,6 +119,7 @@ impl RequireMode {
         match self {
             RequireMode::Roblox(roblox_mode) => roblox_mode.initialize(context),
             RequireMode::Path(path_mode) => path_mode.initialize(context),
+            RequireMode::Auto(_) => Ok(()),
         }
     }
 }
@@ -89,6 +131,7 @@ impl FromStr for RequireMode {
         Ok(match s {
             "path" => Self::Path(Default::default()),
             "roblox" => Self::Roblox(Default::default()),
+            "auto" => Self::Auto(Default::default()),
             _ => return Err(format!("invalid require mode name `{}`", s)),
         })
     }
@@ -140,10 +183,15 @@ impl<'a> RequireConverter<'a> {
         {
             log::trace!("found require path `{}`", require_path.display());
 
-            if let Some(new_arguments) =
+            if let Some(mut new_arguments) =
                 self.target
                     .generate_require(&require_path, &self.current, self.context)?
             {
+                if let Some(quote_style) = self.target.quote_style() {
+                    let original_quote =
+                        find_arguments_quote(call.get_arguments(), self.context.original_code());
+                    quote_style.apply_to_arguments(&mut new_arguments, original_quote);
+                }
                 call.set_arguments(new_arguments);
             }
         }
@@ -153,6 +201,12 @@ impl<'a> RequireConverter<'a> {
 
 impl NodeProcessor for RequireConverter<'_> {
     fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if require_call_line(call)
+            .is_some_and(|line| self.context.is_line_disabled(CONVERT_REQUIRE_RULE_NAME, line))
+        {
+            return;
+        }
+
         if is_require_call(call, self) {
             match self.try_require_conversion(call) {
                 Ok(()) => {}
@@ -160,10 +214,29 @@ impl NodeProcessor for RequireConverter<'_> {
                     log::warn!("{}", err);
                 }
             }
+        } else if let Some(protected_call) = match_protected_require_call(
+            call,
+            |name| !self.is_identifier_used(name),
+            |name| name == REQUIRE_FUNCTION_IDENTIFIER && !self.is_identifier_used(name),
+        ) {
+            let mut require_call = protected_call.require_call().clone();
+            match self.try_require_conversion(&mut require_call) {
+                Ok(()) => {
+                    if require_call.get_arguments() != protected_call.require_call().get_arguments()
+                    {
+                        *call = protected_call.with_require_call(&require_call);
+                    }
+                }
+                Err(err) => {
+                    log::warn!("{}", err);
+                }
+            }
         }
     }
 }
 
+const REQUIRE_FUNCTION_IDENTIFIER: &str = "require";
+
 pub const CONVERT_REQUIRE_RULE_NAME: &str = "convert_require";
 
 /// A rule that converts require calls between environments
@@ -184,7 +257,7 @@ impl Default for ConvertRequire {
 
 impl Rule for ConvertRequire {
     fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
-        let mut current_mode = self.current.clone();
+        let mut current_mode = self.current.resolve(block, context);
         current_mode
             .initialize(context)
             .map_err(|err| err.to_string())?;
@@ -211,7 +284,18 @@ impl RuleConfiguration for ConvertRequire {
                     self.current = value.expect_require_mode(&key)?;
                 }
                 "target" => {
-                    self.target = value.expect_require_mode(&key)?;
+                    let target = value.expect_require_mode(&key)?;
+                    if matches!(target, RequireMode::Auto(_)) {
+                        return Err(RuleConfigurationError::UnexpectedValue {
+                            property: key,
+                            message: concat!(
+                                "the `auto` require mode can only be used ",
+                                "for the `current` require mode"
+                            )
+                            .to_owned(),
+                        });
+                    }
+                    self.target = target;
                 }
                 _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
             }
@@ -262,6 +346,21 @@ mod test {
         );
     }
 
+    #[test]
+    fn configure_with_auto_target_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_require',
+            current: 'path',
+            target: 'auto',
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'target': the `auto` require mode can only be used for the `current` require mode"
+        );
+    }
+
     #[test]
     fn configure_with_extra_field_error() {
         let result = json5::from_str::<Box<dyn Rule>>(
@@ -3,14 +3,27 @@ use serde::{Deserialize, Serialize};
 use crate::{
     frontend::DarkluaResult,
     nodes::{Arguments, Expression, FunctionCall, Prefix, Statement},
-    rules::{convert_require::rojo_sourcemap::RojoSourcemap, Context},
+    rules::{
+        bundle::CaseSensitivePaths,
+        convert_require::rojo_sourcemap::RojoSourcemap,
+        convert_require::sourcemap_command::SourcemapCommandState,
+        require::{
+            format_expression, format_require_argument, match_require_argument, require_call_line,
+            RequireArgument,
+        },
+        Context, WarningCode,
+    },
     utils, DarkluaError,
 };
 
+use std::cell::Cell;
 use std::path::{Component, Path, PathBuf};
 
 use super::{
-    instance_path::{get_parent_instance, script_identifier, InstancePath},
+    instance_path::{
+        get_parent_instance, read_first_string_argument, read_string_index, script_identifier,
+        InstancePath, InstancePathRoot,
+    },
     RequireMode, RobloxIndexStyle,
 };
 
@@ -18,10 +31,16 @@ use super::{
 #[serde(deny_unknown_fields, rename_all = "snake_case")]
 pub struct RobloxRequireMode {
     rojo_sourcemap: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rojo_sourcemap_command: Option<Vec<String>>,
     #[serde(default, deserialize_with = "crate::utils::string_or_struct")]
     indexing_style: RobloxIndexStyle,
     #[serde(skip)]
     cached_sourcemap: Option<RojoSourcemap>,
+    #[serde(skip)]
+    reported_missing_file: Cell<bool>,
+    #[serde(skip)]
+    sourcemap_command_state: SourcemapCommandState,
 }
 
 impl RobloxRequireMode {
@@ -31,17 +50,21 @@ impl RobloxRequireMode {
             .as_ref()
             .map(|rojo_sourcemap_path| context.project_location().join(rojo_sourcemap_path))
         {
+            if let Some(command) = &self.rojo_sourcemap_command {
+                self.sourcemap_command_state
+                    .update_sourcemap(command, rojo_sourcemap_path, context)
+                    .map_err(|err| err.context("while initializing Roblox require mode"))?;
+            }
+
             context.add_file_dependency(rojo_sourcemap_path.clone());
 
             let sourcemap_parent_location = get_relative_parent_path(rojo_sourcemap_path);
-            let sourcemap = RojoSourcemap::parse(
-                &context
-                    .resources()
-                    .get(rojo_sourcemap_path)
-                    .map_err(|err| {
-                        DarkluaError::from(err).context("while initializing Roblox require mode")
-                    })?,
+            let sourcemap = RojoSourcemap::load(
+                &RojoSourcemap::read_content(rojo_sourcemap_path, context.resources())
+                    .map_err(|err| err.context("while initializing Roblox require mode"))?,
                 sourcemap_parent_location,
+                context.resources(),
+                CaseSensitivePaths::default(),
             )
             .map_err(|err| {
                 err.context(format!(
@@ -51,6 +74,7 @@ impl RobloxRequireMode {
             })?;
             self.cached_sourcemap = Some(sourcemap);
         }
+        self.reported_missing_file.set(false);
         Ok(())
     }
 
@@ -60,27 +84,55 @@ impl RobloxRequireMode {
         context: &Context,
         current_block: &crate::nodes::Block,
     ) -> DarkluaResult<Option<PathBuf>> {
-        let instance_path = match call.get_arguments() {
-            Arguments::Tuple(tuple) if tuple.len() == 1 => {
-                let expr = tuple.iter_values().next().unwrap();
+        let instance_path = match match_require_argument(call) {
+            Some(RequireArgument::Expression(expr)) => {
                 self.parse_expression_to_instance_path(expr, context, current_block)
             }
-            _ => None,
+            _ => Err("expected a single instance argument".to_owned()),
         };
 
-        let Some(instance_path) = instance_path else {
-            return Ok(None);
+        let instance_path = match instance_path {
+            Ok(instance_path) => instance_path,
+            Err(reason) => {
+                let message = format!(
+                    "could not resolve instance path of `{}`: {}",
+                    format_require_argument(call),
+                    reason
+                );
+                log::warn!("{} [from `{}`]", message, context.current_path().display());
+                context.add_warning(
+                    WarningCode::UnresolvedRequire,
+                    message,
+                    Some(context.current_path().to_path_buf()),
+                    require_call_line(call),
+                );
+                return Ok(None);
+            }
         };
 
         if let Some(sourcemap) = &self.cached_sourcemap {
             let source_path = utils::normalize_path(context.current_path());
-            if let Some(target_file) =
-                sourcemap.get_file_from_instance_path(&source_path, &instance_path)
-            {
-                return Ok(Some(target_file));
+            match sourcemap.get_file_from_instance_path(&source_path, &instance_path) {
+                Ok(target_file) => {
+                    // changes to the meta files of the instance can change the conversion
+                    for file_path in
+                        sourcemap.get_non_lua_files_from_instance_path(&source_path, &instance_path)
+                    {
+                        context.add_file_dependency(file_path);
+                    }
+                    Ok(Some(target_file))
+                }
+                Err(err) => {
+                    if matches!(instance_path.root(), InstancePathRoot::Script) {
+                        self.report_missing_file(sourcemap, &source_path, context);
+                    }
+                    log::debug!(
+                        "unable to resolve Roblox instance path to file using sourcemap: {}",
+                        err
+                    );
+                    Ok(None)
+                }
             }
-            log::debug!("unable to resolve Roblox instance path to file using sourcemap");
-            Ok(None)
         } else {
             Err(DarkluaError::custom(
                 "Roblox require conversion requires a Rojo sourcemap (missing `rojo_sourcemap`)",
@@ -88,18 +140,43 @@ impl RobloxRequireMode {
         }
     }
 
+    // reports only once per file that the file is missing from the sourcemap, instead of
+    // reporting each require relative to the script
+    fn report_missing_file(
+        &self,
+        sourcemap: &RojoSourcemap,
+        source_path: &Path,
+        context: &Context,
+    ) {
+        if self.reported_missing_file.get() {
+            return;
+        }
+        if let Some(message) = self
+            .rojo_sourcemap
+            .as_ref()
+            .and_then(|sourcemap_path| sourcemap.missing_file_message(source_path, sourcemap_path))
+        {
+            self.reported_missing_file.set(true);
+            log::warn!("{}", message);
+            context.add_warning(
+                WarningCode::FileNotInSourcemap,
+                message,
+                Some(context.current_path().to_path_buf()),
+                None,
+            );
+        }
+    }
+
     fn parse_expression_to_instance_path(
         &self,
         expression: &Expression,
         context: &Context,
         current_block: &crate::nodes::Block,
-    ) -> Option<InstancePath> {
+    ) -> Result<InstancePath, String> {
         match expression {
-            Expression::Identifier(id) => match id.get_name().as_str() {
-                "script" => Some(InstancePath::from_script()),
-                "game" => Some(InstancePath::from_root()),
-                other => self.resolve_identifier_to_instance_path(other, context, current_block),
-            },
+            Expression::Identifier(id) => {
+                self.parse_identifier_to_instance_path(id.get_name(), context, current_block)
+            }
             Expression::Field(field) => {
                 let mut base =
                     self.parse_prefix_to_instance_path(field.get_prefix(), context, current_block)?;
@@ -109,17 +186,14 @@ impl RobloxRequireMode {
                 } else {
                     base.child(name);
                 }
-                Some(base)
+                Ok(base)
             }
             Expression::Index(index) => {
+                let child_name = read_string_index(index.get_index())?;
                 let mut base =
                     self.parse_prefix_to_instance_path(index.get_prefix(), context, current_block)?;
-                let child_name = match index.get_index() {
-                    Expression::String(s) => s.get_string_value()?.to_string(),
-                    _ => return None,
-                };
                 base.child(child_name);
-                Some(base)
+                Ok(base)
             }
             Expression::Call(call) => {
                 self.parse_call_to_instance_path(call, context, current_block)
@@ -129,7 +203,10 @@ impl RobloxRequireMode {
                 context,
                 current_block,
             ),
-            _ => None,
+            _ => Err(format!(
+                "unsupported expression `{}`",
+                format_expression(expression)
+            )),
         }
     }
 
@@ -138,13 +215,11 @@ impl RobloxRequireMode {
         prefix: &Prefix,
         context: &Context,
         current_block: &crate::nodes::Block,
-    ) -> Option<InstancePath> {
+    ) -> Result<InstancePath, String> {
         match prefix {
-            Prefix::Identifier(id) => match id.get_name().as_str() {
-                "script" => Some(InstancePath::from_script()),
-                "game" => Some(InstancePath::from_root()),
-                other => self.resolve_identifier_to_instance_path(other, context, current_block),
-            },
+            Prefix::Identifier(id) => {
+                self.parse_identifier_to_instance_path(id.get_name(), context, current_block)
+            }
             Prefix::Field(field) => {
                 let mut base =
                     self.parse_prefix_to_instance_path(field.get_prefix(), context, current_block)?;
@@ -154,17 +229,14 @@ impl RobloxRequireMode {
                 } else {
                     base.child(name);
                 }
-                Some(base)
+                Ok(base)
             }
             Prefix::Index(index) => {
+                let child_name = read_string_index(index.get_index())?;
                 let mut base =
                     self.parse_prefix_to_instance_path(index.get_prefix(), context, current_block)?;
-                let child_name = match index.get_index() {
-                    Expression::String(s) => s.get_string_value()?.to_string(),
-                    _ => return None,
-                };
                 base.child(child_name);
-                Some(base)
+                Ok(base)
             }
             Prefix::Call(call) => self.parse_call_to_instance_path(call, context, current_block),
             Prefix::Parenthese(paren) => self.parse_expression_to_instance_path(
@@ -175,69 +247,77 @@ impl RobloxRequireMode {
         }
     }
 
+    fn parse_identifier_to_instance_path(
+        &self,
+        name: &str,
+        context: &Context,
+        current_block: &crate::nodes::Block,
+    ) -> Result<InstancePath, String> {
+        match name {
+            "script" => Ok(InstancePath::from_script()),
+            "game" => Ok(InstancePath::from_root()),
+            other => self.resolve_identifier_to_instance_path(other, context, current_block),
+        }
+    }
+
     fn parse_call_to_instance_path(
         &self,
         call: &FunctionCall,
         context: &Context,
         current_block: &crate::nodes::Block,
-    ) -> Option<InstancePath> {
-        let method = call.get_method().map(|m| m.get_name().as_str());
+    ) -> Result<InstancePath, String> {
+        let Some(method) = call.get_method().map(|m| m.get_name().as_str()) else {
+            return Err("unsupported function call".to_owned());
+        };
         let mut base =
             self.parse_prefix_to_instance_path(call.get_prefix(), context, current_block)?;
         match method {
-            Some("GetService") => {
-                let child = self.read_first_string_argument(call)?;
-                base.child(child);
-                Some(base)
-            }
-            Some("WaitForChild") | Some("FindFirstChild") => {
-                let child = self.read_first_string_argument(call)?;
+            "GetService" | "WaitForChild" | "FindFirstChild" => {
+                let child = read_first_string_argument(call, method)?;
                 base.child(child);
-                Some(base)
+                Ok(base)
             }
-            Some("FindFirstAncestor") => {
-                let ancestor = self.read_first_string_argument(call)?;
+            "FindFirstAncestor" => {
+                let ancestor = read_first_string_argument(call, method)?;
                 base.ancestor(ancestor);
-                Some(base)
+                Ok(base)
             }
-            _ => None,
-        }
-    }
-
-    fn read_first_string_argument(&self, call: &FunctionCall) -> Option<String> {
-        match call.get_arguments() {
-            Arguments::String(s) => s.get_string_value().map(|s| s.to_string()),
-            Arguments::Tuple(tuple) if tuple.len() >= 1 => {
-                match tuple.iter_values().next().unwrap() {
-                    Expression::String(s) => s.get_string_value().map(|s| s.to_string()),
-                    _ => None,
-                }
-            }
-            _ => None,
+            _ => Err(format!("unsupported method `{}`", method)),
         }
     }
 
     fn resolve_identifier_to_instance_path(
         &self,
         name: &str,
-        _context: &Context,
+        context: &Context,
         current_block: &crate::nodes::Block,
-    ) -> Option<InstancePath> {
+    ) -> Result<InstancePath, String> {
+        let mut last_error = None;
         // find a local assignment defining this identifier and parse its value
         for statement in current_block.iter_statements() {
             if let Statement::LocalAssign(local) = statement {
                 for (var, value) in local.iter_variables().zip(local.iter_values()) {
                     if var.get_identifier().get_name() == name {
-                        if let Some(path) =
-                            self.parse_expression_to_instance_path(value, _context, current_block)
+                        match self.parse_expression_to_instance_path(value, context, current_block)
                         {
-                            return Some(path);
+                            Ok(path) => return Ok(path),
+                            Err(err) => {
+                                last_error = Some(format!(
+                                    "unable to resolve local variable `{}`: {}",
+                                    name, err
+                                ));
+                            }
                         }
                     }
                 }
             }
         }
-        None
+        Err(last_error.unwrap_or_else(|| {
+            format!(
+                "unable to find a local variable `{}` referring to an instance",
+                name
+            )
+        }))
     }
 
     pub(crate) fn generate_require(
@@ -298,20 +378,8 @@ impl RobloxRequireMode {
                 relative_require_path.display()
             );
 
-            let require_is_module_folder_name = match current {
-                RequireMode::Path(path_mode) => {
-                    path_mode.is_module_folder_name(&relative_require_path)
-                }
-                RequireMode::Roblox(_roblox_mode) => {
-                    // in Roblox mode, module folder is always `init`
-                    matches!(
-                        relative_require_path
-                            .file_stem()
-                            .and_then(std::ffi::OsStr::to_str),
-                        Some("init")
-                    )
-                }
-            };
+            let require_is_module_folder_name =
+                current.is_module_folder_name(&relative_require_path);
             // if we are about to make a require to a path like `./x/y/z/init.lua`
             // we can pop the last component from the path
             let take_components = relative_require_path
@@ -321,15 +389,7 @@ impl RobloxRequireMode {
             let mut path_components = relative_require_path.components().take(take_components);
 
             if let Some(first_component) = path_components.next() {
-                let source_is_module_folder_name = match current {
-                    RequireMode::Path(path_mode) => path_mode.is_module_folder_name(&source_path),
-                    RequireMode::Roblox(_roblox_mode) => {
-                        matches!(
-                            source_path.file_stem().and_then(std::ffi::OsStr::to_str),
-                            Some("init")
-                        )
-                    }
-                };
+                let source_is_module_folder_name = current.is_module_folder_name(&source_path);
 
                 let instance_path = path_components.try_fold(
                     match first_component {
@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::frontend::{DarkluaResult, Resources};
+use crate::utils::normalize_path;
+use crate::DarkluaError;
+
+use super::rojo_sourcemap::RojoSourcemapNode;
+
+const INIT_NAME: &str = "init";
+
+/// Returns true if the given JSON content is a Rojo project file (which defines a `tree`)
+/// rather than a sourcemap generated by Rojo.
+pub(super) fn is_project_file(content: &Value) -> bool {
+    content.get("tree").is_some()
+}
+
+/// Builds the instance tree described by a Rojo project file. The directories referenced
+/// by `$path` entries are read from the resources, so that the resulting tree matches the
+/// sourcemap that Rojo would generate. File paths are joined to the given location.
+pub(super) fn build_project_tree(
+    content: Value,
+    relative_to: &Path,
+    resources: &Resources,
+) -> DarkluaResult<RojoSourcemapNode> {
+    let mut project = match content {
+        Value::Object(project) => project,
+        _ => return Err(DarkluaError::custom("Rojo project file must be an object")),
+    };
+
+    let name = match project.remove("name") {
+        Some(Value::String(name)) => name,
+        _ => {
+            return Err(DarkluaError::custom(
+                "Rojo project file is missing its `name` field",
+            ))
+        }
+    };
+
+    let tree = project
+        .get("tree")
+        .and_then(Value::as_object)
+        .ok_or_else(|| DarkluaError::custom("Rojo project `tree` field must be an object"))?;
+
+    ProjectTreeBuilder {
+        relative_to,
+        resources,
+    }
+    .build_node(name, tree)
+}
+
+struct ProjectTreeBuilder<'a> {
+    relative_to: &'a Path,
+    resources: &'a Resources,
+}
+
+impl ProjectTreeBuilder<'_> {
+    fn build_node(
+        &self,
+        name: String,
+        node: &Map<String, Value>,
+    ) -> DarkluaResult<RojoSourcemapNode> {
+        let class_name = match node.get("$className") {
+            Some(Value::String(class_name)) => Some(class_name.to_owned()),
+            Some(_) => {
+                return Err(DarkluaError::custom(format!(
+                    "`$className` of Rojo project node `{}` must be a string",
+                    name
+                )))
+            }
+            None => None,
+        };
+
+        let mut result = match self.read_node_path(&name, node)? {
+            Some(path) => {
+                let mut result = self.build_path_node(name.clone(), &path)?;
+                if let Some(class_name) = class_name {
+                    result.set_class_name(class_name);
+                }
+                result
+            }
+            // like Rojo, a node without a class name takes the class of the service
+            // with the same name (like `ReplicatedStorage`)
+            None => {
+                let class_name = class_name.unwrap_or_else(|| name.clone());
+                RojoSourcemapNode::new(name, class_name)
+            }
+        };
+
+        for (child_name, child) in node.iter().filter(|(key, _)| !key.starts_with('$')) {
+            let child = child.as_object().ok_or_else(|| {
+                DarkluaError::custom(format!(
+                    "Rojo project node `{}` must be an object",
+                    child_name
+                ))
+            })?;
+            result.insert_child(self.build_node(child_name.to_owned(), child)?);
+        }
+
+        Ok(result)
+    }
+
+    fn read_node_path(
+        &self,
+        name: &str,
+        node: &Map<String, Value>,
+    ) -> DarkluaResult<Option<PathBuf>> {
+        let (path, optional) = match node.get("$path") {
+            Some(Value::String(path)) => (path, false),
+            Some(Value::Object(path)) => match path.get("optional") {
+                Some(Value::String(path)) => (path, true),
+                _ => {
+                    return Err(DarkluaError::custom(format!(
+                        "`$path` of Rojo project node `{}` must define an `optional` path",
+                        name
+                    )))
+                }
+            },
+            Some(_) => {
+                return Err(DarkluaError::custom(format!(
+                    "`$path` of Rojo project node `{}` must be a string",
+                    name
+                )))
+            }
+            None => return Ok(None),
+        };
+
+        let location = self.relative_to.join(path);
+
+        if self.resources.exists(&location)? || self.resources.is_directory(&location)? {
+            Ok(Some(location))
+        } else if optional {
+            Ok(None)
+        } else {
+            Err(DarkluaError::custom(format!(
+                "unable to find `{}` referenced by the `$path` of Rojo project node `{}`",
+                location.display(),
+                name
+            )))
+        }
+    }
+
+    fn build_path_node(&self, name: String, location: &Path) -> DarkluaResult<RojoSourcemapNode> {
+        if self.resources.is_file(location)? {
+            let class_name = script_file_kind(location)
+                .map(|(_, class_name)| class_name)
+                .unwrap_or("ModuleScript");
+
+            return Ok(RojoSourcemapNode::new(name, class_name).with_file_path(location));
+        }
+
+        let location = normalize_path(location);
+        let mut directory = DirectoryEntries::default();
+        for file in self.resources.walk(&location) {
+            let file = normalize_path(file);
+            if let Ok(relative_path) = file.strip_prefix(&location) {
+                directory.insert(relative_path, file.clone());
+            }
+        }
+
+        Ok(directory.into_node(name))
+    }
+}
+
+#[derive(Debug, Default)]
+struct DirectoryEntries {
+    files: Vec<PathBuf>,
+    directories: BTreeMap<String, DirectoryEntries>,
+}
+
+impl DirectoryEntries {
+    fn insert(&mut self, relative_path: &Path, file: PathBuf) {
+        let mut components = relative_path.iter();
+        let file_name = components.next_back();
+        let mut entries = self;
+
+        for component in components {
+            entries = entries
+                .directories
+                .entry(component.to_string_lossy().into_owned())
+                .or_default();
+        }
+
+        if file_name.is_some() {
+            entries.files.push(file);
+        }
+    }
+
+    fn into_node(self, name: String) -> RojoSourcemapNode {
+        let mut scripts: Vec<_> = self
+            .files
+            .into_iter()
+            .filter_map(|file| {
+                let (script_name, class_name) = script_file_kind(&file)?;
+                let script_name = script_name.to_owned();
+                Some((script_name, class_name, file))
+            })
+            .collect();
+        scripts.sort();
+
+        let init_script = scripts
+            .iter()
+            .position(|(script_name, _, _)| script_name == INIT_NAME)
+            .map(|index| scripts.remove(index));
+
+        // a directory with an init script becomes that script
+        let mut node = match init_script {
+            Some((_, class_name, file)) => {
+                RojoSourcemapNode::new(name, class_name).with_file_path(file)
+            }
+            None => RojoSourcemapNode::new(name, "Folder"),
+        };
+
+        for (directory_name, entries) in self.directories {
+            node.insert_child(entries.into_node(directory_name));
+        }
+
+        for (script_name, class_name, file) in scripts {
+            node.insert_child(RojoSourcemapNode::new(script_name, class_name).with_file_path(file));
+        }
+
+        node
+    }
+}
+
+// returns the instance name and class name of a Lua or Luau file
+fn script_file_kind(path: &Path) -> Option<(&str, &'static str)> {
+    match path.extension().and_then(OsStr::to_str) {
+        Some("lua") | Some("luau") => {}
+        _ => return None,
+    }
+
+    let stem = path.file_stem().and_then(OsStr::to_str)?;
+
+    Some(if let Some(name) = stem.strip_suffix(".server") {
+        (name, "Script")
+    } else if let Some(name) = stem.strip_suffix(".client") {
+        (name, "LocalScript")
+    } else {
+        (stem, "ModuleScript")
+    })
+}
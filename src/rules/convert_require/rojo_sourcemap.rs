@@ -1,16 +1,24 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use crate::frontend::Resources;
+use crate::rules::bundle::CaseSensitivePaths;
 use crate::{utils, DarkluaError};
 
 use super::instance_path::{InstancePath, InstancePathComponent, InstancePathRoot};
+use super::rojo_project;
 
 type NodeId = usize;
 
+const MAX_SIMILAR_FILES: usize = 5;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-struct RojoSourcemapNode {
+pub(super) struct RojoSourcemapNode {
     name: String,
     class_name: String,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -24,23 +32,68 @@ struct RojoSourcemapNode {
 }
 
 impl RojoSourcemapNode {
-    fn initialize(mut self, relative_to: &Path) -> Self {
-        let mut queue = vec![&mut self];
-        let mut index = 0;
+    pub(super) fn new(name: impl Into<String>, class_name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            class_name: class_name.into(),
+            file_paths: Vec::new(),
+            children: Vec::new(),
+            id: 0,
+            parent_id: 0,
+        }
+    }
+
+    pub(super) fn with_file_path(mut self, file_path: impl Into<PathBuf>) -> Self {
+        self.file_paths.push(file_path.into());
+        self
+    }
+
+    pub(super) fn set_class_name(&mut self, class_name: impl Into<String>) {
+        self.class_name = class_name.into();
+    }
+
+    /// Adds a child node, replacing the existing child with the same name.
+    pub(super) fn insert_child(&mut self, child: RojoSourcemapNode) {
+        if let Some(existing) = self
+            .children
+            .iter_mut()
+            .find(|node| node.name == child.name)
+        {
+            *existing = child;
+        } else {
+            self.children.push(child);
+        }
+    }
+
+    fn initialize(
+        mut self,
+        relative_to: &Path,
+        case_sensitive_paths: CaseSensitivePaths,
+    ) -> (Self, RojoSourcemapIndex) {
+        let mut index = RojoSourcemapIndex::default();
+        let mut queue = vec![(&mut self, Vec::new())];
+        let mut id = 0;
 
-        while let Some(node) = queue.pop() {
-            node.id = index;
+        while let Some((node, route)) = queue.pop() {
+            node.id = id;
             for file_path in &mut node.file_paths {
                 *file_path = utils::normalize_path(relative_to.join(&file_path));
+                index
+                    .files
+                    .entry(case_sensitive_paths.path_key(file_path))
+                    .or_insert(id);
             }
-            for child in &mut node.children {
-                child.parent_id = index;
-                queue.push(child);
+            for (child_index, child) in node.children.iter_mut().enumerate() {
+                child.parent_id = id;
+                let mut child_route = route.clone();
+                child_route.push(child_index);
+                queue.push((child, child_route));
             }
-            index += 1;
+            index.routes.push(route);
+            id += 1;
         }
 
-        self
+        (self, index)
     }
 
     fn id(&self) -> NodeId {
@@ -51,46 +104,94 @@ impl RojoSourcemapNode {
         self.parent_id
     }
 
-    fn iter(&self) -> impl Iterator<Item = &Self> {
-        RojoSourcemapNodeIterator::new(self)
-    }
-
     fn get_child(&self, id: NodeId) -> Option<&RojoSourcemapNode> {
         self.children.iter().find(|node| node.id == id)
     }
 
-    fn get_descendant(&self, id: NodeId) -> Option<&RojoSourcemapNode> {
-        self.iter().find(|node| node.id == id)
-    }
-
     fn is_root(&self) -> bool {
         self.id == self.parent_id
     }
+
+    /// The file of the script, since the file paths can also include `.meta.json` or
+    /// `.model.json` files. The first file path is used when none of them is a Lua file.
+    fn script_file_path(&self) -> Option<&PathBuf> {
+        self.file_paths
+            .iter()
+            .find(|file_path| is_lua_file(file_path))
+            .or_else(|| self.file_paths.first())
+    }
+
+    fn iter_non_lua_file_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.file_paths
+            .iter()
+            .filter(|file_path| !is_lua_file(file_path))
+    }
 }
 
-struct RojoSourcemapNodeIterator<'a> {
-    queue: Vec<&'a RojoSourcemapNode>,
+fn is_lua_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("lua") || extension.eq_ignore_ascii_case("luau")
+        })
 }
 
-impl<'a> RojoSourcemapNodeIterator<'a> {
-    fn new(root_node: &'a RojoSourcemapNode) -> Self {
-        Self {
-            queue: vec![root_node],
-        }
-    }
+/// Lookup tables built when parsing a sourcemap, so that finding nodes does not
+/// require walking the whole tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct RojoSourcemapIndex {
+    /// maps each file path key to the first node (in traversal order) that contains it
+    files: HashMap<PathBuf, NodeId>,
+    /// the child indexes to follow from the root node to reach each node id
+    routes: Vec<Vec<usize>>,
 }
 
-impl<'a> Iterator for RojoSourcemapNodeIterator<'a> {
-    type Item = &'a RojoSourcemapNode;
+/// The reason why an instance path could not be resolved to a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum InstancePathResolutionError {
+    /// No instance with a file matches the instance path.
+    NotFound,
+    /// A `Child` component names an instance that does not exist in the sourcemap.
+    MissingChild {
+        component_index: usize,
+        child: String,
+        parent: String,
+    },
+    /// A `Parent` component walks above the root of a sourcemap that is not a DataModel.
+    EscapesSourcemapRoot { component_index: usize },
+    /// A `Parent` component walks above the DataModel (`game.Parent` is nil).
+    ParentOfDataModel { component_index: usize },
+    /// A DataModel path is resolved without a sourcemap.
+    MissingSourcemap,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(next_node) = self.queue.pop() {
-            for child in &next_node.children {
-                self.queue.push(child);
-            }
-            Some(next_node)
-        } else {
-            None
+impl fmt::Display for InstancePathResolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "instance path does not match a file in the sourcemap"),
+            Self::MissingChild {
+                component_index,
+                child,
+                parent,
+            } => write!(
+                f,
+                "instance `{}` has no child named `{}` in the sourcemap (component {})",
+                parent, child, component_index
+            ),
+            Self::EscapesSourcemapRoot { component_index } => write!(
+                f,
+                "instance path escapes the sourcemap root (`Parent` at component {})",
+                component_index
+            ),
+            Self::ParentOfDataModel { component_index } => write!(
+                f,
+                "instance path walks above the DataModel (`Parent` at component {})",
+                component_index
+            ),
+            Self::MissingSourcemap => write!(
+                f,
+                "instance paths from the DataModel can only be resolved with a Rojo sourcemap"
+            ),
         }
     }
 }
@@ -99,21 +200,77 @@ impl<'a> Iterator for RojoSourcemapNodeIterator<'a> {
 pub(crate) struct RojoSourcemap {
     root_node: RojoSourcemapNode,
     is_datamodel: bool,
+    index: RojoSourcemapIndex,
+    case_sensitive_paths: CaseSensitivePaths,
 }
 
 impl RojoSourcemap {
-    pub(crate) fn parse(
+    /// Parses a sourcemap generated by Rojo or a Rojo project file (detected by its
+    /// `tree` field). The directories referenced by a project file are read from the
+    /// given resources.
+    /// Reads the content of the Rojo sourcemap (or project file) at the given location,
+    /// which must be a file.
+    pub(crate) fn read_content(path: &Path, resources: &Resources) -> Result<String, DarkluaError> {
+        if resources.is_directory(path).unwrap_or_default() {
+            return Err(DarkluaError::custom(format!(
+                "expected a Rojo sourcemap JSON file, found a directory at `{}`",
+                path.display()
+            )));
+        }
+        resources.get(path).map_err(DarkluaError::from)
+    }
+
+    pub(crate) fn load(
         content: &str,
         relative_to: impl AsRef<Path>,
+        resources: &Resources,
+        case_sensitive_paths: CaseSensitivePaths,
     ) -> Result<Self, DarkluaError> {
-        let root_node =
-            serde_json::from_str::<RojoSourcemapNode>(content)?.initialize(relative_to.as_ref());
+        // the content is parsed as JSON before reading the nodes, so that a file that is
+        // not JSON (like a Lua file) is not reported as an invalid sourcemap node
+        let content = serde_json::from_str::<serde_json::Value>(content).map_err(|err| {
+            DarkluaError::custom(format!(
+                "file does not look like JSON ({}): did you point at a source file instead of \
+                the generated sourcemap or the project file?",
+                err
+            ))
+        })?;
+
+        if rojo_project::is_project_file(&content) {
+            let root_node =
+                rojo_project::build_project_tree(content, relative_to.as_ref(), resources)?;
+
+            // the file paths of the project tree already include the project location
+            Ok(Self::from_root_node(
+                root_node,
+                Path::new(""),
+                case_sensitive_paths,
+            ))
+        } else {
+            let root_node = serde_json::from_value::<RojoSourcemapNode>(content)?;
+
+            Ok(Self::from_root_node(
+                root_node,
+                relative_to.as_ref(),
+                case_sensitive_paths,
+            ))
+        }
+    }
+
+    fn from_root_node(
+        root_node: RojoSourcemapNode,
+        relative_to: &Path,
+        case_sensitive_paths: CaseSensitivePaths,
+    ) -> Self {
+        let (root_node, index) = root_node.initialize(relative_to, case_sensitive_paths);
 
         let is_datamodel = root_node.class_name == "DataModel";
-        Ok(Self {
+        Self {
             root_node,
             is_datamodel,
-        })
+            index,
+            case_sensitive_paths,
+        }
     }
 
     pub(crate) fn get_instance_path(
@@ -167,7 +324,7 @@ impl RojoSourcemap {
 
             self.index_descendants(
                 instance_path,
-                self.root_node.get_descendant(common_ancestor_id)?,
+                self.get_node(common_ancestor_id)?,
                 descendants.iter().rev(),
             )
         } else {
@@ -185,79 +342,99 @@ impl RojoSourcemap {
         &self,
         from_file: impl AsRef<Path>,
         instance_path: &InstancePath,
-    ) -> Option<PathBuf> {
-        let from_file = from_file.as_ref();
-        let from_node = self.find_node(from_file)?;
+    ) -> Result<PathBuf, InstancePathResolutionError> {
+        self.find_node_from_instance_path(from_file.as_ref(), instance_path)?
+            .script_file_path()
+            .cloned()
+            .ok_or(InstancePathResolutionError::NotFound)
+    }
 
-        let target_node = match instance_path.root() {
-            InstancePathRoot::Root => {
-                // From DataModel: first component is service name (child), then walk children
-                let mut iter = instance_path.components().iter();
-                let Some(InstancePathComponent::Child(service_name)) = iter.next() else {
-                    return None;
-                };
-                let mut node = self
-                    .root_node
-                    .children
-                    .iter()
-                    .find(|c| c.name == *service_name)?;
-                for component in iter {
-                    match component {
-                        InstancePathComponent::Parent => return None,
-                        InstancePathComponent::Child(name) => {
-                            node = node.children.iter().find(|c| c.name == *name)?;
-                        }
-                        InstancePathComponent::Ancestor(name) => {
-                            // Start at the parent of the current node and walk upwards
-                            let mut cursor = self.root_node.get_descendant(node.parent_id())?;
-                            loop {
-                                if cursor.name == *name {
-                                    node = cursor;
-                                    break;
-                                }
-                                if cursor.is_root() {
-                                    return None;
-                                }
-                                cursor = self.root_node.get_descendant(cursor.parent_id())?;
+    /// Returns the file paths of the instance at the given instance path that are not Lua
+    /// files (like `.meta.json` files), so they can be tracked as dependencies.
+    pub(crate) fn get_non_lua_files_from_instance_path(
+        &self,
+        from_file: impl AsRef<Path>,
+        instance_path: &InstancePath,
+    ) -> Vec<PathBuf> {
+        self.find_node_from_instance_path(from_file.as_ref(), instance_path)
+            .map(|node| node.iter_non_lua_file_paths().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the class name (like `ModuleScript` or `Folder`) of the instance at the
+    /// given instance path.
+    pub(crate) fn get_class_name_from_instance_path(
+        &self,
+        from_file: impl AsRef<Path>,
+        instance_path: &InstancePath,
+    ) -> Result<&str, InstancePathResolutionError> {
+        self.find_node_from_instance_path(from_file.as_ref(), instance_path)
+            .map(|node| node.class_name.as_str())
+    }
+
+    fn find_node_from_instance_path(
+        &self,
+        from_file: &Path,
+        instance_path: &InstancePath,
+    ) -> Result<&RojoSourcemapNode, InstancePathResolutionError> {
+        // DataModel paths walk down from the root node, while script paths start from the
+        // node of the current file
+        let mut node = match instance_path.root() {
+            InstancePathRoot::Root => &self.root_node,
+            InstancePathRoot::Script => self
+                .find_node(from_file)
+                .ok_or(InstancePathResolutionError::NotFound)?,
+        };
+
+        for (index, component) in instance_path.components().iter().enumerate() {
+            match component {
+                InstancePathComponent::Parent => {
+                    if node.is_root() {
+                        return Err(if self.is_datamodel {
+                            InstancePathResolutionError::ParentOfDataModel {
+                                component_index: index,
                             }
-                        }
+                        } else {
+                            InstancePathResolutionError::EscapesSourcemapRoot {
+                                component_index: index,
+                            }
+                        });
                     }
+                    node = self
+                        .get_node(node.parent_id())
+                        .ok_or(InstancePathResolutionError::NotFound)?;
                 }
-                node
-            }
-            InstancePathRoot::Script => {
-                // Start from current file node and walk up for Parent then down for Child
-                let mut node = from_node;
-                for component in instance_path.components() {
-                    match component {
-                        InstancePathComponent::Parent => {
-                            node = self.root_node.get_descendant(node.parent_id())?;
-                        }
-                        InstancePathComponent::Child(name) => {
-                            node = node.children.iter().find(|c| c.name == *name)?;
+                InstancePathComponent::Child(name) => {
+                    node = node
+                        .children
+                        .iter()
+                        .find(|c| c.name == *name)
+                        .ok_or_else(|| InstancePathResolutionError::MissingChild {
+                            component_index: index,
+                            child: name.to_owned(),
+                            parent: node.name.clone(),
+                        })?;
+                }
+                InstancePathComponent::Ancestor(name) => {
+                    // jump to first ancestor with this name, starting at the parent
+                    let mut cursor = node;
+                    loop {
+                        if cursor.is_root() {
+                            return Err(InstancePathResolutionError::NotFound);
                         }
-                        InstancePathComponent::Ancestor(name) => {
-                            // jump to first ancestor with this name, starting at the parent
-                            let mut cursor = self.root_node.get_descendant(node.parent_id())?;
-                            loop {
-                                if cursor.name == *name {
-                                    node = cursor;
-                                    break;
-                                }
-                                if cursor.is_root() {
-                                    return None;
-                                }
-                                cursor = self.root_node.get_descendant(cursor.parent_id())?;
-                            }
+                        cursor = self
+                            .get_node(cursor.parent_id())
+                            .ok_or(InstancePathResolutionError::NotFound)?;
+                        if cursor.name == *name {
+                            node = cursor;
+                            break;
                         }
                     }
                 }
-                node
             }
-        };
+        }
 
-        // Prefer the first file path if available
-        target_node.file_paths.first().cloned()
+        Ok(node)
     }
 
     /// Returns the absolute InstancePath from DataModel root to the target file.
@@ -294,7 +471,7 @@ impl RojoSourcemap {
 
         let mut parent_id = node.parent_id();
 
-        while let Some(parent) = self.root_node.get_descendant(parent_id) {
+        while let Some(parent) = self.get_node(parent_id) {
             ids.push(parent_id);
             if parent.is_root() {
                 break;
@@ -305,10 +482,79 @@ impl RojoSourcemap {
         ids
     }
 
+    /// Returns a message explaining that the given file is not present in the sourcemap,
+    /// with the files of the sourcemap that have the same name. Returns `None` when the
+    /// file is in the sourcemap.
+    pub(crate) fn missing_file_message(
+        &self,
+        file: &Path,
+        sourcemap_path: &Path,
+    ) -> Option<String> {
+        if self.find_node(file).is_some() {
+            return None;
+        }
+
+        let mut message = format!(
+            concat!(
+                "`{}` is not present in the Rojo sourcemap `{}`; ",
+                "regenerate the sourcemap or check the `rojo_sourcemap` path"
+            ),
+            file.display(),
+            sourcemap_path.display()
+        );
+
+        let similar_files = self.find_files_named_like(file);
+        if !similar_files.is_empty() {
+            message.push_str(&format!(
+                " (files with the same name in the sourcemap: `{}`)",
+                similar_files
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join("`, `")
+            ));
+        }
+
+        Some(message)
+    }
+
+    fn find_files_named_like(&self, file: &Path) -> Vec<&Path> {
+        let file_name = match file.file_name() {
+            Some(file_name) => self.case_sensitive_paths.path_key(Path::new(file_name)),
+            None => return Vec::new(),
+        };
+
+        let mut files: Vec<_> = (0..self.index.routes.len())
+            .filter_map(|id| self.get_node(id))
+            .flat_map(|node| node.file_paths.iter())
+            .filter(|path| {
+                path.file_name().is_some_and(|name| {
+                    self.case_sensitive_paths.path_key(Path::new(name)) == file_name
+                })
+            })
+            .map(PathBuf::as_path)
+            .collect();
+        files.sort();
+        files.dedup();
+        files.truncate(MAX_SIMILAR_FILES);
+        files
+    }
+
     fn find_node(&self, path: &Path) -> Option<&RojoSourcemapNode> {
-        self.root_node
+        self.index
+            .files
+            .get(&self.case_sensitive_paths.path_key(path))
+            .and_then(|id| self.get_node(*id))
+    }
+
+    fn get_node(&self, id: NodeId) -> Option<&RojoSourcemapNode> {
+        self.index
+            .routes
+            .get(id)?
             .iter()
-            .find(|node| node.file_paths.iter().any(|file_path| file_path == path))
+            .try_fold(&self.root_node, |node, child_index| {
+                node.children.get(*child_index)
+            })
     }
 }
 
@@ -317,7 +563,13 @@ mod test {
     use super::*;
 
     fn new_sourcemap(content: &str) -> RojoSourcemap {
-        RojoSourcemap::parse(content, "").expect("unable to parse sourcemap")
+        RojoSourcemap::load(
+            content,
+            "",
+            &Resources::from_memory(),
+            CaseSensitivePaths::Sensitive,
+        )
+        .expect("unable to parse sourcemap")
     }
 
     mod instance_paths {
@@ -483,11 +735,444 @@ mod test {
         }
     }
 
+    mod file_from_instance_path {
+        use super::*;
+
+        const PROJECT_SOURCEMAP: &str = r#"{
+            "name": "Project",
+            "className": "ModuleScript",
+            "filePaths": ["src/init.lua", "default.project.json"],
+            "children": [
+                {
+                    "name": "main",
+                    "className": "ModuleScript",
+                    "filePaths": ["src/main.lua"]
+                },
+                {
+                    "name": "value",
+                    "className": "ModuleScript",
+                    "filePaths": ["src/value.lua"]
+                }
+            ]
+        }"#;
+
+        const DATAMODEL_SOURCEMAP: &str = r#"{
+            "name": "Game",
+            "className": "DataModel",
+            "filePaths": ["default.project.json"],
+            "children": [
+                {
+                    "name": "ReplicatedStorage",
+                    "className": "ReplicatedStorage",
+                    "children": [
+                        {
+                            "name": "main",
+                            "className": "ModuleScript",
+                            "filePaths": ["src/shared/main.lua"]
+                        }
+                    ]
+                },
+                {
+                    "name": "ServerStorage",
+                    "className": "ServerStorage",
+                    "children": [
+                        {
+                            "name": "value",
+                            "className": "ModuleScript",
+                            "filePaths": ["src/server/value.lua"]
+                        }
+                    ]
+                }
+            ]
+        }"#;
+
+        fn path(mut instance_path: InstancePath, components: &[&'static str]) -> InstancePath {
+            for component in components {
+                match *component {
+                    "parent" => instance_path.parent(),
+                    child_name => instance_path.child(child_name),
+                }
+            }
+            instance_path
+        }
+
+        #[test]
+        fn script_sibling_in_project_sourcemap() {
+            let sourcemap = new_sourcemap(PROJECT_SOURCEMAP);
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_file_from_instance_path(
+                    "src/main.lua",
+                    &path(InstancePath::from_script(), &["parent", "value"])
+                ),
+                Ok(PathBuf::from("src/value.lua"))
+            );
+        }
+
+        #[test]
+        fn parent_of_root_escapes_project_sourcemap() {
+            let sourcemap = new_sourcemap(PROJECT_SOURCEMAP);
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_file_from_instance_path(
+                    "src/init.lua",
+                    &path(InstancePath::from_script(), &["parent", "value"])
+                ),
+                Err(InstancePathResolutionError::EscapesSourcemapRoot { component_index: 0 })
+            );
+        }
+
+        #[test]
+        fn grand_parent_of_child_escapes_project_sourcemap() {
+            let sourcemap = new_sourcemap(PROJECT_SOURCEMAP);
+
+            let error = sourcemap
+                .get_file_from_instance_path(
+                    "src/main.lua",
+                    &path(InstancePath::from_script(), &["parent", "parent", "value"]),
+                )
+                .unwrap_err();
+
+            pretty_assertions::assert_eq!(
+                error,
+                InstancePathResolutionError::EscapesSourcemapRoot { component_index: 1 }
+            );
+            pretty_assertions::assert_eq!(
+                error.to_string(),
+                "instance path escapes the sourcemap root (`Parent` at component 1)"
+            );
+        }
+
+        #[test]
+        fn script_path_across_services_in_datamodel_sourcemap() {
+            let sourcemap = new_sourcemap(DATAMODEL_SOURCEMAP);
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_file_from_instance_path(
+                    "src/shared/main.lua",
+                    &path(
+                        InstancePath::from_script(),
+                        &["parent", "parent", "ServerStorage", "value"]
+                    )
+                ),
+                Ok(PathBuf::from("src/server/value.lua"))
+            );
+        }
+
+        #[test]
+        fn datamodel_path_in_datamodel_sourcemap() {
+            let sourcemap = new_sourcemap(DATAMODEL_SOURCEMAP);
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_file_from_instance_path(
+                    "src/shared/main.lua",
+                    &path(InstancePath::from_root(), &["ServerStorage", "value"])
+                ),
+                Ok(PathBuf::from("src/server/value.lua"))
+            );
+        }
+
+        #[test]
+        fn script_parent_of_datamodel_does_not_resolve() {
+            let sourcemap = new_sourcemap(DATAMODEL_SOURCEMAP);
+
+            let error = sourcemap
+                .get_file_from_instance_path(
+                    "src/shared/main.lua",
+                    &path(
+                        InstancePath::from_script(),
+                        &["parent", "parent", "parent", "ServerStorage", "value"],
+                    ),
+                )
+                .unwrap_err();
+
+            pretty_assertions::assert_eq!(
+                error,
+                InstancePathResolutionError::ParentOfDataModel { component_index: 2 }
+            );
+            pretty_assertions::assert_eq!(
+                error.to_string(),
+                "instance path walks above the DataModel (`Parent` at component 2)"
+            );
+        }
+
+        #[test]
+        fn datamodel_parent_does_not_resolve() {
+            let sourcemap = new_sourcemap(DATAMODEL_SOURCEMAP);
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_file_from_instance_path(
+                    "src/shared/main.lua",
+                    &path(
+                        InstancePath::from_root(),
+                        &["parent", "ServerStorage", "value"]
+                    )
+                ),
+                Err(InstancePathResolutionError::ParentOfDataModel { component_index: 0 })
+            );
+        }
+
+        #[test]
+        fn datamodel_service_parent_does_not_resolve() {
+            let sourcemap = new_sourcemap(DATAMODEL_SOURCEMAP);
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_file_from_instance_path(
+                    "src/shared/main.lua",
+                    &path(
+                        InstancePath::from_root(),
+                        &["ServerStorage", "parent", "parent"]
+                    )
+                ),
+                Err(InstancePathResolutionError::ParentOfDataModel { component_index: 2 })
+            );
+        }
+
+        #[test]
+        fn unknown_child_is_missing() {
+            let sourcemap = new_sourcemap(PROJECT_SOURCEMAP);
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_file_from_instance_path(
+                    "src/main.lua",
+                    &path(InstancePath::from_script(), &["parent", "oops"])
+                ),
+                Err(InstancePathResolutionError::MissingChild {
+                    component_index: 1,
+                    child: "oops".to_owned(),
+                    parent: "Project".to_owned(),
+                })
+            );
+        }
+
+        #[test]
+        fn class_name_of_module_script() {
+            let sourcemap = new_sourcemap(DATAMODEL_SOURCEMAP);
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_class_name_from_instance_path(
+                    "src/shared/main.lua",
+                    &path(InstancePath::from_root(), &["ServerStorage", "value"])
+                ),
+                Ok("ModuleScript")
+            );
+        }
+
+        #[test]
+        fn class_name_of_service() {
+            let sourcemap = new_sourcemap(DATAMODEL_SOURCEMAP);
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_class_name_from_instance_path(
+                    "src/shared/main.lua",
+                    &path(InstancePath::from_script(), &["parent"])
+                ),
+                Ok("ReplicatedStorage")
+            );
+        }
+
+        #[test]
+        fn class_name_of_folder_with_project_file() {
+            let sourcemap = new_sourcemap(
+                r#"{
+                "name": "Project",
+                "className": "ModuleScript",
+                "filePaths": ["src/init.lua"],
+                "children": [
+                    {
+                        "name": "Assets",
+                        "className": "Folder",
+                        "filePaths": ["src/assets/default.project.json"]
+                    },
+                    {
+                        "name": "Client",
+                        "className": "LocalScript",
+                        "filePaths": ["src/client.client.lua"]
+                    }
+                ]
+            }"#,
+            );
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_class_name_from_instance_path(
+                    "src/init.lua",
+                    &path(InstancePath::from_script(), &["Assets"])
+                ),
+                Ok("Folder")
+            );
+            pretty_assertions::assert_eq!(
+                sourcemap.get_class_name_from_instance_path(
+                    "src/init.lua",
+                    &path(InstancePath::from_script(), &["Client"])
+                ),
+                Ok("LocalScript")
+            );
+        }
+
+        #[test]
+        fn class_name_of_unknown_child_is_missing() {
+            let sourcemap = new_sourcemap(PROJECT_SOURCEMAP);
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_class_name_from_instance_path(
+                    "src/main.lua",
+                    &path(InstancePath::from_script(), &["parent", "oops"])
+                ),
+                Err(InstancePathResolutionError::MissingChild {
+                    component_index: 1,
+                    child: "oops".to_owned(),
+                    parent: "Project".to_owned(),
+                })
+            );
+        }
+    }
+
+    mod index {
+        use super::*;
+
+        #[test]
+        fn duplicate_file_paths_resolve_to_first_node_in_traversal_order() {
+            let sourcemap = new_sourcemap(
+                r#"{
+                "name": "Project",
+                "className": "ModuleScript",
+                "filePaths": ["src/init.lua"],
+                "children": [
+                    {
+                        "name": "first",
+                        "className": "ModuleScript",
+                        "filePaths": ["src/shared.lua"]
+                    },
+                    {
+                        "name": "second",
+                        "className": "ModuleScript",
+                        "filePaths": ["src/shared.lua"]
+                    }
+                ]
+            }"#,
+            );
+
+            let mut expected = InstancePath::from_script();
+            expected.child("second");
+
+            pretty_assertions::assert_eq!(
+                sourcemap.get_instance_path("src/init.lua", "src/shared.lua"),
+                Some(expected)
+            );
+        }
+
+        #[test]
+        fn resolve_every_module_of_a_large_sourcemap() {
+            const FOLDERS: usize = 50;
+            const MODULES: usize = 100;
+
+            let folders: Vec<_> = (0..FOLDERS)
+                .map(|folder| {
+                    let modules: Vec<_> = (0..MODULES)
+                        .map(|module| {
+                            format!(
+                                r#"{{ "name": "m{module}", "className": "ModuleScript", "filePaths": ["src/f{folder}/m{module}.lua"] }}"#
+                            )
+                        })
+                        .collect();
+                    format!(
+                        r#"{{ "name": "f{folder}", "className": "Folder", "children": [{}] }}"#,
+                        modules.join(",")
+                    )
+                })
+                .collect();
+
+            let sourcemap = new_sourcemap(&format!(
+                r#"{{ "name": "Project", "className": "ModuleScript", "filePaths": ["src/init.lua"], "children": [{}] }}"#,
+                folders.join(",")
+            ));
+
+            for folder in 0..FOLDERS {
+                for module in 0..MODULES {
+                    let file = PathBuf::from(format!("src/f{folder}/m{module}.lua"));
+
+                    let instance_path = sourcemap
+                        .get_instance_path("src/init.lua", &file)
+                        .expect("expected to find instance path");
+
+                    pretty_assertions::assert_eq!(
+                        sourcemap.get_file_from_instance_path("src/init.lua", &instance_path),
+                        Ok(file)
+                    );
+                }
+            }
+        }
+    }
+
+    mod missing_file_message {
+        use super::*;
+
+        const SOURCEMAP: &str = r#"{
+            "name": "Project",
+            "className": "ModuleScript",
+            "filePaths": ["src/init.lua"],
+            "children": [
+                { "name": "a", "className": "ModuleScript", "filePaths": ["src/a/main.lua"] },
+                { "name": "b", "className": "ModuleScript", "filePaths": ["src/b/Main.lua"] },
+                { "name": "c", "className": "ModuleScript", "filePaths": ["src/c.lua"] }
+            ]
+        }"#;
+
+        fn message(case_sensitive_paths: CaseSensitivePaths, file: &str) -> Option<String> {
+            RojoSourcemap::load(
+                SOURCEMAP,
+                "",
+                &Resources::from_memory(),
+                case_sensitive_paths,
+            )
+            .expect("unable to parse sourcemap")
+            .missing_file_message(Path::new(file), Path::new("sourcemap.json"))
+        }
+
+        #[test]
+        fn file_in_sourcemap_has_no_message() {
+            assert_eq!(message(CaseSensitivePaths::Sensitive, "src/c.lua"), None);
+        }
+
+        #[test]
+        fn missing_file_without_similar_files() {
+            pretty_assertions::assert_eq!(
+                message(CaseSensitivePaths::Sensitive, "src/d.lua").unwrap(),
+                concat!(
+                    "`src/d.lua` is not present in the Rojo sourcemap `sourcemap.json`; ",
+                    "regenerate the sourcemap or check the `rojo_sourcemap` path"
+                )
+            );
+        }
+
+        #[test]
+        fn missing_file_lists_files_with_same_name() {
+            assert!(message(CaseSensitivePaths::Sensitive, "src/main.lua")
+                .unwrap()
+                .ends_with("(files with the same name in the sourcemap: `src/a/main.lua`)"));
+        }
+
+        #[test]
+        fn missing_file_lists_files_with_same_name_ignoring_casing() {
+            assert!(message(CaseSensitivePaths::Insensitive, "src/main.lua")
+                .unwrap()
+                .ends_with(
+                    "(files with the same name in the sourcemap: `src/a/main.lua`, `src/b/Main.lua`)"
+                ));
+        }
+    }
+
     mod find_first_ancestor {
         use super::*;
 
         fn new_sourcemap(content: &str) -> RojoSourcemap {
-            RojoSourcemap::parse(content, "").expect("unable to parse sourcemap")
+            RojoSourcemap::load(
+                content,
+                "",
+                &Resources::from_memory(),
+                CaseSensitivePaths::Sensitive,
+            )
+            .expect("unable to parse sourcemap")
         }
 
         #[test]
@@ -546,4 +1231,272 @@ mod test {
             assert!(resolved.ends_with("src/d/inner/d/d2.lua"), "{}", format!("{resolved:?}"));
         }
     }
+
+    mod file_paths {
+        use super::*;
+
+        fn new_sourcemap() -> RojoSourcemap {
+            RojoSourcemap::load(
+                r#"{
+                    "name": "Project",
+                    "className": "ModuleScript",
+                    "filePaths": ["src/init.lua", "default.project.json"],
+                    "children": [
+                        {
+                            "name": "value",
+                            "className": "ModuleScript",
+                            "filePaths": ["src/value.meta.json", "src/value.lua"]
+                        }
+                    ]
+                }"#,
+                "",
+                &Resources::from_memory(),
+                CaseSensitivePaths::Sensitive,
+            )
+            .expect("unable to parse sourcemap")
+        }
+
+        fn value_instance_path() -> InstancePath {
+            let mut instance_path = InstancePath::from_script();
+            instance_path.child("value");
+            instance_path
+        }
+
+        #[test]
+        fn prefers_lua_file_over_meta_file() {
+            let resolved = new_sourcemap()
+                .get_file_from_instance_path(Path::new("src/init.lua"), &value_instance_path())
+                .expect("expected to resolve file path from instance path");
+
+            pretty_assertions::assert_eq!(resolved, PathBuf::from("src/value.lua"));
+        }
+
+        #[test]
+        fn lists_meta_file_as_non_lua_file() {
+            pretty_assertions::assert_eq!(
+                new_sourcemap().get_non_lua_files_from_instance_path(
+                    Path::new("src/init.lua"),
+                    &value_instance_path()
+                ),
+                vec![PathBuf::from("src/value.meta.json")]
+            );
+        }
+
+        #[test]
+        fn lua_extension_is_case_insensitive() {
+            assert!(is_lua_file(Path::new("src/value.LUAU")));
+            assert!(!is_lua_file(Path::new("src/value.meta.json")));
+        }
+    }
+
+    mod project_file {
+        use super::*;
+
+        fn project_sourcemap(project: &str, files: &[&str]) -> RojoSourcemap {
+            let resources = Resources::from_memory();
+            for file in files {
+                resources.write(file, "return nil").unwrap();
+            }
+            RojoSourcemap::load(project, "", &resources, CaseSensitivePaths::Sensitive)
+                .expect("unable to load project file")
+        }
+
+        fn script_path(sourcemap: &RojoSourcemap, file: &str) -> Option<InstancePath> {
+            sourcemap.get_absolute_instance_path(file)
+        }
+
+        fn datamodel_path(components: &[&str]) -> InstancePath {
+            let mut instance_path = InstancePath::from_root();
+            for component in components {
+                instance_path.child(*component);
+            }
+            instance_path
+        }
+
+        const DATAMODEL_PROJECT: &str = r#"{
+            "name": "Game",
+            "tree": {
+                "$className": "DataModel",
+                "ReplicatedStorage": {
+                    "Shared": { "$path": "src/shared" },
+                    "Config": { "$path": "config.lua" }
+                },
+                "ServerScriptService": {
+                    "$ignoreUnknownInstances": true,
+                    "Server": { "$path": "src/server" }
+                }
+            }
+        }"#;
+
+        const DATAMODEL_FILES: &[&str] = &[
+            "src/shared/init.lua",
+            "src/shared/Utils.luau",
+            "src/shared/Math/Vector.lua",
+            "src/shared/README.md",
+            "src/server/main.server.lua",
+            "config.lua",
+        ];
+
+        #[test]
+        fn datamodel_project_is_datamodel() {
+            let sourcemap = project_sourcemap(DATAMODEL_PROJECT, DATAMODEL_FILES);
+
+            assert!(sourcemap.is_datamodel);
+        }
+
+        #[test]
+        fn directory_with_init_file_is_module_script() {
+            let sourcemap = project_sourcemap(DATAMODEL_PROJECT, DATAMODEL_FILES);
+            let instance_path = datamodel_path(&["ReplicatedStorage", "Shared"]);
+
+            assert_eq!(
+                script_path(&sourcemap, "src/shared/init.lua"),
+                Some(instance_path.clone())
+            );
+            assert_eq!(
+                sourcemap.get_class_name_from_instance_path("", &instance_path),
+                Ok("ModuleScript")
+            );
+        }
+
+        #[test]
+        fn file_in_directory_is_child() {
+            let sourcemap = project_sourcemap(DATAMODEL_PROJECT, DATAMODEL_FILES);
+
+            assert_eq!(
+                script_path(&sourcemap, "src/shared/Utils.luau"),
+                Some(datamodel_path(&["ReplicatedStorage", "Shared", "Utils"]))
+            );
+        }
+
+        #[test]
+        fn directory_without_init_file_is_folder() {
+            let sourcemap = project_sourcemap(DATAMODEL_PROJECT, DATAMODEL_FILES);
+            let instance_path = datamodel_path(&["ReplicatedStorage", "Shared", "Math"]);
+
+            assert_eq!(
+                sourcemap.get_class_name_from_instance_path("", &instance_path),
+                Ok("Folder")
+            );
+            assert_eq!(
+                script_path(&sourcemap, "src/shared/Math/Vector.lua"),
+                Some(datamodel_path(&[
+                    "ReplicatedStorage",
+                    "Shared",
+                    "Math",
+                    "Vector"
+                ]))
+            );
+        }
+
+        #[test]
+        fn server_script_name_drops_suffix() {
+            let sourcemap = project_sourcemap(DATAMODEL_PROJECT, DATAMODEL_FILES);
+            let instance_path = datamodel_path(&["ServerScriptService", "Server", "main"]);
+
+            assert_eq!(
+                script_path(&sourcemap, "src/server/main.server.lua"),
+                Some(instance_path.clone())
+            );
+            assert_eq!(
+                sourcemap.get_class_name_from_instance_path("", &instance_path),
+                Ok("Script")
+            );
+        }
+
+        #[test]
+        fn path_to_file_is_module_script() {
+            let sourcemap = project_sourcemap(DATAMODEL_PROJECT, DATAMODEL_FILES);
+
+            assert_eq!(
+                script_path(&sourcemap, "config.lua"),
+                Some(datamodel_path(&["ReplicatedStorage", "Config"]))
+            );
+        }
+
+        #[test]
+        fn service_without_class_name_uses_its_name() {
+            let sourcemap = project_sourcemap(DATAMODEL_PROJECT, DATAMODEL_FILES);
+
+            assert_eq!(
+                sourcemap.get_class_name_from_instance_path(
+                    "",
+                    &datamodel_path(&["ReplicatedStorage"])
+                ),
+                Ok("ReplicatedStorage")
+            );
+        }
+
+        #[test]
+        fn library_project_resolves_from_script() {
+            let sourcemap = project_sourcemap(
+                r#"{ "name": "Library", "tree": { "$path": "src" } }"#,
+                &["src/init.lua", "src/value.lua"],
+            );
+
+            let mut instance_path = InstancePath::from_script();
+            instance_path.child("value");
+
+            assert!(!sourcemap.is_datamodel);
+            assert_eq!(
+                sourcemap.get_instance_path("src/init.lua", "src/value.lua"),
+                Some(instance_path)
+            );
+        }
+
+        #[test]
+        fn project_node_overrides_directory_child() {
+            let sourcemap = project_sourcemap(
+                r#"{
+                    "name": "Library",
+                    "tree": {
+                        "$path": "src",
+                        "value": { "$path": "other/value.lua" }
+                    }
+                }"#,
+                &["src/init.lua", "src/value.lua", "other/value.lua"],
+            );
+
+            assert_eq!(
+                script_path(&sourcemap, "other/value.lua"),
+                Some(datamodel_path(&["value"]))
+            );
+            assert_eq!(script_path(&sourcemap, "src/value.lua"), None);
+        }
+
+        #[test]
+        fn missing_optional_path_is_ignored() {
+            let sourcemap = project_sourcemap(
+                r#"{
+                    "name": "Library",
+                    "tree": {
+                        "$path": "src",
+                        "Packages": { "$path": { "optional": "Packages" } }
+                    }
+                }"#,
+                &["src/init.lua"],
+            );
+
+            assert_eq!(
+                sourcemap.get_class_name_from_instance_path("", &datamodel_path(&["Packages"])),
+                Ok("Packages")
+            );
+        }
+
+        #[test]
+        fn missing_path_errors() {
+            let error = RojoSourcemap::load(
+                r#"{ "name": "Library", "tree": { "$path": "src" } }"#,
+                "",
+                &Resources::from_memory(),
+                CaseSensitivePaths::Sensitive,
+            )
+            .unwrap_err();
+
+            pretty_assertions::assert_eq!(
+                error.to_string(),
+                "unable to find `src` referenced by the `$path` of Rojo project node `Library`"
+            );
+        }
+    }
 }
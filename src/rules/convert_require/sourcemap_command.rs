@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crate::frontend::DarkluaResult;
+use crate::rules::Context;
+use crate::DarkluaError;
+
+#[derive(Debug)]
+enum CommandState {
+    Pending,
+    Completed { project_files: Vec<PathBuf> },
+    Disabled,
+}
+
+/// Tracks the command that regenerates a Rojo sourcemap. Clones of a require mode share
+/// this state, so the project is only checked once per run.
+#[derive(Debug, Clone)]
+pub(crate) struct SourcemapCommandState(Rc<RefCell<CommandState>>);
+
+impl Default for SourcemapCommandState {
+    fn default() -> Self {
+        Self(Rc::new(RefCell::new(CommandState::Pending)))
+    }
+}
+
+// the state is not part of the configuration of a require mode
+impl PartialEq for SourcemapCommandState {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for SourcemapCommandState {}
+
+impl SourcemapCommandState {
+    /// Runs the given command when the sourcemap is missing or older than one of the Lua
+    /// or Luau files of the project. The project files are tracked as dependencies.
+    pub(crate) fn update_sourcemap(
+        &self,
+        command: &[String],
+        sourcemap_path: &Path,
+        context: &Context,
+    ) -> DarkluaResult<()> {
+        let mut state = self.0.borrow_mut();
+
+        if let CommandState::Pending = *state {
+            if !context.resources().is_file_system() {
+                log::warn!(
+                    "unable to run `rojo_sourcemap_command` because resources are not backed by the file system"
+                );
+                *state = CommandState::Disabled;
+            } else {
+                let project_files: Vec<_> = context
+                    .resources()
+                    .collect_work(context.project_location())
+                    .collect();
+
+                if is_sourcemap_stale(sourcemap_path, &project_files) {
+                    run_command(command, context.project_location())?;
+                }
+
+                *state = CommandState::Completed { project_files };
+            }
+        }
+
+        if let CommandState::Completed { project_files } = &*state {
+            for file in project_files {
+                context.add_file_dependency(file.clone());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn is_sourcemap_stale(sourcemap_path: &Path, project_files: &[PathBuf]) -> bool {
+    match modified_time(sourcemap_path) {
+        Some(sourcemap_time) => project_files
+            .iter()
+            .any(|file| modified_time(file).is_some_and(|file_time| file_time > sourcemap_time)),
+        None => true,
+    }
+}
+
+fn run_command(command: &[String], location: &Path) -> DarkluaResult<()> {
+    let (program, arguments) = command.split_first().ok_or_else(|| {
+        DarkluaError::custom(
+            "unable to regenerate Rojo sourcemap: `rojo_sourcemap_command` is empty",
+        )
+    })?;
+
+    let command_display = command.join(" ");
+
+    log::info!("regenerate Rojo sourcemap with `{}`", command_display);
+
+    let mut process = Command::new(program);
+    process.args(arguments);
+
+    if !location.as_os_str().is_empty() {
+        process.current_dir(location);
+    }
+
+    let output = process.output().map_err(|err| {
+        DarkluaError::custom(format!(
+            "unable to run Rojo sourcemap command `{}`: {}",
+            command_display, err
+        ))
+    })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(DarkluaError::custom(format!(
+            "Rojo sourcemap command `{}` failed ({}): {}",
+            command_display,
+            output.status,
+            stderr.trim()
+        )))
+    }
+}
@@ -0,0 +1,259 @@
+use full_moon::{
+    tokenizer::{Lexer, LexerResult, TokenType},
+    LuaVersion,
+};
+
+use crate::utils::{parse_comment_directive, split_directive, DIRECTIVE_PREFIX};
+
+const DISABLE_DIRECTIVE: &str = "disable";
+const ENABLE_DIRECTIVE: &str = "enable";
+const DISABLE_NEXT_LINE_DIRECTIVE: &str = "disable-next-line";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DisabledRange {
+    // `None` disables every rule
+    rule_name: Option<String>,
+    start: usize,
+    end: usize,
+}
+
+impl DisabledRange {
+    fn applies_to(&self, rule_name: &str) -> bool {
+        match &self.rule_name {
+            Some(disabled_rule) => disabled_rule == rule_name,
+            None => true,
+        }
+    }
+
+    fn contains(&self, rule_name: &str, line: usize) -> bool {
+        self.applies_to(rule_name) && self.start <= line && line <= self.end
+    }
+}
+
+/// The lines where rules are disabled with comment directives. A directive without rule
+/// names applies to every rule:
+///
+/// - `-- darklua: disable-next-line <rules>` disables the rules on the following line
+/// - `-- darklua: disable <rules>` disables the rules until a `-- darklua: enable <rules>`
+///   comment, or until the end of the file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct DisabledLines {
+    ranges: Vec<DisabledRange>,
+    // the rule names written in the directives, with the line of their directive
+    rule_names: Vec<(String, usize)>,
+}
+
+impl DisabledLines {
+    pub(crate) fn from_code(code: &str) -> Self {
+        let mut disabled_lines = Self::default();
+
+        if !code.contains(DIRECTIVE_PREFIX) {
+            return disabled_lines;
+        }
+
+        let tokens = match Lexer::new(code, LuaVersion::luau()).collect() {
+            LexerResult::Ok(tokens) | LexerResult::Recovered(tokens, _) => tokens,
+            LexerResult::Fatal(_) => {
+                log::warn!("unable to read comment directives (tokenizer error)");
+                return disabled_lines;
+            }
+        };
+
+        let mut open_regions: Vec<(Option<String>, usize)> = Vec::new();
+
+        for token in tokens {
+            let TokenType::SingleLineComment { comment } = token.token_type() else {
+                continue;
+            };
+            let line = token.start_position().line();
+
+            let comment = format!("--{}", comment);
+            let Some(directive) = parse_comment_directive(&comment) else {
+                continue;
+            };
+            let (name, rule_names) = split_directive(directive);
+            if ![
+                DISABLE_NEXT_LINE_DIRECTIVE,
+                DISABLE_DIRECTIVE,
+                ENABLE_DIRECTIVE,
+            ]
+            .contains(&name)
+            {
+                continue;
+            }
+
+            let mut rule_names: Vec<_> = rule_names.map(|name| Some(name.to_owned())).collect();
+            disabled_lines.rule_names.extend(
+                rule_names
+                    .iter()
+                    .flatten()
+                    .map(|rule_name| (rule_name.clone(), line)),
+            );
+            if rule_names.is_empty() {
+                rule_names.push(None);
+            }
+
+            match name {
+                DISABLE_NEXT_LINE_DIRECTIVE => {
+                    for rule_name in rule_names {
+                        disabled_lines.push(rule_name, line + 1, line + 1);
+                    }
+                }
+                DISABLE_DIRECTIVE => {
+                    for rule_name in rule_names {
+                        if !open_regions.iter().any(|(open, _)| *open == rule_name) {
+                            open_regions.push((rule_name, line));
+                        }
+                    }
+                }
+                ENABLE_DIRECTIVE => {
+                    let close_all = rule_names == [None];
+                    open_regions.retain(|(rule_name, start)| {
+                        if close_all || rule_names.contains(rule_name) {
+                            disabled_lines.push(rule_name.clone(), *start, line);
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        for (rule_name, start) in open_regions {
+            disabled_lines.push(rule_name, start, usize::MAX);
+        }
+
+        disabled_lines
+    }
+
+    pub(crate) fn is_line_disabled(&self, rule_name: &str, line: usize) -> bool {
+        self.ranges
+            .iter()
+            .any(|range| range.contains(rule_name, line))
+    }
+
+    /// Iterates over the regions that start at the given line and are never closed by an
+    /// `enable` directive. `None` means that the region applies to every rule.
+    pub(crate) fn iter_unclosed_regions(&self, line: usize) -> impl Iterator<Item = Option<&str>> {
+        self.ranges
+            .iter()
+            .filter(move |range| range.start == line && range.end == usize::MAX)
+            .map(|range| range.rule_name.as_deref())
+    }
+
+    /// Iterates over the rule names written in the directives, along with the line of
+    /// their directive.
+    pub(crate) fn iter_rule_names(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.rule_names
+            .iter()
+            .map(|(rule_name, line)| (rule_name.as_str(), *line))
+    }
+
+    fn push(&mut self, rule_name: Option<String>, start: usize, end: usize) {
+        self.ranges.push(DisabledRange {
+            rule_name,
+            start,
+            end,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn code_without_directives() {
+        let disabled_lines = DisabledLines::from_code("local a = 1 + 1 -- comment\n");
+
+        assert_eq!(disabled_lines, DisabledLines::default());
+    }
+
+    #[test]
+    fn disable_next_line() {
+        let disabled_lines = DisabledLines::from_code(
+            "local a = 1\n-- darklua: disable-next-line compute_expression\nlocal b = 1 + 1\nlocal c = 1 + 1\n",
+        );
+
+        assert!(!disabled_lines.is_line_disabled("compute_expression", 2));
+        assert!(disabled_lines.is_line_disabled("compute_expression", 3));
+        assert!(!disabled_lines.is_line_disabled("compute_expression", 4));
+        assert!(!disabled_lines.is_line_disabled("remove_unused_variable", 3));
+    }
+
+    #[test]
+    fn disable_next_line_for_every_rule() {
+        let disabled_lines =
+            DisabledLines::from_code("-- darklua: disable-next-line\nlocal b = 1 + 1\n");
+
+        assert!(disabled_lines.is_line_disabled("compute_expression", 2));
+        assert!(disabled_lines.is_line_disabled("remove_unused_variable", 2));
+    }
+
+    #[test]
+    fn disable_region() {
+        let disabled_lines = DisabledLines::from_code(
+            "local a = 1\n-- darklua: disable compute_expression, remove_unused_variable\nlocal b = 1\nlocal c = 1\n-- darklua: enable compute_expression\nlocal d = 1\n",
+        );
+
+        assert!(!disabled_lines.is_line_disabled("compute_expression", 1));
+        assert!(disabled_lines.is_line_disabled("compute_expression", 3));
+        assert!(disabled_lines.is_line_disabled("compute_expression", 4));
+        assert!(!disabled_lines.is_line_disabled("compute_expression", 6));
+        assert!(disabled_lines.is_line_disabled("remove_unused_variable", 6));
+    }
+
+    #[test]
+    fn enable_without_rules_closes_every_region() {
+        let disabled_lines = DisabledLines::from_code(
+            "-- darklua: disable compute_expression, remove_unused_variable\nlocal a = 1\n-- darklua: enable\nlocal b = 1\n",
+        );
+
+        assert!(disabled_lines.is_line_disabled("compute_expression", 2));
+        assert!(!disabled_lines.is_line_disabled("compute_expression", 4));
+        assert!(!disabled_lines.is_line_disabled("remove_unused_variable", 4));
+    }
+
+    #[test]
+    fn unclosed_regions() {
+        let disabled_lines = DisabledLines::from_code(
+            "-- darklua: disable compute_expression, remove_unused_variable\n-- darklua: disable\nlocal a = 1\n-- darklua: enable remove_unused_variable\n",
+        );
+
+        pretty_assertions::assert_eq!(
+            disabled_lines.iter_unclosed_regions(1).collect::<Vec<_>>(),
+            vec![Some("compute_expression")]
+        );
+        pretty_assertions::assert_eq!(
+            disabled_lines.iter_unclosed_regions(2).collect::<Vec<_>>(),
+            vec![None]
+        );
+    }
+
+    #[test]
+    fn directive_inside_string_is_ignored() {
+        let disabled_lines = DisabledLines::from_code(
+            "local a = '-- darklua: disable-next-line compute_expression'\nlocal b = 1 + 1\n",
+        );
+
+        assert!(!disabled_lines.is_line_disabled("compute_expression", 2));
+    }
+
+    #[test]
+    fn records_rule_names_of_directives() {
+        let disabled_lines = DisabledLines::from_code(
+            "-- darklua: disable-next-line compute_expression\nlocal a = 1\n-- darklua: disable rename_variables\n-- darklua: enable rename_variables\n-- darklua: keep unknown_rule\n",
+        );
+
+        pretty_assertions::assert_eq!(
+            disabled_lines.iter_rule_names().collect::<Vec<_>>(),
+            vec![
+                ("compute_expression", 1),
+                ("rename_variables", 3),
+                ("rename_variables", 4)
+            ]
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::nodes::{
+    Block, BlockTokens, DoStatement, Expression, FunctionCall, IfStatement, LastStatement, Prefix,
+    Statement, Token, Trivia, TriviaKind, Variable,
+};
+use crate::process::{
+    Evaluator, IdentifierTracker, NodePostProcessor, NodePostVisitor, NodeProcessor, PureCalls,
+    ScopePostVisitor,
+};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+};
+
+use super::verify_no_rule_properties;
+
+enum Cleanup {
+    Keep,
+    Remove,
+    Replace(Box<Statement>),
+}
+
+#[derive(Debug)]
+struct EmptyBlockCleaner {
+    evaluator: Evaluator,
+}
+
+impl EmptyBlockCleaner {
+    fn new(pure_calls: PureCalls) -> Self {
+        Self {
+            evaluator: Evaluator::default().with_pure_calls(pure_calls),
+        }
+    }
+
+    fn clean_statement(&self, statement: &mut Statement, trivia: &mut Vec<Trivia>) -> Cleanup {
+        match statement {
+            Statement::Do(do_statement) => self.clean_do_statement(do_statement, trivia),
+            Statement::If(if_statement) => self.clean_if_statement(if_statement, trivia),
+            _ => Cleanup::Keep,
+        }
+    }
+
+    fn clean_do_statement(
+        &self,
+        do_statement: &mut DoStatement,
+        trivia: &mut Vec<Trivia>,
+    ) -> Cleanup {
+        let block = do_statement.get_block();
+
+        let cleanup = if block.is_empty() {
+            Cleanup::Remove
+        } else if let Some(value) = discarded_value(block) {
+            self.discard_value(value)
+        } else {
+            Cleanup::Keep
+        };
+
+        if !matches!(cleanup, Cleanup::Keep) {
+            if let Some(tokens) = do_statement.get_tokens() {
+                collect_trivia(&tokens.r#do, trivia);
+                collect_trivia(&tokens.end, trivia);
+            }
+            if let Some(Statement::LocalAssign(local_assign)) = block.first_statement() {
+                if let Some(tokens) = local_assign.get_tokens() {
+                    collect_trivia(&tokens.local, trivia);
+                }
+            }
+        }
+
+        cleanup
+    }
+
+    fn clean_if_statement(
+        &self,
+        if_statement: &mut IfStatement,
+        trivia: &mut Vec<Trivia>,
+    ) -> Cleanup {
+        if if_statement.get_else_block().is_some_and(Block::is_empty) {
+            if_statement.take_else_block();
+
+            if let Some(tokens) = if_statement.mutate_tokens() {
+                if let Some(else_token) = tokens.r#else.take() {
+                    let mut else_trivia = Vec::new();
+                    collect_trivia(&else_token, &mut else_trivia);
+                    tokens.end.insert_leading_trivia(else_trivia);
+                }
+            }
+        }
+
+        if if_statement.get_else_block().is_none() {
+            // the last branch can be removed when its condition is pure, because no other
+            // branch would run in its place
+            while if_statement.branch_count() > 1 {
+                let branches = if_statement.get_branches();
+                let last_branch = &branches[branches.len() - 1];
+
+                if !last_branch.get_block().is_empty()
+                    || self.evaluator.has_side_effects(last_branch.get_condition())
+                {
+                    break;
+                }
+
+                let branch = if_statement
+                    .mutate_branches()
+                    .pop()
+                    .expect("the if statement should have more than one branch");
+
+                if let (Some(branch_tokens), Some(tokens)) =
+                    (branch.get_tokens(), if_statement.mutate_tokens())
+                {
+                    let mut branch_trivia = Vec::new();
+                    collect_trivia(&branch_tokens.elseif, &mut branch_trivia);
+                    collect_trivia(&branch_tokens.then, &mut branch_trivia);
+                    tokens.end.insert_leading_trivia(branch_trivia);
+                }
+            }
+        }
+
+        if if_statement.get_else_block().is_some() || if_statement.branch_count() != 1 {
+            return Cleanup::Keep;
+        }
+
+        let branch = &if_statement.get_branches()[0];
+        if !branch.get_block().is_empty() {
+            return Cleanup::Keep;
+        }
+
+        let cleanup = self.discard_value(branch.get_condition());
+
+        if !matches!(cleanup, Cleanup::Keep) {
+            if let Some(tokens) = if_statement.get_tokens() {
+                collect_trivia(&tokens.r#if, trivia);
+                collect_trivia(&tokens.then, trivia);
+                collect_trivia(&tokens.end, trivia);
+            }
+        }
+
+        cleanup
+    }
+
+    /// Finds how to evaluate a value that is not used: pure values can be removed and
+    /// function calls can be converted to call statements.
+    fn discard_value(&self, value: &Expression) -> Cleanup {
+        if !self.evaluator.has_side_effects(value) {
+            Cleanup::Remove
+        } else if let Some(call) = as_function_call(value) {
+            Cleanup::Replace(Box::new(call.clone().into()))
+        } else {
+            Cleanup::Keep
+        }
+    }
+}
+
+impl Deref for EmptyBlockCleaner {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        self.evaluator.identifier_tracker()
+    }
+}
+
+impl DerefMut for EmptyBlockCleaner {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.evaluator.mutate_identifier_tracker()
+    }
+}
+
+impl NodeProcessor for EmptyBlockCleaner {}
+
+impl NodePostProcessor for EmptyBlockCleaner {
+    // blocks are cleaned after their nested blocks, so a statement that becomes empty
+    // once its nested blocks are cleaned is removed in the same pass
+    fn process_after_block(&mut self, block: &mut Block) {
+        let mut pending_trivia = Vec::new();
+
+        block.filter_mut_statements(|statement| {
+            let keep = match self.clean_statement(statement, &mut pending_trivia) {
+                Cleanup::Keep => true,
+                Cleanup::Remove => false,
+                Cleanup::Replace(new_statement) => {
+                    *statement = *new_statement;
+                    true
+                }
+            };
+
+            if keep && !pending_trivia.is_empty() {
+                if let Some(token) = first_statement_token(statement) {
+                    token.insert_leading_trivia(pending_trivia.drain(..));
+                }
+            }
+
+            keep
+        });
+
+        if pending_trivia.is_empty() {
+            return;
+        }
+
+        if let Some(token) = block
+            .mutate_last_statement()
+            .and_then(first_last_statement_token)
+        {
+            token.insert_leading_trivia(pending_trivia);
+        } else if let Some(tokens) = block.mutate_tokens() {
+            tokens
+                .final_token
+                .get_or_insert_with(|| Token::from_content(""))
+                .insert_leading_trivia(pending_trivia);
+        } else {
+            let mut final_token = Token::from_content("");
+            final_token.insert_leading_trivia(pending_trivia);
+
+            block.set_tokens(BlockTokens {
+                semicolons: Vec::new(),
+                last_semicolon: None,
+                final_token: Some(final_token),
+            });
+        }
+    }
+}
+
+// matches blocks containing only `local _ = value`, like the ones created by the bundler
+fn discarded_value(block: &Block) -> Option<&Expression> {
+    if block.statements_len() != 1 || block.get_last_statement().is_some() {
+        return None;
+    }
+
+    match block.first_statement() {
+        Some(Statement::LocalAssign(local_assign))
+            if local_assign.variables_len() == 1 && local_assign.values_len() == 1 =>
+        {
+            let variable = local_assign.get_variables().first()?;
+            if variable.get_identifier().get_name() == "_" && !variable.has_type() {
+                local_assign.iter_values().next()
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn as_function_call(expression: &Expression) -> Option<&FunctionCall> {
+    match expression {
+        Expression::Call(call) => Some(call),
+        Expression::Parenthese(parenthese) => as_function_call(parenthese.inner_expression()),
+        _ => None,
+    }
+}
+
+// only comments are moved: each one keeps the whitespace written before it (usually its
+// indentation) and ends its own line
+fn collect_trivia(token: &Token, trivia: &mut Vec<Trivia>) {
+    collect_comments(token.iter_leading_trivia(), trivia);
+    collect_comments(token.iter_trailing_trivia(), trivia);
+}
+
+fn collect_comments<'a>(iter: impl Iterator<Item = &'a Trivia>, trivia: &mut Vec<Trivia>) {
+    let mut previous_whitespace = None;
+
+    for current in iter {
+        match current.kind() {
+            TriviaKind::Whitespace => previous_whitespace = Some(current),
+            TriviaKind::Comment => {
+                trivia.extend(previous_whitespace.take().cloned());
+                trivia.push(current.clone());
+                trivia.push(TriviaKind::Whitespace.with_content("\n"));
+            }
+        }
+    }
+}
+
+fn first_statement_token(statement: &mut Statement) -> Option<&mut Token> {
+    match statement {
+        Statement::Do(statement) => statement.mutate_tokens().map(|tokens| &mut tokens.r#do),
+        Statement::Function(statement) => {
+            statement.mutate_tokens().map(|tokens| &mut tokens.function)
+        }
+        Statement::GenericFor(statement) => {
+            statement.mutate_tokens().map(|tokens| &mut tokens.r#for)
+        }
+        Statement::If(statement) => statement.mutate_tokens().map(|tokens| &mut tokens.r#if),
+        Statement::LocalAssign(statement) => {
+            statement.mutate_tokens().map(|tokens| &mut tokens.local)
+        }
+        Statement::LocalFunction(statement) => {
+            statement.mutate_tokens().map(|tokens| &mut tokens.local)
+        }
+        Statement::NumericFor(statement) => {
+            statement.mutate_tokens().map(|tokens| &mut tokens.r#for)
+        }
+        Statement::Repeat(statement) => statement.mutate_tokens().map(|tokens| &mut tokens.repeat),
+        Statement::While(statement) => statement.mutate_tokens().map(|tokens| &mut tokens.r#while),
+        Statement::TypeDeclaration(statement) => statement.mutate_tokens().map(|tokens| {
+            if let Some(export) = tokens.export.as_mut() {
+                export
+            } else {
+                &mut tokens.r#type
+            }
+        }),
+        Statement::Assign(statement) => statement
+            .mutate_variables()
+            .first_mut()
+            .and_then(first_variable_token),
+        Statement::Call(call) => first_prefix_token(call.mutate_prefix()),
+        Statement::CompoundAssign(statement) => first_variable_token(statement.mutate_variable()),
+    }
+}
+
+fn first_variable_token(variable: &mut Variable) -> Option<&mut Token> {
+    match variable {
+        Variable::Identifier(identifier) => identifier.mutate_token(),
+        Variable::Field(field) => first_prefix_token(field.mutate_prefix()),
+        Variable::Index(index) => first_prefix_token(index.mutate_prefix()),
+    }
+}
+
+fn first_prefix_token(prefix: &mut Prefix) -> Option<&mut Token> {
+    match prefix {
+        Prefix::Identifier(identifier) => identifier.mutate_token(),
+        Prefix::Call(call) => first_prefix_token(call.mutate_prefix()),
+        Prefix::Field(field) => first_prefix_token(field.mutate_prefix()),
+        Prefix::Index(index) => first_prefix_token(index.mutate_prefix()),
+        Prefix::Parenthese(parenthese) => parenthese
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.left_parenthese),
+    }
+}
+
+fn first_last_statement_token(statement: &mut LastStatement) -> Option<&mut Token> {
+    match statement {
+        LastStatement::Break(token) | LastStatement::Continue(token) => token.as_mut(),
+        LastStatement::Return(statement) => {
+            statement.mutate_tokens().map(|tokens| &mut tokens.r#return)
+        }
+    }
+}
+
+pub const REMOVE_EMPTY_BLOCKS_RULE_NAME: &str = "remove_empty_blocks";
+
+/// A rule that removes empty do statements, empty if statements and empty else branches.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RemoveEmptyBlocks {}
+
+impl FlawlessRule for RemoveEmptyBlocks {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = EmptyBlockCleaner::new(context.pure_calls(block));
+        ScopePostVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for RemoveEmptyBlocks {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)?;
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        REMOVE_EMPTY_BLOCKS_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> RemoveEmptyBlocks {
+        RemoveEmptyBlocks::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_remove_empty_blocks", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'remove_empty_blocks',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
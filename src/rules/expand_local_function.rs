@@ -0,0 +1,126 @@
+use crate::nodes::{
+    AssignStatement, Block, FunctionExpression, LocalAssignStatement, LocalFunctionStatement,
+    Statement, Variable,
+};
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+};
+
+use std::mem;
+
+use super::verify_no_rule_properties;
+
+fn into_function_expression(local_function: &mut LocalFunctionStatement) -> FunctionExpression {
+    let mut function = FunctionExpression::new(
+        mem::take(local_function.mutate_block()),
+        mem::take(local_function.mutate_parameters()),
+        local_function.is_variadic(),
+    );
+
+    if let Some(variadic_type) = local_function.get_variadic_type() {
+        function.set_variadic_type(variadic_type.clone());
+    }
+    if let Some(return_type) = local_function.get_return_type() {
+        function.set_return_type(return_type.clone());
+    }
+    if let Some(generic_parameters) = local_function.get_generic_parameters() {
+        function.set_generic_parameters(generic_parameters.clone());
+    }
+
+    function
+}
+
+// replaces the local function with its local declaration and returns the assignment
+// of the function, so the function can still reference itself
+fn expand(statement: &mut Statement) -> Option<AssignStatement> {
+    if let Statement::LocalFunction(local_function) = statement {
+        let name = local_function.get_name().to_owned();
+        let function = into_function_expression(local_function);
+
+        *statement = LocalAssignStatement::from_variable(name.as_str()).into();
+
+        Some(AssignStatement::from_variable(
+            Variable::new(name),
+            function,
+        ))
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct Processor;
+
+impl NodeProcessor for Processor {
+    fn process_block(&mut self, block: &mut Block) {
+        // iterate in reverse so that inserting statements does not shift the next indexes
+        for index in (0..block.statements_len()).rev() {
+            let assign = block.iter_mut_statements().nth(index).and_then(expand);
+
+            if let Some(assign) = assign {
+                block.insert_statement(index + 1, assign);
+            }
+        }
+    }
+}
+
+pub const EXPAND_LOCAL_FUNCTION_RULE_NAME: &str = "expand_local_function";
+
+/// A rule that converts local function statements into a local declaration followed by
+/// the assignment of a function expression.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExpandLocalFunction {}
+
+impl FlawlessRule for ExpandLocalFunction {
+    fn flawless_process(&self, block: &mut Block, _: &Context) {
+        let mut processor = Processor;
+        DefaultVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for ExpandLocalFunction {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)?;
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        EXPAND_LOCAL_FUNCTION_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ExpandLocalFunction {
+        ExpandLocalFunction::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_expand_local_function", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'expand_local_function',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -0,0 +1,244 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::nodes::{Block, Expression, Identifier, LocalFunctionStatement};
+use crate::process::utils::{generate_identifier, identifier_permutator, CharPermutator};
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, Scope, ScopeVisitor};
+
+/// The generated identifiers of a file, shared by the contexts of every rule applied to it.
+pub(crate) type SharedGeneratedIdentifiers = Rc<RefCell<GeneratedIdentifiers>>;
+
+/// Allocates the identifiers generated by rules for the current file.
+///
+/// Each prefix has its own permutator, so the names generated for a prefix only depend on
+/// the file and on the previous names generated for that prefix. Generated identifiers are
+/// never one of the reserved names, which include the names found in the file and the
+/// identifiers generated previously.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GeneratedIdentifiers {
+    permutators: HashMap<String, CharPermutator>,
+    namespaces: HashMap<&'static str, CharPermutator>,
+    reserved: HashSet<String>,
+}
+
+impl GeneratedIdentifiers {
+    /// Prevents the given name from being generated.
+    pub(crate) fn reserve(&mut self, name: impl Into<String>) {
+        self.reserved.insert(name.into());
+    }
+
+    /// Reserves the names declared in the given block and the names of the globals it
+    /// references.
+    pub(crate) fn reserve_block(&mut self, block: &mut Block) {
+        let collector = ReservedIdentifierCollector::collect(block);
+        for name in collector.declared.into_iter().chain(collector.globals) {
+            self.reserve(name);
+        }
+    }
+
+    /// Reserves only the names of the globals referenced in the given block.
+    pub(crate) fn reserve_globals(&mut self, block: &mut Block) {
+        for name in ReservedIdentifierCollector::collect(block).globals {
+            self.reserve(name);
+        }
+    }
+
+    pub(crate) fn is_reserved(&self, name: &str) -> bool {
+        self.reserved.contains(name)
+    }
+
+    /// Generates a new identifier (`a`, `b`, `c`, ...) that is not reserved.
+    pub(crate) fn generate(&mut self) -> String {
+        self.generate_with_prefix("")
+    }
+
+    /// Generates a new identifier made of the given prefix followed by a generated suffix.
+    pub(crate) fn generate_with_prefix(&mut self, prefix: &str) -> String {
+        let permutator = self
+            .permutators
+            .entry(prefix.to_owned())
+            .or_insert_with(identifier_permutator);
+
+        let identifier = loop {
+            let identifier = format!("{}{}", prefix, generate_identifier(permutator));
+            if !self.reserved.contains(&identifier) {
+                break identifier;
+            }
+        };
+
+        self.reserved.insert(identifier.clone());
+        identifier
+    }
+
+    /// Generates a new name that is not used as a variable (like the field of a table). The
+    /// name is not reserved, so it only has to be different from the reserved names and from
+    /// the other names generated in the same namespace.
+    pub(crate) fn generate_name(&mut self, namespace: &'static str) -> String {
+        let permutator = self
+            .namespaces
+            .entry(namespace)
+            .or_insert_with(identifier_permutator);
+
+        loop {
+            let name = generate_identifier(permutator);
+            if !self.reserved.contains(&name) {
+                break name;
+            }
+        }
+    }
+
+    /// Returns the given name if it is not reserved, or the name followed by a counter
+    /// otherwise (`name_2`, `name_3`, ...). The returned name is then reserved.
+    pub(crate) fn unique_name(&mut self, name: &str) -> String {
+        let mut identifier = name.to_owned();
+        let mut counter = 1;
+
+        while self.is_reserved(&identifier) {
+            counter += 1;
+            identifier = format!("{}_{}", name, counter);
+        }
+
+        self.reserved.insert(identifier.clone());
+        identifier
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReservedIdentifierCollector {
+    identifier_tracker: IdentifierTracker,
+    declared: HashSet<String>,
+    globals: HashSet<String>,
+}
+
+impl ReservedIdentifierCollector {
+    fn collect(block: &mut Block) -> Self {
+        let mut collector = Self::default();
+        ScopeVisitor::visit_block(block, &mut collector);
+        collector
+    }
+
+    fn declare(&mut self, identifier: &mut String) {
+        self.declared.insert(identifier.clone());
+        self.identifier_tracker.insert(identifier);
+    }
+}
+
+impl Scope for ReservedIdentifierCollector {
+    fn push(&mut self) {
+        self.identifier_tracker.push();
+    }
+
+    fn pop(&mut self) {
+        self.identifier_tracker.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut String) {
+        self.declare(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+    }
+
+    fn insert_local(&mut self, identifier: &mut String, _value: Option<&mut Expression>) {
+        self.declare(identifier);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.declare(function.mutate_identifier().mutate_name());
+    }
+}
+
+impl NodeProcessor for ReservedIdentifierCollector {
+    fn process_variable_expression(&mut self, variable: &mut Identifier) {
+        let name = variable.get_name();
+        if !self.identifier_tracker.is_identifier_used(name) {
+            self.globals.insert(name.to_owned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::process::utils::is_valid_identifier;
+    use crate::Parser;
+
+    fn parse(code: &str) -> Block {
+        Parser::default().parse(code).expect("code should parse")
+    }
+
+    #[test]
+    fn generate_skips_reserved_names() {
+        let mut identifiers = GeneratedIdentifiers::default();
+        identifiers.reserve("a");
+
+        assert_eq!(identifiers.generate(), "b");
+        assert_eq!(identifiers.generate(), "c");
+    }
+
+    #[test]
+    fn generate_with_prefix_uses_a_permutator_per_prefix() {
+        let mut identifiers = GeneratedIdentifiers::default();
+
+        assert_eq!(identifiers.generate(), "a");
+        assert_eq!(identifiers.generate_with_prefix("_"), "_a");
+        assert_eq!(identifiers.generate(), "b");
+        assert_eq!(identifiers.generate_with_prefix("_"), "_b");
+    }
+
+    #[test]
+    fn generate_name_skips_reserved_names_without_reserving() {
+        let mut identifiers = GeneratedIdentifiers::default();
+        identifiers.reserve("a");
+
+        assert_eq!(identifiers.generate_name("fields"), "b");
+        assert_eq!(identifiers.generate_name("other"), "b");
+        assert_eq!(identifiers.generate_name("fields"), "c");
+        assert_eq!(identifiers.generate(), "b");
+    }
+
+    #[test]
+    fn generate_never_returns_a_keyword() {
+        let mut identifiers = GeneratedIdentifiers::default();
+
+        let names: Vec<_> = (0..4000).map(|_| identifiers.generate()).collect();
+
+        assert!(names.iter().all(|name| is_valid_identifier(name)));
+    }
+
+    #[test]
+    fn unique_name_appends_counter_when_reserved() {
+        let mut identifiers = GeneratedIdentifiers::default();
+        identifiers.reserve("modules");
+
+        assert_eq!(identifiers.unique_name("modules"), "modules_2");
+        assert_eq!(identifiers.unique_name("modules"), "modules_3");
+        assert_eq!(identifiers.unique_name("other"), "other");
+    }
+
+    #[test]
+    fn reserve_block_reserves_declared_names_and_globals() {
+        let mut identifiers = GeneratedIdentifiers::default();
+        identifiers.reserve_block(&mut parse(
+            "local a = b\nlocal function c(d) return d end\nfor e in f do end",
+        ));
+
+        for name in ["a", "b", "c", "d", "e", "f"] {
+            assert!(identifiers.is_reserved(name), "`{}` is not reserved", name);
+        }
+        assert_eq!(identifiers.generate(), "g");
+    }
+
+    #[test]
+    fn reserve_globals_ignores_declared_names() {
+        let mut identifiers = GeneratedIdentifiers::default();
+        identifiers.reserve_globals(&mut parse("local a = b\nprint(a)"));
+
+        assert!(!identifiers.is_reserved("a"));
+        assert!(identifiers.is_reserved("b"));
+        assert!(identifiers.is_reserved("print"));
+    }
+}
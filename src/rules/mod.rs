@@ -7,18 +7,26 @@
 mod append_text_comment;
 pub mod bundle;
 mod call_parens;
+mod collapse_function_assign;
 mod compute_expression;
 mod configuration_error;
+mod convert_generalized_iteration;
 mod convert_index_to_field;
 mod convert_luau_number;
+mod convert_method_definitions;
 mod convert_require;
 mod convert_square_root_call;
+mod disabled_lines;
+mod empty_blocks;
 mod empty_do;
+mod expand_local_function;
 mod filter_early_return;
+mod generated_identifiers;
 mod group_local;
 mod inject_value;
 mod method_def;
 mod no_local_function;
+mod normalize_whitespace;
 mod remove_assertions;
 mod remove_call_match;
 mod remove_comments;
@@ -30,31 +38,41 @@ mod remove_if_expression;
 mod remove_interpolated_string;
 mod remove_method_call;
 mod remove_nil_declarations;
+mod remove_redundant_return;
 mod remove_spaces;
 mod remove_types;
 mod remove_unused_variable;
 mod rename_variables;
 mod replace_referenced_tokens;
-pub(crate) mod require;
+pub mod require;
 mod rule_property;
+mod rule_registry;
+mod rule_warning;
 mod shift_token_line;
+mod string_char;
 mod unused_if_branch;
 mod unused_while;
 
 pub use append_text_comment::*;
 pub use call_parens::*;
+pub use collapse_function_assign::*;
 pub use compute_expression::*;
 pub use configuration_error::RuleConfigurationError;
+pub use convert_generalized_iteration::*;
 pub use convert_index_to_field::*;
 pub use convert_luau_number::*;
+pub use convert_method_definitions::*;
 pub use convert_require::*;
 pub use convert_square_root_call::*;
+pub use empty_blocks::*;
 pub use empty_do::*;
+pub use expand_local_function::*;
 pub use filter_early_return::*;
 pub use group_local::*;
 pub use inject_value::*;
 pub use method_def::*;
 pub use no_local_function::*;
+pub use normalize_whitespace::*;
 pub use remove_assertions::*;
 pub use remove_comments::*;
 pub use remove_compound_assign::*;
@@ -65,19 +83,30 @@ pub use remove_if_expression::*;
 pub use remove_interpolated_string::*;
 pub use remove_method_call::*;
 pub use remove_nil_declarations::*;
+pub use remove_redundant_return::*;
 pub use remove_spaces::*;
 pub use remove_types::*;
 pub use remove_unused_variable::*;
 pub use rename_variables::*;
 pub(crate) use replace_referenced_tokens::*;
-pub use require::PathRequireMode;
+pub use require::{
+    AutoRequireMode, DetectedRequireMode, PathExtensionStyle, PathRequireMode, PathStyle,
+    QuoteStyle,
+};
 pub use rule_property::*;
+pub use rule_registry::{register_rule, RuleFactory};
+pub(crate) use rule_registry::{verify_rule_name, with_scoped_rules};
+pub use rule_warning::{RuleWarning, WarningCode};
 pub(crate) use shift_token_line::*;
+pub use string_char::*;
 pub use unused_if_branch::*;
 pub use unused_while::*;
 
 use crate::nodes::Block;
-use crate::Resources;
+use crate::process::{PureCalls, PureFunction};
+use crate::{DarkluaError, Resources};
+pub(crate) use disabled_lines::DisabledLines;
+pub(crate) use generated_identifiers::{GeneratedIdentifiers, SharedGeneratedIdentifiers};
 
 use serde::de::{self, MapAccess, Visitor};
 use serde::ser::SerializeMap;
@@ -98,6 +127,8 @@ pub struct ContextBuilder<'a, 'resources, 'code> {
     original_code: &'code str,
     blocks: HashMap<PathBuf, &'a Block>,
     project_location: Option<PathBuf>,
+    generated_identifiers: Option<SharedGeneratedIdentifiers>,
+    pure_functions: Vec<PureFunction>,
 }
 
 impl<'a, 'resources, 'code> ContextBuilder<'a, 'resources, 'code> {
@@ -113,6 +144,8 @@ impl<'a, 'resources, 'code> ContextBuilder<'a, 'resources, 'code> {
             original_code,
             blocks: Default::default(),
             project_location: None,
+            generated_identifiers: None,
+            pure_functions: Vec::new(),
         }
     }
 
@@ -122,6 +155,22 @@ impl<'a, 'resources, 'code> ContextBuilder<'a, 'resources, 'code> {
         self
     }
 
+    /// Shares the given generated identifiers with the context, so that every rule applied
+    /// to the same file draws its generated names from them.
+    pub(crate) fn with_generated_identifiers(
+        mut self,
+        generated_identifiers: SharedGeneratedIdentifiers,
+    ) -> Self {
+        self.generated_identifiers = Some(generated_identifiers);
+        self
+    }
+
+    /// Sets the functions that rules can consider free of side effects.
+    pub(crate) fn with_pure_functions(mut self, pure_functions: Vec<PureFunction>) -> Self {
+        self.pure_functions = pure_functions;
+        self
+    }
+
     /// Builds the final context with all configured options.
     pub fn build(self) -> Context<'a, 'resources, 'code> {
         Context {
@@ -131,6 +180,12 @@ impl<'a, 'resources, 'code> ContextBuilder<'a, 'resources, 'code> {
             blocks: self.blocks,
             project_location: self.project_location,
             dependencies: Default::default(),
+            warnings: Default::default(),
+            detected_require_modes: Default::default(),
+            error_causes: Default::default(),
+            disabled_lines: Default::default(),
+            generated_identifiers: self.generated_identifiers.unwrap_or_default(),
+            pure_functions: self.pure_functions,
         }
     }
 
@@ -152,6 +207,12 @@ pub struct Context<'a, 'resources, 'code> {
     blocks: HashMap<PathBuf, &'a Block>,
     project_location: Option<PathBuf>,
     dependencies: std::cell::RefCell<Vec<PathBuf>>,
+    warnings: std::cell::RefCell<Vec<RuleWarning>>,
+    detected_require_modes: std::cell::RefCell<Vec<DetectedRequireMode>>,
+    error_causes: std::cell::RefCell<Vec<DarkluaError>>,
+    disabled_lines: std::cell::OnceCell<DisabledLines>,
+    generated_identifiers: SharedGeneratedIdentifiers,
+    pure_functions: Vec<PureFunction>,
 }
 
 impl Context<'_, '_, '_> {
@@ -177,6 +238,92 @@ impl Context<'_, '_, '_> {
         }
     }
 
+    /// Adds a non-fatal warning to the context.
+    ///
+    /// The path and line can be used to point to the code that triggered the warning.
+    pub fn add_warning(
+        &self,
+        code: WarningCode,
+        message: impl Into<String>,
+        path: Option<PathBuf>,
+        line: Option<usize>,
+    ) {
+        let mut warning = RuleWarning::new(code, message);
+        if let Some(path) = path {
+            warning = warning.with_path(path);
+        }
+        if let Some(line) = line {
+            warning = warning.with_line(line);
+        }
+        self.push_warning(warning);
+    }
+
+    pub(crate) fn push_warning(&self, warning: RuleWarning) {
+        if let Ok(mut warnings) = self.warnings.try_borrow_mut() {
+            log::trace!("add warning {}", warning);
+            warnings.push(warning);
+        } else {
+            log::warn!("unable to submit warning (internal error)");
+        }
+    }
+
+    /// Returns the warnings emitted with this context.
+    pub fn warnings(&self) -> Vec<RuleWarning> {
+        self.warnings.borrow().clone()
+    }
+
+    pub(crate) fn add_detected_require_mode(&self, mode: DetectedRequireMode) {
+        if let Ok(mut modes) = self.detected_require_modes.try_borrow_mut() {
+            modes.push(mode);
+        } else {
+            log::warn!("unable to submit detected require mode (internal error)");
+        }
+    }
+
+    /// Returns the require modes detected by the `auto` require mode with this context.
+    pub fn detected_require_modes(&self) -> Vec<DetectedRequireMode> {
+        self.detected_require_modes.borrow().clone()
+    }
+
+    /// Records an error that caused the current rule to fail, so that it can be
+    /// attached to the rule error.
+    pub(crate) fn push_error_cause(&self, error: DarkluaError) {
+        if let Ok(mut error_causes) = self.error_causes.try_borrow_mut() {
+            error_causes.push(error);
+        } else {
+            log::warn!("unable to submit error cause (internal error)");
+        }
+    }
+
+    pub(crate) fn take_error_causes(&self) -> Vec<DarkluaError> {
+        self.error_causes.take()
+    }
+
+    /// Returns true if the rule is disabled at the given line of the current file, with a
+    /// `-- darklua: disable-next-line <rules>` comment or inside a region that starts with a
+    /// `-- darklua: disable <rules>` comment and ends with `-- darklua: enable <rules>`.
+    ///
+    /// Since nodes only have line numbers when tokens are preserved, rules can only
+    /// honor these comments when the code is generated with the `retain_lines` generator.
+    pub fn is_line_disabled(&self, rule_name: &str, line: usize) -> bool {
+        self.disabled_lines
+            .get_or_init(|| DisabledLines::from_code(self.original_code))
+            .is_line_disabled(rule_name, line)
+    }
+
+    /// Returns the identifiers generated by rules for the current file, so that rules do
+    /// not generate names that collide with each other or with the names of the file.
+    pub(crate) fn generated_identifiers(&self) -> &SharedGeneratedIdentifiers {
+        &self.generated_identifiers
+    }
+
+    /// Returns the calls that the evaluators of rules can consider free of side effects in
+    /// the given block: the `pure_functions` from the configuration, `table.freeze` and
+    /// `setmetatable`.
+    pub(crate) fn pure_calls(&self, block: &mut Block) -> PureCalls {
+        PureCalls::new(&self.pure_functions, block)
+    }
+
     /// Consumes the context and returns an iterator over all file dependencies.
     pub fn into_dependencies(self) -> impl Iterator<Item = PathBuf> {
         self.dependencies.into_inner().into_iter()
@@ -293,25 +440,34 @@ pub fn get_default_rules() -> Vec<Box<dyn Rule>> {
 pub fn get_all_rule_names() -> Vec<&'static str> {
     vec![
         APPEND_TEXT_COMMENT_RULE_NAME,
+        COLLAPSE_FUNCTION_ASSIGN_RULE_NAME,
         COMPUTE_EXPRESSIONS_RULE_NAME,
+        CONVERT_GENERALIZED_ITERATION_RULE_NAME,
         CONVERT_INDEX_TO_FIELD_RULE_NAME,
         CONVERT_LOCAL_FUNCTION_TO_ASSIGN_RULE_NAME,
         CONVERT_LUAU_NUMBER_RULE_NAME,
+        CONVERT_METHOD_DEFINITIONS_RULE_NAME,
         CONVERT_REQUIRE_RULE_NAME,
         CONVERT_SQUARE_ROOT_CALL_RULE_NAME,
+        EXPAND_LOCAL_FUNCTION_RULE_NAME,
+        EXPAND_STRINGS_TO_CHAR_RULE_NAME,
         FILTER_AFTER_EARLY_RETURN_RULE_NAME,
+        FOLD_STRING_CHAR_RULE_NAME,
         GROUP_LOCAL_ASSIGNMENT_RULE_NAME,
         INJECT_GLOBAL_VALUE_RULE_NAME,
+        NORMALIZE_WHITESPACE_RULE_NAME,
         REMOVE_ASSERTIONS_RULE_NAME,
         REMOVE_COMMENTS_RULE_NAME,
         REMOVE_COMPOUND_ASSIGNMENT_RULE_NAME,
         REMOVE_DEBUG_PROFILING_RULE_NAME,
+        REMOVE_EMPTY_BLOCKS_RULE_NAME,
         REMOVE_EMPTY_DO_RULE_NAME,
         REMOVE_FUNCTION_CALL_PARENS_RULE_NAME,
         REMOVE_INTERPOLATED_STRING_RULE_NAME,
         REMOVE_METHOD_CALL_RULE_NAME,
         REMOVE_METHOD_DEFINITION_RULE_NAME,
         REMOVE_NIL_DECLARATION_RULE_NAME,
+        REMOVE_REDUNDANT_RETURN_RULE_NAME,
         REMOVE_SPACES_RULE_NAME,
         REMOVE_TYPES_RULE_NAME,
         REMOVE_UNUSED_IF_BRANCH_RULE_NAME,
@@ -323,46 +479,76 @@ pub fn get_all_rule_names() -> Vec<&'static str> {
     ]
 }
 
+fn create_builtin_rule(name: &str) -> Option<Box<dyn Rule>> {
+    let rule: Box<dyn Rule> = match name {
+        APPEND_TEXT_COMMENT_RULE_NAME => Box::<AppendTextComment>::default(),
+        COLLAPSE_FUNCTION_ASSIGN_RULE_NAME => Box::<CollapseFunctionAssign>::default(),
+        COMPUTE_EXPRESSIONS_RULE_NAME => Box::<ComputeExpression>::default(),
+        CONVERT_GENERALIZED_ITERATION_RULE_NAME => Box::<ConvertGeneralizedIteration>::default(),
+        CONVERT_INDEX_TO_FIELD_RULE_NAME => Box::<ConvertIndexToField>::default(),
+        CONVERT_LOCAL_FUNCTION_TO_ASSIGN_RULE_NAME => {
+            Box::<ConvertLocalFunctionToAssign>::default()
+        }
+        CONVERT_LUAU_NUMBER_RULE_NAME => Box::<ConvertLuauNumber>::default(),
+        CONVERT_METHOD_DEFINITIONS_RULE_NAME => Box::<ConvertMethodDefinitions>::default(),
+        CONVERT_REQUIRE_RULE_NAME => Box::<ConvertRequire>::default(),
+        CONVERT_SQUARE_ROOT_CALL_RULE_NAME => Box::<ConvertSquareRootCall>::default(),
+        EXPAND_LOCAL_FUNCTION_RULE_NAME => Box::<ExpandLocalFunction>::default(),
+        EXPAND_STRINGS_TO_CHAR_RULE_NAME => Box::<ExpandStringsToChar>::default(),
+        FILTER_AFTER_EARLY_RETURN_RULE_NAME => Box::<FilterAfterEarlyReturn>::default(),
+        FOLD_STRING_CHAR_RULE_NAME => Box::<FoldStringChar>::default(),
+        GROUP_LOCAL_ASSIGNMENT_RULE_NAME => Box::<GroupLocalAssignment>::default(),
+        INJECT_GLOBAL_VALUE_RULE_NAME => Box::<InjectGlobalValue>::default(),
+        NORMALIZE_WHITESPACE_RULE_NAME => Box::<NormalizeWhitespace>::default(),
+        REMOVE_ASSERTIONS_RULE_NAME => Box::<RemoveAssertions>::default(),
+        REMOVE_COMMENTS_RULE_NAME => Box::<RemoveComments>::default(),
+        REMOVE_COMPOUND_ASSIGNMENT_RULE_NAME => Box::<RemoveCompoundAssignment>::default(),
+        REMOVE_DEBUG_PROFILING_RULE_NAME => Box::<RemoveDebugProfiling>::default(),
+        REMOVE_EMPTY_BLOCKS_RULE_NAME => Box::<RemoveEmptyBlocks>::default(),
+        REMOVE_EMPTY_DO_RULE_NAME => Box::<RemoveEmptyDo>::default(),
+        REMOVE_FLOOR_DIVISION_RULE_NAME => Box::<RemoveFloorDivision>::default(),
+        REMOVE_FUNCTION_CALL_PARENS_RULE_NAME => Box::<RemoveFunctionCallParens>::default(),
+        REMOVE_INTERPOLATED_STRING_RULE_NAME => Box::<RemoveInterpolatedString>::default(),
+        REMOVE_METHOD_CALL_RULE_NAME => Box::<RemoveMethodCall>::default(),
+        REMOVE_METHOD_DEFINITION_RULE_NAME => Box::<RemoveMethodDefinition>::default(),
+        REMOVE_NIL_DECLARATION_RULE_NAME => Box::<RemoveNilDeclaration>::default(),
+        REMOVE_REDUNDANT_RETURN_RULE_NAME => Box::<RemoveRedundantReturn>::default(),
+        REMOVE_SPACES_RULE_NAME => Box::<RemoveSpaces>::default(),
+        REMOVE_TYPES_RULE_NAME => Box::<RemoveTypes>::default(),
+        REMOVE_UNUSED_IF_BRANCH_RULE_NAME => Box::<RemoveUnusedIfBranch>::default(),
+        REMOVE_UNUSED_VARIABLE_RULE_NAME => Box::<RemoveUnusedVariable>::default(),
+        REMOVE_UNUSED_WHILE_RULE_NAME => Box::<RemoveUnusedWhile>::default(),
+        RENAME_VARIABLES_RULE_NAME => Box::<RenameVariables>::default(),
+        REMOVE_IF_EXPRESSION_RULE_NAME => Box::<RemoveIfExpression>::default(),
+        REMOVE_CONTINUE_RULE_NAME => Box::<RemoveContinue>::default(),
+        _ => return None,
+    };
+
+    Some(rule)
+}
+
+fn create_rule(name: &str, properties: RuleProperties) -> Result<Box<dyn Rule>, String> {
+    if let Some(factory) = rule_registry::find_rule_factory(name) {
+        return factory(properties).map_err(|err| err.to_string());
+    }
+
+    let mut rule =
+        create_builtin_rule(name).ok_or_else(|| format!("invalid rule name: {}", name))?;
+
+    rule.configure(properties).map_err(|err| err.to_string())?;
+
+    Ok(rule)
+}
+
 impl FromStr for Box<dyn Rule> {
     type Err = String;
 
     fn from_str(string: &str) -> Result<Self, Self::Err> {
-        let rule: Box<dyn Rule> = match string {
-            APPEND_TEXT_COMMENT_RULE_NAME => Box::<AppendTextComment>::default(),
-            COMPUTE_EXPRESSIONS_RULE_NAME => Box::<ComputeExpression>::default(),
-            CONVERT_INDEX_TO_FIELD_RULE_NAME => Box::<ConvertIndexToField>::default(),
-            CONVERT_LOCAL_FUNCTION_TO_ASSIGN_RULE_NAME => {
-                Box::<ConvertLocalFunctionToAssign>::default()
-            }
-            CONVERT_LUAU_NUMBER_RULE_NAME => Box::<ConvertLuauNumber>::default(),
-            CONVERT_REQUIRE_RULE_NAME => Box::<ConvertRequire>::default(),
-            CONVERT_SQUARE_ROOT_CALL_RULE_NAME => Box::<ConvertSquareRootCall>::default(),
-            FILTER_AFTER_EARLY_RETURN_RULE_NAME => Box::<FilterAfterEarlyReturn>::default(),
-            GROUP_LOCAL_ASSIGNMENT_RULE_NAME => Box::<GroupLocalAssignment>::default(),
-            INJECT_GLOBAL_VALUE_RULE_NAME => Box::<InjectGlobalValue>::default(),
-            REMOVE_ASSERTIONS_RULE_NAME => Box::<RemoveAssertions>::default(),
-            REMOVE_COMMENTS_RULE_NAME => Box::<RemoveComments>::default(),
-            REMOVE_COMPOUND_ASSIGNMENT_RULE_NAME => Box::<RemoveCompoundAssignment>::default(),
-            REMOVE_DEBUG_PROFILING_RULE_NAME => Box::<RemoveDebugProfiling>::default(),
-            REMOVE_EMPTY_DO_RULE_NAME => Box::<RemoveEmptyDo>::default(),
-            REMOVE_FLOOR_DIVISION_RULE_NAME => Box::<RemoveFloorDivision>::default(),
-            REMOVE_FUNCTION_CALL_PARENS_RULE_NAME => Box::<RemoveFunctionCallParens>::default(),
-            REMOVE_INTERPOLATED_STRING_RULE_NAME => Box::<RemoveInterpolatedString>::default(),
-            REMOVE_METHOD_CALL_RULE_NAME => Box::<RemoveMethodCall>::default(),
-            REMOVE_METHOD_DEFINITION_RULE_NAME => Box::<RemoveMethodDefinition>::default(),
-            REMOVE_NIL_DECLARATION_RULE_NAME => Box::<RemoveNilDeclaration>::default(),
-            REMOVE_SPACES_RULE_NAME => Box::<RemoveSpaces>::default(),
-            REMOVE_TYPES_RULE_NAME => Box::<RemoveTypes>::default(),
-            REMOVE_UNUSED_IF_BRANCH_RULE_NAME => Box::<RemoveUnusedIfBranch>::default(),
-            REMOVE_UNUSED_VARIABLE_RULE_NAME => Box::<RemoveUnusedVariable>::default(),
-            REMOVE_UNUSED_WHILE_RULE_NAME => Box::<RemoveUnusedWhile>::default(),
-            RENAME_VARIABLES_RULE_NAME => Box::<RenameVariables>::default(),
-            REMOVE_IF_EXPRESSION_RULE_NAME => Box::<RemoveIfExpression>::default(),
-            REMOVE_CONTINUE_RULE_NAME => Box::<RemoveContinue>::default(),
-            _ => return Err(format!("invalid rule name: {}", string)),
-        };
-
-        Ok(rule)
+        if let Some(factory) = rule_registry::find_rule_factory(string) {
+            return factory(RuleProperties::new()).map_err(|err| err.to_string());
+        }
+
+        create_builtin_rule(string).ok_or_else(|| format!("invalid rule name: {}", string))
     }
 }
 
@@ -407,12 +593,7 @@ impl<'de> Deserialize<'de> for Box<dyn Rule> {
             where
                 E: de::Error,
             {
-                let mut rule: Self::Value = FromStr::from_str(value).map_err(de::Error::custom)?;
-
-                rule.configure(RuleProperties::new())
-                    .map_err(de::Error::custom)?;
-
-                Ok(rule)
+                create_rule(value, RuleProperties::new()).map_err(de::Error::custom)
             }
 
             fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
@@ -445,12 +626,7 @@ impl<'de> Deserialize<'de> for Box<dyn Rule> {
                 }
 
                 if let Some(rule_name) = rule_name {
-                    let mut rule: Self::Value =
-                        FromStr::from_str(&rule_name).map_err(de::Error::custom)?;
-
-                    rule.configure(properties).map_err(de::Error::custom)?;
-
-                    Ok(rule)
+                    create_rule(&rule_name, properties).map_err(de::Error::custom)
                 } else {
                     Err(de::Error::missing_field("rule"))
                 }
@@ -0,0 +1,426 @@
+use crate::nodes::*;
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+};
+
+#[derive(Debug)]
+struct NormalizeWhitespaceProcessor<'a> {
+    original_code: &'a str,
+    tab_size: Option<usize>,
+}
+
+impl<'a> NormalizeWhitespaceProcessor<'a> {
+    fn new(original_code: &'a str, tab_size: Option<usize>) -> Self {
+        Self {
+            original_code,
+            tab_size,
+        }
+    }
+
+    fn normalize_trivia(&self, trivia: &mut Vec<Trivia>, side: TriviaSide) {
+        // leading trivia always start at the beginning of a line
+        let mut line_start = side == TriviaSide::Leading;
+
+        for trivia in trivia.iter_mut() {
+            let content = trivia.read(self.original_code);
+
+            match trivia.kind() {
+                TriviaKind::Comment => {
+                    line_start = false;
+                }
+                TriviaKind::Whitespace => {
+                    let normalized = self.normalize_whitespace(content, line_start);
+                    line_start =
+                        normalized.ends_with('\n') || (line_start && normalized.is_empty());
+
+                    if normalized != content {
+                        *trivia = TriviaKind::Whitespace.with_content(normalized);
+                    }
+                }
+            }
+        }
+
+        trivia.retain(|trivia| !trivia.read(self.original_code).is_empty());
+    }
+
+    fn normalize_whitespace(&self, content: &str, line_start: bool) -> String {
+        let mut normalized = String::new();
+        let mut lines = content.split('\n').peekable();
+        let mut line_start = line_start;
+
+        while let Some(line) = lines.next() {
+            if lines.peek().is_some() {
+                // the whitespace is at the end of the line, only the line ending is kept
+                if line.ends_with('\r') {
+                    normalized.push('\r');
+                }
+                normalized.push('\n');
+                line_start = true;
+            } else if line_start {
+                normalized.push_str(&self.indent(line));
+            } else if !line.is_empty() {
+                normalized.push(' ');
+            }
+        }
+
+        normalized
+    }
+
+    fn indent(&self, indentation: &str) -> String {
+        match self.tab_size {
+            Some(tab_size) => indentation.replace('\t', &" ".repeat(tab_size)),
+            None => indentation.to_owned(),
+        }
+    }
+
+    fn strip_end(&self, trivia: &mut [Trivia]) {
+        if let Some(last) = trivia.last_mut() {
+            if last.kind() == TriviaKind::Whitespace {
+                let content = last.read(self.original_code);
+                let stripped = content.trim_end_matches([' ', '\t']);
+
+                if stripped.len() != content.len() {
+                    *last = TriviaKind::Whitespace.with_content(stripped.to_owned());
+                }
+            }
+        }
+    }
+}
+
+impl NodeProcessor for NormalizeWhitespaceProcessor<'_> {
+    fn process_block(&mut self, block: &mut Block) {
+        block.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+
+        // nothing follows the end of the file, so its indentation is trailing whitespace
+        if let Some(final_token) = block
+            .mutate_tokens()
+            .and_then(|tokens| tokens.final_token.as_mut())
+        {
+            final_token.mutate_trivia(|trivia, _| self.strip_end(trivia));
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        call.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+        call.mutate_arguments()
+            .mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        assign.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        assign.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_do_statement(&mut self, statement: &mut DoStatement) {
+        statement.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        function.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
+        generic_for.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_if_statement(&mut self, if_statement: &mut IfStatement) {
+        if_statement.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_last_statement(&mut self, statement: &mut LastStatement) {
+        match statement {
+            LastStatement::Break(token) | LastStatement::Continue(token) => {
+                if let Some(token) = token {
+                    token.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+                }
+            }
+            LastStatement::Return(statement) => {
+                statement.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side))
+            }
+        }
+    }
+
+    fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
+        assign.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        function.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_numeric_for_statement(&mut self, numeric_for: &mut NumericForStatement) {
+        numeric_for.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_repeat_statement(&mut self, repeat: &mut RepeatStatement) {
+        repeat.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_while_statement(&mut self, statement: &mut WhileStatement) {
+        statement.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_type_declaration(&mut self, type_declaration: &mut TypeDeclarationStatement) {
+        type_declaration.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_expression(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::False(token)
+            | Expression::Nil(token)
+            | Expression::True(token)
+            | Expression::VariableArguments(token) => {
+                if let Some(token) = token {
+                    token.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side))
+                }
+            }
+            Expression::Binary(_)
+            | Expression::Call(_)
+            | Expression::Field(_)
+            | Expression::Function(_)
+            | Expression::Identifier(_)
+            | Expression::If(_)
+            | Expression::Index(_)
+            | Expression::Number(_)
+            | Expression::Parenthese(_)
+            | Expression::String(_)
+            | Expression::InterpolatedString(_)
+            | Expression::Table(_)
+            | Expression::Unary(_)
+            | Expression::TypeCast(_) => {}
+        }
+    }
+
+    fn process_binary_expression(&mut self, binary: &mut BinaryExpression) {
+        binary.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_field_expression(&mut self, field: &mut FieldExpression) {
+        field.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        function.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_if_expression(&mut self, if_expression: &mut IfExpression) {
+        if_expression.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        identifier.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_index_expression(&mut self, index: &mut IndexExpression) {
+        index.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_number_expression(&mut self, number: &mut NumberExpression) {
+        number.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_parenthese_expression(&mut self, expression: &mut ParentheseExpression) {
+        expression.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        string.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_table_expression(&mut self, table: &mut TableExpression) {
+        table.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_unary_expression(&mut self, unary: &mut UnaryExpression) {
+        unary.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_interpolated_string_expression(
+        &mut self,
+        string: &mut InterpolatedStringExpression,
+    ) {
+        string.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_type_cast_expression(&mut self, type_cast: &mut TypeCastExpression) {
+        type_cast.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_prefix_expression(&mut self, _: &mut Prefix) {}
+
+    fn process_type(&mut self, r#type: &mut Type) {
+        match r#type {
+            Type::True(token) | Type::False(token) | Type::Nil(token) => {
+                if let Some(token) = token {
+                    token.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn process_type_name(&mut self, type_name: &mut TypeName) {
+        type_name.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_type_field(&mut self, type_field: &mut TypeField) {
+        type_field.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_string_type(&mut self, string_type: &mut StringType) {
+        string_type.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_array_type(&mut self, array: &mut ArrayType) {
+        array.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_table_type(&mut self, table: &mut TableType) {
+        table.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_expression_type(&mut self, expression_type: &mut ExpressionType) {
+        expression_type.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_parenthese_type(&mut self, parenthese_type: &mut ParentheseType) {
+        parenthese_type.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_function_type(&mut self, function_type: &mut FunctionType) {
+        function_type.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_optional_type(&mut self, optional: &mut OptionalType) {
+        optional.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_intersection_type(&mut self, intersection: &mut IntersectionType) {
+        intersection.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_union_type(&mut self, union: &mut UnionType) {
+        union.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_type_pack(&mut self, type_pack: &mut TypePack) {
+        type_pack.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_generic_type_pack(&mut self, generic_type_pack: &mut GenericTypePack) {
+        generic_type_pack.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+
+    fn process_variadic_type_pack(&mut self, variadic_type_pack: &mut VariadicTypePack) {
+        variadic_type_pack.mutate_trivia(|trivia, side| self.normalize_trivia(trivia, side));
+    }
+}
+
+pub const NORMALIZE_WHITESPACE_RULE_NAME: &str = "normalize_whitespace";
+
+/// A rule that normalizes the whitespaces associated with AST nodes, without changing the line
+/// of any token.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct NormalizeWhitespace {
+    tab_size: Option<usize>,
+}
+
+impl NormalizeWhitespace {
+    /// Converts each tab into the given number of spaces.
+    pub fn with_tab_size(mut self, tab_size: usize) -> Self {
+        self.tab_size = Some(tab_size);
+        self
+    }
+}
+
+impl FlawlessRule for NormalizeWhitespace {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor =
+            NormalizeWhitespaceProcessor::new(context.original_code(), self.tab_size);
+        DefaultVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for NormalizeWhitespace {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "tab_size" => {
+                    self.tab_size = Some(value.expect_usize(&key)?);
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        NORMALIZE_WHITESPACE_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if let Some(tab_size) = self.tab_size {
+            properties.insert("tab_size".to_owned(), tab_size.into());
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> NormalizeWhitespace {
+        NormalizeWhitespace::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_normalize_whitespace", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_tab_size() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_tab_size(4));
+
+        assert_json_snapshot!("normalize_whitespace_with_tab_size", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'normalize_whitespace',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+
+    #[test]
+    fn configure_with_invalid_tab_size_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'normalize_whitespace',
+            tab_size: "4",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unsigned integer expected for field 'tab_size'"
+        );
+    }
+}
@@ -1,9 +1,12 @@
+use std::mem;
+
 use regex::Regex;
 
 use crate::nodes::*;
 use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
 use crate::rules::{
     Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyValue,
 };
 
 #[derive(Debug, Default)]
@@ -233,93 +236,119 @@ impl NodeProcessor for RemoveCommentProcessor {
 #[derive(Debug)]
 pub(crate) struct FilterCommentProcessor<'a> {
     original_code: &'a str,
-    except: &'a Vec<Regex>,
+    keep_patterns: &'a Vec<Regex>,
 }
 
 impl<'a> FilterCommentProcessor<'a> {
-    pub(crate) fn new(original_code: &'a str, except: &'a Vec<Regex>) -> Self {
+    pub(crate) fn new(original_code: &'a str, keep_patterns: &'a Vec<Regex>) -> Self {
         Self {
             original_code,
-            except,
+            keep_patterns,
         }
     }
 
-    fn ignore_trivia(&self, trivia: &Trivia) -> bool {
+    fn filter_comments(&self, trivia: &mut Vec<Trivia>) {
+        *trivia = mem::take(trivia)
+            .into_iter()
+            .filter_map(|trivia| match trivia.kind() {
+                TriviaKind::Comment => self.replace_trivia(&trivia),
+                TriviaKind::Whitespace => Some(trivia),
+            })
+            .collect();
+    }
+
+    fn replace_trivia(&self, trivia: &Trivia) -> Option<Trivia> {
         let content = trivia.read(self.original_code);
-        self.except.iter().any(|pattern| pattern.is_match(content))
+
+        if self
+            .keep_patterns
+            .iter()
+            .any(|pattern| pattern.is_match(content))
+        {
+            return Some(trivia.clone());
+        }
+
+        // multi-line comments are replaced with the same number of lines, so that the
+        // generators that retain lines still produce code aligned with the original code
+        let line_count = content.matches('\n').count();
+        if line_count > 0 {
+            Some(TriviaKind::Whitespace.with_content("\n".repeat(line_count)))
+        } else {
+            None
+        }
     }
 }
 
 impl NodeProcessor for FilterCommentProcessor<'_> {
     fn process_block(&mut self, block: &mut Block) {
-        block.filter_comments(|trivia| self.ignore_trivia(trivia));
+        block.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_function_call(&mut self, call: &mut FunctionCall) {
-        call.filter_comments(|trivia| self.ignore_trivia(trivia));
+        call.mutate_trivia(|trivia, _| self.filter_comments(trivia));
         call.mutate_arguments()
-            .filter_comments(|trivia| self.ignore_trivia(trivia));
+            .mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
-        assign.filter_comments(|trivia| self.ignore_trivia(trivia));
+        assign.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
-        assign.filter_comments(|trivia| self.ignore_trivia(trivia));
+        assign.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_do_statement(&mut self, statement: &mut DoStatement) {
-        statement.filter_comments(|trivia| self.ignore_trivia(trivia));
+        statement.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_function_statement(&mut self, function: &mut FunctionStatement) {
-        function.filter_comments(|trivia| self.ignore_trivia(trivia));
+        function.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
-        generic_for.filter_comments(|trivia| self.ignore_trivia(trivia));
+        generic_for.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_if_statement(&mut self, if_statement: &mut IfStatement) {
-        if_statement.filter_comments(|trivia| self.ignore_trivia(trivia));
+        if_statement.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_last_statement(&mut self, statement: &mut LastStatement) {
         match statement {
             LastStatement::Break(token) | LastStatement::Continue(token) => {
                 if let Some(token) = token {
-                    token.filter_comments(|trivia| self.ignore_trivia(trivia));
+                    token.mutate_trivia(|trivia, _| self.filter_comments(trivia));
                 }
             }
             LastStatement::Return(statement) => {
-                statement.filter_comments(|trivia| self.ignore_trivia(trivia))
+                statement.mutate_trivia(|trivia, _| self.filter_comments(trivia))
             }
         }
     }
 
     fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
-        assign.filter_comments(|trivia| self.ignore_trivia(trivia));
+        assign.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
-        function.filter_comments(|trivia| self.ignore_trivia(trivia));
+        function.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_numeric_for_statement(&mut self, numeric_for: &mut NumericForStatement) {
-        numeric_for.filter_comments(|trivia| self.ignore_trivia(trivia));
+        numeric_for.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_repeat_statement(&mut self, repeat: &mut RepeatStatement) {
-        repeat.filter_comments(|trivia| self.ignore_trivia(trivia));
+        repeat.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_while_statement(&mut self, statement: &mut WhileStatement) {
-        statement.filter_comments(|trivia| self.ignore_trivia(trivia));
+        statement.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_type_declaration(&mut self, type_declaration: &mut TypeDeclarationStatement) {
-        type_declaration.filter_comments(|trivia| self.ignore_trivia(trivia));
+        type_declaration.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_expression(&mut self, expression: &mut Expression) {
@@ -329,7 +358,7 @@ impl NodeProcessor for FilterCommentProcessor<'_> {
             | Expression::True(token)
             | Expression::VariableArguments(token) => {
                 if let Some(token) = token {
-                    token.filter_comments(|trivia| self.ignore_trivia(trivia))
+                    token.mutate_trivia(|trivia, _| self.filter_comments(trivia))
                 }
             }
             Expression::Binary(_)
@@ -350,58 +379,58 @@ impl NodeProcessor for FilterCommentProcessor<'_> {
     }
 
     fn process_binary_expression(&mut self, binary: &mut BinaryExpression) {
-        binary.filter_comments(|trivia| self.ignore_trivia(trivia));
+        binary.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_field_expression(&mut self, field: &mut FieldExpression) {
-        field.filter_comments(|trivia| self.ignore_trivia(trivia));
+        field.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_function_expression(&mut self, function: &mut FunctionExpression) {
-        function.filter_comments(|trivia| self.ignore_trivia(trivia));
+        function.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_if_expression(&mut self, if_expression: &mut IfExpression) {
-        if_expression.filter_comments(|trivia| self.ignore_trivia(trivia));
+        if_expression.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_variable_expression(&mut self, identifier: &mut Identifier) {
-        identifier.filter_comments(|trivia| self.ignore_trivia(trivia));
+        identifier.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_index_expression(&mut self, index: &mut IndexExpression) {
-        index.filter_comments(|trivia| self.ignore_trivia(trivia));
+        index.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_number_expression(&mut self, number: &mut NumberExpression) {
-        number.filter_comments(|trivia| self.ignore_trivia(trivia));
+        number.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_parenthese_expression(&mut self, expression: &mut ParentheseExpression) {
-        expression.filter_comments(|trivia| self.ignore_trivia(trivia));
+        expression.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_string_expression(&mut self, string: &mut StringExpression) {
-        string.filter_comments(|trivia| self.ignore_trivia(trivia));
+        string.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_table_expression(&mut self, table: &mut TableExpression) {
-        table.filter_comments(|trivia| self.ignore_trivia(trivia));
+        table.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_unary_expression(&mut self, unary: &mut UnaryExpression) {
-        unary.filter_comments(|trivia| self.ignore_trivia(trivia));
+        unary.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_interpolated_string_expression(
         &mut self,
         string: &mut InterpolatedStringExpression,
     ) {
-        string.filter_comments(|trivia| self.ignore_trivia(trivia));
+        string.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_type_cast_expression(&mut self, type_cast: &mut TypeCastExpression) {
-        type_cast.filter_comments(|trivia| self.ignore_trivia(trivia));
+        type_cast.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_prefix_expression(&mut self, _: &mut Prefix) {}
@@ -410,7 +439,7 @@ impl NodeProcessor for FilterCommentProcessor<'_> {
         match r#type {
             Type::True(token) | Type::False(token) | Type::Nil(token) => {
                 if let Some(token) = token {
-                    token.filter_comments(|trivia| self.ignore_trivia(trivia));
+                    token.mutate_trivia(|trivia, _| self.filter_comments(trivia));
                 }
             }
             _ => {}
@@ -418,59 +447,59 @@ impl NodeProcessor for FilterCommentProcessor<'_> {
     }
 
     fn process_type_name(&mut self, type_name: &mut TypeName) {
-        type_name.filter_comments(|trivia| self.ignore_trivia(trivia));
+        type_name.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_type_field(&mut self, type_field: &mut TypeField) {
-        type_field.filter_comments(|trivia| self.ignore_trivia(trivia));
+        type_field.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_string_type(&mut self, string_type: &mut StringType) {
-        string_type.filter_comments(|trivia| self.ignore_trivia(trivia));
+        string_type.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_array_type(&mut self, array: &mut ArrayType) {
-        array.filter_comments(|trivia| self.ignore_trivia(trivia));
+        array.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_table_type(&mut self, table: &mut TableType) {
-        table.filter_comments(|trivia| self.ignore_trivia(trivia));
+        table.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_expression_type(&mut self, expression_type: &mut ExpressionType) {
-        expression_type.filter_comments(|trivia| self.ignore_trivia(trivia));
+        expression_type.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_parenthese_type(&mut self, parenthese_type: &mut ParentheseType) {
-        parenthese_type.filter_comments(|trivia| self.ignore_trivia(trivia));
+        parenthese_type.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_function_type(&mut self, function_type: &mut FunctionType) {
-        function_type.filter_comments(|trivia| self.ignore_trivia(trivia));
+        function_type.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_optional_type(&mut self, optional: &mut OptionalType) {
-        optional.filter_comments(|trivia| self.ignore_trivia(trivia));
+        optional.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_intersection_type(&mut self, intersection: &mut IntersectionType) {
-        intersection.filter_comments(|trivia| self.ignore_trivia(trivia));
+        intersection.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_union_type(&mut self, union: &mut UnionType) {
-        union.filter_comments(|trivia| self.ignore_trivia(trivia));
+        union.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_type_pack(&mut self, type_pack: &mut TypePack) {
-        type_pack.filter_comments(|trivia| self.ignore_trivia(trivia));
+        type_pack.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_generic_type_pack(&mut self, generic_type_pack: &mut GenericTypePack) {
-        generic_type_pack.filter_comments(|trivia| self.ignore_trivia(trivia));
+        generic_type_pack.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 
     fn process_variadic_type_pack(&mut self, variadic_type_pack: &mut VariadicTypePack) {
-        variadic_type_pack.filter_comments(|trivia| self.ignore_trivia(trivia));
+        variadic_type_pack.mutate_trivia(|trivia, _| self.filter_comments(trivia));
     }
 }
 
@@ -479,18 +508,14 @@ pub const REMOVE_COMMENTS_RULE_NAME: &str = "remove_comments";
 /// A rule that removes comments associated with AST nodes.
 #[derive(Debug, Default)]
 pub struct RemoveComments {
-    except: Vec<Regex>,
+    keep_patterns: Vec<Regex>,
 }
 
 impl FlawlessRule for RemoveComments {
     fn flawless_process(&self, block: &mut Block, context: &Context) {
-        if self.except.is_empty() {
-            let mut processor = RemoveCommentProcessor::default();
-            DefaultVisitor::visit_block(block, &mut processor);
-        } else {
-            let mut processor = FilterCommentProcessor::new(context.original_code(), &self.except);
-            DefaultVisitor::visit_block(block, &mut processor);
-        }
+        let mut processor =
+            FilterCommentProcessor::new(context.original_code(), &self.keep_patterns);
+        DefaultVisitor::visit_block(block, &mut processor);
     }
 }
 
@@ -498,8 +523,8 @@ impl RuleConfiguration for RemoveComments {
     fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
         for (key, value) in properties {
             match key.as_str() {
-                "except" => {
-                    self.except = value.expect_regex_list(&key)?;
+                "keep_patterns" | "except" => {
+                    self.keep_patterns = value.expect_regex_list(&key)?;
                 }
                 _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
             }
@@ -513,7 +538,21 @@ impl RuleConfiguration for RemoveComments {
     }
 
     fn serialize_to_properties(&self) -> RuleProperties {
-        RuleProperties::new()
+        let mut properties = RuleProperties::new();
+
+        if !self.keep_patterns.is_empty() {
+            properties.insert(
+                "keep_patterns".to_owned(),
+                RulePropertyValue::StringList(
+                    self.keep_patterns
+                        .iter()
+                        .map(|regex| regex.as_str().to_owned())
+                        .collect(),
+                ),
+            );
+        }
+
+        properties
     }
 }
 
@@ -539,6 +578,19 @@ mod test {
         assert_json_snapshot!("default_remove_comments", rule);
     }
 
+    #[test]
+    fn serialize_rule_with_keep_patterns() {
+        let rule = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'remove_comments',
+            keep_patterns: ['^--!', '^-- *darklua:'],
+        }"#,
+        )
+        .unwrap();
+
+        assert_json_snapshot!("remove_comments_with_keep_patterns", rule);
+    }
+
     #[test]
     fn configure_with_extra_field_error() {
         let result = json5::from_str::<Box<dyn Rule>>(
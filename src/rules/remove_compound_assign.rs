@@ -10,7 +10,7 @@ use crate::rules::{
 };
 
 use super::{verify_no_rule_properties, RemoveCommentProcessor, RemoveWhitespacesProcessor};
-use crate::utils::origin::{OriginAnchor, token_from_content_with_anchor};
+use crate::utils::origin::OriginAnchor;
 
 struct Processor {
     identifier_tracker: IdentifierTracker,
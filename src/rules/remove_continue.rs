@@ -10,7 +10,7 @@ use crate::process::{DefaultPostVisitor, NodePostProcessor, NodePostVisitor, Nod
 use crate::rules::{Context, RuleConfiguration, RuleConfigurationError, RuleProperties};
 
 use super::{verify_no_rule_properties, FlawlessRule};
-use crate::utils::origin::{OriginAnchor, token_from_content_with_anchor};
+use crate::utils::origin::OriginAnchor;
 
 #[derive(Default)]
 struct Processor {
@@ -1,43 +1,120 @@
 use crate::nodes::{
-    BinaryExpression, BinaryOperator, Block, Expression, IndexExpression, TableEntry,
-    TableExpression,
+    BinaryExpression, BinaryOperator, Block, Expression, FunctionCall, FunctionExpression,
+    IfExpression, IfStatement, IndexExpression, ReturnStatement, TableEntry, TableExpression,
 };
 use crate::process::{DefaultVisitor, Evaluator, NodeProcessor, NodeVisitor};
 use crate::rules::{
     Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
 };
 
-use super::verify_no_rule_properties;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum FallbackStrategy {
+    #[default]
+    Table,
+    Function,
+}
+
+#[derive(Default)]
+struct VariableArgumentsFinder {
+    found: bool,
+}
+
+impl NodeProcessor for VariableArgumentsFinder {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if matches!(expression, Expression::VariableArguments(_)) {
+            self.found = true;
+        }
+    }
+}
+
+fn uses_variable_arguments(if_expression: &IfExpression) -> bool {
+    let mut finder = VariableArgumentsFinder::default();
+    let mut expression = Expression::from(if_expression.clone());
+    DefaultVisitor::visit_expression(&mut expression, &mut finder);
+    finder.found
+}
 
 #[derive(Default)]
 struct Processor {
     evaluator: Evaluator,
+    fallback: FallbackStrategy,
 }
 
 impl Processor {
-    fn wrap_in_table(&self, expression: Expression) -> Expression {
-        TableExpression::new(vec![TableEntry::from_value({
-            if self.evaluator.can_return_multiple_values(&expression) {
-                expression.in_parentheses()
-            } else {
-                expression
-            }
-        })])
+    fn new(fallback: FallbackStrategy) -> Self {
+        Self {
+            evaluator: Evaluator::default(),
+            fallback,
+        }
+    }
+
+    fn is_truthy(&self, expression: &Expression) -> bool {
+        self.evaluator
+            .evaluate(expression)
+            .is_truthy()
+            .unwrap_or_default()
+    }
+
+    fn single_value(&self, expression: Expression) -> Expression {
+        if self.evaluator.can_return_multiple_values(&expression) {
+            expression.in_parentheses()
+        } else {
+            expression
+        }
+    }
+
+    fn should_use_function(&self, if_expression: &IfExpression) -> bool {
+        if self.fallback != FallbackStrategy::Function {
+            return false;
+        }
+
+        let all_results_truthy = self.is_truthy(if_expression.get_result())
+            && if_expression
+                .iter_branches()
+                .all(|branch| self.is_truthy(branch.get_result()));
+
+        // a function cannot access the variable arguments of its parent
+        // function, so those expressions keep the table form
+        !all_results_truthy && !uses_variable_arguments(if_expression)
+    }
+
+    fn convert_to_function_call(&self, if_expression: &IfExpression) -> Expression {
+        let mut if_statement = IfStatement::create(
+            if_expression.get_condition().clone(),
+            ReturnStatement::one(self.single_value(if_expression.get_result().clone())),
+        );
+
+        for branch in if_expression.iter_branches() {
+            if_statement = if_statement.with_new_branch(
+                branch.get_condition().clone(),
+                ReturnStatement::one(self.single_value(branch.get_result().clone())),
+            );
+        }
+
+        let if_statement = if_statement.with_else_block(ReturnStatement::one(
+            self.single_value(if_expression.get_else_result().clone()),
+        ));
+
+        FunctionCall::from_prefix(
+            Expression::from(FunctionExpression::from_block(
+                Block::default().with_statement(if_statement),
+            ))
+            .in_parentheses(),
+        )
         .into()
     }
 
+    fn wrap_in_table(&self, expression: Expression) -> Expression {
+        TableExpression::new(vec![TableEntry::from_value(self.single_value(expression))]).into()
+    }
+
     fn convert_if_branch(
         &self,
         condition: Expression,
         result: Expression,
         else_result: Expression,
     ) -> Expression {
-        if self
-            .evaluator
-            .evaluate(&result)
-            .is_truthy()
-            .unwrap_or_default()
-        {
+        if self.is_truthy(&result) {
             BinaryExpression::new(
                 BinaryOperator::Or,
                 BinaryExpression::new(BinaryOperator::And, condition, result),
@@ -65,6 +142,11 @@ impl Processor {
 impl NodeProcessor for Processor {
     fn process_expression(&mut self, expression: &mut Expression) {
         if let Expression::If(if_expression) = expression {
+            if self.should_use_function(if_expression) {
+                *expression = self.convert_to_function_call(if_expression);
+                return;
+            }
+
             let else_result = if_expression.iter_branches().fold(
                 if_expression.get_else_result().clone(),
                 |else_result, branch| {
@@ -87,20 +169,46 @@ impl NodeProcessor for Processor {
 
 pub const REMOVE_IF_EXPRESSION_RULE_NAME: &str = "remove_if_expression";
 
-/// A rule that removes trailing `nil` in local assignments.
+/// A rule that removes if expressions and replaces them with equivalent expressions.
+///
+/// When a branch result is known to be truthy, the expression is converted into an
+/// `and`/`or` form. Otherwise, the `fallback` property controls if the expression is
+/// converted using tables (`(c and { a } or { b })[1]`) or using an immediately
+/// invoked function.
 #[derive(Debug, Default, PartialEq, Eq)]
-pub struct RemoveIfExpression {}
+pub struct RemoveIfExpression {
+    fallback: FallbackStrategy,
+}
 
 impl FlawlessRule for RemoveIfExpression {
     fn flawless_process(&self, block: &mut Block, _: &Context) {
-        let mut processor = Processor::default();
+        let mut processor = Processor::new(self.fallback);
         DefaultVisitor::visit_block(block, &mut processor);
     }
 }
 
 impl RuleConfiguration for RemoveIfExpression {
     fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
-        verify_no_rule_properties(&properties)?;
+        for (key, value) in properties {
+            match key.as_str() {
+                "fallback" => {
+                    self.fallback = match value.expect_string(&key)?.as_str() {
+                        "table" => FallbackStrategy::Table,
+                        "function" => FallbackStrategy::Function,
+                        unexpected => {
+                            return Err(RuleConfigurationError::UnexpectedValue {
+                                property: "fallback".to_owned(),
+                                message: format!(
+                                    "invalid value `{}` (must be `table` or `function`)",
+                                    unexpected
+                                ),
+                            })
+                        }
+                    };
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
 
         Ok(())
     }
@@ -110,7 +218,16 @@ impl RuleConfiguration for RemoveIfExpression {
     }
 
     fn serialize_to_properties(&self) -> RuleProperties {
-        RuleProperties::new()
+        let mut properties = RuleProperties::new();
+
+        match self.fallback {
+            FallbackStrategy::Table => {}
+            FallbackStrategy::Function => {
+                properties.insert("fallback".to_owned(), "function".into());
+            }
+        }
+
+        properties
     }
 }
 
@@ -132,6 +249,34 @@ mod test {
         assert_json_snapshot!("default_remove_if_expression", rule);
     }
 
+    #[test]
+    fn serialize_rule_with_function_fallback() {
+        let rule: Box<dyn Rule> = Box::new(RemoveIfExpression {
+            fallback: FallbackStrategy::Function,
+        });
+
+        assert_json_snapshot!(rule, @r###"
+        {
+          "rule": "remove_if_expression",
+          "fallback": "function"
+        }
+        "###);
+    }
+
+    #[test]
+    fn configure_with_invalid_fallback_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'remove_if_expression',
+            fallback: 'oops',
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'fallback': invalid value `oops` (must be `table` or `function`)"
+        );
+    }
+
     #[test]
     fn configure_with_extra_field_error() {
         let result = json5::from_str::<Box<dyn Rule>>(
@@ -0,0 +1,188 @@
+use crate::nodes::{
+    Block, Expression, FunctionExpression, FunctionStatement, LastStatement,
+    LocalFunctionStatement, Statement,
+};
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+};
+
+pub const REMOVE_REDUNDANT_RETURN_RULE_NAME: &str = "remove_redundant_return";
+
+const DEFAULT_MODULES_IDENTIFIER: &str = "__DARKLUA_BUNDLE_MODULES";
+
+struct Processor<'a> {
+    modules_identifier: &'a str,
+}
+
+impl<'a> Processor<'a> {
+    fn new(modules_identifier: &'a str) -> Self {
+        Self { modules_identifier }
+    }
+
+    fn is_bundled_module(&self, function: &FunctionStatement) -> bool {
+        let name = function.get_name();
+        name.get_name().get_name() == self.modules_identifier
+            && name.get_field_names().len() == 1
+            && !name.has_method()
+    }
+}
+
+// Removes the return statements that are the last statement to run in the given block.
+// When `remove_nil` is true, a return of a single `nil` value is also removed.
+fn remove_trailing_return(block: &mut Block, remove_nil: bool) {
+    match block.get_last_statement() {
+        Some(LastStatement::Return(statement)) => {
+            let is_redundant = match statement.len() {
+                0 => true,
+                1 => {
+                    remove_nil
+                        && matches!(
+                            statement.iter_expressions().next(),
+                            Some(Expression::Nil(_))
+                        )
+                }
+                _ => false,
+            };
+            if is_redundant {
+                block.take_last_statement();
+            }
+        }
+        Some(LastStatement::Break(_)) | Some(LastStatement::Continue(_)) => {}
+        None => match block.iter_mut_statements().last() {
+            Some(Statement::Do(do_statement)) => {
+                remove_trailing_return(do_statement.mutate_block(), remove_nil);
+            }
+            Some(Statement::If(if_statement)) => {
+                for branch in if_statement.mutate_branches() {
+                    remove_trailing_return(branch.mutate_block(), remove_nil);
+                }
+                if let Some(else_block) = if_statement.mutate_else_block() {
+                    remove_trailing_return(else_block, remove_nil);
+                }
+            }
+            _ => {}
+        },
+    }
+}
+
+impl NodeProcessor for Processor<'_> {
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        remove_trailing_return(function.mutate_block(), true);
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        // the bundler expects the functions of the bundled modules to return one value
+        let remove_nil = !self.is_bundled_module(function);
+        remove_trailing_return(function.mutate_block(), remove_nil);
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        remove_trailing_return(function.mutate_block(), true);
+    }
+}
+
+/// A rule that removes the return statements found at the end of functions when they
+/// do not return any value, or only return `nil`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RemoveRedundantReturn {
+    modules_identifier: Option<String>,
+}
+
+impl RemoveRedundantReturn {
+    /// Sets the identifier used by the bundler to store the bundled modules, when it is
+    /// different from the default one. The `return nil` statements of the bundled modules
+    /// are kept.
+    pub fn with_modules_identifier(mut self, modules_identifier: impl Into<String>) -> Self {
+        self.modules_identifier = Some(modules_identifier.into());
+        self
+    }
+}
+
+impl FlawlessRule for RemoveRedundantReturn {
+    fn flawless_process(&self, block: &mut Block, _: &Context) {
+        let mut processor = Processor::new(
+            self.modules_identifier
+                .as_deref()
+                .unwrap_or(DEFAULT_MODULES_IDENTIFIER),
+        );
+        DefaultVisitor::visit_block(block, &mut processor);
+
+        // the value returned by a module is used, so only empty returns are removed
+        remove_trailing_return(block, false);
+    }
+}
+
+impl RuleConfiguration for RemoveRedundantReturn {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "modules_identifier" => {
+                    self.modules_identifier = Some(value.expect_string(&key)?);
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        REMOVE_REDUNDANT_RETURN_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if let Some(modules_identifier) = &self.modules_identifier {
+            properties.insert(
+                "modules_identifier".to_owned(),
+                modules_identifier.clone().into(),
+            );
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> RemoveRedundantReturn {
+        RemoveRedundantReturn::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!(rule, @r###""remove_redundant_return""###);
+    }
+
+    #[test]
+    fn serialize_rule_with_modules_identifier() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_modules_identifier("Modules"));
+
+        assert_json_snapshot!(rule, @r###"
+        {
+          "rule": "remove_redundant_return",
+          "modules_identifier": "Modules"
+        }
+        "###);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'remove_redundant_return',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -1,6 +1,10 @@
+use std::ops::{Deref, DerefMut};
+
 use crate::nodes::*;
 use crate::process::processors::FindUsage;
-use crate::process::{DefaultVisitor, Evaluator, NodeProcessor, NodeVisitor, ScopeVisitor};
+use crate::process::{
+    Evaluator, IdentifierTracker, NodeProcessor, NodeVisitor, PureCalls, Scope, ScopeVisitor,
+};
 use crate::rules::{
     Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
 };
@@ -8,19 +12,62 @@ use crate::utils::expressions_as_statement;
 
 use super::verify_no_rule_properties;
 
-#[derive(Default)]
-struct RemoveUnusedVariableProcessor {
+struct RemoveUnusedVariableProcessor<'a> {
     evaluator: Evaluator,
     mutated: bool,
+    context: &'a Context<'a, 'a, 'a>,
 }
 
-impl RemoveUnusedVariableProcessor {
+impl<'a> RemoveUnusedVariableProcessor<'a> {
+    fn new(context: &'a Context, pure_calls: PureCalls) -> Self {
+        Self {
+            evaluator: Evaluator::default().with_pure_calls(pure_calls),
+            mutated: false,
+            context,
+        }
+    }
+
     fn has_mutated(&self) -> bool {
         self.mutated
     }
+
+    fn insert_declared_locals(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::LocalAssign(assign) => {
+                for variable in assign.iter_mut_variables() {
+                    self.insert_local(variable.mutate_name(), None);
+                }
+            }
+            Statement::LocalFunction(function) => self.insert_local_function(function),
+            _ => {}
+        }
+    }
+
+    fn is_disabled(&self, local_token: Option<&Token>) -> bool {
+        local_token
+            .and_then(Token::get_line_number)
+            .is_some_and(|line| {
+                self.context
+                    .is_line_disabled(REMOVE_UNUSED_VARIABLE_RULE_NAME, line)
+            })
+    }
+}
+
+impl Deref for RemoveUnusedVariableProcessor<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        self.evaluator.identifier_tracker()
+    }
+}
+
+impl DerefMut for RemoveUnusedVariableProcessor<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.evaluator.mutate_identifier_tracker()
+    }
 }
 
-impl NodeProcessor for RemoveUnusedVariableProcessor {
+impl NodeProcessor for RemoveUnusedVariableProcessor<'_> {
     fn process_scope(&mut self, block: &mut Block, extra: Option<&mut Expression>) {
         let length = block.statements_len();
 
@@ -28,7 +75,9 @@ impl NodeProcessor for RemoveUnusedVariableProcessor {
             .reverse_iter_statements()
             .enumerate()
             .filter_map(|(i, statement)| match statement {
-                Statement::LocalAssign(assignment) => {
+                Statement::LocalAssign(assignment)
+                    if !self.is_disabled(assignment.get_tokens().map(|tokens| &tokens.local)) =>
+                {
                     let identifiers = assignment
                         .get_variables()
                         .iter()
@@ -39,7 +88,9 @@ impl NodeProcessor for RemoveUnusedVariableProcessor {
 
                     Some((length - i - 1, identifiers))
                 }
-                Statement::LocalFunction(function) => {
+                Statement::LocalFunction(function)
+                    if !self.is_disabled(function.get_tokens().map(|tokens| &tokens.local)) =>
+                {
                     Some((length - i - 1, vec![function.get_name().to_owned()]))
                 }
                 _ => None,
@@ -101,11 +152,15 @@ impl NodeProcessor for RemoveUnusedVariableProcessor {
             let mut i = 0;
             let mut should_find_next = true;
 
+            // the values of a statement are evaluated in the scope of the locals declared by
+            // the previous statements of the block
+            self.push();
+
             block.filter_mut_statements(|statement| {
                 let found = should_find_next && i == find_next_index;
                 i += 1;
 
-                if found {
+                let keep = if found {
                     let keep_statement = if let Statement::LocalAssign(assign) = statement {
                         if usages.iter().all(|used| !used) {
                             let values = assign
@@ -208,8 +263,16 @@ impl NodeProcessor for RemoveUnusedVariableProcessor {
                     keep_statement
                 } else {
                     true
+                };
+
+                if keep {
+                    self.insert_declared_locals(statement);
                 }
+
+                keep
             });
+
+            self.pop();
         }
     }
 }
@@ -221,11 +284,12 @@ pub const REMOVE_UNUSED_VARIABLE_RULE_NAME: &str = "remove_unused_variable";
 pub struct RemoveUnusedVariable {}
 
 impl FlawlessRule for RemoveUnusedVariable {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
         loop {
-            let mut processor = RemoveUnusedVariableProcessor::default();
+            let mut processor =
+                RemoveUnusedVariableProcessor::new(context, context.pure_calls(block));
             processor.process_scope(block, None);
-            DefaultVisitor::visit_block(block, &mut processor);
+            ScopeVisitor::visit_block(block, &mut processor);
             if !processor.has_mutated() {
                 break;
             }
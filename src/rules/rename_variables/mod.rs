@@ -12,23 +12,41 @@ use crate::rules::{
     RulePropertyValue,
 };
 
+use regex::Regex;
+
 use std::collections::HashSet;
 use std::iter::FromIterator;
 
 pub const RENAME_VARIABLES_RULE_NAME: &str = "rename_variables";
 
 /// Rename all identifiers to small and meaningless names.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug)]
 pub struct RenameVariables {
     globals: Vec<String>,
     include_functions: bool,
+    preserve: Vec<Regex>,
+}
+
+impl PartialEq for RenameVariables {
+    fn eq(&self, other: &Self) -> bool {
+        self.globals == other.globals
+            && self.include_functions == other.include_functions
+            && self
+                .preserve
+                .iter()
+                .map(Regex::as_str)
+                .eq(other.preserve.iter().map(Regex::as_str))
+    }
 }
 
+impl Eq for RenameVariables {}
+
 impl RenameVariables {
     pub fn new<I: IntoIterator<Item = String>>(iter: I) -> Self {
         Self {
             globals: Vec::from_iter(iter),
             include_functions: false,
+            preserve: Vec::new(),
         }
     }
 
@@ -94,7 +112,7 @@ impl Default for RenameVariables {
 }
 
 impl FlawlessRule for RenameVariables {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
         let avoid_identifiers = if self.include_functions {
             Vec::new()
         } else {
@@ -103,10 +121,19 @@ impl FlawlessRule for RenameVariables {
             collect_functions.into()
         };
 
+        // every local is renamed, so only the globals referenced by the block are kept
+        // away from the generated names
+        context
+            .generated_identifiers()
+            .borrow_mut()
+            .reserve_globals(block);
+
         let mut processor = RenameProcessor::new(
             self.globals.clone().into_iter().chain(avoid_identifiers),
             self.include_functions,
-        );
+            context.generated_identifiers(),
+        )
+        .with_preserve(&self.preserve);
         ScopeVisitor::visit_block(block, &mut processor);
     }
 }
@@ -121,6 +148,9 @@ impl RuleConfiguration for RenameVariables {
                 "include_functions" => {
                     self.include_functions = value.expect_bool(&key)?;
                 }
+                "preserve" => {
+                    self.preserve = value.expect_regex_list(&key)?;
+                }
                 _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
             }
         }
@@ -150,6 +180,18 @@ impl RuleConfiguration for RenameVariables {
             );
         }
 
+        if !self.preserve.is_empty() {
+            properties.insert(
+                "preserve".to_owned(),
+                RulePropertyValue::StringList(
+                    self.preserve
+                        .iter()
+                        .map(|regex| regex.as_str().to_owned())
+                        .collect(),
+                ),
+            );
+        }
+
         properties
     }
 }
@@ -201,6 +243,50 @@ mod test {
         );
     }
 
+    #[test]
+    fn serialize_with_preserve() {
+        let rule: Box<dyn Rule> = json5::from_str(
+            r#"{
+            rule: 'rename_variables',
+            preserve: ['^HOT_', 'config'],
+        }"#,
+        )
+        .unwrap();
+
+        assert_json_snapshot!("rename_variables_with_preserve", rule);
+    }
+
+    #[test]
+    fn serialize_with_preserve_round_trip() {
+        let rule: Box<dyn Rule> = json5::from_str(
+            r#"{
+            rule: 'rename_variables',
+            preserve: ['^HOT_', 'config'],
+        }"#,
+        )
+        .unwrap();
+
+        let serialized = serde_json::to_string(&rule).unwrap();
+        let deserialized: Box<dyn Rule> = json5::from_str(&serialized).unwrap();
+
+        pretty_assertions::assert_eq!(
+            deserialized.serialize_to_properties(),
+            rule.serialize_to_properties()
+        );
+    }
+
+    #[test]
+    fn configure_with_invalid_preserve_regex_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'rename_variables',
+            preserve: ['^[0-9'],
+        }"#,
+        );
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn serialize_skip_functions() {
         let rule = Box::new(RenameVariables::new(
@@ -1,35 +1,55 @@
 use crate::nodes::{Expression, Identifier, LocalFunctionStatement, TypeField};
-use crate::process::utils::{identifier_permutator, CharPermutator};
 use crate::process::{utils::KEYWORDS, NodeProcessor, Scope};
+use crate::rules::GeneratedIdentifiers;
 
+use regex::Regex;
+
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
 use std::mem;
 
 #[derive(Debug)]
-pub struct RenameProcessor {
+pub struct RenameProcessor<'a> {
     real_to_obfuscated: Vec<HashMap<String, (String, bool)>>,
-    permutator: CharPermutator,
+    generated_identifiers: &'a RefCell<GeneratedIdentifiers>,
     avoid_identifier: HashSet<String>,
     reuse_identifiers: Vec<String>,
     include_functions: bool,
+    preserve: &'a [Regex],
 }
 
-impl RenameProcessor {
-    pub fn new<I: IntoIterator<Item = String>>(iter: I, include_functions: bool) -> Self {
+impl<'a> RenameProcessor<'a> {
+    pub fn new<I: IntoIterator<Item = String>>(
+        iter: I,
+        include_functions: bool,
+        generated_identifiers: &'a RefCell<GeneratedIdentifiers>,
+    ) -> Self {
         let mut avoid_identifier = HashSet::from_iter(iter);
         avoid_identifier.extend(KEYWORDS.iter().map(|s| (*s).to_owned()));
 
         Self {
             real_to_obfuscated: Vec::new(),
-            permutator: identifier_permutator(),
+            generated_identifiers,
             avoid_identifier,
             reuse_identifiers: Vec::new(),
             include_functions,
+            preserve: &[],
         }
     }
 
+    /// Keeps the names matching any of the given patterns, and never generates a name
+    /// matching them.
+    pub fn with_preserve(mut self, preserve: &'a [Regex]) -> Self {
+        self.preserve = preserve;
+        self
+    }
+
+    fn is_preserved(&self, identifier: &str) -> bool {
+        self.preserve.iter().any(|regex| regex.is_match(identifier))
+    }
+
     pub fn add(&mut self, real: String, obfuscated: String, reuse: bool) {
         if let Some(dictionary) = self.real_to_obfuscated.last_mut() {
             dictionary.insert(real, (obfuscated, reuse));
@@ -51,7 +71,7 @@ impl RenameProcessor {
         if let Some(identifier) = self.reuse_identifiers.pop() {
             identifier
         } else {
-            let generated = self.permutator.next().unwrap();
+            let generated = self.generated_identifiers.borrow_mut().generate();
 
             if self.filter_identifier(&generated) {
                 generated
@@ -62,11 +82,15 @@ impl RenameProcessor {
     }
 
     fn filter_identifier(&self, identifier: &str) -> bool {
-        !self.avoid_identifier.contains(identifier)
-            && !identifier.chars().next().unwrap().is_ascii_digit()
+        !self.avoid_identifier.contains(identifier) && !self.is_preserved(identifier)
     }
 
     fn replace_identifier(&mut self, identifier: &mut String) {
+        if self.is_preserved(identifier) {
+            self.add(identifier.clone(), identifier.clone(), false);
+            return;
+        }
+
         let original = mem::take(identifier);
         let obfuscated_name = self.generate_identifier();
 
@@ -119,7 +143,7 @@ fn sort_identifiers(a: &str, b: &str) -> Ordering {
     }
 }
 
-impl Scope for RenameProcessor {
+impl Scope for RenameProcessor<'_> {
     fn push(&mut self) {
         self.real_to_obfuscated.push(HashMap::new())
     }
@@ -158,7 +182,7 @@ impl Scope for RenameProcessor {
     }
 }
 
-impl NodeProcessor for RenameProcessor {
+impl NodeProcessor for RenameProcessor<'_> {
     fn process_variable_expression(&mut self, variable: &mut Identifier) {
         if let Some(obfuscated_name) = self.get_obfuscated_name(variable.get_name()) {
             variable.set_name(obfuscated_name);
@@ -178,18 +202,20 @@ impl NodeProcessor for RenameProcessor {
 mod test {
     use super::*;
 
-    fn new_scope() -> RenameProcessor {
-        RenameProcessor::new(Vec::new(), true)
+    fn new_scope(generated_identifiers: &RefCell<GeneratedIdentifiers>) -> RenameProcessor<'_> {
+        RenameProcessor::new(Vec::new(), true, generated_identifiers)
     }
 
     #[test]
     fn pop_root_should_not_panic() {
-        new_scope().pop();
+        let generated_identifiers = RefCell::default();
+        new_scope(&generated_identifiers).pop();
     }
 
     #[test]
     fn should_get_mapped_name_from_inserted_names() {
-        let mut scope = new_scope();
+        let generated_identifiers = RefCell::default();
+        let mut scope = new_scope(&generated_identifiers);
         let real = "a".to_owned();
         let obfuscated = "b".to_owned();
 
@@ -200,7 +226,8 @@ mod test {
 
     #[test]
     fn mapped_name_should_not_exist_after_pop() {
-        let mut scope = new_scope();
+        let generated_identifiers = RefCell::default();
+        let mut scope = new_scope(&generated_identifiers);
         let real = "a".to_owned();
         let obfuscated = "def".to_owned();
 
@@ -213,7 +240,8 @@ mod test {
 
     #[test]
     fn remapped_name_should_exist_after_pop() {
-        let mut scope = new_scope();
+        let generated_identifiers = RefCell::default();
+        let mut scope = new_scope(&generated_identifiers);
         let real = "a".to_owned();
         let obfuscated = "b".to_owned();
         let other_obfuscated = "c".to_owned();
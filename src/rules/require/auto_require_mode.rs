@@ -0,0 +1,212 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Serialize};
+
+use crate::nodes::{Block, Expression, FunctionCall, LocalAssignStatement, Prefix};
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
+use crate::rules::{Context, WarningCode};
+
+use super::{
+    is_require_call, match_require_argument, require_call_line, PathRequireMode, RequireArgument,
+};
+
+const ROBLOX_ROOT_IDENTIFIERS: [&str; 2] = ["script", "game"];
+
+/// The require mode selected by the `auto` require mode for a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectedRequireMode {
+    /// Requires use file system paths, like `require("./module")`.
+    Path,
+    /// Requires use Roblox instances, like `require(script.Parent.module)`.
+    Roblox,
+}
+
+impl DetectedRequireMode {
+    /// Returns the name of the require mode, as written in configuration files.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Path => "path",
+            Self::Roblox => "roblox",
+        }
+    }
+}
+
+impl fmt::Display for DetectedRequireMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A require mode that inspects the require calls of the processed file to choose between
+/// the path and the Roblox require modes.
+///
+/// Requires with a string argument count for the path require mode and requires with an
+/// instance rooted at `script` or `game` count for the Roblox require mode. The most
+/// frequent one is used, and the path require mode is used when there is a tie.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub struct AutoRequireMode<R> {
+    #[serde(default)]
+    path: PathRequireMode,
+    #[serde(default)]
+    roblox: R,
+}
+
+impl<R> AutoRequireMode<R> {
+    /// Sets the path require mode used when the detected mode is `path`.
+    pub fn with_path(mut self, path: PathRequireMode) -> Self {
+        self.path = path;
+        self
+    }
+
+    /// Sets the Roblox require mode used when the detected mode is `roblox`.
+    pub fn with_roblox(mut self, roblox: R) -> Self {
+        self.roblox = roblox;
+        self
+    }
+
+    pub(crate) fn path_mode(&self) -> &PathRequireMode {
+        &self.path
+    }
+
+    pub(crate) fn roblox_mode(&self) -> &R {
+        &self.roblox
+    }
+
+    /// Detects the require mode of the given block and reports it to the context, with a
+    /// warning for each require call that does not match the detected mode.
+    pub(crate) fn detect(&self, block: &mut Block, context: &Context) -> DetectedRequireMode {
+        let mut detector = RequireModeDetector::default();
+        ScopeVisitor::visit_block(block, &mut detector);
+
+        let path_count = detector.path_lines.len();
+        let roblox_count = detector.roblox_lines.len();
+
+        let (mode, minority_lines) = if roblox_count > path_count {
+            (DetectedRequireMode::Roblox, &detector.path_lines)
+        } else {
+            (DetectedRequireMode::Path, &detector.roblox_lines)
+        };
+
+        log::info!(
+            "detected `{}` require mode for `{}` ({} path requires, {} Roblox requires)",
+            mode,
+            context.current_path().display(),
+            path_count,
+            roblox_count,
+        );
+
+        for line in minority_lines {
+            context.add_warning(
+                WarningCode::MixedRequireModes,
+                format!(
+                    concat!(
+                        "require call does not match the detected `{}` require mode ",
+                        "({} path requires, {} Roblox requires)"
+                    ),
+                    mode, path_count, roblox_count,
+                ),
+                Some(context.current_path().to_path_buf()),
+                *line,
+            );
+        }
+
+        context.add_detected_require_mode(mode);
+
+        mode
+    }
+}
+
+#[derive(Debug, Default)]
+struct RequireModeDetector {
+    identifier_tracker: IdentifierTracker,
+    roblox_locals: HashSet<String>,
+    path_lines: Vec<Option<usize>>,
+    roblox_lines: Vec<Option<usize>>,
+}
+
+impl Deref for RequireModeDetector {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for RequireModeDetector {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl RequireModeDetector {
+    fn is_roblox_expression(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::Identifier(identifier) => self.is_roblox_identifier(identifier.get_name()),
+            Expression::Field(field) => self.is_roblox_prefix(field.get_prefix()),
+            Expression::Index(index) => self.is_roblox_prefix(index.get_prefix()),
+            Expression::Call(call) => {
+                call.get_method().is_some() && self.is_roblox_prefix(call.get_prefix())
+            }
+            Expression::Parenthese(parenthese) => {
+                self.is_roblox_expression(parenthese.inner_expression())
+            }
+            _ => false,
+        }
+    }
+
+    fn is_roblox_prefix(&self, prefix: &Prefix) -> bool {
+        match prefix {
+            Prefix::Identifier(identifier) => self.is_roblox_identifier(identifier.get_name()),
+            Prefix::Field(field) => self.is_roblox_prefix(field.get_prefix()),
+            Prefix::Index(index) => self.is_roblox_prefix(index.get_prefix()),
+            Prefix::Call(call) => {
+                call.get_method().is_some() && self.is_roblox_prefix(call.get_prefix())
+            }
+            Prefix::Parenthese(parenthese) => {
+                self.is_roblox_expression(parenthese.inner_expression())
+            }
+        }
+    }
+
+    fn is_roblox_identifier(&self, name: &str) -> bool {
+        if self.roblox_locals.contains(name) {
+            return true;
+        }
+        ROBLOX_ROOT_IDENTIFIERS.contains(&name) && !self.is_identifier_used(name)
+    }
+}
+
+impl NodeProcessor for RequireModeDetector {
+    fn process_local_assign_statement(&mut self, statement: &mut LocalAssignStatement) {
+        for (variable, value) in statement.iter_variables().zip(statement.iter_values()) {
+            let name = variable.get_identifier().get_name();
+            if self.is_roblox_expression(value) {
+                self.roblox_locals.insert(name.to_owned());
+            } else {
+                self.roblox_locals.remove(name);
+            }
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if !is_require_call(call, self) {
+            return;
+        }
+
+        match match_require_argument(call) {
+            Some(RequireArgument::String(_)) => {
+                self.path_lines.push(require_call_line(call));
+            }
+            Some(RequireArgument::Expression(expression))
+                if self.is_roblox_expression(expression) =>
+            {
+                self.roblox_lines.push(require_call_line(call));
+            }
+            _ => {}
+        }
+    }
+}
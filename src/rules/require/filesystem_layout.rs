@@ -0,0 +1,205 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::rules::convert_require::{
+    InstancePath, InstancePathComponent, InstancePathResolutionError, InstancePathRoot,
+};
+use crate::Resources;
+
+const MODULE_EXTENSIONS: [&str; 2] = ["lua", "luau"];
+const INIT_FILE_STEM: &str = "init";
+
+/// Resolves a `script` instance path to a file by assuming that the instances are laid out
+/// like the files (as Rojo does), without using a sourcemap. The instance of a file is the
+/// file without its extension, or its directory for `init` files.
+pub(crate) fn get_file_from_instance_path(
+    from_file: &Path,
+    instance_path: &InstancePath,
+    resources: &Resources,
+) -> Result<PathBuf, InstancePathResolutionError> {
+    if *instance_path.root() == InstancePathRoot::Root {
+        return Err(InstancePathResolutionError::MissingSourcemap);
+    }
+
+    let mut location = script_location(from_file);
+
+    for (index, component) in instance_path.components().iter().enumerate() {
+        match component {
+            InstancePathComponent::Parent => push_parent(&mut location),
+            InstancePathComponent::Child(name) => {
+                let child = location.join(name);
+                let exists = resources.is_directory(&child).unwrap_or_default()
+                    || find_module_file(&child, resources).is_some();
+
+                if !exists {
+                    return Err(InstancePathResolutionError::MissingChild {
+                        component_index: index,
+                        child: name.to_owned(),
+                        parent: location.display().to_string(),
+                    });
+                }
+                location = child;
+            }
+            InstancePathComponent::Ancestor(name) => loop {
+                if !location
+                    .components()
+                    .any(|component| matches!(component, Component::Normal(_)))
+                {
+                    return Err(InstancePathResolutionError::NotFound);
+                }
+                location.pop();
+                if location
+                    .file_name()
+                    .is_some_and(|file_name| file_name == name.as_str())
+                {
+                    break;
+                }
+            },
+        }
+    }
+
+    find_module_file(&location, resources).ok_or(InstancePathResolutionError::NotFound)
+}
+
+fn script_location(file: &Path) -> PathBuf {
+    let is_init_file = file.file_stem().is_some_and(|stem| stem == INIT_FILE_STEM);
+
+    if is_init_file {
+        file.parent().map(Path::to_path_buf).unwrap_or_default()
+    } else {
+        file.with_extension("")
+    }
+}
+
+fn push_parent(location: &mut PathBuf) {
+    match location.components().next_back() {
+        Some(Component::Normal(_)) => {
+            location.pop();
+        }
+        _ => location.push(".."),
+    }
+}
+
+fn find_module_file(location: &Path, resources: &Resources) -> Option<PathBuf> {
+    let with_extensions = MODULE_EXTENSIONS.iter().map(|extension| {
+        let mut file = location.as_os_str().to_owned();
+        file.push(".");
+        file.push(extension);
+        PathBuf::from(file)
+    });
+    let init_files = MODULE_EXTENSIONS
+        .iter()
+        .map(|extension| location.join(INIT_FILE_STEM).with_extension(extension));
+
+    with_extensions
+        .chain(init_files)
+        .find(|file| resources.is_file(file).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resources() -> Resources {
+        let resources = Resources::from_memory();
+        for file in [
+            "src/init.lua",
+            "src/sibling.lua",
+            "src/folder/init.luau",
+            "src/folder/child.lua",
+            "src/folder/nested/value.lua",
+        ] {
+            resources.write(file, "return nil").unwrap();
+        }
+        resources
+    }
+
+    fn resolve(
+        from_file: &str,
+        build: impl FnOnce(&mut InstancePath),
+    ) -> Result<PathBuf, InstancePathResolutionError> {
+        let mut instance_path = InstancePath::from_script();
+        build(&mut instance_path);
+        get_file_from_instance_path(Path::new(from_file), &instance_path, &resources())
+    }
+
+    #[test]
+    fn child_of_init_file() {
+        assert_eq!(
+            resolve("src/init.lua", |path| path.child("sibling")),
+            Ok(PathBuf::from("src/sibling.lua"))
+        );
+    }
+
+    #[test]
+    fn child_folder_with_init_file() {
+        assert_eq!(
+            resolve("src/init.lua", |path| path.child("folder")),
+            Ok(PathBuf::from("src/folder/init.luau"))
+        );
+    }
+
+    #[test]
+    fn sibling_of_plain_file() {
+        assert_eq!(
+            resolve("src/sibling.lua", |path| {
+                path.parent();
+                path.child("folder");
+                path.child("child");
+            }),
+            Ok(PathBuf::from("src/folder/child.lua"))
+        );
+    }
+
+    #[test]
+    fn parent_of_init_file_is_the_parent_directory() {
+        assert_eq!(
+            resolve("src/folder/init.luau", |path| {
+                path.parent();
+                path.child("sibling");
+            }),
+            Ok(PathBuf::from("src/sibling.lua"))
+        );
+    }
+
+    #[test]
+    fn ancestor_by_name() {
+        assert_eq!(
+            resolve("src/folder/nested/value.lua", |path| {
+                path.ancestor("folder");
+                path.child("child");
+            }),
+            Ok(PathBuf::from("src/folder/child.lua"))
+        );
+    }
+
+    #[test]
+    fn missing_child_errors() {
+        assert_eq!(
+            resolve("src/init.lua", |path| path.child("missing")),
+            Err(InstancePathResolutionError::MissingChild {
+                component_index: 0,
+                child: "missing".to_owned(),
+                parent: "src".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn folder_without_init_file_is_not_found() {
+        assert_eq!(
+            resolve("src/folder/init.luau", |path| path.child("nested")),
+            Err(InstancePathResolutionError::NotFound)
+        );
+    }
+
+    #[test]
+    fn datamodel_path_requires_a_sourcemap() {
+        let mut instance_path = InstancePath::from_root();
+        instance_path.child("ReplicatedStorage");
+
+        assert_eq!(
+            get_file_from_instance_path(Path::new("src/init.lua"), &instance_path, &resources()),
+            Err(InstancePathResolutionError::MissingSourcemap)
+        );
+    }
+}
@@ -3,24 +3,259 @@ use std::path::{Path, PathBuf};
 use bstr::{BStr, ByteSlice};
 
 use crate::{
-    nodes::{Arguments, Expression, FunctionCall, Prefix, StringExpression},
+    generator::{DenseLuaGenerator, LuaGenerator, ReadableLuaGenerator},
+    nodes::{
+        Arguments, Expression, FunctionCall, FunctionExpression, Identifier, Prefix,
+        ReturnStatement, StringExpression,
+    },
     process::IdentifierTracker,
     utils,
 };
 
 const REQUIRE_FUNCTION_IDENTIFIER: &str = "require";
+const PCALL_FUNCTION_IDENTIFIER: &str = "pcall";
+const XPCALL_FUNCTION_IDENTIFIER: &str = "xpcall";
 
-pub(crate) fn is_require_call(call: &FunctionCall, identifier_tracker: &IdentifierTracker) -> bool {
+/// Returns true if the given call is a call to the global `require` function, which can be
+/// wrapped in parentheses like `(require)('./module')`. Method calls and calls where
+/// `require` refers to a local variable are not require calls.
+///
+/// ```
+/// # use darklua_core::{nodes::Statement, process::IdentifierTracker, Parser};
+/// # use darklua_core::rules::require::is_require_call;
+/// let block = Parser::default().parse("require('./module')").unwrap();
+/// let call = match block.iter_statements().next() {
+///     Some(Statement::Call(call)) => call,
+///     _ => unreachable!(),
+/// };
+///
+/// assert!(is_require_call(call, &IdentifierTracker::new()));
+/// ```
+pub fn is_require_call(call: &FunctionCall, identifier_tracker: &IdentifierTracker) -> bool {
+    match call_identifier(call) {
+        Some(identifier) => {
+            identifier.get_name() == REQUIRE_FUNCTION_IDENTIFIER
+                && !identifier_tracker.is_identifier_used(REQUIRE_FUNCTION_IDENTIFIER)
+        }
+        None => false,
+    }
+}
+
+/// Returns the identifier of the function called, when the prefix of the call is an
+/// identifier that can be wrapped in parentheses, like `(require)('./module')`. Method calls
+/// return `None`.
+pub(crate) fn call_identifier(call: &FunctionCall) -> Option<&Identifier> {
     if call.get_method().is_some() {
-        return false;
+        return None;
     }
 
     match call.get_prefix() {
-        Prefix::Identifier(identifier) => {
-            identifier.get_name() == REQUIRE_FUNCTION_IDENTIFIER
-                && !identifier_tracker.is_identifier_used(REQUIRE_FUNCTION_IDENTIFIER)
+        Prefix::Identifier(identifier) => Some(identifier),
+        Prefix::Parenthese(parenthese) => parenthese_identifier(parenthese.inner_expression()),
+        _ => None,
+    }
+}
+
+fn parenthese_identifier(expression: &Expression) -> Option<&Identifier> {
+    match expression {
+        Expression::Identifier(identifier) => Some(identifier),
+        Expression::Parenthese(parenthese) => parenthese_identifier(parenthese.inner_expression()),
+        _ => None,
+    }
+}
+
+/// A require call made through `pcall(require, ...)` or `xpcall(require, handler, ...)`.
+#[derive(Debug, Clone)]
+pub(crate) struct ProtectedRequireCall {
+    prefix: Prefix,
+    require_function: Expression,
+    handler: Option<Expression>,
+    require_call: FunctionCall,
+}
+
+impl ProtectedRequireCall {
+    /// The equivalent `require(...)` call, using the same `require` identifier.
+    pub(crate) fn require_call(&self) -> &FunctionCall {
+        &self.require_call
+    }
+
+    /// Generates the protected call of a function returning the given value, like
+    /// `pcall(function() return value end)`.
+    pub(crate) fn wrap(&self, value: Expression) -> FunctionCall {
+        let function = FunctionExpression::from_block(ReturnStatement::one(value));
+        let call = FunctionCall::from_prefix(self.prefix.clone()).with_argument(function);
+        match &self.handler {
+            Some(handler) => call.with_argument(handler.clone()),
+            None => call,
         }
-        _ => false,
+    }
+
+    /// Generates the protected call with the arguments of the given require call.
+    pub(crate) fn with_require_call(&self, require_call: &FunctionCall) -> FunctionCall {
+        let mut call = FunctionCall::from_prefix(self.prefix.clone())
+            .with_argument(self.require_function.clone());
+        if let Some(handler) = &self.handler {
+            call = call.with_argument(handler.clone());
+        }
+        for argument in require_call.get_arguments().clone().to_expressions() {
+            call = call.with_argument(argument);
+        }
+        call
+    }
+}
+
+/// Matches `pcall(require, ...)` and `xpcall(require, handler, ...)` calls. The `is_global`
+/// function tells if `pcall` or `xpcall` refers to the global function and the
+/// `is_require_identifier` function tells if an identifier refers to `require`.
+pub(crate) fn match_protected_require_call(
+    call: &FunctionCall,
+    is_global: impl Fn(&str) -> bool,
+    is_require_identifier: impl Fn(&str) -> bool,
+) -> Option<ProtectedRequireCall> {
+    if call.get_method().is_some() {
+        return None;
+    }
+
+    let is_xpcall = match call.get_prefix() {
+        Prefix::Identifier(identifier) if is_global(identifier.get_name()) => {
+            match identifier.get_name().as_str() {
+                PCALL_FUNCTION_IDENTIFIER => false,
+                XPCALL_FUNCTION_IDENTIFIER => true,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    let tuple = match call.get_arguments() {
+        Arguments::Tuple(tuple) => tuple,
+        _ => return None,
+    };
+
+    let mut values = tuple.iter_values();
+    let require_function = values.next()?;
+
+    let require_identifier = match require_function {
+        Expression::Identifier(identifier) if is_require_identifier(identifier.get_name()) => {
+            identifier
+        }
+        _ => return None,
+    };
+
+    let handler = if is_xpcall {
+        Some(values.next()?.clone())
+    } else {
+        None
+    };
+
+    let require_call = values.fold(
+        FunctionCall::from_prefix(require_identifier.clone()),
+        |require_call, argument| require_call.with_argument(argument.clone()),
+    );
+
+    Some(ProtectedRequireCall {
+        prefix: call.get_prefix().clone(),
+        require_function: require_function.clone(),
+        handler,
+        require_call,
+    })
+}
+
+/// Returns the line of the `require` identifier when tokens are available.
+pub(crate) fn require_call_line(call: &FunctionCall) -> Option<usize> {
+    call_identifier(call)
+        .and_then(Identifier::get_token)
+        .and_then(|token| token.get_line_number())
+}
+
+/// Generates the code of a require call so it can be shown in diagnostics.
+pub(crate) fn format_require_call(call: &FunctionCall) -> String {
+    let mut generator = DenseLuaGenerator::default();
+    generator.write_function_call(call);
+    generator.into_string()
+}
+
+/// Generates the code of a require argument so it can be shown in diagnostics.
+pub(crate) fn format_require_argument(call: &FunctionCall) -> String {
+    match call.get_arguments() {
+        Arguments::Tuple(tuple) if tuple.len() == 1 => {
+            format_expression(tuple.iter_values().next().unwrap())
+        }
+        arguments => {
+            let mut generator = ReadableLuaGenerator::default();
+            generator.write_arguments(arguments);
+            generator.into_string().trim().to_owned()
+        }
+    }
+}
+
+/// Generates the code of an expression so it can be shown in diagnostics.
+pub(crate) fn format_expression(expression: &Expression) -> String {
+    let mut generator = ReadableLuaGenerator::default();
+    generator.write_expression(expression);
+    generator.into_string().trim().to_owned()
+}
+
+/// The argument of a require call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequireArg {
+    /// A string literal argument, like `require("./module")`.
+    Path(String),
+    /// Any other expression, like the Roblox instance in `require(script.Parent.module)`.
+    Instance(Expression),
+}
+
+/// Returns the argument of a require call. Only calls with a single argument are matched,
+/// so method calls, table arguments and calls with multiple arguments return `None`.
+///
+/// ```
+/// # use darklua_core::{nodes::Statement, Parser};
+/// # use darklua_core::rules::require::{require_argument, RequireArg};
+/// let block = Parser::default()
+///     .parse("require './module' require(script.Parent.module) require(a, b)")
+///     .unwrap();
+/// let arguments: Vec<_> = block
+///     .iter_statements()
+///     .map(|statement| match statement {
+///         Statement::Call(call) => require_argument(call),
+///         _ => unreachable!(),
+///     })
+///     .collect();
+///
+/// assert_eq!(arguments[0], Some(RequireArg::Path("./module".to_owned())));
+/// assert!(matches!(arguments[1], Some(RequireArg::Instance(_))));
+/// assert_eq!(arguments[2], None);
+/// ```
+pub fn require_argument(call: &FunctionCall) -> Option<RequireArg> {
+    Some(match match_require_argument(call)? {
+        RequireArgument::String(string) => RequireArg::Path(
+            string
+                .get_string_value()
+                .map(str::to_owned)
+                .unwrap_or_else(|| String::from_utf8_lossy(string.get_value()).into_owned()),
+        ),
+        RequireArgument::Expression(expression) => RequireArg::Instance(expression.clone()),
+    })
+}
+
+/// A borrowed require argument, see [`require_argument`].
+pub(crate) enum RequireArgument<'a> {
+    String(&'a StringExpression),
+    Expression(&'a Expression),
+}
+
+pub(crate) fn match_require_argument(call: &FunctionCall) -> Option<RequireArgument<'_>> {
+    if call.get_method().is_some() {
+        return None;
+    }
+
+    match call.get_arguments() {
+        Arguments::String(string) => Some(RequireArgument::String(string)),
+        Arguments::Tuple(tuple) if tuple.len() == 1 => match tuple.iter_values().next().unwrap() {
+            Expression::String(string) => Some(RequireArgument::String(string)),
+            expression => Some(RequireArgument::Expression(expression)),
+        },
+        _ => None,
     }
 }
 
@@ -32,17 +267,9 @@ fn convert_string_expression_to_path(string: &StringExpression) -> Option<&Path>
 }
 
 pub(crate) fn match_path_require_call(call: &FunctionCall) -> Option<PathBuf> {
-    match call.get_arguments() {
-        Arguments::String(string) => convert_string_expression_to_path(string),
-        Arguments::Tuple(tuple) if tuple.len() == 1 => {
-            let expression = tuple.iter_values().next().unwrap();
-
-            match expression {
-                Expression::String(string) => convert_string_expression_to_path(string),
-                _ => None,
-            }
-        }
-        _ => None,
+    match match_require_argument(call)? {
+        RequireArgument::String(string) => convert_string_expression_to_path(string),
+        RequireArgument::Expression(_) => None,
     }
     .map(utils::normalize_path_with_current_dir)
 }
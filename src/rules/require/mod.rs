@@ -1,10 +1,31 @@
+//! Helpers to find and convert require calls, which can be reused by custom rules.
+
+mod auto_require_mode;
+mod filesystem_layout;
 mod match_require;
 mod path_iterator;
 mod path_locator;
 mod path_require_mode;
+mod path_style;
+mod quote_style;
+mod require_aliases;
+mod require_directive;
 mod roblox_require_mode;
+mod scoped_locals;
 
-pub(crate) use match_require::{is_require_call, match_path_require_call};
+pub(crate) use match_require::{
+    format_expression, format_require_argument, format_require_call, match_path_require_call,
+    match_protected_require_call, match_require_argument, require_call_line, ProtectedRequireCall,
+    RequireArgument,
+};
+pub use match_require::{is_require_call, require_argument, RequireArg};
 pub(crate) use path_locator::RequirePathLocator;
+pub(crate) use quote_style::find_arguments_quote;
+pub(crate) use require_aliases::RequireAliases;
+pub(crate) use require_directive::{match_require_directive, RequireDirective};
+pub(crate) use scoped_locals::{iter_local_assign_values, ScopedLocals};
+pub use auto_require_mode::{AutoRequireMode, DetectedRequireMode};
 pub use path_require_mode::PathRequireMode;
+pub use path_style::{PathExtensionStyle, PathStyle};
+pub use quote_style::QuoteStyle;
 pub use roblox_require_mode::RobloxRequireMode;
@@ -53,15 +53,36 @@ impl<'a, 'b, 'c> RequirePathLocator<'a, 'b, 'c> {
                     ))
                 })?;
 
-                let mut extra_module_location = self
+                let mut extra_module_location = match self
                     .path_require_mode
                     .get_source(source_name, self.extra_module_relative_location)
-                    .ok_or_else(|| {
-                        DarkluaError::invalid_resource_path(
+                {
+                    Some(location) => location,
+                    None => {
+                        if let Some(module_path) = self.find_in_lookup_directories(&path, source)? {
+                            return Ok(module_path);
+                        }
+                        return Err(DarkluaError::invalid_resource_path(
                             path.display().to_string(),
-                            format!("unknown source name `{}`", source_name),
-                        )
-                    })?;
+                            self.unknown_source_message(source_name),
+                        ));
+                    }
+                };
+                if let Some(source_file) = self.find_source_file(
+                    &utils::normalize_path_with_current_dir(&extra_module_location),
+                )? {
+                    if components.next().is_some() {
+                        return Err(DarkluaError::invalid_resource_path(
+                            path.display().to_string(),
+                            format!(
+                                "source `{}` points to the file `{}` and cannot be followed by a path",
+                                source_name,
+                                source_file.display()
+                            ),
+                        ));
+                    }
+                    return Ok(utils::normalize_path_with_current_dir(source_file));
+                }
                 extra_module_location.extend(components);
                 path = extra_module_location;
             }
@@ -93,6 +114,97 @@ impl<'a, 'b, 'c> RequirePathLocator<'a, 'b, 'c> {
     }
 }
 
+impl RequirePathLocator<'_, '_, '_> {
+    /// Returns the lookup directories where bare requires from the given source file are
+    /// searched, from the nearest to the furthest. The search stops at the project location.
+    pub(crate) fn lookup_locations(&self, source: &Path) -> Vec<PathBuf> {
+        let lookup_directories = self.path_require_mode.lookup_directories();
+        if lookup_directories.is_empty() {
+            return Vec::new();
+        }
+
+        let stop_location = utils::normalize_path(self.extra_module_relative_location);
+        let source_directory =
+            utils::normalize_path(source.parent().unwrap_or_else(|| Path::new("")));
+
+        source_directory
+            .ancestors()
+            .take_while(|directory| directory.starts_with(&stop_location))
+            .flat_map(|directory| {
+                lookup_directories
+                    .iter()
+                    .map(move |lookup_directory| directory.join(lookup_directory))
+            })
+            .collect()
+    }
+
+    fn find_in_lookup_directories(
+        &self,
+        path: &Path,
+        source: &Path,
+    ) -> Result<Option<PathBuf>, DarkluaError> {
+        for lookup_location in self.lookup_locations(source) {
+            let module_path = utils::normalize_path_with_current_dir(lookup_location.join(path));
+            for potential_path in path_iterator::find_require_paths(
+                &module_path,
+                self.path_require_mode.module_folder_name(),
+            ) {
+                if self.resources.is_file(&potential_path)? {
+                    log::trace!(
+                        "found `{}` in lookup directory `{}`",
+                        path.display(),
+                        lookup_location.display()
+                    );
+                    return Ok(Some(utils::normalize_path_with_current_dir(potential_path)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn unknown_source_message(&self, source_name: &str) -> String {
+        let lookup_directories = self.path_require_mode.lookup_directories();
+        if lookup_directories.is_empty() {
+            format!("unknown source name `{}`", source_name)
+        } else {
+            format!(
+                "unknown source name `{}` and no module found in lookup directories (`{}`)",
+                source_name,
+                lookup_directories.join("`, `")
+            )
+        }
+    }
+
+    // a source can point to a module file directly, with or without its extension
+    fn find_source_file(&self, location: &Path) -> Result<Option<PathBuf>, DarkluaError> {
+        if self.resources.is_file(location)? {
+            return Ok(Some(location.to_path_buf()));
+        }
+        if self.resources.is_directory(location)? {
+            return Ok(None);
+        }
+        if let Some(file_name) = location.file_name() {
+            if !matches!(
+                location
+                    .extension()
+                    .and_then(|extension| extension.to_str()),
+                Some("luau" | "lua")
+            ) {
+                for extension in ["luau", "lua"] {
+                    let mut next_name = file_name.to_os_string();
+                    next_name.push(".");
+                    next_name.push(extension);
+                    let potential_path = location.with_file_name(next_name);
+                    if self.resources.is_file(&potential_path)? {
+                        return Ok(Some(potential_path));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
 // the `is_relative` method from std::path::Path is not what darklua needs
 // to consider a require relative, which are paths that starts with `.` or `..`
 fn is_require_relative(path: &Path) -> bool {
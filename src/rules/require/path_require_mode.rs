@@ -11,7 +11,7 @@ use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 
-use super::RequirePathLocator;
+use super::{PathStyle, QuoteStyle, RequirePathLocator};
 
 /// A require mode for handling content from file system paths.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -26,6 +26,12 @@ pub struct PathRequireMode {
     sources: HashMap<String, PathBuf>,
     #[serde(default = "default_use_luau_configuration")]
     use_luau_configuration: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    lookup_directories: Vec<String>,
+    #[serde(default, skip_serializing_if = "PathStyle::is_default")]
+    path_style: PathStyle,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quote_style: Option<QuoteStyle>,
     #[serde(skip)]
     luau_rc_aliases: Option<HashMap<String, PathBuf>>,
 }
@@ -40,6 +46,9 @@ impl Default for PathRequireMode {
             module_folder_name: get_default_module_folder_name(),
             sources: Default::default(),
             use_luau_configuration: default_use_luau_configuration(),
+            lookup_directories: Vec::new(),
+            path_style: Default::default(),
+            quote_style: None,
             luau_rc_aliases: Default::default(),
         }
     }
@@ -63,10 +72,40 @@ impl PathRequireMode {
             module_folder_name: module_folder_name.into(),
             sources: Default::default(),
             use_luau_configuration: default_use_luau_configuration(),
+            lookup_directories: Vec::new(),
+            path_style: Default::default(),
+            quote_style: None,
             luau_rc_aliases: Default::default(),
         }
     }
 
+    /// Adds a directory name where bare requires (like `require('package')`) are searched,
+    /// in each ancestor directory of the requiring file.
+    pub fn with_lookup_directory(mut self, directory: impl Into<String>) -> Self {
+        self.lookup_directories.push(directory.into());
+        self
+    }
+
+    pub(crate) fn lookup_directories(&self) -> &[String] {
+        &self.lookup_directories
+    }
+
+    /// Sets how the require paths are written when converting requires to this mode.
+    pub fn with_path_style(mut self, path_style: PathStyle) -> Self {
+        self.path_style = path_style;
+        self
+    }
+
+    /// Sets the quote used to write the require paths when converting requires to this mode.
+    pub fn with_quote_style(mut self, quote_style: QuoteStyle) -> Self {
+        self.quote_style = Some(quote_style);
+        self
+    }
+
+    pub(crate) fn quote_style(&self) -> Option<QuoteStyle> {
+        self.quote_style
+    }
+
     pub(crate) fn initialize(&mut self, context: &Context) -> Result<(), DarkluaError> {
         if !self.use_luau_configuration {
             self.luau_rc_aliases.take();
@@ -127,6 +166,13 @@ impl PathRequireMode {
         _current_mode: &crate::rules::RequireMode,
         context: &Context<'_, '_, '_>,
     ) -> Result<Option<crate::nodes::Arguments>, crate::DarkluaError> {
+        if let Some((lookup_path, is_module_folder)) = self.find_lookup_require(path, context) {
+            let as_str = self.path_style.format(&lookup_path, is_module_folder);
+            return Ok(Some(
+                Arguments::default().with_argument(StringExpression::from_value(as_str)),
+            ));
+        }
+
         // Convert absolute or project-relative path to a relative './' path from current file
         let source = context.current_path();
         let source_dir = source.parent().unwrap_or_else(|| Path::new("."));
@@ -142,12 +188,51 @@ impl PathRequireMode {
             relative = Path::new(".").join(relative);
         }
 
-        let normalized = crate::utils::normalize_path_with_current_dir(relative);
-        let as_str = normalized.display().to_string();
+        let mut normalized = crate::utils::normalize_path_with_current_dir(relative);
+
+        // a require to a path like `./x/y/init.lua` can point to its folder instead
+        let mut is_module_folder = false;
+        if self.is_module_folder_name(&normalized) {
+            if let Some(folder) = normalized
+                .parent()
+                .filter(|folder| folder.file_name().is_some())
+            {
+                normalized = folder.to_path_buf();
+                is_module_folder = true;
+            }
+        }
+
+        let as_str = self.path_style.format(&normalized, is_module_folder);
         Ok(Some(
             Arguments::default().with_argument(StringExpression::from_value(as_str)),
         ))
     }
+
+    // finds the bare require path of a module located in a lookup directory, only if
+    // requiring that path from the current file resolves to the same module
+    fn find_lookup_require(&self, path: &Path, context: &Context) -> Option<(PathBuf, bool)> {
+        let locator =
+            RequirePathLocator::new(self, context.project_location(), context.resources());
+        let normalized_path = crate::utils::normalize_path(path);
+
+        locator
+            .lookup_locations(context.current_path())
+            .into_iter()
+            .filter_map(|lookup_location| {
+                let mut lookup_path = normalized_path.strip_prefix(&lookup_location).ok()?;
+                let is_module_folder = self.is_module_folder_name(lookup_path);
+                if is_module_folder {
+                    lookup_path = lookup_path.parent()?;
+                }
+                lookup_path.file_name()?;
+                Some((lookup_path.to_path_buf(), is_module_folder))
+            })
+            .find(|(lookup_path, _)| {
+                locator
+                    .find_require_path(lookup_path, context.current_path())
+                    .is_ok_and(|resolved| crate::utils::normalize_path(resolved) == normalized_path)
+            })
+    }
 }
 
 #[cfg(test)]
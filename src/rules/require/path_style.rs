@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+/// Defines how the extension of Lua and Luau files is written in generated require paths.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PathExtensionStyle {
+    /// Keeps the extension of the required file as is.
+    #[default]
+    Keep,
+    /// Removes the `.lua` or `.luau` extension.
+    Strip,
+    /// Always writes the `.luau` extension.
+    ForceLuau,
+    /// Always writes the `.lua` extension.
+    ForceLua,
+}
+
+impl PathExtensionStyle {
+    fn is_keep(&self) -> bool {
+        matches!(self, Self::Keep)
+    }
+
+    fn apply(&self, path: &Path) -> PathBuf {
+        let is_lua_file = match path.extension().and_then(OsStr::to_str) {
+            Some(extension) => matches!(extension, "lua" | "luau"),
+            None => true,
+        };
+
+        if !is_lua_file || path.file_name().is_none() {
+            return path.to_path_buf();
+        }
+
+        match self {
+            Self::Keep => path.to_path_buf(),
+            Self::Strip => path.with_extension(""),
+            Self::ForceLuau => path.with_extension("luau"),
+            Self::ForceLua => path.with_extension("lua"),
+        }
+    }
+}
+
+/// Defines how require paths are written when generating path requires.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub struct PathStyle {
+    #[serde(default, skip_serializing_if = "PathExtensionStyle::is_keep")]
+    extension: PathExtensionStyle,
+    #[serde(
+        default = "get_default_separator",
+        skip_serializing_if = "is_default_separator"
+    )]
+    separator: char,
+}
+
+const DEFAULT_SEPARATOR: char = '/';
+
+fn get_default_separator() -> char {
+    DEFAULT_SEPARATOR
+}
+
+fn is_default_separator(separator: &char) -> bool {
+    *separator == DEFAULT_SEPARATOR
+}
+
+impl Default for PathStyle {
+    fn default() -> Self {
+        Self {
+            extension: PathExtensionStyle::default(),
+            separator: DEFAULT_SEPARATOR,
+        }
+    }
+}
+
+impl PathStyle {
+    /// Sets how the extension of the required files is written.
+    pub fn with_extension(mut self, extension: PathExtensionStyle) -> Self {
+        self.extension = extension;
+        self
+    }
+
+    /// Sets the separator written between the components of the paths.
+    pub fn with_separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    pub(crate) fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Formats a relative require path. The extension is not applied to paths pointing
+    /// to a module folder, since the folder is required instead of its init file.
+    pub(crate) fn format(&self, path: &Path, is_module_folder: bool) -> String {
+        let path = if is_module_folder {
+            path.to_path_buf()
+        } else {
+            self.extension.apply(path)
+        };
+
+        path.components()
+            .filter_map(|component| match component {
+                Component::CurDir => Some(".".into()),
+                Component::ParentDir => Some("..".into()),
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                Component::RootDir | Component::Prefix(_) => None,
+            })
+            .collect::<Vec<_>>()
+            .join(&self.separator.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn format(style: PathStyle, path: &str) -> String {
+        style.format(Path::new(path), false)
+    }
+
+    #[test]
+    fn default_style_keeps_extension() {
+        assert_eq!(format(PathStyle::default(), "./a.lua"), "./a.lua");
+    }
+
+    #[test]
+    fn strip_extension() {
+        let style = PathStyle::default().with_extension(PathExtensionStyle::Strip);
+
+        assert_eq!(format(style, "../lib/a.luau"), "../lib/a");
+    }
+
+    #[test]
+    fn force_luau_extension_replaces_lua_extension() {
+        let style = PathStyle::default().with_extension(PathExtensionStyle::ForceLuau);
+
+        assert_eq!(format(style, "./a.lua"), "./a.luau");
+    }
+
+    #[test]
+    fn force_lua_extension_adds_missing_extension() {
+        let style = PathStyle::default().with_extension(PathExtensionStyle::ForceLua);
+
+        assert_eq!(format(style, "./a"), "./a.lua");
+    }
+
+    #[test]
+    fn force_extension_keeps_other_extensions() {
+        let style = PathStyle::default().with_extension(PathExtensionStyle::ForceLuau);
+
+        assert_eq!(format(style, "./config.json"), "./config.json");
+    }
+
+    #[test]
+    fn force_extension_is_not_applied_to_module_folder() {
+        let style = PathStyle::default().with_extension(PathExtensionStyle::ForceLuau);
+
+        assert_eq!(style.format(Path::new("../folder"), true), "../folder");
+    }
+
+    #[test]
+    fn custom_separator() {
+        let style = PathStyle::default().with_separator('\\');
+
+        assert_eq!(format(style, "../lib/a.lua"), "..\\lib\\a.lua");
+    }
+
+    #[test]
+    fn deserialize_path_style() {
+        let style: PathStyle =
+            json5::from_str("{ extension: 'force_luau', separator: '/' }").unwrap();
+
+        assert_eq!(
+            style,
+            PathStyle::default().with_extension(PathExtensionStyle::ForceLuau)
+        );
+    }
+}
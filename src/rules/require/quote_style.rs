@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+use crate::nodes::{Arguments, Expression, StringExpression, StringQuote};
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+
+/// Defines the quote used to write the strings of generated requires.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteStyle {
+    /// Writes strings with single quotes (`'`).
+    Single,
+    /// Writes strings with double quotes (`"`).
+    Double,
+    /// Uses the quote of the strings in the original require argument, when available.
+    Preserve,
+}
+
+impl QuoteStyle {
+    /// Returns the quote to use, given the quote found in the original require argument.
+    pub(crate) fn resolve(&self, original: Option<StringQuote>) -> Option<StringQuote> {
+        match self {
+            Self::Single => Some(StringQuote::Single),
+            Self::Double => Some(StringQuote::Double),
+            Self::Preserve => original,
+        }
+    }
+
+    /// Sets the quote of every string in the given arguments.
+    pub(crate) fn apply_to_arguments(
+        &self,
+        arguments: &mut Arguments,
+        original: Option<StringQuote>,
+    ) {
+        if let Some(quote) = self.resolve(original) {
+            DefaultVisitor::visit_arguments(arguments, &mut SetQuote(quote));
+        }
+    }
+
+    /// Sets the quote of every string in the given expression.
+    pub(crate) fn apply_to_expression(
+        &self,
+        expression: &mut Expression,
+        original: Option<StringQuote>,
+    ) {
+        if let Some(quote) = self.resolve(original) {
+            DefaultVisitor::visit_expression(expression, &mut SetQuote(quote));
+        }
+    }
+}
+
+struct SetQuote(StringQuote);
+
+impl NodeProcessor for SetQuote {
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        string.set_quote(self.0);
+    }
+}
+
+struct FindQuote<'a> {
+    code: &'a str,
+    quote: Option<StringQuote>,
+}
+
+impl NodeProcessor for FindQuote<'_> {
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        if self.quote.is_some() {
+            return;
+        }
+        self.quote = string
+            .get_token()
+            .and_then(|token| token.try_read_from(self.code))
+            .and_then(StringQuote::from_literal)
+            .or_else(|| string.get_quote());
+    }
+}
+
+/// Finds the quote of the first string written in the given require arguments. The
+/// quote can only be found when the tokens of the arguments are preserved.
+pub(crate) fn find_arguments_quote(arguments: &Arguments, code: &str) -> Option<StringQuote> {
+    let mut find_quote = FindQuote { code, quote: None };
+    DefaultVisitor::visit_arguments(&mut arguments.clone(), &mut find_quote);
+    find_quote.quote
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::nodes::Statement;
+    use crate::Parser;
+
+    fn parse_arguments(code: &str) -> Arguments {
+        let block = Parser::default().preserve_tokens().parse(code).unwrap();
+        let arguments = match block.iter_statements().next() {
+            Some(Statement::Call(call)) => call.get_arguments().clone(),
+            _ => panic!("expected a call statement"),
+        };
+        arguments
+    }
+
+    #[test]
+    fn find_single_quote() {
+        let code = "require('./a')";
+        assert_eq!(
+            find_arguments_quote(&parse_arguments(code), code),
+            Some(StringQuote::Single)
+        );
+    }
+
+    #[test]
+    fn find_double_quote_in_nested_call() {
+        let code = "require(script.Parent:FindFirstChild(\"a\"))";
+        assert_eq!(
+            find_arguments_quote(&parse_arguments(code), code),
+            Some(StringQuote::Double)
+        );
+    }
+
+    #[test]
+    fn find_no_quote_without_strings() {
+        let code = "require(script.Parent.a)";
+        assert_eq!(find_arguments_quote(&parse_arguments(code), code), None);
+    }
+
+    #[test]
+    fn preserve_without_original_quote() {
+        assert_eq!(QuoteStyle::Preserve.resolve(None), None);
+    }
+
+    #[test]
+    fn double_ignores_original_quote() {
+        assert_eq!(
+            QuoteStyle::Double.resolve(Some(StringQuote::Single)),
+            Some(StringQuote::Double)
+        );
+    }
+
+    #[test]
+    fn deserialize_preserve() {
+        assert_eq!(
+            serde_json::from_str::<QuoteStyle>("\"preserve\"").unwrap(),
+            QuoteStyle::Preserve
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::nodes::{Expression, FunctionCall, LocalAssignStatement};
+
+use super::match_require::call_identifier;
+use super::scoped_locals::{iter_local_assign_values, ScopedLocals};
+
+const REQUIRE_FUNCTION_IDENTIFIER: &str = "require";
+
+/// Tracks the locals in scope to find the ones bound to the global `require` function
+/// (like `local load = require`), so that calls through these locals are handled like
+/// regular require calls.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RequireAliases {
+    // each local is bound to true when it is an alias of `require`
+    locals: ScopedLocals<bool>,
+}
+
+impl RequireAliases {
+    pub(crate) fn push_scope(&mut self) {
+        self.locals.push_scope();
+    }
+
+    pub(crate) fn pop_scope(&mut self) {
+        self.locals.pop_scope();
+    }
+
+    pub(crate) fn prepare_local_assign(&mut self, statement: &LocalAssignStatement) {
+        let pending: Vec<_> = iter_local_assign_values(statement)
+            .map(|value| match value {
+                Some(Expression::Identifier(identifier)) => {
+                    self.is_require_identifier(identifier.get_name())
+                }
+                _ => false,
+            })
+            .collect();
+        self.locals.prepare_local_assign(pending.into_iter());
+    }
+
+    pub(crate) fn insert_local(&mut self, name: &str) {
+        self.locals.insert_local(name, false);
+    }
+
+    /// Inserts a local that is not bound to `require`, like a parameter or a loop variable.
+    pub(crate) fn insert_other(&mut self, name: &str) {
+        self.locals.insert(name, false);
+    }
+
+    /// Marks the innermost local with the given name as reassigned, so it is no longer
+    /// considered an alias of `require`.
+    pub(crate) fn reassign(&mut self, name: &str) {
+        self.locals.reassign(name, false);
+    }
+
+    /// Returns true if the call is made through the unshadowed `require` identifier or
+    /// one of its aliases, which can be wrapped in parentheses.
+    pub(crate) fn is_require_call(&self, call: &FunctionCall) -> bool {
+        call_identifier(call)
+            .is_some_and(|identifier| self.is_require_identifier(identifier.get_name()))
+    }
+
+    /// Returns true if the identifier refers to the unshadowed `require` function or one of
+    /// its aliases.
+    pub(crate) fn is_require_identifier(&self, name: &str) -> bool {
+        match self.locals.get(name) {
+            Some(is_require_alias) => *is_require_alias,
+            None => name == REQUIRE_FUNCTION_IDENTIFIER,
+        }
+    }
+
+    /// Returns true if no local with the given name is in scope.
+    pub(crate) fn is_global(&self, name: &str) -> bool {
+        self.locals.get(name).is_none()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::nodes::{Identifier, ParentheseExpression};
+
+    fn call(name: &str) -> FunctionCall {
+        FunctionCall::from_name(name)
+    }
+
+    fn declare(aliases: &mut RequireAliases, name: &str, value: &str) {
+        let statement =
+            LocalAssignStatement::from_variable(name).with_value(Identifier::new(value));
+        aliases.prepare_local_assign(&statement);
+        aliases.insert_local(name);
+    }
+
+    #[test]
+    fn global_require_is_a_require_call() {
+        assert!(RequireAliases::default().is_require_call(&call("require")));
+    }
+
+    #[test]
+    fn parenthesized_require_is_a_require_call() {
+        let call = FunctionCall::from_prefix(ParentheseExpression::new(Identifier::new("require")));
+
+        assert!(RequireAliases::default().is_require_call(&call));
+    }
+
+    #[test]
+    fn local_assigned_to_require_is_a_require_call() {
+        let mut aliases = RequireAliases::default();
+        declare(&mut aliases, "load", "require");
+
+        assert!(aliases.is_require_call(&call("load")));
+    }
+
+    #[test]
+    fn local_assigned_to_an_alias_is_a_require_call() {
+        let mut aliases = RequireAliases::default();
+        declare(&mut aliases, "load", "require");
+        declare(&mut aliases, "loadModule", "load");
+
+        assert!(aliases.is_require_call(&call("loadModule")));
+    }
+
+    #[test]
+    fn shadowed_alias_is_not_a_require_call() {
+        let mut aliases = RequireAliases::default();
+        declare(&mut aliases, "load", "require");
+        aliases.push_scope();
+        aliases.insert_other("load");
+
+        assert!(!aliases.is_require_call(&call("load")));
+
+        aliases.pop_scope();
+
+        assert!(aliases.is_require_call(&call("load")));
+    }
+
+    #[test]
+    fn reassigned_alias_is_not_a_require_call() {
+        let mut aliases = RequireAliases::default();
+        declare(&mut aliases, "load", "require");
+        aliases.reassign("load");
+
+        assert!(!aliases.is_require_call(&call("load")));
+    }
+
+    #[test]
+    fn alias_of_shadowed_require_is_not_a_require_call() {
+        let mut aliases = RequireAliases::default();
+        aliases.insert_other("require");
+        declare(&mut aliases, "load", "require");
+
+        assert!(!aliases.is_require_call(&call("require")));
+        assert!(!aliases.is_require_call(&call("load")));
+    }
+
+    #[test]
+    fn local_is_not_global() {
+        let mut aliases = RequireAliases::default();
+        aliases.push_scope();
+        aliases.insert_other("pcall");
+
+        assert!(!aliases.is_global("pcall"));
+
+        aliases.pop_scope();
+
+        assert!(aliases.is_global("pcall"));
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    nodes::{Arguments, FunctionCall, Token},
+    utils::iter_trailing_comment_directives,
+};
+
+const KEEP_REQUIRE_DIRECTIVE: &str = "keep-require";
+const BUNDLE_DIRECTIVE: &str = "bundle";
+
+/// A comment directive that changes how the bundler handles a single require call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RequireDirective {
+    /// `-- darklua: keep-require`: handle the require as if it was excluded
+    KeepRequire,
+    /// `-- darklua: bundle`: bundle the require even if it matches an exclude pattern
+    Bundle,
+}
+
+impl RequireDirective {
+    fn parse(directive: &str) -> Option<Self> {
+        match directive {
+            KEEP_REQUIRE_DIRECTIVE => Some(Self::KeepRequire),
+            BUNDLE_DIRECTIVE => Some(Self::Bundle),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the require directive written in a comment right after the call. Directives can
+/// only be found when tokens are preserved.
+pub(crate) fn match_require_directive(call: &FunctionCall) -> Option<RequireDirective> {
+    last_call_token(call)
+        .into_iter()
+        .flat_map(iter_trailing_comment_directives)
+        .find_map(RequireDirective::parse)
+}
+
+fn last_call_token(call: &FunctionCall) -> Option<&Token> {
+    match call.get_arguments() {
+        Arguments::Tuple(tuple) => tuple.get_tokens().map(|tokens| &tokens.closing_parenthese),
+        Arguments::String(string) => string.get_token(),
+        Arguments::Table(table) => table.get_tokens().map(|tokens| &tokens.closing_brace),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{
+        nodes::{Block, Expression, Statement},
+        rules::{ContextBuilder, FlawlessRule, ReplaceReferencedTokens},
+        Parser, Resources,
+    };
+
+    fn parse_call(code: &str) -> FunctionCall {
+        let mut block: Block = Parser::default().preserve_tokens().parse(code).unwrap();
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new("test.lua", &resources, code).build();
+        ReplaceReferencedTokens::default().flawless_process(&mut block, &context);
+
+        let call = match block.iter_statements().next() {
+            Some(Statement::Call(call)) => call.clone(),
+            Some(Statement::LocalAssign(assign)) => match assign.iter_values().next() {
+                Some(Expression::Call(call)) => *call.clone(),
+                _ => panic!("expected a call value"),
+            },
+            _ => panic!("expected a call"),
+        };
+        call
+    }
+
+    #[test]
+    fn keep_require_after_parentheses() {
+        assert_eq!(
+            match_require_directive(&parse_call(
+                "local x = require('./a') -- darklua: keep-require"
+            )),
+            Some(RequireDirective::KeepRequire)
+        );
+    }
+
+    #[test]
+    fn bundle_after_string_argument() {
+        assert_eq!(
+            match_require_directive(&parse_call("require './a' -- darklua: bundle")),
+            Some(RequireDirective::Bundle)
+        );
+    }
+
+    #[test]
+    fn unknown_directive() {
+        assert_eq!(
+            match_require_directive(&parse_call("require('./a') -- darklua: other")),
+            None
+        );
+    }
+
+    #[test]
+    fn call_without_comment() {
+        assert_eq!(match_require_directive(&parse_call("require('./a')")), None);
+    }
+}
@@ -2,13 +2,18 @@ use serde::{Deserialize, Serialize};
 
 use crate::frontend::DarkluaResult;
 use crate::nodes::FunctionCall;
+use crate::rules::bundle::CaseSensitivePaths;
 use crate::rules::Context;
-use crate::DarkluaError;
+use crate::{DarkluaError, Resources};
+
+use super::filesystem_layout;
 
 use std::path::{Path, PathBuf};
 
 // Reuse the Rojo sourcemap and instance path data structures from convert_require
-use crate::rules::convert_require::{InstancePath, RojoSourcemap};
+use crate::rules::convert_require::{
+    InstancePath, InstancePathResolutionError, RojoSourcemap, SourcemapCommandState,
+};
 
 /// A require mode for handling Roblox-specific require patterns.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -16,15 +21,27 @@ use crate::rules::convert_require::{InstancePath, RojoSourcemap};
 pub struct RobloxRequireMode {
     #[serde(default)]
     rojo_sourcemap: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rojo_sourcemap_command: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    filesystem_fallback: bool,
     #[serde(skip)]
     cached_sourcemap: Option<RojoSourcemap>,
+    #[serde(skip)]
+    case_sensitive_paths: CaseSensitivePaths,
+    #[serde(skip)]
+    sourcemap_command_state: SourcemapCommandState,
 }
 
 impl Default for RobloxRequireMode {
     fn default() -> Self {
         Self {
             rojo_sourcemap: None,
+            rojo_sourcemap_command: None,
+            filesystem_fallback: false,
             cached_sourcemap: None,
+            case_sensitive_paths: CaseSensitivePaths::default(),
+            sourcemap_command_state: SourcemapCommandState::default(),
         }
     }
 }
@@ -35,22 +52,64 @@ impl RobloxRequireMode {
         Self::default()
     }
 
+    /// Sets the path of the Rojo sourcemap used to locate modules, relative to the
+    /// project location.
+    pub fn with_rojo_sourcemap(mut self, rojo_sourcemap: impl Into<PathBuf>) -> Self {
+        self.rojo_sourcemap = Some(rojo_sourcemap.into());
+        self
+    }
+
+    /// Sets a command that regenerates the Rojo sourcemap (like `rojo sourcemap -o
+    /// sourcemap.json`). The command runs from the project location when the sourcemap is
+    /// missing or older than a Lua or Luau file of the project.
+    pub fn with_rojo_sourcemap_command<I, S>(mut self, command: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rojo_sourcemap_command = Some(command.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets if `script` instance paths are resolved from the file layout when no Rojo
+    /// sourcemap is provided: `Parent` is the parent directory (the directory of an `init`
+    /// file is its script) and a child is a `.lua` or `.luau` file, or a directory with an
+    /// `init` file.
+    pub fn with_filesystem_fallback(mut self, filesystem_fallback: bool) -> Self {
+        self.filesystem_fallback = filesystem_fallback;
+        self
+    }
+
+    /// Sets if the file paths of the Rojo sourcemap are matched with their casing. This
+    /// must be set before initializing the require mode.
+    pub(crate) fn set_case_sensitive_paths(&mut self, case_sensitive_paths: CaseSensitivePaths) {
+        self.case_sensitive_paths = case_sensitive_paths;
+    }
+
     pub(crate) fn initialize(&mut self, context: &Context) -> Result<(), DarkluaError> {
         if let Some(ref rojo_sourcemap_path) = self
             .rojo_sourcemap
             .as_ref()
             .map(|p| context.project_location().join(p))
         {
+            if let Some(command) = &self.rojo_sourcemap_command {
+                self.sourcemap_command_state
+                    .update_sourcemap(command, rojo_sourcemap_path, context)
+                    .map_err(|err| err.context("while initializing Roblox require mode"))?;
+            }
+
             // track sourcemap as a dependency and cache parsed content
             context.add_file_dependency(rojo_sourcemap_path.clone());
             let parent = get_relative_parent_path(rojo_sourcemap_path);
-            let content = context
-                .resources()
-                .get(rojo_sourcemap_path)
-                .map_err(|err| {
-                    DarkluaError::from(err).context("while initializing Roblox require mode")
-                })?;
-            let sourcemap = RojoSourcemap::parse(&content, parent).map_err(|err| {
+            let content = RojoSourcemap::read_content(rojo_sourcemap_path, context.resources())
+                .map_err(|err| err.context("while initializing Roblox require mode"))?;
+            let sourcemap = RojoSourcemap::load(
+                &content,
+                parent,
+                context.resources(),
+                self.case_sensitive_paths,
+            )
+            .map_err(|err| {
                 err.context(format!(
                     "unable to parse Rojo sourcemap at `{}`",
                     rojo_sourcemap_path.display()
@@ -77,10 +136,41 @@ impl RobloxRequireMode {
         &self,
         from_file: &Path,
         instance_path: &InstancePath,
-    ) -> Option<PathBuf> {
+        resources: &Resources,
+    ) -> Result<PathBuf, InstancePathResolutionError> {
+        match &self.cached_sourcemap {
+            Some(map) => map.get_file_from_instance_path(from_file, instance_path),
+            None if self.filesystem_fallback => {
+                filesystem_layout::get_file_from_instance_path(from_file, instance_path, resources)
+            }
+            None => Err(InstancePathResolutionError::NotFound),
+        }
+    }
+
+    pub(crate) fn get_non_lua_files_from_instance_path(
+        &self,
+        from_file: &Path,
+        instance_path: &InstancePath,
+    ) -> Vec<PathBuf> {
         self.cached_sourcemap
             .as_ref()
-            .and_then(|map| map.get_file_from_instance_path(from_file, instance_path))
+            .map(|map| map.get_non_lua_files_from_instance_path(from_file, instance_path))
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn has_sourcemap(&self) -> bool {
+        self.cached_sourcemap.is_some()
+    }
+
+    pub(crate) fn get_class_name_from_instance_path(
+        &self,
+        from_file: &Path,
+        instance_path: &InstancePath,
+    ) -> Result<&str, InstancePathResolutionError> {
+        self.cached_sourcemap
+            .as_ref()
+            .ok_or(InstancePathResolutionError::NotFound)
+            .and_then(|map| map.get_class_name_from_instance_path(from_file, instance_path))
     }
 
     pub(crate) fn get_instance_path_for_file(
@@ -99,6 +189,15 @@ impl RobloxRequireMode {
             .and_then(|map| map.get_absolute_instance_path(target_file))
     }
 
+    /// Returns a message explaining that the given file is not present in the Rojo
+    /// sourcemap, or `None` if the file is in the sourcemap or if there is no sourcemap.
+    pub(crate) fn missing_file_message(&self, file: &Path) -> Option<String> {
+        let sourcemap_path = self.rojo_sourcemap.as_ref()?;
+        self.cached_sourcemap
+            .as_ref()?
+            .missing_file_message(file, sourcemap_path)
+    }
+
     pub(crate) fn generate_require(
         &self,
         _path: &Path,
@@ -165,7 +264,7 @@ mod test {
 
         let mut mode = RobloxRequireMode {
             rojo_sourcemap: Some(PathBuf::from("default.project.json")),
-            cached_sourcemap: None,
+            ..Default::default()
         };
 
         mode.initialize(&context).expect("initialize failed");
@@ -191,7 +290,7 @@ mod test {
 
         let mut mode = RobloxRequireMode {
             rojo_sourcemap: Some(PathBuf::from("default.project.json")),
-            cached_sourcemap: None,
+            ..Default::default()
         };
         mode.initialize(&context).expect("initialize failed");
 
@@ -199,7 +298,7 @@ mod test {
         path.child("value");
 
         let resolved = mode
-            .get_file_from_instance_path(Path::new("src/init.lua"), &path)
+            .get_file_from_instance_path(Path::new("src/init.lua"), &path, &resources)
             .expect("failed to resolve instance path to file");
 
         assert!(resolved.ends_with("src/value.lua"), "got: {resolved:?}");
@@ -212,7 +311,7 @@ mod test {
 
         let mut mode = RobloxRequireMode {
             rojo_sourcemap: Some(PathBuf::from("default.project.json")),
-            cached_sourcemap: None,
+            ..Default::default()
         };
         mode.initialize(&context).expect("initialize failed");
 
@@ -0,0 +1,167 @@
+use crate::nodes::{Expression, LocalAssignStatement};
+
+#[derive(Debug, Clone)]
+struct ScopedLocal<T> {
+    name: String,
+    value: T,
+}
+
+/// Tracks the local variables visible at each point of a block, along with a value bound to
+/// each local when it is declared.
+#[derive(Debug, Clone)]
+pub(crate) struct ScopedLocals<T> {
+    locals: Vec<ScopedLocal<T>>,
+    scopes: Vec<usize>,
+    // computed before the local assignments are visited, since the values are visited
+    // before the locals are inserted
+    pending_values: Vec<Vec<T>>,
+}
+
+impl<T> Default for ScopedLocals<T> {
+    fn default() -> Self {
+        Self {
+            locals: Vec::new(),
+            scopes: Vec::new(),
+            pending_values: Vec::new(),
+        }
+    }
+}
+
+impl<T> ScopedLocals<T> {
+    pub(crate) fn push_scope(&mut self) {
+        self.scopes.push(self.locals.len());
+    }
+
+    pub(crate) fn pop_scope(&mut self) {
+        if let Some(start) = self.scopes.pop() {
+            self.locals.truncate(start);
+        }
+    }
+
+    /// Stores the values of the next locals inserted with `insert_local`, in the order of
+    /// the variables of the local assignment.
+    pub(crate) fn prepare_local_assign(&mut self, values: impl Iterator<Item = T>) {
+        let mut pending: Vec<_> = values.collect();
+        pending.reverse();
+        self.pending_values.push(pending);
+    }
+
+    /// Inserts a local declared by a local assignment, bound to the value prepared for it
+    /// or to `default` when no value was prepared.
+    pub(crate) fn insert_local(&mut self, name: &str, default: T) {
+        let value = match self.pending_values.last_mut() {
+            Some(pending) => {
+                let value = pending.pop().unwrap_or(default);
+                if pending.is_empty() {
+                    self.pending_values.pop();
+                }
+                value
+            }
+            None => default,
+        };
+        self.insert(name, value);
+    }
+
+    pub(crate) fn insert(&mut self, name: &str, value: T) {
+        self.locals.push(ScopedLocal {
+            name: name.to_owned(),
+            value,
+        });
+    }
+
+    /// Replaces the value of the innermost local with the given name.
+    pub(crate) fn reassign(&mut self, name: &str, value: T) {
+        if let Some(local) = self
+            .locals
+            .iter_mut()
+            .rev()
+            .find(|local| local.name == name)
+        {
+            local.value = value;
+        }
+    }
+
+    /// Returns the number of locals currently in scope.
+    pub(crate) fn visible(&self) -> usize {
+        self.locals.len()
+    }
+
+    /// Finds the value of the innermost local with the given name.
+    pub(crate) fn get(&self, name: &str) -> Option<&T> {
+        self.find(name, self.locals.len()).map(|(_, value)| value)
+    }
+
+    /// Finds the innermost local named `name` among the first `visible` locals. The returned
+    /// position is the number of locals visible from the value of that local.
+    pub(crate) fn find(&self, name: &str, visible: usize) -> Option<(usize, &T)> {
+        self.locals[..visible.min(self.locals.len())]
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, local)| local.name == name)
+            .map(|(position, local)| (position, &local.value))
+    }
+}
+
+/// Iterates over the value assigned to each variable of a local assignment.
+pub(crate) fn iter_local_assign_values(
+    statement: &LocalAssignStatement,
+) -> impl Iterator<Item = Option<&Expression>> {
+    let mut values = statement.iter_values();
+    statement.iter_variables().map(move |_| values.next())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn inner_local_shadows_outer_local() {
+        let mut locals = ScopedLocals::default();
+        locals.insert("value", 1);
+        locals.push_scope();
+        locals.insert("value", 2);
+
+        assert_eq!(locals.get("value"), Some(&2));
+
+        locals.pop_scope();
+
+        assert_eq!(locals.get("value"), Some(&1));
+    }
+
+    #[test]
+    fn insert_local_uses_prepared_values_in_order() {
+        let mut locals = ScopedLocals::default();
+        locals.prepare_local_assign(vec![1, 2].into_iter());
+        locals.insert_local("a", 0);
+        locals.insert_local("b", 0);
+        locals.insert_local("c", 0);
+
+        assert_eq!(locals.get("a"), Some(&1));
+        assert_eq!(locals.get("b"), Some(&2));
+        assert_eq!(locals.get("c"), Some(&0));
+    }
+
+    #[test]
+    fn reassign_updates_innermost_local() {
+        let mut locals = ScopedLocals::default();
+        locals.insert("value", 1);
+        locals.push_scope();
+        locals.insert("value", 2);
+        locals.reassign("value", 3);
+        locals.pop_scope();
+
+        assert_eq!(locals.get("value"), Some(&1));
+    }
+
+    #[test]
+    fn find_ignores_locals_declared_after_visible_position() {
+        let mut locals = ScopedLocals::default();
+        locals.insert("value", 1);
+        let visible = locals.visible();
+        locals.insert("value", 2);
+
+        assert_eq!(locals.find("value", visible), Some((0, &1)));
+        assert_eq!(locals.find("other", visible), None);
+    }
+}
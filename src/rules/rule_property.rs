@@ -8,7 +8,10 @@ use crate::{
     process::to_expression,
 };
 
-use super::{require::PathRequireMode, RequireMode, RobloxRequireMode, RuleConfigurationError};
+use super::{
+    require::{AutoRequireMode, PathRequireMode},
+    RequireMode, RobloxRequireMode, RuleConfigurationError,
+};
 
 pub type RuleProperties = HashMap<String, RulePropertyValue>;
 
@@ -39,6 +42,14 @@ impl RulePropertyValue {
         }
     }
 
+    pub(crate) fn expect_usize(self, key: &str) -> Result<usize, RuleConfigurationError> {
+        if let Self::Usize(value) = self {
+            Ok(value)
+        } else {
+            Err(RuleConfigurationError::UsizeExpected(key.to_owned()))
+        }
+    }
+
     pub(crate) fn expect_string(self, key: &str) -> Result<String, RuleConfigurationError> {
         if let Self::String(value) = self {
             Ok(value)
@@ -166,6 +177,11 @@ impl From<&RequireMode> for RulePropertyValue {
                     return Self::from("roblox");
                 }
             }
+            RequireMode::Auto(mode) => {
+                if mode.as_ref() == &AutoRequireMode::default() {
+                    return Self::from("auto");
+                }
+            }
         }
 
         Self::RequireMode(value.clone())
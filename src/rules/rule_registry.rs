@@ -0,0 +1,125 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use super::{Rule, RuleConfigurationError, RuleProperties};
+
+/// A function that creates a rule from its configuration properties.
+pub type RuleFactory = fn(RuleProperties) -> Result<Box<dyn Rule>, RuleConfigurationError>;
+
+static REGISTERED_RULES: OnceLock<RwLock<HashMap<&'static str, RuleFactory>>> = OnceLock::new();
+
+thread_local! {
+    static SCOPED_RULES: RefCell<Vec<HashMap<&'static str, RuleFactory>>> =
+        RefCell::new(Vec::new());
+}
+
+fn registered_rules() -> &'static RwLock<HashMap<&'static str, RuleFactory>> {
+    REGISTERED_RULES.get_or_init(Default::default)
+}
+
+/// Registers a rule so that it can be used from configuration files, in the same way
+/// built-in rules are referenced by their name.
+///
+/// Registering a rule with the name of a built-in rule returns an error. Registering
+/// a rule with a name that was already registered replaces the previous factory.
+pub fn register_rule(
+    name: &'static str,
+    factory: RuleFactory,
+) -> Result<(), RuleConfigurationError> {
+    verify_rule_name(name)?;
+
+    let mut rules = registered_rules()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    rules.insert(name, factory);
+
+    Ok(())
+}
+
+pub(crate) fn verify_rule_name(name: &str) -> Result<(), RuleConfigurationError> {
+    if super::create_builtin_rule(name).is_some() {
+        Err(RuleConfigurationError::RuleNameCollision(name.to_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Makes the given rules available while running `callback` on the current thread,
+/// without registering them globally.
+pub(crate) fn with_scoped_rules<T>(
+    rules: &HashMap<&'static str, RuleFactory>,
+    callback: impl FnOnce() -> T,
+) -> T {
+    if rules.is_empty() {
+        return callback();
+    }
+
+    SCOPED_RULES.with(|scoped| scoped.borrow_mut().push(rules.clone()));
+    let _guard = ScopedRulesGuard;
+
+    callback()
+}
+
+// removes the scoped rules when dropped, even if the callback panics
+struct ScopedRulesGuard;
+
+impl Drop for ScopedRulesGuard {
+    fn drop(&mut self) {
+        SCOPED_RULES.with(|scoped| {
+            scoped.borrow_mut().pop();
+        });
+    }
+}
+
+pub(crate) fn find_rule_factory(name: &str) -> Option<RuleFactory> {
+    let scoped_factory = SCOPED_RULES.with(|scoped| {
+        scoped
+            .borrow()
+            .iter()
+            .rev()
+            .find_map(|rules| rules.get(name).copied())
+    });
+
+    scoped_factory.or_else(|| {
+        registered_rules()
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(name)
+            .copied()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_rule(_: RuleProperties) -> Result<Box<dyn Rule>, RuleConfigurationError> {
+        Ok(Box::<crate::rules::RemoveComments>::default())
+    }
+
+    fn scoped_rules() -> HashMap<&'static str, RuleFactory> {
+        let mut rules = HashMap::new();
+        rules.insert("scoped_test_rule", create_rule as RuleFactory);
+        rules
+    }
+
+    #[test]
+    fn scoped_rule_is_found_in_callback() {
+        assert!(with_scoped_rules(&scoped_rules(), || find_rule_factory(
+            "scoped_test_rule"
+        )
+        .is_some()));
+        assert!(find_rule_factory("scoped_test_rule").is_none());
+    }
+
+    #[test]
+    fn scoped_rules_are_removed_when_callback_panics() {
+        let result = std::panic::catch_unwind(|| {
+            with_scoped_rules(&scoped_rules(), || panic!("callback panicked"))
+        });
+
+        assert!(result.is_err());
+        assert!(find_rule_factory("scoped_test_rule").is_none());
+    }
+}
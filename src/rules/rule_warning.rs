@@ -0,0 +1,194 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// A stable identifier for each kind of warning that rules can emit.
+///
+/// New codes may be added in future versions, so matching on this enum
+/// should always include a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum WarningCode {
+    /// A require call could not be resolved, so it was left as is.
+    UnresolvedRequire,
+    /// An excluded require could not be rewritten from the DataModel root and
+    /// falls back to a path relative to the script.
+    ExcludedFallbackScriptRoot,
+    /// A file that requires modules relative to its script is not present in the
+    /// Rojo sourcemap, so its requires cannot be resolved.
+    FileNotInSourcemap,
+    /// A `WaitForChild` call used to locate a required module has a timeout argument,
+    /// which bundling cannot preserve.
+    WaitForChildTimeout,
+    /// A bundled source file has no mapping in the generated sourcemap.
+    UnmappedSource,
+    /// A `darklua: disable` comment directive names a rule that does not exist.
+    UnknownDirectiveRule,
+    /// A require call does not match the require mode detected by the `auto` require mode.
+    MixedRequireModes,
+    /// A method call could not be converted to a regular call without evaluating its
+    /// receiver twice.
+    UnconvertedMethodCall,
+    /// A bundled module reads or writes the `_G` or `shared` global tables.
+    GlobalAccess,
+    /// A `string.char` call has a constant argument that is not a valid byte, so it was
+    /// not folded into a string.
+    InvalidStringCharByte,
+    /// The generated code of a bundle is larger than the configured warning size.
+    OutputSize,
+    /// A warning emitted by a rule that is not part of darklua.
+    Custom,
+}
+
+impl WarningCode {
+    /// Returns the name of the code, as written in configuration files.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UnresolvedRequire => "unresolved_require",
+            Self::ExcludedFallbackScriptRoot => "excluded_fallback_script_root",
+            Self::FileNotInSourcemap => "file_not_in_sourcemap",
+            Self::WaitForChildTimeout => "wait_for_child_timeout",
+            Self::UnmappedSource => "unmapped_source",
+            Self::UnknownDirectiveRule => "unknown_directive_rule",
+            Self::MixedRequireModes => "mixed_require_modes",
+            Self::UnconvertedMethodCall => "unconverted_method_call",
+            Self::GlobalAccess => "global_access",
+            Self::InvalidStringCharByte => "invalid_string_char_byte",
+            Self::OutputSize => "output_size",
+            Self::Custom => "custom",
+        }
+    }
+}
+
+impl fmt::Display for WarningCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A non-fatal diagnostic emitted by a rule while processing a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleWarning {
+    code: WarningCode,
+    message: String,
+    path: Option<PathBuf>,
+    line: Option<usize>,
+}
+
+impl RuleWarning {
+    /// Creates a new warning with the given code and message.
+    pub fn new(code: WarningCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            path: None,
+            line: None,
+        }
+    }
+
+    /// Associates the warning with a file path.
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Associates the warning with a line number.
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    /// Returns the code of the warning.
+    pub fn code(&self) -> WarningCode {
+        self.code
+    }
+
+    /// Returns the message of the warning.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the file path associated with the warning, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the line number associated with the warning, if any.
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+}
+
+impl fmt::Display for RuleWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        match (&self.path, self.line) {
+            (Some(path), Some(line)) => write!(f, " (`{}` at line {})", path.display(), line),
+            (Some(path), None) => write!(f, " (`{}`)", path.display()),
+            (None, Some(line)) => write!(f, " (line {})", line),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_message_only() {
+        assert_eq!(
+            RuleWarning::new(WarningCode::Custom, "oops").to_string(),
+            "oops"
+        );
+    }
+
+    #[test]
+    fn display_with_path() {
+        assert_eq!(
+            RuleWarning::new(WarningCode::Custom, "oops")
+                .with_path("src/main.lua")
+                .to_string(),
+            "oops (`src/main.lua`)"
+        );
+    }
+
+    #[test]
+    fn display_with_line() {
+        assert_eq!(
+            RuleWarning::new(WarningCode::Custom, "oops")
+                .with_line(4)
+                .to_string(),
+            "oops (line 4)"
+        );
+    }
+
+    #[test]
+    fn display_with_path_and_line() {
+        assert_eq!(
+            RuleWarning::new(WarningCode::Custom, "oops")
+                .with_path("src/main.lua")
+                .with_line(4)
+                .to_string(),
+            "oops (`src/main.lua` at line 4)"
+        );
+    }
+
+    #[test]
+    fn deserialize_warning_code() {
+        let code: WarningCode = serde_json::from_str("\"excluded_fallback_script_root\"").unwrap();
+
+        assert_eq!(code, WarningCode::ExcludedFallbackScriptRoot);
+    }
+
+    #[test]
+    fn display_warning_code() {
+        assert_eq!(
+            WarningCode::UnresolvedRequire.to_string(),
+            "unresolved_require"
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::generator::utils::write_string;
+use crate::nodes::{
+    Arguments, BinaryExpression, BinaryOperator, Block, Expression, FieldExpression, FunctionCall,
+    Identifier, Prefix, StringExpression, TupleArguments,
+};
+use crate::process::{
+    Evaluator, IdentifierTracker, LuaValue, NodeProcessor, NodeVisitor, ScopeVisitor,
+};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties, WarningCode,
+};
+use crate::utils::origin::{
+    anchor_from_expression, anchor_from_prefix, token_from_content_with_anchor, OriginAnchor,
+};
+
+use super::verify_no_rule_properties;
+
+const STRING_LIBRARY: &str = "string";
+const CHAR_FUNCTION: &str = "char";
+// Lua functions have at most 250 registers, so long strings are split into multiple calls
+const MAX_CHAR_ARGUMENTS: usize = 100;
+
+fn is_string_char_call(call: &FunctionCall, identifier_tracker: &IdentifierTracker) -> bool {
+    if call.has_method() {
+        return false;
+    }
+
+    match call.get_prefix() {
+        Prefix::Field(field) => {
+            field.get_field().get_name() == CHAR_FUNCTION
+                && matches!(
+                    field.get_prefix(),
+                    Prefix::Identifier(identifier) if identifier.get_name() == STRING_LIBRARY
+                )
+                && !identifier_tracker.is_identifier_used(STRING_LIBRARY)
+        }
+        _ => false,
+    }
+}
+
+struct FoldProcessor<'a> {
+    evaluator: Evaluator,
+    identifier_tracker: IdentifierTracker,
+    context: &'a Context<'a, 'a, 'a>,
+    invalid_bytes: Vec<(String, Option<usize>)>,
+}
+
+impl<'a> FoldProcessor<'a> {
+    fn new(context: &'a Context) -> Self {
+        Self {
+            evaluator: Evaluator::default(),
+            identifier_tracker: IdentifierTracker::default(),
+            context,
+            invalid_bytes: Vec::new(),
+        }
+    }
+
+    fn fold(&mut self, call: &FunctionCall) -> Option<Vec<u8>> {
+        let arguments = call.get_arguments().clone().to_expressions();
+        let mut values = Vec::with_capacity(arguments.len());
+
+        for argument in arguments.iter() {
+            if self.evaluator.has_side_effects(argument) {
+                return None;
+            }
+            match self.evaluator.evaluate(argument).number_coercion() {
+                LuaValue::Number(value) => values.push(value),
+                _ => return None,
+            }
+        }
+
+        let mut bytes = Vec::with_capacity(values.len());
+
+        for (index, value) in values.into_iter().enumerate() {
+            if value.fract() != 0.0 || !(0.0..=255.0).contains(&value) {
+                let line = anchor_from_expression(&arguments[index])
+                    .or_else(|| anchor_from_prefix(call.get_prefix()))
+                    .map(|anchor| anchor.line_number);
+                self.invalid_bytes.push((
+                    format!(
+                        concat!(
+                            "unable to fold `string.char` call because argument #{} ({}) ",
+                            "is not a byte value between 0 and 255"
+                        ),
+                        index + 1,
+                        value
+                    ),
+                    line,
+                ));
+                return None;
+            }
+            bytes.push(value as u8);
+        }
+
+        Some(bytes)
+    }
+
+    fn is_disabled(&self, expression: &Expression) -> bool {
+        anchor_from_expression(expression).is_some_and(|anchor| {
+            self.context
+                .is_line_disabled(FOLD_STRING_CHAR_RULE_NAME, anchor.line_number)
+        })
+    }
+}
+
+impl Deref for FoldProcessor<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for FoldProcessor<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for FoldProcessor<'_> {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if self.is_disabled(expression) {
+            return;
+        }
+
+        let bytes = match expression {
+            Expression::Call(call) if is_string_char_call(call, &self.identifier_tracker) => {
+                match self.fold(call) {
+                    Some(bytes) => bytes,
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+
+        let mut string = StringExpression::from_value(bytes);
+
+        if let Some(anchor) = anchor_from_expression(expression) {
+            let token = token_from_content_with_anchor(write_string(string.get_value()), anchor);
+            string.set_token(token);
+        }
+
+        *expression = string.into();
+    }
+}
+
+pub const FOLD_STRING_CHAR_RULE_NAME: &str = "fold_string_char";
+
+/// A rule that replaces `string.char` calls with constant arguments with string literals.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FoldStringChar {}
+
+impl FlawlessRule for FoldStringChar {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = FoldProcessor::new(context);
+        ScopeVisitor::visit_block(block, &mut processor);
+
+        for (message, line) in processor.invalid_bytes {
+            context.add_warning(
+                WarningCode::InvalidStringCharByte,
+                message,
+                Some(context.current_path().to_path_buf()),
+                line,
+            );
+        }
+    }
+}
+
+impl RuleConfiguration for FoldStringChar {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)
+    }
+
+    fn get_name(&self) -> &'static str {
+        FOLD_STRING_CHAR_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+}
+
+struct ExpandProcessor<'a> {
+    identifier_tracker: IdentifierTracker,
+    context: &'a Context<'a, 'a, 'a>,
+}
+
+impl<'a> ExpandProcessor<'a> {
+    fn new(context: &'a Context) -> Self {
+        Self {
+            identifier_tracker: IdentifierTracker::default(),
+            context,
+        }
+    }
+
+    fn expand(&self, string: &StringExpression) -> Option<Expression> {
+        if string.get_value().is_empty() {
+            return None;
+        }
+
+        let anchor = string.get_token().and_then(OriginAnchor::from_token);
+
+        if anchor.is_some_and(|anchor| {
+            self.context
+                .is_line_disabled(EXPAND_STRINGS_TO_CHAR_RULE_NAME, anchor.line_number)
+        }) {
+            return None;
+        }
+
+        if self.identifier_tracker.is_identifier_used(STRING_LIBRARY) {
+            log::debug!("unable to expand string because the `string` library is shadowed");
+            return None;
+        }
+
+        string
+            .get_value()
+            .chunks(MAX_CHAR_ARGUMENTS)
+            .map(|bytes| {
+                let mut library = Identifier::new(STRING_LIBRARY);
+                if let Some(anchor) = anchor {
+                    library.set_token(token_from_content_with_anchor(STRING_LIBRARY, anchor));
+                }
+                FunctionCall::from_prefix(FieldExpression::new(library, CHAR_FUNCTION))
+                    .with_arguments(
+                        bytes
+                            .iter()
+                            .copied()
+                            .map(Expression::from)
+                            .collect::<TupleArguments>(),
+                    )
+                    .into()
+            })
+            .rev()
+            .reduce(|right, left| BinaryExpression::new(BinaryOperator::Concat, left, right).into())
+    }
+}
+
+impl Deref for ExpandProcessor<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for ExpandProcessor<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for ExpandProcessor<'_> {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if let Expression::String(string) = expression {
+            if let Some(expanded) = self.expand(string) {
+                *expression = expanded;
+            }
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if let Arguments::String(string) = call.get_arguments() {
+            if let Some(expanded) = self.expand(string) {
+                call.set_arguments(TupleArguments::default().with_argument(expanded).into());
+            }
+        }
+    }
+}
+
+pub const EXPAND_STRINGS_TO_CHAR_RULE_NAME: &str = "expand_strings_to_char";
+
+/// A rule that replaces string literals with `string.char` calls, so that the generated
+/// code only contains ASCII characters.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExpandStringsToChar {}
+
+impl FlawlessRule for ExpandStringsToChar {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = ExpandProcessor::new(context);
+        ScopeVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for ExpandStringsToChar {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)
+    }
+
+    fn get_name(&self) -> &'static str {
+        EXPAND_STRINGS_TO_CHAR_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nodes::{DecimalNumber, LastStatement, ReturnStatement, Token};
+    use crate::rules::{ContextBuilder, Rule};
+    use crate::Resources;
+
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn serialize_default_fold_string_char_rule() {
+        let rule: Box<dyn Rule> = Box::<FoldStringChar>::default();
+
+        assert_json_snapshot!(rule, @r###""fold_string_char""###);
+    }
+
+    #[test]
+    fn serialize_default_expand_strings_to_char_rule() {
+        let rule: Box<dyn Rule> = Box::<ExpandStringsToChar>::default();
+
+        assert_json_snapshot!(rule, @r###""expand_strings_to_char""###);
+    }
+
+    #[test]
+    fn fold_preserves_origin_of_the_call() {
+        let call = FunctionCall::from_prefix(FieldExpression::new(
+            Identifier::new(STRING_LIBRARY).with_token(Token::from_content_with_origin(
+                STRING_LIBRARY,
+                4,
+                2,
+            )),
+            CHAR_FUNCTION,
+        ))
+        .with_argument(DecimalNumber::new(104.0))
+        .with_argument(DecimalNumber::new(105.0));
+        let mut block = Block::default().with_last_statement(ReturnStatement::one(call));
+
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new("test.lua", &resources, "").build();
+
+        FoldStringChar::default().flawless_process(&mut block, &context);
+
+        match block.get_last_statement() {
+            Some(LastStatement::Return(statement)) => match statement.iter_expressions().next() {
+                Some(Expression::String(string)) => {
+                    pretty_assertions::assert_eq!(string.get_value(), b"hi");
+                    let token = string.get_token().expect("token should be present");
+                    pretty_assertions::assert_eq!(token.get_line_number(), Some(4));
+                    pretty_assertions::assert_eq!(token.get_source_id(), Some(2));
+                }
+                _ => panic!("expected string expression"),
+            },
+            _ => panic!("expected return statement"),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::nodes::{Token, TriviaKind};
+
+pub(crate) const DIRECTIVE_PREFIX: &str = "darklua:";
+
+/// Returns the name of the directive written in a comment like `-- darklua: keep-require`.
+pub(crate) fn parse_comment_directive(comment: &str) -> Option<&str> {
+    let content = comment.strip_prefix("--")?;
+
+    if content.starts_with('[') {
+        return None;
+    }
+
+    content
+        .trim()
+        .strip_prefix(DIRECTIVE_PREFIX)
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+}
+
+/// Iterates over the directives found in the comments that trail the token. The token
+/// references must be replaced beforehand, because the comments are read from the trivia.
+pub(crate) fn iter_trailing_comment_directives(token: &Token) -> impl Iterator<Item = &str> {
+    token
+        .iter_trailing_trivia()
+        .filter(|trivia| trivia.kind() == TriviaKind::Comment)
+        .filter_map(|trivia| trivia.try_read())
+        .filter_map(parse_comment_directive)
+}
+
+/// Splits a directive into its name and the comma-separated list that follows it, like
+/// `disable compute_expression, remove_unused_variable`.
+pub(crate) fn split_directive(directive: &str) -> (&str, impl Iterator<Item = &str>) {
+    let (name, arguments) = directive
+        .split_once(char::is_whitespace)
+        .unwrap_or((directive, ""));
+
+    (
+        name,
+        arguments
+            .split(',')
+            .map(str::trim)
+            .filter(|argument| !argument.is_empty()),
+    )
+}
+
+/// Finds the directives written in the comments at the start of the code, before the first
+/// statement, along with the line where each comment is written.
+pub(crate) fn find_leading_comment_directives(code: &str) -> Vec<(usize, &str)> {
+    let mut directives = Vec::new();
+    let mut line = 1;
+    let mut rest = code;
+
+    if rest.starts_with("#!") {
+        rest = rest.find('\n').map(|index| &rest[index..]).unwrap_or("");
+    }
+
+    loop {
+        let trimmed = rest.trim_start();
+        line += rest[..rest.len() - trimmed.len()].matches('\n').count();
+        rest = trimmed;
+
+        if !rest.starts_with("--") {
+            break;
+        }
+
+        let comment_length = match long_comment_level(rest) {
+            Some(level) => {
+                let closing = format!("]{}]", "=".repeat(level));
+                rest.find(&closing)
+                    .map(|index| index + closing.len())
+                    .unwrap_or(rest.len())
+            }
+            None => rest.find('\n').unwrap_or(rest.len()),
+        };
+        let (comment, next) = rest.split_at(comment_length);
+
+        if let Some(directive) = parse_comment_directive(comment.trim_end()) {
+            directives.push((line, directive));
+        }
+
+        line += comment.matches('\n').count();
+        rest = next;
+    }
+
+    directives
+}
+
+// returns the number of `=` of a comment opening like `--[==[`
+fn long_comment_level(comment: &str) -> Option<usize> {
+    let opening = comment.strip_prefix("--[")?;
+    let level = opening.len() - opening.trim_start_matches('=').len();
+    opening[level..].starts_with('[').then_some(level)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_directive() {
+        assert_eq!(
+            parse_comment_directive("-- darklua: keep-require"),
+            Some("keep-require")
+        );
+    }
+
+    #[test]
+    fn parse_directive_without_spaces() {
+        assert_eq!(parse_comment_directive("--darklua:bundle"), Some("bundle"));
+    }
+
+    #[test]
+    fn parse_directive_with_trailing_spaces() {
+        assert_eq!(
+            parse_comment_directive("-- darklua: bundle  "),
+            Some("bundle")
+        );
+    }
+
+    #[test]
+    fn parse_comment_without_directive() {
+        assert_eq!(parse_comment_directive("-- keep-require"), None);
+    }
+
+    #[test]
+    fn parse_directive_without_name() {
+        assert_eq!(parse_comment_directive("-- darklua:"), None);
+    }
+
+    #[test]
+    fn parse_block_comment() {
+        assert_eq!(parse_comment_directive("--[[ darklua: bundle ]]"), None);
+    }
+
+    #[test]
+    fn iter_directives_from_trailing_comments() {
+        let token = Token::from_content(")")
+            .with_trailing_trivia(TriviaKind::Whitespace.with_content(" "))
+            .with_trailing_trivia(TriviaKind::Comment.with_content("-- darklua: keep-require"));
+
+        assert_eq!(
+            iter_trailing_comment_directives(&token).collect::<Vec<_>>(),
+            vec!["keep-require"]
+        );
+    }
+
+    #[test]
+    fn iter_directives_ignores_leading_comments() {
+        let token = Token::from_content(")")
+            .with_leading_trivia(TriviaKind::Comment.with_content("-- darklua: keep-require"));
+
+        assert_eq!(iter_trailing_comment_directives(&token).next(), None);
+    }
+
+    #[test]
+    fn split_directive_with_arguments() {
+        let (name, arguments) =
+            split_directive("disable remove_unused_variable, compute_expression");
+
+        assert_eq!(name, "disable");
+        assert_eq!(
+            arguments.collect::<Vec<_>>(),
+            vec!["remove_unused_variable", "compute_expression"]
+        );
+    }
+
+    #[test]
+    fn split_directive_without_arguments() {
+        let (name, arguments) = split_directive("keep-require");
+
+        assert_eq!(name, "keep-require");
+        assert_eq!(arguments.count(), 0);
+    }
+
+    #[test]
+    fn find_leading_directives() {
+        assert_eq!(
+            find_leading_comment_directives(
+                "--!strict\n-- darklua: disable compute_expression\nlocal a = 1\n"
+            ),
+            vec![(2, "disable compute_expression")]
+        );
+    }
+
+    #[test]
+    fn find_leading_directives_after_long_comment() {
+        assert_eq!(
+            find_leading_comment_directives(
+                "--[==[\n  license\n]==]\n\n-- darklua: disable compute_expression\nreturn 1\n"
+            ),
+            vec![(5, "disable compute_expression")]
+        );
+    }
+
+    #[test]
+    fn find_leading_directives_ignores_comments_after_first_statement() {
+        assert_eq!(
+            find_leading_comment_directives(
+                "local a = 1\n-- darklua: disable compute_expression\n"
+            ),
+            Vec::<(usize, &str)>::new()
+        );
+    }
+
+    #[test]
+    fn find_leading_directives_after_shebang() {
+        assert_eq!(
+            find_leading_comment_directives("#!/usr/bin/env lua\n-- darklua: disable a, b"),
+            vec![(2, "disable a, b")]
+        );
+    }
+}
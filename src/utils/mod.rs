@@ -1,3 +1,5 @@
+mod base64;
+mod comment_directives;
 mod expressions_as_statement;
 pub(crate) mod lines;
 mod luau_config;
@@ -8,6 +10,11 @@ mod timer;
 pub(crate) mod source_registry;
 pub mod origin;
 
+pub(crate) use base64::encode_base64;
+pub(crate) use comment_directives::{
+    find_leading_comment_directives, iter_trailing_comment_directives, parse_comment_directive,
+    split_directive, DIRECTIVE_PREFIX,
+};
 pub(crate) use expressions_as_statement::{expressions_as_expression, expressions_as_statement};
 pub(crate) use luau_config::{clear_luau_configuration_cache, find_luau_configuration};
 pub(crate) use preserve_arguments_side_effects::preserve_arguments_side_effects;
@@ -1,4 +1,4 @@
-use crate::nodes::{Expression, Token};
+use crate::nodes::{Expression, Prefix, Token};
 use std::borrow::Cow;
 
 /// Anchors represent an origin (line, source_id) to apply to synthesized tokens.
@@ -27,10 +27,22 @@ pub fn anchor_from_expression(expr: &Expression) -> Option<OriginAnchor> {
         E::Unary(u) => u.get_token().and_then(OriginAnchor::from_token),
         E::Binary(b) => b.get_token().and_then(OriginAnchor::from_token),
         E::If(i) => i.get_tokens().map(|t| &t.r#if).and_then(OriginAnchor::from_token),
+        E::Call(call) => anchor_from_prefix(call.get_prefix()),
         _ => None,
     }
 }
 
+/// Try to extract an origin anchor from the identifier at the root of a prefix.
+pub fn anchor_from_prefix(prefix: &Prefix) -> Option<OriginAnchor> {
+    match prefix {
+        Prefix::Identifier(id) => id.get_token().and_then(OriginAnchor::from_token),
+        Prefix::Field(field) => anchor_from_prefix(field.get_prefix()),
+        Prefix::Index(index) => anchor_from_prefix(index.get_prefix()),
+        Prefix::Call(call) => anchor_from_prefix(call.get_prefix()),
+        Prefix::Parenthese(_) => None,
+    }
+}
+
 /// Apply origin to a token content, preserving source_id and line.
 pub fn token_from_content_with_anchor(
     content: impl Into<Cow<'static, str>>,
@@ -30,6 +30,19 @@ impl SourceRegistry {
         id
     }
 
+    /// Returns the id for a path known by its canonical path, so that a file reachable
+    /// through different paths (like a symlink and its target) gets a single id. The first
+    /// path interned for a canonical path is the one kept for reverse lookups.
+    pub fn intern_canonical(&mut self, path: impl AsRef<Path>, canonical: impl AsRef<Path>) -> u32 {
+        let canonical = canonical.as_ref();
+        if let Some(id) = self.path_to_id.get(canonical) {
+            return *id;
+        }
+        let id = self.intern(path);
+        self.path_to_id.insert(canonical.to_path_buf(), id);
+        id
+    }
+
     pub fn get_path(&self, id: u32) -> Option<&Path> {
         self.id_to_path
             .get(id as usize)
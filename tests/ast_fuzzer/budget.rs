@@ -2,6 +2,7 @@ pub struct FuzzBudget {
     statements: usize,
     expressions: usize,
     types: usize,
+    requires: usize,
 }
 
 impl FuzzBudget {
@@ -10,6 +11,7 @@ impl FuzzBudget {
             statements,
             expressions,
             types: 0,
+            requires: 0,
         }
     }
 
@@ -18,6 +20,12 @@ impl FuzzBudget {
         self
     }
 
+    #[allow(dead_code)]
+    pub fn with_requires(mut self, requires_budget: usize) -> Self {
+        self.requires = requires_budget;
+        self
+    }
+
     pub fn remaining_expressions(&self) -> usize {
         self.expressions
     }
@@ -67,6 +75,15 @@ impl FuzzBudget {
         took
     }
 
+    pub fn take_require(&mut self) -> bool {
+        if self.requires == 0 {
+            false
+        } else {
+            self.requires -= 1;
+            true
+        }
+    }
+
     #[inline]
     pub fn can_have_expression(&self, amount: usize) -> bool {
         self.expressions >= amount
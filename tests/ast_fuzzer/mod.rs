@@ -29,6 +29,7 @@ pub struct AstFuzzer {
     function_return_types: Vec<FunctionReturnType>,
     type_packs: Vec<TypePack>,
     type_parameters: Vec<TypeParameter>,
+    required_modules: Vec<String>,
     work_stack: Vec<AstFuzzerWork>,
 }
 
@@ -51,10 +52,19 @@ impl AstFuzzer {
             function_return_types: Vec::new(),
             type_packs: Vec::new(),
             type_parameters: Vec::new(),
+            required_modules: Vec::new(),
             random: RandomAst::default(),
         }
     }
 
+    /// Generates calls like `require('./name')` to the given modules, within the requires
+    /// budget.
+    #[allow(dead_code)]
+    pub fn with_required_modules(mut self, modules: Vec<String>) -> Self {
+        self.required_modules = modules;
+        self
+    }
+
     pub fn fuzz_block(mut self) -> Block {
         self.work_stack.push(AstFuzzerWork::FuzzBlock);
 
@@ -101,6 +111,10 @@ impl AstFuzzer {
 
                     self.push_repeated_work(AstFuzzerWork::FuzzStatement, statement_count);
                 }
+                AstFuzzerWork::FuzzStatement if self.fuzz_require_call() => {
+                    let call = self.pop_call();
+                    self.statements.push(call.into());
+                }
                 AstFuzzerWork::FuzzStatement => {
                     match self
                         .random
@@ -412,6 +426,10 @@ impl AstFuzzer {
                         self.fuzz_multiple_expression(expressions);
                     }
                 },
+                AstFuzzerWork::FuzzExpression { .. } if self.fuzz_require_call() => {
+                    let call = self.pop_call();
+                    self.expressions.push(call.into());
+                }
                 AstFuzzerWork::FuzzExpression { depth } => {
                     let start = if !self.random.nested_expression(depth) {
                         6
@@ -554,6 +572,10 @@ impl AstFuzzer {
                         }
                     }
                 }
+                AstFuzzerWork::FuzzPrefix if self.fuzz_require_call() => {
+                    let call = self.pop_call();
+                    self.prefixes.push(call.into());
+                }
                 AstFuzzerWork::FuzzPrefix => {
                     let bound = if self.budget.can_have_expression(2) {
                         4
@@ -1610,6 +1632,23 @@ impl AstFuzzer {
         }
     }
 
+    // pushes a require call to one of the required modules when the budget allows it
+    fn fuzz_require_call(&mut self) -> bool {
+        if self.required_modules.is_empty()
+            || !self.random.require_call()
+            || !self.budget.take_require()
+        {
+            return false;
+        }
+
+        let module = self.random.required_module(&self.required_modules);
+        self.calls.push(
+            FunctionCall::from_name("require")
+                .with_argument(StringExpression::from_value(format!("./{}", module))),
+        );
+        true
+    }
+
     fn pop_block(&mut self) -> Block {
         self.blocks.pop().expect("expected block")
     }
@@ -1769,6 +1808,37 @@ impl AstFuzzer {
     }
 }
 
+/// Generates a main block and the given number of modules (named `mod1`, `mod2`, ...) that
+/// end with a single return statement. The blocks contain require calls to the modules, but
+/// a module only requires the modules that come after it, so the requires never form a cycle.
+#[allow(dead_code)]
+pub fn fuzz_modules(
+    module_count: usize,
+    create_budget: impl Fn() -> FuzzBudget,
+) -> (Block, Vec<(String, Block)>) {
+    let names: Vec<String> = (1..=module_count)
+        .map(|index| format!("mod{}", index))
+        .collect();
+
+    let modules = names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let mut block = AstFuzzer::new(create_budget())
+                .with_required_modules(names[index + 1..].to_vec())
+                .fuzz_block();
+            block.set_last_statement(ReturnStatement::one(Expression::nil()));
+            (name.clone(), block)
+        })
+        .collect();
+
+    let main = AstFuzzer::new(create_budget())
+        .with_required_modules(names)
+        .fuzz_block();
+
+    (main, modules)
+}
+
 fn wrap_in_parenthese_if_leading_union_or_intersection(r#type: Type) -> Type {
     match r#type {
         Type::Intersection(intersection_type) => {
@@ -28,6 +28,7 @@ pub struct RandomAst {
     intersection_type_length_std_dev: f64,
     union_type_length_mean: f64,
     union_type_length_std_dev: f64,
+    require_call_prob: f64,
 }
 
 impl Default for RandomAst {
@@ -57,6 +58,7 @@ impl Default for RandomAst {
             intersection_type_length_std_dev: 0.5,
             union_type_length_mean: 2.0,
             union_type_length_std_dev: 0.5,
+            require_call_prob: 0.2,
         }
     }
 }
@@ -318,6 +320,14 @@ impl RandomAst {
     pub fn leading_intersection_or_union_operator(&self) -> bool {
         thread_rng().gen_bool(0.4)
     }
+
+    pub fn require_call(&self) -> bool {
+        thread_rng().gen_bool(self.require_call_prob)
+    }
+
+    pub fn required_module<'a>(&self, modules: &'a [String]) -> &'a str {
+        &modules[thread_rng().gen_range(0..modules.len())]
+    }
 }
 
 #[inline]
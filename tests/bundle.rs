@@ -1,4 +1,7 @@
-use darklua_core::{process, Options, Resources};
+use darklua_core::{
+    process, rules::bundle::BundleRequireMode, BundleConfiguration, Configuration,
+    GeneratorParameters, Options, Resources, WorkerTree,
+};
 
 mod ast_fuzzer;
 mod utils;
@@ -42,6 +45,71 @@ fn expect_file_process(resources: &Resources, file_name: &str, expect_content: &
     pretty_assertions::assert_eq!(process_file(resources, file_name), expect_content);
 }
 
+/// A Rojo sourcemap where `src/init.lua` is a module script with a `value` child module
+/// (`src/value.lua`).
+const VALUE_MODULE_SOURCEMAP: &str = r#"{
+    "name": "Project",
+    "className": "ModuleScript",
+    "filePaths": ["src/init.lua", "default.project.json"],
+    "children": [
+        {
+            "name": "value",
+            "className": "ModuleScript",
+            "filePaths": ["src/value.lua"]
+        }
+    ]
+}"#;
+
+/// Bundles `entry` into `out.lua` with a configuration without rules. When a Rojo sourcemap
+/// is given, it is written to `default.project.json` and requires are bundled with the
+/// Roblox require mode, otherwise with the path require mode. Each bundle option is a field
+/// name with its JSON value.
+fn process_bundle(
+    sourcemap: Option<&str>,
+    generator: &str,
+    bundle_options: &[(&str, &str)],
+    files: &[(&str, &str)],
+    entry: &str,
+) -> (Resources, WorkerTree) {
+    let resources = Resources::from_memory();
+    for (path, content) in files {
+        resources.write(path, content).unwrap();
+    }
+
+    let require_mode = match sourcemap {
+        Some(sourcemap) => {
+            resources.write("default.project.json", sourcemap).unwrap();
+            "{ \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }"
+        }
+        None => "\"path\"",
+    };
+    let bundle_options: String = bundle_options
+        .iter()
+        .map(|(name, value)| format!(", \"{}\": {}", name, value))
+        .collect();
+    resources
+        .write(
+            ".darklua.json",
+            &format!(
+                "{{ \"rules\": [], \"generator\": \"{}\", \"bundle\": {{ \"require_mode\": {}{} }} }}",
+                generator, require_mode, bundle_options
+            ),
+        )
+        .unwrap();
+
+    let worker_tree = process(&resources, Options::new(entry).with_output("out.lua")).unwrap();
+
+    (resources, worker_tree)
+}
+
+fn warning_messages(worker_tree: &WorkerTree) -> Vec<String> {
+    worker_tree
+        .collect_warnings()
+        .into_iter()
+        .flat_map(|(_, warnings)| warnings.iter().map(ToString::to_string))
+        .collect()
+}
+
 #[test]
 fn skip_require_call_without_a_string() {
     let resources = memory_resources!(
@@ -75,15 +143,107 @@ fn skip_require_call_with_2_arguments() {
     );
 }
 
+#[test]
+fn skip_require_call_with_a_variable_emits_warning() {
+    let resources = memory_resources!(
+        "src/main.lua" => "local name = 'library'\nlocal library = require(name)",
+        ".darklua.json" => DARKLUA_BUNDLE_ONLY_RETAIN_LINES_CONFIG,
+    );
+
+    let worker_tree = process(
+        &resources,
+        Options::new("src/main.lua").with_output("out.lua"),
+    )
+    .unwrap();
+
+    let warnings = worker_tree.collect_warnings();
+
+    pretty_assertions::assert_eq!(warnings.len(), 1);
+
+    let (source, file_warnings) = warnings[0];
+    pretty_assertions::assert_eq!(source, std::path::Path::new("src/main.lua"));
+    pretty_assertions::assert_eq!(file_warnings.len(), 1);
+
+    let warning = &file_warnings[0];
+    pretty_assertions::assert_eq!(
+        warning.message(),
+        "unable to bundle `require(name)` because its argument is not a string literal"
+    );
+    pretty_assertions::assert_eq!(warning.path(), Some(std::path::Path::new("src/main.lua")));
+    pretty_assertions::assert_eq!(warning.line(), Some(2));
+
+    worker_tree.result().unwrap();
+}
+
+#[test]
+fn bundle_without_skipped_require_has_no_warnings() {
+    let resources = memory_resources!(
+        "src/main.lua" => "local library = require('./library')",
+        "src/library.lua" => "return true",
+        ".darklua.json" => DARKLUA_BUNDLE_ONLY_RETAIN_LINES_CONFIG,
+    );
+
+    let worker_tree = process(
+        &resources,
+        Options::new("src/main.lua").with_output("out.lua"),
+    )
+    .unwrap();
+
+    assert!(worker_tree.collect_warnings().is_empty());
+}
+
+#[test]
+fn skip_require_call_with_a_variable_succeeds_without_warnings_as_errors() {
+    let resources = memory_resources!(
+        "src/main.lua" => "local name = 'library'\nlocal library = require(name)",
+        ".darklua.json" => "{ \"rules\": [], \"bundle\": { \"require_mode\": \"path\" }, \"warnings_as_errors\": [\"excluded_fallback_script_root\"] }",
+    );
+
+    process(
+        &resources,
+        Options::new("src/main.lua").with_output("out.lua"),
+    )
+    .unwrap()
+    .result()
+    .unwrap();
+}
+
+#[test]
+fn skip_require_call_with_a_variable_errors_with_unresolved_require_as_error() {
+    let resources = memory_resources!(
+        "src/main.lua" => "local name = 'library'\nlocal library = require(name)",
+        ".darklua.json" => "{ \"rules\": [], \"bundle\": { \"require_mode\": \"path\" }, \"warnings_as_errors\": [\"unresolved_require\"] }",
+    );
+
+    let worker_tree = process(
+        &resources,
+        Options::new("src/main.lua").with_output("out.lua"),
+    )
+    .unwrap();
+
+    assert!(worker_tree.collect_warnings().is_empty());
+
+    let errors = worker_tree.result().unwrap_err();
+
+    pretty_assertions::assert_eq!(
+        errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec![concat!(
+            "`src/main.lua` emitted warnings configured as errors:\n",
+            "    - [unresolved_require] unable to bundle `require(name)` because its argument ",
+            "is not a string literal (`src/main.lua` at line 2)"
+        )]
+    );
+}
+
 mod without_rules {
     use std::time::Duration;
 
     use darklua_core::{
         generator::{LuaGenerator, ReadableLuaGenerator},
-        nodes::{Expression, ReturnStatement},
+        nodes::{Block, Expression, ReturnStatement},
     };
 
-    use crate::ast_fuzzer::{AstFuzzer, FuzzBudget};
+    use crate::ast_fuzzer::{fuzz_modules, AstFuzzer, FuzzBudget};
 
     use super::*;
 
@@ -235,6 +395,24 @@ mod without_rules {
             ));
         }
 
+        #[test]
+        fn require_source_pointing_to_file() {
+            process_main_require_value(memory_resources!(
+                "vendor/value.lua" => "return true",
+                "src/main.lua" => "local value = require('Value')",
+                ".darklua.json" => "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"path\", \"sources\": { \"Value\": \"./vendor/value.lua\" } } } }",
+            ));
+        }
+
+        #[test]
+        fn require_source_pointing_to_file_without_extension() {
+            process_main_require_value(memory_resources!(
+                "vendor/value.luau" => "return true",
+                "src/main.lua" => "local value = require('Value')",
+                ".darklua.json" => "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"path\", \"sources\": { \"Value\": \"./vendor/value\" } } } }",
+            ));
+        }
+
         #[test]
         fn require_directory_with_custom_init_file() {
             process_main_require_value(memory_resources!(
@@ -245,6 +423,17 @@ mod without_rules {
         }
     }
 
+    #[test]
+    fn require_path_in_source_pointing_to_file_errors() {
+        let resources = memory_resources!(
+            "vendor/value.lua" => "return true",
+            "src/main.lua" => "local value = require('Value/other')",
+            ".darklua.json" => "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"path\", \"sources\": { \"Value\": \"./vendor/value.lua\" } } } }",
+        );
+
+        process_main_with_errors(&resources, "require_path_in_source_pointing_to_file_errors");
+    }
+
     #[test]
     fn require_lua_file_forward_exported_types() {
         process_main(
@@ -326,6 +515,28 @@ mod without_rules {
         process_main(&resources, "require_lua_file_twice_with_different_paths");
     }
 
+    #[test]
+    fn require_lua_file_twice_through_symlink() {
+        use std::path::Path;
+
+        let resources = memory_resources!(
+            "src/store/constant.lua" => "print('load constant module') return 2",
+            "src/Packages/constant.lua" => "print('load constant module') return 2",
+            "src/main.lua" => concat!(
+                "local value_a = require('./Packages/constant.lua')\n",
+                "local value_b = require('./store/constant.lua')\n",
+                "print(value_a + value_b)"
+            ),
+            ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+        )
+        .with_canonicalizer(|path: &Path| match path.strip_prefix("src/Packages") {
+            Ok(relative_path) => Path::new("src/store").join(relative_path),
+            Err(_) => path.to_path_buf(),
+        });
+
+        process_main(&resources, "require_lua_file_twice_through_symlink");
+    }
+
     #[test]
     fn require_lua_file_with_field_expression() {
         let resources = memory_resources!(
@@ -381,6 +592,107 @@ mod without_rules {
         process_main(&resources, "require_json_file_with_object");
     }
 
+    const MIXED_NESTING_JSON: &str = r#"{
+        "items": [
+            [1, { "name": "first", "tags": null }],
+            [null, { "1abc": true, "foo-bar": [null, 2] }]
+        ],
+        "missing": null
+    }"#;
+
+    #[test]
+    fn require_json_file_with_null_sentinel() {
+        let resources = memory_resources!(
+            "src/value.json" => MIXED_NESTING_JSON,
+            "src/main.lua" => "local value = require('./value.json')",
+            ".darklua.json" => "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": \"path\", \"null_sentinel\": \"NULL_SENTINEL\" } }",
+        );
+
+        process_main(&resources, "require_json_file_with_null_sentinel");
+
+        let main = resources.get("out.lua").unwrap();
+        darklua_core::Parser::default()
+            .parse(&main)
+            .expect("bundled code should parse");
+    }
+
+    const LOCALE_JSON: &str = r#"{
+        "greeting": { "title": "Welcome to the game", "button": "Press to continue" },
+        "shop": { "title": "Welcome to the game", "button": "Press to continue" },
+        "settings": { "title": "Welcome to the game", "button": "Press to continue", "back": "Back" },
+        "menu": ["Back", "Back", "Back"]
+    }"#;
+
+    const INTERN_DATA_STRINGS_CONFIG: &str = "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": \"path\", \"intern_data_strings\": {} } }";
+
+    #[test]
+    fn require_json_file_with_interned_strings() {
+        let resources = memory_resources!(
+            "src/value.json" => LOCALE_JSON,
+            "src/main.lua" => "local value = require('./value.json')",
+            ".darklua.json" => INTERN_DATA_STRINGS_CONFIG,
+        );
+
+        process_main(&resources, "require_json_file_with_interned_strings");
+    }
+
+    #[test]
+    fn require_json_file_with_interned_strings_is_smaller() {
+        let bundle = |config: &'static str| {
+            let resources = memory_resources!(
+                "src/value.json" => LOCALE_JSON,
+                "src/main.lua" => "local value = require('./value.json')",
+                ".darklua.json" => config,
+            );
+            process(
+                &resources,
+                Options::new("src/main.lua").with_output("out.lua"),
+            )
+            .unwrap()
+            .result()
+            .unwrap();
+            resources.get("out.lua").unwrap()
+        };
+
+        let interned = bundle(INTERN_DATA_STRINGS_CONFIG);
+        let original = bundle(DARKLUA_BUNDLE_ONLY_READABLE_CONFIG);
+
+        assert!(
+            interned.len() < original.len(),
+            "interned bundle ({} bytes) should be smaller than the original ({} bytes)",
+            interned.len(),
+            original.len()
+        );
+        pretty_assertions::assert_eq!(interned.matches("Welcome to the game").count(), 1);
+        pretty_assertions::assert_eq!(interned.matches("Press to continue").count(), 1);
+        pretty_assertions::assert_eq!(interned.matches("'Back'").count(), 4);
+    }
+
+    #[test]
+    fn require_json_file_with_null_in_array_errors() {
+        let resources = memory_resources!(
+            "src/value.json" => MIXED_NESTING_JSON,
+            "src/main.lua" => "local value = require('./value.json')",
+            ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+        );
+
+        process_main_with_errors(&resources, "require_json_file_with_null_in_array_errors");
+    }
+
+    #[test]
+    fn require_json_file_with_invalid_null_sentinel_errors() {
+        let resources = memory_resources!(
+            "src/value.json" => MIXED_NESTING_JSON,
+            "src/main.lua" => "local value = require('./value.json')",
+            ".darklua.json" => "{ \"rules\": [], \"bundle\": { \"require_mode\": \"path\", \"null_sentinel\": \"not-valid\" } }",
+        );
+
+        process_main_with_errors(
+            &resources,
+            "require_json_file_with_invalid_null_sentinel_errors",
+        );
+    }
+
     #[test]
     fn require_toml_with_object() {
         let resources = memory_resources!(
@@ -424,6 +736,183 @@ data:
         process_main(&resources, "require_yml_with_object");
     }
 
+    #[test]
+    fn require_json_file_preserves_key_order() {
+        let resources = memory_resources!(
+            "src/value.json" => r#"{ "zeta": 1, "alpha": { "mid": true, "beta": false }, "kappa": [3, 2] }"#,
+            "src/main.lua" => "local value = require('./value.json')",
+            ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+        );
+
+        process_main(&resources, "require_json_file_preserves_key_order");
+    }
+
+    #[test]
+    fn require_toml_file_preserves_key_order() {
+        let resources = memory_resources!(
+            "src/value.toml" => "zeta = 1\nkappa = [3, 2]\n\n[alpha]\nmid = true\nbeta = false\n",
+            "src/main.lua" => "local value = require('./value.toml')",
+            ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+        );
+
+        process_main(&resources, "require_toml_file_preserves_key_order");
+    }
+
+    #[test]
+    fn require_yaml_file_preserves_key_order() {
+        let resources = memory_resources!(
+            "src/value.yaml" => r#"
+zeta: 1
+alpha:
+    mid: true
+    beta: false
+kappa:
+- 3
+- 2
+            "#,
+            "src/main.lua" => "local value = require('./value.yaml')",
+            ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+        );
+
+        process_main(&resources, "require_yaml_file_preserves_key_order");
+    }
+
+    #[test]
+    fn require_md_file_with_raw_text_extensions() {
+        let resources = memory_resources!(
+            "src/CHANGELOG.md" => "# Changelog\n\n* initial release\n",
+            "src/main.lua" => "local value = require('./CHANGELOG.md')",
+            ".darklua.json" => "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": \"path\", \"raw_text_extensions\": [\"md\"] } }",
+        );
+
+        process_main(&resources, "require_md_file_with_raw_text_extensions");
+    }
+
+    #[test]
+    fn require_md_file_without_raw_text_extensions_errors() {
+        let resources = memory_resources!(
+            "src/CHANGELOG.md" => "# Changelog\n",
+            "src/main.lua" => "local value = require('./CHANGELOG.md')",
+            ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+        );
+
+        process_main_with_errors(
+            &resources,
+            "require_md_file_without_raw_text_extensions_errors",
+        );
+    }
+
+    #[test]
+    fn require_raw_text_file_larger_than_max_size_errors() {
+        let resources = memory_resources!(
+            "src/CHANGELOG.md" => "# Changelog\n\n* initial release\n",
+            "src/main.lua" => "local value = require('./CHANGELOG.md')",
+            ".darklua.json" => "{ \"rules\": [], \"bundle\": { \"require_mode\": \"path\", \"raw_text_extensions\": [\"md\"], \"max_raw_text_size\": 16 } }",
+        );
+
+        process_main_with_errors(
+            &resources,
+            "require_raw_text_file_larger_than_max_size_errors",
+        );
+    }
+
+    const BINARY_CONTENT: &[u8] = &[0x89, b'P', b'N', b'G', 0, 0xff, 0xfe, 0x10, 0x7f, 0x80];
+
+    fn decode_base64(encoded: &str) -> Vec<u8> {
+        const ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        let values: Vec<u32> = encoded
+            .trim_end_matches('=')
+            .chars()
+            .map(|c| ALPHABET.find(c).expect("invalid base64 character") as u32)
+            .collect();
+
+        values
+            .chunks(4)
+            .flat_map(|chunk| {
+                let group = chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |group, (i, value)| group | (value << (18 - 6 * i)));
+                (0..chunk.len() - 1).map(move |i| (group >> (16 - 8 * i)) as u8)
+            })
+            .collect()
+    }
+
+    fn binary_bundle_config(extra: &str) -> String {
+        format!(
+            "{{ \"rules\": [], \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": \"path\", \"binary_extensions\": [\"png\"]{} }} }}",
+            extra
+        )
+    }
+
+    #[test]
+    fn require_binary_file_as_base64_string() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local value = require('./image.png')",
+            ".darklua.json" => binary_bundle_config(""),
+        );
+        resources
+            .write_bytes("src/image.png", BINARY_CONTENT)
+            .unwrap();
+
+        process_main(&resources, "require_binary_file_as_base64_string");
+
+        let main = resources.get("out.lua").unwrap();
+        let encoded = main
+            .split("return '")
+            .nth(1)
+            .and_then(|rest| rest.split('\'').next())
+            .expect("bundle should return the encoded string");
+
+        pretty_assertions::assert_eq!(decode_base64(encoded), BINARY_CONTENT);
+    }
+
+    #[test]
+    fn require_binary_file_with_wrapper() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local value = require('./image.png')",
+            ".darklua.json" => binary_bundle_config(", \"binary_wrapper\": \"Base64.decode\""),
+        );
+        resources
+            .write_bytes("src/image.png", BINARY_CONTENT)
+            .unwrap();
+
+        process_main(&resources, "require_binary_file_with_wrapper");
+    }
+
+    #[test]
+    fn require_binary_file_larger_than_max_size_errors() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local value = require('./image.png')",
+            ".darklua.json" => binary_bundle_config(", \"max_binary_size\": 4"),
+        );
+        resources
+            .write_bytes("src/image.png", BINARY_CONTENT)
+            .unwrap();
+
+        process_main_with_errors(
+            &resources,
+            "require_binary_file_larger_than_max_size_errors",
+        );
+    }
+
+    #[test]
+    fn require_binary_file_with_invalid_wrapper_errors() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local value = require('./image.png')",
+            ".darklua.json" => binary_bundle_config(", \"binary_wrapper\": \"Base64:decode\""),
+        );
+        resources
+            .write_bytes("src/image.png", BINARY_CONTENT)
+            .unwrap();
+
+        process_main_with_errors(
+            &resources,
+            "require_binary_file_with_invalid_wrapper_errors",
+        );
+    }
+
     #[test]
     fn require_txt_file() {
         let resources = memory_resources!(
@@ -435,6 +924,17 @@ data:
         process_main(&resources, "require_txt_file");
     }
 
+    #[test]
+    fn require_txt_file_as_statement() {
+        let resources = memory_resources!(
+            "src/value.txt" => "Hello from txt file!\n",
+            "src/main.lua" => "require('./value.txt')\nprint('start')\ndo\n    require('./value.txt')\nend\n",
+            ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+        );
+
+        process_main(&resources, "require_txt_file_as_statement");
+    }
+
     #[test]
     fn require_value_and_override_require_function() {
         let resources = memory_resources!(
@@ -557,7 +1057,8 @@ data:
             let resources = memory_resources!(
                 "src/value.lua" => &block_file,
                 "src/main.lua" => "local value = require('./value')",
-                ".darklua.json" => DARKLUA_BUNDLE_ONLY_RETAIN_LINES_CONFIG,
+                // the fuzzer can generate `...` outside of functions
+                ".darklua.json" => "{ \"rules\": [], \"generator\": \"retain_lines\", \"bundle\": { \"require_mode\": \"path\", \"module_varargs\": \"nil\" } }",
             );
             let resource_ref = &resources;
 
@@ -582,20 +1083,72 @@ data:
         })
     }
 
-    mod cyclic_requires {
-        use super::*;
+    #[test]
+    fn fuzz_bundle_with_requires() {
+        utils::run_for_minimum_time(Duration::from_millis(250), || {
+            let (main, modules) = fuzz_modules(3, || {
+                FuzzBudget::new(15, 30).with_types(15).with_requires(4)
+            });
 
-        fn process_main_with_error(resources: &Resources, snapshot_name: &str) {
-            process_main_with_errors(resources, &format!("cyclic_requires__{}", snapshot_name));
-        }
+            let write_block = |block: &Block| {
+                let mut generator = ReadableLuaGenerator::new(80);
+                generator.write_block(block);
+                generator.into_string()
+            };
+
+            let main_file = write_block(&main);
 
-        #[test]
-        fn simple_direct_cycle() {
             let resources = memory_resources!(
-                "src/value1.lua" => "return require('./value2')",
-                "src/value2.lua" => "return require('./value1')",
-                "src/main.lua" => "local value = require('./value1.lua')",
-                ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+                "src/main.lua" => &main_file,
+                // the fuzzer can generate `...` outside of functions
+                ".darklua.json" => "{ \"rules\": [], \"generator\": \"retain_lines\", \"bundle\": { \"require_mode\": \"path\", \"module_varargs\": \"nil\" } }",
+            );
+            let module_files: Vec<_> = modules
+                .iter()
+                .map(|(name, block)| (format!("src/{}.lua", name), write_block(block)))
+                .collect();
+            for (path, content) in module_files.iter() {
+                resources.write(path, content).unwrap();
+            }
+            let resource_ref = &resources;
+
+            let result = std::panic::catch_unwind(|| {
+                process(
+                    resource_ref,
+                    Options::new("src/main.lua").with_output("out.lua"),
+                )
+                .unwrap()
+                .result()
+                .unwrap();
+            });
+
+            result
+                .inspect_err(|_err| {
+                    std::fs::write("fuzz_bundle_failure.repro.lua", &main_file).unwrap();
+                    for (path, content) in module_files.iter() {
+                        let file_name = path.trim_start_matches("src/");
+                        std::fs::write(format!("fuzz_bundle_failure.{}", file_name), content)
+                            .unwrap();
+                    }
+                })
+                .unwrap();
+        })
+    }
+
+    mod cyclic_requires {
+        use super::*;
+
+        fn process_main_with_error(resources: &Resources, snapshot_name: &str) {
+            process_main_with_errors(resources, &format!("cyclic_requires__{}", snapshot_name));
+        }
+
+        #[test]
+        fn simple_direct_cycle() {
+            let resources = memory_resources!(
+                "src/value1.lua" => "return require('./value2')",
+                "src/value2.lua" => "return require('./value1')",
+                "src/main.lua" => "local value = require('./value1.lua')",
+                ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
             );
 
             process_main_with_error(&resources, "simple_direct_cycle");
@@ -658,7 +1211,9 @@ data:
 
 mod sourcemap_emit {
     use super::*;
+    use darklua_core::WorkerTree;
     use sourcemap::SourceMap;
+    use std::path::PathBuf;
 
     #[test]
     fn retain_lines_path_mode_writes_sourcemap_json() {
@@ -794,6 +1349,171 @@ mod sourcemap_emit {
         assert!(sources_str.contains(&"value.lua"), "sources should include value.lua, got: {:?}", sources_str);
         assert_eq!(sources_str.len(), 2, "unexpected extra sources: {:?}", sources_str);
     }
+
+    fn process_main_with_sourcemap(
+        resources: &Resources,
+    ) -> (Vec<(String, usize)>, Vec<String>) {
+        let worker_tree = process(
+            resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap();
+
+        let mappings = worker_tree
+            .collect_sourcemap_mappings()
+            .into_iter()
+            .flat_map(|(_, mappings)| mappings.iter().cloned())
+            .collect();
+        let warnings = worker_tree
+            .collect_warnings()
+            .into_iter()
+            .flat_map(|(_, warnings)| warnings.iter().map(ToString::to_string))
+            .collect();
+
+        worker_tree.result().unwrap();
+
+        (mappings, warnings)
+    }
+
+    #[test]
+    fn counts_mappings_of_each_bundled_source() {
+        let resources = memory_resources!(
+            "src/value.lua" => "return true\n",
+            "src/main.lua" => "local value = require('./value.lua')\n",
+            ".darklua.json" => DARKLUA_BUNDLE_RETAIN_LINES_WITH_SOURCEMAP,
+        );
+
+        let (mappings, warnings) = process_main_with_sourcemap(&resources);
+
+        pretty_assertions::assert_eq!(mappings.len(), 2, "{:?}", mappings);
+        assert!(
+            mappings.iter().all(|(_, count)| *count > 0),
+            "every source should have mappings: {:?}",
+            mappings
+        );
+        pretty_assertions::assert_eq!(warnings, Vec::<String>::new());
+    }
+
+    #[test]
+    fn data_source_without_mapping_does_not_warn() {
+        let resources = memory_resources!(
+            "src/value.json" => "{ \"value\": true }",
+            "src/main.lua" => "local value = require('./value.json')\n",
+            ".darklua.json" => DARKLUA_BUNDLE_RETAIN_LINES_WITH_SOURCEMAP,
+        );
+
+        let (_, warnings) = process_main_with_sourcemap(&resources);
+
+        pretty_assertions::assert_eq!(warnings, Vec::<String>::new());
+    }
+    fn generated_line_containing(resources: &Resources, pattern: &str) -> usize {
+        resources
+            .get("out.lua")
+            .unwrap()
+            .lines()
+            .position(|line| line.contains(pattern))
+            .unwrap_or_else(|| panic!("out.lua should contain `{}`", pattern))
+            + 1
+    }
+
+    fn process_two_modules_bundle() -> (Resources, WorkerTree) {
+        let resources = memory_resources!(
+            "src/value.lua" => "\n\nlocal value = true\nreturn value\n",
+            "src/main.lua" => "local value = require('./value.lua')\nprint(value)\n",
+            ".darklua.json" => DARKLUA_BUNDLE_RETAIN_LINES_WITH_SOURCEMAP,
+        );
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap();
+
+        (resources, worker_tree)
+    }
+
+    #[test]
+    fn maps_generated_lines_to_original_lines() {
+        let (resources, worker_tree) = process_two_modules_bundle();
+
+        let print_line = generated_line_containing(&resources, "print(value)");
+        let return_line = generated_line_containing(&resources, "return value");
+
+        pretty_assertions::assert_eq!(
+            worker_tree.map_generated_to_original("out.lua", print_line),
+            Some((PathBuf::from("src/main.lua"), 2))
+        );
+        pretty_assertions::assert_eq!(
+            worker_tree.map_generated_to_original("out.lua", return_line),
+            Some((PathBuf::from("src/value.lua"), 4))
+        );
+    }
+
+    #[test]
+    fn maps_original_lines_to_generated_lines() {
+        let (resources, worker_tree) = process_two_modules_bundle();
+
+        let print_line = generated_line_containing(&resources, "print(value)");
+        let return_line = generated_line_containing(&resources, "return value");
+
+        pretty_assertions::assert_eq!(
+            worker_tree.map_original_to_generated("src/main.lua", 2),
+            vec![print_line]
+        );
+        pretty_assertions::assert_eq!(
+            worker_tree.map_original_to_generated("src/value.lua", 4),
+            vec![return_line]
+        );
+        pretty_assertions::assert_eq!(
+            worker_tree.map_original_to_generated("src/value.lua", 1),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn module_definitions_preamble_line_maps_to_none() {
+        let (resources, worker_tree) = process_two_modules_bundle();
+
+        let preamble_line = generated_line_containing(&resources, "local __DARKLUA_BUNDLE_MODULES");
+
+        pretty_assertions::assert_eq!(
+            worker_tree.map_generated_to_original("out.lua", preamble_line),
+            None
+        );
+    }
+
+    #[test]
+    fn unknown_output_maps_to_none() {
+        let (_, worker_tree) = process_two_modules_bundle();
+
+        pretty_assertions::assert_eq!(worker_tree.map_generated_to_original("other.lua", 1), None);
+    }
+
+    #[test]
+    fn data_module_line_maps_to_data_file() {
+        let resources = memory_resources!(
+            "src/data.json" => "{ \"value\": true }",
+            "src/main.lua" => "\n\nlocal data = require('./data.json')\nprint(data)\n",
+            ".darklua.json" => DARKLUA_BUNDLE_RETAIN_LINES_WITH_SOURCEMAP,
+        );
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap();
+
+        let data_line = generated_line_containing(&resources, "{value=true}");
+
+        pretty_assertions::assert_eq!(
+            worker_tree.map_generated_to_original("out.lua", data_line),
+            Some((PathBuf::from("src/data.json"), 1))
+        );
+        pretty_assertions::assert_eq!(
+            worker_tree.map_original_to_generated("src/data.json", 1),
+            vec![data_line]
+        );
+    }
 }
 
 #[test]
@@ -849,178 +1569,4524 @@ fn bundle_roblox_require() {
 }
 
 #[test]
-fn bundle_roblox_require_respects_instance_indexing_is_pure() {
-    const ROBLOX_BUNDLE_CONFIG: &str =
-        "{ \"rules\": [\"remove_unused_variable\"], \"generator\": \"readable\", \"instance_indexing_is_pure\": true, \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" } } }";
-
+fn bundle_roblox_require_with_configuration_built_in_rust() {
     const ROJO_SOURCEMAP: &str = r#"{
         "name": "Project",
         "className": "ModuleScript",
         "filePaths": ["src/init.lua", "default.project.json"],
         "children": [
             {
-                "name": "a1",
-                "className": "ModuleScript",
-                "filePaths": ["src/a1.lua"]
-            },
-            {
-                "name": "a2",
+                "name": "value",
                 "className": "ModuleScript",
-                "filePaths": ["src/a2.lua"]
+                "filePaths": ["src/value.lua"]
             }
         ]
     }"#;
 
-    let resources = memory_resources!(
-        "src/a1.lua" => "return os.clock() > 1",
-        "src/a2.lua" => "local Root = script.Parent\nlocal a1 = Root.a1\nreturn require(a1)",
-        "src/init.lua" => "local a1 = require(script.a1)\nlocal a2 = require(script.a2)\nprint(a1, a2)",
+    let json_resources = memory_resources!(
+        "src/value.lua" => "return true",
+        "src/init.lua" => "local value = require(script.value)",
         "default.project.json" => ROJO_SOURCEMAP,
-        ".darklua.json" => ROBLOX_BUNDLE_CONFIG,
+        ".darklua.json" => "{ \"rules\": [], \"generator\": \"retain_lines\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" } } }",
     );
 
     process(
-        &resources,
+        &json_resources,
         Options::new("src/init.lua").with_output("out.lua"),
     )
     .unwrap()
     .result()
     .unwrap();
 
-    //let out = resources.get("out.lua").unwrap();
-
-    expect_file_process(
-        &resources,
-        "out.lua",
-        r#"local __DARKLUA_BUNDLE_MODULES
-
-__DARKLUA_BUNDLE_MODULES = {
-    cache = {},
-    load = function(m)
-        if not __DARKLUA_BUNDLE_MODULES.cache[m] then
-            __DARKLUA_BUNDLE_MODULES.cache[m] = {
-                c = __DARKLUA_BUNDLE_MODULES[m](),
-            }
-        end
-
-        return __DARKLUA_BUNDLE_MODULES.cache[m].c
-    end,
-}
-
-do
-    function __DARKLUA_BUNDLE_MODULES.a()
-        return os.clock() > 1
-    end
-    function __DARKLUA_BUNDLE_MODULES.b()
-        return __DARKLUA_BUNDLE_MODULES.load('a')
-    end
-end
-
-local a1 = __DARKLUA_BUNDLE_MODULES.load('a')
-local a2 = __DARKLUA_BUNDLE_MODULES.load('b')
-
-print(a1, a2)
-"#);
-}
-
-#[test]
-fn bundle_roblox_require_respects_excludes() {
-    const ROBLOX_BUNDLE_CONFIG_WITH_EXCLUDES: &str =
-        "{ \"rules\": [], \"generator\": \"retain_lines\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"excludes\": [\"**/value.lua\"] } }";
-
-    const ROJO_SOURCEMAP: &str = r#"{
-        "name": "Project",
-        "className": "ModuleScript",
-        "filePaths": ["src/init.lua", "default.project.json"],
-        "children": [
-            {
-                "name": "value",
-                "className": "ModuleScript",
-                "filePaths": ["src/value.lua"]
-            }
-        ]
-    }"#;
-
     let resources = memory_resources!(
         "src/value.lua" => "return true",
         "src/init.lua" => "local value = require(script.value)",
         "default.project.json" => ROJO_SOURCEMAP,
-        ".darklua.json" => ROBLOX_BUNDLE_CONFIG_WITH_EXCLUDES,
     );
 
+    // without a location, the sourcemap would be resolved from the input file directory
+    let configuration = Configuration::empty()
+        .with_location(".")
+        .with_generator(GeneratorParameters::RetainLines)
+        .with_bundle(
+            BundleConfiguration::builder()
+                .require_mode(BundleRequireMode::roblox("default.project.json"))
+                .build(),
+        );
+
     process(
         &resources,
-        Options::new("src/init.lua").with_output("out.lua"),
+        Options::new("src/init.lua")
+            .with_output("out.lua")
+            .with_configuration(configuration),
     )
     .unwrap()
     .result()
     .unwrap();
 
-    let out = resources.get("out.lua").unwrap();
-
-    assert!(
-        out.contains("require(game.value)"),
-        "require should be rewritten to DataModel path due to excludes, but output was: {}",
-        out
-    );
-    assert!(
-        !out.contains("__DARKLUA_BUNDLE_MODULES"),
-        "bundle modules table should not be generated when exclude prevents inlining: {}",
-        out
+    pretty_assertions::assert_eq!(
+        resources.get("out.lua").unwrap(),
+        json_resources.get("out.lua").unwrap()
     );
 }
 
-#[test]
-fn bundle_roblox_require_respects_excludes_with_instance_indexing_is_pure() {
-    const ROBLOX_BUNDLE_CONFIG_WITH_EXCLUDES: &str =
-        "{ \"rules\": [\"remove_unused_variable\"], \"generator\": \"readable\", \"instance_indexing_is_pure\": true, \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"excludes\": [\"**/value.lua\"] } }";
+mod roblox_unsupported_expressions {
+    use super::*;
 
-    const ROJO_SOURCEMAP: &str = r#"{
-  "name": "Roblox Place",
-  "className": "DataModel",
-  "filePaths": ["place.project.json"],
-  "children": [
-    {
-      "name": "ReplicatedStorage",
-      "className": "ReplicatedStorage",
-      "children": [
-        {
-          "name": "Project",
-          "className": "ModuleScript",
-          "filePaths": ["src/init.lua"],
-          "children": [
-            {
-              "name": "value",
-              "className": "ModuleScript",
-              "filePaths": ["src/value.lua"]
-            }
-          ]
-        }
-      ]
-    }
-  ]
-}
-"#;
+    fn process_init_warnings(init_code: &str) -> Vec<String> {
+        let (resources, worker_tree) = process_bundle(
+            Some(VALUE_MODULE_SOURCEMAP),
+            "retain_lines",
+            &[],
+            &[
+                ("src/value.lua", "return true"),
+                ("src/init.lua", init_code),
+            ],
+            "src/init.lua",
+        );
 
-    let resources = memory_resources!(
-        "src/value.lua" => "return true",
-        "src/init.lua" => "local a = script.value\nlocal value = require(a)",
-        "default.project.json" => ROJO_SOURCEMAP,
-        ".darklua.json" => ROBLOX_BUNDLE_CONFIG_WITH_EXCLUDES,
-    );
+        let warnings = warning_messages(&worker_tree);
 
-    process(
-        &resources,
-        Options::new("src/init.lua").with_output("out.lua"),
-    )
-    .unwrap()
-    .result()
-    .unwrap();
+        worker_tree.result().unwrap();
 
+        pretty_assertions::assert_eq!(resources.get("out.lua").unwrap(), init_code);
 
-    expect_file_process(
-        &resources,
-        "out.lua",
-        r#"require(game.ReplicatedStorage.Project.value)
-"#);
-}
\ No newline at end of file
+        warnings
+    }
+
+    #[test]
+    fn numeric_index_of_get_children() {
+        pretty_assertions::assert_eq!(
+            process_init_warnings("local a = 1\nlocal value = require(script:GetChildren()[1])"),
+            vec![concat!(
+                "could not resolve instance path of `script:GetChildren()[1]`: numeric index ",
+                "`[1]` is not supported, only string indexes can be resolved ",
+                "(`src/init.lua` at line 2)"
+            )]
+        );
+    }
+
+    #[test]
+    fn unsupported_method() {
+        pretty_assertions::assert_eq!(
+            process_init_warnings("local value = require(script:FindFirstChildOfClass('value'))"),
+            vec![concat!(
+                "could not resolve instance path of `script:FindFirstChildOfClass('value')`: ",
+                "unsupported method `FindFirstChildOfClass` (`src/init.lua` at line 1)"
+            )]
+        );
+    }
+
+    #[test]
+    fn skipped_requires_of_every_bundled_module() {
+        let (_, worker_tree) = process_bundle(
+            Some(VALUE_MODULE_SOURCEMAP),
+            "retain_lines",
+            &[],
+            &[
+                (
+                    "src/value.lua",
+                    "local other = require(script.Parent:GetChildren()[2])\nreturn true",
+                ),
+                (
+                    "src/init.lua",
+                    "local value = require(script.value)\nlocal a = require(script:GetChildren()[1])",
+                ),
+            ],
+            "src/init.lua",
+        );
+
+        let skipped_requires: Vec<_> = worker_tree
+            .collect_skipped_requires()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+
+        worker_tree.result().unwrap();
+
+        pretty_assertions::assert_eq!(
+            skipped_requires,
+            vec![
+                concat!(
+                    "could not resolve instance path of `script:GetChildren()[1]`: numeric index ",
+                    "`[1]` is not supported, only string indexes can be resolved ",
+                    "(`src/init.lua` at line 2)"
+                ),
+                concat!(
+                    "could not resolve instance path of `script.Parent:GetChildren()[2]`: numeric ",
+                    "index `[2]` is not supported, only string indexes can be resolved ",
+                    "(`src/value.lua` at line 1)"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_variable() {
+        pretty_assertions::assert_eq!(
+            process_init_warnings("local value = require(modules.value)"),
+            vec![concat!(
+                "could not resolve instance path of `modules.value`: unable to find a local ",
+                "variable `modules` referring to an instance (`src/init.lua` at line 1)"
+            )]
+        );
+    }
+}
+
+mod require_aliases {
+    use super::*;
+
+    fn process_main(main_code: &str) -> String {
+        let (resources, worker_tree) = process_bundle(
+            None,
+            "readable",
+            &[],
+            &[
+                ("src/value.lua", "return true"),
+                ("src/main.lua", main_code),
+            ],
+            "src/main.lua",
+        );
+
+        worker_tree.result().unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    fn process_roblox_init(init_code: &str) -> String {
+        let (resources, worker_tree) = process_bundle(
+            Some(VALUE_MODULE_SOURCEMAP),
+            "readable",
+            &[],
+            &[
+                ("src/value.lua", "return true"),
+                ("src/init.lua", init_code),
+            ],
+            "src/init.lua",
+        );
+
+        worker_tree.result().unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    fn assert_bundled(out: &str) {
+        assert!(
+            out.contains("__DARKLUA_BUNDLE_MODULES.load"),
+            "module should be bundled: {}",
+            out
+        );
+    }
+
+    fn assert_not_bundled(out: &str, call: &str) {
+        assert!(
+            !out.contains("__DARKLUA_BUNDLE_MODULES"),
+            "module should not be bundled: {}",
+            out
+        );
+        assert!(out.contains(call), "`{}` should be kept: {}", call, out);
+    }
+
+    #[test]
+    fn call_through_alias_is_bundled() {
+        let out = process_main(concat!(
+            "local strictRequire = require\n",
+            "return strictRequire('./value')\n",
+        ));
+
+        assert_bundled(&out);
+    }
+
+    #[test]
+    fn call_through_alias_of_alias_is_bundled() {
+        let out = process_main(concat!(
+            "local strictRequire = require\n",
+            "local loadModule = strictRequire\n",
+            "return loadModule('./value')\n",
+        ));
+
+        assert_bundled(&out);
+    }
+
+    #[test]
+    fn call_through_alias_in_nested_function_is_bundled() {
+        let out = process_main(concat!(
+            "local strictRequire = require\n",
+            "local function load()\n",
+            "    return strictRequire('./value')\n",
+            "end\n",
+            "return load()\n",
+        ));
+
+        assert_bundled(&out);
+    }
+
+    #[test]
+    fn call_through_shadowed_alias_is_not_bundled() {
+        let out = process_main(concat!(
+            "local strictRequire = require\n",
+            "local function load(strictRequire)\n",
+            "    return strictRequire('./value')\n",
+            "end\n",
+            "return load(print)\n",
+        ));
+
+        assert_not_bundled(&out, "strictRequire('./value')");
+    }
+
+    #[test]
+    fn call_through_reassigned_alias_is_not_bundled() {
+        let out = process_main(concat!(
+            "local strictRequire = require\n",
+            "strictRequire = print\n",
+            "return strictRequire('./value')\n",
+        ));
+
+        assert_not_bundled(&out, "strictRequire('./value')");
+    }
+
+    #[test]
+    fn alias_of_shadowed_require_is_not_bundled() {
+        let out = process_main(concat!(
+            "local require = print\n",
+            "local strictRequire = require\n",
+            "return strictRequire('./value')\n",
+        ));
+
+        assert_not_bundled(&out, "strictRequire('./value')");
+    }
+
+    #[test]
+    fn call_through_alias_in_required_module_is_bundled() {
+        let resources = memory_resources!(
+            "src/value.lua" => "return true",
+            "src/module.lua" => "local load = require\nreturn load('./value')",
+            "src/main.lua" => "return require('./module')",
+            ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let out = resources.get("out.lua").unwrap();
+        assert!(
+            !out.contains("load('./value')"),
+            "alias call should be bundled: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn parenthesized_require_in_expression_is_bundled() {
+        let out = process_main("local value = (require)('./value')\nreturn value\n");
+
+        assert_bundled(&out);
+        assert!(!out.contains("require"), "{}", out);
+    }
+
+    #[test]
+    fn parenthesized_require_in_prefix_is_bundled() {
+        let out = process_main("return (require)('./value').field\n");
+
+        assert_bundled(&out);
+        assert!(!out.contains("require"), "{}", out);
+    }
+
+    #[test]
+    fn parenthesized_require_in_statement_is_bundled() {
+        let out = process_main("(require)('./value')\n");
+
+        assert_bundled(&out);
+        assert!(!out.contains("require"), "{}", out);
+    }
+
+    #[test]
+    fn nested_parenthesized_alias_is_bundled() {
+        let out = process_main(concat!(
+            "local strictRequire = require\n",
+            "return ((strictRequire))('./value')\n",
+        ));
+
+        assert_bundled(&out);
+    }
+
+    #[test]
+    fn parenthesized_shadowed_require_is_not_bundled() {
+        let out = process_main(concat!(
+            "local function load(require)\n",
+            "    return (require)('./value')\n",
+            "end\n",
+            "return load(print)\n",
+        ));
+
+        assert_not_bundled(&out, "(require)('./value')");
+    }
+
+    #[test]
+    fn require_inside_assert_is_bundled() {
+        let out = process_main("local value = assert((require)('./value'))\nreturn value\n");
+
+        assert!(
+            out.contains("assert(__DARKLUA_BUNDLE_MODULES.load('a'))"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn require_inside_select_is_bundled() {
+        let out = process_main("local value = select(1, require('./value'))\nreturn value\n");
+
+        assert!(
+            out.contains("select(1, __DARKLUA_BUNDLE_MODULES.load('a'))"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn parenthesized_require_is_bundled_in_roblox_mode() {
+        let out = process_roblox_init("return (require)(script.value)");
+
+        assert_bundled(&out);
+    }
+
+    #[test]
+    fn call_through_alias_is_bundled_in_roblox_mode() {
+        let out = process_roblox_init(
+            "local strictRequire = require\nreturn strictRequire(script.value)",
+        );
+
+        assert_bundled(&out);
+    }
+}
+
+mod roblox_local_aliases {
+    use super::*;
+
+    fn process_init(init_code: &str) -> (String, Vec<String>) {
+        let (resources, worker_tree) = process_bundle(
+            Some(VALUE_MODULE_SOURCEMAP),
+            "retain_lines",
+            &[],
+            &[
+                ("src/value.lua", "return true"),
+                ("src/init.lua", init_code),
+            ],
+            "src/init.lua",
+        );
+
+        let warnings = warning_messages(&worker_tree);
+
+        worker_tree.result().unwrap();
+
+        (resources.get("out.lua").unwrap(), warnings)
+    }
+
+    #[test]
+    fn local_in_sibling_function_does_not_shadow_alias() {
+        let (out, warnings) = process_init(concat!(
+            "local Modules = script\n",
+            "local function setup()\n",
+            "    local Modules = script.Parent\n",
+            "    return Modules\n",
+            "end\n",
+            "local value = require(Modules.value)\n",
+        ));
+
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+        pretty_assertions::assert_eq!(warnings, Vec::<String>::new());
+    }
+
+    #[test]
+    fn innermost_local_is_used() {
+        let (out, warnings) = process_init(concat!(
+            "local Modules = script.Parent\n",
+            "do\n",
+            "    local Modules = script\n",
+            "    local value = require(Modules.value)\n",
+            "end\n",
+        ));
+
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+        pretty_assertions::assert_eq!(warnings, Vec::<String>::new());
+    }
+
+    #[test]
+    fn local_reassigned_before_require_is_not_resolved() {
+        let code = concat!(
+            "local Modules = script\n",
+            "Modules = script.Parent\n",
+            "local value = require(Modules.value)\n",
+        );
+        let (out, warnings) = process_init(code);
+
+        pretty_assertions::assert_eq!(out, code);
+        pretty_assertions::assert_eq!(
+            warnings,
+            vec![concat!(
+                "could not resolve instance path of `Modules.value`: local variable `Modules` ",
+                "is reassigned before the require call (`src/init.lua` at line 3)"
+            )]
+        );
+    }
+
+    #[test]
+    fn local_reassigned_after_require_is_resolved() {
+        let (out, warnings) = process_init(concat!(
+            "local Modules = script\n",
+            "local value = require(Modules.value)\n",
+            "Modules = nil\n",
+        ));
+
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+        pretty_assertions::assert_eq!(warnings, Vec::<String>::new());
+    }
+}
+
+mod module_naming {
+    use super::*;
+
+    fn process_main(module_naming: &str, main_code: &str) -> String {
+        let (resources, worker_tree) = process_bundle(
+            None,
+            "readable",
+            &[("module_naming", &format!("\"{}\"", module_naming))],
+            &[
+                ("src/a.lua", "return 'a'"),
+                ("src/b.lua", "return 'b'"),
+                ("src/c.lua", "return 'c'"),
+                ("src/main.lua", main_code),
+            ],
+            "src/main.lua",
+        );
+
+        worker_tree.result().unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    fn module_name<'a>(out: &'a str, variable: &str) -> &'a str {
+        let prefix = format!("local {} = __DARKLUA_BUNDLE_MODULES.load('", variable);
+        let start = out.find(&prefix).expect("module should be bundled") + prefix.len();
+        let end = start + out[start..].find('\'').unwrap();
+        &out[start..end]
+    }
+
+    #[test]
+    fn content_hash_names_do_not_change_when_a_module_is_added() {
+        let before = process_main(
+            "content_hash",
+            "local b = require('./b')\nlocal c = require('./c')\nprint(b, c)",
+        );
+        let after = process_main(
+            "content_hash",
+            "local a = require('./a')\nlocal b = require('./b')\nlocal c = require('./c')\nprint(a, b, c)",
+        );
+
+        pretty_assertions::assert_eq!(module_name(&before, "b"), module_name(&after, "b"));
+        pretty_assertions::assert_eq!(module_name(&before, "c"), module_name(&after, "c"));
+    }
+
+    #[test]
+    fn sequential_names_change_when_a_module_is_added() {
+        let before = process_main("sequential", "local b = require('./b')\nprint(b)");
+        let after = process_main(
+            "sequential",
+            "local a = require('./a')\nlocal b = require('./b')\nprint(a, b)",
+        );
+
+        pretty_assertions::assert_eq!(module_name(&before, "b"), "a");
+        pretty_assertions::assert_eq!(module_name(&after, "b"), "d");
+    }
+
+    #[test]
+    fn bundle_with_content_hash_names() {
+        let out = process_main(
+            "content_hash",
+            "local a = require('./a')\nlocal b = require('./b')\nprint(a, b)",
+        );
+
+        insta::assert_snapshot!("bundle_with_content_hash_names", out);
+    }
+
+    fn process_main_at(root: &str) -> String {
+        let resources = Resources::from_memory();
+        resources
+            .write(format!("{}/src/a.lua", root), "return 'a'")
+            .unwrap();
+        resources
+            .write(
+                format!("{}/src/main.lua", root),
+                "local a = require('./a')\nprint(a)",
+            )
+            .unwrap();
+        resources
+            .write(
+                format!("{}/.darklua.json", root),
+                "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": \"path\", \"module_naming\": \"content_hash\" } }",
+            )
+            .unwrap();
+
+        process(
+            &resources,
+            Options::new(format!("{}/src/main.lua", root))
+                .with_configuration_at(format!("{}/.darklua.json", root))
+                .with_output(format!("{}/out.lua", root)),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get(format!("{}/out.lua", root)).unwrap()
+    }
+
+    #[test]
+    fn content_hash_names_do_not_depend_on_the_project_location() {
+        let first = process_main_at("/home/user/project");
+        let second = process_main_at("/tmp/build/project");
+
+        pretty_assertions::assert_eq!(module_name(&first, "a"), module_name(&second, "a"));
+    }
+}
+
+mod pcall_requires {
+    use super::*;
+
+    fn process_bundle_with_policy(
+        sourcemap: Option<&str>,
+        entry: &str,
+        pcall_requires: &str,
+        code: &str,
+    ) -> String {
+        let (resources, worker_tree) = process_bundle(
+            sourcemap,
+            "readable",
+            &[("pcall_requires", &format!("\"{}\"", pcall_requires))],
+            &[("src/value.lua", "return true"), (entry, code)],
+            entry,
+        );
+
+        worker_tree.result().unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    fn process_path_main(pcall_requires: &str, main_code: &str) -> String {
+        process_bundle_with_policy(None, "src/main.lua", pcall_requires, main_code)
+    }
+
+    fn process_roblox_init(pcall_requires: &str, init_code: &str) -> String {
+        process_bundle_with_policy(
+            Some(VALUE_MODULE_SOURCEMAP),
+            "src/init.lua",
+            pcall_requires,
+            init_code,
+        )
+    }
+
+    #[test]
+    fn path_pcall_require_is_bundled() {
+        let out = process_path_main("bundle", "local ok, value = pcall(require, './value')\n");
+
+        insta::assert_snapshot!("path_pcall_require_is_bundled", out);
+    }
+
+    #[test]
+    fn path_xpcall_require_is_bundled() {
+        let out = process_path_main(
+            "bundle",
+            "local ok, value = xpcall(require, warn, './value')\n",
+        );
+
+        assert!(
+            out.contains("xpcall(function()"),
+            "module loading should be protected: {}",
+            out
+        );
+        assert!(
+            out.contains("end, warn)"),
+            "handler should be kept: {}",
+            out
+        );
+        assert!(
+            !out.contains("require"),
+            "require should be inlined: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn path_pcall_require_through_alias_is_bundled() {
+        let out = process_path_main(
+            "bundle",
+            "local load = require\nlocal ok, value = pcall(load, './value')\n",
+        );
+
+        assert!(
+            out.contains("pcall(function()"),
+            "module loading should be protected: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn path_pcall_require_with_local_pcall_is_not_bundled() {
+        let code = "local pcall = nil\nlocal ok, value = pcall(require, './value')\n";
+        let out = process_path_main("bundle", code);
+
+        assert!(
+            !out.contains("__DARKLUA_BUNDLE_MODULES"),
+            "module should not be bundled: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn path_pcall_require_is_excluded() {
+        let out = process_path_main("exclude", "local ok, value = pcall(require, './value')\n");
+
+        pretty_assertions::assert_eq!(out, "local ok, value = pcall(require, './value')\n");
+    }
+
+    #[test]
+    fn path_pcall_require_is_ignored() {
+        let out = process_path_main("ignore", "local ok, value = pcall(require, './value')\n");
+
+        pretty_assertions::assert_eq!(out, "local ok, value = pcall(require, './value')\n");
+    }
+
+    #[test]
+    fn roblox_pcall_require_is_bundled() {
+        let out = process_roblox_init("bundle", "local ok, value = pcall(require, script.value)\n");
+
+        insta::assert_snapshot!("roblox_pcall_require_is_bundled", out);
+    }
+
+    #[test]
+    fn roblox_pcall_require_is_excluded() {
+        let out = process_roblox_init(
+            "exclude",
+            "local ok, value = pcall(require, script.value)\n",
+        );
+
+        pretty_assertions::assert_eq!(out, "local ok, value = pcall(require, game.value)\n");
+    }
+
+    #[test]
+    fn roblox_xpcall_require_is_excluded() {
+        let out = process_roblox_init(
+            "exclude",
+            "local ok, value = xpcall(require, warn, script.value)\n",
+        );
+
+        pretty_assertions::assert_eq!(out, "local ok, value = xpcall(require, warn, game.value)\n");
+    }
+
+    #[test]
+    fn roblox_pcall_require_is_ignored() {
+        let out = process_roblox_init("ignore", "local ok, value = pcall(require, script.value)\n");
+
+        pretty_assertions::assert_eq!(out, "local ok, value = pcall(require, script.value)\n");
+    }
+}
+
+mod require_directives {
+    use super::*;
+
+    fn process_bundle_with_excludes(
+        sourcemap: Option<&str>,
+        entry: &str,
+        excludes: &str,
+        code: &str,
+    ) -> String {
+        let (resources, worker_tree) = process_bundle(
+            sourcemap,
+            "retain_lines",
+            &[("excludes", &format!("[{}]", excludes))],
+            &[("src/value.lua", "return true"), (entry, code)],
+            entry,
+        );
+
+        worker_tree.result().unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    fn process_path_main(excludes: &str, main_code: &str) -> String {
+        process_bundle_with_excludes(None, "src/main.lua", excludes, main_code)
+    }
+
+    fn process_roblox_init(excludes: &str, init_code: &str) -> String {
+        process_bundle_with_excludes(
+            Some(VALUE_MODULE_SOURCEMAP),
+            "src/init.lua",
+            excludes,
+            init_code,
+        )
+    }
+
+    #[test]
+    fn path_keep_require_is_untouched() {
+        let code = "local value = require('./value') -- darklua: keep-require\n";
+        let out = process_path_main("", code);
+
+        pretty_assertions::assert_eq!(out, code);
+    }
+
+    #[test]
+    fn path_keep_require_only_applies_to_its_call() {
+        let out = process_path_main(
+            "",
+            "local kept = require('./value') -- darklua: keep-require\nlocal value = require('./value')\n",
+        );
+
+        assert!(
+            out.contains("local kept = require('./value') -- darklua: keep-require"),
+            "require with the directive should be kept: {}",
+            out
+        );
+        assert!(
+            out.contains("local value = __DARKLUA_BUNDLE_MODULES"),
+            "require without the directive should be bundled: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn path_bundle_directive_overrides_excludes() {
+        let out = process_path_main(
+            "\"**/value.lua\"",
+            "local value = require('./value') -- darklua: bundle\n",
+        );
+
+        assert!(
+            out.contains("__DARKLUA_BUNDLE_MODULES"),
+            "module should be bundled: {}",
+            out
+        );
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn path_excluded_require_without_directive_is_untouched() {
+        let code = "local value = require('./value') -- not a directive\n";
+        let out = process_path_main("\"**/value.lua\"", code);
+
+        pretty_assertions::assert_eq!(out, code);
+    }
+
+    #[test]
+    fn roblox_keep_require_is_rewritten_from_datamodel() {
+        let out = process_roblox_init(
+            "",
+            "local value = require(script.value) -- darklua: keep-require\n",
+        );
+
+        assert!(
+            out.contains("require(game.value)"),
+            "require should be rewritten from the DataModel root: {}",
+            out
+        );
+        assert!(
+            !out.contains("__DARKLUA_BUNDLE_MODULES"),
+            "module should not be bundled: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn roblox_bundle_directive_overrides_excludes() {
+        let out = process_roblox_init(
+            "\"**/value.lua\"",
+            "local value = require(script.value) -- darklua: bundle\n",
+        );
+
+        assert!(
+            out.contains("__DARKLUA_BUNDLE_MODULES"),
+            "module should be bundled: {}",
+            out
+        );
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+    }
+}
+
+mod roblox_wait_for_child {
+    use darklua_core::rules::WarningCode;
+
+    use super::*;
+
+    fn process_init(init_code: &str, strict_wait_for_child: bool) -> (String, Vec<WarningCode>) {
+        let (resources, worker_tree) = process_bundle(
+            Some(VALUE_MODULE_SOURCEMAP),
+            "retain_lines",
+            &[("strict_wait_for_child", &strict_wait_for_child.to_string())],
+            &[
+                ("src/value.lua", "return true"),
+                ("src/init.lua", init_code),
+            ],
+            "src/init.lua",
+        );
+
+        let warning_codes = worker_tree
+            .collect_warnings()
+            .into_iter()
+            .flat_map(|(_, warnings)| warnings.iter().map(|warning| warning.code()))
+            .collect();
+
+        worker_tree.result().unwrap();
+
+        (resources.get("out.lua").unwrap(), warning_codes)
+    }
+
+    #[test]
+    fn without_timeout_inlines_without_warnings() {
+        let (out, warnings) =
+            process_init("local value = require(script:WaitForChild('value'))", false);
+
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+        pretty_assertions::assert_eq!(warnings, Vec::new());
+    }
+
+    #[test]
+    fn with_timeout_inlines_and_warns() {
+        let (out, warnings) = process_init(
+            "local value = require(script:WaitForChild('value', 5))",
+            false,
+        );
+
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+        pretty_assertions::assert_eq!(warnings, vec![WarningCode::WaitForChildTimeout]);
+    }
+
+    #[test]
+    fn with_timeout_in_strict_mode_skips_inlining() {
+        let code = "local value = require(script:WaitForChild('value', 5))";
+        let (out, warnings) = process_init(code, true);
+
+        pretty_assertions::assert_eq!(out, code);
+        assert!(
+            warnings.contains(&WarningCode::WaitForChildTimeout),
+            "missing WaitForChild timeout warning: {:?}",
+            warnings
+        );
+    }
+
+    #[test]
+    fn without_timeout_in_strict_mode_inlines() {
+        let (out, warnings) =
+            process_init("local value = require(script:WaitForChild('value'))", true);
+
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+        pretty_assertions::assert_eq!(warnings, Vec::new());
+    }
+}
+
+mod roblox_verify_find_first_child {
+    use darklua_core::DarkluaError;
+
+    use super::*;
+
+    fn process_init(
+        init_code: &str,
+        verify_find_first_child: bool,
+    ) -> Result<String, DarkluaError> {
+        let (resources, worker_tree) = process_bundle(
+            Some(VALUE_MODULE_SOURCEMAP),
+            "retain_lines",
+            &[(
+                "verify_find_first_child",
+                &verify_find_first_child.to_string(),
+            )],
+            &[
+                ("src/value.lua", "return true"),
+                ("src/init.lua", init_code),
+            ],
+            "src/init.lua",
+        );
+
+        worker_tree
+            .result()
+            .map_err(|errors| errors.into_iter().next().unwrap())?;
+
+        Ok(resources.get("out.lua").unwrap())
+    }
+
+    #[test]
+    fn existing_child_is_inlined() {
+        let out = process_init(
+            "local value = require(script:FindFirstChild('value'))",
+            true,
+        )
+        .unwrap();
+
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn missing_child_is_left_as_is_without_verification() {
+        let code = "local value = require(script:FindFirstChild('missing'))";
+
+        pretty_assertions::assert_eq!(process_init(code, false).unwrap(), code);
+    }
+
+    #[test]
+    fn missing_child_errors() {
+        let error = process_init(
+            "local value = require(script:FindFirstChild('missing'))",
+            true,
+        )
+        .unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            error.to_string(),
+            concat!(
+                "error processing `src/init.lua` (bundler): unable to bundle ",
+                "`require(script:FindFirstChild('missing'))`: `FindFirstChild` searched for ",
+                "`missing` under `Project` but it does not exist in the Rojo sourcemap ",
+                "(`src/init.lua` at line 1)"
+            )
+        );
+    }
+
+    #[test]
+    fn multiple_missing_children_errors_are_sorted() {
+        let error = process_init(
+            concat!(
+                "local zeta = require(script:FindFirstChild('zeta'))\n",
+                "local alpha = require(script:FindFirstChild('alpha'))\n",
+                "local mid = require(script:FindFirstChild('mid'))\n",
+            ),
+            true,
+        )
+        .unwrap_err();
+
+        insta::assert_snapshot!(
+            "multiple_missing_children_errors_are_sorted",
+            error.to_string()
+        );
+    }
+}
+
+mod roblox_non_module_requires {
+    use darklua_core::DarkluaError;
+
+    use super::*;
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            {
+                "name": "value",
+                "className": "ModuleScript",
+                "filePaths": ["src/value.lua"]
+            },
+            {
+                "name": "Assets",
+                "className": "Folder",
+                "filePaths": ["src/assets/default.project.json"]
+            },
+            {
+                "name": "Client",
+                "className": "LocalScript",
+                "filePaths": ["src/client.client.lua"]
+            }
+        ]
+    }"#;
+
+    fn process_init(
+        init_code: &str,
+        allow_non_module_requires: bool,
+    ) -> Result<String, DarkluaError> {
+        let (resources, worker_tree) = process_bundle(
+            Some(ROJO_SOURCEMAP),
+            "retain_lines",
+            &[(
+                "allow_non_module_requires",
+                &allow_non_module_requires.to_string(),
+            )],
+            &[
+                ("src/value.lua", "return true"),
+                (
+                    "src/assets/default.project.json",
+                    "{ \"name\": \"Assets\", \"tree\": {} }",
+                ),
+                ("src/client.client.lua", "print('client')"),
+                ("src/init.lua", init_code),
+            ],
+            "src/init.lua",
+        );
+
+        worker_tree
+            .result()
+            .map_err(|errors| errors.into_iter().next().unwrap())?;
+
+        Ok(resources.get("out.lua").unwrap())
+    }
+
+    #[test]
+    fn module_script_is_inlined() {
+        let out = process_init("local value = require(script.value)", false).unwrap();
+
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn folder_require_errors() {
+        let error = process_init("local assets = require(script.Assets)", false).unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            error.to_string(),
+            concat!(
+                "error processing `src/init.lua` (bundler): unable to bundle ",
+                "`require(script.Assets)`: `script.Assets` is a `Folder`, only a ",
+                "`ModuleScript` can be required (`src/init.lua` at line 1)"
+            )
+        );
+    }
+
+    #[test]
+    fn local_script_require_errors() {
+        let error = process_init("local client = require(script.Client)", false).unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            error.to_string(),
+            concat!(
+                "error processing `src/init.lua` (bundler): unable to bundle ",
+                "`require(script.Client)`: `script.Client` is a `LocalScript`, only a ",
+                "`ModuleScript` can be required (`src/init.lua` at line 1)"
+            )
+        );
+    }
+
+    #[test]
+    fn folder_require_is_inlined_when_allowed() {
+        let out = process_init("local assets = require(script.Assets)", true).unwrap();
+
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+    }
+}
+
+#[test]
+fn bundle_roblox_require_respects_instance_indexing_is_pure() {
+    const ROBLOX_BUNDLE_CONFIG: &str =
+        "{ \"rules\": [\"remove_unused_variable\"], \"generator\": \"readable\", \"instance_indexing_is_pure\": true, \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" } } }";
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            {
+                "name": "a1",
+                "className": "ModuleScript",
+                "filePaths": ["src/a1.lua"]
+            },
+            {
+                "name": "a2",
+                "className": "ModuleScript",
+                "filePaths": ["src/a2.lua"]
+            },
+            {
+                "name": "a3",
+                "className": "ModuleScript",
+                "filePaths": ["src/a3.lua"]
+            }
+        ]
+    }"#;
+
+    let resources = memory_resources!(
+        "src/a1.lua" => "return os.clock() > 1",
+        "src/a2.lua" => "local Root = script.Parent\nlocal a1 = Root.a1\nreturn require(a1)",
+        "src/a3.lua" => "local Parent = script.Parent\nlocal Shared = Parent:FindFirstChild('Shared')\nlocal Config = Shared.Config\nreturn 'a3'",
+        "src/init.lua" => "local a1 = require(script.a1)\nlocal a2 = require(script.a2)\nlocal a3 = require(script.a3)\nprint(a1, a2, a3)",
+        "default.project.json" => ROJO_SOURCEMAP,
+        ".darklua.json" => ROBLOX_BUNDLE_CONFIG,
+    );
+
+    process(
+        &resources,
+        Options::new("src/init.lua").with_output("out.lua"),
+    )
+    .unwrap()
+    .result()
+    .unwrap();
+
+    //let out = resources.get("out.lua").unwrap();
+
+    expect_file_process(
+        &resources,
+        "out.lua",
+        r#"local __DARKLUA_BUNDLE_MODULES
+
+__DARKLUA_BUNDLE_MODULES = {
+    cache = {},
+    load = function(m)
+        if not __DARKLUA_BUNDLE_MODULES.cache[m] then
+            __DARKLUA_BUNDLE_MODULES.cache[m] = {
+                c = __DARKLUA_BUNDLE_MODULES[m](),
+            }
+        end
+
+        return __DARKLUA_BUNDLE_MODULES.cache[m].c
+    end,
+}
+
+do
+    function __DARKLUA_BUNDLE_MODULES.a()
+        return os.clock() > 1
+    end
+    function __DARKLUA_BUNDLE_MODULES.b()
+        return __DARKLUA_BUNDLE_MODULES.load('a')
+    end
+    function __DARKLUA_BUNDLE_MODULES.c()
+        return 'a3'
+    end
+end
+
+local a1 = __DARKLUA_BUNDLE_MODULES.load('a')
+local a2 = __DARKLUA_BUNDLE_MODULES.load('b')
+local a3 = __DARKLUA_BUNDLE_MODULES.load('c')
+
+print(a1, a2, a3)
+"#);
+}
+
+#[test]
+fn bundle_roblox_require_respects_instance_indexing_is_pure_without_rules() {
+    const ROBLOX_BUNDLE_CONFIG: &str =
+        "{ \"rules\": [], \"generator\": \"readable\", \"instance_indexing_is_pure\": true, \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" } } }";
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            {
+                "name": "value",
+                "className": "ModuleScript",
+                "filePaths": ["src/value.lua"]
+            }
+        ]
+    }"#;
+
+    let resources = memory_resources!(
+        "src/value.lua" => "local Parent = script.Parent\nlocal Shared = Parent:FindFirstChild('Shared')\nlocal Config = Shared.Config\nreturn 'value'",
+        "src/init.lua" => "local value = require(script.value)\nprint(value)",
+        "default.project.json" => ROJO_SOURCEMAP,
+        ".darklua.json" => ROBLOX_BUNDLE_CONFIG,
+    );
+
+    process(
+        &resources,
+        Options::new("src/init.lua").with_output("out.lua"),
+    )
+    .unwrap()
+    .result()
+    .unwrap();
+
+    let out = resources.get("out.lua").unwrap();
+
+    assert!(
+        !out.contains("Shared"),
+        "unused aliases should be removed: {}",
+        out
+    );
+}
+
+#[test]
+fn bundle_roblox_require_respects_excludes() {
+    const ROBLOX_BUNDLE_CONFIG_WITH_EXCLUDES: &str =
+        "{ \"rules\": [], \"generator\": \"retain_lines\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"excludes\": [\"**/value.lua\"] } }";
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            {
+                "name": "value",
+                "className": "ModuleScript",
+                "filePaths": ["src/value.lua"]
+            }
+        ]
+    }"#;
+
+    let resources = memory_resources!(
+        "src/value.lua" => "return true",
+        "src/init.lua" => "local value = require(script.value)",
+        "default.project.json" => ROJO_SOURCEMAP,
+        ".darklua.json" => ROBLOX_BUNDLE_CONFIG_WITH_EXCLUDES,
+    );
+
+    process(
+        &resources,
+        Options::new("src/init.lua").with_output("out.lua"),
+    )
+    .unwrap()
+    .result()
+    .unwrap();
+
+    let out = resources.get("out.lua").unwrap();
+
+    assert!(
+        out.contains("require(game.value)"),
+        "require should be rewritten to DataModel path due to excludes, but output was: {}",
+        out
+    );
+    assert!(
+        !out.contains("__DARKLUA_BUNDLE_MODULES"),
+        "bundle modules table should not be generated when exclude prevents inlining: {}",
+        out
+    );
+}
+
+#[test]
+fn bundle_roblox_require_hoists_excluded_requires() {
+    const ROBLOX_BUNDLE_CONFIG: &str =
+        "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"excludes\": [\"**/value.lua\"], \"hoist_excluded_requires\": true } }";
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            {
+                "name": "a",
+                "className": "ModuleScript",
+                "filePaths": ["src/a.lua"]
+            },
+            {
+                "name": "value",
+                "className": "ModuleScript",
+                "filePaths": ["src/value.lua"]
+            }
+        ]
+    }"#;
+
+    let resources = memory_resources!(
+        "src/value.lua" => "return true",
+        "src/a.lua" => "local value = require(script.Parent.value)\nreturn not value",
+        "src/init.lua" => "local a = require(script.a)\nlocal value = require(script.value)\nprint(a, value)",
+        "default.project.json" => ROJO_SOURCEMAP,
+        ".darklua.json" => ROBLOX_BUNDLE_CONFIG,
+    );
+
+    process(
+        &resources,
+        Options::new("src/init.lua").with_output("out.lua"),
+    )
+    .unwrap()
+    .result()
+    .unwrap();
+
+    insta::assert_snapshot!(
+        "bundle_roblox_require_hoists_excluded_requires",
+        resources.get("out.lua").unwrap()
+    );
+}
+
+#[test]
+fn bundle_roblox_require_removes_hoisted_excluded_require_statement() {
+    const ROBLOX_BUNDLE_CONFIG: &str =
+        "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"excludes\": [\"**/value.lua\"], \"hoist_excluded_requires\": true } }";
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            {
+                "name": "value",
+                "className": "ModuleScript",
+                "filePaths": ["src/value.lua"]
+            }
+        ]
+    }"#;
+
+    let resources = memory_resources!(
+        "src/value.lua" => "return true",
+        "src/init.lua" => "require(script.value)\nprint('start')\nif condition then\n    require(script.value)\n    print('inside')\nend",
+        "default.project.json" => ROJO_SOURCEMAP,
+        ".darklua.json" => ROBLOX_BUNDLE_CONFIG,
+    );
+
+    process(
+        &resources,
+        Options::new("src/init.lua").with_output("out.lua"),
+    )
+    .unwrap()
+    .result()
+    .unwrap();
+
+    insta::assert_snapshot!(
+        "bundle_roblox_require_removes_hoisted_excluded_require_statement",
+        resources.get("out.lua").unwrap()
+    );
+}
+
+#[test]
+fn bundle_roblox_require_respects_excludes_with_instance_indexing_is_pure() {
+    const ROBLOX_BUNDLE_CONFIG_WITH_EXCLUDES: &str =
+        "{ \"rules\": [\"remove_unused_variable\"], \"generator\": \"readable\", \"instance_indexing_is_pure\": true, \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"excludes\": [\"**/value.lua\"] } }";
+
+    const ROJO_SOURCEMAP: &str = r#"{
+  "name": "Roblox Place",
+  "className": "DataModel",
+  "filePaths": ["place.project.json"],
+  "children": [
+    {
+      "name": "ReplicatedStorage",
+      "className": "ReplicatedStorage",
+      "children": [
+        {
+          "name": "Project",
+          "className": "ModuleScript",
+          "filePaths": ["src/init.lua"],
+          "children": [
+            {
+              "name": "value",
+              "className": "ModuleScript",
+              "filePaths": ["src/value.lua"]
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}
+"#;
+
+    let resources = memory_resources!(
+        "src/value.lua" => "return true",
+        "src/init.lua" => "local a = script.value\nlocal value = require(a)",
+        "default.project.json" => ROJO_SOURCEMAP,
+        ".darklua.json" => ROBLOX_BUNDLE_CONFIG_WITH_EXCLUDES,
+    );
+
+    process(
+        &resources,
+        Options::new("src/init.lua").with_output("out.lua"),
+    )
+    .unwrap()
+    .result()
+    .unwrap();
+
+
+    expect_file_process(
+        &resources,
+        "out.lua",
+        r#"require(game.ReplicatedStorage.Project.value)
+"#);
+}
+mod roblox_script_entry {
+    use super::*;
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Game",
+        "className": "DataModel",
+        "filePaths": ["default.project.json"],
+        "children": [
+            {
+                "name": "ReplicatedStorage",
+                "className": "ReplicatedStorage",
+                "children": [
+                    {
+                        "name": "Config",
+                        "className": "ModuleScript",
+                        "filePaths": ["src/shared/Config.lua"]
+                    }
+                ]
+            },
+            {
+                "name": "ServerScriptService",
+                "className": "ServerScriptService",
+                "children": [
+                    {
+                        "name": "Main",
+                        "className": "Script",
+                        "filePaths": ["src/server/main.server.lua"]
+                    },
+                    {
+                        "name": "Service",
+                        "className": "ModuleScript",
+                        "filePaths": ["src/server/Service.lua"]
+                    }
+                ]
+            },
+            {
+                "name": "StarterPlayer",
+                "className": "StarterPlayer",
+                "children": [
+                    {
+                        "name": "StarterPlayerScripts",
+                        "className": "StarterPlayerScripts",
+                        "children": [
+                            {
+                                "name": "Client",
+                                "className": "LocalScript",
+                                "filePaths": ["src/client/init.client.lua"],
+                                "children": [
+                                    {
+                                        "name": "Controller",
+                                        "className": "ModuleScript",
+                                        "filePaths": ["src/client/Controller.lua"]
+                                    }
+                                ]
+                            }
+                        ]
+                    }
+                ]
+            }
+        ]
+    }"#;
+
+    fn process_entry(entry_path: &str, entry_code: &str) -> String {
+        let (resources, worker_tree) = process_bundle(
+            Some(ROJO_SOURCEMAP),
+            "readable",
+            &[],
+            &[
+                ("src/shared/Config.lua", "return { debug = false }"),
+                (
+                    "src/server/Service.lua",
+                    "local Config = require(game:GetService('ReplicatedStorage').Config)\nreturn { config = Config }",
+                ),
+                ("src/client/Controller.lua", "return { started = true }"),
+                (entry_path, entry_code),
+            ],
+            entry_path,
+        );
+
+        worker_tree.result().unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    #[test]
+    fn script_entry_requires_two_module_scripts() {
+        let out = process_entry(
+            "src/server/main.server.lua",
+            concat!(
+                "local Service = require(script.Parent.Service)\n",
+                "local Config = require(game:GetService('ReplicatedStorage').Config)\n",
+                "print(Service, Config)\n",
+            ),
+        );
+
+        insta::assert_snapshot!("script_entry_requires_two_module_scripts", out);
+    }
+
+    #[test]
+    fn script_entry_without_return_is_not_changed_into_a_module() {
+        let out = process_entry(
+            "src/server/main.server.lua",
+            "local Service = require(script.Parent.Service)\nprint(Service)\n",
+        );
+
+        assert!(
+            out.ends_with("print(Service)\n"),
+            "entry code should not get a return statement: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn script_entry_can_return_multiple_values() {
+        let out = process_entry(
+            "src/server/main.server.lua",
+            "local Service = require(script.Parent.Service)\nreturn Service, 2\n",
+        );
+
+        assert!(
+            out.ends_with("return Service, 2\n"),
+            "entry return statement should be kept: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn local_script_entry_requires_child_module_script() {
+        let out = process_entry(
+            "src/client/init.client.lua",
+            "local Controller = require(script.Controller)\nprint(Controller)\n",
+        );
+
+        assert!(
+            !out.contains("require("),
+            "require should be inlined: {}",
+            out
+        );
+        assert!(
+            out.contains("return {started = true}"),
+            "child module should be bundled: {}",
+            out
+        );
+    }
+}
+
+mod prune_bundled_files {
+    use super::*;
+
+    fn config(prune: bool) -> String {
+        format!(
+            "{{ \"rules\": [], \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": \"path\", \"excludes\": [\"**/excluded.lua\"], \"prune_bundled_files\": {} }} }}",
+            prune
+        )
+    }
+
+    fn process_tree(config: &str) -> Resources {
+        let resources = memory_resources!(
+            "src/main.lua" => "local util = require('./util')\nlocal helper = require('./lib/helper')\nlocal excluded = require('./excluded')\nprint(util, helper, excluded)",
+            "src/util.lua" => "return 'util'",
+            "src/lib/helper.lua" => "local shared = require('./shared')\nreturn shared",
+            "src/lib/shared.lua" => "return 'shared'",
+            "src/excluded.lua" => "return 'excluded'",
+            "src/other.lua" => "return 'other'",
+            ".darklua.json" => config,
+        );
+
+        process(&resources, Options::new("src").with_output("dist"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources
+    }
+
+    #[test]
+    fn bundled_files_are_pruned() {
+        let resources = process_tree(&config(true));
+
+        assert!(resources.exists("dist/main.lua").unwrap());
+        assert!(!resources.exists("dist/util.lua").unwrap());
+        assert!(!resources.exists("dist/lib/helper.lua").unwrap());
+        assert!(!resources.exists("dist/lib/shared.lua").unwrap());
+    }
+
+    #[test]
+    fn excluded_and_unused_files_are_retained() {
+        let resources = process_tree(&config(true));
+
+        assert!(resources.exists("dist/excluded.lua").unwrap());
+        assert!(resources.exists("dist/other.lua").unwrap());
+    }
+
+    #[test]
+    fn sources_are_not_removed() {
+        let resources = process_tree(&config(true));
+
+        assert!(resources.exists("src/util.lua").unwrap());
+        assert!(resources.exists("src/lib/helper.lua").unwrap());
+    }
+
+    #[test]
+    fn bundled_files_are_retained_without_the_option() {
+        let resources = process_tree(&config(false));
+
+        for path in [
+            "dist/main.lua",
+            "dist/util.lua",
+            "dist/lib/helper.lua",
+            "dist/lib/shared.lua",
+            "dist/excluded.lua",
+            "dist/other.lua",
+        ] {
+            assert!(resources.exists(path).unwrap(), "missing {}", path);
+        }
+    }
+}
+
+mod pre_bundle_rules {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "rules": [],
+        "pre_bundle_rules": [
+            { "rule": "inject_global_value", "identifier": "__DEV__", "value": false },
+            "remove_unused_if_branch"
+        ],
+        "generator": "readable",
+        "bundle": { "require_mode": "path" }
+    }"#;
+
+    fn process_main(config: &str, main_code: &str) -> String {
+        let resources = memory_resources!(
+            "src/devtools.lua" => "return 'DEVTOOLS'",
+            "src/value.lua" => "if __DEV__ then\n    require('./devtools')\nend\nreturn 'VALUE'",
+            "src/main.lua" => main_code,
+            ".darklua.json" => config,
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    #[test]
+    fn removed_require_in_entry_is_not_bundled() {
+        let out = process_main(
+            CONFIG,
+            "if __DEV__ then\n    require('./devtools')\nend\nprint('main')",
+        );
+
+        assert!(!out.contains("DEVTOOLS"), "{}", out);
+        assert!(!out.contains("__DARKLUA_BUNDLE_MODULES"), "{}", out);
+    }
+
+    #[test]
+    fn removed_require_in_bundled_module_is_not_bundled() {
+        let out = process_main(CONFIG, "local value = require('./value')\nprint(value)");
+
+        assert!(out.contains("VALUE"), "{}", out);
+        assert!(!out.contains("DEVTOOLS"), "{}", out);
+    }
+
+    #[test]
+    fn require_is_bundled_without_pre_bundle_rules() {
+        let out = process_main(
+            DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+            "local value = require('./value')\nprint(value)",
+        );
+
+        assert!(out.contains("DEVTOOLS"), "{}", out);
+    }
+}
+
+mod multi_return_modules {
+    use super::*;
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            {
+                "name": "values",
+                "className": "ModuleScript",
+                "filePaths": ["src/values.lua"]
+            }
+        ]
+    }"#;
+
+    fn path_config(multi_return_modules: &str) -> String {
+        format!(
+            "{{ \"rules\": [], \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": \"path\", \"multi_return_modules\": \"{}\" }} }}",
+            multi_return_modules
+        )
+    }
+
+    fn process_main(config: &str, values_code: &str, main_code: &str) -> String {
+        let resources = memory_resources!(
+            "src/values.lua" => values_code,
+            "src/main.lua" => main_code,
+            ".darklua.json" => config,
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    #[test]
+    fn multiple_values_module_errors_by_default() {
+        let resources = memory_resources!(
+            "src/values.lua" => "return 1, 2",
+            "src/main.lua" => "local a, b = require('./values')",
+            ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        let message = errors.first().unwrap().to_string();
+        assert!(
+            message.contains("module must return exactly one value"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn wrap_unpacks_values_in_local_multi_assignment() {
+        let out = process_main(
+            &path_config("wrap"),
+            "return 1, 2",
+            "local a, b = require('./values')\nprint(a, b)",
+        );
+
+        insta::assert_snapshot!("wrap_unpacks_values_in_local_multi_assignment", out);
+    }
+
+    #[test]
+    fn wrap_reads_first_value_in_single_value_context() {
+        let out = process_main(
+            &path_config("wrap"),
+            "return 1, 2",
+            "local a = require('./values')\nprint(a + 1)",
+        );
+
+        assert!(
+            out.contains("local a = __DARKLUA_BUNDLE_MODULES.load('b')[1]"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn wrap_unpacks_values_as_last_call_argument() {
+        let out = process_main(
+            &path_config("wrap"),
+            "return 1, 2",
+            "print(require('./values'))",
+        );
+
+        assert!(
+            out.contains("print(table.unpack(__DARKLUA_BUNDLE_MODULES.load('a'), 1, 2))"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn wrap_packs_values_ending_with_a_call() {
+        let out = process_main(
+            &path_config("wrap"),
+            "return 1, tostring(2)",
+            "local a, b = require('./values')",
+        );
+
+        assert!(out.contains("return table.pack(1, tostring(2))"), "{}", out);
+        assert!(
+            out.contains(
+                "table.unpack(__DARKLUA_BUNDLE_MODULES.load('c'), 1, __DARKLUA_BUNDLE_MODULES.load('c').n)"
+            ),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn wrap_early_returns() {
+        let out = process_main(
+            &path_config("wrap"),
+            "if _G.x then return 1 end\nreturn 1, 2",
+            "local a, b = require('./values')\nprint(a, b)",
+        );
+
+        insta::assert_snapshot!("wrap_early_returns", out);
+    }
+
+    #[test]
+    fn wrap_early_returns_with_different_value_counts() {
+        let out = process_main(
+            &path_config("wrap"),
+            "for _, value in _G.list do\n\tif value then return value, 1 end\nend\nreturn 1, 2, 3",
+            "local a, b = require('./values')\nprint(a, b)",
+        );
+
+        insta::assert_snapshot!("wrap_early_returns_with_different_value_counts", out);
+    }
+
+    #[test]
+    fn wrap_module_with_multiple_values_in_early_return_only() {
+        let out = process_main(
+            &path_config("wrap"),
+            "if _G.x then return 1, 2 end\nreturn 1",
+            "local a = require('./values')\nprint(a)",
+        );
+
+        insta::assert_snapshot!("wrap_module_with_multiple_values_in_early_return_only", out);
+    }
+
+    #[test]
+    fn wrap_keeps_returns_of_nested_functions() {
+        let out = process_main(
+            &path_config("wrap"),
+            "local function get() return 'a', 'b' end\nreturn get, 2",
+            "local a, b = require('./values')",
+        );
+
+        insta::assert_snapshot!("wrap_keeps_returns_of_nested_functions", out);
+    }
+
+    #[test]
+    fn wrap_keeps_single_value_modules_unchanged() {
+        let out = process_main(
+            &path_config("wrap"),
+            "return 1",
+            "local a, b = require('./values')",
+        );
+
+        assert!(
+            out.contains("local a, b = __DARKLUA_BUNDLE_MODULES.load('c')"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn wrap_unpacks_values_in_roblox_mode() {
+        let resources = memory_resources!(
+            "src/values.lua" => "return 1, 2",
+            "src/init.lua" => "local a, b = require(script.values)",
+            "default.project.json" => ROJO_SOURCEMAP,
+            ".darklua.json" => "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"multi_return_modules\": \"wrap\" } }",
+        );
+
+        process(
+            &resources,
+            Options::new("src/init.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let out = resources.get("out.lua").unwrap();
+
+        assert!(
+            out.contains("local a, b = table.unpack(__DARKLUA_BUNDLE_MODULES.load('c'), 1, 2)"),
+            "{}",
+            out
+        );
+    }
+}
+
+mod module_varargs {
+    use super::*;
+
+    fn config(generator: &str, module_varargs: &str) -> String {
+        format!(
+            "{{ \"rules\": [], \"generator\": \"{}\", \"bundle\": {{ \"require_mode\": \"path\", \"module_varargs\": \"{}\" }} }}",
+            generator, module_varargs
+        )
+    }
+
+    fn process_main(config: &str, value_code: &str) -> Result<String, String> {
+        let resources = memory_resources!(
+            "src/value.lua" => value_code,
+            "src/main.lua" => "local value = require('./value')\nprint(value, ...)",
+            ".darklua.json" => config,
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .map_err(|errors| errors.first().unwrap().to_string())?;
+
+        Ok(resources.get("out.lua").unwrap())
+    }
+
+    #[test]
+    fn top_level_varargs_in_module_errors_by_default() {
+        let message = process_main(
+            &config("retain_lines", "error"),
+            "local count = select('#', ...)\n\nreturn count",
+        )
+        .unwrap_err();
+
+        assert!(message.contains("`src/value.lua`"), "{}", message);
+        assert!(
+            message.contains("`...` is used outside of a function at line 1"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn varargs_in_module_function_are_bundled() {
+        let out = process_main(
+            &config("readable", "error"),
+            "return function(...)\n    return select('#', ...)\nend",
+        )
+        .unwrap();
+
+        assert!(out.contains("return select('#', ...)"), "{}", out);
+    }
+
+    #[test]
+    fn varargs_in_entry_are_bundled() {
+        let out = process_main(&config("readable", "error"), "return true").unwrap();
+
+        assert!(out.contains("print(value, ...)"), "{}", out);
+    }
+
+    #[test]
+    fn nil_mode_declares_variadic_module_function() {
+        let out = process_main(
+            &config("readable", "nil"),
+            "local count = select('#', ...)\n\nreturn count",
+        )
+        .unwrap();
+
+        insta::assert_snapshot!("nil_mode_declares_variadic_module_function", out);
+    }
+}
+
+mod cross_module_types {
+    use super::*;
+
+    use darklua_core::Parser;
+
+    const SIGNAL_MODULE: &str = concat!(
+        "export type Connection<T> = { disconnect: (self: Connection<T>) -> (), value: T }\n",
+        "return {}\n",
+    );
+
+    fn process_main(config: &str, handler_code: &str) -> String {
+        let resources = memory_resources!(
+            "src/signal.lua" => SIGNAL_MODULE,
+            "src/handler.lua" => handler_code,
+            "src/main.lua" => concat!(
+                "local Handler = require('./handler')\n",
+                "export type Handler = Handler.Handler\n",
+                "local handler: Handler.Handler = Handler.new()\n",
+            ),
+            ".darklua.json" => config,
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    fn assert_parses_without_module_locals(out: &str, locals: &[&str]) {
+        Parser::default()
+            .preserve_tokens()
+            .parse(out)
+            .unwrap_or_else(|error| panic!("unable to parse bundle: {:?}\n{}", error, out));
+
+        for local in locals {
+            assert!(
+                !out.contains(&format!("{}.Connection", local)),
+                "type should not reference `{}`: {}",
+                local,
+                out
+            );
+        }
+    }
+
+    #[test]
+    fn type_referencing_another_module_type() {
+        let out = process_main(
+            DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+            concat!(
+                "local Signal = require('./signal')\n",
+                "export type Payload = { id: number }\n",
+                "export type Handler = Signal.Connection<Payload>\n",
+                "export type Callback<T = Signal.Connection<number>> = (T) -> ()\n",
+                "return { new = function(): Handler return nil :: any end }\n",
+            ),
+        );
+
+        insta::assert_snapshot!("type_referencing_another_module_type", out);
+        assert_parses_without_module_locals(&out, &["Signal"]);
+    }
+
+    #[test]
+    fn type_referencing_module_required_with_type_cast() {
+        let out = process_main(
+            DARKLUA_BUNDLE_ONLY_RETAIN_LINES_CONFIG,
+            concat!(
+                "local Signal = require('./signal') :: any\n",
+                "export type Handler = Signal.Connection<string>\n",
+                "return { new = function(): Handler return nil :: any end }\n",
+            ),
+        );
+
+        assert_parses_without_module_locals(&out, &["Signal"]);
+    }
+
+    #[test]
+    fn type_referencing_module_required_in_parentheses() {
+        let out = process_main(
+            DARKLUA_BUNDLE_ONLY_RETAIN_LINES_CONFIG,
+            concat!(
+                "local Signal = (require('./signal'))\n",
+                "local Other = (require('./signal') :: any)\n",
+                "export type Handler = Signal.Connection<string>\n",
+                "export type OtherHandler = Other.Connection<Handler>\n",
+                "return { new = function(): OtherHandler return nil :: any end }\n",
+            ),
+        );
+
+        assert_parses_without_module_locals(&out, &["Signal", "Other"]);
+    }
+}
+
+mod strip_types {
+    use super::*;
+
+    use full_moon::LuaVersion;
+    use sourcemap::SourceMap;
+
+    const VALUE_MODULE: &str = concat!(
+        "export type Box<T> = { value: T }\n",
+        "type Unwrap<T> = (Box<T>) -> T\n",
+        "local unwrap: Unwrap<number> = function(box)\n",
+        "    return box.value\n",
+        "end\n",
+        "return { unwrap = unwrap, answer = 42 :: number }\n",
+    );
+
+    fn config(generator: &str, sourcemap: bool) -> String {
+        format!(
+            "{{ \"rules\": [], \"generator\": \"{}\", \"bundle\": {{ \"require_mode\": \"path\", \"strip_types\": true{} }} }}",
+            generator,
+            if sourcemap {
+                ", \"sourcemap\": { \"enabled\": true, \"output_path\": \"out.lua.map\" }"
+            } else {
+                ""
+            }
+        )
+    }
+
+    fn process_main(config: &str, main_code: &str) -> Resources {
+        let resources = memory_resources!(
+            "src/value.lua" => VALUE_MODULE,
+            "src/main.lua" => main_code,
+            ".darklua.json" => config,
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources
+    }
+
+    fn assert_is_lua(code: &str) {
+        if let Err(errors) = full_moon::parse_fallible(code, LuaVersion::lua51()).into_result() {
+            panic!("bundle should be valid Lua: {:?}\n{}", errors, code);
+        }
+        assert!(!code.contains("__DARKLUA_TYPE"), "{}", code);
+    }
+
+    #[test]
+    fn bundle_with_exported_generic_types_is_valid_lua() {
+        let resources = process_main(
+            &config("readable", false),
+            concat!(
+                "local Value = require('./value')\n",
+                "export type Box = Value.Box<string>\n",
+                "local box: Box = { value = 'hello' }\n",
+                "print(Value.unwrap(box :: any))\n",
+            ),
+        );
+
+        let out = resources.get("out.lua").unwrap();
+
+        assert_is_lua(&out);
+        insta::assert_snapshot!("bundle_with_exported_generic_types_is_valid_lua", out);
+    }
+
+    #[test]
+    fn entry_without_requires_is_stripped() {
+        let resources = process_main(
+            &config("readable", false),
+            "type Value = number\nlocal value: Value = 1\nprint(value)\n",
+        );
+
+        let out = resources.get("out.lua").unwrap();
+
+        assert_is_lua(&out);
+        assert!(out.contains("local value = 1"), "{}", out);
+    }
+
+    #[test]
+    fn retain_lines_keeps_lines_of_bundled_modules() {
+        let resources = process_main(
+            &config("retain_lines", false),
+            concat!(
+                "local Value = require('./value')\n",
+                "local box: Value.Box<number> = { value = 1 }\n",
+                "print(Value.unwrap(box))\n",
+            ),
+        );
+
+        let out = resources.get("out.lua").unwrap();
+
+        assert_is_lua(&out);
+        let module_line = out
+            .lines()
+            .position(|line| line.contains("return box.value"))
+            .expect("bundle should contain the module");
+        // the stripped type declarations are not hoisted at the top of the bundle, so the
+        // module lines are not shifted
+        assert_eq!(module_line, 3, "{}", out);
+    }
+
+    #[test]
+    fn sourcemap_lines_resolve_to_bundled_module() {
+        let resources = process_main(
+            &config("retain_lines", true),
+            concat!(
+                "local Value = require('./value')\n",
+                "print(Value.unwrap({ value = 1 }))\n",
+            ),
+        );
+
+        let out = resources.get("out.lua").unwrap();
+        assert_is_lua(&out);
+
+        let map = resources
+            .get("out.lua.map")
+            .expect("sourcemap must be written");
+        let sourcemap = SourceMap::from_slice(map.as_bytes()).expect("parse sourcemap");
+
+        let generated_line = out
+            .lines()
+            .position(|line| line.contains("return box.value"))
+            .expect("bundle should contain the module") as u32;
+        let token = sourcemap
+            .lookup_token(generated_line, 0)
+            .expect("lookup token");
+
+        let source = sourcemap.get_source(token.get_src_id()).unwrap_or_default();
+        assert!(source.ends_with("src/value.lua"), "{}", source);
+        assert_eq!(token.get_src_line(), 3);
+    }
+}
+
+mod auto_require_mode {
+    use super::*;
+
+    use darklua_core::rules::{DetectedRequireMode, WarningCode};
+
+    const CONFIG: &str = "{ \"rules\": [], \"generator\": \"retain_lines\", \"bundle\": { \"require_mode\": { \"name\": \"auto\", \"roblox\": { \"rojo_sourcemap\": \"default.project.json\" } } } }";
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            { "name": "value", "className": "ModuleScript", "filePaths": ["src/value.lua"] },
+            { "name": "other", "className": "ModuleScript", "filePaths": ["src/other.lua"] }
+        ]
+    }"#;
+
+    fn process_init(init_code: &str) -> (Resources, darklua_core::WorkerTree) {
+        let resources = memory_resources!(
+            "src/value.lua" => "return 'value'",
+            "src/other.lua" => "return 'other'",
+            "src/init.lua" => init_code,
+            "default.project.json" => ROJO_SOURCEMAP,
+            ".darklua.json" => CONFIG,
+        );
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src/init.lua").with_output("out.lua"),
+        )
+        .unwrap();
+
+        (resources, worker_tree)
+    }
+
+    fn detected_modes(worker_tree: &darklua_core::WorkerTree) -> Vec<DetectedRequireMode> {
+        worker_tree
+            .collect_detected_require_modes()
+            .into_iter()
+            .flat_map(|(_, modes)| modes.iter().copied())
+            .collect()
+    }
+
+    #[test]
+    fn path_requires_are_bundled_with_path_mode() {
+        let (resources, worker_tree) =
+            process_init("local value = require('./value')\nlocal other = require('./other')");
+
+        pretty_assertions::assert_eq!(
+            detected_modes(&worker_tree),
+            vec![DetectedRequireMode::Path]
+        );
+        assert!(worker_tree.collect_warnings().is_empty());
+        worker_tree.result().unwrap();
+
+        let out = resources.get("out.lua").unwrap();
+        assert!(!out.contains("require"), "{}", out);
+    }
+
+    #[test]
+    fn roblox_requires_are_bundled_with_roblox_mode() {
+        let (resources, worker_tree) = process_init(
+            "local value = require(script.value)\nlocal other = require(script:FindFirstChild('other'))",
+        );
+
+        pretty_assertions::assert_eq!(
+            detected_modes(&worker_tree),
+            vec![DetectedRequireMode::Roblox]
+        );
+        assert!(worker_tree.collect_warnings().is_empty());
+        worker_tree.result().unwrap();
+
+        let out = resources.get("out.lua").unwrap();
+        assert!(!out.contains("require"), "{}", out);
+    }
+
+    #[test]
+    fn mixed_requires_use_most_frequent_mode_and_warn_about_others() {
+        let (resources, worker_tree) = process_init(concat!(
+            "local Root = script\n",
+            "local value = require(Root.value)\n",
+            "local other = require(script.other)\n",
+            "local extra = require('./value')\n",
+        ));
+
+        pretty_assertions::assert_eq!(
+            detected_modes(&worker_tree),
+            vec![DetectedRequireMode::Roblox]
+        );
+
+        let warnings: Vec<_> = worker_tree
+            .collect_warnings()
+            .into_iter()
+            .flat_map(|(_, warnings)| warnings.iter())
+            .filter(|warning| warning.code() == WarningCode::MixedRequireModes)
+            .map(|warning| (warning.message().to_owned(), warning.line()))
+            .collect();
+        pretty_assertions::assert_eq!(
+            warnings,
+            vec![(
+                "require call does not match the detected `roblox` require mode (1 path requires, 2 Roblox requires)".to_owned(),
+                Some(4)
+            )]
+        );
+        worker_tree.result().unwrap();
+
+        let out = resources.get("out.lua").unwrap();
+        assert!(out.contains("require('./value')"), "{}", out);
+    }
+}
+
+mod generated_identifiers {
+    use super::*;
+
+    fn process_main(main_code: &str) -> String {
+        let resources = memory_resources!(
+            "src/value.lua" => "return 'value'",
+            "src/other.lua" => "return 'other'",
+            "src/main.lua" => main_code,
+            ".darklua.json" => DARKLUA_BUNDLE_ONLY_READABLE_CONFIG,
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    #[test]
+    fn modules_identifier_does_not_collide_with_a_local() {
+        let out = process_main(concat!(
+            "local __DARKLUA_BUNDLE_MODULES = 'user'\n",
+            "local value = require('./value')\n",
+            "print(__DARKLUA_BUNDLE_MODULES, value)",
+        ));
+
+        insta::assert_snapshot!("modules_identifier_does_not_collide_with_a_local", out);
+    }
+
+    #[test]
+    fn modules_identifier_does_not_collide_with_prefixed_names() {
+        let out = process_main(concat!(
+            "local __DARKLUA_BUNDLE_MODULES = 'user'\n",
+            "__DARKLUA_BUNDLE_MODULES_2 = 'global'\n",
+            "local value = require('./value')\n",
+            "print(__DARKLUA_BUNDLE_MODULES, __DARKLUA_BUNDLE_MODULES_2, value)",
+        ));
+
+        assert!(
+            out.contains("local value = __DARKLUA_BUNDLE_MODULES_3.load('a')"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("print(__DARKLUA_BUNDLE_MODULES, __DARKLUA_BUNDLE_MODULES_2, value)"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn module_names_avoid_locals_named_a_and_b() {
+        let out = process_main(concat!(
+            "local a = require('./value')\n",
+            "local b = require('./other')\n",
+            "print(a, b)",
+        ));
+
+        assert!(
+            out.contains("local a = __DARKLUA_BUNDLE_MODULES.load('c')"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("local b = __DARKLUA_BUNDLE_MODULES.load('d')"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn bundle_is_deterministic() {
+        let code = concat!(
+            "local a = require('./value')\n",
+            "local __DARKLUA_BUNDLE_MODULES = require('./other')\n",
+            "print(a, __DARKLUA_BUNDLE_MODULES)",
+        );
+
+        pretty_assertions::assert_eq!(process_main(code), process_main(code));
+    }
+
+    #[test]
+    fn hoisted_requires_do_not_collide_with_prefixed_names() {
+        const CONFIG: &str = "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"excludes\": [\"**/value.lua\"], \"hoist_excluded_requires\": true } }";
+
+        const ROJO_SOURCEMAP: &str = r#"{
+            "name": "Project",
+            "className": "ModuleScript",
+            "filePaths": ["src/init.lua", "default.project.json"],
+            "children": [
+                { "name": "value", "className": "ModuleScript", "filePaths": ["src/value.lua"] }
+            ]
+        }"#;
+
+        let resources = memory_resources!(
+            "src/value.lua" => "return true",
+            "src/init.lua" => "local __DARKLUA_EXT_a = 1\nlocal value = require(script.value)\nprint(__DARKLUA_EXT_a, value)",
+            "default.project.json" => ROJO_SOURCEMAP,
+            ".darklua.json" => CONFIG,
+        );
+
+        process(
+            &resources,
+            Options::new("src/init.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let out = resources.get("out.lua").unwrap();
+
+        assert!(
+            out.contains("local __DARKLUA_EXT_b = require(game.value)"),
+            "{}",
+            out
+        );
+        assert!(out.contains("local value = __DARKLUA_EXT_b"), "{}", out);
+    }
+
+    #[test]
+    fn hoisted_requires_do_not_collide_with_names_of_inlined_modules() {
+        const CONFIG: &str = "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"excludes\": [\"**/value.lua\"], \"hoist_excluded_requires\": true } }";
+
+        const ROJO_SOURCEMAP: &str = r#"{
+            "name": "Project",
+            "className": "ModuleScript",
+            "filePaths": ["src/init.lua", "default.project.json"],
+            "children": [
+                { "name": "module", "className": "ModuleScript", "filePaths": ["src/module.lua"] },
+                { "name": "value", "className": "ModuleScript", "filePaths": ["src/value.lua"] }
+            ]
+        }"#;
+
+        let resources = memory_resources!(
+            "src/value.lua" => "return true",
+            "src/module.lua" => "local value = require(script.Parent.value)\nreturn __DARKLUA_EXT_a or value",
+            "src/init.lua" => "local module = require(script.module)\nprint(module)",
+            "default.project.json" => ROJO_SOURCEMAP,
+            ".darklua.json" => CONFIG,
+        );
+
+        process(
+            &resources,
+            Options::new("src/init.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let out = resources.get("out.lua").unwrap();
+
+        assert!(
+            out.contains("local __DARKLUA_EXT_b = require(game.value)"),
+            "{}",
+            out
+        );
+        assert!(out.contains("return __DARKLUA_EXT_a or value"), "{}", out);
+    }
+}
+
+mod case_sensitive_paths {
+    use super::*;
+
+    fn bundle_config(case_sensitive_paths: bool) -> String {
+        format!(
+            "{{ \"rules\": [], \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": \"path\", \"case_sensitive_paths\": {} }} }}",
+            case_sensitive_paths
+        )
+    }
+
+    fn process_main(resources: &Resources) -> String {
+        process(
+            resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    #[test]
+    fn require_with_different_casing_bundles_module_once() {
+        let resources = memory_resources!(
+            "src/utils.lua" => "print('load utils') return 1",
+            "src/main.lua" => "local a = require('./Utils.lua')\nlocal b = require('./utils.lua')\nprint(a + b)",
+            ".darklua.json" => bundle_config(false),
+        )
+        .case_insensitive();
+
+        let out = process_main(&resources);
+
+        assert_eq!(out.matches("print('load utils')").count(), 1, "{}", out);
+    }
+
+    #[test]
+    fn require_with_different_casing_when_case_sensitive_bundles_module_twice() {
+        let resources = memory_resources!(
+            "src/utils.lua" => "print('load utils') return 1",
+            "src/main.lua" => "local a = require('./Utils.lua')\nlocal b = require('./utils.lua')\nprint(a + b)",
+            ".darklua.json" => bundle_config(true),
+        )
+        .case_insensitive();
+
+        let out = process_main(&resources);
+
+        assert_eq!(out.matches("print('load utils')").count(), 2, "{}", out);
+    }
+
+    #[test]
+    fn sourcemap_sources_use_casing_on_disk() {
+        let resources = memory_resources!(
+            "src/utils.lua" => "return true\n",
+            "src/main.lua" => "local a = require('./Utils.lua')\nlocal b = require('./utils.lua')\n",
+            ".darklua.json" => "{ \"rules\": [], \"generator\": \"retain_lines\", \"bundle\": { \"require_mode\": \"path\", \"case_sensitive_paths\": false, \"sourcemap\": { \"enabled\": true, \"output_path\": \"out.lua.map\" } } }",
+        )
+        .case_insensitive();
+
+        process_main(&resources);
+
+        let map: serde_json::Value =
+            serde_json::from_str(&resources.get("out.lua.map").unwrap()).unwrap();
+        let sources: Vec<_> = map["sources"]
+            .as_array()
+            .expect("sources must be an array")
+            .iter()
+            .filter_map(|source| source.as_str())
+            .collect();
+
+        assert_eq!(sources.len(), 2, "{:?}", sources);
+        assert!(
+            sources.iter().any(|source| source.ends_with("src/utils.lua")),
+            "{:?}",
+            sources
+        );
+        assert!(
+            !sources.iter().any(|source| source.ends_with("Utils.lua")),
+            "{:?}",
+            sources
+        );
+    }
+
+    #[test]
+    fn roblox_mode_matches_sourcemap_file_with_different_casing() {
+        const ROJO_SOURCEMAP: &str = r#"{
+            "name": "Project",
+            "className": "ModuleScript",
+            "filePaths": ["src/Init.lua", "default.project.json"],
+            "children": [
+                { "name": "value", "className": "ModuleScript", "filePaths": ["src/Value.lua"] }
+            ]
+        }"#;
+
+        let resources = memory_resources!(
+            "src/value.lua" => "return 'value'",
+            "src/init.lua" => "local value = require(script.value)\nprint(value)",
+            "default.project.json" => ROJO_SOURCEMAP,
+            ".darklua.json" => "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"case_sensitive_paths\": false } }",
+        )
+        .case_insensitive();
+
+        process(
+            &resources,
+            Options::new("src/init.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let out = resources.get("out.lua").unwrap();
+
+        assert!(out.contains("return 'value'"), "{}", out);
+        assert!(!out.contains("require(script.value)"), "{}", out);
+    }
+}
+
+mod quote_style {
+    use super::*;
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            {
+                "name": "Shared",
+                "className": "Folder",
+                "children": [
+                    {
+                        "name": "excluded value",
+                        "className": "ModuleScript",
+                        "filePaths": ["src/Shared/excluded value.lua"]
+                    },
+                    {
+                        "name": "value",
+                        "className": "ModuleScript",
+                        "filePaths": ["src/Shared/value.lua"]
+                    }
+                ]
+            }
+        ]
+    }"#;
+
+    fn path_bundle_config(quote_style: &str) -> String {
+        format!(
+            "{{ \"rules\": [], \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": \"path\", \"quote_style\": \"{}\" }} }}",
+            quote_style
+        )
+    }
+
+    fn roblox_bundle_config(quote_style: &str) -> String {
+        format!(
+            "{{ \"rules\": [], \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": {{ \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }}, \"excludes\": [\"**/excluded value.lua\"], \"quote_style\": \"{}\" }} }}",
+            quote_style
+        )
+    }
+
+    fn process_main(resources: &Resources, main_file: &str) -> String {
+        process(resources, Options::new(main_file).with_output("out.lua"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    fn process_path_main(quote_style: &str) -> String {
+        let resources = memory_resources!(
+            "src/value.lua" => "return 'value'",
+            "src/main.lua" => "local value = require('./value')\nprint(value)",
+            ".darklua.json" => path_bundle_config(quote_style),
+        );
+
+        process_main(&resources, "src/main.lua")
+    }
+
+    fn process_roblox_main(quote_style: &str) -> String {
+        let resources = memory_resources!(
+            "src/Shared/value.lua" => "return 'value'",
+            "src/Shared/excluded value.lua" => "return 'excluded'",
+            "src/init.lua" => concat!(
+                "local value = require(script.Shared.value)\n",
+                "local excluded = require(script.Shared['excluded value'])\n",
+                "return { value = value, excluded = excluded }",
+            ),
+            "default.project.json" => ROJO_SOURCEMAP,
+            ".darklua.json" => roblox_bundle_config(quote_style),
+        );
+
+        process_main(&resources, "src/init.lua")
+    }
+
+    #[test]
+    fn path_bundle_with_single_quote_style() {
+        insta::assert_snapshot!(
+            "path_bundle_with_single_quote_style",
+            process_path_main("single")
+        );
+    }
+
+    #[test]
+    fn path_bundle_with_double_quote_style() {
+        insta::assert_snapshot!(
+            "path_bundle_with_double_quote_style",
+            process_path_main("double")
+        );
+    }
+
+    #[test]
+    fn roblox_bundle_with_single_quote_style() {
+        insta::assert_snapshot!(
+            "roblox_bundle_with_single_quote_style",
+            process_roblox_main("single")
+        );
+    }
+
+    #[test]
+    fn roblox_bundle_with_double_quote_style() {
+        insta::assert_snapshot!(
+            "roblox_bundle_with_double_quote_style",
+            process_roblox_main("double")
+        );
+    }
+
+    #[test]
+    fn path_bundle_with_preserve_quote_style() {
+        let resources = memory_resources!(
+            "src/value.lua" => "return 'value'",
+            "src/main.lua" => "local value = require(\"./value\")\nprint(value)",
+            ".darklua.json" => "{ \"rules\": [], \"generator\": \"retain_lines\", \"bundle\": { \"require_mode\": \"path\", \"quote_style\": \"preserve\" } }",
+        );
+
+        let out = process_main(&resources, "src/main.lua");
+
+        assert!(
+            out.contains("__DARKLUA_BUNDLE_MODULES.load(\"a\")"),
+            "{}",
+            out
+        );
+    }
+}
+
+mod lookup_directories {
+    use super::*;
+
+    const LOOKUP_DIRECTORIES_CONFIG: &str = "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"path\", \"lookup_directories\": [\"lune_modules\"] } } }";
+
+    fn process_main(resources: &Resources, main_file: &str) -> String {
+        process(resources, Options::new(main_file).with_output("out.lua"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    #[test]
+    fn require_module_from_lookup_directory_in_parent() {
+        let resources = memory_resources!(
+            "lune_modules/utils.lua" => "return 'outer utils'",
+            "src/main.lua" => "local utils = require('utils')\nprint(utils)",
+            ".darklua.json" => LOOKUP_DIRECTORIES_CONFIG,
+        );
+
+        let out = process_main(&resources, "src/main.lua");
+
+        assert!(out.contains("return 'outer utils'"), "{}", out);
+    }
+
+    #[test]
+    fn require_module_folder_from_lookup_directory() {
+        let resources = memory_resources!(
+            "lune_modules/lune-utils/init.luau" => "return 'utils'",
+            "src/main.lua" => "local utils = require('lune-utils')\nprint(utils)",
+            ".darklua.json" => LOOKUP_DIRECTORIES_CONFIG,
+        );
+
+        let out = process_main(&resources, "src/main.lua");
+
+        assert!(out.contains("return 'utils'"), "{}", out);
+    }
+
+    #[test]
+    fn nested_lookup_directory_shadows_outer_one() {
+        let resources = memory_resources!(
+            "lune_modules/utils.lua" => "return 'outer utils'",
+            "src/lune_modules/utils.lua" => "return 'inner utils'",
+            "src/main.lua" => "local utils = require('utils')\nprint(utils)",
+            ".darklua.json" => LOOKUP_DIRECTORIES_CONFIG,
+        );
+
+        let out = process_main(&resources, "src/main.lua");
+
+        assert!(out.contains("return 'inner utils'"), "{}", out);
+        assert!(!out.contains("return 'outer utils'"), "{}", out);
+    }
+
+    #[test]
+    fn nested_lookup_directory_is_not_visible_from_parent() {
+        let resources = memory_resources!(
+            "lune_modules/utils.lua" => "return 'outer utils'",
+            "src/lune_modules/utils.lua" => "return 'inner utils'",
+            "main.lua" => "local utils = require('utils')\nprint(utils)",
+            ".darklua.json" => LOOKUP_DIRECTORIES_CONFIG,
+        );
+
+        let out = process_main(&resources, "main.lua");
+
+        assert!(out.contains("return 'outer utils'"), "{}", out);
+        assert!(!out.contains("return 'inner utils'"), "{}", out);
+    }
+
+    #[test]
+    fn lookup_stops_at_configuration_location() {
+        let resources = memory_resources!(
+            "lune_modules/utils.lua" => "return 'outer utils'",
+            "project/src/main.lua" => "local utils = require('utils')\nprint(utils)",
+            "project/.darklua.json" => LOOKUP_DIRECTORIES_CONFIG,
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("project/src/main.lua")
+                .with_configuration_at("project/.darklua.json")
+                .with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        let error_display: Vec<_> = errors.into_iter().map(|err| err.to_string()).collect();
+
+        pretty_assertions::assert_eq!(
+            error_display,
+            vec![concat!(
+                "error processing `project/src/main.lua` (bundler): unable to require resource ",
+                "at `utils`: unknown source name `utils` and no module found in lookup ",
+                "directories (`lune_modules`)"
+            )]
+        );
+    }
+}
+
+mod file_not_in_sourcemap {
+    use super::*;
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            {
+                "name": "value",
+                "className": "ModuleScript",
+                "filePaths": ["src/value.lua"]
+            },
+            {
+                "name": "Client",
+                "className": "Folder",
+                "children": [
+                    {
+                        "name": "main",
+                        "className": "ModuleScript",
+                        "filePaths": ["src/client/main.lua"]
+                    }
+                ]
+            }
+        ]
+    }"#;
+
+    fn bundle_config(warnings_as_errors: bool) -> String {
+        format!(
+            "{{ \"rules\": [], \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": {{ \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }} }}{} }}",
+            if warnings_as_errors {
+                ", \"warnings_as_errors\": [\"file_not_in_sourcemap\"]"
+            } else {
+                ""
+            }
+        )
+    }
+
+    fn resources(warnings_as_errors: bool) -> Resources {
+        memory_resources!(
+            "src/value.lua" => "return true",
+            "src/client/main.lua" => "return nil",
+            "src/main.lua" => concat!(
+                "local value = require(script.Parent.value)\n",
+                "local other = require(script.Parent.other)\n",
+                "return value",
+            ),
+            "default.project.json" => ROJO_SOURCEMAP,
+            ".darklua.json" => bundle_config(warnings_as_errors),
+        )
+    }
+
+    #[test]
+    fn reports_a_single_warning_for_the_file() {
+        let resources = resources(false);
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap();
+
+        let warnings: Vec<_> = worker_tree
+            .collect_warnings()
+            .into_iter()
+            .flat_map(|(_, warnings)| warnings)
+            .map(|warning| format!("[{}] {}", warning.code(), warning))
+            .collect();
+
+        insta::assert_snapshot!("file_not_in_sourcemap_warnings", warnings.join("\n"));
+    }
+
+    #[test]
+    fn errors_when_configured_as_error() {
+        let resources = resources(true);
+
+        let errors = process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        let error_display: Vec<_> = errors.iter().map(ToString::to_string).collect();
+
+        insta::assert_snapshot!("file_not_in_sourcemap_errors", error_display.join("\n"));
+    }
+}
+
+mod exclude_rewrites {
+    use super::*;
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Game",
+        "className": "DataModel",
+        "children": [
+            {
+                "name": "ReplicatedStorage",
+                "className": "ReplicatedStorage",
+                "children": [
+                    {
+                        "name": "main",
+                        "className": "ModuleScript",
+                        "filePaths": ["src/main.lua"]
+                    },
+                    {
+                        "name": "Packages",
+                        "className": "Folder",
+                        "children": [
+                            {
+                                "name": "Promise",
+                                "className": "ModuleScript",
+                                "filePaths": ["Packages/Promise/init.lua"]
+                            },
+                            {
+                                "name": "Signal",
+                                "className": "ModuleScript",
+                                "filePaths": ["Packages/Signal.lua"]
+                            }
+                        ]
+                    },
+                    {
+                        "name": "value",
+                        "className": "ModuleScript",
+                        "filePaths": ["src/value.lua"]
+                    }
+                ]
+            }
+        ]
+    }"#;
+
+    fn bundle(exclude_rewrites: &str) -> Result<String, Vec<String>> {
+        let resources = memory_resources!(
+            "Packages/Promise/init.lua" => "return {}",
+            "Packages/Signal.lua" => "return {}",
+            "src/value.lua" => "return true",
+            "src/main.lua" => concat!(
+                "local Promise = require(script.Parent.Packages.Promise)\n",
+                "local Signal = require(script.Parent.Packages.Signal)\n",
+                "local value = require(script.Parent.value)\n",
+            ),
+            "default.project.json" => ROJO_SOURCEMAP,
+            ".darklua.json" => format!(
+                "{{ \"rules\": [], \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": {{ \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }}, \"excludes\": [\"**/Packages/**\", \"**/value.lua\"], \"exclude_rewrites\": {} }} }}",
+                exclude_rewrites
+            ),
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .map_err(|errors| errors.iter().map(ToString::to_string).collect::<Vec<_>>())?;
+
+        Ok(resources.get("out.lua").unwrap())
+    }
+
+    #[test]
+    fn rewrite_with_module_name() {
+        insta::assert_snapshot!(
+            "exclude_rewrites_with_module_name",
+            bundle(r#"[{ "pattern": "**/Packages/**", "target": "game.ReplicatedStorage.RuntimePackages.{name}" }]"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn first_matching_rewrite_is_used() {
+        insta::assert_snapshot!(
+            "exclude_rewrites_first_matching_rewrite",
+            bundle(r#"[
+                { "pattern": "**/Signal.lua", "target": "game.ReplicatedStorage.Shared.Signal" },
+                { "pattern": "**/Packages/**", "target": "game.ReplicatedStorage.RuntimePackages[\"{name}\"]" }
+            ]"#)
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn rewrite_to_script_path() {
+        insta::assert_snapshot!(
+            "exclude_rewrites_to_script_path",
+            bundle(r#"[{ "pattern": "**/Packages/**", "target": "script:FindFirstAncestor(\"Game\").Packages.{name}" }]"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_target_errors() {
+        insta::assert_snapshot!(
+            "exclude_rewrites_invalid_target_errors",
+            bundle(r#"[{ "pattern": "**/Signal.lua", "target": "ReplicatedStorage.{name}" }]"#)
+                .unwrap_err()
+                .join("\n")
+        );
+    }
+}
+
+mod rojo_project_file {
+    use super::*;
+
+    const ROJO_PROJECT: &str = r#"{
+        "name": "Project",
+        "tree": {
+            "$className": "DataModel",
+            "ReplicatedStorage": {
+                "Shared": { "$path": "src/shared" }
+            },
+            "ServerScriptService": {
+                "Server": { "$path": "src/server" }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn bundle_with_project_file_as_sourcemap() {
+        let resources = memory_resources!(
+            "src/shared/init.lua" => "return { value = require(script.value) }",
+            "src/shared/value.lua" => "return true",
+            "src/server/main.server.lua" => "local shared = require(game:GetService('ReplicatedStorage').Shared)\nprint(shared.value)",
+            "default.project.json" => ROJO_PROJECT,
+            ".darklua.json" => "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" } } }",
+        );
+
+        process(
+            &resources,
+            Options::new("src/server/main.server.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        insta::assert_snapshot!(
+            "bundle_with_project_file_as_sourcemap",
+            resources.get("out.lua").unwrap()
+        );
+    }
+}
+
+#[cfg(unix)]
+mod rojo_sourcemap_command {
+    use super::*;
+
+    use std::fs;
+    use std::path::Path;
+
+    use tempfile::{tempdir_in, TempDir};
+
+    const ROJO_SOURCEMAP: &str = r#"{"name":"Project","className":"ModuleScript","filePaths":["src/init.lua"],"children":[{"name":"value","className":"ModuleScript","filePaths":["src/value.lua"]}]}"#;
+
+    fn bundle_config(command: &str) -> String {
+        format!(
+            "{{ \"rules\": [], \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": {{ \"name\": \"roblox\", \"rojo_sourcemap\": \"sourcemap.json\", \"rojo_sourcemap_command\": [\"sh\", \"-c\", {:?}] }} }} }}",
+            command
+        )
+    }
+
+    fn write_project(command: &str) -> TempDir {
+        let directory =
+            tempdir_in(env!("CARGO_TARGET_TMPDIR")).expect("unable to create temporary directory");
+        let root = directory.path();
+
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/value.lua"), "return true").unwrap();
+        fs::write(
+            root.join("src/init.lua"),
+            "local value = require(script.value)\nreturn value",
+        )
+        .unwrap();
+        fs::write(root.join(".darklua.json"), bundle_config(command)).unwrap();
+
+        directory
+    }
+
+    fn bundle(resources: &Resources, root: &Path) -> Result<String, String> {
+        process(
+            resources,
+            Options::new(root.join("src/init.lua"))
+                .with_output(root.join("out.lua"))
+                .with_configuration_at(root.join(".darklua.json")),
+        )
+        .unwrap()
+        .result()
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+
+        Ok(resources.get(root.join("out.lua")).unwrap())
+    }
+
+    #[test]
+    fn generates_missing_sourcemap() {
+        let directory = write_project(&format!("echo '{}' > sourcemap.json", ROJO_SOURCEMAP));
+        let root = directory.path();
+
+        bundle(&Resources::from_file_system(), root).unwrap();
+
+        assert!(root.join("sourcemap.json").is_file());
+    }
+
+    #[test]
+    fn skips_command_when_sourcemap_is_up_to_date() {
+        let directory = write_project("touch command-ran");
+        let root = directory.path();
+        fs::write(root.join("sourcemap.json"), ROJO_SOURCEMAP).unwrap();
+
+        bundle(&Resources::from_file_system(), root).unwrap();
+
+        assert!(!root.join("command-ran").exists());
+    }
+
+    #[test]
+    fn failing_command_errors_with_its_output() {
+        let directory = write_project("echo 'rojo project not found' >&2; exit 1");
+        let root = directory.path();
+
+        let error = bundle(&Resources::from_file_system(), root).unwrap_err();
+
+        assert!(
+            error.contains("rojo project not found"),
+            "unexpected error: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn command_is_skipped_for_memory_resources() {
+        let resources = memory_resources!(
+            "src/value.lua" => "return true",
+            "src/init.lua" => "local value = require(script.value)\nreturn value",
+            "sourcemap.json" => ROJO_SOURCEMAP,
+            ".darklua.json" => bundle_config("exit 1"),
+        );
+
+        bundle(&resources, Path::new("")).unwrap();
+    }
+}
+
+mod tree_shake_lazy_requires {
+    use super::*;
+
+    const MAIN_WITH_DEBUG_HELPER: &str = concat!(
+        "local value = require('./value')\n",
+        "local function debugDump()\n",
+        "    local Heavy = require('./heavy')\n",
+        "    print(Heavy.dump(value))\n",
+        "end\n",
+        "return value\n",
+    );
+
+    fn bundle(main: &str, rules: &str, tree_shake: bool) -> String {
+        let resources = memory_resources!(
+            "src/value.lua" => "return true",
+            "src/heavy.lua" => "return { dump = function(value) return tostring(value) end }",
+            "src/main.lua" => main,
+            ".darklua.json" => format!(
+                "{{ \"rules\": {}, \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": \"path\", \"tree_shake_lazy_requires\": {} }} }}",
+                rules, tree_shake
+            ),
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    #[test]
+    fn drop_module_required_in_never_called_function() {
+        insta::assert_snapshot!(
+            "tree_shake_lazy_requires_never_called_function",
+            bundle(MAIN_WITH_DEBUG_HELPER, "[\"remove_unused_variable\"]", true)
+        );
+    }
+
+    #[test]
+    fn keep_require_in_never_called_function_without_unused_variable_rule() {
+        let out = bundle(MAIN_WITH_DEBUG_HELPER, "[]", true);
+
+        assert!(out.contains("require('./heavy')"), "{}", out);
+    }
+
+    #[test]
+    fn bundle_module_required_in_never_called_function_when_disabled() {
+        let out = bundle(
+            MAIN_WITH_DEBUG_HELPER,
+            "[\"remove_unused_variable\"]",
+            false,
+        );
+
+        assert!(out.contains("dump = function"), "{}", out);
+        assert!(!out.contains("require("), "{}", out);
+    }
+
+    #[test]
+    fn bundle_module_required_in_referenced_function() {
+        let out = bundle(
+            concat!(
+                "local function getHeavy()\n",
+                "    return require('./heavy')\n",
+                "end\n",
+                "return getHeavy\n",
+            ),
+            "[]",
+            true,
+        );
+
+        assert!(out.contains("dump = function"), "{}", out);
+        assert!(!out.contains("require("), "{}", out);
+    }
+
+    #[test]
+    fn bundle_module_required_in_function_used_by_repeat_condition() {
+        let out = bundle(
+            concat!(
+                "repeat\n",
+                "    local function isDone()\n",
+                "        return require('./value')\n",
+                "    end\n",
+                "until isDone()\n",
+            ),
+            "[]",
+            true,
+        );
+
+        assert!(!out.contains("require("), "{}", out);
+    }
+
+    #[test]
+    fn drop_module_required_in_nested_function_of_never_called_function() {
+        let out = bundle(
+            concat!(
+                "local function debugDump()\n",
+                "    local function dump()\n",
+                "        return require('./heavy')\n",
+                "    end\n",
+                "    return dump()\n",
+                "end\n",
+                "return require('./value')\n",
+            ),
+            "[]",
+            true,
+        );
+
+        assert!(out.contains("require('./heavy')"), "{}", out);
+        assert!(!out.contains("dump = function"), "{}", out);
+    }
+
+    #[test]
+    fn drop_module_required_in_never_called_function_with_roblox_require_mode() {
+        let resources = memory_resources!(
+            "src/value.lua" => "return true",
+            "src/heavy.lua" => "return { dump = function(value) return tostring(value) end }",
+            "src/main.lua" => concat!(
+                "local value = require(script.Parent.value)\n",
+                "local function debugDump()\n",
+                "    print(require(script.Parent.heavy).dump(value))\n",
+                "end\n",
+                "return value\n",
+            ),
+            "default.project.json" => r#"{
+                "name": "Project",
+                "className": "Folder",
+                "children": [
+                    { "name": "main", "className": "ModuleScript", "filePaths": ["src/main.lua"] },
+                    { "name": "value", "className": "ModuleScript", "filePaths": ["src/value.lua"] },
+                    { "name": "heavy", "className": "ModuleScript", "filePaths": ["src/heavy.lua"] }
+                ]
+            }"#,
+            ".darklua.json" => "{ \"rules\": [\"remove_unused_variable\"], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" }, \"tree_shake_lazy_requires\": true } }",
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let out = resources.get("out.lua").unwrap();
+
+        assert!(!out.contains("heavy"), "{}", out);
+        assert!(!out.contains("dump"), "{}", out);
+        assert!(!out.contains("require("), "{}", out);
+    }
+}
+
+mod module_order {
+    use super::*;
+
+    fn bundle(main: &str, generator: &str) -> String {
+        let resources = memory_resources!(
+            "src/a.lua" => "return 'a'",
+            "src/b.lua" => "return 'b'",
+            "src/c/init.lua" => "return 'c'",
+            "src/main.lua" => main,
+            ".darklua.json" => format!(
+                "{{ \"rules\": [], \"generator\": \"{}\", \"bundle\": {{ \"require_mode\": \"path\", \"module_naming\": \"content_hash\", \"module_order\": \"path\" }} }}",
+                generator
+            ),
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    // the module definitions end with the first `end` that is not indented
+    fn module_definitions(bundle: &str) -> &str {
+        let end = bundle
+            .find("\nend\n")
+            .expect("unable to find module definitions");
+        &bundle[..end]
+    }
+
+    #[test]
+    fn sort_module_definitions_by_path() {
+        insta::assert_snapshot!(
+            "module_order_path",
+            bundle(
+                "local c = require('./c')\nlocal b = require('./b')\nlocal a = require('./a')\n",
+                "readable"
+            )
+        );
+    }
+
+    #[test]
+    fn module_definitions_are_stable_when_requires_are_swapped() {
+        let first = bundle(
+            "local a = require('./a')\nlocal b = require('./b')\nlocal c = require('./c')\n",
+            "readable",
+        );
+        let second = bundle(
+            "local c = require('./c')\nlocal a = require('./a')\nlocal b = require('./b')\n",
+            "readable",
+        );
+
+        pretty_assertions::assert_eq!(module_definitions(&first), module_definitions(&second));
+    }
+
+    #[test]
+    fn sort_module_definitions_by_path_with_retain_lines() {
+        insta::assert_snapshot!(
+            "module_order_path_retain_lines",
+            bundle(
+                "local b = require('./b')\n\nlocal a = require('./a')\nprint(a, b)\n",
+                "retain_lines"
+            )
+        );
+    }
+}
+
+mod identifier_conflict {
+    use super::*;
+
+    fn process_with_conflict(on_identifier_conflict: &str) -> (Resources, Result<(), String>) {
+        let resources = memory_resources!(
+            "src/value.lua" => "return 'value'",
+            "src/module.lua" => "local __DARKLUA_BUNDLE_MODULES = {}\nlocal value = require('./value')\n__DARKLUA_BUNDLE_MODULES.value = value\nreturn __DARKLUA_BUNDLE_MODULES",
+            "src/main.lua" => "local module = require('./module')\nprint(module.value)\n",
+            ".darklua.json" => format!(
+                "{{ \"rules\": [], \"generator\": \"readable\", \"bundle\": {{ \"require_mode\": \"path\", \"on_identifier_conflict\": \"{}\" }} }}",
+                on_identifier_conflict
+            ),
+        );
+
+        let result = process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .map_err(|errors| errors.into_iter().next().unwrap().to_string());
+
+        (resources, result)
+    }
+
+    #[test]
+    fn module_declaring_modules_identifier_errors() {
+        let (_, result) = process_with_conflict("error");
+
+        let error = result.unwrap_err();
+        assert!(
+            error.contains("declares a variable named `__DARKLUA_BUNDLE_MODULES`"),
+            "unexpected error: {}",
+            error
+        );
+        assert!(error.contains("module.lua"), "unexpected error: {}", error);
+    }
+
+    #[test]
+    fn module_declaring_modules_identifier_is_renamed() {
+        let (resources, result) = process_with_conflict("rename");
+
+        result.unwrap();
+
+        insta::assert_snapshot!(
+            "identifier_conflict_rename",
+            resources.get("out.lua").unwrap()
+        );
+    }
+}
+
+mod expression_requires {
+    use super::*;
+
+    const ROJO_SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "ModuleScript",
+        "filePaths": ["src/init.lua", "default.project.json"],
+        "children": [
+            { "name": "Keys", "className": "ModuleScript", "filePaths": ["src/Keys.lua"] },
+            { "name": "Thing", "className": "ModuleScript", "filePaths": ["src/Thing.lua"] }
+        ]
+    }"#;
+
+    fn bundle_path(main: &str, generator: &str) -> String {
+        let resources = memory_resources!(
+            "src/Keys.lua" => "return { Submit = 'submit' }",
+            "src/Thing.lua" => "export type Thing = typeof(require('./Keys'))\nlocal thing: Thing = require('./Keys')\nreturn thing",
+            "src/main.lua" => main,
+            ".darklua.json" => format!(
+                "{{ \"rules\": [], \"generator\": \"{}\", \"bundle\": {{ \"require_mode\": \"path\" }} }}",
+                generator
+            ),
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    fn bundle_roblox(init: &str) -> String {
+        let resources = memory_resources!(
+            "src/Keys.lua" => "return { Submit = 'submit' }",
+            "src/Thing.lua" => "export type Thing = typeof(require(script.Parent.Keys))\nreturn {}",
+            "src/init.lua" => init,
+            "default.project.json" => ROJO_SOURCEMAP,
+            ".darklua.json" => "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"default.project.json\" } } }",
+        );
+
+        process(
+            &resources,
+            Options::new("src/init.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    #[test]
+    fn require_as_table_key() {
+        let bundle = bundle_path(
+            "local handlers = { [require('./Keys')] = print }\nreturn handlers",
+            "readable",
+        );
+
+        assert!(!bundle.contains("require("), "{}", bundle);
+        assert!(
+            bundle.contains("[__DARKLUA_BUNDLE_MODULES.load('a')] = print"),
+            "{}",
+            bundle
+        );
+    }
+
+    #[test]
+    fn require_as_table_key_in_roblox_mode() {
+        let bundle =
+            bundle_roblox("local handlers = { [require(script.Keys)] = print }\nreturn handlers");
+
+        assert!(!bundle.contains("require("), "{}", bundle);
+        assert!(
+            bundle.contains("[__DARKLUA_BUNDLE_MODULES.load('a')] = print"),
+            "{}",
+            bundle
+        );
+    }
+
+    #[test]
+    fn require_in_typeof_annotation() {
+        let bundle = bundle_path(
+            "local keys: typeof(require('./Keys')) = require('./Keys')\nreturn keys",
+            "readable",
+        );
+
+        assert!(
+            bundle.contains("local keys: typeof(__DARKLUA_BUNDLE_MODULES.load('a')) = __DARKLUA_BUNDLE_MODULES.load('a')"),
+            "{}",
+            bundle
+        );
+    }
+
+    #[test]
+    fn exported_type_with_typeof_require() {
+        insta::assert_snapshot!(
+            "exported_type_with_typeof_require",
+            bundle_path(
+                "local Thing = require('./Thing')\nexport type Thing = Thing.Thing\nreturn Thing",
+                "readable"
+            )
+        );
+    }
+
+    #[test]
+    fn exported_type_with_typeof_require_retain_lines() {
+        insta::assert_snapshot!(
+            "exported_type_with_typeof_require_retain_lines",
+            bundle_path(
+                "local Thing = require('./Thing')\nexport type Thing = Thing.Thing\nreturn Thing",
+                "retain_lines"
+            )
+        );
+    }
+
+    #[test]
+    fn exported_type_with_typeof_require_in_roblox_mode() {
+        insta::assert_snapshot!(
+            "exported_type_with_typeof_require_roblox",
+            bundle_roblox("local Thing = require(script.Thing)\nexport type Thing = Thing.Thing\nreturn Thing")
+        );
+    }
+}
+
+mod roblox_filesystem_fallback {
+    use super::*;
+
+    const ROBLOX_FILESYSTEM_CONFIG: &str = "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"filesystem_fallback\": true } } }";
+
+    fn bundle(resources: &Resources, entry: &str) -> Result<String, String> {
+        resources
+            .write(".darklua.json", ROBLOX_FILESYSTEM_CONFIG)
+            .unwrap();
+
+        process(resources, Options::new(entry).with_output("out.lua"))
+            .unwrap()
+            .result()
+            .map_err(|errors| errors.into_iter().next().unwrap().to_string())?;
+
+        Ok(resources.get("out.lua").unwrap())
+    }
+
+    #[test]
+    fn init_file_requires_child_file_and_folder() {
+        let resources = memory_resources!(
+            "src/init.lua" => "local value = require(script.value)\nlocal folder = require(script.folder)\nreturn { value = value, folder = folder }",
+            "src/value.lua" => "return 'value'",
+            "src/folder/init.luau" => "return 'folder'",
+        );
+
+        insta::assert_snapshot!(
+            "roblox_filesystem_fallback_init_file",
+            bundle(&resources, "src/init.lua").unwrap()
+        );
+    }
+
+    #[test]
+    fn plain_file_requires_sibling() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local sibling = require(script.Parent.sibling)\nreturn sibling",
+            "src/sibling.lua" => "local value = require(script.Parent.folder.value)\nreturn value",
+            "src/folder/value.luau" => "return 'value'",
+        );
+
+        insta::assert_snapshot!(
+            "roblox_filesystem_fallback_plain_file",
+            bundle(&resources, "src/main.lua").unwrap()
+        );
+    }
+
+    #[test]
+    fn datamodel_require_errors() {
+        let resources = memory_resources!(
+            "src/main.lua" => "return require(game.ReplicatedStorage.value)",
+        );
+
+        let error = bundle(&resources, "src/main.lua").unwrap_err();
+
+        assert!(
+            error.contains("can only be resolved with a Rojo sourcemap"),
+            "unexpected error: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn missing_sibling_is_not_bundled() {
+        let resources = memory_resources!(
+            "src/main.lua" => "return require(script.Parent.missing)",
+        );
+
+        let out = bundle(&resources, "src/main.lua").unwrap();
+
+        assert!(!out.contains("__DARKLUA_BUNDLE_MODULES"), "{}", out);
+    }
+}
+
+mod invalid_rojo_sourcemap {
+    use super::*;
+
+    fn process_with_sourcemap(resources: &Resources, rojo_sourcemap: &str, snapshot_name: &str) {
+        resources
+            .write("src/init.lua", "return require(script.value)")
+            .unwrap();
+        resources.write("src/value.lua", "return true").unwrap();
+        resources
+            .write(
+                ".darklua.json",
+                &format!(
+                    "{{ \"rules\": [], \"bundle\": {{ \"require_mode\": {{ \"name\": \"roblox\", \"rojo_sourcemap\": \"{}\" }} }} }}",
+                    rojo_sourcemap
+                ),
+            )
+            .unwrap();
+
+        let errors = process(
+            resources,
+            Options::new("src/init.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        let error_display: Vec<_> = errors.iter().map(ToString::to_string).collect();
+
+        let mut settings = insta::Settings::clone_current();
+        settings.add_filter("\\\\", "/");
+        settings.bind(|| {
+            insta::assert_snapshot!(snapshot_name, error_display.join("\n"));
+        });
+    }
+
+    #[test]
+    fn sourcemap_pointing_to_a_directory_errors() {
+        let resources = memory_resources!(
+            "default.project/default.project.json" => "{ \"name\": \"Project\", \"tree\": { \"$path\": \"src\" } }",
+        );
+
+        process_with_sourcemap(
+            &resources,
+            "default.project",
+            "invalid_rojo_sourcemap_directory",
+        );
+    }
+
+    #[test]
+    fn sourcemap_pointing_to_a_lua_file_errors() {
+        let resources = Resources::from_memory();
+
+        process_with_sourcemap(
+            &resources,
+            "src/value.lua",
+            "invalid_rojo_sourcemap_lua_file",
+        );
+    }
+}
+
+mod rojo_sourcemap_file_paths {
+    use super::*;
+
+    #[test]
+    fn require_module_with_meta_file_listed_first() {
+        let resources = memory_resources!(
+            "src/init.lua" => "local value = require(script.value)\nreturn value",
+            "src/value.lua" => "return 'value'",
+            "src/value.meta.json" => "{ \"properties\": {} }",
+            "sourcemap.json" => r#"{
+                "name": "Project",
+                "className": "ModuleScript",
+                "filePaths": ["src/init.lua", "default.project.json"],
+                "children": [
+                    {
+                        "name": "value",
+                        "className": "ModuleScript",
+                        "filePaths": ["src/value.meta.json", "src/value.lua"]
+                    }
+                ]
+            }"#,
+            ".darklua.json" => "{ \"rules\": [], \"generator\": \"readable\", \"bundle\": { \"require_mode\": { \"name\": \"roblox\", \"rojo_sourcemap\": \"sourcemap.json\" } } }",
+        );
+
+        process(
+            &resources,
+            Options::new("src/init.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        insta::assert_snapshot!(
+            "rojo_sourcemap_meta_file_listed_first",
+            resources.get("out.lua").unwrap()
+        );
+    }
+}
+
+mod global_access_report {
+    use super::*;
+    use darklua_core::rules::WarningCode;
+
+    fn global_access_warnings(resources: &Resources) -> Vec<String> {
+        resources
+            .write(
+                ".darklua.json",
+                "{ \"rules\": [], \"generator\": \"retain_lines\", \"bundle\": { \"require_mode\": \"path\", \"report_global_access\": true } }",
+            )
+            .unwrap();
+
+        let worker_tree = process(
+            resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap();
+
+        let warnings = worker_tree
+            .collect_warnings()
+            .into_iter()
+            .flat_map(|(_, warnings)| warnings.iter())
+            .filter(|warning| warning.code() == WarningCode::GlobalAccess)
+            .map(|warning| warning.to_string().replace('\\', "/"))
+            .collect();
+
+        worker_tree.result().unwrap();
+
+        warnings
+    }
+
+    #[test]
+    fn reports_accesses_of_each_module() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local a = require('./a')\nlocal b = require('./b')\nreturn a + b",
+            "src/a.lua" => "_G.counter = 1\nreturn 1",
+            "src/b.lua" => "local _G = {}\n_G.ignored = true\nlocal value = shared.value\nreturn value",
+        );
+
+        pretty_assertions::assert_eq!(
+            global_access_warnings(&resources),
+            vec![
+                "global `_G` is written (bundling can change when globals are initialized) (`src/a.lua` at line 1)".to_owned(),
+                "global `shared` is read (bundling can change when globals are initialized) (`src/b.lua` at line 3)".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_nothing_when_disabled() {
+        let resources = memory_resources!(
+            "src/main.lua" => "_G.value = require('./a')",
+            "src/a.lua" => "return _G",
+            ".darklua.json" => "{ \"rules\": [], \"bundle\": { \"require_mode\": \"path\" } }",
+        );
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap();
+
+        assert!(worker_tree.collect_warnings().is_empty());
+        worker_tree.result().unwrap();
+    }
+}
+
+mod max_depth {
+    use super::*;
+
+    const CHAIN_LENGTH: usize = 300;
+
+    // bundling recurses for each nested require, and the default test thread stack is too
+    // small for hundreds of nested modules in debug builds
+    fn with_large_stack(test: impl FnOnce() + Send + 'static) {
+        std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(test)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    fn chain_resources(config: &str) -> Resources {
+        let resources = memory_resources!(
+            "src/main.lua" => "local value = require('./module1')\nreturn value",
+            ".darklua.json" => config,
+        );
+        for i in 1..CHAIN_LENGTH {
+            resources
+                .write(
+                    format!("src/module{}.lua", i),
+                    &format!("return require('./module{}') + 1", i + 1),
+                )
+                .unwrap();
+        }
+        resources
+            .write(format!("src/module{}.lua", CHAIN_LENGTH), "return 0")
+            .unwrap();
+        resources
+    }
+
+    fn process_chain_error(config: &str) -> String {
+        let resources = chain_resources(config);
+
+        let errors = process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        pretty_assertions::assert_eq!(errors.len(), 1);
+        errors[0].to_string()
+    }
+
+    #[test]
+    fn chain_deeper_than_max_depth_errors() {
+        let error = process_chain_error(
+            "{ rules: [], generator: 'dense', bundle: { require_mode: 'path', max_depth: 10 } }",
+        );
+
+        pretty_assertions::assert_eq!(
+            error,
+            "error processing `src/main.lua` (bundler): unable to require `src/module11.lua`: requires are nested deeper than the maximum depth of 10 (see `max_depth`), with the deepest requires being `src/module6.lua` > `src/module7.lua` > `src/module8.lua` > `src/module9.lua` > `src/module10.lua` > `src/module11.lua`"
+        );
+    }
+
+    #[test]
+    fn chain_deeper_than_default_max_depth_errors() {
+        with_large_stack(|| {
+            let error = process_chain_error(
+                "{ rules: [], generator: 'dense', bundle: { require_mode: 'path' } }",
+            );
+
+            assert!(
+                error.contains("nested deeper than the maximum depth of 200"),
+                "{}",
+                error
+            );
+        });
+    }
+
+    #[test]
+    fn chain_within_max_depth_is_bundled() {
+        with_large_stack(|| {
+            let resources = chain_resources(
+                "{ rules: [], generator: 'dense', bundle: { require_mode: 'path', max_depth: 300 } }",
+            );
+
+            process(
+                &resources,
+                Options::new("src/main.lua").with_output("out.lua"),
+            )
+            .unwrap()
+            .result()
+            .unwrap();
+
+            let code = resources.get("out.lua").unwrap();
+            assert!(code.contains("return 0"), "{}", code);
+        });
+    }
+}
+
+mod shared_data_module {
+    use super::*;
+
+    const REQUIRING_FILES: usize = 20;
+
+    fn large_json() -> String {
+        let entries: Vec<_> = (0..2000)
+            .map(|i| format!("\"entry_{0}\": {{ \"value\": {0} }}", i))
+            .collect();
+        format!("{{ \"marker_key\": true, {} }}", entries.join(", "))
+    }
+
+    #[test]
+    fn large_json_required_from_many_files_is_bundled_once() {
+        let resources = memory_resources!(
+            ".darklua.json" => "{ rules: [], generator: 'dense', bundle: { require_mode: 'path' } }",
+        );
+        resources.write("src/data.json", &large_json()).unwrap();
+
+        let mut main = String::new();
+        for i in 0..REQUIRING_FILES {
+            resources
+                .write(
+                    format!("src/user{}.lua", i),
+                    "local data = require('./data.json')\nreturn data.entry_1",
+                )
+                .unwrap();
+            main.push_str(&format!("print(require('./user{}'))\n", i));
+        }
+        resources.write("src/main.lua", &main).unwrap();
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let code = resources.get("out.lua").unwrap();
+        pretty_assertions::assert_eq!(code.matches("marker_key").count(), 1);
+        pretty_assertions::assert_eq!(code.matches("entry_1999").count(), 1);
+    }
+}
+
+mod dependency_graph {
+    use super::*;
+
+    fn process_graph(resources: &Resources) -> String {
+        process(resources, Options::new("src/main.lua").with_output("out.lua"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources.get("deps.dot").unwrap()
+    }
+
+    #[test]
+    fn writes_modules_and_requires() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local a = require('./a')\nlocal b = require('./b')\nreturn a + b",
+            "src/a.lua" => "return require('./b') + 1",
+            "src/b.lua" => "return 1",
+            ".darklua.json" => "{ rules: [], bundle: { require_mode: 'path', graph_output: 'deps.dot' } }",
+        );
+
+        pretty_assertions::assert_eq!(
+            process_graph(&resources),
+            concat!(
+                "digraph bundle {\n",
+                "    \"src/main.lua\";\n",
+                "    \"src/a.lua\";\n",
+                "    \"src/b.lua\";\n",
+                "    \"src/main.lua\" -> \"src/a.lua\";\n",
+                "    \"src/a.lua\" -> \"src/b.lua\";\n",
+                "    \"src/main.lua\" -> \"src/b.lua\";\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn excluded_requires_are_dashed() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local a = require('./a')\nreturn a",
+            "src/a.lua" => "return require('@pkg/value')",
+            ".darklua.json" => "{ rules: [], bundle: { require_mode: 'path', excludes: ['@pkg/**'], graph_output: 'deps.dot' } }",
+        );
+
+        pretty_assertions::assert_eq!(
+            process_graph(&resources),
+            concat!(
+                "digraph bundle {\n",
+                "    \"src/main.lua\";\n",
+                "    \"src/a.lua\";\n",
+                "    \"@pkg/value\" [style=dashed];\n",
+                "    \"src/main.lua\" -> \"src/a.lua\";\n",
+                "    \"src/a.lua\" -> \"@pkg/value\" [style=dashed];\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn cyclic_requires_are_colored() {
+        let resources = memory_resources!(
+            "src/main.lua" => "return require('./a')",
+            "src/a.lua" => "return require('./b')",
+            "src/b.lua" => "return require('./a')",
+            ".darklua.json" => "{ rules: [], bundle: { require_mode: 'path', graph_output: 'deps.dot' } }",
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            resources.get("deps.dot").unwrap(),
+            concat!(
+                "digraph bundle {\n",
+                "    \"src/main.lua\";\n",
+                "    \"src/a.lua\";\n",
+                "    \"src/b.lua\";\n",
+                "    \"src/main.lua\" -> \"src/a.lua\";\n",
+                "    \"src/a.lua\" -> \"src/b.lua\";\n",
+                "    \"src/b.lua\" -> \"src/a.lua\" [color=red];\n",
+                "}\n",
+            )
+        );
+    }
+}
+
+mod output_size {
+    use super::*;
+
+    fn size_resources(bundle_options: &str) -> Resources {
+        memory_resources!(
+            "src/main.lua" => "local a = require('./a')\nlocal b = require('./b')\nreturn a .. b",
+            "src/a.lua" => "return 'a long string that makes this module the largest one'",
+            "src/b.lua" => "return 'b'",
+            ".darklua.json" => &format!(
+                "{{ rules: [], generator: 'dense', bundle: {{ require_mode: 'path', {} }} }}",
+                bundle_options
+            ),
+        )
+    }
+
+    fn process_size(resources: &Resources) -> darklua_core::WorkerTree {
+        process(resources, Options::new("src/main.lua").with_output("out.lua")).unwrap()
+    }
+
+    #[test]
+    fn output_larger_than_max_output_size_errors() {
+        let resources = size_resources("max_output_size: '10B'");
+
+        let errors = process_size(&resources).result().unwrap_err();
+
+        pretty_assertions::assert_eq!(errors.len(), 1);
+        pretty_assertions::assert_eq!(
+            errors[0].to_string(),
+            concat!(
+                "generated code of `out.lua` is 502 bytes, which exceeds the maximum output size of 10 bytes\n",
+                "  largest bundled files:\n",
+                "    - `src/main.lua`: 63 bytes, 3 lines\n",
+                "    - `src/a.lua`: 61 bytes, 1 line\n",
+                "    - `src/b.lua`: 10 bytes, 1 line",
+            )
+        );
+        assert!(resources.get("out.lua").is_err());
+    }
+
+    #[test]
+    fn output_larger_than_warn_output_size_warns() {
+        let resources = size_resources("warn_output_size: 10, max_output_size: '1KB'");
+
+        let worker_tree = process_size(&resources);
+
+        let warnings: Vec<_> = worker_tree
+            .collect_warnings()
+            .into_iter()
+            .flat_map(|(_, warnings)| warnings.iter().map(|warning| warning.code().as_str()))
+            .collect();
+        pretty_assertions::assert_eq!(warnings, vec!["output_size"]);
+
+        worker_tree.result().unwrap();
+        assert!(resources.get("out.lua").is_ok());
+    }
+
+    #[test]
+    fn output_within_sizes_has_no_warnings() {
+        let resources = size_resources("warn_output_size: '1KB', max_output_size: '1KB'");
+
+        let worker_tree = process_size(&resources);
+
+        assert!(worker_tree.collect_warnings().is_empty());
+        worker_tree.result().unwrap();
+    }
+}
+
+#[test]
+fn remove_redundant_return_keeps_bundled_module_return() {
+    let resources = memory_resources!(
+        "src/main.lua" => "local value = require('./value')\nlocal function log() print(value) return end\nlog()",
+        "src/value.lua" => "print('loaded')\nreturn nil",
+        ".darklua.json" => "{ rules: ['remove_redundant_return'], generator: { name: 'dense', column_span: 1000 }, bundle: { require_mode: 'path' } }",
+    );
+
+    process(
+        &resources,
+        Options::new("src/main.lua").with_output("out.lua"),
+    )
+    .unwrap()
+    .result()
+    .unwrap();
+
+    let code = resources.get("out.lua").unwrap();
+    assert!(code.contains("print('loaded')return nil end"), "{}", code);
+    assert!(code.contains("print(value)end"), "{}", code);
+}
+
+mod metadata {
+    use super::*;
+
+    fn process_main(bundle_options: &str, generator: &str, main_code: &str) -> String {
+        let resources = memory_resources!(
+            "src/value.lua" => "return 'VALUE'",
+            "src/main.lua" => main_code,
+            ".darklua.json" => format!(
+                "{{ rules: [], generator: {}, bundle: {{ require_mode: 'path'{} }} }}",
+                generator, bundle_options
+            ),
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("out.lua").unwrap()
+    }
+
+    #[test]
+    fn default_fields() {
+        let out = process_main(
+            ", metadata_variable: '__BUILD_INFO'",
+            "{ name: 'dense', column_span: 1000 }",
+            "local value = require('./value')\nprint(value)",
+        );
+
+        assert!(out.contains("local __BUILD_INFO={time='"), "{}", out);
+        assert!(
+            out.contains(&format!(
+                "',entry='src/main.lua',modules=1,darklua='{}'}}",
+                env!("CARGO_PKG_VERSION")
+            )),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn time_field_is_an_iso_8601_timestamp() {
+        let out = process_main(
+            ", metadata_variable: '__BUILD_INFO'",
+            "{ name: 'dense', column_span: 1000 }",
+            "local value = require('./value')\nprint(value)",
+        );
+
+        let start = out
+            .find("time='")
+            .expect("bundle should contain the time field")
+            + 6;
+        let time = &out[start..start + 20];
+
+        assert!(
+            time.chars().enumerate().all(|(i, c)| match i {
+                4 | 7 => c == '-',
+                10 => c == 'T',
+                13 | 16 => c == ':',
+                19 => c == 'Z',
+                _ => c.is_ascii_digit(),
+            }),
+            "{}",
+            time
+        );
+    }
+
+    #[test]
+    fn reproducible_build_without_time() {
+        let out = process_main(
+            ", metadata_variable: '__BUILD_INFO', metadata: { time: false, darklua: false }",
+            "'readable'",
+            "local value = require('./value')\nprint(value)",
+        );
+
+        pretty_assertions::assert_eq!(
+            out,
+            process_main(
+                ", metadata_variable: '__BUILD_INFO', metadata: { time: false, darklua: false }",
+                "'readable'",
+                "local value = require('./value')\nprint(value)",
+            )
+        );
+        insta::assert_snapshot!("metadata_reproducible_build_without_time", out);
+    }
+
+    #[test]
+    fn custom_template_fields() {
+        let out = process_main(
+            ", metadata_variable: 'BUILD', metadata: { time: false, darklua: false, entry: false, modules: false, name: 'app ({modules} modules)', 'built-with': 'darklua {version}' }",
+            "{ name: 'dense', column_span: 1000 }",
+            "local value = require('./value')\nprint(value)",
+        );
+
+        assert!(
+            out.contains(&format!(
+                "local BUILD={{['built-with']='darklua {}',name='app (1 modules)'}}",
+                env!("CARGO_PKG_VERSION")
+            )),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn entry_without_modules() {
+        let out = process_main(
+            ", metadata_variable: '__BUILD_INFO', metadata: { time: false, darklua: false }",
+            "{ name: 'dense', column_span: 1000 }",
+            "print('main')",
+        );
+
+        assert!(
+            out.contains("local __BUILD_INFO={entry='src/main.lua',modules=0}"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn retain_lines_keeps_lines_of_entry() {
+        let main_code = "local value = require('./value')\n\nprint('marker', value)\n";
+        let marker_line = |out: &str| {
+            out.lines()
+                .position(|line| line.contains("'marker'"))
+                .expect("bundle should contain the marker")
+        };
+
+        let without_metadata = process_main("", "'retain_lines'", main_code);
+        let with_metadata = process_main(
+            ", metadata_variable: '__BUILD_INFO', metadata: { time: false }",
+            "'retain_lines'",
+            main_code,
+        );
+
+        assert!(with_metadata.contains("__BUILD_INFO"), "{}", with_metadata);
+        pretty_assertions::assert_eq!(
+            marker_line(&with_metadata),
+            marker_line(&without_metadata),
+            "{}",
+            with_metadata
+        );
+    }
+}
+
+mod reproducible {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "rules": [],
+        "generator": "retain_lines",
+        "reproducible": true,
+        "bundle": {
+            "require_mode": "path",
+            "metadata_variable": "__BUILD_INFO",
+            "metadata": { "built": "{modified}", "name": "app {version}" },
+            "graph_output": "deps.dot",
+            "sourcemap": { "enabled": true, "output_path": "out.lua.map" }
+        }
+    }"#;
+
+    fn process_project(root: &str, config: &str) -> Result<Vec<String>, String> {
+        let path = |file: &str| format!("{}/{}", root, file);
+        let resources = Resources::from_memory();
+        resources
+            .write(path("src/value.lua"), "return 'VALUE'\n")
+            .unwrap();
+        resources
+            .write(
+                path("src/main.lua"),
+                "local value = require('./value')\nprint(value)\n",
+            )
+            .unwrap();
+        resources.write(path(".darklua.json"), config).unwrap();
+
+        process(
+            &resources,
+            Options::new(path("src/main.lua"))
+                .with_configuration_at(path(".darklua.json"))
+                .with_output(path("out.lua")),
+        )
+        .map_err(|err| err.to_string())?
+        .result()
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+
+        Ok(["out.lua", "out.lua.map", "deps.dot"]
+            .iter()
+            .map(|file| resources.get(path(file)).unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn outputs_are_identical_from_different_locations() {
+        let first = process_project("/home/user/project", CONFIG).unwrap();
+        let second = process_project("/builds/1234/project", CONFIG).unwrap();
+
+        pretty_assertions::assert_eq!(first, second);
+        assert!(!first.iter().any(|content| content.contains("/home/user")));
+    }
+
+    #[test]
+    fn time_placeholders_are_removed() {
+        let outputs = process_project("/home/user/project", CONFIG).unwrap();
+
+        let code = &outputs[0];
+        assert!(code.contains("__BUILD_INFO"), "{}", code);
+        assert!(!code.contains("time"), "{}", code);
+        assert!(!code.contains("built"), "{}", code);
+        assert!(code.contains("entry='src/main.lua'"), "{}", code);
+    }
+
+    #[test]
+    fn source_outside_of_relative_base_is_an_error() {
+        let config = CONFIG.replace(
+            "\"output_path\": \"out.lua.map\"",
+            "\"output_path\": \"out.lua.map\", \"relative_to\": \"src/value.lua\"",
+        );
+
+        let error = process_project("/home/user/project", &config).unwrap_err();
+
+        assert!(
+            error.contains("unable to write the absolute path `/home/user/project/src/main.lua` in a sourcemap in reproducible mode"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn inject_global_value_from_environment_is_an_error() {
+        let config = CONFIG.replace(
+            "\"rules\": []",
+            "\"rules\": [{ \"rule\": \"inject_global_value\", \"identifier\": \"BUILD_HOST\", \"env\": \"DARKLUA_TEST_BUILD_HOST\" }]",
+        );
+
+        let error = process_project("/home/user/project", &config).unwrap_err();
+
+        assert!(
+            error.contains("rule `inject_global_value` reads the environment variable `DARKLUA_TEST_BUILD_HOST`, which is not allowed in reproducible mode"),
+            "{}",
+            error
+        );
+    }
+}
+
+mod retain_lines_preamble {
+    use super::*;
+    use sourcemap::SourceMap;
+
+    fn process_with_preamble(preamble: &str) -> (String, SourceMap) {
+        let resources = memory_resources!(
+            "src/value.lua" => "local value = 'VALUE'\n\nreturn value\n",
+            "src/main.lua" => "local value = require('./value')\n\nprint(value)\n",
+        );
+        resources
+            .write(
+                ".darklua.json",
+                format!(
+                    r#"{{
+                        "rules": [{{ "rule": "append_text_comment", "text": "generated file" }}],
+                        "generator": {{ "name": "retain_lines", "preamble": "{}" }},
+                        "bundle": {{
+                            "require_mode": "path",
+                            "sourcemap": {{ "enabled": true, "output_path": "out.lua.map" }}
+                        }}
+                    }}"#,
+                    preamble
+                )
+                .as_str(),
+            )
+            .unwrap();
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let map = resources.get("out.lua.map").unwrap();
+        (
+            resources.get("out.lua").unwrap(),
+            SourceMap::from_slice(map.as_bytes()).unwrap(),
+        )
+    }
+
+    fn print_source_line(code: &str, map: &SourceMap) -> u32 {
+        let line = code
+            .lines()
+            .position(|line| line.contains("print(value)"))
+            .unwrap() as u32;
+        let token = map.lookup_token(line, 0).unwrap();
+
+        assert!(token.get_source().unwrap().ends_with("src/main.lua"));
+        token.get_src_line()
+    }
+
+    #[test]
+    fn pack_preamble() {
+        let (code, _) = process_with_preamble("pack");
+
+        insta::assert_snapshot!("retain_lines_pack_preamble", code);
+    }
+
+    #[test]
+    fn expand_preamble() {
+        let (code, _) = process_with_preamble("expand");
+
+        insta::assert_snapshot!("retain_lines_expand_preamble", code);
+    }
+
+    #[test]
+    fn expand_preamble_keeps_sourcemap_source_lines() {
+        let (pack_code, pack_map) = process_with_preamble("pack");
+        let (expand_code, expand_map) = process_with_preamble("expand");
+
+        assert_eq!(
+            print_source_line(&expand_code, &expand_map),
+            print_source_line(&pack_code, &pack_map)
+        );
+    }
+}
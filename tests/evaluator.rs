@@ -0,0 +1,63 @@
+mod utils;
+
+use darklua_core::nodes::{Expression, Statement};
+use darklua_core::process::{Evaluator, LuaValue};
+use darklua_core::{process, Options, Resources};
+
+use pretty_assertions::assert_eq;
+
+use utils::{memory_resources, parse_input};
+
+fn parse_local_value(code: &str) -> Expression {
+    match parse_input(code).first_statement() {
+        Some(Statement::LocalAssign(assign)) => assign.iter_values().next().unwrap().clone(),
+        _ => panic!("expected a local assignment"),
+    }
+}
+
+#[test]
+fn fold_expression_without_a_rule() {
+    let expression = parse_local_value("local value = ((1 + 2) * 4) .. ' items'");
+
+    let evaluator = Evaluator::default();
+
+    assert!(!evaluator.has_side_effects(&expression));
+    assert_eq!(
+        evaluator.evaluate(&expression),
+        LuaValue::String(b"12 items".to_vec())
+    );
+    assert_eq!(
+        evaluator.evaluate(&expression).to_expression(),
+        Some(parse_local_value("local value = '12 items'"))
+    );
+}
+
+#[test]
+fn call_is_unknown_with_side_effects() {
+    let expression = parse_local_value("local value = compute(1 + 2)");
+
+    let evaluator = Evaluator::default();
+
+    assert!(evaluator.has_side_effects(&expression));
+    assert_eq!(evaluator.evaluate(&expression), LuaValue::Unknown);
+}
+
+#[test]
+fn instance_indexing_is_not_pure_after_processing() {
+    let resources = memory_resources!(
+        "src/main.lua" => "local parent = script.Parent\nreturn parent",
+        ".darklua.json" => "{ rules: [], instance_indexing_is_pure: true }",
+    );
+
+    process(
+        &resources,
+        Options::new("src/main.lua").with_output("out.lua"),
+    )
+    .unwrap()
+    .result()
+    .unwrap();
+
+    let expression = parse_local_value("local parent = script.Parent");
+
+    assert!(Evaluator::default().has_side_effects(&expression));
+}
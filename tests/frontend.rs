@@ -230,105 +230,1947 @@ fn instance_indexing_is_pure_removes_unused_indexing_as_var_from_game_root() {
     assert_eq!(resources.get("src/test.lua").unwrap(), "");
 }
 
+mod post_rules {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn process_test_file(code: &str, config: &str) -> String {
+        let resources = memory_resources!(
+            "src/test.lua" => code,
+            ".darklua.json" => config,
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources.get("src/test.lua").unwrap()
+    }
+
+    #[test]
+    fn default_post_rules_remove_unused_instance_aliases() {
+        let code = process_test_file(
+            "local Root = script.Parent\nreturn true",
+            "{ rules: [], generator: 'dense', instance_indexing_is_pure: true }",
+        );
+
+        assert_eq!(code, "return true");
+    }
+
+    #[test]
+    fn empty_post_rules_keep_unused_locals() {
+        let code = process_test_file(
+            "local Root = script.Parent\nreturn true",
+            "{ rules: [], generator: 'dense', instance_indexing_is_pure: true, post_rules: [] }",
+        );
+
+        assert_eq!(code, "local Root=script.Parent return true");
+    }
+
+    #[test]
+    fn apply_configured_post_rules() {
+        let code = process_test_file(
+            "local unused = 1\nreturn true",
+            "{ rules: [], generator: 'dense', post_rules: ['remove_unused_variable'] }",
+        );
+
+        assert_eq!(code, "return true");
+    }
+
+    #[test]
+    fn post_rules_are_not_applied_by_default() {
+        let code = process_test_file(
+            "local unused = 1\nreturn true",
+            "{ rules: [], generator: 'dense' }",
+        );
+
+        assert_eq!(code, "local unused=1 return true");
+    }
+}
+
+mod pure_functions {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn process_test_file(code: &str) -> String {
+        let resources = memory_resources!(
+            "src/test.lua" => code,
+            ".darklua.json" => "{ rules: ['remove_unused_variable', 'remove_empty_blocks'], generator: 'dense', pure_functions: ['Color3.fromRGB', 'Maths.*'] }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources.get("src/test.lua").unwrap()
+    }
+
+    #[test]
+    fn remove_unused_pure_function_call() {
+        let code = process_test_file("local color = Color3.fromRGB(255, 0, 0)\nreturn true");
+
+        assert_eq!(code, "return true");
+    }
+
+    #[test]
+    fn remove_unused_wildcard_pure_function_call() {
+        let code = process_test_file("local value = Maths.clamp(4, 0, 1)\nreturn true");
+
+        assert_eq!(code, "return true");
+    }
+
+    #[test]
+    fn remove_unused_pure_function_call_in_condition() {
+        let code = process_test_file("if Maths.clamp(4, 0, 1) then end\nreturn true");
+
+        assert_eq!(code, "return true");
+    }
+
+    #[test]
+    fn keep_pure_function_call_with_impure_argument() {
+        let code = process_test_file("local color = Color3.fromRGB(read(), 0, 0)\nreturn true");
+
+        assert_eq!(code, "Color3.fromRGB(read(),0,0)return true");
+    }
+
+    #[test]
+    fn keep_call_to_other_function() {
+        let code = process_test_file("local color = Color3.new(1, 0, 0)\nreturn true");
+
+        assert_eq!(code, "Color3.new(1,0,0)return true");
+    }
+
+    #[test]
+    fn keep_call_to_shadowed_pure_function() {
+        let code = process_test_file(
+            "local Maths = require('./Maths')\nlocal value = Maths.clamp(4, 0, 1)\nreturn true",
+        );
+
+        assert_eq!(
+            code,
+            "local Maths=require('./Maths')Maths.clamp(4,0,1)return true"
+        );
+    }
+
+    #[test]
+    fn remove_pure_function_call_outside_of_shadowing_scope() {
+        let code = process_test_file(
+            "do\n    local Maths = require('./Maths')\n    print(Maths)\nend\nlocal value = Maths.clamp(4, 0, 1)\nreturn true",
+        );
+
+        assert_eq!(
+            code,
+            "do local Maths=require('./Maths')print(Maths)end return true"
+        );
+    }
+
+    #[test]
+    fn keep_pure_function_call_on_parameter() {
+        let code = process_test_file(
+            "local function clamp(Maths)\n    local value = Maths.clamp(4, 0, 1)\n    if Maths.clamp(4, 0, 1) then end\nend\nreturn clamp",
+        );
+
+        assert_eq!(
+            code,
+            "local function clamp(Maths)Maths.clamp(4,0,1)Maths.clamp(4,0,1)end return clamp"
+        );
+    }
+
+    #[test]
+    fn keep_call_to_reassigned_pure_function() {
+        let code = process_test_file(
+            "local function setup()\n    Maths = require('./Maths')\nend\nlocal value = Maths.clamp(4, 0, 1)\nreturn setup",
+        );
+
+        assert_eq!(
+            code,
+            "local function setup()Maths=require('./Maths')end Maths.clamp(4,0,1)return setup"
+        );
+    }
+}
+
+mod table_constructor_calls {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn process_test_file(code: &str) -> String {
+        let resources = memory_resources!(
+            "src/test.lua" => code,
+            ".darklua.json" => "{ rules: ['remove_unused_variable'], generator: 'dense' }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources.get("src/test.lua").unwrap()
+    }
+
+    #[test]
+    fn remove_unused_frozen_table() {
+        let code = process_test_file("local CONSTANTS = table.freeze({ A = 1 })\nreturn true");
+
+        assert_eq!(code, "return true");
+    }
+
+    #[test]
+    fn remove_unused_frozen_table_with_table_argument() {
+        let code = process_test_file("local CONSTANTS = table.freeze { A = 1 }\nreturn true");
+
+        assert_eq!(code, "return true");
+    }
+
+    #[test]
+    fn remove_unused_table_with_metatable() {
+        let code = process_test_file(
+            "local Base = {}\nlocal object = setmetatable({}, { __index = Base })\nreturn Base",
+        );
+
+        assert_eq!(code, "local Base={}return Base");
+    }
+
+    #[test]
+    fn remove_unused_table_with_nil_metatable() {
+        let code = process_test_file("local object = setmetatable({ 1 }, nil)\nreturn true");
+
+        assert_eq!(code, "return true");
+    }
+
+    #[test]
+    fn keep_frozen_local_table() {
+        let code = process_test_file(
+            "local values = {}\nlocal frozen = table.freeze(values)\nreturn values",
+        );
+
+        assert_eq!(code, "local values={}table.freeze(values)return values");
+    }
+
+    #[test]
+    fn keep_frozen_table_with_side_effects() {
+        let code = process_test_file("local CONSTANTS = table.freeze({ read() })\nreturn true");
+
+        assert_eq!(code, "table.freeze({read()})return true");
+    }
+
+    #[test]
+    fn keep_metatable_from_local() {
+        let code = process_test_file(
+            "local Base = {}\nlocal object = setmetatable({}, Base)\nreturn Base",
+        );
+
+        assert_eq!(code, "local Base={}setmetatable({},Base)return Base");
+    }
+
+    #[test]
+    fn keep_metatable_with_gc_metamethod() {
+        let code =
+            process_test_file("local object = setmetatable({}, { __gc = print })\nreturn true");
+
+        assert_eq!(code, "setmetatable({},{__gc=print})return true");
+    }
+
+    #[test]
+    fn keep_shadowed_table_freeze() {
+        let code = process_test_file(
+            "local table = require('./table')\nlocal CONSTANTS = table.freeze({ A = 1 })\nreturn true",
+        );
+
+        assert_eq!(
+            code,
+            "local table=require('./table')table.freeze({A=1})return true"
+        );
+    }
+
+    #[test]
+    fn keep_reassigned_setmetatable() {
+        let code = process_test_file(
+            "setmetatable = print\nlocal object = setmetatable({}, {})\nreturn true",
+        );
+
+        assert_eq!(code, "setmetatable=print setmetatable({},{})return true");
+    }
+
+    #[test]
+    fn remove_frozen_table_when_table_is_shadowed_elsewhere() {
+        let code = process_test_file(
+            "do\n    local table = {}\n    table.freeze = print\n    print(table)\nend\nlocal CONSTANTS = table.freeze({ A = 1 })\nreturn true",
+        );
+
+        assert_eq!(
+            code,
+            "do local table={}table.freeze=print print(table)end return true"
+        );
+    }
+}
+
+const LUAU_ONLY_CODE: &str = r#"local count: number = 0
+for _, value in values do
+    if value == nil then
+        continue
+    end
+    count += 1
+end
+return `total: {if count > 0 then count // 2 else 0}`"#;
+
+#[test]
+fn target_luau_accepts_luau_syntax() {
+    let resources = memory_resources!(
+        "src/test.lua" => LUAU_ONLY_CODE,
+        ".darklua.json" => "{ rules: [], target: 'luau' }",
+    );
+
+    process(&resources, Options::new("src"))
+        .unwrap()
+        .result()
+        .unwrap();
+
+    assert_eq!(resources.get("src/test.lua").unwrap(), LUAU_ONLY_CODE);
+}
+
+#[test]
+fn target_lua51_accepts_code_converted_by_rules() {
+    let resources = memory_resources!(
+        "src/test.lua" => "local a: number = 1\na += 1\nreturn a",
+        ".darklua.json" => "{ rules: ['remove_types', 'remove_compound_assignment'], target: 'lua51' }",
+    );
+
+    process(&resources, Options::new("src"))
+        .unwrap()
+        .result()
+        .unwrap();
+
+    assert_eq!(
+        resources.get("src/test.lua").unwrap(),
+        "local a= 1\na =a+ 1\nreturn a"
+    );
+}
+
+mod disable_directives {
+    use darklua_core::{
+        rules::{ComputeExpression, Rule},
+        Configuration, GeneratorParameters,
+    };
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const DISABLED_CODE: &str = "-- darklua: disable compute_expression\nreturn 1 + 1";
+
+    #[test]
+    fn disable_rule_for_one_file_only() {
+        let resources = memory_resources!(
+            "src/disabled.lua" => DISABLED_CODE,
+            "src/enabled.lua" => "return 1 + 1",
+            ".darklua.json" => "{ \"rules\": [\"compute_expression\"], \"generator\": \"retain_lines\" }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("src/disabled.lua").unwrap(), DISABLED_CODE);
+        assert_eq!(resources.get("src/enabled.lua").unwrap(), "return 2");
+    }
+
+    #[test]
+    fn disable_rule_from_configuration_built_in_rust() {
+        let resources = memory_resources!(
+            "src/disabled.lua" => DISABLED_CODE,
+            "src/enabled.lua" => "return 1 + 1",
+        );
+
+        let rule: Box<dyn Rule> = Box::<ComputeExpression>::default();
+        let configuration = Configuration::empty()
+            .with_rule(rule)
+            .with_generator(GeneratorParameters::RetainLines);
+
+        process(
+            &resources,
+            Options::new("src").with_configuration(configuration),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(resources.get("src/disabled.lua").unwrap(), DISABLED_CODE);
+        assert_eq!(resources.get("src/enabled.lua").unwrap(), "return 2");
+    }
+
+    #[test]
+    fn disable_unknown_rule_warns() {
+        let resources = memory_resources!(
+            "src/main.lua" => "--!strict\n-- darklua: disable compute_expression, oops\nreturn 1 + 1",
+            ".darklua.json" => "{ \"rules\": [\"compute_expression\"], \"generator\": \"retain_lines\" }",
+        );
+
+        let worker_tree = process(&resources, Options::new("src/main.lua")).unwrap();
+
+        let warnings = worker_tree.collect_warnings();
+
+        assert_eq!(warnings.len(), 1);
+        let (source, file_warnings) = warnings[0];
+        assert_eq!(source, std::path::Path::new("src/main.lua"));
+        assert_eq!(
+            file_warnings
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["unable to disable unknown rule `oops` (`src/main.lua` at line 2)"]
+        );
+
+        worker_tree.result().unwrap();
+
+        assert_eq!(
+            resources.get("src/main.lua").unwrap(),
+            "--!strict\n-- darklua: disable compute_expression, oops\nreturn 1 + 1"
+        );
+    }
+
+    #[test]
+    fn disable_without_rule_names_disables_every_rule_for_the_file() {
+        let code = "-- darklua: disable\nlocal a = 1 + 1\nreturn nil";
+        let out =
+            process_main_with_rules("\"compute_expression\", \"remove_unused_variable\"", code);
+
+        assert_eq!(out, code);
+    }
+
+    #[test]
+    fn leading_disable_region_does_not_disable_the_whole_file() {
+        let out = process_main_with_rules(
+            "\"compute_expression\"",
+            "-- darklua: disable compute_expression\nlocal a = 1 + 1\n-- darklua: enable compute_expression\nlocal b = 1 + 1\nreturn a, b\n",
+        );
+
+        assert_eq!(
+            out,
+            "-- darklua: disable compute_expression\nlocal a = 1 + 1\n-- darklua: enable compute_expression\nlocal b = 2\nreturn a, b\n"
+        );
+    }
+
+    fn process_main_with_rules(rules: &str, code: &str) -> String {
+        let resources = memory_resources!(
+            "src/main.lua" => code,
+            "src/value.lua" => "return true",
+            ".darklua.json" => &format!("{{ \"rules\": [{}], \"generator\": \"retain_lines\" }}", rules),
+        );
+
+        process(&resources, Options::new("src/main.lua"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources.get("src/main.lua").unwrap()
+    }
+
+    #[test]
+    fn disable_next_line_compute_expression() {
+        let out = process_main_with_rules(
+            "\"compute_expression\"",
+            "local a = 1 + 1\n-- darklua: disable-next-line compute_expression\nlocal b = 1 + 1\nlocal c = 1 + 1\nreturn a, b, c\n",
+        );
+
+        assert!(
+            out.contains("local b = 1 + 1\n"),
+            "line should be kept: {}",
+            out
+        );
+        assert!(
+            out.contains("local c = 2\n"),
+            "line should be computed: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn disable_region_remove_unused_variable() {
+        let out = process_main_with_rules(
+            "\"remove_unused_variable\"",
+            "local a = 1\n-- darklua: disable remove_unused_variable\nlocal b = 2\nlocal function c() end\n-- darklua: enable remove_unused_variable\nlocal d = 3\nreturn nil\n",
+        );
+
+        assert_eq!(
+            out,
+            "-- darklua: disable remove_unused_variable\n\nlocal b = 2\nlocal function c() end\n\n\nreturn nil\n"
+        );
+    }
+
+    #[test]
+    fn disable_region_for_every_rule() {
+        let out = process_main_with_rules(
+            "\"compute_expression\", \"remove_unused_variable\"",
+            "-- darklua: disable\nlocal a = 1 + 1\n-- darklua: enable\nlocal b = 1 + 1\nreturn a\n",
+        );
+
+        assert_eq!(out, "-- darklua: disable\nlocal a = 1 + 1\n\n\nreturn a\n");
+    }
+
+    #[test]
+    fn disable_next_line_convert_require() {
+        let out = process_main_with_rules(
+            "{ \"rule\": \"convert_require\", \"current\": \"path\", \"target\": \"roblox\" }",
+            "-- darklua: disable-next-line convert_require\nlocal kept = require('./value')\nlocal value = require('./value')\nreturn kept, value\n",
+        );
+
+        assert_eq!(
+            out,
+            "-- darklua: disable-next-line convert_require\nlocal kept = require('./value')\nlocal value = require(script.Parent:FindFirstChild('value'))\nreturn kept, value\n"
+        );
+    }
+
+    #[test]
+    fn disable_next_line_unknown_rule_warns() {
+        let resources = memory_resources!(
+            "src/main.lua" => "-- darklua: disable-next-line oops\nlocal a = 1 + 1\n-- darklua: disable other\nreturn a",
+            ".darklua.json" => "{ \"rules\": [\"compute_expression\"], \"generator\": \"retain_lines\" }",
+        );
+
+        let worker_tree = process(&resources, Options::new("src/main.lua")).unwrap();
+
+        let warnings = worker_tree.collect_warnings();
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0]
+                .1
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "unable to disable unknown rule `oops` (`src/main.lua` at line 1)",
+                "unable to disable unknown rule `other` (`src/main.lua` at line 3)",
+            ]
+        );
+
+        worker_tree.result().unwrap();
+    }
+}
+
+mod retain_lines_max_line_length {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn long_line_code() -> String {
+        let values: Vec<_> = (0..2000).map(|value| value.to_string()).collect();
+        format!("local list = {{ {} }}\n\nreturn list\n", values.join(", "))
+    }
+
+    #[test]
+    fn split_long_lines_from_configuration() {
+        let code = long_line_code();
+        let resources = memory_resources!(
+            "src/test.lua" => &code,
+            ".darklua.json" => "{ rules: [], generator: { name: 'retain_lines', max_line_length: 80 } }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        let output = resources.get("src/test.lua").unwrap();
+        let lines: Vec<_> = output.lines().collect();
+
+        assert!(lines.len() > 100);
+        assert!(lines.iter().all(|line| line.chars().count() <= 80));
+        // the empty line is removed to move the return statement closer to its original line
+        assert_eq!(lines.last(), Some(&"return list"));
+        assert!(!lines.contains(&""));
+        assert_eq!(
+            output.split_whitespace().collect::<String>(),
+            code.split_whitespace().collect::<String>()
+        );
+    }
+
+    #[test]
+    fn zero_max_line_length_errors() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 1",
+            ".darklua.json" => "{ rules: [], generator: { name: 'retain_lines', max_line_length: 0 } }",
+        );
+
+        let error = process(&resources, Options::new("src")).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("`max_line_length` must be greater than zero"));
+    }
+}
+
 mod errors {
     use std::path::{Path, PathBuf};
 
     use darklua_core::{
-        nodes::Block,
+        nodes::Block,
+        rules::{
+            Context, PathRequireMode, Rule, RuleConfiguration, RuleConfigurationError,
+            RuleProcessResult, RuleProperties,
+        },
+        BundleConfiguration, Configuration, DarkluaError, ErrorKind, WorkerTree,
+    };
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn assert_errors(snapshot_name: &'static str, resources: &Resources, options: Options) {
+        let errors = process(resources, options)
+            .map_err(|err| vec![err])
+            .and_then(WorkerTree::result)
+            .unwrap_err();
+
+        let errors_display = errors
+            .into_iter()
+            .map(|err| format!("- {}", err).replace('\\', "/"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        insta::assert_snapshot!(snapshot_name, errors_display);
+    }
+
+    #[test]
+    fn snapshot_simple_cyclic_work_error() {
+        let resources = memory_resources!(
+            "src/a.lua" => "return 'module a'",
+            "src/b.lua" => "return 'module b'",
+        );
+
+        #[derive(Debug)]
+        struct CustomRule;
+
+        impl RuleConfiguration for CustomRule {
+            fn configure(
+                &mut self,
+                _properties: RuleProperties,
+            ) -> Result<(), RuleConfigurationError> {
+                Ok(())
+            }
+
+            fn get_name(&self) -> &'static str {
+                "custom-rule"
+            }
+
+            fn serialize_to_properties(&self) -> RuleProperties {
+                Default::default()
+            }
+        }
+
+        impl Rule for CustomRule {
+            fn process(&self, _: &mut Block, _: &Context) -> RuleProcessResult {
+                Ok(())
+            }
+
+            fn require_content(&self, _: &Path, _: &Block) -> Vec<PathBuf> {
+                vec!["src/a.lua".into(), "src/b.lua".into()]
+            }
+        }
+
+        let rule: Box<dyn Rule> = Box::new(CustomRule);
+
+        assert_errors(
+            "simple_cyclic_work_error",
+            &resources,
+            Options::new("src").with_configuration(Configuration::empty().with_rule(rule)),
+        );
+    }
+
+    #[test]
+    fn snapshot_missing_configuration_file() {
+        let resources = memory_resources!(
+            "src/init.lua" => "return ''",
+        );
+
+        assert_errors(
+            "missing_configuration_file",
+            &resources,
+            Options::new("src").with_configuration_at("missing/config.json"),
+        );
+    }
+
+    #[test]
+    fn snapshot_target_lua51_incompatibilities() {
+        let resources = memory_resources!(
+            "src/test.lua" => LUAU_ONLY_CODE,
+            ".darklua.json" => "{ rules: [], target: 'lua51' }",
+        );
+
+        assert_errors(
+            "target_lua51_incompatibilities",
+            &resources,
+            Options::new("src"),
+        );
+    }
+
+    #[test]
+    fn snapshot_target_lua53_incompatibilities() {
+        let resources = memory_resources!(
+            "src/test.lua" => LUAU_ONLY_CODE,
+            ".darklua.json" => "{ rules: [], target: 'lua53' }",
+        );
+
+        assert_errors(
+            "target_lua53_incompatibilities",
+            &resources,
+            Options::new("src"),
+        );
+    }
+
+    #[test]
+    fn snapshot_multiple_configuration_file_found() {
+        let resources = memory_resources!(
+            "src/init.lua" => "return ''",
+            ".darklua.json" => "{ rules: [] }",
+            ".darklua.json5" => "{ rules: [] }",
+        );
+
+        assert_errors(
+            "multiple_configuration_file_found",
+            &resources,
+            Options::new("src"),
+        );
+    }
+
+    fn process_errors(resources: &Resources, options: Options) -> Vec<DarkluaError> {
+        process(resources, options)
+            .map_err(|err| vec![err])
+            .and_then(WorkerTree::result)
+            .unwrap_err()
+    }
+
+    fn bundle_options() -> Options {
+        Options::new("src/main.lua")
+            .with_output("main.lua")
+            .with_configuration(
+                Configuration::empty().with_bundle_configuration(BundleConfiguration::new(
+                    PathRequireMode::default(),
+                )),
+            )
+    }
+
+    #[test]
+    fn parse_failure_has_parser_kind_with_location() {
+        let resources = memory_resources!(
+            "src/test.lua" => "local a = 1\nlocal b = ",
+            ".darklua.json" => "{ rules: [] }",
+        );
+
+        let errors = process_errors(&resources, Options::new("src"));
+
+        assert_eq!(errors.len(), 1);
+        let error = &errors[0];
+        assert_eq!(error.kind(), ErrorKind::Parser);
+        assert_eq!(error.path(), Some(Path::new("src/test.lua")));
+        assert_eq!(error.line(), Some(2));
+    }
+
+    #[test]
+    fn missing_bundled_module_is_a_cause_of_the_rule_error() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local value = require('./value')",
+        );
+
+        let errors = process_errors(&resources, bundle_options());
+
+        assert_eq!(errors.len(), 1);
+        let error = &errors[0];
+        assert_eq!(error.kind(), ErrorKind::Rule);
+        assert_eq!(error.path(), Some(Path::new("src/main.lua")));
+        assert_eq!(error.causes().len(), 1);
+        assert_eq!(error.causes()[0].kind(), ErrorKind::ResourceNotFound);
+    }
+
+    #[test]
+    fn cyclic_bundled_require_has_cyclic_require_kind() {
+        let resources = memory_resources!(
+            "src/main.lua" => "return require('./a')",
+            "src/a.lua" => "return require('./b')",
+            "src/b.lua" => "return require('./a')",
+        );
+
+        let errors = process_errors(&resources, bundle_options());
+
+        assert_eq!(errors.len(), 1);
+        let causes = errors[0].causes();
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].kind(), ErrorKind::CyclicRequire);
+        assert_eq!(causes[0].path(), Some(Path::new("src/a.lua")));
+    }
+
+    #[test]
+    fn snapshot_errors_json() {
+        let resources = memory_resources!(
+            "src/broken.lua" => "local a = 1\nlocal b = ",
+            "src/main.lua" => "local value = require('./missing')",
+        );
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src").with_configuration(Configuration::empty().with_bundle_configuration(
+                BundleConfiguration::new(PathRequireMode::default()),
+            )),
+        )
+        .unwrap();
+
+        insta::assert_snapshot!("errors_json", worker_tree.errors_json().replace("\\\\", "/"));
+    }
+}
+
+mod custom_rules {
+    use darklua_core::{
+        nodes::{Block, ReturnStatement, StringExpression},
+        rules::{
+            register_rule, Context, FlawlessRule, Rule, RuleConfiguration,
+            RuleConfigurationError, RuleProperties, RulePropertyValue,
+        },
+    };
+    use pretty_assertions::assert_eq;
+
+    use super::{memory_resources, process, Options, Resources};
+
+    #[derive(Debug, Default)]
+    struct ReturnValue {
+        value: String,
+    }
+
+    impl ReturnValue {
+        fn create(properties: RuleProperties) -> Result<Box<dyn Rule>, RuleConfigurationError> {
+            let mut rule = Self::default();
+            rule.configure(properties)?;
+            Ok(Box::new(rule))
+        }
+    }
+
+    impl FlawlessRule for ReturnValue {
+        fn flawless_process(&self, block: &mut Block, _: &Context) {
+            block.set_last_statement(ReturnStatement::one(StringExpression::from_value(
+                self.value.as_str(),
+            )));
+        }
+    }
+
+    impl RuleConfiguration for ReturnValue {
+        fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+            for (key, value) in properties {
+                match (key.as_str(), value) {
+                    ("value", RulePropertyValue::String(value)) => {
+                        self.value = value;
+                    }
+                    ("value", _) => return Err(RuleConfigurationError::StringExpected(key)),
+                    _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+                }
+            }
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "return_value"
+        }
+
+        fn serialize_to_properties(&self) -> RuleProperties {
+            let mut properties = RuleProperties::new();
+            properties.insert("value".to_owned(), self.value.clone().into());
+            properties
+        }
+    }
+
+    #[test]
+    fn registered_rule_is_loaded_from_configuration() {
+        register_rule("registered_return_value", ReturnValue::create).unwrap();
+
+        let resources = memory_resources!(
+            "src/test.lua" => "return nil",
+            ".darklua.json" => "{ rules: [{ rule: 'registered_return_value', value: 'ok' }] }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("src/test.lua").unwrap(), "return 'ok'");
+    }
+
+    #[test]
+    fn registered_rule_is_deserialized_from_string() {
+        register_rule("registered_return_empty", ReturnValue::create).unwrap();
+
+        let rule: Box<dyn Rule> = json5::from_str("'registered_return_empty'").unwrap();
+
+        assert_eq!(rule.get_name(), "return_value");
+    }
+
+    #[test]
+    fn register_rule_with_built_in_name_errors() {
+        let error = register_rule("remove_comments", ReturnValue::create).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unable to register rule `remove_comments` because a built-in rule already uses this name"
+        );
+    }
+
+    #[test]
+    fn extra_rule_is_loaded_from_configuration() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return nil",
+            ".darklua.json" => "{ rules: [{ rule: 'extra_return_value', value: 'extra' }] }",
+        );
+
+        process(
+            &resources,
+            Options::new("src").with_extra_rules([(
+                "extra_return_value",
+                ReturnValue::create as darklua_core::rules::RuleFactory,
+            )]),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(resources.get("src/test.lua").unwrap(), "return 'extra'");
+    }
+
+    #[test]
+    fn extra_rule_is_not_available_in_other_runs() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return nil",
+            ".darklua.json" => "{ rules: ['unavailable_extra_rule'] }",
+        );
+
+        process(
+            &resources,
+            Options::new("src").with_extra_rules([(
+                "other_extra_rule",
+                ReturnValue::create as darklua_core::rules::RuleFactory,
+            )]),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn extra_rule_with_built_in_name_errors() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return nil",
+        );
+
+        let error = process(
+            &resources,
+            Options::new("src").with_extra_rules([(
+                "remove_spaces",
+                ReturnValue::create as darklua_core::rules::RuleFactory,
+            )]),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unable to register rule `remove_spaces` because a built-in rule already uses this name"
+        );
+    }
+}
+
+mod process_code {
+    use darklua_core::{process_code, rules::PathRequireMode, BundleConfiguration, Configuration};
+    use pretty_assertions::assert_eq;
+
+    use super::{memory_resources, Resources, ANY_CODE, ANY_CODE_DEFAULT_PROCESS};
+
+    fn bundle_configuration() -> Configuration {
+        Configuration::empty()
+            .with_bundle_configuration(BundleConfiguration::new(PathRequireMode::default()))
+    }
+
+    #[test]
+    fn applies_default_rules_without_resources() {
+        let config: Configuration = json5::from_str("{}").unwrap();
+
+        assert_eq!(
+            process_code(ANY_CODE, "src/test.lua", config, None).unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+    }
+
+    #[test]
+    fn bundles_snippet_with_in_memory_modules() {
+        let resources = memory_resources!(
+            "src/value.lua" => "return 'value'",
+        );
+
+        let code = process_code(
+            "local value = require('./value')\nreturn value",
+            "src/main.lua",
+            bundle_configuration(),
+            Some(&resources),
+        )
+        .unwrap();
+
+        assert!(
+            code.contains("return 'value'"),
+            "module not bundled: {}",
+            code
+        );
+        assert!(
+            !code.contains("require("),
+            "require left in output: {}",
+            code
+        );
+    }
+
+    #[test]
+    fn does_not_write_to_resources() {
+        let resources = memory_resources!(
+            "src/value.lua" => "return true",
+        );
+
+        process_code(
+            "local value = require('./value')",
+            "src/main.lua",
+            bundle_configuration(),
+            Some(&resources),
+        )
+        .unwrap();
+
+        assert_eq!(resources.exists("src/main.lua"), Ok(false));
+        assert_eq!(resources.get("src/value.lua").unwrap(), "return true");
+    }
+
+    #[test]
+    fn returns_errors_of_missing_modules() {
+        let errors = process_code(
+            "local value = require('./value')",
+            "src/main.lua",
+            bundle_configuration(),
+            None,
+        )
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+    }
+}
+
+mod copy_assets {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    const PNG_BYTES: &[u8] = &[137, 80, 78, 71, 13, 10, 26, 10, 0, 159, 255];
+
+    fn process_tree(config: &str) -> Resources {
+        let resources = memory_resources!(
+            "src/main.lua" => "local config = require('./config.json')\nreturn config",
+            "src/config.json" => "{ \"debug\": true }",
+            "src/notes.txt" => "not copied",
+            ".darklua.json" => config,
+        );
+        resources
+            .write_bytes("src/assets/icon.png", PNG_BYTES)
+            .unwrap();
+        resources
+            .write_bytes("src/assets/draft.png", PNG_BYTES)
+            .unwrap();
+
+        process(&resources, Options::new("src").with_output("dist"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources
+    }
+
+    #[test]
+    fn copy_matching_assets_unchanged() {
+        let resources = process_tree("{ rules: [], copy_assets: [\"**/*.json\", \"assets/**\"] }");
+
+        assert_eq!(
+            resources.get("dist/config.json").unwrap(),
+            "{ \"debug\": true }"
+        );
+        assert_eq!(
+            resources.get_bytes("dist/assets/icon.png").unwrap(),
+            PNG_BYTES
+        );
+        assert_eq!(resources.exists("dist/notes.txt"), Ok(false));
+    }
+
+    #[test]
+    fn skip_excluded_assets() {
+        let resources =
+            process_tree("{ rules: [], copy_assets: [\"assets/**\", \"!**/draft.png\"] }");
+
+        assert_eq!(resources.exists("dist/assets/icon.png"), Ok(true));
+        assert_eq!(resources.exists("dist/assets/draft.png"), Ok(false));
+    }
+
+    #[test]
+    fn copy_assets_required_as_data_modules() {
+        let resources = process_tree(
+            "{ rules: [], copy_assets: [\"**/*.json\"], bundle: { require_mode: \"path\" } }",
+        );
+
+        assert_eq!(resources.exists("dist/config.json"), Ok(true));
+    }
+
+    #[test]
+    fn skip_pruned_assets_required_as_data_modules() {
+        let resources = process_tree(
+            "{ rules: [], copy_assets: [\"**/*.json\", \"assets/**\"], bundle: { require_mode: \"path\", prune_bundled_files: true } }",
+        );
+
+        assert_eq!(resources.exists("dist/config.json"), Ok(false));
+        assert_eq!(resources.exists("dist/assets/icon.png"), Ok(true));
+    }
+
+    #[test]
+    fn assets_are_not_copied_without_patterns() {
+        let resources = process_tree("{ rules: [] }");
+
+        assert_eq!(resources.exists("dist/config.json"), Ok(false));
+        assert_eq!(resources.exists("dist/assets/icon.png"), Ok(false));
+    }
+}
+
+mod capture_output {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    const BUNDLE_CONFIG: &str = "{ rules: [], generator: \"retain_lines\", bundle: { require_mode: \"path\", sourcemap: { enabled: true, output_path: \"out.lua.map\" } } }";
+
+    #[test]
+    fn capture_bundled_code_without_writing() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local value = require('./value')\nprint(value)",
+            "src/value.lua" => "return true",
+            ".darklua.json" => BUNDLE_CONFIG,
+        );
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src/main.lua")
+                .with_output("out.lua")
+                .capture_output(true),
+        )
+        .unwrap();
+
+        let code = worker_tree.generated("out.lua").unwrap();
+        assert!(code.contains("__DARKLUA_BUNDLE_MODULES"), "{}", code);
+        assert!(worker_tree.generated("out.lua.map").is_some());
+
+        assert_eq!(resources.exists("out.lua"), Ok(false));
+        assert_eq!(resources.exists("out.lua.map"), Ok(false));
+        worker_tree.result().unwrap();
+    }
+
+    #[test]
+    fn capture_in_place_does_not_modify_sources() {
+        let resources = memory_resources!(
+            "src/test.lua" => ANY_CODE,
+        );
+
+        let worker_tree = process(&resources, Options::new("src").capture_output(true)).unwrap();
+
+        assert_eq!(
+            worker_tree.generated("src/test.lua"),
+            Some(ANY_CODE_DEFAULT_PROCESS)
+        );
+        assert_eq!(resources.get("src/test.lua").unwrap(), ANY_CODE);
+    }
+
+    #[test]
+    fn generated_code_is_not_available_without_capture() {
+        let resources = memory_resources!(
+            "src/test.lua" => ANY_CODE,
+        );
+
+        let worker_tree = process(&resources, Options::new("src")).unwrap();
+
+        assert_eq!(worker_tree.generated("src/test.lua"), None);
+    }
+}
+
+mod profiles {
+    use std::{cell::Cell, rc::Rc};
+
+    use darklua_core::{
+        nodes::Block,
+        rules::{
+            Context, FlawlessRule, RemoveComments, RuleConfiguration, RuleConfigurationError,
+            RuleProperties,
+        },
+        Configuration, GeneratorParameters, ProfileConfiguration,
+    };
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const MAIN_CODE: &str = "-- main\nlocal value = require('./value')\n\nprint(value)\n";
+    const VALUE_CODE: &str = "-- value\nreturn true\n";
+    const PROFILES_CONFIG: &str = concat!(
+        "{ rules: [], generator: 'retain_lines', ",
+        "bundle: { require_mode: 'path', sourcemap: { enabled: true, output_path: 'out.lua.map' } }, ",
+        "profiles: { dev: {}, min: { generator: 'dense', sourcemap: { enabled: false } } } }",
+    );
+
+    #[test]
+    fn dev_and_min_bundles_from_one_run() {
+        let resources = memory_resources!(
+            "src/main.lua" => MAIN_CODE,
+            "src/value.lua" => VALUE_CODE,
+            ".darklua.json" => PROFILES_CONFIG,
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let dev_code = resources.get("out.dev.lua").unwrap();
+        assert!(dev_code.contains("-- main"), "{}", dev_code);
+        assert!(
+            dev_code.contains("__DARKLUA_BUNDLE_MODULES"),
+            "{}",
+            dev_code
+        );
+
+        let min_code = resources.get("out.min.lua").unwrap();
+        assert!(!min_code.contains("-- main"), "{}", min_code);
+        assert!(
+            min_code.contains("__DARKLUA_BUNDLE_MODULES"),
+            "{}",
+            min_code
+        );
+
+        let map = resources.get("out.dev.lua.map").unwrap();
+        let sourcemap = sourcemap::SourceMap::from_slice(map.as_bytes()).unwrap();
+        assert_eq!(sourcemap.get_file(), Some("out.dev.lua"));
+
+        assert_eq!(resources.exists("out.lua"), Ok(false));
+        assert_eq!(resources.exists("out.lua.map"), Ok(false));
+        assert_eq!(resources.exists("out.min.lua.map"), Ok(false));
+    }
+
+    #[test]
+    fn capture_output_of_each_profile() {
+        let resources = memory_resources!(
+            "src/main.lua" => MAIN_CODE,
+            "src/value.lua" => VALUE_CODE,
+            ".darklua.json" => PROFILES_CONFIG,
+        );
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src/main.lua")
+                .with_output("out.lua")
+                .capture_output(true),
+        )
+        .unwrap();
+
+        assert!(worker_tree.generated("out.dev.lua").is_some());
+        assert!(worker_tree.generated("out.dev.lua.map").is_some());
+        assert!(worker_tree.generated("out.min.lua").is_some());
+        assert_eq!(worker_tree.generated("out.lua"), None);
+        worker_tree.result().unwrap();
+    }
+
+    #[test]
+    fn map_generated_lines_of_profile_output() {
+        let resources = memory_resources!(
+            "src/main.lua" => MAIN_CODE,
+            "src/value.lua" => VALUE_CODE,
+            ".darklua.json" => PROFILES_CONFIG,
+        );
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap();
+
+        assert!(worker_tree.collect_errors().is_empty());
+
+        let dev_code = resources.get("out.dev.lua").unwrap();
+        let print_line = dev_code
+            .lines()
+            .position(|line| line.contains("print(value)"))
+            .unwrap()
+            + 1;
+
+        assert_eq!(
+            worker_tree.map_generated_to_original("out.dev.lua", print_line),
+            Some(("src/main.lua".into(), 4))
+        );
+        assert_eq!(
+            worker_tree.map_generated_to_original("out.min.lua", print_line),
+            None
+        );
+    }
+
+    #[test]
+    fn process_directory_with_profiles() {
+        let resources = memory_resources!(
+            "src/a.lua" => "-- a\nreturn 1",
+            "src/b.lua" => "-- b\nreturn 2",
+            ".darklua.json" => "{ rules: [], profiles: { dev: {}, prod: { rules: ['remove_comments'] } } }",
+        );
+
+        process(&resources, Options::new("src").with_output("dist"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("dist/a.dev.lua").unwrap(), "-- a\nreturn 1");
+        assert_eq!(resources.get("dist/a.prod.lua").unwrap(), "\nreturn 1");
+        assert_eq!(resources.get("dist/b.dev.lua").unwrap(), "-- b\nreturn 2");
+        assert_eq!(resources.get("dist/b.prod.lua").unwrap(), "\nreturn 2");
+    }
+
+    #[derive(Debug, Default)]
+    struct CountProcess {
+        count: Rc<Cell<usize>>,
+    }
+
+    impl FlawlessRule for CountProcess {
+        fn flawless_process(&self, _: &mut Block, _: &Context) {
+            self.count.set(self.count.get() + 1);
+        }
+    }
+
+    impl RuleConfiguration for CountProcess {
+        fn configure(&mut self, _: RuleProperties) -> Result<(), RuleConfigurationError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "count_process"
+        }
+
+        fn serialize_to_properties(&self) -> RuleProperties {
+            RuleProperties::new()
+        }
+    }
+
+    #[test]
+    fn profiles_with_same_rules_apply_rules_once() {
+        let resources = memory_resources!(
+            "src/main.lua" => "-- comment\nreturn true",
+        );
+
+        let count = Rc::new(Cell::new(0));
+        let configuration = Configuration::empty()
+            .with_rule(Box::new(CountProcess {
+                count: count.clone(),
+            }) as Box<dyn darklua_core::rules::Rule>)
+            .with_profile("dev", ProfileConfiguration::default())
+            .with_profile(
+                "min",
+                ProfileConfiguration::default()
+                    .with_generator(GeneratorParameters::default_dense()),
+            )
+            .with_profile(
+                "stripped",
+                ProfileConfiguration::default().with_rule(
+                    Box::<RemoveComments>::default() as Box<dyn darklua_core::rules::Rule>
+                ),
+            );
+
+        process(
+            &resources,
+            Options::new("src/main.lua")
+                .with_output("out.lua")
+                .with_configuration(configuration),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(count.get(), 1);
+        assert_eq!(
+            resources.get("out.dev.lua").unwrap(),
+            "-- comment\nreturn true"
+        );
+        assert_eq!(resources.get("out.min.lua").unwrap(), "return true");
+        assert_eq!(resources.get("out.stripped.lua").unwrap(), "\nreturn true");
+    }
+}
+
+mod deterministic_output {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const FILES: &[(&str, &str)] = &[
+        (
+            ".darklua.json",
+            concat!(
+                "{ rules: [], generator: 'retain_lines', ",
+                "bundle: { require_mode: 'path', sourcemap: { enabled: true, output_path: 'out.lua.map' } } }"
+            ),
+        ),
+        (
+            "src/a.lua",
+            "local shared = require('../modules/shared')\nprint(shared)\n",
+        ),
+        (
+            "src/b.lua",
+            "local other = require('../modules/other')\nprint(other)\n",
+        ),
+        (
+            "src/c/d.lua",
+            "local shared = require('../../modules/shared')\nreturn shared\n",
+        ),
+        ("modules/shared.lua", "return 'shared'\n"),
+        (
+            "modules/other.lua",
+            "local shared = require('./shared')\nreturn shared\n",
+        ),
+    ];
+
+    fn process_files(
+        files: impl Iterator<Item = &'static (&'static str, &'static str)>,
+    ) -> Vec<(String, String)> {
+        let resources = Resources::from_memory();
+        for (path, content) in files {
+            resources.write(path, content).unwrap();
+        }
+
+        process(&resources, Options::new("src").with_output("out"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources
+            .walk("")
+            .map(|path| {
+                let content = resources.get(&path).unwrap();
+                (path.display().to_string(), content)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn outputs_do_not_depend_on_insertion_order() {
+        let outputs = process_files(FILES.iter());
+
+        assert_eq!(outputs, process_files(FILES.iter().rev()));
+        assert!(
+            outputs.iter().any(|(path, _)| path.ends_with(".map")),
+            "{:#?}",
+            outputs
+        );
+    }
+}
+
+mod validate_output {
+    use darklua_core::{
+        nodes::{Block, Expression, ReturnStatement},
         rules::{
-            Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult,
-            RuleProperties,
+            Context, FlawlessRule, Rule, RuleConfiguration, RuleConfigurationError, RuleProperties,
         },
-        Configuration, WorkerTree,
+        Configuration, ErrorKind, WorkerTree,
     };
+    use pretty_assertions::assert_eq;
 
     use super::*;
 
-    fn assert_errors(snapshot_name: &'static str, resources: &Resources, options: Options) {
-        let errors = process(resources, options)
-            .map_err(|err| vec![err])
-            .and_then(WorkerTree::result)
-            .unwrap_err();
+    // returns an identifier named like a keyword, which generates code that cannot be parsed
+    #[derive(Debug)]
+    struct ReturnKeyword;
 
-        let errors_display = errors
-            .into_iter()
-            .map(|err| format!("- {}", err).replace('\\', "/"))
-            .collect::<Vec<_>>()
-            .join("\n");
-        insta::assert_snapshot!(snapshot_name, errors_display);
+    impl FlawlessRule for ReturnKeyword {
+        fn flawless_process(&self, block: &mut Block, _: &Context) {
+            block.set_last_statement(ReturnStatement::one(Expression::identifier("end")));
+        }
+    }
+
+    impl RuleConfiguration for ReturnKeyword {
+        fn configure(&mut self, _: RuleProperties) -> Result<(), RuleConfigurationError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "return_keyword"
+        }
+
+        fn serialize_to_properties(&self) -> RuleProperties {
+            RuleProperties::new()
+        }
+    }
+
+    fn process_with(configuration: Configuration) -> (Resources, Vec<darklua_core::DarkluaError>) {
+        let resources = memory_resources!(
+            "src/test.lua" => "return nil",
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src/test.lua")
+                .with_output("out/test.lua")
+                .with_configuration(configuration),
+        )
+        .map_err(|err| vec![err])
+        .and_then(WorkerTree::result)
+        .err()
+        .unwrap_or_default();
+
+        (resources, errors)
     }
 
     #[test]
-    fn snapshot_simple_cyclic_work_error() {
+    fn invalid_output_errors() {
+        let (_, errors) = process_with(
+            Configuration::empty()
+                .with_rule(Box::new(ReturnKeyword) as Box<dyn Rule>)
+                .with_validate_output(true),
+        );
+
+        assert_eq!(errors.len(), 1);
+        let error = &errors[0];
+        assert_eq!(error.kind(), ErrorKind::InvalidOutput);
+        assert_eq!(error.line(), Some(1));
+        let message = error.to_string();
+        assert!(
+            message.starts_with(
+                "generated code for `out/test.lua` is invalid at line 1, column 8 (rules: return_keyword): "
+            ),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn invalid_output_is_written_next_to_output() {
+        let (resources, _) = process_with(
+            Configuration::empty()
+                .with_rule(Box::new(ReturnKeyword) as Box<dyn Rule>)
+                .with_validate_output(true),
+        );
+
+        assert_eq!(resources.get("out/test.lua.invalid").unwrap(), "return end");
+        assert!(!resources.exists("out/test.lua").unwrap());
+    }
+
+    #[test]
+    fn invalid_output_is_written_without_validation() {
+        let (resources, errors) = process_with(
+            Configuration::empty()
+                .with_rule(Box::new(ReturnKeyword) as Box<dyn Rule>)
+                .with_validate_output(false),
+        );
+
+        assert!(errors.is_empty());
+        assert_eq!(resources.get("out/test.lua").unwrap(), "return end");
+        assert!(!resources.exists("out/test.lua.invalid").unwrap());
+    }
+
+    #[test]
+    fn valid_output_is_written() {
+        let (resources, errors) = process_with(Configuration::empty().with_validate_output(true));
+
+        assert!(errors.is_empty());
+        assert_eq!(resources.get("out/test.lua").unwrap(), "return nil");
+        assert!(!resources.exists("out/test.lua.invalid").unwrap());
+    }
+}
+
+mod output_collisions {
+    use std::path::PathBuf;
+
+    use darklua_core::{Configuration, ErrorKind, WorkerTree};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn luau_pair_tree() -> WorkerTree {
+        let mut worker_tree = WorkerTree::default();
+        worker_tree.add_source("src/foo.lua", Some(PathBuf::from("out/foo.lua")));
+        worker_tree.add_source("src/foo.luau", Some(PathBuf::from("out/foo.lua")));
+        worker_tree
+    }
+
+    #[test]
+    fn lua_and_luau_files_with_same_output_errors() {
         let resources = memory_resources!(
-            "src/a.lua" => "return 'module a'",
-            "src/b.lua" => "return 'module b'",
+            "src/foo.lua" => "return 'lua'",
+            "src/foo.luau" => "return 'luau'",
         );
 
-        #[derive(Debug)]
-        struct CustomRule;
+        let error = luau_pair_tree()
+            .process(&resources, Options::new("src"))
+            .unwrap_err();
 
-        impl RuleConfiguration for CustomRule {
-            fn configure(
-                &mut self,
-                _properties: RuleProperties,
-            ) -> Result<(), RuleConfigurationError> {
-                Ok(())
-            }
+        assert_eq!(error.kind(), ErrorKind::Configuration);
+        assert_eq!(
+            error.to_string(),
+            concat!(
+                "multiple files are written to the same output (enable `allow_overwrite` to ignore):\n",
+                "    - `out/foo.lua` from `src/foo.lua`, `src/foo.luau`"
+            )
+        );
+        assert!(!resources.exists("out/foo.lua").unwrap());
+    }
 
-            fn get_name(&self) -> &'static str {
-                "custom-rule"
-            }
+    #[test]
+    fn duplicate_output_targets_errors() {
+        let resources = memory_resources!(
+            "src/a.lua" => "return 'a'",
+            "src/b.lua" => "return 'b'",
+        );
 
-            fn serialize_to_properties(&self) -> RuleProperties {
-                Default::default()
-            }
+        let mut worker_tree = WorkerTree::default();
+        for input in ["src/a.lua", "src/b.lua"] {
+            worker_tree
+                .collect_work(&resources, &Options::new(input).with_output("out/main.lua"))
+                .unwrap();
         }
 
-        impl Rule for CustomRule {
-            fn process(&self, _: &mut Block, _: &Context) -> RuleProcessResult {
-                Ok(())
-            }
+        let error = worker_tree
+            .process(&resources, Options::new("src"))
+            .unwrap_err();
 
-            fn require_content(&self, _: &Path, _: &Block) -> Vec<PathBuf> {
-                vec!["src/a.lua".into(), "src/b.lua".into()]
-            }
-        }
+        assert_eq!(error.kind(), ErrorKind::Configuration);
+        assert_eq!(
+            error.to_string(),
+            concat!(
+                "multiple files are written to the same output (enable `allow_overwrite` to ignore):\n",
+                "    - `out/main.lua` from `src/a.lua`, `src/b.lua`"
+            )
+        );
+        assert!(!resources.exists("out/main.lua").unwrap());
+    }
 
-        let rule: Box<dyn Rule> = Box::new(CustomRule);
+    #[test]
+    fn allow_overwrite_writes_colliding_outputs() {
+        let resources = memory_resources!(
+            "src/foo.lua" => "return 'lua'",
+            "src/foo.luau" => "return 'luau'",
+        );
 
-        assert_errors(
-            "simple_cyclic_work_error",
+        let mut worker_tree = luau_pair_tree();
+        worker_tree
+            .process(
+                &resources,
+                Options::new("src")
+                    .with_configuration(Configuration::empty().with_allow_overwrite(true)),
+            )
+            .unwrap();
+
+        worker_tree.result().unwrap();
+        assert!(resources.exists("out/foo.lua").unwrap());
+    }
+
+    #[test]
+    fn different_outputs_do_not_collide() {
+        let resources = memory_resources!(
+            "src/foo.lua" => "return 'lua'",
+            "src/foo.luau" => "return 'luau'",
+        );
+
+        process(&resources, Options::new("src").with_output("out"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("out/foo.lua").unwrap(), "return'lua'");
+        assert_eq!(resources.get("out/foo.luau").unwrap(), "return'luau'");
+    }
+}
+
+mod extension_rewrites {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn process_luau_tree_into_lua_outputs() {
+        let resources = memory_resources!(
+            "src/init.luau" => ANY_CODE,
+            "src/nested/value.luau" => "local value: number = 1 return value",
+            "src/other.lua" => ANY_CODE,
+        );
+
+        process(
             &resources,
-            Options::new("src").with_configuration(Configuration::empty().with_rule(rule)),
+            Options::new("src")
+                .with_output("out")
+                .with_extension_rewrite("luau", "lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(
+            resources.get("out/init.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert_eq!(
+            resources.get("out/nested/value.lua").unwrap(),
+            "local a:number=1 return a"
+        );
+        assert_eq!(
+            resources.get("out/other.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
         );
+        assert!(!resources.exists("out/init.luau").unwrap());
+        assert!(!resources.exists("out/nested/value.luau").unwrap());
     }
 
     #[test]
-    fn snapshot_missing_configuration_file() {
+    fn process_file_into_directory_with_rewritten_extension() {
         let resources = memory_resources!(
-            "src/init.lua" => "return ''",
+            "src/test.luau" => ANY_CODE,
+            "output/placeholder.txt" => "",
         );
 
-        assert_errors(
-            "missing_configuration_file",
+        process(
             &resources,
-            Options::new("src").with_configuration_at("missing/config.json"),
+            Options::new("src/test.luau")
+                .with_output("output")
+                .with_extension_rewrite("luau", "lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(
+            resources.get("output/test.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
         );
     }
 
     #[test]
-    fn snapshot_multiple_configuration_file_found() {
+    fn explicit_output_file_keeps_its_extension() {
         let resources = memory_resources!(
-            "src/init.lua" => "return ''",
-            ".darklua.json" => "{ rules: [] }",
-            ".darklua.json5" => "{ rules: [] }",
+            "src/test.luau" => ANY_CODE,
         );
 
-        assert_errors(
-            "multiple_configuration_file_found",
+        process(
             &resources,
-            Options::new("src"),
+            Options::new("src/test.luau")
+                .with_output("out.luau")
+                .with_extension_rewrite("luau", "lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(resources.get("out.luau").unwrap(), ANY_CODE_DEFAULT_PROCESS);
+    }
+
+    #[test]
+    fn bundle_sourcemap_file_uses_rewritten_output() {
+        let resources = memory_resources!(
+            "src/main.luau" => "local value = require('./value')\nprint(value)\n",
+            "src/value.luau" => "return true\n",
+            "out/placeholder.txt" => "",
+            ".darklua.json" => "{ rules: [], generator: 'retain_lines', bundle: { require_mode: 'path', sourcemap: { enabled: true, output_path: 'out/main.lua.map' } } }",
+        );
+
+        process(
+            &resources,
+            Options::new("src/main.luau")
+                .with_output("out")
+                .with_extension_rewrite("luau", "lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert!(!resources.exists("out/main.luau").unwrap());
+        let main = resources.get("out/main.lua").unwrap();
+        assert!(main.contains("__DARKLUA_BUNDLE_MODULES"), "{}", main);
+
+        let map = resources.get("out/main.lua.map").unwrap();
+        let sourcemap = sourcemap::SourceMap::from_slice(map.as_bytes()).unwrap();
+        assert_eq!(sourcemap.get_file(), Some("out/main.lua"));
+    }
+}
+
+mod ignore_files {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn nested_ignore_file_overrides_parent_rule() {
+        let resources = memory_resources!(
+            "src/main.lua" => ANY_CODE,
+            "src/generated.lua" => ANY_CODE,
+            "src/.gitignore" => "generated.lua\n",
+            "src/nested/generated.lua" => ANY_CODE,
+            "src/nested/.darkluaignore" => "# keep this one\n!generated.lua\n",
+            ".darklua.json" => "{ rules: [], ignore_files: ['.gitignore', '.darkluaignore'] }",
+        );
+
+        process(&resources, Options::new("src").with_output("out"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("out/main.lua").unwrap(), ANY_CODE);
+        assert_eq!(resources.exists("out/generated.lua"), Ok(false));
+        assert_eq!(resources.get("out/nested/generated.lua").unwrap(), ANY_CODE);
+    }
+
+    #[test]
+    fn inline_ignore_patterns_are_relative_to_input() {
+        let resources = memory_resources!(
+            "src/main.lua" => ANY_CODE,
+            "src/dist/a.lua" => ANY_CODE,
+            "src/dist/nested/b.lua" => ANY_CODE,
+            "src/other/dist/c.lua" => ANY_CODE,
+            ".darklua.json" => "{ rules: [], ignore: ['dist/**'] }",
+        );
+
+        process(&resources, Options::new("src").with_output("out"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("out/main.lua").unwrap(), ANY_CODE);
+        assert_eq!(resources.exists("out/dist/a.lua"), Ok(false));
+        assert_eq!(resources.exists("out/dist/nested/b.lua"), Ok(false));
+        assert_eq!(resources.get("out/other/dist/c.lua").unwrap(), ANY_CODE);
+    }
+
+    #[test]
+    fn ignored_assets_are_not_copied() {
+        let resources = memory_resources!(
+            "src/main.lua" => ANY_CODE,
+            "src/config.json" => "{}",
+            "src/drafts/config.json" => "{}",
+            ".darklua.json" => "{ rules: [], copy_assets: ['**/*.json'], ignore: ['drafts/'] }",
+        );
+
+        process(&resources, Options::new("src").with_output("out"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("out/config.json").unwrap(), "{}");
+        assert_eq!(resources.exists("out/drafts/config.json"), Ok(false));
+    }
+
+    #[test]
+    fn explicit_input_file_bypasses_ignores() {
+        let resources = memory_resources!(
+            "src/generated.lua" => ANY_CODE,
+            "src/.gitignore" => "generated.lua\n",
+            ".darklua.json" => "{ rules: [], ignore: ['*.lua'], ignore_files: ['.gitignore'] }",
+        );
+
+        process(
+            &resources,
+            Options::new("src/generated.lua").with_output("out/generated.lua"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(resources.get("out/generated.lua").unwrap(), ANY_CODE);
+    }
+
+    #[test]
+    fn invalid_ignore_pattern_errors() {
+        let resources = memory_resources!(
+            "src/main.lua" => ANY_CODE,
+            ".darklua.json" => "{ rules: [], ignore: ['[z-a].lua'] }",
+        );
+
+        let error = process(&resources, Options::new("src").with_output("out")).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("invalid ignore pattern `[z-a].lua` in the configuration"));
+    }
+}
+
+mod artifacts {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use darklua_core::ArtifactKind;
+
+    use super::*;
+
+    #[test]
+    fn list_bundle_output_and_sourcemap() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local value = require('./value')\nprint(value)",
+            "src/value.lua" => "return true",
+            ".darklua.json" => "{ rules: [], generator: 'retain_lines', bundle: { require_mode: 'path', sourcemap: { enabled: true, output_path: 'out.lua.map' } } }",
+        );
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src/main.lua").with_output("out.lua"),
+        )
+        .unwrap();
+
+        let output = resources.get("out.lua").unwrap();
+        let sourcemap = resources.get("out.lua.map").unwrap();
+
+        assert_eq!(
+            worker_tree.artifacts(),
+            vec![
+                (PathBuf::from("out.lua"), output.len(), ArtifactKind::Output),
+                (
+                    PathBuf::from("out.lua.map"),
+                    sourcemap.len(),
+                    ArtifactKind::Sourcemap
+                ),
+            ]
+        );
+        worker_tree.result().unwrap();
+    }
+
+    #[test]
+    fn list_outputs_of_directory() {
+        let resources = memory_resources!(
+            "src/a.lua" => ANY_CODE,
+            "src/nested/b.lua" => ANY_CODE,
+        );
+
+        let worker_tree = process(&resources, Options::new("src").with_output("out")).unwrap();
+
+        assert_eq!(
+            worker_tree.artifacts(),
+            vec![
+                (
+                    PathBuf::from("out/a.lua"),
+                    ANY_CODE_DEFAULT_PROCESS.len(),
+                    ArtifactKind::Output
+                ),
+                (
+                    PathBuf::from("out/nested/b.lua"),
+                    ANY_CODE_DEFAULT_PROCESS.len(),
+                    ArtifactKind::Output
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn captured_output_is_not_listed() {
+        let resources = memory_resources!(
+            "src/a.lua" => ANY_CODE,
         );
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src").with_output("out").capture_output(true),
+        )
+        .unwrap();
+
+        assert_eq!(worker_tree.artifacts(), Vec::new());
     }
 }
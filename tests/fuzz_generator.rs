@@ -328,3 +328,135 @@ generate_fuzz_tests!(
 
     token_based_generator(TokenBasedLuaGenerator::new("")) => {},
 );
+
+mod sourcemap_line_mappings {
+    use super::*;
+    use darklua_core::{
+        generator::{ReadableLuaGenerator, TokenBasedLuaGenerator},
+        Parser,
+    };
+    use rand::{thread_rng, Rng};
+    use sourcemap::SourceMapBuilder;
+
+    fn count_lines(code: &str) -> usize {
+        code.matches('\n').count() + 1
+    }
+
+    // inserts random empty lines so that tokens are assigned to random lines
+    fn spread_lines(code: &str) -> String {
+        let mut rng = thread_rng();
+        let mut spread = String::with_capacity(code.len());
+
+        for character in code.chars() {
+            spread.push(character);
+            if character == '\n' {
+                for _ in 0..rng.gen_range(0..4) {
+                    spread.push('\n');
+                }
+            }
+        }
+
+        spread
+    }
+
+    fn find_violation(code: &str) -> Option<String> {
+        let block = Parser::default()
+            .preserve_tokens()
+            .parse_with_source_id(0, code)
+            .expect("unable to parse fuzzed code");
+
+        let mut generator =
+            TokenBasedLuaGenerator::new(code).with_line_mapping(SourceMapBuilder::new(None));
+        generator.write_block(&block);
+
+        let line_mappings = generator.line_mappings();
+        let emitted_mappings = generator.emitted_line_mappings();
+        let (output, sourcemap) = generator.into_string_and_sourcemap();
+        let sourcemap = sourcemap.expect("sourcemap should be generated");
+
+        let generated_lines: Vec<_> = sourcemap
+            .tokens()
+            .map(|token| token.get_dst_line() as usize)
+            .collect();
+
+        if let Some(window) = emitted_mappings
+            .windows(2)
+            .find(|pair| pair[0].get_generated_line() > pair[1].get_generated_line())
+        {
+            return Some(format!(
+                "mappings are not emitted in order: {:?} comes before {:?}",
+                window[0], window[1]
+            ));
+        }
+
+        let source_line_count = count_lines(code);
+        if let Some(mapping) = line_mappings
+            .iter()
+            .find(|mapping| mapping.get_source_line() >= source_line_count)
+        {
+            return Some(format!(
+                "mapping {:?} points outside of the source ({} lines)",
+                mapping, source_line_count
+            ));
+        }
+
+        let output_line_count = count_lines(&output);
+        if let Some(max_line) = generated_lines.iter().max() {
+            if *max_line >= output_line_count {
+                return Some(format!(
+                    "mapping to generated line {} but the output only has {} lines",
+                    max_line, output_line_count
+                ));
+            }
+        }
+
+        if line_mappings.len() != generated_lines.len() {
+            return Some(format!(
+                "{} mappings were recorded but the sourcemap contains {}",
+                line_mappings.len(),
+                generated_lines.len(),
+            ));
+        }
+
+        None
+    }
+
+    fn fuzz_line_mappings(create_budget: impl Fn() -> FuzzBudget) {
+        run_for_minimum_time(|| {
+            let block = AstFuzzer::new(create_budget()).fuzz_block();
+
+            let mut generator = ReadableLuaGenerator::new(thread_rng().gen_range(0..80));
+            generator.write_block(&block);
+            let generated_code = generator.into_string();
+
+            let spread_code = spread_lines(&generated_code);
+            // adding lines inside a quoted string can make the code invalid
+            let code = if test_utils::try_parse_input(&spread_code).is_ok() {
+                spread_code
+            } else {
+                generated_code
+            };
+
+            if let Some(violation) = find_violation(&code) {
+                let repro_path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"))
+                    .join("fuzz_sourcemap_failure.repro.lua");
+                std::fs::write(&repro_path, &code).unwrap();
+                panic!(
+                    "{}\n(code written to `{}`)",
+                    violation,
+                    repro_path.display()
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn fuzz_small_block() {
+        fuzz_line_mappings(|| FuzzBudget::new(20, 40));
+    }
+
+    #[test]
+    fn fuzz_medium_block_with_types() {
+        fuzz_line_mappings(|| FuzzBudget::new(100, 200).with_types(50));
+    }
+}
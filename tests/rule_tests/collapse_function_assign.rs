@@ -0,0 +1,78 @@
+use darklua_core::{
+    rules::{CollapseFunctionAssign, ContextBuilder, ExpandLocalFunction, FlawlessRule, Rule},
+    Resources,
+};
+
+use crate::utils;
+
+test_rule!(
+    collapse_function_assign,
+    CollapseFunctionAssign::default(),
+    empty_function("local foo foo = function() end") => "local function foo() end",
+    function_with_parameters("local foo foo = function(a, b) return a + b end")
+        => "local function foo(a, b) return a + b end",
+    variadic_function("local foo foo = function(a, ...) end") => "local function foo(a, ...) end",
+    recursive_function("local fact fact = function(n) if n <= 1 then return 1 end return n * fact(n - 1) end")
+        => "local function fact(n) if n <= 1 then return 1 end return n * fact(n - 1) end",
+    name_in_parameters("local foo foo = function(foo) return foo end")
+        => "local function foo(foo) return foo end",
+    typed_function("local foo foo = function<T>(value: T, ...: string): T return value end")
+        => "local function foo<T>(value: T, ...: string): T return value end",
+    statements_between_declaration_and_assign("local foo print('start') foo = function() end")
+        => "print('start') local function foo() end",
+    function_used_after_assign("local foo foo = function() end foo() return foo")
+        => "local function foo() end foo() return foo",
+    shadowing_local_in_function("local foo foo = function() local foo = 1 return foo end")
+        => "local function foo() local foo = 1 return foo end",
+    nested_block("do local foo foo = function() end end") => "do local function foo() end end",
+    expanded_local_functions("local foo foo = function() end local bar bar = function() foo() end")
+        => "local function foo() end local function bar() foo() end",
+);
+
+test_rule_without_effects!(
+    CollapseFunctionAssign::default(),
+    local_with_value("local foo = function() end"),
+    local_with_type("local foo: () -> () foo = function() end"),
+    multiple_locals("local foo, bar foo = function() end"),
+    assign_is_not_a_function("local foo foo = 1"),
+    local_read_before_assign("local foo print(foo) foo = function() end"),
+    local_written_before_assign("local foo foo = 1 foo = function() end"),
+    local_written_after_assign("local foo foo = function() end foo = nil"),
+    local_compound_assigned_after_assign("local foo foo = function() end foo ..= 'a'"),
+    local_written_inside_function("local foo foo = function() foo = nil end"),
+    local_used_in_closure_before_assign(
+        "local foo local function call() return foo() end foo = function() end"
+    ),
+    local_shadowed_before_assign("local foo local foo = 1 foo = function() end"),
+    local_shadowed_by_local_function("local foo local function foo() end foo = function() end"),
+    assign_in_nested_block("local foo do foo = function() end end"),
+    multiple_assign("local foo foo, bar = function() end, 1"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'collapse_function_assign',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'collapse_function_assign'").unwrap();
+}
+
+#[test]
+fn expand_then_collapse_recursive_function() {
+    let code = "local function fact(n) if n <= 1 then return 1 end return n * fact(n - 1) end";
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("test.lua", &resources, code).build();
+    let mut block = utils::parse_input(code);
+
+    ExpandLocalFunction::default().flawless_process(&mut block, &context);
+    CollapseFunctionAssign::default().flawless_process(&mut block, &context);
+
+    pretty_assertions::assert_eq!(block, utils::parse_input(code));
+}
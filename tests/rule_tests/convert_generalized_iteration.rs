@@ -0,0 +1,89 @@
+use darklua_core::rules::{ConvertGeneralizedIteration, Rule};
+
+test_rule!(
+    convert_generalized_iteration,
+    ConvertGeneralizedIteration::default().assume_no_iter_metamethod(),
+    identifier("for k, v in t do end") => "for k, v in pairs(t) do end",
+    single_identifier("for k in t do print(k) end") => "for k in pairs(t) do print(k) end",
+    field("for k, v in self.items do end") => "for k, v in pairs(self.items) do end",
+    index("for k, v in items[key] do end") => "for k, v in pairs(items[key]) do end",
+    parenthese("for k, v in (t) do end") => "for k, v in pairs((t)) do end",
+    table("for i, v in { 1, 2, 3 } do end") => "for i, v in pairs({ 1, 2, 3 }) do end",
+    nested_loops("for _, list in lists do for _, v in list do end end")
+        => "for _, list in pairs(lists) do for _, v in pairs(list) do end end",
+);
+
+test_rule!(
+    convert_generalized_iteration_with_iterator_function,
+    ConvertGeneralizedIteration::default().with_iterator_function("iter"),
+    identifier("for k, v in t do end") => "for k, v in iter(t) do end",
+    field("for k, v in self.items do end") => "for k, v in iter(self.items) do end",
+);
+
+test_rule!(
+    convert_generalized_iteration_with_iterator_function_path,
+    ConvertGeneralizedIteration::default().with_iterator_function("Iter.wrap"),
+    identifier("for k, v in t do end") => "for k, v in Iter.wrap(t) do end",
+    field("for k, v in self.items do end") => "for k, v in Iter.wrap(self.items) do end",
+);
+
+test_rule_without_effects!(
+    ConvertGeneralizedIteration::default().with_iterator_function("Iter.wrap"),
+    shadowed_iterator_function_root("local Iter = {} for k, v in t do end"),
+);
+
+test_rule_without_effects!(
+    ConvertGeneralizedIteration::default().assume_no_iter_metamethod(),
+    pairs_call("for k, v in pairs(t) do end"),
+    ipairs_call("for i, v in ipairs(t) do end"),
+    next_with_table("for k, v in next, t do end"),
+    next_identifier("for k, v in next do end"),
+    any_call("for value in getIterator() do end"),
+    multiple_expressions("for k, v in iterate, state, nil do end"),
+    variable_arguments("for k, v in ... do end"),
+    function_expression("for k in function() return nil end do end"),
+    shadowed_pairs("local pairs = customPairs for k, v in t do end"),
+);
+
+test_rule_without_effects!(
+    ConvertGeneralizedIteration::default(),
+    without_assume_no_iter_metamethod("for k, v in t do end"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'convert_generalized_iteration',
+        assume_no_iter_metamethod: true,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_object_notation_with_iterator_function() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'convert_generalized_iteration',
+        iterator_function: 'iter',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_object_notation_with_iterator_function_path() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'convert_generalized_iteration',
+        iterator_function: 'Iter.wrap',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'convert_generalized_iteration'").unwrap();
+}
@@ -0,0 +1,142 @@
+use darklua_core::{
+    rules::{ContextBuilder, ConvertMethodDefinitions, FlawlessRule, Rule, WarningCode},
+    Resources,
+};
+
+use crate::utils;
+
+test_rule!(
+    convert_method_definitions,
+    ConvertMethodDefinitions::default(),
+    method_definition("function Class:method() end") => "function Class.method(self) end",
+    method_definition_with_parameters("function Class:method(a, b) return self, a, b end")
+        => "function Class.method(self, a, b) return self, a, b end",
+    method_definition_in_nested_field("function a.b.c:method(...) end")
+        => "function a.b.c.method(self, ...) end",
+    method_definition_in_function("local function f() function Class:method() end end")
+        => "local function f() function Class.method(self) end end",
+);
+
+test_rule_without_effects!(
+    ConvertMethodDefinitions::default(),
+    method_call_statement("object:method()"),
+    method_call_expression("return object:method(1)"),
+    function_definition("function Class.method(self) end"),
+);
+
+test_rule!(
+    convert_method_definitions_with_calls,
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+            rule: 'convert_method_definitions',
+            also_calls: true,
+        }"#
+    ).unwrap(),
+    method_definition("function Class:method() end") => "function Class.method(self) end",
+    identifier_receiver("object:method()") => "object.method(object)",
+    identifier_receiver_with_arguments("object:method(a, b)") => "object.method(object, a, b)",
+    identifier_receiver_with_string_argument("object:method 'a'") => "object.method(object, 'a')",
+    identifier_receiver_with_table_argument("object:method {}") => "object.method(object, {})",
+    identifier_receiver_in_expression("return object:method()") => "return object.method(object)",
+    string_receiver("return ('abc'):upper()") => "return ('abc').upper('abc')",
+    parenthese_identifier_receiver("return (object):method()") => "return (object).method(object)",
+    nested_method_calls("return object:first(other:second())")
+        => "return object.first(object, other.second(other))",
+    call_receiver_statement("getObject():method(a)")
+        => "do local __DARKLUA_SELF = getObject() __DARKLUA_SELF.method(__DARKLUA_SELF, a) end",
+    field_receiver_statement("self.object:method()")
+        => "do local __DARKLUA_SELF = self.object __DARKLUA_SELF.method(__DARKLUA_SELF) end",
+    call_receiver_statement_with_used_temporary_name(
+        "local __DARKLUA_SELF = 1 getObject():method(__DARKLUA_SELF)"
+    ) => "local __DARKLUA_SELF = 1 do local __DARKLUA_SELF_2 = getObject() __DARKLUA_SELF_2.method(__DARKLUA_SELF_2, __DARKLUA_SELF) end",
+    call_receiver_statement_with_method_call_argument("getObject():method(object:get())")
+        => "do local __DARKLUA_SELF = getObject() __DARKLUA_SELF.method(__DARKLUA_SELF, object.get(object)) end",
+    call_receiver_statement_with_temporary_name_declared_later(
+        "getObject():method() local __DARKLUA_SELF = 1"
+    ) => "do local __DARKLUA_SELF_2 = getObject() __DARKLUA_SELF_2.method(__DARKLUA_SELF_2) end local __DARKLUA_SELF = 1",
+    call_receiver_statements_share_temporary_name("getFirst():method() getSecond():method()")
+        => "do local __DARKLUA_SELF = getFirst() __DARKLUA_SELF.method(__DARKLUA_SELF) end do local __DARKLUA_SELF = getSecond() __DARKLUA_SELF.method(__DARKLUA_SELF) end",
+);
+
+test_rule_without_effects!(
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+            rule: 'convert_method_definitions',
+            also_calls: true,
+        }"#
+    )
+    .unwrap(),
+    call_receiver_expression("return getObject():method()"),
+    field_receiver_expression("local value = self.object:method()"),
+    table_receiver_expression("local value = ({}):method()"),
+);
+
+fn process_with_calls(code: &str) -> Vec<(WarningCode, Option<usize>)> {
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("test.lua", &resources, code).build();
+    let mut block = darklua_core::Parser::default()
+        .preserve_tokens()
+        .parse(code)
+        .expect("code should parse");
+
+    ConvertMethodDefinitions::default()
+        .with_calls()
+        .flawless_process(&mut block, &context);
+
+    context
+        .warnings()
+        .into_iter()
+        .map(|warning| (warning.code(), warning.line()))
+        .collect()
+}
+
+#[test]
+fn warns_when_receiver_with_side_effects_is_not_a_statement() {
+    pretty_assertions::assert_eq!(
+        process_with_calls("local a = 1\nlocal value = getObject():method()"),
+        vec![(WarningCode::UnconvertedMethodCall, Some(2))]
+    );
+}
+
+#[test]
+fn does_not_warn_when_receiver_with_side_effects_is_a_statement() {
+    pretty_assertions::assert_eq!(process_with_calls("getObject():method()"), vec![]);
+}
+
+#[test]
+fn does_not_warn_when_calls_are_not_converted() {
+    let code = "return getObject():method()";
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("test.lua", &resources, code).build();
+    let mut block = utils::parse_input(code);
+
+    ConvertMethodDefinitions::default().flawless_process(&mut block, &context);
+
+    assert!(context.warnings().is_empty());
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'convert_method_definitions',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'convert_method_definitions'").unwrap();
+}
+
+#[test]
+fn deserialize_with_also_calls() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'convert_method_definitions',
+        also_calls: true,
+    }"#,
+    )
+    .unwrap();
+}
@@ -136,6 +136,10 @@ test_rule!(
         => "local module = require(script.Parent['while'])",
     sibling_module_with_name_with_space("local module = require('./a module.lua')")
         => "local module = require(script.Parent['a module'])",
+    sibling_module_with_pcall("local ok, module = pcall(require, './module.lua')")
+        => "local ok, module = pcall(require, script.Parent.module)",
+    sibling_module_with_xpcall("local ok, module = xpcall(require, warn, './module.lua')")
+        => "local ok, module = xpcall(require, warn, script.Parent.module)",
 );
 
 fn process_file(resources: &Resources, file_name: &str) -> String {
@@ -148,7 +152,7 @@ fn process_file(resources: &Resources, file_name: &str) -> String {
 }
 
 fn expect_file_process(resources: &Resources, file_name: &str, expect_content: &str) {
-    pretty_assertions::assert_eq!(process_file(resources, file_name), expect_content);
+    darklua_core::assert_block_equivalent!(process_file(resources, file_name), expect_content);
 }
 
 fn snapshot_file_process(resources: &Resources, file_name: &str, snapshot_name: &str) {
@@ -542,6 +546,61 @@ mod sourcemap_to_path {
         );
     }
 
+    #[test]
+    fn numeric_index_require_is_reported() {
+        let code = "local a = require(script.Parent:GetChildren()[1])\n\nreturn a\n";
+        let resources = memory_resources!(
+            "src/a.lua" => "return nil",
+            "src/b.lua" => code,
+            ".darklua.json" => get_darklua_config_with_sourcemap_reverse("./sourcemap.json"),
+            "sourcemap.json" => include_str!("../test_cases/sourcemap/sourcemap.json"),
+        );
+
+        let worker_tree = darklua_core::process(&resources, Options::new("src/b.lua")).unwrap();
+
+        let warnings: Vec<_> = worker_tree
+            .collect_warnings()
+            .into_iter()
+            .flat_map(|(_, warnings)| warnings.iter().map(ToString::to_string))
+            .collect();
+
+        worker_tree.result().unwrap();
+
+        pretty_assertions::assert_eq!(resources.get("src/b.lua").unwrap(), code);
+        pretty_assertions::assert_eq!(
+            warnings,
+            vec!["could not resolve instance path of `script.Parent:GetChildren()[1]`: numeric index `[1]` is not supported, only string indexes can be resolved (`src/b.lua` at line 1)"]
+        );
+    }
+
+    #[test]
+    fn numeric_index_requires_are_listed_in_skipped_requires() {
+        let resources = memory_resources!(
+            "src/a.lua" => "local b = require(script.Parent:GetChildren()[2])\n\nreturn b\n",
+            "src/b.lua" => "local a = require(script.Parent:GetChildren()[1])\n\nreturn a\n",
+            ".darklua.json" => get_darklua_config_with_sourcemap_reverse("./sourcemap.json"),
+            "sourcemap.json" => include_str!("../test_cases/sourcemap/sourcemap.json"),
+        );
+
+        let worker_tree = darklua_core::process(&resources, Options::new("src")).unwrap();
+
+        let skipped_requires: Vec<_> = worker_tree
+            .collect_skipped_requires()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+
+        worker_tree.result().unwrap();
+
+        pretty_assertions::assert_eq!(
+            skipped_requires,
+            vec![
+                "could not resolve instance path of `script.Parent:GetChildren()[2]`: numeric index `[2]` is not supported, only string indexes can be resolved (`src/a.lua` at line 1)",
+                "could not resolve instance path of `script.Parent:GetChildren()[1]`: numeric index `[1]` is not supported, only string indexes can be resolved (`src/b.lua` at line 1)",
+            ]
+        );
+    }
+
     #[test]
     fn convert_sibling_instance_as_var_require_to_path() {
         let resources = memory_resources!(
@@ -587,6 +646,117 @@ mod sourcemap_to_path {
         );
     }
 
+    fn get_darklua_config_with_path_target(target_options: &str) -> String {
+        format!(
+            r#"{{
+                generator: 'retain_lines',
+                rules: [
+                    {{
+                        rule: 'convert_require',
+                        current: {{
+                            name: 'roblox',
+                            rojo_sourcemap: './sourcemap.json',
+                        }},
+                        target: {{ name: 'path', {target_options} }}
+                    }}
+                ]
+            }}"#
+        )
+    }
+
+    fn snapshot_path_target(target_options: &str, snapshot_name: &str) {
+        let resources = memory_resources!(
+            "src/init.lua" => "return nil",
+            "src/a.lua" => "return nil",
+            "src/d/init.lua" => "return nil",
+            "src/d/d2.lua" => "return nil",
+            "src/d/d1.lua" => concat!(
+                "local d = require(script.Parent)\n",
+                "local d2 = require(script.Parent.d2)\n",
+                "local a = require(script.Parent.Parent.a)\n",
+                "local Package1 = require(script.Parent.Parent.Packages.Package1)\n",
+                "local value = require(script.Parent.Parent.Packages.Package1.value)\n",
+            ),
+            "Packages/Package1/init.lua" => "return nil",
+            "Packages/Package1/value.lua" => "return nil",
+            ".darklua.json" => get_darklua_config_with_path_target(target_options),
+            "sourcemap.json" => include_str!("../test_cases/sourcemap/sourcemap.json"),
+        );
+        snapshot_file_process(&resources, "src/d/d1.lua", snapshot_name);
+    }
+
+    fn snapshot_path_style(path_style: &str, snapshot_name: &str) {
+        snapshot_path_target(&format!("path_style: {}", path_style), snapshot_name);
+    }
+
+    #[test]
+    fn convert_to_path_with_keep_extension_style() {
+        snapshot_path_style("{ extension: 'keep' }", "path_style_keep_extension");
+    }
+
+    #[test]
+    fn convert_to_path_with_strip_extension_style() {
+        snapshot_path_style("{ extension: 'strip' }", "path_style_strip_extension");
+    }
+
+    #[test]
+    fn convert_to_path_with_force_luau_extension_style() {
+        snapshot_path_style(
+            "{ extension: 'force_luau' }",
+            "path_style_force_luau_extension",
+        );
+    }
+
+    #[test]
+    fn convert_to_path_with_force_lua_extension_style() {
+        snapshot_path_style(
+            "{ extension: 'force_lua' }",
+            "path_style_force_lua_extension",
+        );
+    }
+
+    #[test]
+    fn convert_to_path_with_separator_style() {
+        snapshot_path_style(
+            "{ extension: 'strip', separator: '\\\\' }",
+            "path_style_strip_extension_with_separator",
+        );
+    }
+
+    #[test]
+    fn convert_to_path_with_lookup_directories() {
+        snapshot_path_target("lookup_directories: ['Packages']", "lookup_directories");
+    }
+
+    #[test]
+    fn convert_to_path_with_single_quote_style() {
+        snapshot_path_target("quote_style: 'single'", "quote_style_single");
+    }
+
+    #[test]
+    fn convert_to_path_with_double_quote_style() {
+        snapshot_path_target("quote_style: 'double'", "quote_style_double");
+    }
+
+    #[test]
+    fn convert_to_path_with_preserve_quote_style() {
+        let resources = memory_resources!(
+            "src/a.lua" => "return nil",
+            "src/b.lua" => "return nil",
+            "src/c.lua" => concat!(
+                "local a = require(script.Parent:FindFirstChild(\"a\"))\n",
+                "local b = require(script.Parent:FindFirstChild('b'))\n",
+            ),
+            ".darklua.json" => get_darklua_config_with_path_target("quote_style: 'preserve'"),
+            "sourcemap.json" => include_str!("../test_cases/sourcemap/sourcemap.json"),
+        );
+        expect_file_process(
+            &resources,
+            "src/c.lua",
+            "local a = require(\"./a.lua\")\nlocal b = require('./b.lua')",
+        );
+    }
+
     #[test]
     fn datamodel_convert_across_service_instance_to_path() {
         let resources = memory_resources!(
@@ -635,3 +805,259 @@ mod sourcemap_to_path {
         );
     }
 }
+
+mod module_folder_name {
+    use super::*;
+
+    const PATH_TO_ROBLOX_CONFIG: &str = r#"{
+        generator: 'retain_lines',
+        rules: [
+            {
+                rule: 'convert_require',
+                current: { name: 'path', module_folder_name: '__init__' },
+                target: 'roblox',
+            }
+        ]
+    }"#;
+
+    const ROBLOX_TO_PATH_CONFIG: &str = r#"{
+        generator: 'retain_lines',
+        rules: [
+            {
+                rule: 'convert_require',
+                current: { name: 'roblox', rojo_sourcemap: './sourcemap.json' },
+                target: { name: 'path', module_folder_name: '__init__' },
+            }
+        ]
+    }"#;
+
+    const SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "Folder",
+        "children": [
+            { "name": "main", "className": "ModuleScript", "filePaths": ["src/main.lua"] },
+            { "name": "util", "className": "ModuleScript", "filePaths": ["src/util.lua"] },
+            {
+                "name": "folder",
+                "className": "ModuleScript",
+                "filePaths": ["src/folder/__init__.lua"],
+                "children": [
+                    { "name": "child", "className": "ModuleScript", "filePaths": ["src/folder/child.lua"] }
+                ]
+            }
+        ]
+    }"#;
+
+    const MAIN_ROBLOX: &str = "local folder = require(script.Parent:FindFirstChild('folder'))";
+    const INIT_ROBLOX: &str = "local child = require(script:FindFirstChild('child'))\nlocal util = require(script.Parent:FindFirstChild('util'))";
+    const CHILD_ROBLOX: &str = "local folder = require(script.Parent)";
+
+    fn process_directory(resources: &Resources, configuration: &str) {
+        darklua_core::process(
+            resources,
+            Options::new("src").with_configuration_at(configuration),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+    }
+
+    fn expect_files(resources: &Resources, files: &[(&str, &str)]) {
+        for (path, expect_content) in files {
+            pretty_assertions::assert_eq!(&resources.get(path).unwrap(), expect_content);
+        }
+    }
+
+    #[test]
+    fn convert_path_tree_to_roblox() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local folder = require('./folder')",
+            "src/util.lua" => "return nil",
+            "src/folder/__init__.lua" => "local child = require('./child')\nlocal util = require('../util')",
+            "src/folder/child.lua" => "local folder = require('./__init__')",
+            "path-to-roblox.json" => PATH_TO_ROBLOX_CONFIG,
+        );
+
+        process_directory(&resources, "path-to-roblox.json");
+
+        expect_files(
+            &resources,
+            &[
+                ("src/main.lua", MAIN_ROBLOX),
+                ("src/folder/__init__.lua", INIT_ROBLOX),
+                ("src/folder/child.lua", CHILD_ROBLOX),
+            ],
+        );
+    }
+
+    #[test]
+    fn convert_roblox_tree_to_path() {
+        let resources = memory_resources!(
+            "src/main.lua" => MAIN_ROBLOX,
+            "src/util.lua" => "return nil",
+            "src/folder/__init__.lua" => INIT_ROBLOX,
+            "src/folder/child.lua" => CHILD_ROBLOX,
+            "sourcemap.json" => SOURCEMAP,
+            "roblox-to-path.json" => ROBLOX_TO_PATH_CONFIG,
+        );
+
+        process_directory(&resources, "roblox-to-path.json");
+
+        expect_files(
+            &resources,
+            &[
+                ("src/main.lua", "local folder = require('./folder')"),
+                (
+                    "src/folder/__init__.lua",
+                    "local child = require('./child.lua')\nlocal util = require('../util.lua')",
+                ),
+                (
+                    "src/folder/child.lua",
+                    "local folder = require('./__init__.lua')",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn convert_path_tree_to_roblox_and_back() {
+        let resources = memory_resources!(
+            "src/main.lua" => "local folder = require('./folder')",
+            "src/util.lua" => "return nil",
+            "src/folder/__init__.lua" => "local child = require('./child.lua')\nlocal util = require('../util.lua')",
+            "src/folder/child.lua" => "local folder = require('./__init__.lua')",
+            "sourcemap.json" => SOURCEMAP,
+            "path-to-roblox.json" => PATH_TO_ROBLOX_CONFIG,
+            "roblox-to-path.json" => ROBLOX_TO_PATH_CONFIG,
+        );
+        let original: Vec<_> = [
+            "src/main.lua",
+            "src/folder/__init__.lua",
+            "src/folder/child.lua",
+        ]
+        .iter()
+        .map(|path| (*path, resources.get(path).unwrap()))
+        .collect();
+
+        process_directory(&resources, "path-to-roblox.json");
+        process_directory(&resources, "roblox-to-path.json");
+
+        for (path, content) in original {
+            pretty_assertions::assert_eq!(resources.get(path).unwrap(), content);
+        }
+    }
+}
+
+mod auto_current_mode {
+    use super::*;
+
+    use darklua_core::rules::{DetectedRequireMode, WarningCode};
+
+    const CONFIG: &str = r#"{
+        generator: 'retain_lines',
+        rules: [
+            {
+                rule: 'convert_require',
+                current: { name: 'auto', roblox: { rojo_sourcemap: './sourcemap.json' } },
+                target: 'path',
+            }
+        ]
+    }"#;
+
+    const SOURCEMAP: &str = r#"{
+        "name": "Project",
+        "className": "Folder",
+        "children": [
+            { "name": "main", "className": "ModuleScript", "filePaths": ["src/main.lua"] },
+            { "name": "value", "className": "ModuleScript", "filePaths": ["src/value.lua"] }
+        ]
+    }"#;
+
+    fn process_main(code: &str) -> (Resources, darklua_core::WorkerTree) {
+        let resources = memory_resources!(
+            "src/main.lua" => code,
+            "src/value.lua" => "return nil",
+            "sourcemap.json" => SOURCEMAP,
+            ".darklua.json" => CONFIG,
+        );
+        let worker_tree = darklua_core::process(&resources, Options::new("src/main.lua")).unwrap();
+
+        (resources, worker_tree)
+    }
+
+    fn detected_modes(worker_tree: &darklua_core::WorkerTree) -> Vec<DetectedRequireMode> {
+        worker_tree
+            .collect_detected_require_modes()
+            .into_iter()
+            .flat_map(|(_, modes)| modes.iter().copied())
+            .collect()
+    }
+
+    fn mixed_mode_warning_lines(worker_tree: &darklua_core::WorkerTree) -> Vec<Option<usize>> {
+        worker_tree
+            .collect_warnings()
+            .into_iter()
+            .flat_map(|(_, warnings)| warnings.iter())
+            .filter(|warning| warning.code() == WarningCode::MixedRequireModes)
+            .map(|warning| warning.line())
+            .collect()
+    }
+
+    #[test]
+    fn path_requires_are_detected_as_path_mode() {
+        let code = "local value = require('./value.lua')";
+        let (resources, worker_tree) = process_main(code);
+
+        pretty_assertions::assert_eq!(
+            detected_modes(&worker_tree),
+            vec![DetectedRequireMode::Path]
+        );
+        pretty_assertions::assert_eq!(mixed_mode_warning_lines(&worker_tree), vec![]);
+        worker_tree.result().unwrap();
+
+        pretty_assertions::assert_eq!(resources.get("src/main.lua").unwrap(), code);
+    }
+
+    #[test]
+    fn roblox_requires_are_detected_as_roblox_mode() {
+        let (resources, worker_tree) =
+            process_main("local Root = script.Parent\nlocal value = require(Root.value)");
+
+        pretty_assertions::assert_eq!(
+            detected_modes(&worker_tree),
+            vec![DetectedRequireMode::Roblox]
+        );
+        pretty_assertions::assert_eq!(mixed_mode_warning_lines(&worker_tree), vec![]);
+        worker_tree.result().unwrap();
+
+        pretty_assertions::assert_eq!(
+            resources.get("src/main.lua").unwrap(),
+            "local Root = script.Parent\nlocal value = require('./value.lua')"
+        );
+    }
+
+    #[test]
+    fn mixed_requires_are_detected_as_most_frequent_mode() {
+        let (resources, worker_tree) = process_main(concat!(
+            "local a = require('./value.lua')\n",
+            "local b = require(script.Parent.value)\n",
+            "local c = require('./value.lua')",
+        ));
+
+        pretty_assertions::assert_eq!(
+            detected_modes(&worker_tree),
+            vec![DetectedRequireMode::Path]
+        );
+        pretty_assertions::assert_eq!(mixed_mode_warning_lines(&worker_tree), vec![Some(2)]);
+        worker_tree.result().unwrap();
+
+        pretty_assertions::assert_eq!(
+            resources.get("src/main.lua").unwrap(),
+            concat!(
+                "local a = require('./value.lua')\n",
+                "local b = require(script.Parent.value)\n",
+                "local c = require('./value.lua')",
+            )
+        );
+    }
+}
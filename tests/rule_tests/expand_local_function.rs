@@ -0,0 +1,45 @@
+use darklua_core::rules::{ExpandLocalFunction, Rule};
+
+test_rule!(
+    expand_local_function,
+    ExpandLocalFunction::default(),
+    empty_function("local function foo() end") => "local foo foo = function() end",
+    function_with_parameters("local function foo(a, b) return a + b end")
+        => "local foo foo = function(a, b) return a + b end",
+    variadic_function("local function foo(a, ...) end") => "local foo foo = function(a, ...) end",
+    recursive_function("local function fact(n) if n <= 1 then return 1 end return n * fact(n - 1) end")
+        => "local fact fact = function(n) if n <= 1 then return 1 end return n * fact(n - 1) end",
+    name_in_parameters("local function foo(foo) return foo end")
+        => "local foo foo = function(foo) return foo end",
+    typed_function("local function foo<T>(value: T, ...: string): T return value end")
+        => "local foo foo = function<T>(value: T, ...: string): T return value end",
+    two_functions("local function foo() end local function bar() foo() end")
+        => "local foo foo = function() end local bar bar = function() foo() end",
+    nested_local_function("local function foo() local function bar() end return bar end")
+        => "local foo foo = function() local bar bar = function() end return bar end",
+    local_function_in_nested_block("do local function foo() end end")
+        => "do local foo foo = function() end end",
+    keeps_semicolon("local function foo() end; print(foo)")
+        => "local foo; foo = function() end print(foo)",
+);
+
+test_rule_without_effects!(
+    ExpandLocalFunction::default(),
+    function_statement("function foo() end"),
+    local_assign_function("local foo = function() end"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'expand_local_function',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'expand_local_function'").unwrap();
+}
@@ -0,0 +1,60 @@
+use darklua_core::generator::LuaGenerator;
+use darklua_core::rules::{ExpandStringsToChar, Rule};
+
+test_rule!(
+    expand_strings_to_char,
+    ExpandStringsToChar::default(),
+    single_character("return 'h'") => "return string.char(104)",
+    multiple_characters("return 'hello'") => "return string.char(104, 101, 108, 108, 111)",
+    escaped_characters("return '\\0\\n\\255'") => "return string.char(0, 10, 255)",
+    utf8_characters("return 'é'") => "return string.char(195, 169)",
+    string_call_argument("print 'ok'") => "print(string.char(111, 107))",
+    table_key_and_value("return { ['a'] = 'b' }") => "return { [string.char(97)] = string.char(98) }",
+    method_call_prefix("return ('a'):upper()") => "return (string.char(97)):upper()",
+    after_shadowing_scope_ends("do local string = {} end return 'A'")
+        => "do local string = {} end return string.char(65)",
+);
+
+test_rule_without_effects!(
+    ExpandStringsToChar::default(),
+    empty_string("return ''"),
+    shadowed_string_library("local string = {} return 'A'"),
+    shadowed_string_parameter("local function f(string) return 'A' end"),
+    number("return 1"),
+);
+
+#[test]
+fn long_string_is_split_into_concatenated_calls() {
+    let code = format!("return '{}'", "a".repeat(250));
+    let mut block = darklua_core::Parser::default().parse(&code).unwrap();
+    let resources = darklua_core::Resources::from_memory();
+    let context = darklua_core::rules::ContextBuilder::new("test.lua", &resources, &code).build();
+
+    ExpandStringsToChar::default()
+        .process(&mut block, &context)
+        .expect("rule should succeed");
+
+    let mut generator = darklua_core::generator::ReadableLuaGenerator::default();
+    generator.write_block(&block);
+
+    let chunk = |length: usize| format!("string.char({})", vec!["97"; length].join(", "));
+    darklua_core::assert_block_equivalent!(
+        generator.into_string(),
+        format!("return {} .. {} .. {}", chunk(100), chunk(100), chunk(50))
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'expand_strings_to_char',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'expand_strings_to_char'").unwrap();
+}
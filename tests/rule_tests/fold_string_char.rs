@@ -0,0 +1,85 @@
+use darklua_core::{
+    rules::{ContextBuilder, FlawlessRule, FoldStringChar, Rule, WarningCode},
+    Resources,
+};
+
+test_rule!(
+    fold_string_char,
+    FoldStringChar::default(),
+    single_byte("return string.char(104)") => "return 'h'",
+    multiple_bytes("return string.char(104, 101, 108, 108, 111)") => "return 'hello'",
+    no_arguments("return string.char()") => "return ''",
+    mixed_constant_arguments("return string.char(104, 100 + 5, '33')") => "return 'hi!'",
+    hexadecimal_number("return string.char(0x41)") => "return 'A'",
+    escaped_bytes("return string.char(0, 10, 255)") => "return '\\0\\n\\255'",
+    nested_in_call("print(string.char(111, 107))") => "print('ok')",
+    in_local_function("local function f() return string.char(65) end")
+        => "local function f() return 'A' end",
+    after_shadowing_scope_ends("do local string = {} end return string.char(65)")
+        => "do local string = {} end return 'A'",
+);
+
+test_rule_without_effects!(
+    FoldStringChar::default(),
+    non_constant_argument("return string.char(104, value)"),
+    call_argument("return string.char(getByte())"),
+    variadic_arguments("local function f(...) return string.char(...) end"),
+    negative_byte("return string.char(-1)"),
+    byte_too_large("return string.char(256)"),
+    fractional_byte("return string.char(65.5)"),
+    table_argument("return string.char({})"),
+    shadowed_string_library("local string = {} return string.char(65)"),
+    shadowed_string_parameter("local function f(string) return string.char(65) end"),
+    method_call("return string:char(65)"),
+    other_string_function("return string.byte(65)"),
+);
+
+fn process(code: &str) -> Vec<(WarningCode, Option<usize>)> {
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("test.lua", &resources, code).build();
+    let mut block = darklua_core::Parser::default()
+        .preserve_tokens()
+        .parse(code)
+        .expect("code should parse");
+
+    FoldStringChar::default().flawless_process(&mut block, &context);
+
+    context
+        .warnings()
+        .into_iter()
+        .map(|warning| (warning.code(), warning.line()))
+        .collect()
+}
+
+#[test]
+fn warns_when_byte_is_out_of_range() {
+    pretty_assertions::assert_eq!(
+        process("local a = 1\nlocal b = string.char(104,\n300)"),
+        vec![(WarningCode::InvalidStringCharByte, Some(3))]
+    );
+}
+
+#[test]
+fn does_not_warn_on_non_constant_argument() {
+    pretty_assertions::assert_eq!(process("return string.char(300, value)"), vec![]);
+}
+
+#[test]
+fn does_not_warn_on_valid_bytes() {
+    pretty_assertions::assert_eq!(process("return string.char(104, 105)"), vec![]);
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'fold_string_char',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'fold_string_char'").unwrap();
+}
@@ -416,21 +416,29 @@ macro_rules! test_rule_snapshot {
 }
 
 mod append_text_comment;
+mod collapse_function_assign;
 mod compute_expression;
+mod convert_generalized_iteration;
 mod convert_index_to_field;
 mod convert_luau_number;
+mod convert_method_definitions;
 mod convert_require;
 mod convert_square_root_call;
+mod expand_local_function;
+mod expand_strings_to_char;
 mod filter_early_return;
+mod fold_string_char;
 mod group_local_assignment;
 mod inject_value;
 mod no_local_function;
+mod normalize_whitespace;
 mod remove_assertions;
 mod remove_call_parens;
 mod remove_comments;
 mod remove_compound_assignment;
 mod remove_continue;
 mod remove_debug_profiling;
+mod remove_empty_blocks;
 mod remove_empty_do;
 mod remove_floor_division;
 mod remove_if_expression;
@@ -438,6 +446,7 @@ mod remove_interpolated_string;
 mod remove_method_call;
 mod remove_method_definition;
 mod remove_nil_declaration;
+mod remove_redundant_return;
 mod remove_types;
 mod remove_unused_if_branch;
 mod remove_unused_variable;
@@ -0,0 +1,138 @@
+use darklua_core::rules::{NormalizeWhitespace, Rule};
+
+test_rule_with_tokens!(
+    normalize_whitespace,
+    NormalizeWhitespace::default(),
+    trailing_spaces("local a = 1   \nreturn a") => "local a = 1\nreturn a",
+    trailing_tab("local a = 1\t\nreturn a") => "local a = 1\nreturn a",
+    trailing_spaces_with_carriage_return("local a = 1  \r\nreturn a") => "local a = 1\r\nreturn a",
+    blank_line_with_spaces("local a = 1\n   \nreturn a") => "local a = 1\n\nreturn a",
+    spaces_between_tokens("local   a  =   1") => "local a = 1",
+    tab_between_tokens("local\ta\t=\t1") => "local a = 1",
+    keep_indentation("do\n    local a = 1\nend") => "do\n    local a = 1\nend",
+    keep_tab_indentation("do\n\tlocal a = 1\nend") => "do\n\tlocal a = 1\nend",
+    keep_string_content("local a = '  a  b  '") => "local a = '  a  b  '",
+    keep_multiline_string_content("local a = [[\n  a   \n]]") => "local a = [[\n  a   \n]]",
+    keep_comment_content("local a -- a   comment   \nreturn a") => "local a -- a   comment   \nreturn a",
+    spaces_before_comment("local a     -- comment") => "local a -- comment",
+    trailing_spaces_at_end_of_file("return nil\n   ") => "return nil\n",
+);
+
+test_rule_with_tokens!(
+    normalize_whitespace_with_tab_size,
+    NormalizeWhitespace::default().with_tab_size(2),
+    convert_tab_indentation("do\n\tlocal a = 1\nend") => "do\n  local a = 1\nend",
+    convert_nested_tab_indentation("do\n\tdo\n\t\tlocal a = 1\n\tend\nend")
+        => "do\n  do\n    local a = 1\n  end\nend",
+    convert_mixed_indentation("do\n \tlocal a = 1\nend") => "do\n   local a = 1\nend",
+    keep_tab_in_string("local a = '\t'") => "local a = '\t'",
+);
+
+fn normalize_fixture(rule: &dyn Rule) -> String {
+    use darklua_core::{
+        generator::{LuaGenerator, TokenBasedLuaGenerator},
+        Parser,
+    };
+
+    let code = include_str!("../test_cases/messy_whitespace.lua");
+
+    let resources = darklua_core::Resources::from_memory();
+    let context = darklua_core::rules::ContextBuilder::new(".", &resources, code).build();
+    let mut block = Parser::default()
+        .preserve_tokens()
+        .parse(code)
+        .expect("unable to parse code");
+
+    rule.process(&mut block, &context)
+        .expect("rule should succeed");
+
+    let mut generator = TokenBasedLuaGenerator::new(code);
+    generator.write_block(&block);
+    let lua_code = generator.into_string();
+
+    pretty_assertions::assert_eq!(
+        lua_code.matches('\n').count(),
+        code.matches('\n').count(),
+        "line count should not change"
+    );
+
+    lua_code
+}
+
+#[test]
+fn normalize_messy_fixture() {
+    pretty_assertions::assert_eq!(
+        normalize_fixture(&NormalizeWhitespace::default()),
+        concat!(
+            "local value = \"keep   these   spaces\"\n",
+            "\n",
+            "local function compute(a, b)\n",
+            "\tlocal sum = a + b\n",
+            "\n",
+            "\tif sum > 10 then -- keep   this   comment   \n",
+            "\t\treturn sum\n",
+            "\tend\n",
+            "    --[[ keep\t  this  \n",
+            "       block   comment ]]\n",
+            "\treturn [[\n",
+            "  multi-line   \n",
+            "  string\t\n",
+            "]]\n",
+            "end\n",
+            "\n",
+            "return compute(value, 2)\n",
+        )
+    );
+}
+
+#[test]
+fn normalize_messy_fixture_with_tab_size() {
+    pretty_assertions::assert_eq!(
+        normalize_fixture(&NormalizeWhitespace::default().with_tab_size(4)),
+        concat!(
+            "local value = \"keep   these   spaces\"\n",
+            "\n",
+            "local function compute(a, b)\n",
+            "    local sum = a + b\n",
+            "\n",
+            "    if sum > 10 then -- keep   this   comment   \n",
+            "        return sum\n",
+            "    end\n",
+            "    --[[ keep\t  this  \n",
+            "       block   comment ]]\n",
+            "    return [[\n",
+            "  multi-line   \n",
+            "  string\t\n",
+            "]]\n",
+            "end\n",
+            "\n",
+            "return compute(value, 2)\n",
+        )
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'normalize_whitespace',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'normalize_whitespace'").unwrap();
+}
+
+#[test]
+fn deserialize_with_tab_size() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'normalize_whitespace',
+        tab_size: 4,
+    }"#,
+    )
+    .unwrap();
+}
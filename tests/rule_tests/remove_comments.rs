@@ -56,6 +56,143 @@ test_remove_comments_rule!(
     keep_one_comment_before_empty_do("--!native\n-- comment\ndo end") => "--!native\n\ndo end",
 );
 
+test_remove_comments_rule!(
+    RemoveComments::default(),
+    multiline_comment_before_statement("--[[\n  comment\n]]\nreturn nil") => "\n\n\nreturn nil",
+    multiline_comment_at_end_of_file("return nil\n--[[\n  comment\n]]") => "return nil\n\n\n",
+    multiline_comment_inside_statement("local a = --[[ one\ntwo ]] 1\nreturn a") => "local a = \n 1\nreturn a",
+);
+
+test_remove_comments_rule!(
+    json5::from_str::<Box<dyn Rule>>(r#"{
+        rule: 'remove_comments',
+        keep_patterns: ['^--!', '^-- *darklua:'],
+    }"#,
+    )
+    .unwrap(),
+    keep_directive_comments("--!strict\n-- darklua: skip\n-- comment\nreturn nil")
+        => "--!strict\n-- darklua: skip\n\nreturn nil",
+    keep_directive_comment_without_space("--darklua: skip\nreturn nil") => "--darklua: skip\nreturn nil",
+    remove_comment_not_starting_with_directive("-- see --!strict\nreturn nil") => "\nreturn nil",
+);
+
+const DIRECTIVES_CODE: &str = r#"--!strict
+--[[
+    This module returns the sum of
+    two numbers
+]]
+-- darklua: keep
+local function add(a, b) -- adds two numbers
+    --[==[ a multi-line
+    comment inside a function ]==]
+    return a + b
+end
+
+return add -- the end
+--[[
+    trailing comment
+]]
+"#;
+
+macro_rules! snapshot_remove_comments {
+    ($($name:ident => $generator:expr),* $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                use darklua_core::{generator::LuaGenerator, Parser};
+
+                let rule = json5::from_str::<Box<dyn Rule>>(
+                    r#"{
+                    rule: 'remove_comments',
+                    keep_patterns: ['^--!', '^-- *darklua:'],
+                }"#,
+                )
+                .unwrap();
+                let resources = darklua_core::Resources::from_memory();
+                let context =
+                    darklua_core::rules::ContextBuilder::new(".", &resources, DIRECTIVES_CODE)
+                        .build();
+
+                let mut block = Parser::default()
+                    .preserve_tokens()
+                    .parse(DIRECTIVES_CODE)
+                    .expect("unable to parse code");
+
+                rule.process(&mut block, &context)
+                    .expect("rule should succeed");
+
+                let mut generator = $generator;
+                generator.write_block(&block);
+                let lua_code = generator.into_string();
+
+                insta::assert_snapshot!(stringify!($name), lua_code);
+            }
+        )*
+    };
+}
+
+snapshot_remove_comments!(
+    remove_comments_with_token_based_generator
+        => darklua_core::generator::TokenBasedLuaGenerator::new(DIRECTIVES_CODE),
+    remove_comments_with_retain_lines_compact_generator
+        => darklua_core::generator::RetainLinesCompactLuaGenerator::new(DIRECTIVES_CODE, 1),
+    remove_comments_with_dense_generator
+        => darklua_core::generator::DenseLuaGenerator::new(80),
+    remove_comments_with_readable_generator
+        => darklua_core::generator::ReadableLuaGenerator::new(80),
+);
+
+#[test]
+fn retain_lines_output_keeps_line_count() {
+    use darklua_core::{
+        generator::{LuaGenerator, TokenBasedLuaGenerator},
+        Parser,
+    };
+
+    let resources = darklua_core::Resources::from_memory();
+    let context =
+        darklua_core::rules::ContextBuilder::new(".", &resources, DIRECTIVES_CODE).build();
+    let mut block = Parser::default()
+        .preserve_tokens()
+        .parse(DIRECTIVES_CODE)
+        .expect("unable to parse code");
+
+    RemoveComments::default()
+        .process(&mut block, &context)
+        .expect("rule should succeed");
+
+    let mut generator = TokenBasedLuaGenerator::new(DIRECTIVES_CODE);
+    generator.write_block(&block);
+    let lua_code = generator.into_string();
+
+    pretty_assertions::assert_eq!(lua_code.lines().count(), DIRECTIVES_CODE.lines().count());
+}
+
+#[test]
+fn removing_comments_keeps_block_structure() {
+    use darklua_core::{
+        generator::{LuaGenerator, TokenBasedLuaGenerator},
+        Parser,
+    };
+
+    let resources = darklua_core::Resources::from_memory();
+    let context =
+        darklua_core::rules::ContextBuilder::new(".", &resources, DIRECTIVES_CODE).build();
+    let mut block = Parser::default()
+        .preserve_tokens()
+        .parse(DIRECTIVES_CODE)
+        .expect("unable to parse code");
+
+    RemoveComments::default()
+        .process(&mut block, &context)
+        .expect("rule should succeed");
+
+    let mut generator = TokenBasedLuaGenerator::new(DIRECTIVES_CODE);
+    generator.write_block(&block);
+
+    darklua_core::assert_block_equivalent!(generator.into_string(), DIRECTIVES_CODE);
+}
+
 #[test]
 fn deserialize_from_object_notation() {
     json5::from_str::<Box<dyn Rule>>(
@@ -27,6 +27,12 @@ test_rule!(
         => "do local __DARKLUA_VAR = call() a[__DARKLUA_VAR] = a[__DARKLUA_VAR] + 1 end",
     increase_index_with_side_effects_in_prefix("object[call()][key] += 1")
         => "do local __DARKLUA_VAR = object[call()] __DARKLUA_VAR[key] = __DARKLUA_VAR[key] + 1 end",
+    increase_field_on_call_result("f().x += 1")
+        => "do local __DARKLUA_VAR = f() __DARKLUA_VAR.x = __DARKLUA_VAR.x + 1 end",
+    concat_field_on_method_call_result("obj:get().name ..= 'suffix'")
+        => "do local __DARKLUA_VAR = obj:get() __DARKLUA_VAR.name = __DARKLUA_VAR.name .. 'suffix' end",
+    increase_index_on_call_result("f()[key] += 1")
+        => "do local __DARKLUA_VAR = f() __DARKLUA_VAR[key] = __DARKLUA_VAR[key] + 1 end",
     increase_index_with_side_effects_in_prefix_and_index("object[call()][getKey()] += 1")
         => "do local __DARKLUA_VAR, __DARKLUA_VAR0 = object[call()], getKey() __DARKLUA_VAR[__DARKLUA_VAR0] = __DARKLUA_VAR[__DARKLUA_VAR0] + 1 end",
     nested_field_expressions("var.object.prop += 1")
@@ -42,6 +48,8 @@ test_rule_with_tokens!(
     trailing_comment_on_second_line("\ni += 1 -- comment") => "\ni =i+ 1 -- comment",
     comment_after_operator("i += --[[ comment ]] 1") => "i =i+ --[[ comment ]] 1",
     comment_after_variable("i --[[ comment ]] += 1") => "i --[[ comment ]] =i+ 1",
+    field_on_call_result_keeps_line("\nf().x += 1 -- comment")
+        => "do local __DARKLUA_VAR=\nf()__DARKLUA_VAR.x =__DARKLUA_VAR.x+ 1 -- comment\nend",
 );
 
 #[test]
@@ -139,6 +139,69 @@ test_rule_snapshot!(
     end
     "#
     ),
+    while_continue(
+        r#"
+    local i = 0
+    while i < 10 do
+        i += 1
+        if i % 2 == 0 then
+            continue
+        end
+        print(i)
+    end
+    "#
+    ),
+    while_continue_and_break(
+        r#"
+    while true do
+        local value = next()
+        if value == nil then
+            break
+        elseif value < 0 then
+            continue
+        end
+        print(value)
+    end
+    "#
+    ),
+    repeat_continue(
+        r#"
+    repeat
+        if shouldSkip() then
+            continue
+        end
+        work()
+    until done()
+    "#
+    ),
+    nested_loops_both_continue(
+        r#"
+    for key, list in pairs(lists) do
+        if key == "skip" then
+            continue
+        end
+        for _, value in ipairs(list) do
+            if not value then
+                continue
+            end
+            print(value)
+        end
+    end
+    "#
+    ),
+    nested_while_inside_for_with_inner_continue(
+        r#"
+    for i = 1, 10 do
+        while check(i) do
+            if i % 2 == 0 then
+                continue
+            end
+            i += 1
+        end
+        print(i)
+    end
+    "#
+    ),
     for_loop_continue_in_function_statement(
         r#"
     for i = 1, 10 do
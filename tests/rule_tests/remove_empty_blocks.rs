@@ -0,0 +1,112 @@
+use darklua_core::rules::{RemoveEmptyBlocks, Rule};
+
+test_rule!(
+    remove_empty_blocks,
+    RemoveEmptyBlocks::default(),
+    empty_do_statements("do end do end") => "",
+    nested_empty_do_statements("do do end do do end end end") => "",
+    empty_do_statement_in_function("local function foo() do end end") => "local function foo() end",
+    do_statement_discarding_pure_value("do local _ = value end") => "",
+    do_statement_discarding_call("do local _ = foo() end") => "foo()",
+    do_statement_discarding_call_in_parentheses("do local _ = (foo()) end") => "foo()",
+    do_statement_discarding_index("do local _ = object.field end") => "do local _ = object.field end",
+    do_statement_with_typed_local("do local _: number = value end") => "do local _: number = value end",
+    do_statement_with_other_local("do local value = foo() end") => "do local value = foo() end",
+    empty_if_with_pure_condition("if condition then end") => "",
+    empty_if_with_call_condition("if check() then end") => "check()",
+    empty_if_with_side_effect_condition("if object.field then end") => "if object.field then end",
+    empty_else_branch("if condition then foo() else end") => "if condition then foo() end",
+    empty_if_with_empty_else("if condition then else end") => "",
+    empty_trailing_elseif_with_pure_condition("if a then foo() elseif b then end")
+        => "if a then foo() end",
+    empty_trailing_elseif_with_call_condition("if a then foo() elseif check() then end")
+        => "if a then foo() elseif check() then end",
+    empty_elseif_before_else("if a then foo() elseif b then else bar() end")
+        => "if a then foo() elseif b then else bar() end",
+    empty_branches_collapse_to_pure_condition("if a then elseif b then else end") => "",
+    empty_branches_collapse_to_call_condition("if check() then elseif b then end") => "check()",
+    if_with_nested_empty_blocks("if a then do end if b then end end") => "",
+    do_with_nested_empty_if("do if a then else end end") => "",
+    nested_empty_blocks_in_loop("while true do do end if a then end end") => "while true do end",
+);
+
+test_rule_without_effects!(
+    RemoveEmptyBlocks::default(),
+    non_empty_do("do foo() end"),
+    non_empty_if("if a then foo() end"),
+    empty_if_with_non_empty_else("if a then else foo() end"),
+    empty_while("while a do end"),
+    empty_function("local function foo() end"),
+);
+
+test_rule_snapshot!(
+    remove_empty_blocks,
+    RemoveEmptyBlocks::default(),
+    comment_before_empty_do(
+        r#"
+local value = 1
+-- explain the value
+do end
+local other = 2
+"#
+    ),
+    comment_inside_empty_do(
+        r#"
+local value = 1
+do
+    -- nothing to do
+end
+return value
+"#
+    ),
+    comment_in_removed_else_branch(
+        r#"
+if condition then
+    foo()
+else
+    -- nothing else
+end
+"#
+    ),
+    comment_in_empty_if_with_call_condition(
+        r#"
+-- run the check
+if check() then
+    -- the result is not used
+end
+"#
+    ),
+    comment_at_end_of_block(
+        r#"
+local function foo()
+    bar()
+    -- cleanup
+    do end
+end
+"#
+    ),
+    bundled_statement_require(
+        r#"
+local value = 1
+do
+    local _ = __DARKLUA_BUNDLE_MODULES.load('a')
+end
+print(value)
+"#
+    ),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'remove_empty_blocks',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'remove_empty_blocks'").unwrap();
+}
@@ -23,6 +23,63 @@ test_rule!(
         => "local function f(...: string) return (condition(...) and {(...)} or { ((condition2(...) and {(...)} or { (transform(...)) })[1]) }) [1] end"
 );
 
+test_rule!(
+    remove_if_expression_with_function_fallback,
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+            rule: 'remove_if_expression',
+            fallback: 'function',
+        }"#
+    ).unwrap(),
+    if_with_truthy_result("local a = if condition() then 1 else 2")
+        => "local a = condition() and 1 or 2",
+    if_with_truthy_results_and_elseif("local a = if c1 then 1 elseif c2 then 2 else 3")
+        => "local a = c1 and 1 or (c2 and 2 or 3)",
+    if_with_nil_result_else_false("local a = if condition() then nil else false")
+        => "local a = (function() if condition() then return nil else return false end end)()",
+    if_with_unknown_result_else_unknown("local a = if condition() then update() else default()")
+        => "local a = (function() if condition() then return (update()) else return (default()) end end)()",
+    if_with_unknown_result_and_elseif("local a = if c1 then value elseif c2 then 2 else nil")
+        => "local a = (function() if c1 then return value elseif c2 then return 2 else return nil end end)()",
+    if_expression_with_varargs("local function f(...: string) return if condition(...) then ... else transform(...) end")
+        => "local function f(...: string) return (condition(...) and {(...)} or {(transform(...))})[1] end",
+);
+
+test_rule_snapshot!(
+    remove_if_expression_snapshots,
+    RemoveIfExpression::default(),
+    table_fallback_with_elseif(
+        r#"
+    local kind = if value == nil then nil
+        elseif type(value) == "table" then getKind(value)
+        else false
+    "#
+    ),
+);
+
+test_rule_snapshot!(
+    remove_if_expression_function_fallback_snapshots,
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+            rule: 'remove_if_expression',
+            fallback: 'function',
+        }"#
+    )
+    .unwrap(),
+    function_fallback_with_elseif(
+        r#"
+    local kind = if value == nil then nil
+        elseif type(value) == "table" then getKind(value)
+        else false
+    "#
+    ),
+    function_fallback_nested_in_call(
+        r#"
+    print(if enabled then nil else fallback(), "done")
+    "#
+    ),
+);
+
 #[test]
 fn deserialize_from_object_notation() {
     json5::from_str::<Box<dyn Rule>>(
@@ -33,6 +90,17 @@ fn deserialize_from_object_notation() {
     .unwrap();
 }
 
+#[test]
+fn deserialize_from_object_notation_with_function_fallback() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'remove_if_expression',
+        fallback: 'function',
+    }"#,
+    )
+    .unwrap();
+}
+
 #[test]
 fn deserialize_from_string() {
     json5::from_str::<Box<dyn Rule>>("'remove_if_expression'").unwrap();
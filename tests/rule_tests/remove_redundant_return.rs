@@ -0,0 +1,57 @@
+use darklua_core::rules::{RemoveRedundantReturn, Rule};
+
+test_rule!(
+    remove_redundant_return,
+    RemoveRedundantReturn::default(),
+    empty_return_in_local_function("local function f() print() return end")
+        => "local function f() print() end",
+    nil_return_in_local_function("local function f() print() return nil end")
+        => "local function f() print() end",
+    empty_return_in_function_statement("function f() return end") => "function f() end",
+    nil_return_in_method("function Class:f() return nil end") => "function Class:f() end",
+    empty_return_in_function_expression("callback(function() print() return end)")
+        => "callback(function() print() end)",
+    return_in_trailing_if("local function f() if a then return else print() return nil end end")
+        => "local function f() if a then else print() end end",
+    return_in_trailing_do("local function f() do print() return end end")
+        => "local function f() do print() end end",
+    nested_functions("local function f() local function g() return end return nil end")
+        => "local function f() local function g() end end",
+    empty_return_in_chunk("print() return") => "print()",
+    bundled_module_with_empty_return("function __DARKLUA_BUNDLE_MODULES.a() return end")
+        => "function __DARKLUA_BUNDLE_MODULES.a() end",
+);
+
+test_rule_without_effects!(
+    RemoveRedundantReturn::default(),
+    return_value("local function f() return true end"),
+    return_multiple_values("local function f() return nil, nil end"),
+    return_call("local function f() return g() end"),
+    return_in_numeric_for("local function f() for i = 1, 10 do return end end"),
+    return_in_while("local function f() while true do return nil end end"),
+    return_in_repeat("local function f() repeat return until true end"),
+    return_in_if_followed_by_statement("local function f() if a then return end print() end"),
+    nil_return_in_chunk("return nil"),
+    bundled_module_nil_return("function __DARKLUA_BUNDLE_MODULES.a() return nil end"),
+);
+
+test_rule_without_effects!(
+    RemoveRedundantReturn::default().with_modules_identifier("Modules"),
+    custom_bundled_module_nil_return("function Modules.a() return nil end"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'remove_redundant_return',
+        modules_identifier: 'Modules',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'remove_redundant_return'").unwrap();
+}
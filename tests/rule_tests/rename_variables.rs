@@ -96,6 +96,51 @@ test_rule!(
         => "local a = require('@pkg/@jsdotlua/react') type Props = { children: a.ReactNode }",
 );
 
+test_rule!(
+    rename_variables_with_preserve,
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'rename_variables',
+        preserve: ['^HOT_', 'config'],
+    }"#,
+    ).unwrap(),
+    preserve_local_in_same_scope_as_renamed_local("local HOT_value = 1 local other = 2 return HOT_value + other")
+        => "local HOT_value = 1 local a = 2 return HOT_value + a",
+    preserve_local_matching_unanchored_pattern("local myconfig, value = {}, 1 return myconfig[value]")
+        => "local myconfig, a = {}, 1 return myconfig[a]",
+    preserve_function_parameter("local function load(config, value) return config[value] end")
+        => "local function load(config, a) return config[a] end",
+    preserve_function_expression_parameter("return function(HOT_reload, callback) HOT_reload(callback) end")
+        => "return function(HOT_reload, a) HOT_reload(a) end",
+    preserved_local_is_not_reused("do local HOT_value end local foo return foo")
+        => "do local HOT_value end local a return a",
+);
+
+test_rule!(
+    rename_variables_never_generates_preserved_names,
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'rename_variables',
+        preserve: ['^a$'],
+    }"#,
+    ).unwrap(),
+    generated_name_skips_preserved_name("local a = 1 local foo = 2 return a + foo")
+        => "local a = 1 local b = 2 return a + b",
+    generated_name_skips_preserved_name_in_nested_scope("local foo = 1 do local bar = foo end")
+        => "local b = 1 do local c = b end",
+);
+
+test_rule!(
+    rename_variables_avoids_referenced_globals,
+    RenameVariables::default(),
+    generated_name_skips_globals_named_a_and_b("local value = a + b return value")
+        => "local c = a + b return c",
+    generated_name_skips_global_referenced_in_nested_scope("local value = 1 local function f() return a end return value, f")
+        => "local b = 1 local function f() return a end return b, f",
+    bundle_prefixed_locals_are_renamed("local __DARKLUA_BUNDLE_MODULES_a = 1 return __DARKLUA_BUNDLE_MODULES_a + __DARKLUA_BUNDLE_MODULES")
+        => "local a = 1 return a + __DARKLUA_BUNDLE_MODULES",
+);
+
 test_rule_without_effects!(
     RenameVariables::default(),
     local_function_name("local function foo() end"),
@@ -0,0 +1,18 @@
+local   value  =   "keep   these   spaces"   
+
+local function   compute(a,   b)  
+	local   sum = a  +  b	
+	
+	if sum  >  10 then   -- keep   this   comment   
+		return   sum
+	end
+    --[[ keep	  this  
+       block   comment ]]   
+	return [[
+  multi-line   
+  string	
+]]
+end  
+
+return   compute(value,  2)   
+  	
\ No newline at end of file